@@ -222,7 +222,7 @@ impl CompletionProvider for SlashCommandCompletionProvider {
         buffer_position: Anchor,
         _: editor::CompletionContext,
         cx: &mut ViewContext<Editor>,
-    ) -> Task<Result<Vec<project::Completion>>> {
+    ) -> Task<Result<project::CompletionResponse>> {
         let Some((name, argument, command_range, argument_range)) =
             buffer.update(cx, |buffer, _cx| {
                 let position = buffer_position.to_point(buffer);
@@ -253,14 +253,21 @@ impl CompletionProvider for SlashCommandCompletionProvider {
                 })
             })
         else {
-            return Task::ready(Ok(Vec::new()));
+            return Task::ready(Ok(project::CompletionResponse::default()));
         };
 
-        if let Some(argument) = argument {
+        let completions = if let Some(argument) = argument {
             self.complete_command_argument(&name, argument, command_range, argument_range, cx)
         } else {
             self.complete_command_name(&name, command_range, argument_range, cx)
-        }
+        };
+        // The commands and their arguments are completed for the query, so they're completed again as it changes.
+        cx.background_executor().spawn(async move {
+            Ok(project::CompletionResponse {
+                completions: completions.await?,
+                is_incomplete: true,
+            })
+        })
     }
 
     fn resolve_completions(
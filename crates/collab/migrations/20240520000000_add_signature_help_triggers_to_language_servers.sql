@@ -0,0 +1,2 @@
+ALTER TABLE language_servers ADD COLUMN signature_help_trigger_characters TEXT;
+ALTER TABLE language_servers ADD COLUMN signature_help_retrigger_characters TEXT;
@@ -498,13 +498,23 @@ impl Database {
                 project_id: ActiveValue::set(project_id),
                 id: ActiveValue::set(server.id as i64),
                 name: ActiveValue::set(server.name.clone()),
+                signature_help_trigger_characters: ActiveValue::set(Some(serde_json::to_string(
+                    &server.signature_help_trigger_characters,
+                )?)),
+                signature_help_retrigger_characters: ActiveValue::set(Some(serde_json::to_string(
+                    &server.signature_help_retrigger_characters,
+                )?)),
             })
             .on_conflict(
                 OnConflict::columns([
                     language_server::Column::ProjectId,
                     language_server::Column::Id,
                 ])
-                .update_column(language_server::Column::Name)
+                .update_columns([
+                    language_server::Column::Name,
+                    language_server::Column::SignatureHelpTriggerCharacters,
+                    language_server::Column::SignatureHelpRetriggerCharacters,
+                ])
                 .to_owned(),
             )
             .exec(&*tx)
@@ -816,10 +826,7 @@ impl Database {
             worktrees,
             language_servers: language_servers
                 .into_iter()
-                .map(|language_server| proto::LanguageServer {
-                    id: language_server.id as u64,
-                    name: language_server.name,
-                })
+                .map(|language_server| language_server.to_proto())
                 .collect(),
             dev_server_project_id: project.dev_server_project_id,
         };
@@ -1131,6 +1138,47 @@ impl Database {
         .map(|guard| guard.into_inner())
     }
 
+    /// Returns the connections of the owner of the dev server hosting the project, for
+    /// the messages of the dev server that are only meant for its owner.
+    pub async fn owner_connection_ids_for_dev_server_project(
+        &self,
+        project_id: ProjectId,
+        connection_id: ConnectionId,
+    ) -> Result<TransactionGuard<Vec<ConnectionId>>> {
+        self.project_transaction(project_id, |tx| async move {
+            let (project, dev_server_project) = project::Entity::find_by_id(project_id)
+                .find_also_related(dev_server_project::Entity)
+                .one(&*tx)
+                .await?
+                .ok_or_else(|| anyhow!("no such project"))?;
+            if project.host_connection()? != connection_id {
+                return Err(anyhow!("not the host of the project"))?;
+            }
+            let Some(dev_server_project) = dev_server_project else {
+                return Err(anyhow!("not a dev server project"))?;
+            };
+            let dev_server = dev_server::Entity::find_by_id(dev_server_project.dev_server_id)
+                .one(&*tx)
+                .await?
+                .ok_or_else(|| anyhow!("no such dev server"))?;
+
+            let collaborators = project_collaborator::Entity::find()
+                .filter(
+                    project_collaborator::Column::ProjectId
+                        .eq(project_id)
+                        .and(project_collaborator::Column::UserId.eq(dev_server.user_id))
+                        .and(project_collaborator::Column::IsHost.eq(false)),
+                )
+                .all(&*tx)
+                .await?;
+            Ok(collaborators
+                .into_iter()
+                .map(|collaborator| collaborator.connection())
+                .collect())
+        })
+        .await
+    }
+
     pub async fn connections_for_buffer_update(
         &self,
         project_id: ProjectId,
@@ -709,10 +709,7 @@ impl Database {
             .all(tx)
             .await?
             .into_iter()
-            .map(|language_server| proto::LanguageServer {
-                id: language_server.id as u64,
-                name: language_server.name,
-            })
+            .map(|language_server| language_server.to_proto())
             .collect::<Vec<_>>();
 
         {
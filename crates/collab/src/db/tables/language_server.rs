@@ -1,4 +1,5 @@
 use crate::db::ProjectId;
+use rpc::proto;
 use sea_orm::entity::prelude::*;
 
 #[derive(Clone, Debug, PartialEq, Eq, DeriveEntityModel)]
@@ -9,6 +10,27 @@ pub struct Model {
     #[sea_orm(primary_key)]
     pub id: i64,
     pub name: String,
+    /// The JSON-encoded characters after which the server shows signature help.
+    pub signature_help_trigger_characters: Option<String>,
+    /// The JSON-encoded characters after which the server requests signature help again when it's shown.
+    pub signature_help_retrigger_characters: Option<String>,
+}
+
+impl Model {
+    pub fn to_proto(&self) -> proto::LanguageServer {
+        let decode = |characters: &Option<String>| {
+            characters
+                .as_deref()
+                .and_then(|characters| serde_json::from_str(characters).ok())
+                .unwrap_or_default()
+        };
+        proto::LanguageServer {
+            id: self.id as u64,
+            name: self.name.clone(),
+            signature_help_trigger_characters: decode(&self.signature_help_trigger_characters),
+            signature_help_retrigger_characters: decode(&self.signature_help_retrigger_characters),
+        }
+    }
 }
 
 #[derive(Copy, Clone, Debug, EnumIter, DeriveRelation)]
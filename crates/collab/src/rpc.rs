@@ -476,6 +476,9 @@ impl Server {
             .add_request_handler(user_handler(
                 forward_read_only_project_request::<proto::OpenBufferForSymbol>,
             ))
+            .add_request_handler(user_handler(
+                forward_read_only_project_request::<proto::ResolveWorkspaceSymbol>,
+            ))
             .add_request_handler(user_handler(
                 forward_read_only_project_request::<proto::OpenBufferById>,
             ))
@@ -488,6 +491,75 @@ impl Server {
             .add_request_handler(user_handler(
                 forward_read_only_project_request::<proto::OpenBufferByPath>,
             ))
+            .add_request_handler(user_handler(
+                forward_read_only_project_request::<proto::LspExtViewHir>,
+            ))
+            .add_request_handler(user_handler(
+                forward_read_only_project_request::<proto::LspExtViewMir>,
+            ))
+            .add_request_handler(user_handler(
+                forward_read_only_project_request::<proto::LspExtClangdAst>,
+            ))
+            .add_request_handler(user_handler(
+                forward_read_only_project_request::<proto::LspExtOpenCargoToml>,
+            ))
+            .add_request_handler(user_handler(
+                forward_read_only_project_request::<proto::LspExtParentModule>,
+            ))
+            .add_request_handler(user_handler(
+                forward_read_only_project_request::<proto::LspExtRunnables>,
+            ))
+            .add_request_handler(user_handler(
+                forward_read_only_project_request::<proto::LspExtRelatedTests>,
+            ))
+            .add_request_handler(user_handler(
+                forward_read_only_project_request::<proto::GetDocumentDiagnostics>,
+            ))
+            .add_request_handler(user_handler(
+                forward_read_only_project_request::<proto::PrepareTypeHierarchy>,
+            ))
+            .add_request_handler(user_handler(
+                forward_read_only_project_request::<proto::GetTypeHierarchySupertypes>,
+            ))
+            .add_request_handler(user_handler(
+                forward_read_only_project_request::<proto::GetTypeHierarchySubtypes>,
+            ))
+            .add_request_handler(user_handler(
+                forward_read_only_project_request::<proto::PrepareCallHierarchy>,
+            ))
+            .add_request_handler(user_handler(
+                forward_read_only_project_request::<proto::GetIncomingCalls>,
+            ))
+            .add_request_handler(user_handler(
+                forward_read_only_project_request::<proto::GetFoldingRanges>,
+            ))
+            .add_request_handler(user_handler(
+                forward_read_only_project_request::<proto::GetDocumentSymbols>,
+            ))
+            .add_request_handler(user_handler(
+                forward_read_only_project_request::<proto::GetSignatureHelp>,
+            ))
+            .add_request_handler(user_handler(
+                forward_read_only_project_request::<proto::GetInlineValues>,
+            ))
+            .add_request_handler(user_handler(
+                forward_read_only_project_request::<proto::GetOutgoingCalls>,
+            ))
+            .add_request_handler(user_handler(
+                forward_read_only_project_request::<proto::GetSemanticTokens>,
+            ))
+            .add_request_handler(user_handler(
+                forward_read_only_project_request::<proto::GetDocumentLinks>,
+            ))
+            .add_request_handler(user_handler(
+                forward_read_only_project_request::<proto::ResolveDocumentLink>,
+            ))
+            .add_request_handler(user_handler(
+                forward_read_only_project_request::<proto::GetDocumentColors>,
+            ))
+            .add_request_handler(user_handler(
+                forward_read_only_project_request::<proto::GetColorPresentations>,
+            ))
             .add_request_handler(user_handler(
                 forward_mutating_project_request::<proto::GetCompletions>,
             ))
@@ -551,13 +623,31 @@ impl Server {
             .add_request_handler(user_handler(
                 forward_mutating_project_request::<proto::LinkedEditingRange>,
             ))
+            .add_request_handler(user_handler(
+                forward_mutating_project_request::<proto::LspExtReloadWorkspace>,
+            ))
+            .add_request_handler(user_handler(
+                forward_mutating_project_request::<proto::LspExtRebuildProcMacros>,
+            ))
+            .add_request_handler(user_handler(
+                forward_mutating_project_request::<proto::CancelLanguageServerWork>,
+            ))
+            .add_request_handler(user_handler(
+                forward_mutating_project_request::<proto::ExecuteLspCommand>,
+            ))
             .add_message_handler(create_buffer_for_peer)
+            .add_message_handler(forward_lsp_partial_result)
             .add_request_handler(update_buffer)
             .add_message_handler(broadcast_project_message_from_host::<proto::RefreshInlayHints>)
+            .add_message_handler(
+                broadcast_project_message_from_host::<proto::RefreshSemanticTokens>,
+            )
+            .add_message_handler(broadcast_project_message_from_host::<proto::RefreshInlineValues>)
             .add_message_handler(broadcast_project_message_from_host::<proto::UpdateBufferFile>)
             .add_message_handler(broadcast_project_message_from_host::<proto::BufferReloaded>)
             .add_message_handler(broadcast_project_message_from_host::<proto::BufferSaved>)
             .add_message_handler(broadcast_project_message_from_host::<proto::UpdateDiffBase>)
+            .add_message_handler(forward_project_message_to_owner::<proto::ShowDocument>)
             .add_request_handler(get_users)
             .add_request_handler(user_handler(fuzzy_search_users))
             .add_request_handler(user_handler(request_contact))
@@ -3001,6 +3091,27 @@ async fn create_buffer_for_peer(
     Ok(())
 }
 
+/// Forward the partial results of a language server request to the guest
+/// that made the request, before the host responds to it.
+async fn forward_lsp_partial_result(
+    request: proto::LspPartialResult,
+    session: Session,
+) -> Result<()> {
+    session
+        .db()
+        .await
+        .check_user_is_project_host(
+            ProjectId::from_proto(request.project_id),
+            session.connection_id,
+        )
+        .await?;
+    let peer_id = request.peer_id.ok_or_else(|| anyhow!("invalid peer id"))?;
+    session
+        .peer
+        .forward_send(session.connection_id, peer_id.into(), request)?;
+    Ok(())
+}
+
 /// Notify other participants that a buffer has been updated. This is
 /// allowed for guests as long as the update is limited to selections.
 async fn update_buffer(
@@ -3080,6 +3191,30 @@ async fn broadcast_project_message_from_host<T: EntityMessage<Entity = ShareProj
     Ok(())
 }
 
+/// Forward a message of a dev server to the clients of its owner only.
+async fn forward_project_message_to_owner<T: EntityMessage<Entity = ShareProject>>(
+    request: T,
+    session: Session,
+) -> Result<()> {
+    let project_id = ProjectId::from_proto(request.remote_entity_id());
+    let owner_connection_ids = session
+        .db()
+        .await
+        .owner_connection_ids_for_dev_server_project(project_id, session.connection_id)
+        .await?;
+
+    broadcast(
+        Some(session.connection_id),
+        owner_connection_ids.iter().copied(),
+        |connection_id| {
+            session
+                .peer
+                .forward_send(session.connection_id, connection_id, request.clone())
+        },
+    );
+    Ok(())
+}
+
 /// Start following another user in a call.
 async fn follow(
     request: proto::Follow,
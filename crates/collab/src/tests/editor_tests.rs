@@ -1341,27 +1341,17 @@ async fn test_on_input_format_from_host_to_guest(
         editor.handle_input(">", cx);
     });
 
+    executor.advance_clock(editor::ON_TYPE_FORMATTING_DEBOUNCE_TIMEOUT);
     executor.run_until_parked();
 
     buffer_b.read_with(cx_b, |buffer, _| {
         assert_eq!(buffer.text(), "fn main() { a>~< }")
     });
 
-    // Undo should remove LSP edits first
+    // Undo should remove LSP edits together with the typed trigger character
     editor_a.update(cx_a, |editor, cx| {
         assert_eq!(editor.text(cx), "fn main() { a>~< }");
         editor.undo(&Undo, cx);
-        assert_eq!(editor.text(cx), "fn main() { a> }");
-    });
-    executor.run_until_parked();
-
-    buffer_b.read_with(cx_b, |buffer, _| {
-        assert_eq!(buffer.text(), "fn main() { a> }")
-    });
-
-    editor_a.update(cx_a, |editor, cx| {
-        assert_eq!(editor.text(cx), "fn main() { a> }");
-        editor.undo(&Undo, cx);
         assert_eq!(editor.text(cx), "fn main() { a }");
     });
     executor.run_until_parked();
@@ -1434,6 +1424,7 @@ async fn test_on_input_format_from_guest_to_host(
         editor.change_selections(None, cx, |s| s.select_ranges([13..13]));
         editor.handle_input(":", cx);
     });
+    executor.advance_clock(editor::ON_TYPE_FORMATTING_DEBOUNCE_TIMEOUT);
 
     // Receive an OnTypeFormatting request as the host's language server.
     // Return some formatting from the host's language server.
@@ -1470,21 +1461,10 @@ async fn test_on_input_format_from_guest_to_host(
         assert_eq!(buffer.text(), "fn main() { a:~: }")
     });
 
-    // Undo should remove LSP edits first
+    // Undo should remove LSP edits together with the typed trigger character
     editor_b.update(cx_b, |editor, cx| {
         assert_eq!(editor.text(cx), "fn main() { a:~: }");
         editor.undo(&Undo, cx);
-        assert_eq!(editor.text(cx), "fn main() { a: }");
-    });
-    executor.run_until_parked();
-
-    buffer_a.read_with(cx_a, |buffer, _| {
-        assert_eq!(buffer.text(), "fn main() { a: }")
-    });
-
-    editor_b.update(cx_b, |editor, cx| {
-        assert_eq!(editor.text(cx), "fn main() { a: }");
-        editor.undo(&Undo, cx);
         assert_eq!(editor.text(cx), "fn main() { a }");
     });
     executor.run_until_parked();
@@ -5049,6 +5049,102 @@ async fn test_document_highlights(
     });
 }
 
+#[gpui::test(iterations = 10)]
+async fn test_document_symbols(
+    executor: BackgroundExecutor,
+    cx_a: &mut TestAppContext,
+    cx_b: &mut TestAppContext,
+) {
+    let mut server = TestServer::start(executor.clone()).await;
+    let client_a = server.create_client(cx_a, "user_a").await;
+    let client_b = server.create_client(cx_b, "user_b").await;
+    server
+        .create_room(&mut [(&client_a, cx_a), (&client_b, cx_b)])
+        .await;
+    let active_call_a = cx_a.read(ActiveCall::global);
+
+    client_a
+        .fs()
+        .insert_tree(
+            "/root-1",
+            json!({
+                "main.rs": "mod one {\n    fn two() {}\n}\n",
+            }),
+        )
+        .await;
+
+    let mut fake_language_servers = client_a
+        .language_registry()
+        .register_fake_lsp_adapter("Rust", Default::default());
+    client_a.language_registry().add(rust_lang());
+
+    let (project_a, worktree_id) = client_a.build_local_project("/root-1", cx_a).await;
+    let project_id = active_call_a
+        .update(cx_a, |call, cx| call.share_project(project_a.clone(), cx))
+        .await
+        .unwrap();
+    let project_b = client_b.build_dev_server_project(project_id, cx_b).await;
+
+    // Open the file on client B.
+    let open_b = project_b.update(cx_b, |p, cx| p.open_buffer((worktree_id, "main.rs"), cx));
+    let buffer_b = cx_b.executor().spawn(open_b).await.unwrap();
+
+    // Request the document symbols as the guest.
+    let fake_language_server = fake_language_servers.next().await.unwrap();
+    fake_language_server.handle_request::<lsp::request::DocumentSymbolRequest, _, _>(
+        |params, _| async move {
+            assert_eq!(params.text_document.uri.as_str(), "file:///root-1/main.rs");
+            #[allow(deprecated)]
+            let symbol = |name: &str, kind, range: lsp::Range, children| lsp::DocumentSymbol {
+                name: name.to_string(),
+                detail: None,
+                kind,
+                tags: None,
+                deprecated: None,
+                range,
+                selection_range: range,
+                children: Some(children),
+            };
+            Ok(Some(lsp::DocumentSymbolResponse::Nested(vec![symbol(
+                "one",
+                lsp::SymbolKind::MODULE,
+                lsp::Range::new(lsp::Position::new(0, 0), lsp::Position::new(2, 1)),
+                vec![symbol(
+                    "two",
+                    lsp::SymbolKind::FUNCTION,
+                    lsp::Range::new(lsp::Position::new(1, 4), lsp::Position::new(1, 15)),
+                    Vec::new(),
+                )],
+            )])))
+        },
+    );
+
+    let symbols = project_b
+        .update(cx_b, |p, cx| p.document_symbols(&buffer_b, cx))
+        .await
+        .unwrap();
+
+    buffer_b.read_with(cx_b, |buffer, _| {
+        let snapshot = buffer.snapshot();
+        assert_eq!(symbols.version, buffer.version());
+        assert_eq!(symbols.symbols.len(), 1);
+        let module = &symbols.symbols[0];
+        assert_eq!(module.name, "one");
+        assert_eq!(
+            module.range.to_point(&snapshot),
+            Point::new(0, 0)..Point::new(2, 1)
+        );
+        let function = &module.children[..];
+        assert_eq!(function.len(), 1);
+        assert_eq!(function[0].name, "two");
+        assert_eq!(function[0].kind, lsp::SymbolKind::FUNCTION);
+        assert_eq!(
+            function[0].range.to_point(&snapshot),
+            Point::new(1, 4)..Point::new(1, 15)
+        );
+    });
+}
+
 #[gpui::test(iterations = 10)]
 async fn test_lsp_hover(
     executor: BackgroundExecutor,
@@ -5245,6 +5341,81 @@ async fn test_lsp_hover(
     });
 }
 
+#[gpui::test(iterations = 10)]
+async fn test_signature_help_triggers_of_remote_language_servers(
+    executor: BackgroundExecutor,
+    cx_a: &mut TestAppContext,
+    cx_b: &mut TestAppContext,
+) {
+    let mut server = TestServer::start(executor.clone()).await;
+    let client_a = server.create_client(cx_a, "user_a").await;
+    let client_b = server.create_client(cx_b, "user_b").await;
+    server
+        .create_room(&mut [(&client_a, cx_a), (&client_b, cx_b)])
+        .await;
+    let active_call_a = cx_a.read(ActiveCall::global);
+
+    client_a
+        .fs()
+        .insert_tree("/dir", json!({ "main.rs": "fn main() {}" }))
+        .await;
+
+    let adapter = || FakeLspAdapter {
+        capabilities: lsp::ServerCapabilities {
+            signature_help_provider: Some(lsp::SignatureHelpOptions {
+                trigger_characters: Some(vec!["(".into(), "<".into()]),
+                retrigger_characters: Some(vec![",".into()]),
+                work_done_progress_options: Default::default(),
+            }),
+            ..Default::default()
+        },
+        ..Default::default()
+    };
+    client_a.language_registry().add(rust_lang());
+    let mut fake_language_servers = client_a
+        .language_registry()
+        .register_fake_lsp_adapter("Rust", adapter());
+    client_b.language_registry().add(rust_lang());
+    client_b
+        .language_registry()
+        .register_fake_lsp_adapter("Rust", adapter());
+
+    // The language server starts before the project is shared.
+    let (project_a, worktree_id) = client_a.build_local_project("/dir", cx_a).await;
+    let _buffer_a = project_a
+        .update(cx_a, |project, cx| {
+            project.open_buffer((worktree_id, "main.rs"), cx)
+        })
+        .await
+        .unwrap();
+    fake_language_servers.next().await.unwrap();
+    executor.run_until_parked();
+
+    let project_id = active_call_a
+        .update(cx_a, |call, cx| call.share_project(project_a.clone(), cx))
+        .await
+        .unwrap();
+    let project_b = client_b.build_dev_server_project(project_id, cx_b).await;
+    let buffer_b = project_b
+        .update(cx_b, |project, cx| {
+            project.open_buffer((worktree_id, "main.rs"), cx)
+        })
+        .await
+        .unwrap();
+
+    project_b.read_with(cx_b, |project, cx| {
+        let triggers = project.signature_help_triggers(&buffer_b, cx);
+        assert_eq!(
+            triggers.trigger_characters,
+            HashSet::from_iter(["(".to_string(), "<".to_string()])
+        );
+        assert_eq!(
+            triggers.retrigger_characters,
+            HashSet::from_iter([",".to_string()])
+        );
+    });
+}
+
 #[gpui::test(iterations = 10)]
 async fn test_project_symbols(
     executor: BackgroundExecutor,
@@ -14,7 +14,7 @@ use language::{
 };
 use lazy_static::lazy_static;
 use parking_lot::RwLock;
-use project::{search::SearchQuery, Completion};
+use project::{search::SearchQuery, Completion, CompletionResponse};
 use settings::Settings;
 use std::{ops::Range, sync::Arc, time::Duration};
 use theme::ThemeSettings;
@@ -55,12 +55,19 @@ impl CompletionProvider for MessageEditorCompletionProvider {
         buffer_position: language::Anchor,
         _: editor::CompletionContext,
         cx: &mut ViewContext<Editor>,
-    ) -> Task<anyhow::Result<Vec<Completion>>> {
+    ) -> Task<anyhow::Result<CompletionResponse>> {
         let Some(handle) = self.0.upgrade() else {
-            return Task::ready(Ok(Vec::new()));
+            return Task::ready(Ok(CompletionResponse::default()));
         };
-        handle.update(cx, |message_editor, cx| {
+        let completions = handle.update(cx, |message_editor, cx| {
             message_editor.completions(buffer, buffer_position, cx)
+        });
+        // The mentions are searched for the query, so they're searched again as it changes.
+        cx.background_executor().spawn(async move {
+            Ok(CompletionResponse {
+                completions: completions.await?,
+                is_incomplete: true,
+            })
         })
     }
 
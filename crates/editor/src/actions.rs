@@ -132,11 +132,20 @@ pub struct ShowCompletions {
     pub(super) trigger: Option<char>,
 }
 
+/// Invokes an arbitrary `workspace/executeCommand` command on a language server of the current buffer.
+#[derive(PartialEq, Clone, Deserialize, Default)]
+pub struct ExecuteLanguageServerCommand {
+    pub command: String,
+    #[serde(default)]
+    pub arguments: Vec<serde_json::Value>,
+}
+
 impl_actions!(
     editor,
     [
         ConfirmCodeAction,
         ConfirmCompletion,
+        ExecuteLanguageServerCommand,
         ExpandExcerpts,
         ExpandExcerptsUp,
         ExpandExcerptsDown,
@@ -168,10 +177,13 @@ gpui::actions!(
         AcceptPartialInlineCompletion,
         AddSelectionAbove,
         AddSelectionBelow,
+        ApplyRenamePreview,
         Backspace,
         Cancel,
         CancelLanguageServerWork,
+        ClearCodeCellOutputs,
         ConfirmRename,
+        ConfirmRenameWithPreview,
         ContextMenuFirst,
         ContextMenuLast,
         ContextMenuNext,
@@ -205,7 +217,9 @@ gpui::actions!(
         ExpandAllHunkDiffs,
         ExpandMacroRecursively,
         FindAllReferences,
+        FixAllOccurrences,
         Fold,
+        FoldImports,
         FoldSelectedRanges,
         Format,
         GoToDefinition,
@@ -214,6 +228,7 @@ gpui::actions!(
         GoToHunk,
         GoToImplementation,
         GoToImplementationSplit,
+        GoToParentModule,
         GoToPrevDiagnostic,
         GoToPrevHunk,
         GoToTypeDefinition,
@@ -245,6 +260,7 @@ gpui::actions!(
         NewlineBelow,
         NextInlineCompletion,
         NextScreen,
+        OpenCargoToml,
         OpenExcerpts,
         OpenExcerptsSplit,
         OpenPermalinkToLine,
@@ -254,13 +270,18 @@ gpui::actions!(
         PageUp,
         Paste,
         PreviousInlineCompletion,
+        RebuildProcMacros,
         Redo,
         RedoSelection,
+        ReloadWorkspace,
         Rename,
+        RestartKernels,
         RestartLanguageServer,
         RevealInFinder,
         ReverseLines,
         RevertSelectedHunks,
+        RunCodeCell,
+        RunRelatedTests,
         ScrollCursorBottom,
         ScrollCursorCenter,
         ScrollCursorTop,
@@ -284,8 +305,12 @@ gpui::actions!(
         SelectUp,
         SelectPageDown,
         SelectPageUp,
+        ShowCallHierarchy,
         ShowCharacterPalette,
+        ShowColorPresentations,
         ShowInlineCompletion,
+        ShowSignatureHelp,
+        ShowTypeHierarchy,
         ShuffleLines,
         SortLinesCaseInsensitive,
         SortLinesCaseSensitive,
@@ -307,6 +332,9 @@ gpui::actions!(
         UnfoldLines,
         UniqueLinesCaseInsensitive,
         UniqueLinesCaseSensitive,
+        ViewClangdAst,
+        ViewHir,
+        ViewMir,
     ]
 );
 
@@ -0,0 +1,116 @@
+use language::{language_settings::language_settings, BufferSnapshot};
+use multi_buffer::Anchor;
+use project::DocumentSymbol;
+use text::BufferId;
+use ui::{AppContext, ViewContext};
+use util::ResultExt;
+
+use crate::{lsp_decorations::LSP_DECORATIONS_DEBOUNCE, Editor, EditorEvent, EditorMode};
+
+/// Requests the document symbols of the buffer of the newest cursor from its language server, which the
+/// breadcrumbs show instead of the tree-sitter outline for the languages configured with
+/// `outline_from_language_server`. The symbols of remote buffers are requested from the host.
+///
+/// They're refreshed with the decorations of the language servers, and when the cursor moves to another buffer.
+pub(super) fn refresh_breadcrumb_symbols(
+    editor: &mut Editor,
+    debounce: bool,
+    cx: &mut ViewContext<Editor>,
+) {
+    if editor.mode != EditorMode::Full || !editor.show_breadcrumbs {
+        return;
+    }
+    let Some(project) = editor.project.clone() else {
+        return;
+    };
+    let cursor = editor.selections.newest_anchor().head();
+    let Some(buffer) = cursor
+        .buffer_id
+        .and_then(|buffer_id| editor.buffer.read(cx).buffer(buffer_id))
+    else {
+        return;
+    };
+    let (buffer_id, version, outline_from_language_server) = {
+        let buffer = buffer.read(cx);
+        let settings = language_settings(buffer.language(), buffer.file(), cx);
+        (
+            buffer.remote_id(),
+            buffer.version(),
+            settings.outline_from_language_server,
+        )
+    };
+    if !outline_from_language_server {
+        editor.breadcrumb_symbols = None;
+        editor.breadcrumb_symbols_task = None;
+        return;
+    }
+    if editor
+        .breadcrumb_symbols
+        .as_ref()
+        .map_or(false, |(symbols_buffer_id, symbols)| {
+            *symbols_buffer_id == buffer_id && symbols.version == version
+        })
+    {
+        return;
+    }
+
+    editor.breadcrumb_symbols_task = Some(cx.spawn(|editor, mut cx| async move {
+        if debounce {
+            cx.background_executor()
+                .timer(LSP_DECORATIONS_DEBOUNCE)
+                .await;
+        }
+        let Some(symbols) = project
+            .update(&mut cx, |project, cx| project.document_symbols(&buffer, cx))
+            .log_err()
+        else {
+            return;
+        };
+        let Some(symbols) = symbols.await.log_err() else {
+            return;
+        };
+        editor
+            .update(&mut cx, |editor, cx| {
+                editor.breadcrumb_symbols = Some((buffer_id, symbols));
+                cx.emit(EditorEvent::BreadcrumbsChanged);
+            })
+            .ok();
+    }));
+}
+
+/// Returns the names of the document symbols containing the cursor, from the outermost to the innermost, if
+/// the language server reported symbols for its buffer.
+pub(super) fn breadcrumb_symbols(
+    editor: &Editor,
+    cursor: Anchor,
+    cx: &AppContext,
+) -> Option<(BufferId, Vec<String>)> {
+    let (buffer_id, symbols) = editor.breadcrumb_symbols.as_ref()?;
+    if cursor.buffer_id != Some(*buffer_id) || symbols.symbols.is_empty() {
+        return None;
+    }
+    let buffer = editor.buffer.read(cx).buffer(*buffer_id)?;
+    let names = symbols_containing(
+        &symbols.symbols,
+        cursor.text_anchor,
+        &buffer.read(cx).snapshot(),
+    );
+    Some((*buffer_id, names))
+}
+
+fn symbols_containing(
+    symbols: &[DocumentSymbol],
+    position: text::Anchor,
+    snapshot: &BufferSnapshot,
+) -> Vec<String> {
+    let mut names = Vec::new();
+    let mut symbols = symbols;
+    while let Some(symbol) = symbols.iter().find(|symbol| {
+        symbol.range.start.cmp(&position, snapshot).is_le()
+            && symbol.range.end.cmp(&position, snapshot).is_ge()
+    }) {
+        names.push(symbol.name.clone());
+        symbols = &symbol.children;
+    }
+    names
+}
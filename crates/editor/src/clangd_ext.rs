@@ -0,0 +1,114 @@
+use anyhow::Context as _;
+use gpui::{Context, Model, View, ViewContext, VisualContext, WindowContext};
+use language::{Buffer, Language};
+use lsp::LanguageServerId;
+use multi_buffer::MultiBuffer;
+use project::lsp_ext_command::ClangdAst;
+use text::ToPointUtf16;
+
+use crate::{element::register_action, Editor, ViewClangdAst};
+
+const CLANGD_SERVER_NAME: &str = "clangd";
+
+pub fn apply_related_actions(editor: &View<Editor>, cx: &mut WindowContext) {
+    let is_c_related = editor.update(cx, |editor, cx| {
+        editor
+            .buffer()
+            .read(cx)
+            .all_buffers()
+            .iter()
+            .any(|b| match b.read(cx).language() {
+                Some(l) => is_c_language(l),
+                None => false,
+            })
+    });
+
+    if is_c_related {
+        register_action(editor, cx, view_clangd_ast);
+    }
+}
+
+pub fn view_clangd_ast(editor: &mut Editor, _: &ViewClangdAst, cx: &mut ViewContext<'_, Editor>) {
+    let Some(project) = &editor.project else {
+        return;
+    };
+    let Some(workspace) = editor.workspace() else {
+        return;
+    };
+
+    let Some((range, server_to_query, buffer)) = find_clangd_in_selection(editor, cx) else {
+        return;
+    };
+
+    let project = project.clone();
+    let ast_task = project.update(cx, |project, cx| {
+        project.request_lsp(
+            buffer,
+            project::LanguageServerToQuery::Other(server_to_query),
+            ClangdAst {
+                range: range.clone(),
+            },
+            cx,
+        )
+    });
+    cx.spawn(|_editor, mut cx| async move {
+        let Some(ast) = ast_task.await.context("view clangd AST")? else {
+            log::info!("No AST for range {range:?}");
+            return Ok(());
+        };
+
+        let buffer = project
+            .update(&mut cx, |project, cx| project.create_buffer(cx))?
+            .await?;
+        workspace.update(&mut cx, |workspace, cx| {
+            buffer.update(cx, |buffer, cx| {
+                buffer.edit([(0..0, ast.render_tree())], None, cx);
+            });
+            let multibuffer =
+                cx.new_model(|cx| MultiBuffer::singleton(buffer, cx).with_title("AST".to_string()));
+            let editor = cx.new_view(|cx| {
+                let mut editor = Editor::for_multibuffer(multibuffer, Some(project), true, cx);
+                editor.set_read_only(true);
+                editor
+            });
+            workspace.add_item_to_active_pane(Box::new(editor), None, cx);
+        })
+    })
+    .detach_and_log_err(cx);
+}
+
+/// Finds the newest selection in a C or C++ buffer that clangd runs for, returning its range in the buffer.
+fn find_clangd_in_selection(
+    editor: &Editor,
+    cx: &mut ViewContext<'_, Editor>,
+) -> Option<(
+    std::ops::Range<text::PointUtf16>,
+    LanguageServerId,
+    Model<Buffer>,
+)> {
+    let project = editor.project.as_ref()?;
+    let multibuffer = editor.buffer().read(cx);
+    let selection = editor.selections.newest_anchor();
+    let buffer_id = selection.start.buffer_id?;
+    if selection.end.buffer_id != Some(buffer_id) {
+        return None;
+    }
+    let buffer = multibuffer.buffer(buffer_id)?;
+    let snapshot = buffer.read(cx).snapshot();
+    let language = snapshot.language_at(selection.start.text_anchor)?;
+    if !is_c_language(&language) {
+        return None;
+    }
+    let (_, server) = project
+        .read(cx)
+        .language_servers_for_buffer(buffer.read(cx), cx)
+        .find(|(adapter, _)| adapter.name.0.as_ref() == CLANGD_SERVER_NAME)?;
+    let range = selection.start.text_anchor.to_point_utf16(&snapshot)
+        ..selection.end.text_anchor.to_point_utf16(&snapshot);
+    Some((range, server.server_id(), buffer.clone()))
+}
+
+fn is_c_language(language: &Language) -> bool {
+    let name = language.name();
+    name.as_ref() == "C" || name.as_ref() == "C++"
+}
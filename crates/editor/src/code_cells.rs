@@ -0,0 +1,243 @@
+use std::sync::Arc;
+
+use collections::{HashMap, HashSet};
+use gpui::{
+    div, img, ImageData, IntoElement, ObjectFit, ParentElement, SharedString, Styled, ViewContext,
+};
+use project::{CellExecution, CellExecutionStatus, CellOutput};
+use settings::Settings;
+use text::BufferId;
+use theme::{ActiveTheme, ThemeSettings};
+use ui::v_flex;
+use workspace::{notifications::NotificationId, Toast};
+
+use crate::{
+    display_map::{BlockContext, BlockDisposition, BlockProperties, BlockStyle, RenderBlock},
+    ClearCodeCellOutputs, Editor, RestartKernels, RunCodeCell,
+};
+
+/// The most lines of text output shown below a cell; the last lines are kept.
+const MAX_OUTPUT_LINES: usize = 20;
+/// The height of the images displayed below a cell, in lines. Larger images are scaled down.
+const IMAGE_OUTPUT_LINES: u8 = 12;
+
+enum OutputRow {
+    Text(SharedString, bool),
+    Image(Arc<ImageData>),
+}
+
+pub(super) fn run_code_cell(editor: &mut Editor, _: &RunCodeCell, cx: &mut ViewContext<Editor>) {
+    let Some(project) = editor.project.clone() else {
+        return;
+    };
+    let head = editor.selections.newest_anchor().head();
+    let Some((buffer, position)) = editor.buffer.read(cx).text_anchor_for_position(head, cx) else {
+        return;
+    };
+    let result = project.update(cx, |project, cx| {
+        project.run_code_cell(&buffer, position, cx)
+    });
+    if let Err(error) = result {
+        log::info!("Failed to run code cell: {error:#}");
+        if let Some(workspace) = editor.workspace() {
+            workspace.update(cx, |workspace, cx| {
+                workspace.show_toast(
+                    Toast::new(NotificationId::unique::<RunCodeCell>(), error.to_string()),
+                    cx,
+                )
+            });
+        }
+    }
+}
+
+pub(super) fn clear_code_cell_outputs(
+    editor: &mut Editor,
+    _: &ClearCodeCellOutputs,
+    cx: &mut ViewContext<Editor>,
+) {
+    let Some(project) = editor.project.clone() else {
+        return;
+    };
+    let buffer_ids = editor
+        .buffer
+        .read(cx)
+        .all_buffers()
+        .into_iter()
+        .map(|buffer| buffer.read(cx).remote_id())
+        .collect::<Vec<_>>();
+    project.update(cx, |project, cx| {
+        for buffer_id in buffer_ids {
+            project.clear_code_cell_outputs(buffer_id, cx);
+        }
+    });
+}
+
+pub(super) fn restart_kernels(
+    editor: &mut Editor,
+    _: &RestartKernels,
+    cx: &mut ViewContext<Editor>,
+) {
+    if let Some(project) = editor.project.clone() {
+        project.update(cx, |project, cx| project.restart_kernels(cx));
+    }
+}
+
+/// Shows the outputs of the buffer's executed cells in blocks below the cells.
+pub(super) fn refresh_code_cell_outputs(
+    editor: &mut Editor,
+    buffer_id: BufferId,
+    cx: &mut ViewContext<Editor>,
+) {
+    let Some(project) = editor.project.clone() else {
+        return;
+    };
+    let multi_buffer = editor.buffer.read(cx);
+    let Some(buffer) = multi_buffer.buffer(buffer_id) else {
+        return;
+    };
+    let snapshot = multi_buffer.snapshot(cx);
+    let buffer_snapshot = buffer.read(cx).snapshot();
+    let excerpts = multi_buffer.excerpts_for_buffer(&buffer, cx);
+    let executions = project.read(cx).code_cell_executions(buffer_id).to_vec();
+
+    let mut blocks_to_remove = editor
+        .code_cell_blocks
+        .iter()
+        .filter(|((block_buffer_id, _), _)| *block_buffer_id == buffer_id)
+        .map(|(key, _)| *key)
+        .collect::<HashSet<_>>();
+    let mut blocks_to_insert = Vec::new();
+    let mut blocks_to_replace = HashMap::default();
+    for execution in executions {
+        let key = (buffer_id, execution.id);
+        let Some(position) = excerpts.iter().find_map(|(excerpt_id, excerpt_range)| {
+            let context = &excerpt_range.context;
+            let end = execution.range.end;
+            (context.start.cmp(&end, &buffer_snapshot).is_le()
+                && context.end.cmp(&end, &buffer_snapshot).is_ge())
+            .then(|| snapshot.anchor_in_excerpt(*excerpt_id, end))
+            .flatten()
+        }) else {
+            continue;
+        };
+        let (height, render) = render_execution(&execution);
+        match editor.code_cell_blocks.get(&key) {
+            Some(block_id) => {
+                blocks_to_remove.remove(&key);
+                blocks_to_replace.insert(*block_id, (Some(height), render));
+            }
+            None => blocks_to_insert.push((
+                key,
+                BlockProperties {
+                    position,
+                    height,
+                    style: BlockStyle::Fixed,
+                    render,
+                    disposition: BlockDisposition::Below,
+                },
+            )),
+        }
+    }
+
+    let removed_block_ids = blocks_to_remove
+        .into_iter()
+        .filter_map(|key| editor.code_cell_blocks.remove(&key))
+        .collect::<HashSet<_>>();
+    if !removed_block_ids.is_empty() {
+        editor.remove_blocks(removed_block_ids, None, cx);
+    }
+    if !blocks_to_replace.is_empty() {
+        editor.replace_blocks(blocks_to_replace, None, cx);
+    }
+    if !blocks_to_insert.is_empty() {
+        let (keys, blocks): (Vec<_>, Vec<_>) = blocks_to_insert.into_iter().unzip();
+        let block_ids = editor.insert_blocks(blocks, None, cx);
+        editor
+            .code_cell_blocks
+            .extend(keys.into_iter().zip(block_ids));
+    }
+}
+
+fn render_execution(execution: &CellExecution) -> (u8, RenderBlock) {
+    let line_count = execution
+        .outputs
+        .iter()
+        .map(|output| match output {
+            CellOutput::Text(text) => text.lines().count(),
+            CellOutput::Image(_) => 0,
+        })
+        .sum::<usize>();
+    // Images are always shown, only the first lines of text are hidden.
+    let mut hidden_lines = line_count.saturating_sub(MAX_OUTPUT_LINES);
+    let mut rows = Vec::new();
+    if hidden_lines > 0 {
+        rows.push(OutputRow::Text(
+            format!("… {hidden_lines} more lines").into(),
+            false,
+        ));
+    }
+    for output in &execution.outputs {
+        match output {
+            CellOutput::Text(text) => {
+                let lines = text.lines().collect::<Vec<_>>();
+                let hidden_here = hidden_lines.min(lines.len());
+                hidden_lines -= hidden_here;
+                rows.extend(
+                    lines[hidden_here..]
+                        .iter()
+                        .map(|line| OutputRow::Text(line.to_string().into(), false)),
+                );
+            }
+            CellOutput::Image(image) => rows.push(OutputRow::Image(image.clone())),
+        }
+    }
+    match &execution.status {
+        CellExecutionStatus::Queued => rows.push(OutputRow::Text("Queued…".into(), false)),
+        CellExecutionStatus::Running if execution.outputs.is_empty() => {
+            rows.push(OutputRow::Text("Running…".into(), false))
+        }
+        CellExecutionStatus::Running | CellExecutionStatus::Finished => {}
+        CellExecutionStatus::Failed(error) => {
+            rows.push(OutputRow::Text(error.clone().into(), true))
+        }
+    }
+    if rows.is_empty() {
+        rows.push(OutputRow::Text("".into(), false));
+    }
+    let height = rows
+        .iter()
+        .map(|row| match row {
+            OutputRow::Text(..) => 1,
+            OutputRow::Image(_) => IMAGE_OUTPUT_LINES as usize,
+        })
+        .sum::<usize>()
+        .min(u8::MAX as usize) as u8;
+
+    let render: RenderBlock = Box::new(move |cx: &mut BlockContext| {
+        let theme_settings = ThemeSettings::get_global(cx);
+        let font_family = theme_settings.buffer_font.family.clone();
+        let text_color = cx.theme().colors().text_muted;
+        let error_color = cx.theme().status().error;
+        v_flex()
+            .pl(cx.anchor_x)
+            .font_family(font_family)
+            .children(rows.iter().map(|row| {
+                match row {
+                    OutputRow::Text(text, is_error) => div()
+                        .h(cx.line_height)
+                        .text_color(if *is_error { error_color } else { text_color })
+                        .child(text.clone()),
+                    OutputRow::Image(image) => {
+                        div().h(cx.line_height * IMAGE_OUTPUT_LINES as f32).child(
+                            img(image.clone())
+                                .object_fit(ObjectFit::ScaleDown)
+                                .max_w_full()
+                                .max_h_full(),
+                        )
+                    }
+                }
+            }))
+            .into_any_element()
+    });
+    (height, render)
+}
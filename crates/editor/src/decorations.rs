@@ -0,0 +1,283 @@
+//! Decorations: the gutter icons, line highlights and inline hints that other crates, like coverage tools,
+//! profilers or security scanners, add to an editor. Each provider sets all of its decorations at once, keyed by
+//! a type like the other highlights of the editor, and they're anchored, so they move with the edits of the buffer.
+//!
+//! When several decorations cover a row, the gutter icon and the line highlight of the one with the highest
+//! z-index are shown, and on a tie the ones of the provider that set its decorations last. The row highlights of
+//! the editor itself, like the ones of the diff hunks, are shown over the line highlights of decorations.
+
+use std::{any::TypeId, cmp, ops::Range};
+
+use collections::{BTreeMap, HashMap};
+use gpui::{Hsla, SharedString, ViewContext};
+use language::{Bias, ToOffset};
+use multi_buffer::Anchor;
+use text::BufferId;
+use ui::{Color, IconName};
+use util::post_inc;
+
+use crate::{
+    display_map::ToDisplayPoint, DisplayPoint, DisplayRow, DisplaySnapshot, Editor, Inlay, InlayId,
+};
+
+#[derive(Clone, Debug)]
+pub struct Decoration {
+    pub range: Range<Anchor>,
+    pub kind: DecorationKind,
+    /// Decorations with a higher z-index are shown over the ones with a lower z-index on the same rows.
+    pub z_index: i32,
+    pub invalidation: DecorationInvalidation,
+}
+
+#[derive(Clone, Debug)]
+pub enum DecorationKind {
+    /// An icon in the gutter of the first row of the range.
+    GutterIcon {
+        icon: IconName,
+        color: Color,
+        tooltip: Option<SharedString>,
+    },
+    /// A background highlight of the rows of the range.
+    LineHighlight(Hsla),
+    /// A hint shown inline at the end of the range.
+    InlineHint(SharedString),
+}
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum DecorationInvalidation {
+    /// The decoration is kept until its provider replaces or clears its decorations.
+    #[default]
+    Never,
+    /// The decoration is removed when the text of its range is edited, like a coverage marker of a changed line.
+    OnEdit,
+}
+
+#[derive(Default)]
+pub(crate) struct Decorations {
+    providers: HashMap<TypeId, ProviderDecorations>,
+    next_order: usize,
+}
+
+struct ProviderDecorations {
+    /// The order in which the providers set their decorations, which breaks the ties of z-indices.
+    order: usize,
+    decorations: Vec<(Decoration, Option<InlayId>)>,
+    /// The versions of the buffers the edits of which were last checked for invalidations.
+    versions: HashMap<BufferId, clock::Global>,
+}
+
+pub(crate) struct DecorationGutterIcon {
+    pub icon: IconName,
+    pub color: Color,
+    pub tooltip: Option<SharedString>,
+}
+
+impl Editor {
+    /// Replaces the decorations of the provider with the given decorations.
+    pub fn set_decorations<T: 'static>(
+        &mut self,
+        decorations: Vec<Decoration>,
+        cx: &mut ViewContext<Self>,
+    ) {
+        let removed_inlays = self.remove_decorations(TypeId::of::<T>());
+        let mut inserted_inlays = Vec::new();
+        let mut versions = HashMap::default();
+        let multibuffer = self.buffer.read(cx);
+        let decorations = decorations
+            .into_iter()
+            .map(|decoration| {
+                if let Some(buffer_id) = decoration.range.start.buffer_id {
+                    if let Some(buffer) = multibuffer.buffer(buffer_id) {
+                        versions
+                            .entry(buffer_id)
+                            .or_insert_with(|| buffer.read(cx).version());
+                    }
+                }
+                let inlay_id = match &decoration.kind {
+                    DecorationKind::InlineHint(text) => {
+                        let inlay = Inlay::decoration(
+                            post_inc(&mut self.next_inlay_id),
+                            decoration.range.end,
+                            text.to_string(),
+                        );
+                        let inlay_id = inlay.id;
+                        inserted_inlays.push(inlay);
+                        Some(inlay_id)
+                    }
+                    DecorationKind::GutterIcon { .. } | DecorationKind::LineHighlight(_) => None,
+                };
+                (decoration, inlay_id)
+            })
+            .collect();
+        self.decorations.providers.insert(
+            TypeId::of::<T>(),
+            ProviderDecorations {
+                order: post_inc(&mut self.decorations.next_order),
+                decorations,
+                versions,
+            },
+        );
+        self.splice_inlays(removed_inlays, inserted_inlays, cx);
+    }
+
+    /// Removes all the decorations of the provider.
+    pub fn clear_decorations<T: 'static>(&mut self, cx: &mut ViewContext<Self>) {
+        let removed_inlays = self.remove_decorations(TypeId::of::<T>());
+        self.splice_inlays(removed_inlays, Vec::new(), cx);
+    }
+
+    /// Returns the decorations of the provider that weren't invalidated by edits.
+    pub fn decorations<T: 'static>(&self) -> impl Iterator<Item = &Decoration> {
+        self.decorations
+            .providers
+            .get(&TypeId::of::<T>())
+            .into_iter()
+            .flat_map(|provider| provider.decorations.iter())
+            .map(|(decoration, _)| decoration)
+    }
+
+    fn remove_decorations(&mut self, provider: TypeId) -> Vec<InlayId> {
+        self.decorations
+            .providers
+            .remove(&provider)
+            .into_iter()
+            .flat_map(|provider| provider.decorations)
+            .filter_map(|(_, inlay_id)| inlay_id)
+            .collect()
+    }
+
+    /// Returns the color of the line highlight of each of the display rows covered by decorations.
+    pub(crate) fn decoration_line_highlights(
+        &self,
+        rows: Range<DisplayRow>,
+        snapshot: &DisplaySnapshot,
+    ) -> BTreeMap<DisplayRow, Hsla> {
+        let buffer = &snapshot.buffer_snapshot;
+        let rows_start =
+            snapshot.display_point_to_anchor(DisplayPoint::new(rows.start, 0), Bias::Left);
+        let rows_end = if rows.end > snapshot.max_point().row() {
+            Anchor::max()
+        } else {
+            snapshot.display_point_to_anchor(DisplayPoint::new(rows.end, 0), Bias::Right)
+        };
+
+        let mut highlights = BTreeMap::default();
+        for (decoration, _) in self.decorations_by_z_index() {
+            let DecorationKind::LineHighlight(color) = &decoration.kind else {
+                continue;
+            };
+            // Only the decorations around the rows are converted to display points.
+            if decoration.range.end.cmp(&rows_start, buffer).is_lt()
+                || decoration.range.start.cmp(&rows_end, buffer).is_gt()
+            {
+                continue;
+            }
+            let start_row = decoration.range.start.to_display_point(snapshot).row();
+            let end_row = decoration.range.end.to_display_point(snapshot).row();
+            for row in start_row.0.max(rows.start.0)..(end_row.0 + 1).min(rows.end.0) {
+                highlights.insert(DisplayRow(row), *color);
+            }
+        }
+        highlights
+    }
+
+    /// Returns the gutter icon shown on each of the display rows with decorations.
+    pub(crate) fn decoration_gutter_icons(
+        &self,
+        rows: Range<DisplayRow>,
+        snapshot: &DisplaySnapshot,
+    ) -> BTreeMap<DisplayRow, DecorationGutterIcon> {
+        let mut icons = BTreeMap::default();
+        for (decoration, _) in self.decorations_by_z_index() {
+            if let DecorationKind::GutterIcon {
+                icon,
+                color,
+                tooltip,
+            } = &decoration.kind
+            {
+                let row = decoration.range.start.to_display_point(snapshot).row();
+                if rows.contains(&row) {
+                    icons.insert(
+                        row,
+                        DecorationGutterIcon {
+                            icon: *icon,
+                            color: *color,
+                            tooltip: tooltip.clone(),
+                        },
+                    );
+                }
+            }
+        }
+        icons
+    }
+
+    /// The decorations of all providers, from the bottom to the top.
+    fn decorations_by_z_index(&self) -> Vec<(&Decoration, usize)> {
+        let mut decorations = self
+            .decorations
+            .providers
+            .values()
+            .flat_map(|provider| {
+                provider
+                    .decorations
+                    .iter()
+                    .map(|(decoration, _)| (decoration, provider.order))
+            })
+            .collect::<Vec<_>>();
+        decorations.sort_by_key(|(decoration, order)| (decoration.z_index, *order));
+        decorations
+    }
+}
+
+/// Removes the decorations invalidated on edit the ranges of which were edited since they were last checked.
+pub(super) fn invalidate_edited_decorations(editor: &mut Editor, cx: &mut ViewContext<Editor>) {
+    let multibuffer = editor.buffer.read(cx);
+    let mut removed_inlays = Vec::new();
+    for provider in editor.decorations.providers.values_mut() {
+        let mut edits = HashMap::default();
+        for (buffer_id, version) in &mut provider.versions {
+            let Some(buffer) = multibuffer.buffer(*buffer_id) else {
+                continue;
+            };
+            let buffer = buffer.read(cx);
+            if buffer.version().changed_since(version) {
+                let edited_ranges = buffer
+                    .edits_since::<usize>(version)
+                    .map(|edit| edit.new)
+                    .collect::<Vec<_>>();
+                edits.insert(*buffer_id, (buffer.snapshot(), edited_ranges));
+                *version = buffer.version();
+            }
+        }
+        if edits.is_empty() {
+            continue;
+        }
+
+        provider.decorations.retain(|(decoration, inlay_id)| {
+            if decoration.invalidation != DecorationInvalidation::OnEdit {
+                return true;
+            }
+            let Some((buffer, edited_ranges)) = decoration
+                .range
+                .start
+                .buffer_id
+                .and_then(|buffer_id| edits.get(&buffer_id))
+            else {
+                return true;
+            };
+            let start = decoration.range.start.text_anchor.to_offset(buffer);
+            let end = decoration.range.end.text_anchor.to_offset(buffer);
+            let (start, end) = (cmp::min(start, end), cmp::max(start, end));
+            let edited = edited_ranges
+                .iter()
+                .any(|edited_range| edited_range.start <= end && edited_range.end >= start);
+            if edited {
+                removed_inlays.extend(*inlay_id);
+            }
+            !edited
+        });
+    }
+    if !removed_inlays.is_empty() {
+        editor.splice_inlays(removed_inlays, Vec::new(), cx);
+    }
+}
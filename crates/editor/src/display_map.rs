@@ -80,7 +80,15 @@ pub trait ToDisplayPoint {
     fn to_display_point(&self, map: &DisplaySnapshot) -> DisplayPoint;
 }
 
-type TextHighlights = TreeMap<Option<TypeId>, Arc<(HighlightStyle, Vec<Range<Anchor>>)>>;
+/// Identifies a set of text highlights: either all highlights of a type, or one of the
+/// several differently styled highlight sets a single type may own.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord)]
+pub enum HighlightKey {
+    Type(TypeId),
+    TypePlus(TypeId, usize),
+}
+
+type TextHighlights = TreeMap<HighlightKey, Arc<(HighlightStyle, Vec<Range<Anchor>>)>>;
 type InlayHighlights = TreeMap<TypeId, TreeMap<InlayId, (HighlightStyle, InlayHighlight)>>;
 
 /// Decides how text in a [`MultiBuffer`] should be displayed in a buffer, handling inlay hints,
@@ -107,6 +115,9 @@ pub struct DisplayMap {
     inlay_highlights: InlayHighlights,
     /// A container for explicitly foldable ranges, which supersede indentation based fold range suggestions.
     crease_map: CreaseMap,
+    /// The foldable ranges reported by language servers, sorted by their start. They come after the creases and
+    /// before the indentation when looking for the range to fold at a row.
+    folding_ranges: Arc<[Range<Anchor>]>,
     fold_placeholder: FoldPlaceholder,
     pub clip_at_line_ends: bool,
 }
@@ -152,6 +163,7 @@ impl DisplayMap {
             wrap_map,
             block_map,
             crease_map,
+            folding_ranges: Arc::from([]),
             fold_placeholder,
             text_highlights: Default::default(),
             inlay_highlights: Default::default(),
@@ -179,6 +191,7 @@ impl DisplayMap {
             wrap_snapshot,
             block_snapshot,
             crease_snapshot: self.crease_map.snapshot(),
+            folding_ranges: self.folding_ranges.clone(),
             text_highlights: self.text_highlights.clone(),
             inlay_highlights: self.inlay_highlights.clone(),
             clip_at_line_ends: self.clip_at_line_ends,
@@ -367,6 +380,11 @@ impl DisplayMap {
         Some(DisplayRow(block_row.0))
     }
 
+    /// Replaces the foldable ranges reported by language servers, which must be sorted by their start.
+    pub fn set_folding_ranges(&mut self, ranges: Arc<[Range<Anchor>]>) {
+        self.folding_ranges = ranges;
+    }
+
     pub fn highlight_text(
         &mut self,
         type_id: TypeId,
@@ -374,7 +392,20 @@ impl DisplayMap {
         style: HighlightStyle,
     ) {
         self.text_highlights
-            .insert(Some(type_id), Arc::new((style, ranges)));
+            .insert(HighlightKey::Type(type_id), Arc::new((style, ranges)));
+    }
+
+    pub fn highlight_text_key(
+        &mut self,
+        type_id: TypeId,
+        key: usize,
+        ranges: Vec<Range<Anchor>>,
+        style: HighlightStyle,
+    ) {
+        self.text_highlights.insert(
+            HighlightKey::TypePlus(type_id, key),
+            Arc::new((style, ranges)),
+        );
     }
 
     pub(crate) fn highlight_inlays(
@@ -397,11 +428,27 @@ impl DisplayMap {
     }
 
     pub fn text_highlights(&self, type_id: TypeId) -> Option<(HighlightStyle, &[Range<Anchor>])> {
-        let highlights = self.text_highlights.get(&Some(type_id))?;
+        let highlights = self.text_highlights.get(&HighlightKey::Type(type_id))?;
         Some((highlights.0, &highlights.1))
     }
     pub fn clear_highlights(&mut self, type_id: TypeId) -> bool {
-        let mut cleared = self.text_highlights.remove(&Some(type_id)).is_some();
+        let mut cleared = self
+            .text_highlights
+            .remove(&HighlightKey::Type(type_id))
+            .is_some();
+        let is_keyed_highlight = |key: &HighlightKey| match key {
+            HighlightKey::Type(_) => false,
+            HighlightKey::TypePlus(key_type_id, _) => *key_type_id == type_id,
+        };
+        if self
+            .text_highlights
+            .iter()
+            .any(|(key, _)| is_keyed_highlight(key))
+        {
+            self.text_highlights
+                .retain(|key, _| !is_keyed_highlight(key));
+            cleared = true;
+        }
         cleared |= self.inlay_highlights.remove(&type_id).is_some();
         cleared
     }
@@ -492,6 +539,7 @@ pub struct DisplaySnapshot {
     pub buffer_snapshot: MultiBufferSnapshot,
     pub fold_snapshot: FoldSnapshot,
     pub crease_snapshot: CreaseSnapshot,
+    folding_ranges: Arc<[Range<Anchor>]>,
     inlay_snapshot: InlaySnapshot,
     tab_snapshot: TabSnapshot,
     wrap_snapshot: WrapSnapshot,
@@ -970,6 +1018,20 @@ impl DisplaySnapshot {
             .unwrap_or(false)
     }
 
+    /// The largest foldable range reported by language servers that starts at the row, if any.
+    pub fn folding_range_for_row(&self, buffer_row: MultiBufferRow) -> Option<Range<Point>> {
+        let start_ix = self.folding_ranges.partition_point(|range| {
+            range.start.to_point(&self.buffer_snapshot).row < buffer_row.0
+        });
+        self.folding_ranges[start_ix..]
+            .iter()
+            .take_while(|range| range.start.to_point(&self.buffer_snapshot).row == buffer_row.0)
+            .filter(|range| range.start.is_valid(&self.buffer_snapshot))
+            .map(|range| range.to_point(&self.buffer_snapshot))
+            .filter(|range| range.end.row > range.start.row)
+            .max_by_key(|range| range.end)
+    }
+
     pub fn foldable_range(
         &self,
         buffer_row: MultiBufferRow,
@@ -983,6 +1045,11 @@ impl DisplaySnapshot {
                 crease.range.to_point(&self.buffer_snapshot),
                 crease.placeholder.clone(),
             ))
+        } else if let Some(range) = self
+            .folding_range_for_row(buffer_row)
+            .filter(|_| !self.is_line_folded(buffer_row))
+        {
+            Some((range, self.fold_placeholder.clone()))
         } else if self.starts_indent(MultiBufferRow(start.row))
             && !self.is_line_folded(MultiBufferRow(start.row))
         {
@@ -1030,7 +1097,9 @@ impl DisplaySnapshot {
         &self,
     ) -> Option<Arc<(HighlightStyle, Vec<Range<Anchor>>)>> {
         let type_id = TypeId::of::<Tag>();
-        self.text_highlights.get(&Some(type_id)).cloned()
+        self.text_highlights
+            .get(&HighlightKey::Type(type_id))
+            .cloned()
     }
 
     #[allow(unused)]
@@ -0,0 +1,200 @@
+use collections::HashMap;
+use gpui::{HighlightStyle, Hsla, Model, Rgba};
+use language::Buffer;
+use multi_buffer::Anchor;
+use project::{BufferDocumentColors, DocumentColor, Project};
+use ui::{ContextMenu, ViewContext};
+use util::ResultExt;
+
+use crate::{
+    lsp_decorations::request_buffer_decorations, mouse_context_menu::MouseContextMenu, Editor,
+    EditorMode, ShowColorPresentations,
+};
+
+/// Text highlights of the document colors, keyed by the index of their color.
+pub(super) enum DocumentColorHighlight {}
+
+/// Requests the colors of all buffers of the editor, and shows them as swatches: the text of each color is
+/// highlighted with it.
+pub(super) fn refresh_document_colors(
+    editor: &mut Editor,
+    debounce: bool,
+    cx: &mut ViewContext<Editor>,
+) {
+    if editor.mode != EditorMode::Full {
+        return;
+    }
+    let Some(project) = editor.project.clone() else {
+        return;
+    };
+    let buffers = editor
+        .buffer
+        .read(cx)
+        .all_buffers()
+        .into_iter()
+        .collect::<Vec<_>>();
+
+    editor.document_colors_task = Some(request_buffer_decorations(
+        project,
+        buffers,
+        debounce,
+        Project::document_colors,
+        apply_document_colors,
+        cx,
+    ));
+}
+
+fn apply_document_colors(
+    editor: &mut Editor,
+    document_colors: Vec<(Model<Buffer>, BufferDocumentColors)>,
+    cx: &mut ViewContext<Editor>,
+) {
+    highlight_document_colors(editor, &document_colors, cx);
+    editor.document_colors = document_colors
+        .into_iter()
+        .map(|(buffer, colors)| (buffer.read(cx).remote_id(), (buffer, colors)))
+        .collect();
+}
+
+fn highlight_document_colors(
+    editor: &mut Editor,
+    document_colors: &[(Model<Buffer>, BufferDocumentColors)],
+    cx: &mut ViewContext<Editor>,
+) {
+    let multi_buffer = editor.buffer.read(cx);
+    let snapshot = multi_buffer.snapshot(cx);
+    let mut highlights = Vec::<(HighlightStyle, Vec<std::ops::Range<Anchor>>)>::new();
+    let mut highlight_ixs_by_color = HashMap::<[u32; 4], usize>::default();
+    for (buffer, colors) in document_colors {
+        let buffer_snapshot = buffer.read(cx).snapshot();
+        for (excerpt_id, excerpt_range) in multi_buffer.excerpts_for_buffer(buffer, cx) {
+            let context = excerpt_range.context;
+            for color in colors.colors.iter() {
+                if color
+                    .range
+                    .end
+                    .cmp(&context.start, &buffer_snapshot)
+                    .is_le()
+                    || color
+                        .range
+                        .start
+                        .cmp(&context.end, &buffer_snapshot)
+                        .is_ge()
+                {
+                    continue;
+                }
+                let Some(range) = snapshot
+                    .anchor_in_excerpt(excerpt_id, color.range.start)
+                    .zip(snapshot.anchor_in_excerpt(excerpt_id, color.range.end))
+                    .map(|(start, end)| start..end)
+                else {
+                    continue;
+                };
+                let lsp::Color {
+                    red,
+                    green,
+                    blue,
+                    alpha,
+                } = color.color;
+                let key = [red, green, blue, alpha].map(f32::to_bits);
+                let ix = *highlight_ixs_by_color.entry(key).or_insert_with(|| {
+                    highlights.push((swatch_style(&color.color), Vec::new()));
+                    highlights.len() - 1
+                });
+                highlights[ix].1.push(range);
+            }
+        }
+    }
+
+    editor.clear_highlights::<DocumentColorHighlight>(cx);
+    for (key, (style, mut ranges)) in highlights.into_iter().enumerate() {
+        ranges.sort_by(|a, b| a.start.cmp(&b.start, &snapshot));
+        editor.highlight_text_key::<DocumentColorHighlight>(key, ranges, style, cx);
+    }
+}
+
+/// Fills the text of a color with it, in black or white, whichever is more legible on the color.
+fn swatch_style(color: &lsp::Color) -> HighlightStyle {
+    let background = Hsla::from(Rgba {
+        r: color.red,
+        g: color.green,
+        b: color.blue,
+        a: color.alpha,
+    });
+    let luminance = 0.2126 * color.red + 0.7152 * color.green + 0.0722 * color.blue;
+    let text = if luminance * color.alpha > 0.5 {
+        gpui::black()
+    } else {
+        gpui::white()
+    };
+    HighlightStyle {
+        color: Some(text),
+        background_color: Some(background),
+        ..Default::default()
+    }
+}
+
+/// The color under the given position, with the buffer it belongs to.
+fn color_at(
+    editor: &Editor,
+    anchor: Anchor,
+    cx: &mut ViewContext<Editor>,
+) -> Option<(Model<Buffer>, DocumentColor)> {
+    let (buffer, position) = editor
+        .buffer
+        .read(cx)
+        .text_anchor_for_position(anchor, cx)?;
+    let (_, colors) = editor.document_colors.get(&buffer.read(cx).remote_id())?;
+    let color = colors
+        .color_at(position, &buffer.read(cx).snapshot())?
+        .clone();
+    Some((buffer, color))
+}
+
+impl Editor {
+    /// Offers the server's presentations of the color under the newest cursor, which replace it when picked.
+    pub fn show_color_presentations(
+        &mut self,
+        _: &ShowColorPresentations,
+        cx: &mut ViewContext<Self>,
+    ) {
+        let Some(project) = self.project.clone() else {
+            return;
+        };
+        let Some(position) = self.pixel_position_of_newest_cursor else {
+            return;
+        };
+        let cursor = self.selections.newest_anchor().head();
+        let Some((buffer, color)) = color_at(self, cursor, cx) else {
+            return;
+        };
+        let presentations = project.update(cx, |project, cx| {
+            project.color_presentations(&buffer, &color, cx)
+        });
+        cx.spawn(|editor, mut cx| async move {
+            let presentations = presentations.await.log_err()?;
+            if presentations.is_empty() {
+                return None;
+            }
+            editor
+                .update(&mut cx, |editor, cx| {
+                    let context_menu = ContextMenu::build(cx, |menu, _| {
+                        presentations.into_iter().fold(menu, |menu, presentation| {
+                            let buffer = buffer.clone();
+                            let edits = presentation.edits(&color);
+                            menu.entry(presentation.label, None, move |cx| {
+                                buffer.update(cx, |buffer, cx| {
+                                    buffer.edit(edits.clone(), None, cx);
+                                });
+                            })
+                        })
+                    });
+                    editor.mouse_context_menu =
+                        Some(MouseContextMenu::new(position, context_menu, cx));
+                    cx.notify();
+                })
+                .ok()
+        })
+        .detach();
+    }
+}
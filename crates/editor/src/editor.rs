@@ -15,10 +15,16 @@
 pub mod actions;
 mod blame_entry_tooltip;
 mod blink_manager;
+mod breadcrumb_symbols;
+mod clangd_ext;
+mod code_cells;
 mod debounced_delay;
+mod decorations;
 pub mod display_map;
+mod document_colors;
 mod editor_settings;
 mod element;
+mod folding_ranges;
 mod git;
 mod highlight_matching_bracket;
 mod hover_links;
@@ -27,14 +33,20 @@ mod hunk_diff;
 mod indent_guides;
 mod inlay_hint_cache;
 mod inline_completion_provider;
+mod inline_values;
 pub mod items;
 mod linked_editing_ranges;
+mod lsp_decorations;
 mod mouse_context_menu;
 pub mod movement;
 mod persistence;
+mod rename_preview;
 mod rust_analyzer_ext;
 pub mod scroll;
 mod selections_collection;
+mod semantic_tokens;
+mod signature_help;
+mod snippets;
 pub mod tasks;
 
 #[cfg(test)]
@@ -52,6 +64,7 @@ use clock::ReplicaId;
 use collections::{BTreeMap, Bound, HashMap, HashSet, VecDeque};
 use convert_case::{Case, Casing};
 use debounced_delay::DebouncedDelay;
+pub use decorations::{Decoration, DecorationInvalidation, DecorationKind};
 use display_map::*;
 pub use display_map::{DisplayPoint, FoldPlaceholder};
 pub use editor_settings::{CurrentLineHighlight, EditorSettings};
@@ -59,7 +72,7 @@ use element::LineWithInvisibles;
 pub use element::{
     CursorLayout, EditorElement, HighlightedRange, HighlightedRangeLine, PointForPosition,
 };
-use futures::FutureExt;
+use futures::{FutureExt, StreamExt as _};
 use fuzzy::{StringMatch, StringMatchCandidate};
 use git::blame::GitBlame;
 use git::diff_hunk_to_display;
@@ -68,10 +81,10 @@ use gpui::{
     AppContext, AsyncWindowContext, AvailableSpace, BackgroundExecutor, Bounds, ClipboardItem,
     Context, DispatchPhase, ElementId, EventEmitter, FocusHandle, FocusOutEvent, FocusableView,
     FontId, FontStyle, FontWeight, HighlightStyle, Hsla, InteractiveText, KeyContext,
-    ListSizingBehavior, Model, MouseButton, PaintQuad, ParentElement, Pixels, Render, SharedString,
-    Size, StrikethroughStyle, Styled, StyledText, Subscription, Task, TextStyle, UnderlineStyle,
-    UniformListScrollHandle, View, ViewContext, ViewInputHandler, VisualContext, WeakFocusHandle,
-    WeakView, WhiteSpace, WindowContext,
+    ListSizingBehavior, Model, ModelContext, MouseButton, PaintQuad, ParentElement, Pixels, Render,
+    SharedString, Size, StrikethroughStyle, Styled, StyledText, Subscription, Task, TextStyle,
+    UnderlineStyle, UniformListScrollHandle, View, ViewContext, ViewInputHandler, VisualContext,
+    WeakFocusHandle, WeakView, WhiteSpace, WindowContext,
 };
 use highlight_matching_bracket::refresh_matching_bracket_highlights;
 use hover_popover::{hide_hover, HoverState};
@@ -105,10 +118,12 @@ pub use multi_buffer::{
 use multi_buffer::{ExpandExcerptDirection, MultiBufferPoint, MultiBufferRow, ToOffsetUtf16};
 use ordered_float::OrderedFloat;
 use parking_lot::{Mutex, RwLock};
+use project::lsp_command::ExecuteLspCommand;
 use project::project_settings::{GitGutterSetting, ProjectSettings};
 use project::{
-    CodeAction, Completion, FormatTrigger, Item, Location, Project, ProjectPath,
-    ProjectTransaction, TaskSourceKind, WorktreeId,
+    BufferDocumentColors, BufferDocumentSymbols, CodeAction, Completion, CompletionResponse,
+    FormatTrigger, Item, Location, Project, ProjectPath, ProjectTransaction, TaskSourceKind,
+    WorktreeId,
 };
 use rand::prelude::*;
 use rpc::{proto::*, ErrorExt};
@@ -160,11 +175,15 @@ const CURSOR_BLINK_INTERVAL: Duration = Duration::from_millis(500);
 const MAX_LINE_LEN: usize = 1024;
 const MIN_NAVIGATION_HISTORY_ROW_DELTA: i64 = 10;
 const MAX_SELECTION_HISTORY_LEN: usize = 1024;
+/// The most completions shown for a query.
+const MAX_COMPLETION_MATCHES: usize = 100;
 pub(crate) const CURSORS_VISIBLE_FOR: Duration = Duration::from_millis(2000);
 #[doc(hidden)]
 pub const CODE_ACTIONS_DEBOUNCE_TIMEOUT: Duration = Duration::from_millis(250);
 #[doc(hidden)]
 pub const DOCUMENT_HIGHLIGHTS_DEBOUNCE_TIMEOUT: Duration = Duration::from_millis(75);
+#[doc(hidden)]
+pub const ON_TYPE_FORMATTING_DEBOUNCE_TIMEOUT: Duration = Duration::from_millis(75);
 
 pub(crate) const FORMAT_TIMEOUT: Duration = Duration::from_secs(2);
 
@@ -235,6 +254,7 @@ pub fn render_parsed_markdown(
 pub(crate) enum InlayId {
     Suggestion(usize),
     Hint(usize),
+    Decoration(usize),
 }
 
 impl InlayId {
@@ -242,6 +262,7 @@ impl InlayId {
         match self {
             Self::Suggestion(id) => *id,
             Self::Hint(id) => *id,
+            Self::Decoration(id) => *id,
         }
     }
 }
@@ -268,9 +289,27 @@ pub fn init(cx: &mut AppContext) {
     workspace::register_followable_item::<Editor>(cx);
     workspace::register_deserializable_item::<Editor>(cx);
     cx.observe_new_views(
-        |workspace: &mut Workspace, _cx: &mut ViewContext<Workspace>| {
+        |workspace: &mut Workspace, cx: &mut ViewContext<Workspace>| {
             workspace.register_action(Editor::new_file);
             workspace.register_action(Editor::new_file_in_direction);
+            cx.subscribe(workspace.project(), |workspace, _, event, cx| match event {
+                project::Event::ReviewConflictingEdits(edits) => {
+                    rename_preview::review_conflicting_edits(workspace, edits.clone(), cx);
+                }
+                project::Event::ShowDocument {
+                    buffer,
+                    selection,
+                    take_focus,
+                } => show_document(
+                    workspace,
+                    buffer.clone(),
+                    selection.clone(),
+                    *take_focus,
+                    cx,
+                ),
+                _ => {}
+            })
+            .detach();
         },
     )
     .detach();
@@ -295,6 +334,31 @@ pub fn init(cx: &mut AppContext) {
     });
 }
 
+/// Opens the buffer a language server asked to show in the active pane, and selects the range of it the server
+/// asked for. The focus stays where it was unless the server asked to take it.
+fn show_document(
+    workspace: &mut Workspace,
+    buffer: Model<Buffer>,
+    selection: Option<Range<text::Anchor>>,
+    take_focus: bool,
+    cx: &mut ViewContext<Workspace>,
+) {
+    let focused = if take_focus { None } else { cx.focused() };
+    let selection = selection.map(|selection| selection.to_offset(buffer.read(cx)));
+    let pane = workspace.active_pane().clone();
+    let editor = workspace.open_project_item::<Editor>(pane, buffer, cx);
+    if let Some(selection) = selection {
+        editor.update(cx, |editor, cx| {
+            editor.change_selections(Some(Autoscroll::center()), cx, |s| {
+                s.select_ranges([selection])
+            });
+        });
+    }
+    if let Some(focused) = focused {
+        cx.focus(&focused);
+    }
+}
+
 pub struct SearchWithinRange;
 
 trait InvalidationRegion {
@@ -506,7 +570,25 @@ pub struct Editor {
     document_highlights_task: Option<Task<()>>,
     linked_editing_range_task: Option<Task<Option<()>>>,
     linked_edit_ranges: linked_editing_ranges::LinkedEditingRanges,
+    semantic_tokens_task: Option<Task<()>>,
+    document_colors_task: Option<Task<()>>,
+    document_colors: HashMap<BufferId, (Model<Buffer>, BufferDocumentColors)>,
+    folding_ranges_task: Option<Task<()>>,
+    breadcrumb_symbols_task: Option<Task<()>>,
+    /// The document symbols of the buffer of the newest cursor, shown by the breadcrumbs.
+    breadcrumb_symbols: Option<(BufferId, BufferDocumentSymbols)>,
+    /// The folding ranges of the imports reported by language servers, folded by [`FoldImports`].
+    import_folding_ranges: Vec<Range<Anchor>>,
+    code_cell_blocks: HashMap<(BufferId, usize), BlockId>,
+    /// The stack frame of the debugger the inline values are shown for.
+    inline_values_frame: Option<inline_values::InlineValuesFrame>,
+    inline_values_task: Option<Task<()>>,
+    /// Restarted on every trigger character, so only the last one of quickly typed triggers is formatted.
+    on_type_formatting_debounce: DebouncedDelay,
     pending_rename: Option<RenameState>,
+    rename_preview: Option<rename_preview::RenamePreview>,
+    signature_help_state: signature_help::SignatureHelpState,
+    decorations: decorations::Decorations,
     searchable: bool,
     cursor_shape: CursorShape,
     current_line_highlight: Option<CurrentLineHighlight>,
@@ -768,6 +850,17 @@ struct AutocloseRegion {
 struct SnippetState {
     ranges: Vec<Vec<Range<Anchor>>>,
     active_index: usize,
+    /// The choices of the tabstops with a choice placeholder, by tabstop index.
+    choices: BTreeMap<usize, Vec<String>>,
+    transforms: Vec<SnippetTransformState>,
+}
+
+/// The ranges that mirror a tabstop of a snippet through a transform.
+#[derive(Debug)]
+struct SnippetTransformState {
+    tabstop: usize,
+    ranges: Vec<Range<Anchor>>,
+    transform: snippet::Transform,
 }
 
 #[doc(hidden)]
@@ -896,6 +989,14 @@ struct CompletionsMenu {
     selected_item: usize,
     scroll_handle: UniformListScrollHandle,
     selected_completion_documentation_resolve_debounce: Arc<Mutex<DebouncedDelay>>,
+    /// Whether the completions are the choices of a snippet tabstop rather than the ones of a provider.
+    is_snippet_choice: bool,
+    /// Whether the provider returned only some of its completions, so that they're requested again as the query
+    /// changes rather than filtered.
+    is_incomplete: bool,
+    /// The last query the completions were filtered with, along with the candidates that may match it: the
+    /// candidates of the queries extending it are among them, so that typing narrows the candidates down.
+    last_filter: Option<(String, Arc<[StringMatchCandidate]>)>,
 }
 
 impl CompletionsMenu {
@@ -1175,16 +1276,20 @@ impl CompletionsMenu {
 
     pub async fn filter(&mut self, query: Option<&str>, executor: BackgroundExecutor) {
         let mut matches = if let Some(query) = query {
-            fuzzy::match_strings(
-                &self.match_candidates,
+            let candidates = self.candidates_for_query(query, &executor).await;
+            let matches = fuzzy::match_strings(
+                &candidates,
                 query,
                 query.chars().any(|c| c.is_uppercase()),
-                100,
+                MAX_COMPLETION_MATCHES,
                 &Default::default(),
                 executor,
             )
-            .await
+            .await;
+            self.last_filter = Some((query.to_string(), candidates));
+            matches
         } else {
+            self.last_filter = None;
             self.match_candidates
                 .iter()
                 .enumerate()
@@ -1276,6 +1381,42 @@ impl CompletionsMenu {
         self.matches = matches.into();
         self.selected_item = 0;
     }
+
+    /// Returns the candidates containing the characters of the query in order, which the fuzzy matches of the
+    /// query are among, searching the candidates of the last query when the query extends it.
+    async fn candidates_for_query(
+        &self,
+        query: &str,
+        executor: &BackgroundExecutor,
+    ) -> Arc<[StringMatchCandidate]> {
+        let candidates = match &self.last_filter {
+            Some((last_query, candidates)) if query.starts_with(last_query.as_str()) => {
+                candidates.clone()
+            }
+            _ => self.match_candidates.clone(),
+        };
+        let query = query
+            .chars()
+            .flat_map(char::to_lowercase)
+            .collect::<Vec<_>>();
+        executor
+            .spawn(async move {
+                candidates
+                    .iter()
+                    .filter(|candidate| {
+                        let mut query = query.iter().peekable();
+                        for character in candidate.string.chars().flat_map(char::to_lowercase) {
+                            if query.peek() == Some(&&character) {
+                                query.next();
+                            }
+                        }
+                        query.peek().is_none()
+                    })
+                    .cloned()
+                    .collect()
+            })
+            .await
+    }
 }
 
 #[derive(Clone)]
@@ -1733,6 +1874,14 @@ impl Editor {
                 project_subscriptions.push(cx.subscribe(project, |editor, _, event, cx| {
                     if let project::Event::RefreshInlayHints = event {
                         editor.refresh_inlay_hints(InlayHintRefreshReason::RefreshRequested, cx);
+                    } else if let project::Event::RefreshSemanticTokens
+                    | project::Event::LanguageServerAdded(_) = event
+                    {
+                        lsp_decorations::refresh_lsp_decorations(editor, false, cx);
+                    } else if let project::Event::RefreshInlineValues = event {
+                        inline_values::refresh_inline_values(editor, cx);
+                    } else if let project::Event::CodeCellOutputsChanged(buffer_id) = event {
+                        code_cells::refresh_code_cell_outputs(editor, *buffer_id, cx);
                     } else if let project::Event::SnippetEdit(id, snippet_edits) = event {
                         if let Some(buffer) = editor.buffer.read(cx).buffer(*id) {
                             let focus_handle = editor.focus_handle(cx);
@@ -1825,7 +1974,21 @@ impl Editor {
             code_actions_task: Default::default(),
             document_highlights_task: Default::default(),
             linked_editing_range_task: Default::default(),
+            semantic_tokens_task: None,
+            document_colors_task: None,
+            document_colors: HashMap::default(),
+            folding_ranges_task: None,
+            breadcrumb_symbols_task: None,
+            breadcrumb_symbols: None,
+            import_folding_ranges: Vec::new(),
+            code_cell_blocks: HashMap::default(),
+            inline_values_frame: None,
+            inline_values_task: None,
+            on_type_formatting_debounce: DebouncedDelay::new(),
             pending_rename: Default::default(),
+            rename_preview: None,
+            signature_help_state: Default::default(),
+            decorations: Default::default(),
             searchable: true,
             cursor_shape: Default::default(),
             current_line_highlight: None,
@@ -1894,6 +2057,7 @@ impl Editor {
             breadcrumb_header: None,
         };
         this.tasks_update_task = Some(this.refresh_runnables(cx));
+        lsp_decorations::refresh_lsp_decorations(&mut this, false, cx);
         this._subscriptions.extend(project_subscriptions);
 
         this.end_selection(cx);
@@ -2353,6 +2517,7 @@ impl Editor {
             }
 
             hide_hover(self, cx);
+            signature_help::refresh_signature_help(self, cx);
 
             if old_cursor_position.to_display_point(&display_map).row()
                 != new_cursor_position.to_display_point(&display_map).row()
@@ -2361,6 +2526,7 @@ impl Editor {
             }
             self.refresh_code_actions(cx);
             self.refresh_document_highlights(cx);
+            breadcrumb_symbols::refresh_breadcrumb_symbols(self, true, cx);
             refresh_matching_bracket_highlights(self, cx);
             self.discard_inline_completion(false, cx);
             linked_editing_ranges::refresh_linked_ranges(self, cx);
@@ -2859,6 +3025,10 @@ impl Editor {
             return true;
         }
 
+        if signature_help::hide_signature_help(self, cx) {
+            return true;
+        }
+
         if self.hide_context_menu(cx).is_some() {
             return true;
         }
@@ -3141,6 +3311,7 @@ impl Editor {
 
         drop(snapshot);
 
+        let mut format_on_type = false;
         self.transact(cx, |this, cx| {
             this.buffer.update(cx, |buffer, cx| {
                 buffer.edit(edits, this.autoindent_mode.clone(), cx);
@@ -3207,19 +3378,20 @@ impl Editor {
                 s.select(new_selections)
             });
 
-            if !brace_inserted && EditorSettings::get_global(cx).use_on_type_format {
-                if let Some(on_type_format_task) =
-                    this.trigger_on_type_formatting(text.to_string(), cx)
-                {
-                    on_type_format_task.detach_and_log_err(cx);
-                }
-            }
+            format_on_type = !brace_inserted && EditorSettings::get_global(cx).use_on_type_format;
 
             let trigger_in_words = !had_active_inline_completion;
             this.trigger_completion_on_input(&text, trigger_in_words, cx);
+            signature_help::signature_help_on_input(this, &text, cx);
             linked_editing_ranges::refresh_linked_ranges(this, cx);
             this.refresh_inline_completion(true, cx);
         });
+
+        // The formatting is triggered once the transaction of the typed text has ended, so that it's merged into
+        // that transaction.
+        if format_on_type {
+            self.trigger_on_type_formatting(text.to_string(), cx);
+        }
     }
 
     fn find_possible_emoji_shortcode_at_position(
@@ -3932,52 +4104,76 @@ impl Editor {
         cx.notify();
     }
 
-    fn trigger_on_type_formatting(
-        &self,
-        input: String,
-        cx: &mut ViewContext<Self>,
-    ) -> Option<Task<Result<()>>> {
+    fn trigger_on_type_formatting(&mut self, input: String, cx: &mut ViewContext<Self>) {
         if input.len() != 1 {
-            return None;
+            return;
         }
 
-        let project = self.project.as_ref()?;
+        let Some(project) = self.project.clone() else {
+            return;
+        };
         let position = self.selections.newest_anchor().head();
-        let (buffer, buffer_position) = self
-            .buffer
-            .read(cx)
-            .text_anchor_for_position(position, cx)?;
+        let Some((buffer, buffer_position)) =
+            self.buffer.read(cx).text_anchor_for_position(position, cx)
+        else {
+            return;
+        };
 
         // OnTypeFormatting returns a list of edits, no need to pass them between Zed instances,
-        // hence we do LSP request & edit on host side only — add formats to host's history.
+        // hence we do LSP request & edit on host side only — add formats to host's history.
         let push_to_lsp_host_history = true;
         // If this is not the host, append its history with new edits.
         let push_to_client_history = project.read(cx).is_remote();
+        // The formatting is merged into the transaction of the typed trigger character, which was just
+        // ended, so that both are undone at once. It's captured now, as other transactions, or an
+        // undo, can happen before the formatting arrives.
+        let typed_transaction_id = buffer
+            .read(cx)
+            .peek_undo_stack()
+            .map(|entry| entry.transaction_id());
 
-        let on_type_formatting = project.update(cx, |project, cx| {
-            project.on_type_format(
-                buffer.clone(),
-                buffer_position,
-                input,
-                push_to_lsp_host_history,
-                cx,
-            )
-        });
-        Some(cx.spawn(|editor, mut cx| async move {
-            if let Some(transaction) = on_type_formatting.await? {
-                if push_to_client_history {
-                    buffer
-                        .update(&mut cx, |buffer, _| {
-                            buffer.push_transaction(transaction, Instant::now());
-                        })
-                        .ok();
-                }
-                editor.update(&mut cx, |editor, cx| {
-                    editor.refresh_document_highlights(cx);
-                })?;
-            }
-            Ok(())
-        }))
+        self.on_type_formatting_debounce.fire_new(
+            ON_TYPE_FORMATTING_DEBOUNCE_TIMEOUT,
+            cx,
+            move |_, cx| {
+                let on_type_formatting = project.update(cx, |project, cx| {
+                    project.on_type_format(
+                        buffer.clone(),
+                        buffer_position,
+                        input,
+                        push_to_lsp_host_history,
+                        cx,
+                    )
+                });
+                cx.spawn(|editor, mut cx| {
+                    async move {
+                        if let Some(transaction) = on_type_formatting.await? {
+                            buffer.update(&mut cx, |buffer, _| {
+                                if push_to_client_history {
+                                    buffer.push_transaction(transaction.clone(), Instant::now());
+                                }
+                                // The typed transaction may have been undone in the meantime.
+                                if let Some(typed_transaction_id) =
+                                    typed_transaction_id.filter(|typed_transaction_id| {
+                                        *typed_transaction_id != transaction.id
+                                            && buffer.can_undo_transaction(*typed_transaction_id)
+                                    })
+                                {
+                                    buffer.merge_transactions(transaction.id, typed_transaction_id);
+                                }
+                            })?;
+                            editor.update(&mut cx, |editor, cx| {
+                                editor.refresh_document_highlights(cx);
+                            })?;
+                        }
+                        anyhow::Ok(())
+                    }
+                    .map(|result| {
+                        result.log_err();
+                    })
+                })
+            },
+        );
     }
 
     pub fn show_completions(&mut self, options: &ShowCompletions, cx: &mut ViewContext<Self>) {
@@ -3998,14 +4194,33 @@ impl Editor {
             };
 
         let query = Self::completion_query(&self.buffer.read(cx).read(cx), position);
+        let is_trigger_character = options.trigger.map_or(false, |trigger| {
+            buffer
+                .read(cx)
+                .completion_triggers()
+                .iter()
+                .any(|completion_trigger| completion_trigger.chars().eq(std::iter::once(trigger)))
+        });
         let is_followup_invoke = {
             let context_menu_state = self.context_menu.read();
-            matches!(
-                context_menu_state.deref(),
-                Some(ContextMenu::Completions(_))
-            )
+            match context_menu_state.deref() {
+                Some(ContextMenu::Completions(menu)) => {
+                    // Complete lists are only filtered as the query changes, which happens as the selections
+                    // change.
+                    if !menu.is_incomplete
+                        && !menu.is_snippet_choice
+                        && !is_trigger_character
+                        && menu.buffer == buffer
+                    {
+                        return;
+                    }
+                    true
+                }
+                _ => false,
+            }
         };
         let trigger_kind = match (options.trigger, is_followup_invoke) {
+            (Some(_), _) if is_trigger_character => CompletionTriggerKind::TRIGGER_CHARACTER,
             (_, true) => CompletionTriggerKind::TRIGGER_FOR_INCOMPLETE_COMPLETIONS,
             (Some(_), _) => CompletionTriggerKind::TRIGGER_CHARACTER,
             _ => CompletionTriggerKind::INVOKED,
@@ -4028,8 +4243,12 @@ impl Editor {
                 this.update(&mut cx, |this, _| {
                     this.completion_tasks.retain(|(task_id, _)| *task_id >= id);
                 })?;
-                let completions = completions.await.log_err();
-                let menu = if let Some(completions) = completions {
+                let response = completions.await.log_err();
+                let menu = if let Some(CompletionResponse {
+                    completions,
+                    is_incomplete,
+                }) = response
+                {
                     let mut menu = CompletionsMenu {
                         id,
                         initial_position: position,
@@ -4052,6 +4271,9 @@ impl Editor {
                         selected_completion_documentation_resolve_debounce: Arc::new(Mutex::new(
                             DebouncedDelay::new(),
                         )),
+                        is_snippet_choice: false,
+                        is_incomplete,
+                        last_filter: None,
                     };
                     menu.filter(query.as_deref(), cx.background_executor().clone())
                         .await;
@@ -4149,7 +4371,13 @@ impl Editor {
         let text;
 
         if completion.is_snippet() {
-            snippet = Some(Snippet::parse(&completion.new_text).log_err()?);
+            let variables = snippets::snippet_variables(self, cx);
+            snippet = Some(
+                Snippet::parse_with_variables(&completion.new_text, |name| {
+                    variables.get(name).cloned()
+                })
+                .log_err()?,
+            );
             text = snippet.as_ref().unwrap().text.clone();
         } else {
             snippet = None;
@@ -4238,9 +4466,14 @@ impl Editor {
         self.transact(cx, |this, cx| {
             if let Some(mut snippet) = snippet {
                 snippet.text = text.to_string();
-                for tabstop in snippet.tabstops.iter_mut().flatten() {
-                    tabstop.start -= common_prefix_len as isize;
-                    tabstop.end -= common_prefix_len as isize;
+                let snippet_ranges = snippet
+                    .tabstops
+                    .iter_mut()
+                    .flatten()
+                    .chain(snippet.transforms.iter_mut().map(|t| &mut t.range));
+                for range in snippet_ranges {
+                    range.start -= common_prefix_len as isize;
+                    range.end -= common_prefix_len as isize;
                 }
 
                 this.insert_snippet(&ranges, snippet, cx).log_err();
@@ -4281,6 +4514,9 @@ impl Editor {
             self.show_completions(&ShowCompletions { trigger: None }, cx);
         }
 
+        if completions_menu.is_snippet_choice {
+            return Some(Task::ready(Ok(())));
+        }
         let provider = self.completion_provider.as_ref()?;
         let apply_edits = provider.apply_additional_edits_for_completion(
             buffer_handle,
@@ -4516,6 +4752,51 @@ impl Editor {
         }
     }
 
+    /// Applies the quick fix of each diagnostic in the project with the same code as the diagnostic under the cursor,
+    /// and opens the edits along with a report of the fixed diagnostics.
+    pub fn fix_all_occurrences(
+        &mut self,
+        _: &FixAllOccurrences,
+        cx: &mut ViewContext<Self>,
+    ) -> Option<Task<Result<()>>> {
+        let head = self.selections.newest::<usize>(cx).head();
+        let diagnostic = self
+            .buffer
+            .read(cx)
+            .snapshot(cx)
+            .diagnostics_in_range::<_, usize>(head..head, false)
+            .filter(|entry| entry.diagnostic.is_primary && entry.diagnostic.code.is_some())
+            .min_by_key(|entry| entry.range.len())?
+            .diagnostic;
+        let code = diagnostic.code.clone()?;
+        let workspace = self.workspace()?.downgrade();
+        let fix_all = self.project.clone()?.update(cx, |project, cx| {
+            project.fix_all_diagnostics(&diagnostic, cx)
+        });
+        Some(cx.spawn(|editor, mut cx| async move {
+            let report = fix_all.await?;
+            let total = report.fixed + report.unfixable + report.failed;
+            let mut message = format!("Fixed {} of {total} occurrences of {code}", report.fixed);
+            if report.failed > 0 {
+                message.push_str(&format!(", {} fixes failed to apply", report.failed));
+            }
+            workspace.update(&mut cx, |workspace, cx| {
+                workspace.show_toast(
+                    Toast::new(NotificationId::unique::<FixAllOccurrences>(), message),
+                    cx,
+                )
+            })?;
+            Self::open_project_transaction(
+                &editor,
+                workspace,
+                report.transaction,
+                format!("Fix All {code}"),
+                cx,
+            )
+            .await
+        }))
+    }
+
     pub async fn open_project_transaction(
         this: &WeakView<Editor>,
         workspace: WeakView<Workspace>,
@@ -5096,7 +5377,7 @@ impl Editor {
             ranges: Vec<Range<T>>,
         }
 
-        let tabstops = self.buffer.update(cx, |buffer, cx| {
+        let (tabstops, transforms) = self.buffer.update(cx, |buffer, cx| {
             let snippet_text: Arc<str> = snippet.text.clone().into();
             buffer.edit(
                 insertion_ranges
@@ -5109,30 +5390,32 @@ impl Editor {
 
             let snapshot = &*buffer.read(cx);
             let snippet = &snippet;
-            snippet
+            // Maps a range of the snippet to its ranges in each of the insertions.
+            let anchor_ranges = |snippet_range: &Range<isize>| {
+                let mut delta = 0_isize;
+                insertion_ranges
+                    .iter()
+                    .map(|insertion_range| {
+                        let insertion_start = insertion_range.start as isize + delta;
+                        delta += snippet.text.len() as isize - insertion_range.len() as isize;
+
+                        let start =
+                            ((insertion_start + snippet_range.start) as usize).min(snapshot.len());
+                        let end =
+                            ((insertion_start + snippet_range.end) as usize).min(snapshot.len());
+                        snapshot.anchor_before(start)..snapshot.anchor_after(end)
+                    })
+                    .collect::<Vec<_>>()
+            };
+            let tabstops = snippet
                 .tabstops
                 .iter()
                 .map(|tabstop| {
                     let is_end_tabstop = tabstop.first().map_or(false, |tabstop| {
                         tabstop.is_empty() && tabstop.start == snippet.text.len() as isize
                     });
-                    let mut tabstop_ranges = tabstop
-                        .iter()
-                        .flat_map(|tabstop_range| {
-                            let mut delta = 0_isize;
-                            insertion_ranges.iter().map(move |insertion_range| {
-                                let insertion_start = insertion_range.start as isize + delta;
-                                delta +=
-                                    snippet.text.len() as isize - insertion_range.len() as isize;
-
-                                let start = ((insertion_start + tabstop_range.start) as usize)
-                                    .min(snapshot.len());
-                                let end = ((insertion_start + tabstop_range.end) as usize)
-                                    .min(snapshot.len());
-                                snapshot.anchor_before(start)..snapshot.anchor_after(end)
-                            })
-                        })
-                        .collect::<Vec<_>>();
+                    let mut tabstop_ranges =
+                        tabstop.iter().flat_map(anchor_ranges).collect::<Vec<_>>();
                     tabstop_ranges.sort_unstable_by(|a, b| a.start.cmp(&b.start, snapshot));
 
                     Tabstop {
@@ -5140,7 +5423,17 @@ impl Editor {
                         ranges: tabstop_ranges,
                     }
                 })
-                .collect::<Vec<_>>()
+                .collect::<Vec<_>>();
+            let transforms = snippet
+                .transforms
+                .iter()
+                .map(|transform| SnippetTransformState {
+                    tabstop: transform.tabstop,
+                    ranges: anchor_ranges(&transform.range),
+                    transform: transform.transform.clone(),
+                })
+                .collect::<Vec<_>>();
+            (tabstops, transforms)
         });
 
         if let Some(tabstop) = tabstops.first() {
@@ -5155,10 +5448,14 @@ impl Editor {
                     .into_iter()
                     .map(|tabstop| tabstop.ranges)
                     .collect::<Vec<_>>();
-                self.snippet_stack.push(SnippetState {
+                let snippet = SnippetState {
                     active_index: 0,
                     ranges,
-                });
+                    choices: snippet.choices,
+                    transforms,
+                };
+                snippets::show_snippet_choices(self, &snippet, cx);
+                self.snippet_stack.push(snippet);
             }
 
             // Check whether the just-entered snippet ends with an auto-closable bracket.
@@ -5210,6 +5507,7 @@ impl Editor {
 
     pub fn move_to_snippet_tabstop(&mut self, bias: Bias, cx: &mut ViewContext<Self>) -> bool {
         if let Some(mut snippet) = self.snippet_stack.pop() {
+            snippets::apply_snippet_transforms(self, &snippet, cx);
             match bias {
                 Bias::Left => {
                     if snippet.active_index > 0 {
@@ -5232,6 +5530,7 @@ impl Editor {
                 self.change_selections(Some(Autoscroll::fit()), cx, |s| {
                     s.select_anchor_ranges(current_ranges.iter().cloned())
                 });
+                snippets::show_snippet_choices(self, &snippet, cx);
                 // If snippet state is not at the last tabstop, push it back on the stack
                 if snippet.active_index + 1 < snippet.ranges.len() {
                     self.snippet_stack.push(snippet);
@@ -8971,10 +9270,24 @@ impl Editor {
                 HoverLink::InlayHint(lsp_location, server_id) => {
                     self.compute_target_location(lsp_location, server_id, cx)
                 }
+                HoverLink::InlayHintCommand(command, server_id, buffer_id) => {
+                    self.execute_inlay_hint_command(command, server_id, buffer_id, cx);
+                    return Task::ready(Ok(true));
+                }
                 HoverLink::Url(url) => {
                     cx.open_url(&url);
                     Task::ready(Ok(None))
                 }
+                HoverLink::File(path) => {
+                    if let Some(workspace) = self.workspace() {
+                        workspace.update(cx, |workspace, cx| {
+                            workspace
+                                .open_abs_path(path, true, cx)
+                                .detach_and_log_err(cx);
+                        });
+                    }
+                    Task::ready(Ok(None))
+                }
             };
             cx.spawn(|editor, mut cx| async move {
                 let target = target_task.await.context("target resolution task")?;
@@ -9066,8 +9379,9 @@ impl Editor {
                                             .collect::<String>()
                                     )
                                 }),
-                                HoverLink::InlayHint(_, _) => None,
-                                HoverLink::Url(_) => None,
+                                HoverLink::InlayHint(_, _)
+                                | HoverLink::InlayHintCommand(_, _, _) => None,
+                                HoverLink::Url(_) | HoverLink::File(_) => None,
                             })
                             .unwrap_or(tab_kind.to_string());
                         let location_tasks = definitions
@@ -9077,7 +9391,9 @@ impl Editor {
                                 HoverLink::InlayHint(lsp_location, server_id) => {
                                     editor.compute_target_location(lsp_location, server_id, cx)
                                 }
-                                HoverLink::Url(_) => Task::ready(Ok(None)),
+                                HoverLink::InlayHintCommand(_, _, _)
+                                | HoverLink::Url(_)
+                                | HoverLink::File(_) => Task::ready(Ok(None)),
                             })
                             .collect::<Vec<_>>();
                         (title, location_tasks, editor.workspace().clone())
@@ -9198,7 +9514,9 @@ impl Editor {
         let replica_id = self.replica_id(cx);
         let workspace = self.workspace()?;
         let project = workspace.read(cx).project().clone();
-        let references = project.update(cx, |project, cx| project.references(&buffer, head, cx));
+        let mut references = project.update(cx, |project, cx| {
+            project.references_with_partial_results(&buffer, head, cx)
+        });
         Some(cx.spawn(|editor, mut cx| async move {
             let _cleanup = defer({
                 let mut cx = cx.clone();
@@ -9217,73 +9535,88 @@ impl Editor {
                 }
             });
 
-            let locations = references.await?;
-            if locations.is_empty() {
-                return anyhow::Ok(());
-            }
-
-            workspace.update(&mut cx, |workspace, cx| {
-                let title = locations
-                    .first()
-                    .as_ref()
-                    .map(|location| {
-                        let buffer = location.buffer.read(cx);
-                        format!(
-                            "References to `{}`",
-                            buffer
-                                .text_for_range(location.range.clone())
-                                .collect::<String>()
-                        )
-                    })
-                    .unwrap();
-                Self::open_locations_in_multibuffer(
-                    workspace, locations, replica_id, title, false, cx,
-                );
-            })
+            // The references are shown as soon as the language server reports the first ones, and the ones it
+            // finds afterwards are added to them.
+            let mut references_editor = None;
+            while let Some(locations) = references.partial.next().await {
+                references_editor = Self::show_references(
+                    &workspace,
+                    references_editor,
+                    locations,
+                    replica_id,
+                    &mut cx,
+                )?;
+            }
+            let locations = references.rest.await?;
+            Self::show_references(
+                &workspace,
+                references_editor,
+                locations,
+                replica_id,
+                &mut cx,
+            )?;
+            anyhow::Ok(())
         }))
     }
 
+    /// Opens the references in a multibuffer, or adds them to the one opened for the previous ones, if any.
+    fn show_references(
+        workspace: &View<Workspace>,
+        references_editor: Option<WeakView<Editor>>,
+        locations: Vec<Location>,
+        replica_id: ReplicaId,
+        cx: &mut AsyncWindowContext,
+    ) -> Result<Option<WeakView<Editor>>> {
+        if locations.is_empty() {
+            return Ok(references_editor);
+        }
+        if let Some(references_editor) = references_editor {
+            // The references aren't added anymore once their multibuffer is closed.
+            references_editor
+                .update(cx, |editor, cx| {
+                    editor.add_locations_to_multibuffer(locations, cx)
+                })
+                .ok();
+            return Ok(Some(references_editor));
+        }
+
+        workspace.update(cx, |workspace, cx| {
+            let title = locations
+                .first()
+                .as_ref()
+                .map(|location| {
+                    let buffer = location.buffer.read(cx);
+                    format!(
+                        "References to `{}`",
+                        buffer
+                            .text_for_range(location.range.clone())
+                            .collect::<String>()
+                    )
+                })
+                .unwrap();
+            let editor = Self::open_locations_in_multibuffer(
+                workspace, locations, replica_id, title, false, cx,
+            );
+            Some(editor.downgrade())
+        })
+    }
+
     /// Opens a multibuffer with the given project locations in it
     pub fn open_locations_in_multibuffer(
         workspace: &mut Workspace,
-        mut locations: Vec<Location>,
+        locations: Vec<Location>,
         replica_id: ReplicaId,
         title: String,
         split: bool,
         cx: &mut ViewContext<Workspace>,
-    ) {
+    ) -> View<Editor> {
         // If there are multiple definitions, open them in a multibuffer
-        locations.sort_by_key(|location| location.buffer.read(cx).remote_id());
-        let mut locations = locations.into_iter().peekable();
         let mut ranges_to_highlight = Vec::new();
         let capability = workspace.project().read(cx).capability();
 
         let excerpt_buffer = cx.new_model(|cx| {
             let mut multibuffer = MultiBuffer::new(replica_id, capability);
-            while let Some(location) = locations.next() {
-                let buffer = location.buffer.read(cx);
-                let mut ranges_for_buffer = Vec::new();
-                let range = location.range.to_offset(buffer);
-                ranges_for_buffer.push(range.clone());
-
-                while let Some(next_location) = locations.peek() {
-                    if next_location.buffer == location.buffer {
-                        ranges_for_buffer.push(next_location.range.to_offset(buffer));
-                        locations.next();
-                    } else {
-                        break;
-                    }
-                }
-
-                ranges_for_buffer.sort_by_key(|range| (range.start, Reverse(range.end)));
-                ranges_to_highlight.extend(multibuffer.push_excerpts_with_context_lines(
-                    location.buffer.clone(),
-                    ranges_for_buffer,
-                    DEFAULT_MULTIBUFFER_CONTEXT,
-                    cx,
-                ))
-            }
-
+            ranges_to_highlight = push_locations_to_multibuffer(&mut multibuffer, locations, cx);
             multibuffer.with_title(title)
         });
 
@@ -9322,6 +9655,29 @@ impl Editor {
         workspace.active_pane().update(cx, |pane, cx| {
             pane.set_preview_item_id(Some(item_id), cx);
         });
+        editor
+    }
+
+    /// Adds more locations to the multibuffer opened with [`Editor::open_locations_in_multibuffer`], highlighting
+    /// them along with the ones it already has.
+    fn add_locations_to_multibuffer(
+        &mut self,
+        locations: Vec<Location>,
+        cx: &mut ViewContext<Self>,
+    ) {
+        let new_ranges = self.buffer.update(cx, |multibuffer, cx| {
+            push_locations_to_multibuffer(multibuffer, locations, cx)
+        });
+        let mut ranges_to_highlight = self
+            .background_highlights
+            .get(&TypeId::of::<Self>())
+            .map_or(Vec::new(), |(_, ranges)| ranges.to_vec());
+        ranges_to_highlight.extend(new_ranges);
+        self.highlight_background::<Self>(
+            &ranges_to_highlight,
+            |theme| theme.editor_highlighted_line_background,
+            cx,
+        );
     }
 
     pub fn rename(&mut self, _: &Rename, cx: &mut ViewContext<Self>) -> Option<Task<Result<()>>> {
@@ -9509,31 +9865,15 @@ impl Editor {
         _: &ConfirmRename,
         cx: &mut ViewContext<Self>,
     ) -> Option<Task<Result<()>>> {
-        let rename = self.take_rename(false, cx)?;
+        let (buffer, position, old_name, new_name) = self.finish_rename(cx)?;
         let workspace = self.workspace()?;
-        let (start_buffer, start) = self
-            .buffer
-            .read(cx)
-            .text_anchor_for_position(rename.range.start, cx)?;
-        let (end_buffer, end) = self
-            .buffer
-            .read(cx)
-            .text_anchor_for_position(rename.range.end, cx)?;
-        if start_buffer != end_buffer {
-            return None;
-        }
-
-        let buffer = start_buffer;
-        let range = start..end;
-        let old_name = rename.old_name;
-        let new_name = rename.editor.read(cx).text(cx);
 
         let rename = workspace
             .read(cx)
             .project()
             .clone()
             .update(cx, |project, cx| {
-                project.perform_rename(buffer.clone(), range.start, new_name.clone(), true, cx)
+                project.perform_rename(buffer.clone(), position, new_name.clone(), true, cx)
             });
         let workspace = workspace.downgrade();
 
@@ -9555,6 +9895,27 @@ impl Editor {
         }))
     }
 
+    /// Ends the pending rename, returning the buffer and position of the renamed symbol, with its old and new names.
+    fn finish_rename(
+        &mut self,
+        cx: &mut ViewContext<Self>,
+    ) -> Option<(Model<Buffer>, text::Anchor, Arc<str>, String)> {
+        let rename = self.take_rename(false, cx)?;
+        let (start_buffer, start) = self
+            .buffer
+            .read(cx)
+            .text_anchor_for_position(rename.range.start, cx)?;
+        let (end_buffer, _) = self
+            .buffer
+            .read(cx)
+            .text_anchor_for_position(rename.range.end, cx)?;
+        if start_buffer != end_buffer {
+            return None;
+        }
+        let new_name = rename.editor.read(cx).text(cx);
+        Some((start_buffer, start, rename.old_name, new_name))
+    }
+
     fn take_rename(
         &mut self,
         moving_cursor: bool,
@@ -9659,6 +10020,30 @@ impl Editor {
         }
     }
 
+    fn execute_language_server_command(
+        &mut self,
+        action: &ExecuteLanguageServerCommand,
+        cx: &mut ViewContext<Self>,
+    ) {
+        let Some(project) = self.project.clone() else {
+            return;
+        };
+        let head = self.selections.newest_anchor().head();
+        let Some((buffer, _)) = self.buffer.read(cx).text_anchor_for_position(head, cx) else {
+            return;
+        };
+        let command = ExecuteLspCommand {
+            command: action.command.clone(),
+            arguments: action.arguments.clone(),
+            server_id: None,
+        };
+        project
+            .update(cx, |project, cx| {
+                project.execute_lsp_command(&buffer, command, cx)
+            })
+            .detach_and_log_err(cx);
+    }
+
     fn cancel_language_server_work(
         &mut self,
         _: &CancelLanguageServerWork,
@@ -9883,6 +10268,19 @@ impl Editor {
         self.fold_ranges(fold_ranges, true, cx);
     }
 
+    /// Folds the imports of the buffers, as reported by their language servers.
+    pub fn fold_imports(&mut self, _: &FoldImports, cx: &mut ViewContext<Self>) {
+        let display_map = self.display_map.update(cx, |map, cx| map.snapshot(cx));
+        let buffer = &display_map.buffer_snapshot;
+        let fold_ranges = self
+            .import_folding_ranges
+            .iter()
+            .filter(|range| range.start.is_valid(buffer))
+            .map(|range| (range.to_point(buffer), display_map.fold_placeholder.clone()))
+            .collect::<Vec<_>>();
+        self.fold_ranges(fold_ranges, true, cx);
+    }
+
     pub fn fold_at(&mut self, fold_at: &FoldAt, cx: &mut ViewContext<Self>) {
         let buffer_row = fold_at.buffer_row;
         let display_map = self.display_map.update(cx, |map, cx| map.snapshot(cx));
@@ -11007,6 +11405,21 @@ impl Editor {
         cx.notify();
     }
 
+    /// Like [`Self::highlight_text`], but allows a single highlight type to own several
+    /// differently styled sets of ranges, told apart by the `key`.
+    pub fn highlight_text_key<T: 'static>(
+        &mut self,
+        key: usize,
+        ranges: Vec<Range<Anchor>>,
+        style: HighlightStyle,
+        cx: &mut ViewContext<Self>,
+    ) {
+        self.display_map.update(cx, |map, _| {
+            map.highlight_text_key(TypeId::of::<T>(), key, ranges, style)
+        });
+        cx.notify();
+    }
+
     pub(crate) fn highlight_inlays<T: 'static>(
         &mut self,
         highlights: Vec<InlayHighlight>,
@@ -11066,6 +11479,8 @@ impl Editor {
                 if self.has_active_inline_completion(cx) {
                     self.update_visible_inline_completion(cx);
                 }
+                lsp_decorations::refresh_lsp_decorations(self, true, cx);
+                decorations::invalidate_edited_decorations(self, cx);
                 cx.emit(EditorEvent::BufferEdited);
                 cx.emit(SearchEvent::MatchesInvalidated);
                 if *singleton_buffer_edited {
@@ -11114,6 +11529,7 @@ impl Editor {
                     excerpts: excerpts.clone(),
                 });
                 self.refresh_inlay_hints(InlayHintRefreshReason::NewLinesShown, cx);
+                lsp_decorations::refresh_lsp_decorations(self, false, cx);
             }
             multi_buffer::Event::ExcerptsRemoved { ids } => {
                 self.refresh_inlay_hints(InlayHintRefreshReason::ExcerptsRemoved(ids.clone()), cx);
@@ -11132,6 +11548,7 @@ impl Editor {
             }
             multi_buffer::Event::LanguageChanged(buffer_id) => {
                 linked_editing_ranges::refresh_linked_ranges(self, cx);
+                lsp_decorations::refresh_lsp_decorations(self, false, cx);
                 cx.emit(EditorEvent::Reparsed(*buffer_id));
                 cx.notify();
             }
@@ -11177,6 +11594,8 @@ impl Editor {
         let editor_settings = EditorSettings::get_global(cx);
         self.scroll_manager.vertical_scroll_margin = editor_settings.vertical_scroll_margin;
         self.show_breadcrumbs = editor_settings.toolbar.breadcrumbs;
+        // Once the breadcrumbs setting is updated, since it decides whether their symbols are requested.
+        lsp_decorations::refresh_lsp_decorations(self, false, cx);
 
         if self.mode == EditorMode::Full {
             let inline_blame_enabled = ProjectSettings::get_global(cx).git.inline_blame_enabled();
@@ -11583,6 +12002,7 @@ impl Editor {
         }
         self.hide_context_menu(cx);
         hide_hover(self, cx);
+        signature_help::hide_signature_help(self, cx);
         cx.emit(EditorEvent::Blurred);
         cx.notify();
     }
@@ -11708,7 +12128,7 @@ pub trait CompletionProvider {
         buffer_position: text::Anchor,
         trigger: CompletionContext,
         cx: &mut ViewContext<Editor>,
-    ) -> Task<Result<Vec<Completion>>>;
+    ) -> Task<Result<CompletionResponse>>;
 
     fn resolve_completions(
         &self,
@@ -11743,7 +12163,7 @@ impl CompletionProvider for Model<Project> {
         buffer_position: text::Anchor,
         options: CompletionContext,
         cx: &mut ViewContext<Editor>,
-    ) -> Task<Result<Vec<Completion>>> {
+    ) -> Task<Result<CompletionResponse>> {
         self.update(cx, |project, cx| {
             project.completions(&buffer, buffer_position, options, cx)
         })
@@ -11821,6 +12241,42 @@ fn inlay_hint_settings(
         .inlay_hints
 }
 
+/// Adds excerpts for the locations to the multibuffer, the locations of a buffer sharing them, returning the ranges
+/// of the locations in the multibuffer.
+fn push_locations_to_multibuffer(
+    multibuffer: &mut MultiBuffer,
+    mut locations: Vec<Location>,
+    cx: &mut ModelContext<MultiBuffer>,
+) -> Vec<Range<Anchor>> {
+    locations.sort_by_key(|location| location.buffer.read(cx).remote_id());
+    let mut locations = locations.into_iter().peekable();
+    let mut ranges = Vec::new();
+    while let Some(location) = locations.next() {
+        let buffer = location.buffer.read(cx);
+        let mut ranges_for_buffer = Vec::new();
+        let range = location.range.to_offset(buffer);
+        ranges_for_buffer.push(range.clone());
+
+        while let Some(next_location) = locations.peek() {
+            if next_location.buffer == location.buffer {
+                ranges_for_buffer.push(next_location.range.to_offset(buffer));
+                locations.next();
+            } else {
+                break;
+            }
+        }
+
+        ranges_for_buffer.sort_by_key(|range| (range.start, Reverse(range.end)));
+        ranges.extend(multibuffer.push_excerpts_with_context_lines(
+            location.buffer.clone(),
+            ranges_for_buffer,
+            DEFAULT_MULTIBUFFER_CONTEXT,
+            cx,
+        ))
+    }
+    ranges
+}
+
 fn consume_contiguous_rows(
     contiguous_row_selections: &mut Vec<Selection<Point>>,
     selection: &Selection<Point>,
@@ -12001,7 +12457,9 @@ impl EditorSnapshot {
                 cx,
             ))
         } else if folded
-            || (self.starts_indent(buffer_row) && (row_contains_cursor || self.gutter_hovered))
+            || ((self.starts_indent(buffer_row)
+                || self.folding_range_for_row(buffer_row).is_some())
+                && (row_contains_cursor || self.gutter_hovered))
         {
             Some(
                 Disclosure::new(("indent-fold-indicator", buffer_row.0), !folded)
@@ -12069,6 +12527,8 @@ pub enum EditorEvent {
         transaction_id: clock::Lamport,
     },
     Reparsed(BufferId),
+    /// The document symbols shown by the breadcrumbs changed.
+    BreadcrumbsChanged,
     Focused,
     Blurred,
     DirtyChanged,
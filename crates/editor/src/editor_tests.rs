@@ -6072,6 +6072,36 @@ async fn test_snippets(cx: &mut gpui::TestAppContext) {
     });
 }
 
+#[gpui::test]
+async fn test_snippet_choices_and_transforms(cx: &mut gpui::TestAppContext) {
+    init_test(cx, |_| {});
+
+    let (text, insertion_ranges) = marked_text_ranges("let ˇ;", false);
+    let buffer = cx.update(|cx| MultiBuffer::build_simple(&text, cx));
+    let (editor, cx) = cx.add_window_view(|cx| build_editor(buffer, cx));
+
+    _ = editor.update(cx, |editor, cx| {
+        let snippet = Snippet::parse("${1|one,two|} = ${1/(.*)/${1:/upcase}/}$0").unwrap();
+        editor
+            .insert_snippet(&insertion_ranges, snippet, cx)
+            .unwrap();
+        assert_eq!(editor.text(cx), "let one = ONE;");
+        assert_eq!(editor.selections.ranges::<usize>(cx), vec![4..7]);
+
+        // The choices of the tabstop are offered when it's entered.
+        assert!(editor.context_menu_visible());
+        editor
+            .confirm_completion(&ConfirmCompletion { item_ix: Some(1) }, cx)
+            .unwrap()
+            .detach();
+        assert_eq!(editor.text(cx), "let two = ONE;");
+
+        // The transforms of the tabstop are applied when it's left.
+        assert!(editor.move_to_next_snippet_tabstop(cx));
+        assert_eq!(editor.text(cx), "let two = TWO;");
+    });
+}
+
 #[gpui::test]
 async fn test_document_format_during_save(cx: &mut gpui::TestAppContext) {
     init_test(cx, |_| {});
@@ -6553,6 +6583,80 @@ async fn test_range_format_during_save(cx: &mut gpui::TestAppContext) {
     save.await;
 }
 
+#[gpui::test]
+async fn test_format_changed_lines_only_during_save(cx: &mut gpui::TestAppContext) {
+    init_test(cx, |settings| {
+        settings.defaults.formatter = Some(language_settings::Formatter::LanguageServer);
+        settings.defaults.format_changed_lines_only = Some(true);
+    });
+
+    let fs = FakeFs::new(cx.executor());
+    fs.insert_file("/file.rs", "one\ntwo\nthree\nfour\n".into())
+        .await;
+
+    let project = Project::test(fs, ["/file.rs".as_ref()], cx).await;
+
+    let language_registry = project.read_with(cx, |project, _| project.languages().clone());
+    language_registry.add(rust_lang());
+    let mut fake_servers = language_registry.register_fake_lsp_adapter(
+        "Rust",
+        FakeLspAdapter {
+            capabilities: lsp::ServerCapabilities {
+                document_formatting_provider: Some(lsp::OneOf::Left(true)),
+                document_range_formatting_provider: Some(lsp::OneOf::Left(true)),
+                ..Default::default()
+            },
+            ..Default::default()
+        },
+    );
+
+    let buffer = project
+        .update(cx, |project, cx| project.open_local_buffer("/file.rs", cx))
+        .await
+        .unwrap();
+    buffer.update(cx, |buffer, cx| {
+        buffer.set_diff_base(Some("one\ntwo\nthree\nfour\n".into()), cx)
+    });
+
+    cx.executor().start_waiting();
+    let fake_server = fake_servers.next().await.unwrap();
+
+    let multi_buffer = cx.new_model(|cx| MultiBuffer::singleton(buffer.clone(), cx));
+    let (editor, cx) = cx.add_window_view(|cx| build_editor(multi_buffer, cx));
+    buffer.update(cx, |buffer, cx| {
+        buffer.edit([(Point::new(2, 0)..Point::new(2, 5), "three;")], None, cx)
+    });
+    assert!(cx.read(|cx| editor.is_dirty(cx)));
+
+    let _pending_format_request = fake_server
+        .handle_request::<lsp::request::Formatting, _, _>(move |_, _| async move {
+            panic!("The whole file should not be formatted");
+        })
+        .next();
+    let save = editor
+        .update(cx, |editor, cx| editor.save(true, project.clone(), cx))
+        .unwrap();
+    fake_server
+        .handle_request::<lsp::request::RangeFormatting, _, _>(move |params, _| async move {
+            assert_eq!(
+                params.range,
+                lsp::Range::new(lsp::Position::new(2, 0), lsp::Position::new(2, 6))
+            );
+            Ok(Some(vec![lsp::TextEdit::new(
+                lsp::Range::new(lsp::Position::new(2, 5), lsp::Position::new(2, 6)),
+                "".to_string(),
+            )]))
+        })
+        .next()
+        .await;
+    cx.executor().start_waiting();
+    save.await;
+    assert_eq!(
+        editor.update(cx, |editor, cx| editor.text(cx)),
+        "one\ntwo\nthree\nfour\n"
+    );
+}
+
 #[gpui::test]
 async fn test_document_format_manual_trigger(cx: &mut gpui::TestAppContext) {
     init_test(cx, |settings| {
@@ -6948,22 +7052,14 @@ async fn test_completion(cx: &mut gpui::TestAppContext) {
     assert_eq!(counter.load(atomic::Ordering::Acquire), 2);
 
     cx.simulate_keystroke("i");
-
-    handle_completion_request(
-        &mut cx,
-        indoc! {"
-            one.second_completion
-            two si
-            three <si|>
-            additional edit
-        "},
-        vec!["fourth_completion", "fifth_completion", "sixth_completion"],
-        counter.clone(),
-    )
-    .await;
+    cx.executor().run_until_parked();
     cx.condition(|editor, _| editor.context_menu_visible())
         .await;
-    assert_eq!(counter.load(atomic::Ordering::Acquire), 3);
+    assert_eq!(
+        counter.load(atomic::Ordering::Acquire),
+        2,
+        "A complete list of completions should be filtered rather than requested again"
+    );
 
     let apply_additional_edits = cx.update_editor(|editor, cx| {
         editor
@@ -7007,7 +7103,7 @@ async fn test_completion(cx: &mut gpui::TestAppContext) {
     .await;
     cx.condition(|editor, _| editor.context_menu_visible())
         .await;
-    assert_eq!(counter.load(atomic::Ordering::Acquire), 4);
+    assert_eq!(counter.load(atomic::Ordering::Acquire), 3);
 
     let apply_additional_edits = cx.update_editor(|editor, cx| {
         editor
@@ -7086,9 +7182,11 @@ async fn test_no_duplicated_completion_requests(cx: &mut gpui::TestAppContext) {
         let task_completion_item = closure_completion_item.clone();
         counter_clone.fetch_add(1, atomic::Ordering::Release);
         async move {
-            Ok(Some(lsp::CompletionResponse::Array(vec![
-                task_completion_item,
-            ])))
+            Ok(Some(lsp::CompletionResponse::List(lsp::CompletionList {
+                is_incomplete: true,
+                items: vec![task_completion_item],
+                ..Default::default()
+            })))
         }
     });
 
@@ -7936,6 +8034,205 @@ fn test_highlighted_ranges(cx: &mut TestAppContext) {
     });
 }
 
+#[gpui::test]
+fn test_decorations(cx: &mut TestAppContext) {
+    init_test(cx, |_| {});
+
+    let editor = cx.add_window(|cx| {
+        let buffer = MultiBuffer::build_simple("one\ntwo\nthree\nfour\n", cx);
+        build_editor(buffer.clone(), cx)
+    });
+
+    enum Coverage {}
+    enum Profiler {}
+
+    fn line_highlights(
+        editor: &mut Editor,
+        cx: &mut ViewContext<Editor>,
+    ) -> BTreeMap<DisplayRow, Hsla> {
+        let snapshot = editor.snapshot(cx);
+        let rows = DisplayRow(0)..snapshot.max_point().row().next_row();
+        editor.decoration_line_highlights(rows, &snapshot.display_snapshot)
+    }
+
+    _ = editor.update(cx, |editor, cx| {
+        let buffer = editor.buffer.read(cx).snapshot(cx);
+        let anchor_range =
+            |range: Range<Point>| buffer.anchor_after(range.start)..buffer.anchor_before(range.end);
+        let decoration = |range, kind, z_index, invalidation| Decoration {
+            range: anchor_range(range),
+            kind,
+            z_index,
+            invalidation,
+        };
+
+        editor.set_decorations::<Coverage>(
+            vec![
+                decoration(
+                    Point::new(0, 0)..Point::new(1, 3),
+                    DecorationKind::LineHighlight(Hsla::red()),
+                    0,
+                    DecorationInvalidation::Never,
+                ),
+                decoration(
+                    Point::new(2, 0)..Point::new(2, 5),
+                    DecorationKind::LineHighlight(Hsla::red()),
+                    0,
+                    DecorationInvalidation::OnEdit,
+                ),
+                decoration(
+                    Point::new(0, 3)..Point::new(0, 3),
+                    DecorationKind::InlineHint(" 3 hits".into()),
+                    0,
+                    DecorationInvalidation::Never,
+                ),
+            ],
+            cx,
+        );
+        editor.set_decorations::<Profiler>(
+            vec![
+                decoration(
+                    Point::new(0, 0)..Point::new(0, 3),
+                    DecorationKind::LineHighlight(Hsla::blue()),
+                    -1,
+                    DecorationInvalidation::Never,
+                ),
+                decoration(
+                    Point::new(1, 0)..Point::new(1, 3),
+                    DecorationKind::LineHighlight(Hsla::green()),
+                    1,
+                    DecorationInvalidation::Never,
+                ),
+            ],
+            cx,
+        );
+
+        assert_eq!(editor.display_text(cx), "one 3 hits\ntwo\nthree\nfour\n");
+        assert_eq!(
+            line_highlights(editor, cx),
+            BTreeMap::from_iter([
+                (DisplayRow(0), Hsla::red()),
+                (DisplayRow(1), Hsla::green()),
+                (DisplayRow(2), Hsla::red()),
+            ])
+        );
+        let snapshot = editor.snapshot(cx);
+        assert_eq!(
+            editor.decoration_line_highlights(
+                DisplayRow(1)..DisplayRow(2),
+                &snapshot.display_snapshot
+            ),
+            BTreeMap::from_iter([(DisplayRow(1), Hsla::green())])
+        );
+    });
+
+    // Editing the range of a decoration invalidated on edit removes it.
+    _ = editor.update(cx, |editor, cx| {
+        editor.change_selections(None, cx, |s| {
+            s.select_ranges([Point::new(2, 5)..Point::new(2, 5)])
+        });
+        editor.insert("!", cx);
+    });
+    _ = editor.update(cx, |editor, cx| {
+        assert_eq!(editor.decorations::<Coverage>().count(), 2);
+        assert_eq!(
+            line_highlights(editor, cx),
+            BTreeMap::from_iter([(DisplayRow(0), Hsla::red()), (DisplayRow(1), Hsla::green()),])
+        );
+    });
+
+    // The other decorations move with the edits.
+    _ = editor.update(cx, |editor, cx| {
+        editor.change_selections(None, cx, |s| {
+            s.select_ranges([Point::new(0, 0)..Point::new(0, 0)])
+        });
+        editor.insert("zero\n", cx);
+    });
+    _ = editor.update(cx, |editor, cx| {
+        assert_eq!(
+            editor.display_text(cx),
+            "zero\none 3 hits\ntwo\nthree!\nfour\n"
+        );
+        assert_eq!(
+            line_highlights(editor, cx),
+            BTreeMap::from_iter([(DisplayRow(1), Hsla::red()), (DisplayRow(2), Hsla::green()),])
+        );
+
+        editor.clear_decorations::<Coverage>(cx);
+        assert_eq!(editor.display_text(cx), "zero\none\ntwo\nthree!\nfour\n");
+        assert_eq!(
+            line_highlights(editor, cx),
+            BTreeMap::from_iter([
+                (DisplayRow(1), Hsla::blue()),
+                (DisplayRow(2), Hsla::green()),
+            ])
+        );
+    });
+}
+
+#[gpui::test]
+async fn test_inline_values(cx: &mut gpui::TestAppContext) {
+    init_test(cx, |_| {});
+
+    let mut cx = EditorLspTestContext::new_rust(
+        lsp::ServerCapabilities {
+            inline_value_provider: Some(lsp::OneOf::Left(true)),
+            ..Default::default()
+        },
+        cx,
+    )
+    .await;
+    cx.set_state(indoc! {"
+        let a = 1;
+        let b = a + 1;ˇ
+    "});
+
+    let mut requests =
+        cx.handle_request::<lsp::request::InlineValueRequest, _, _>(|_, params, _| async move {
+            assert_eq!(params.context.frame_id, 7);
+            assert_eq!(
+                params.context.stopped_location,
+                lsp::Range::new(lsp::Position::new(1, 0), lsp::Position::new(1, 14))
+            );
+            let text = |row, column, text: &str| {
+                lsp::InlineValue::Text(lsp::InlineValueText {
+                    range: lsp::Range::new(
+                        lsp::Position::new(row, column),
+                        lsp::Position::new(row, column + 1),
+                    ),
+                    text: text.to_string(),
+                })
+            };
+            Ok(Some(vec![
+                text(0, 4, "a = 1"),
+                text(1, 4, "b = 2"),
+                text(1, 8, "a = 1"),
+                // Only the debugger can look up the variables.
+                lsp::InlineValue::VariableLookup(lsp::InlineValueVariableLookup {
+                    range: lsp::Range::new(lsp::Position::new(0, 4), lsp::Position::new(0, 5)),
+                    variable_name: None,
+                    case_sensitive_lookup: true,
+                }),
+            ]))
+        });
+    cx.update_editor(|editor, cx| {
+        let snapshot = editor.buffer().read(cx).snapshot(cx);
+        let stopped_location =
+            snapshot.anchor_before(Point::new(1, 0))..snapshot.anchor_after(Point::new(1, 14));
+        editor.show_inline_values(7, stopped_location, cx);
+    });
+    requests.next().await;
+    cx.run_until_parked();
+    cx.update_editor(|editor, cx| {
+        assert_eq!(
+            editor.display_text(cx),
+            "let a = 1; a = 1\nlet b = a + 1; b = 2, a = 1\n"
+        );
+        editor.hide_inline_values(cx);
+        assert_eq!(editor.display_text(cx), "let a = 1;\nlet b = a + 1;\n");
+    });
+}
+
 #[gpui::test]
 async fn test_following(cx: &mut gpui::TestAppContext) {
     init_test(cx, |_| {});
@@ -8677,6 +8974,8 @@ async fn test_on_type_formatting_not_triggered(cx: &mut gpui::TestAppContext) {
         editor.handle_input("{", cx);
     });
 
+    cx.executor()
+        .advance_clock(ON_TYPE_FORMATTING_DEBOUNCE_TIMEOUT);
     cx.executor().run_until_parked();
 
     _ = buffer.update(cx, |buffer, _| {
@@ -8768,6 +9067,7 @@ async fn test_language_server_restart_due_to_settings_change(cx: &mut gpui::Test
             "Some other server name".into(),
             LspSettings {
                 binary: None,
+                roots: None,
                 settings: None,
                 initialization_options: Some(json!({
                     "some other init value": false
@@ -8787,6 +9087,7 @@ async fn test_language_server_restart_due_to_settings_change(cx: &mut gpui::Test
             language_server_name.into(),
             LspSettings {
                 binary: None,
+                roots: None,
                 settings: None,
                 initialization_options: Some(json!({
                     "anotherInitValue": false
@@ -8806,6 +9107,7 @@ async fn test_language_server_restart_due_to_settings_change(cx: &mut gpui::Test
             language_server_name.into(),
             LspSettings {
                 binary: None,
+                roots: None,
                 settings: None,
                 initialization_options: Some(json!({
                     "anotherInitValue": false
@@ -8825,6 +9127,7 @@ async fn test_language_server_restart_due_to_settings_change(cx: &mut gpui::Test
             language_server_name.into(),
             LspSettings {
                 binary: None,
+                roots: None,
                 settings: None,
                 initialization_options: None,
             },
@@ -1,6 +1,7 @@
 use crate::editor_settings::ScrollBeyondLastLine;
 use crate::{
     blame_entry_tooltip::{blame_entry_relative_timestamp, BlameEntryTooltip},
+    code_cells,
     display_map::{
         BlockContext, BlockStyle, DisplaySnapshot, HighlightedChunk, ToDisplayPoint, TransformBlock,
     },
@@ -160,6 +161,7 @@ impl EditorElement {
         });
 
         crate::rust_analyzer_ext::apply_related_actions(view, cx);
+        crate::clangd_ext::apply_related_actions(view, cx);
         register_action(view, cx, Editor::move_left);
         register_action(view, cx, Editor::move_right);
         register_action(view, cx, Editor::move_down);
@@ -313,11 +315,14 @@ impl EditorElement {
         });
         register_action(view, cx, Editor::open_url);
         register_action(view, cx, Editor::fold);
+        register_action(view, cx, Editor::fold_imports);
         register_action(view, cx, Editor::fold_at);
         register_action(view, cx, Editor::unfold_lines);
         register_action(view, cx, Editor::unfold_at);
         register_action(view, cx, Editor::fold_selected_ranges);
         register_action(view, cx, Editor::show_completions);
+        register_action(view, cx, Editor::show_signature_help);
+        register_action(view, cx, Editor::show_color_presentations);
         register_action(view, cx, Editor::toggle_code_actions);
         register_action(view, cx, Editor::open_excerpts);
         register_action(view, cx, Editor::open_excerpts_in_split);
@@ -327,6 +332,9 @@ impl EditorElement {
         register_action(view, cx, Editor::toggle_indent_guides);
         register_action(view, cx, Editor::toggle_inlay_hints);
         register_action(view, cx, hover_popover::hover);
+        register_action(view, cx, code_cells::run_code_cell);
+        register_action(view, cx, code_cells::clear_code_cell_outputs);
+        register_action(view, cx, code_cells::restart_kernels);
         register_action(view, cx, Editor::reveal_in_finder);
         register_action(view, cx, Editor::copy_path);
         register_action(view, cx, Editor::copy_relative_path);
@@ -345,6 +353,7 @@ impl EditorElement {
             }
         });
         register_action(view, cx, Editor::restart_language_server);
+        register_action(view, cx, Editor::execute_language_server_command);
         register_action(view, cx, Editor::cancel_language_server_work);
         register_action(view, cx, Editor::show_character_palette);
         register_action(view, cx, |editor, action, cx| {
@@ -361,6 +370,13 @@ impl EditorElement {
                 cx.propagate();
             }
         });
+        register_action(view, cx, |editor, action, cx| {
+            if let Some(task) = editor.fix_all_occurrences(action, cx) {
+                task.detach_and_log_err(cx);
+            } else {
+                cx.propagate();
+            }
+        });
         register_action(view, cx, |editor, action, cx| {
             if let Some(task) = editor.rename(action, cx) {
                 task.detach_and_log_err(cx);
@@ -375,6 +391,14 @@ impl EditorElement {
                 cx.propagate();
             }
         });
+        register_action(view, cx, |editor, action, cx| {
+            if let Some(task) = editor.confirm_rename_with_preview(action, cx) {
+                task.detach_and_log_err(cx);
+            } else {
+                cx.propagate();
+            }
+        });
+        register_action(view, cx, Editor::apply_rename_preview);
         register_action(view, cx, |editor, action, cx| {
             if let Some(task) = editor.find_all_references(action, cx) {
                 task.detach_and_log_err(cx);
@@ -687,11 +711,10 @@ impl EditorElement {
                     .anchor_before(point.to_offset(&position_map.snapshot, Bias::Left));
                 hover_at(editor, Some(anchor), cx);
                 Self::update_visible_cursor(editor, point, position_map, cx);
-            } else {
-                hover_at(editor, None, cx);
             }
         } else {
             editor.hide_hovered_link(cx);
+            editor.hover_state.inlay_hover_point = None;
             hover_at(editor, None, cx);
             if gutter_hovered {
                 cx.stop_propagation();
@@ -1607,6 +1630,57 @@ impl EditorElement {
         })
     }
 
+    /// Lays out the gutter icons of the decorations of the visible rows, except on the rows with a run or a
+    /// code actions indicator, which are shown instead.
+    #[allow(clippy::too_many_arguments)]
+    fn layout_decoration_indicators(
+        &self,
+        rows: Range<DisplayRow>,
+        line_height: Pixels,
+        scroll_pixel_position: gpui::Point<Pixels>,
+        gutter_dimensions: &GutterDimensions,
+        gutter_hitbox: &Hitbox,
+        snapshot: &EditorSnapshot,
+        show_runnables: bool,
+        code_actions_row: Option<DisplayRow>,
+        cx: &mut WindowContext,
+    ) -> Vec<AnyElement> {
+        let editor = self.editor.read(cx);
+        let mut icons = editor.decoration_gutter_icons(rows, &snapshot.display_snapshot);
+        if show_runnables {
+            for tasks in editor.tasks.values() {
+                let point = tasks.offset.0.to_point(&snapshot.buffer_snapshot);
+                icons.remove(&point.to_display_point(snapshot).row());
+            }
+        }
+        if let Some(row) = code_actions_row {
+            icons.remove(&row);
+        }
+
+        icons
+            .into_iter()
+            .map(|(row, icon)| {
+                let mut button =
+                    IconButton::new(("decoration_indicator", row.0 as usize), icon.icon)
+                        .shape(ui::IconButtonShape::Square)
+                        .icon_size(IconSize::XSmall)
+                        .icon_color(icon.color);
+                if let Some(tooltip) = icon.tooltip {
+                    button = button.tooltip(move |cx| Tooltip::text(tooltip.clone(), cx));
+                }
+                prepaint_gutter_button(
+                    button,
+                    row,
+                    line_height,
+                    gutter_dimensions,
+                    scroll_pixel_position,
+                    gutter_hitbox,
+                    cx,
+                )
+            })
+            .collect()
+    }
+
     fn layout_code_actions_indicator(
         &self,
         line_height: Pixels,
@@ -2629,6 +2703,58 @@ impl EditorElement {
         }
     }
 
+    /// Lays out the signature help above the newest cursor, or below it when there isn't enough space above.
+    #[allow(clippy::too_many_arguments)]
+    fn layout_signature_help(
+        &self,
+        hitbox: &Hitbox,
+        text_hitbox: &Hitbox,
+        content_origin: gpui::Point<Pixels>,
+        scroll_pixel_position: gpui::Point<Pixels>,
+        start_row: DisplayRow,
+        line_layouts: &[LineWithInvisibles],
+        newest_selection_head: DisplayPoint,
+        line_height: Pixels,
+        em_width: Pixels,
+        cx: &mut WindowContext,
+    ) {
+        let max_size = size(
+            (120. * em_width)
+                .min(hitbox.size.width / 2.)
+                .max(MIN_POPOVER_CHARACTER_WIDTH * em_width),
+            (8. * line_height)
+                .min(hitbox.size.height / 3.)
+                .max(MIN_POPOVER_LINE_HEIGHT * line_height),
+        );
+        let Some(mut signature_help) = self.editor.update(cx, |editor, cx| {
+            editor.render_signature_help(&self.style, max_size, cx)
+        }) else {
+            return;
+        };
+        let available_space = size(AvailableSpace::MinContent, AvailableSpace::MinContent);
+        let size = signature_help.layout_as_root(available_space, cx);
+
+        let cursor_row_layout =
+            &line_layouts[newest_selection_head.row().minus(start_row) as usize];
+        let x = cursor_row_layout.x_for_index(newest_selection_head.column() as usize)
+            - scroll_pixel_position.x;
+        let y = newest_selection_head.row().as_f32() * line_height - scroll_pixel_position.y;
+        let cursor_point = content_origin + point(x, y);
+        let horizontal_offset =
+            (text_hitbox.upper_right().x - (cursor_point.x + size.width)).min(Pixels::ZERO);
+        let above_y = cursor_point.y - HOVER_POPOVER_GAP - size.height;
+        let origin_y = if above_y >= text_hitbox.top() {
+            above_y
+        } else {
+            cursor_point.y + line_height + HOVER_POPOVER_GAP
+        };
+        cx.defer_draw(
+            signature_help,
+            point(cursor_point.x + horizontal_offset, origin_y),
+            2,
+        );
+    }
+
     fn paint_background(&self, layout: &EditorLayout, cx: &mut WindowContext) {
         cx.paint_layer(layout.hitbox.bounds, |cx| {
             let scroll_top = layout.position_map.snapshot.scroll_position().y;
@@ -3009,6 +3135,10 @@ impl EditorElement {
                 }
             });
 
+            for decoration_indicator in layout.decoration_indicators.iter_mut() {
+                decoration_indicator.paint(cx);
+            }
+
             for test_indicators in layout.test_indicators.iter_mut() {
                 test_indicators.paint(cx);
             }
@@ -4733,9 +4863,17 @@ impl Element for EditorElement {
                         )
                     };
 
-                    let highlighted_rows = self
+                    let mut highlighted_rows = self
                         .editor
                         .update(cx, |editor, cx| editor.highlighted_display_rows(cx));
+                    // The line highlights of decorations are shown beneath the row highlights.
+                    for (row, color) in self
+                        .editor
+                        .read(cx)
+                        .decoration_line_highlights(start_row..end_row, &snapshot.display_snapshot)
+                    {
+                        highlighted_rows.entry(row).or_insert(color);
+                    }
                     let highlighted_ranges = self.editor.read(cx).background_highlights_in_range(
                         start_anchor..end_anchor,
                         &snapshot.display_snapshot,
@@ -5058,6 +5196,21 @@ impl Element for EditorElement {
                         vec![]
                     };
 
+                    let decoration_indicators = self.layout_decoration_indicators(
+                        start_row..end_row,
+                        line_height,
+                        scroll_pixel_position,
+                        &gutter_dimensions,
+                        &gutter_hitbox,
+                        &snapshot,
+                        gutter_settings.runnables,
+                        code_actions_indicator
+                            .as_ref()
+                            .and(newest_selection_head)
+                            .map(|head| head.row()),
+                        cx,
+                    );
+
                     if !cx.has_active_drag() {
                         self.layout_hover_popovers(
                             &snapshot,
@@ -5073,6 +5226,23 @@ impl Element for EditorElement {
                         );
                     }
 
+                    if let Some(newest_selection_head) = newest_selection_head {
+                        if (start_row..end_row).contains(&newest_selection_head.row()) {
+                            self.layout_signature_help(
+                                &hitbox,
+                                &text_hitbox,
+                                content_origin,
+                                scroll_pixel_position,
+                                start_row,
+                                &line_layouts,
+                                newest_selection_head,
+                                line_height,
+                                em_width,
+                                cx,
+                            );
+                        }
+                    }
+
                     let mouse_context_menu = self.layout_mouse_context_menu(cx);
 
                     cx.with_element_namespace("gutter_fold_toggles", |cx| {
@@ -5156,6 +5326,7 @@ impl Element for EditorElement {
                         selections,
                         mouse_context_menu,
                         test_indicators,
+                        decoration_indicators,
                         code_actions_indicator,
                         gutter_fold_toggles,
                         crease_trailers,
@@ -5288,6 +5459,7 @@ pub struct EditorLayout {
     selections: Vec<(PlayerColor, Vec<SelectionLayout>)>,
     code_actions_indicator: Option<AnyElement>,
     test_indicators: Vec<AnyElement>,
+    decoration_indicators: Vec<AnyElement>,
     gutter_fold_toggles: Vec<Option<AnyElement>>,
     crease_trailers: Vec<Option<CreaseTrailerLayout>>,
     mouse_context_menu: Option<AnyElement>,
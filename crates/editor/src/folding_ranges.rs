@@ -0,0 +1,92 @@
+use std::ops::Range;
+
+use gpui::Model;
+use language::Buffer;
+use multi_buffer::Anchor;
+use project::{BufferFoldingRanges, FoldingRangeKind, Project};
+use ui::ViewContext;
+
+use crate::{lsp_decorations::request_buffer_decorations, Editor, EditorMode};
+
+/// Requests the folding ranges of all buffers of the editor from their language servers, which are then offered
+/// in the gutter and by the fold actions along with the creases and the indentation.
+pub(super) fn refresh_folding_ranges(
+    editor: &mut Editor,
+    debounce: bool,
+    cx: &mut ViewContext<Editor>,
+) {
+    if editor.mode != EditorMode::Full {
+        return;
+    }
+    let Some(project) = editor.project.clone() else {
+        return;
+    };
+    let buffers = editor
+        .buffer
+        .read(cx)
+        .all_buffers()
+        .into_iter()
+        .collect::<Vec<_>>();
+
+    editor.folding_ranges_task = Some(request_buffer_decorations(
+        project,
+        buffers,
+        debounce,
+        Project::folding_ranges,
+        set_folding_ranges,
+        cx,
+    ));
+}
+
+/// Maps the folding ranges of the buffers into their excerpts of the editor.
+fn set_folding_ranges(
+    editor: &mut Editor,
+    folding_ranges: Vec<(Model<Buffer>, BufferFoldingRanges)>,
+    cx: &mut ViewContext<Editor>,
+) {
+    let multi_buffer = editor.buffer.read(cx);
+    let snapshot = multi_buffer.snapshot(cx);
+    let mut ranges = Vec::<(Range<Anchor>, Option<FoldingRangeKind>)>::new();
+    for (buffer, buffer_ranges) in &folding_ranges {
+        let buffer_snapshot = buffer.read(cx).snapshot();
+        for (excerpt_id, excerpt_range) in multi_buffer.excerpts_for_buffer(buffer, cx) {
+            let context = excerpt_range.context;
+            for folding_range in buffer_ranges.ranges.iter() {
+                // Only the ranges that start and end in the excerpt can be folded in it.
+                if folding_range
+                    .range
+                    .start
+                    .cmp(&context.start, &buffer_snapshot)
+                    .is_lt()
+                    || folding_range
+                        .range
+                        .end
+                        .cmp(&context.end, &buffer_snapshot)
+                        .is_gt()
+                {
+                    continue;
+                }
+                let Some(range) = snapshot
+                    .anchor_in_excerpt(excerpt_id, folding_range.range.start)
+                    .zip(snapshot.anchor_in_excerpt(excerpt_id, folding_range.range.end))
+                    .map(|(start, end)| start..end)
+                else {
+                    continue;
+                };
+                ranges.push((range, folding_range.kind));
+            }
+        }
+    }
+    ranges.sort_by(|(a, _), (b, _)| a.start.cmp(&b.start, &snapshot));
+
+    editor.import_folding_ranges = ranges
+        .iter()
+        .filter(|(_, kind)| *kind == Some(FoldingRangeKind::Imports))
+        .map(|(range, _)| range.clone())
+        .collect();
+    let ranges = ranges.into_iter().map(|(range, _)| range).collect();
+    editor
+        .display_map
+        .update(cx, |map, _| map.set_folding_ranges(ranges));
+    cx.notify();
+}
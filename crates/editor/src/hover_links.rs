@@ -9,10 +9,11 @@ use language::{Bias, ToOffset};
 use linkify::{LinkFinder, LinkKind};
 use lsp::LanguageServerId;
 use project::{
-    HoverBlock, HoverBlockKind, InlayHintLabelPartTooltip, InlayHintTooltip, LocationLink,
-    ResolveState,
+    lsp_command::ExecuteLspCommand, HoverBlock, HoverBlockKind, InlayHintLabelPartCommand,
+    InlayHintLabelPartTooltip, InlayHintTooltip, LocationLink, Project, ResolveState,
 };
-use std::ops::Range;
+use std::{ops::Range, path::PathBuf};
+use text::BufferId;
 use theme::ActiveTheme as _;
 use util::{maybe, ResultExt, TryFutureExt};
 
@@ -49,13 +50,20 @@ impl RangeInEditor {
                 let point_after_start = range.start.cmp(point, &snapshot.buffer_snapshot).is_le();
                 point_after_start && range.end.cmp(point, &snapshot.buffer_snapshot).is_ge()
             }
-            (Self::Inlay(highlight), TriggerPoint::InlayHint(point, _, _)) => {
+            (
+                Self::Inlay(highlight),
+                TriggerPoint::InlayHint(point, _, _)
+                | TriggerPoint::InlayHintCommand(point, _, _, _),
+            ) => {
                 highlight.inlay == point.inlay
                     && highlight.range.contains(&point.range.start)
                     && highlight.range.contains(&point.range.end)
             }
             (Self::Inlay(_), TriggerPoint::Text(_))
-            | (Self::Text(_), TriggerPoint::InlayHint(_, _, _)) => false,
+            | (
+                Self::Text(_),
+                TriggerPoint::InlayHint(_, _, _) | TriggerPoint::InlayHintCommand(_, _, _, _),
+            ) => false,
         }
     }
 }
@@ -63,8 +71,12 @@ impl RangeInEditor {
 #[derive(Debug, Clone)]
 pub enum HoverLink {
     Url(String),
+    /// A local file, linked with a `file://` URI.
+    File(PathBuf),
     Text(LocationLink),
     InlayHint(lsp::Location, LanguageServerId),
+    /// A command of the language server, run when an inlay hint label part in the buffer is clicked.
+    InlayHintCommand(InlayHintLabelPartCommand, LanguageServerId, BufferId),
 }
 
 #[derive(Debug, Clone, PartialEq, Eq)]
@@ -78,13 +90,20 @@ pub(crate) struct InlayHighlight {
 pub enum TriggerPoint {
     Text(Anchor),
     InlayHint(InlayHighlight, lsp::Location, LanguageServerId),
+    InlayHintCommand(
+        InlayHighlight,
+        InlayHintLabelPartCommand,
+        LanguageServerId,
+        BufferId,
+    ),
 }
 
 impl TriggerPoint {
     fn anchor(&self) -> &Anchor {
         match self {
             TriggerPoint::Text(anchor) => anchor,
-            TriggerPoint::InlayHint(inlay_range, _, _) => &inlay_range.inlay_position,
+            TriggerPoint::InlayHint(inlay_range, _, _)
+            | TriggerPoint::InlayHintCommand(inlay_range, _, _, _) => &inlay_range.inlay_position,
         }
     }
 }
@@ -116,13 +135,14 @@ impl Editor {
         modifiers: Modifiers,
         cx: &mut ViewContext<Self>,
     ) {
-        if !modifiers.secondary() || self.has_pending_selection() {
-            self.hide_hovered_link(cx);
-            return;
-        }
-
+        let secondary_held = modifiers.secondary() && !self.has_pending_selection();
         match point_for_position.as_valid() {
             Some(point) => {
+                self.hover_state.inlay_hover_point = None;
+                if !secondary_held {
+                    self.hide_hovered_link(cx);
+                    return;
+                }
                 let trigger_point = TriggerPoint::Text(
                     snapshot
                         .buffer_snapshot
@@ -132,11 +152,12 @@ impl Editor {
                 show_link_definition(modifiers.shift, self, trigger_point, snapshot, cx);
             }
             None => {
+                // Inlay hints show their tooltips without the modifier, so they're updated either way.
                 update_inlay_link_and_hover_points(
                     &snapshot,
                     point_for_position,
                     self,
-                    modifiers.secondary(),
+                    secondary_held,
                     modifiers.shift,
                     cx,
                 );
@@ -144,6 +165,24 @@ impl Editor {
         }
     }
 
+    /// Updates the hover and the link of the inlay hint under the mouse, like after it got resolved.
+    pub(crate) fn refresh_inlay_hover(&mut self, cx: &mut ViewContext<Self>) {
+        let Some(point_for_position) = self.hover_state.inlay_hover_point else {
+            return;
+        };
+        let modifiers = cx.modifiers();
+        let secondary_held = modifiers.secondary() && !self.has_pending_selection();
+        let snapshot = self.snapshot(cx);
+        update_inlay_link_and_hover_points(
+            &snapshot,
+            point_for_position,
+            self,
+            secondary_held,
+            modifiers.shift,
+            cx,
+        );
+    }
+
     pub(crate) fn hide_hovered_link(&mut self, cx: &mut ViewContext<Self>) {
         self.hovered_link_state.take();
         self.clear_highlights::<HoveredLinkState>(cx);
@@ -174,6 +213,37 @@ impl Editor {
         .detach();
     }
 
+    /// Runs the command of a clicked inlay hint label part with the language server that provided the hint.
+    pub(crate) fn execute_inlay_hint_command(
+        &mut self,
+        command: InlayHintLabelPartCommand,
+        server_id: LanguageServerId,
+        buffer_id: BufferId,
+        cx: &mut ViewContext<Self>,
+    ) {
+        let Some(project) = self.project.clone() else {
+            return;
+        };
+        let Some(buffer) = self.buffer.read(cx).buffer(buffer_id) else {
+            return;
+        };
+        let arguments = command
+            .arguments
+            .iter()
+            .filter_map(|argument| serde_json::from_str(argument).log_err())
+            .collect();
+        let command = ExecuteLspCommand {
+            command: command.command,
+            arguments,
+            server_id: Some(server_id),
+        };
+        project
+            .update(cx, |project, cx| {
+                project.execute_lsp_command(&buffer, command, cx)
+            })
+            .detach_and_log_err(cx);
+    }
+
     pub fn scroll_hover(&mut self, amount: &ScrollAmount, cx: &mut ViewContext<Self>) -> bool {
         let selection = self.selections.newest_anchor().head();
         let snapshot = self.snapshot(cx);
@@ -265,6 +335,7 @@ pub fn update_inlay_link_and_hover_points(
     } else {
         None
     };
+    editor.hover_state.inlay_hover_point = Some(point_for_position);
     let mut go_to_definition_updated = false;
     let mut hover_updated = false;
     if let Some(hovered_offset) = hovered_offset {
@@ -389,9 +460,26 @@ pub fn update_inlay_link_and_hover_points(
                                         );
                                         hover_updated = true;
                                     }
-                                    if let Some((language_server_id, location)) =
-                                        hovered_hint_part.location
-                                    {
+                                    let trigger_point =
+                                        if let Some((language_server_id, location)) =
+                                            hovered_hint_part.location
+                                        {
+                                            Some(TriggerPoint::InlayHint(
+                                                highlight,
+                                                location,
+                                                language_server_id,
+                                            ))
+                                        } else {
+                                            hovered_hint_part
+                                                .command
+                                                .zip(previous_valid_anchor.buffer_id)
+                                                .map(|((server_id, command), buffer_id)| {
+                                                    TriggerPoint::InlayHintCommand(
+                                                        highlight, command, server_id, buffer_id,
+                                                    )
+                                                })
+                                        };
+                                    if let Some(trigger_point) = trigger_point {
                                         if secondary_held
                                             && !editor.has_pending_nonempty_selection()
                                         {
@@ -399,11 +487,7 @@ pub fn update_inlay_link_and_hover_points(
                                             show_link_definition(
                                                 shift_held,
                                                 editor,
-                                                TriggerPoint::InlayHint(
-                                                    highlight,
-                                                    location,
-                                                    language_server_id,
-                                                ),
+                                                trigger_point,
                                                 snapshot,
                                                 cx,
                                             );
@@ -486,7 +570,7 @@ pub fn show_link_definition(
         || hovered_link_state
             .links
             .first()
-            .is_some_and(|d| matches!(d, HoverLink::Url(_)));
+            .is_some_and(|d| matches!(d, HoverLink::Url(_) | HoverLink::File(_)));
 
     if same_kind {
         if is_cached && (&hovered_link_state.last_trigger_point == &trigger_point)
@@ -521,6 +605,16 @@ pub fn show_link_definition(
                             (range, vec![HoverLink::Url(url)])
                         })
                         .ok()
+                    } else if let Some((link_range, link)) =
+                        find_document_link(project.as_ref(), &buffer, buffer_position, &mut cx)
+                            .await
+                    {
+                        let range = maybe!({
+                            let start = snapshot.anchor_in_excerpt(excerpt_id, link_range.start)?;
+                            let end = snapshot.anchor_in_excerpt(excerpt_id, link_range.end)?;
+                            Some(RangeInEditor::Text(start..end))
+                        });
+                        Some((range, vec![link]))
                     } else if let Some(project) = project {
                         // query the LSP for definition info
                         project
@@ -559,6 +653,14 @@ pub fn show_link_definition(
                     Some(RangeInEditor::Inlay(highlight.clone())),
                     vec![HoverLink::InlayHint(lsp_location.clone(), *server_id)],
                 )),
+                TriggerPoint::InlayHintCommand(highlight, command, server_id, buffer_id) => Some((
+                    Some(RangeInEditor::Inlay(highlight.clone())),
+                    vec![HoverLink::InlayHintCommand(
+                        command.clone(),
+                        *server_id,
+                        *buffer_id,
+                    )],
+                )),
             };
 
             this.update(&mut cx, |editor, cx| {
@@ -599,7 +701,8 @@ pub fn show_link_definition(
                                             ..snapshot.anchor_after(offset_range.end),
                                     )
                                 }
-                                TriggerPoint::InlayHint(highlight, _, _) => {
+                                TriggerPoint::InlayHint(highlight, _, _)
+                                | TriggerPoint::InlayHintCommand(highlight, _, _, _) => {
                                     RangeInEditor::Inlay(highlight.clone())
                                 }
                             });
@@ -624,6 +727,39 @@ pub fn show_link_definition(
     editor.hovered_link_state = Some(hovered_link_state);
 }
 
+/// Finds the document link reported by the language server at the position, resolving its target if needed.
+async fn find_document_link(
+    project: Option<&Model<Project>>,
+    buffer: &Model<language::Buffer>,
+    position: text::Anchor,
+    cx: &mut AsyncWindowContext,
+) -> Option<(Range<text::Anchor>, HoverLink)> {
+    let project = project?;
+    let links = project
+        .update(cx, |project, cx| project.document_links(buffer, cx))
+        .ok()?
+        .await
+        .log_err()?;
+    let link = buffer
+        .update(cx, |buffer, _| {
+            links.link_at(position, &buffer.snapshot()).cloned()
+        })
+        .ok()??;
+    let link = project
+        .update(cx, |project, cx| {
+            project.resolve_document_link(buffer, link, cx)
+        })
+        .ok()?
+        .await
+        .log_err()?;
+    let target = link.target?;
+    let hover_link = match lsp::Url::parse(&target) {
+        Ok(url) if url.scheme() == "file" => HoverLink::File(url.to_file_path().ok()?),
+        _ => HoverLink::Url(target),
+    };
+    Some((link.range, hover_link))
+}
+
 pub(crate) fn find_url(
     buffer: &Model<language::Buffer>,
     position: text::Anchor,
@@ -1187,6 +1323,131 @@ mod tests {
             "});
     }
 
+    #[gpui::test]
+    async fn test_inlay_hover_link_commands(cx: &mut gpui::TestAppContext) {
+        init_test(cx, |settings| {
+            settings.defaults.inlay_hints = Some(InlayHintSettings {
+                enabled: true,
+                edit_debounce_ms: 0,
+                scroll_debounce_ms: 0,
+                show_type_hints: true,
+                show_parameter_hints: true,
+                show_other_hints: true,
+            })
+        });
+
+        let mut cx = EditorLspTestContext::new_rust(
+            lsp::ServerCapabilities {
+                inlay_hint_provider: Some(lsp::OneOf::Left(true)),
+                execute_command_provider: Some(lsp::ExecuteCommandOptions {
+                    commands: vec!["rust-analyzer.showReferences".to_string()],
+                    ..Default::default()
+                }),
+                ..Default::default()
+            },
+            cx,
+        )
+        .await;
+        cx.set_state(indoc! {"
+                struct TestStruct;
+
+                fn main() {
+                    let variableˇ = TestStruct;
+                }
+            "});
+        let hint_start_offset = cx.ranges(indoc! {"
+                struct TestStruct;
+
+                fn main() {
+                    let variableˇ = TestStruct;
+                }
+            "})[0]
+            .start;
+        let hint_position = cx.to_lsp(hint_start_offset);
+        let hint_label = ": TestStruct";
+        cx.lsp
+            .handle_request::<lsp::request::InlayHintRequest, _, _>(move |_, _| async move {
+                Ok(Some(vec![lsp::InlayHint {
+                    position: hint_position,
+                    label: lsp::InlayHintLabel::LabelParts(vec![lsp::InlayHintLabelPart {
+                        value: hint_label.to_string(),
+                        command: Some(lsp::Command {
+                            title: "Show references".to_string(),
+                            command: "rust-analyzer.showReferences".to_string(),
+                            arguments: Some(vec![serde_json::json!({ "position": 3 })]),
+                        }),
+                        ..Default::default()
+                    }]),
+                    kind: Some(lsp::InlayHintKind::TYPE),
+                    text_edits: None,
+                    tooltip: None,
+                    padding_left: Some(false),
+                    padding_right: Some(false),
+                    data: None,
+                }]))
+            })
+            .next()
+            .await;
+        cx.background_executor.run_until_parked();
+
+        let inlay_range = cx
+            .ranges(indoc! {"
+                struct TestStruct;
+
+                fn main() {
+                    let variable« »= TestStruct;
+                }
+            "})
+            .get(0)
+            .cloned()
+            .unwrap();
+        let midpoint = cx.update_editor(|editor, cx| {
+            let snapshot = editor.snapshot(cx);
+            let previous_valid = inlay_range.start.to_display_point(&snapshot);
+            DisplayPoint::new(
+                previous_valid.row(),
+                previous_valid.column() + (hint_label.len() / 2) as u32,
+            )
+        });
+        let hover_point = cx.pixel_position_for(midpoint);
+        cx.simulate_mouse_move(hover_point, None, Modifiers::secondary_key());
+        cx.background_executor.run_until_parked();
+        cx.update_editor(|editor, cx| {
+            let snapshot = editor.snapshot(cx);
+            let actual_highlights = snapshot
+                .inlay_highlights::<HoveredLinkState>()
+                .into_iter()
+                .flat_map(|highlights| highlights.values().map(|(_, highlight)| highlight))
+                .collect::<Vec<_>>();
+
+            let buffer_snapshot = editor.buffer().update(cx, |buffer, cx| buffer.snapshot(cx));
+            let expected_highlight = InlayHighlight {
+                inlay: InlayId::Hint(0),
+                inlay_position: buffer_snapshot.anchor_at(inlay_range.start, Bias::Right),
+                range: 0..hint_label.len(),
+            };
+            assert_set_eq!(actual_highlights, vec![&expected_highlight]);
+        });
+
+        let mut requests =
+            cx.lsp
+                .handle_request::<lsp::request::ExecuteCommand, _, _>(|params, _| async move {
+                    assert_eq!(params.command, "rust-analyzer.showReferences");
+                    assert_eq!(params.arguments, vec![serde_json::json!({ "position": 3 })]);
+                    Ok(None)
+                });
+        cx.simulate_click(hover_point, Modifiers::secondary_key());
+        requests.next().await;
+        cx.background_executor.run_until_parked();
+        cx.assert_editor_state(indoc! {"
+                struct TestStruct;
+
+                fn main() {
+                    let variableˇ = TestStruct;
+                }
+            "});
+    }
+
     #[gpui::test]
     async fn test_urls(cx: &mut gpui::TestAppContext) {
         init_test(cx, |_| {});
@@ -610,7 +610,8 @@ impl InlayHintCache {
                         if let Some(resolved_hint_task) = resolved_hint_task {
                             let mut resolved_hint =
                                 resolved_hint_task.await.context("hint resolve task")?;
-                            editor.update(&mut cx, |editor, _| {
+                            editor.update(&mut cx, |editor, cx| {
+                                let mut resolved = false;
                                 if let Some(excerpt_hints) =
                                     editor.inlay_hint_cache.hints.get(&excerpt_id)
                                 {
@@ -619,9 +620,15 @@ impl InlayHintCache {
                                         if cached_hint.resolve_state == ResolveState::Resolving {
                                             resolved_hint.resolve_state = ResolveState::Resolved;
                                             *cached_hint = resolved_hint;
+                                            resolved = true;
                                         }
                                     }
                                 }
+                                // The mouse may still be over the hint, which can only show its tooltip and
+                                // link now.
+                                if resolved {
+                                    editor.refresh_inlay_hover(cx);
+                                }
                             })?;
                         }
 
@@ -0,0 +1,137 @@
+//! Inline values: while a debugger is stopped in a stack frame, the values the language servers report for the
+//! lines of the editor are shown at their ends, and requested again when the servers ask for it with
+//! `workspace/inlineValue/refresh`.
+//!
+//! Only the values given as text are shown: the variables and expressions that servers ask the debugger to look
+//! up or evaluate can only be shown by the debugger itself, with [`project::Project::inline_values`].
+
+use std::ops::Range;
+
+use collections::BTreeMap;
+use gpui::Model;
+use language::{Buffer, Point, ToOffset as _, ToPoint as _};
+use multi_buffer::Anchor;
+use project::{InlineValue, InlineValueKind};
+use ui::ViewContext;
+use util::ResultExt;
+
+use crate::{
+    decorations::{Decoration, DecorationInvalidation, DecorationKind},
+    Editor, EditorMode,
+};
+
+/// The decorations of the inline values.
+enum InlineValueDecorations {}
+
+/// The stack frame the inline values are shown for.
+pub(super) struct InlineValuesFrame {
+    frame_id: i32,
+    stopped_location: Range<Anchor>,
+}
+
+impl Editor {
+    /// Shows the inline values of the stack frame `frame_id` of the debug adapter, the debuggee being stopped at
+    /// `stopped_location`, in the buffer of the stopped location. They replace the values of the previous frame.
+    pub fn show_inline_values(
+        &mut self,
+        frame_id: i32,
+        stopped_location: Range<Anchor>,
+        cx: &mut ViewContext<Self>,
+    ) {
+        self.inline_values_frame = Some(InlineValuesFrame {
+            frame_id,
+            stopped_location,
+        });
+        refresh_inline_values(self, cx);
+    }
+
+    /// Hides the inline values, like when the debuggee continues.
+    pub fn hide_inline_values(&mut self, cx: &mut ViewContext<Self>) {
+        self.inline_values_frame = None;
+        self.inline_values_task = None;
+        self.clear_decorations::<InlineValueDecorations>(cx);
+    }
+}
+
+/// Requests the inline values of the stack frame the editor shows them for, if any, again.
+pub(super) fn refresh_inline_values(editor: &mut Editor, cx: &mut ViewContext<Editor>) {
+    if editor.mode != EditorMode::Full {
+        return;
+    }
+    let Some(project) = editor.project.clone() else {
+        return;
+    };
+    let Some(frame) = editor.inline_values_frame.as_ref() else {
+        return;
+    };
+    let frame_id = frame.frame_id;
+    let stopped_location = frame.stopped_location.clone();
+    let Some(buffer) = stopped_location
+        .start
+        .buffer_id
+        .and_then(|buffer_id| editor.buffer.read(cx).buffer(buffer_id))
+    else {
+        return;
+    };
+
+    let snapshot = buffer.read(cx).snapshot();
+    let stopped_location = stopped_location.start.text_anchor.to_offset(&snapshot)
+        ..stopped_location.end.text_anchor.to_offset(&snapshot);
+    let values = project.update(cx, |project, cx| {
+        project.inline_values(&buffer, 0..snapshot.len(), frame_id, stopped_location, cx)
+    });
+    editor.inline_values_task = Some(cx.spawn(|editor, mut cx| async move {
+        let Some(values) = values.await.log_err() else {
+            return;
+        };
+        editor
+            .update(&mut cx, |editor, cx| {
+                let decorations = inline_value_decorations(editor, &buffer, &values, cx);
+                editor.set_decorations::<InlineValueDecorations>(decorations, cx);
+            })
+            .ok();
+    }));
+}
+
+/// Shows the text values at the ends of their lines in the excerpts of the buffer, the values of a line joined.
+fn inline_value_decorations(
+    editor: &Editor,
+    buffer: &Model<Buffer>,
+    values: &[InlineValue],
+    cx: &mut ViewContext<Editor>,
+) -> Vec<Decoration> {
+    let buffer_snapshot = buffer.read(cx).snapshot();
+    let mut texts_by_row = BTreeMap::<u32, Vec<&str>>::default();
+    for value in values {
+        if let InlineValueKind::Text(text) = &value.kind {
+            let row = value.range.end.to_point(&buffer_snapshot).row;
+            texts_by_row.entry(row).or_default().push(text);
+        }
+    }
+
+    let multi_buffer = editor.buffer.read(cx);
+    let snapshot = multi_buffer.snapshot(cx);
+    let excerpts = multi_buffer.excerpts_for_buffer(buffer, cx);
+    let mut decorations = Vec::new();
+    for (row, texts) in texts_by_row {
+        let line_end = buffer_snapshot.anchor_after(Point::new(row, buffer_snapshot.line_len(row)));
+        for (excerpt_id, excerpt_range) in &excerpts {
+            let context = &excerpt_range.context;
+            if line_end.cmp(&context.start, &buffer_snapshot).is_lt()
+                || line_end.cmp(&context.end, &buffer_snapshot).is_gt()
+            {
+                continue;
+            }
+            let Some(position) = snapshot.anchor_in_excerpt(*excerpt_id, line_end) else {
+                continue;
+            };
+            decorations.push(Decoration {
+                range: position..position,
+                kind: DecorationKind::InlineHint(format!(" {}", texts.join(", ")).into()),
+                z_index: 0,
+                invalidation: DecorationInvalidation::OnEdit,
+            });
+        }
+    }
+    decorations
+}
@@ -1,7 +1,8 @@
 use crate::{
-    editor_settings::SeedQuerySetting, persistence::DB, scroll::ScrollAnchor, Anchor, Autoscroll,
-    Editor, EditorEvent, EditorSettings, ExcerptId, ExcerptRange, MultiBuffer, MultiBufferSnapshot,
-    NavigationData, SearchWithinRange, ToPoint as _,
+    breadcrumb_symbols::breadcrumb_symbols, editor_settings::SeedQuerySetting, persistence::DB,
+    scroll::ScrollAnchor, Anchor, Autoscroll, Editor, EditorEvent, EditorSettings, ExcerptId,
+    ExcerptRange, MultiBuffer, MultiBufferSnapshot, NavigationData, SearchWithinRange,
+    ToPoint as _,
 };
 use anyhow::{anyhow, Context as _, Result};
 use collections::HashSet;
@@ -601,10 +602,17 @@ impl Item for Editor {
             self.buffer()
                 .read(cx)
                 .as_singleton()
-                .and_then(|buffer| buffer.read(cx).project_path(cx))
-                .and_then(|path| self.project.as_ref()?.read(cx).entry_for_path(&path, cx))
-                .map(|entry| {
-                    entry_git_aware_label_color(entry.git_status, entry.is_ignored, params.selected)
+                .zip(self.project.as_ref())
+                .and_then(|(buffer, project)| {
+                    let project = project.read(cx);
+                    let entry = project.entry_for_path(&buffer.read(cx).project_path(cx)?, cx)?;
+                    // The files in the repositories of other version control systems get their status from them.
+                    let git_status = entry.git_status.or_else(|| project.vcs_status(&buffer, cx));
+                    Some(entry_git_aware_label_color(
+                        git_status,
+                        entry.is_ignored,
+                        params.selected,
+                    ))
                 })
                 .unwrap_or_else(|| entry_label_color(params.selected))
         } else {
@@ -811,8 +819,33 @@ impl Item for Editor {
     fn breadcrumbs(&self, variant: &Theme, cx: &AppContext) -> Option<Vec<BreadcrumbText>> {
         let cursor = self.selections.newest_anchor().head();
         let multibuffer = &self.buffer().read(cx);
-        let (buffer_id, symbols) =
-            multibuffer.symbols_containing(cursor, Some(&variant.syntax()), cx)?;
+        let settings = ThemeSettings::get_global(cx);
+        let (buffer_id, symbols) = match breadcrumb_symbols(self, cursor, cx) {
+            Some((buffer_id, names)) => (
+                buffer_id,
+                names
+                    .into_iter()
+                    .map(|name| BreadcrumbText {
+                        text: name,
+                        highlights: None,
+                        font: Some(settings.buffer_font.clone()),
+                    })
+                    .collect::<Vec<_>>(),
+            ),
+            None => {
+                let (buffer_id, symbols) =
+                    multibuffer.symbols_containing(cursor, Some(&variant.syntax()), cx)?;
+                let symbols = symbols
+                    .into_iter()
+                    .map(|symbol| BreadcrumbText {
+                        text: symbol.text,
+                        highlights: Some(symbol.highlight_ranges),
+                        font: Some(settings.buffer_font.clone()),
+                    })
+                    .collect();
+                (buffer_id, symbols)
+            }
+        };
         let buffer = multibuffer.buffer(buffer_id)?;
 
         let buffer = buffer.read(cx);
@@ -830,19 +863,12 @@ impl Item for Editor {
                 .unwrap_or_else(|| "untitled".to_string())
         });
 
-        let settings = ThemeSettings::get_global(cx);
-
         let mut breadcrumbs = vec![BreadcrumbText {
             text,
             highlights: None,
             font: Some(settings.buffer_font.clone()),
         }];
-
-        breadcrumbs.extend(symbols.into_iter().map(|symbol| BreadcrumbText {
-            text: symbol.text,
-            highlights: Some(symbol.highlight_ranges),
-            font: Some(settings.buffer_font.clone()),
-        }));
+        breadcrumbs.extend(symbols);
         Some(breadcrumbs)
     }
 
@@ -905,7 +931,7 @@ impl Item for Editor {
                 f(ItemEvent::UpdateBreadcrumbs);
             }
 
-            EditorEvent::Reparsed(_) => {
+            EditorEvent::Reparsed(_) | EditorEvent::BreadcrumbsChanged => {
                 f(ItemEvent::UpdateBreadcrumbs);
             }
 
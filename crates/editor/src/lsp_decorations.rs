@@ -0,0 +1,67 @@
+use std::time::Duration;
+
+use anyhow::Result;
+use futures::future::join_all;
+use gpui::{Model, ModelContext, Task};
+use language::Buffer;
+use project::Project;
+use ui::ViewContext;
+use util::ResultExt;
+
+use crate::{breadcrumb_symbols, document_colors, folding_ranges, semantic_tokens, Editor};
+
+pub(super) const LSP_DECORATIONS_DEBOUNCE: Duration = Duration::from_millis(150);
+
+/// Refreshes all the decorations that the language servers provide for the buffers of the editor, and the symbols
+/// of the breadcrumbs, after a short delay when `debounce` is set, as when the buffers are edited.
+pub(super) fn refresh_lsp_decorations(
+    editor: &mut Editor,
+    debounce: bool,
+    cx: &mut ViewContext<Editor>,
+) {
+    semantic_tokens::refresh_semantic_tokens(editor, debounce, cx);
+    document_colors::refresh_document_colors(editor, debounce, cx);
+    folding_ranges::refresh_folding_ranges(editor, debounce, cx);
+    breadcrumb_symbols::refresh_breadcrumb_symbols(editor, debounce, cx);
+}
+
+/// Requests a decoration of each of the buffers from the project, after a short delay when `debounce` is set, and
+/// applies the ones that were received to the editor.
+pub(super) fn request_buffer_decorations<T: 'static>(
+    project: Model<Project>,
+    buffers: Vec<Model<Buffer>>,
+    debounce: bool,
+    request: fn(&mut Project, &Model<Buffer>, &mut ModelContext<Project>) -> Task<Result<T>>,
+    apply: fn(&mut Editor, Vec<(Model<Buffer>, T)>, &mut ViewContext<Editor>),
+    cx: &mut ViewContext<Editor>,
+) -> Task<()> {
+    cx.spawn(|editor, mut cx| async move {
+        if debounce {
+            cx.background_executor()
+                .timer(LSP_DECORATIONS_DEBOUNCE)
+                .await;
+        }
+        let Some(tasks) = project
+            .update(&mut cx, |project, cx| {
+                buffers
+                    .iter()
+                    .map(|buffer| request(project, buffer, cx))
+                    .collect::<Vec<_>>()
+            })
+            .log_err()
+        else {
+            return;
+        };
+        let decorations = join_all(tasks).await;
+        editor
+            .update(&mut cx, |editor, cx| {
+                let decorations = buffers
+                    .into_iter()
+                    .zip(decorations)
+                    .filter_map(|(buffer, decoration)| Some((buffer, decoration.log_err()?)))
+                    .collect();
+                apply(editor, decorations, cx);
+            })
+            .ok();
+    })
+}
@@ -0,0 +1,246 @@
+use std::ops::Range;
+
+use anyhow::Result;
+use collections::HashMap;
+use gpui::{Model, SharedString, Task, ViewContext, WeakView};
+use language::{Buffer, Capability, ToOffset};
+use multi_buffer::{Anchor, MultiBuffer};
+use project::{PendingRenameEdit, Project};
+use ui::{prelude::*, CheckboxWithLabel};
+use workspace::Workspace;
+
+use crate::{
+    display_map::{
+        BlockContext, BlockDisposition, BlockId, BlockProperties, BlockStyle, RenderBlock,
+    },
+    ApplyRenamePreview, ConfirmRename, ConfirmRenameWithPreview, Editor,
+    DEFAULT_MULTIBUFFER_CONTEXT,
+};
+
+/// The edits of a rename under review, each of which can be accepted or rejected before they are applied.
+pub(crate) struct RenamePreview {
+    edits: Vec<PendingRenameEdit>,
+    ranges: Vec<Range<Anchor>>,
+    accepted: Vec<bool>,
+    blocks: Vec<BlockId>,
+}
+
+/// Highlights of the accepted edits of a rename preview.
+enum RenamePreviewHighlight {}
+
+impl Editor {
+    /// Confirms the pending rename like [`Editor::confirm_rename`], but opens its edits for review instead
+    /// of applying them.
+    ///
+    /// Renames that can't be previewed, like the ones of remote projects, are applied right away.
+    pub fn confirm_rename_with_preview(
+        &mut self,
+        _: &ConfirmRenameWithPreview,
+        cx: &mut ViewContext<Self>,
+    ) -> Option<Task<Result<()>>> {
+        let project = self.project.clone()?;
+        if !project.read(cx).is_local() {
+            return self.confirm_rename(&ConfirmRename, cx);
+        }
+        let (buffer, position, old_name, new_name) = self.finish_rename(cx)?;
+        let workspace = self.workspace()?.downgrade();
+        let preview = project.update(cx, |project, cx| {
+            project.preview_rename(buffer, position, new_name.clone(), cx)
+        });
+
+        Some(cx.spawn(|_, mut cx| async move {
+            let edits = preview.await?;
+            if edits.is_empty() {
+                return Ok(());
+            }
+            workspace.update(&mut cx, |workspace, cx| {
+                let project = workspace.project().clone();
+                let title = format!("Rename Preview: {old_name} → {new_name}");
+                let editor = cx.new_view(|cx| rename_preview_editor(edits, title, project, cx));
+                workspace.add_item_to_active_pane(Box::new(editor), None, cx);
+            })
+        }))
+    }
+
+    /// Applies the accepted edits of the rename preview shown by the editor.
+    pub fn apply_rename_preview(&mut self, _: &ApplyRenamePreview, cx: &mut ViewContext<Self>) {
+        let Some(project) = self.project.clone() else {
+            return;
+        };
+        let Some(preview) = self.rename_preview.take() else {
+            cx.propagate();
+            return;
+        };
+        self.remove_blocks(preview.blocks.into_iter().collect(), None, cx);
+        self.clear_background_highlights::<RenamePreviewHighlight>(cx);
+
+        let accepted_edits = preview
+            .edits
+            .into_iter()
+            .zip(preview.accepted)
+            .filter_map(|(edit, accepted)| accepted.then_some(edit))
+            .collect::<Vec<_>>();
+        let transaction = project.update(cx, |project, cx| {
+            project.apply_rename_edits(accepted_edits, true, cx)
+        });
+        self.buffer.update(cx, |multi_buffer, cx| {
+            multi_buffer.push_transaction(transaction.0.iter(), cx);
+        });
+        self.set_read_only(false);
+        cx.notify();
+    }
+
+    fn toggle_rename_preview_edit(&mut self, ix: usize, cx: &mut ViewContext<Self>) {
+        let Some(preview) = self.rename_preview.as_mut() else {
+            return;
+        };
+        let Some(accepted) = preview.accepted.get_mut(ix) else {
+            return;
+        };
+        *accepted = !*accepted;
+        let accepted = *accepted;
+        let render = render_edit(
+            cx.view().downgrade(),
+            ix,
+            preview.edits[ix].new_text.clone(),
+            accepted,
+        );
+        let block = preview.blocks[ix];
+        self.replace_blocks(HashMap::from_iter([(block, (None, render))]), None, cx);
+        highlight_accepted_edits(self, cx);
+    }
+}
+
+/// Opens the edits of a file that conflicted with its unsaved changes for review, like the edits of a rename.
+pub(crate) fn review_conflicting_edits(
+    workspace: &mut Workspace,
+    edits: Vec<PendingRenameEdit>,
+    cx: &mut ViewContext<Workspace>,
+) {
+    let Some(buffer) = edits.first().map(|edit| edit.buffer.clone()) else {
+        return;
+    };
+    let path = buffer.read(cx).file().map_or_else(
+        || "untitled".to_string(),
+        |file| file.path().display().to_string(),
+    );
+    let title = format!("Conflicting Edits: {path}");
+    let project = workspace.project().clone();
+    let editor = cx.new_view(|cx| rename_preview_editor(edits, title, project, cx));
+    workspace.add_item_to_active_pane(Box::new(editor), None, cx);
+}
+
+fn rename_preview_editor(
+    mut edits: Vec<PendingRenameEdit>,
+    title: String,
+    project: Model<Project>,
+    cx: &mut ViewContext<Editor>,
+) -> Editor {
+    edits.sort_by_cached_key(|edit| {
+        let buffer = edit.buffer.read(cx);
+        (
+            buffer.file().map(|file| file.path().clone()),
+            edit.buffer.entity_id(),
+            edit.range.start.to_offset(buffer),
+        )
+    });
+
+    let replica_id = project.read(cx).replica_id();
+    let mut ranges = Vec::with_capacity(edits.len());
+    let multi_buffer = cx.new_model(|cx| {
+        let mut multi_buffer =
+            MultiBuffer::new(replica_id, Capability::ReadWrite).with_title(title);
+        for buffer_edits in edits.chunk_by(|a, b| a.buffer == b.buffer) {
+            let buffer: Model<Buffer> = buffer_edits[0].buffer.clone();
+            ranges.extend(multi_buffer.push_excerpts_with_context_lines(
+                buffer,
+                buffer_edits.iter().map(|edit| edit.range.clone()).collect(),
+                DEFAULT_MULTIBUFFER_CONTEXT,
+                cx,
+            ));
+        }
+        multi_buffer
+    });
+
+    let mut editor = Editor::for_multibuffer(multi_buffer, Some(project), true, cx);
+    editor.set_read_only(true);
+    let view = cx.view().downgrade();
+    let blocks = editor.insert_blocks(
+        edits
+            .iter()
+            .zip(&ranges)
+            .enumerate()
+            .map(|(ix, (edit, range))| BlockProperties {
+                position: range.start,
+                height: 1,
+                style: BlockStyle::Sticky,
+                render: render_edit(view.clone(), ix, edit.new_text.clone(), true),
+                disposition: BlockDisposition::Below,
+            }),
+        None,
+        cx,
+    );
+    editor.rename_preview = Some(RenamePreview {
+        accepted: vec![true; edits.len()],
+        edits,
+        ranges,
+        blocks,
+    });
+    highlight_accepted_edits(&mut editor, cx);
+    editor
+}
+
+fn highlight_accepted_edits(editor: &mut Editor, cx: &mut ViewContext<Editor>) {
+    let Some(preview) = editor.rename_preview.as_ref() else {
+        return;
+    };
+    let ranges = preview
+        .ranges
+        .iter()
+        .zip(&preview.accepted)
+        .filter_map(|(range, accepted)| accepted.then(|| range.clone()))
+        .collect::<Vec<_>>();
+    editor.highlight_background::<RenamePreviewHighlight>(
+        &ranges,
+        |theme| theme.editor_document_highlight_write_background,
+        cx,
+    );
+}
+
+/// Renders the replacement of an edit below it, with a checkbox to accept or reject it.
+fn render_edit(
+    editor: WeakView<Editor>,
+    ix: usize,
+    new_text: String,
+    accepted: bool,
+) -> RenderBlock {
+    let label = SharedString::from(format!("→ {new_text}"));
+    Box::new(move |cx: &mut BlockContext| {
+        let editor = editor.clone();
+        let selection = if accepted {
+            Selection::Selected
+        } else {
+            Selection::Unselected
+        };
+        h_flex()
+            .pl(cx.anchor_x)
+            .gap_2()
+            .child(CheckboxWithLabel::new(
+                ("rename-preview-edit", ix),
+                Label::new(label.clone()),
+                selection,
+                move |_, cx| {
+                    editor
+                        .update(cx, |editor, cx| editor.toggle_rename_preview_edit(ix, cx))
+                        .ok();
+                },
+            ))
+            .when(ix == 0, |this| {
+                this.child(
+                    Button::new("apply-rename-preview", "Apply Accepted Edits")
+                        .on_click(|_, cx| cx.dispatch_action(Box::new(ApplyRenamePreview))),
+                )
+            })
+            .into_any_element()
+    })
+}
@@ -1,13 +1,22 @@
 use std::sync::Arc;
 
 use anyhow::Context as _;
-use gpui::{Context, View, ViewContext, VisualContext, WindowContext};
-use language::Language;
-use multi_buffer::MultiBuffer;
-use project::lsp_ext_command::ExpandMacro;
+use gpui::{Context, Model, Task, View, ViewContext, VisualContext, WindowContext};
+use language::{Buffer, Language};
+use lsp::LanguageServerId;
+use multi_buffer::{Anchor, MultiBuffer};
+use project::{
+    lsp_ext_command::{self, ExpandMacro, RelatedTests},
+    LocationLink, TaskSourceKind,
+};
 use text::ToPointUtf16;
+use workspace::tasks::schedule_task;
 
-use crate::{element::register_action, Editor, ExpandMacroRecursively};
+use crate::{
+    element::register_action, hover_links::HoverLink, tasks::task_context, Editor,
+    ExpandMacroRecursively, GoToParentModule, OpenCargoToml, RebuildProcMacros, ReloadWorkspace,
+    RunRelatedTests, ViewHir, ViewMir,
+};
 
 pub fn apply_related_actions(editor: &View<Editor>, cx: &mut WindowContext) {
     let is_rust_related = editor.update(cx, |editor, cx| {
@@ -24,6 +33,13 @@ pub fn apply_related_actions(editor: &View<Editor>, cx: &mut WindowContext) {
 
     if is_rust_related {
         register_action(editor, cx, expand_macro_recursively);
+        register_action(editor, cx, view_hir);
+        register_action(editor, cx, view_mir);
+        register_action(editor, cx, run_related_tests);
+        register_action(editor, cx, reload_workspace);
+        register_action(editor, cx, rebuild_proc_macros);
+        register_action(editor, cx, open_cargo_toml);
+        register_action(editor, cx, go_to_parent_module);
     }
 }
 
@@ -42,39 +58,8 @@ pub fn expand_macro_recursively(
         return;
     };
 
-    let multibuffer = editor.buffer().read(cx);
-
-    let Some((trigger_anchor, rust_language, server_to_query, buffer)) = editor
-        .selections
-        .disjoint_anchors()
-        .into_iter()
-        .filter(|selection| selection.start == selection.end)
-        .filter_map(|selection| Some((selection.start.buffer_id?, selection.start)))
-        .filter_map(|(buffer_id, trigger_anchor)| {
-            let buffer = multibuffer.buffer(buffer_id)?;
-            let rust_language = buffer.read(cx).language_at(trigger_anchor.text_anchor)?;
-            if !is_rust_language(&rust_language) {
-                return None;
-            }
-            Some((trigger_anchor, rust_language, buffer))
-        })
-        .find_map(|(trigger_anchor, rust_language, buffer)| {
-            project
-                .read(cx)
-                .language_servers_for_buffer(buffer.read(cx), cx)
-                .find_map(|(adapter, server)| {
-                    if adapter.name.0.as_ref() == "rust-analyzer" {
-                        Some((
-                            trigger_anchor,
-                            Arc::clone(&rust_language),
-                            server.server_id(),
-                            buffer.clone(),
-                        ))
-                    } else {
-                        None
-                    }
-                })
-        })
+    let Some((trigger_anchor, rust_language, server_to_query, buffer)) =
+        find_rust_analyzer_in_selection(editor, cx)
     else {
         return;
     };
@@ -98,13 +83,11 @@ pub fn expand_macro_recursively(
         }
 
         let buffer = project
-            .update(&mut cx, |project, cx| project.create_buffer(cx))?
+            .update(&mut cx, |project, cx| {
+                project.create_buffer_with_text(macro_expansion.expansion, Some(rust_language), cx)
+            })?
             .await?;
         workspace.update(&mut cx, |workspace, cx| {
-            buffer.update(cx, |buffer, cx| {
-                buffer.edit([(0..0, macro_expansion.expansion)], None, cx);
-                buffer.set_language(Some(rust_language), cx)
-            });
             let multibuffer = cx.new_model(|cx| {
                 MultiBuffer::singleton(buffer, cx).with_title(macro_expansion.name)
             });
@@ -120,6 +103,320 @@ pub fn expand_macro_recursively(
     .detach_and_log_err(cx);
 }
 
+pub fn view_hir(editor: &mut Editor, _: &ViewHir, cx: &mut ViewContext<'_, Editor>) {
+    view_intermediate_representation(editor, IntermediateRepresentation::Hir, cx);
+}
+
+pub fn view_mir(editor: &mut Editor, _: &ViewMir, cx: &mut ViewContext<'_, Editor>) {
+    view_intermediate_representation(editor, IntermediateRepresentation::Mir, cx);
+}
+
+#[derive(Clone, Copy)]
+enum IntermediateRepresentation {
+    Hir,
+    Mir,
+}
+
+impl IntermediateRepresentation {
+    fn title(&self) -> &'static str {
+        match self {
+            Self::Hir => "HIR",
+            Self::Mir => "MIR",
+        }
+    }
+}
+
+fn view_intermediate_representation(
+    editor: &mut Editor,
+    representation: IntermediateRepresentation,
+    cx: &mut ViewContext<'_, Editor>,
+) {
+    if editor.selections.count() == 0 {
+        return;
+    }
+    let Some(project) = &editor.project else {
+        return;
+    };
+    let Some(workspace) = editor.workspace() else {
+        return;
+    };
+
+    let Some((trigger_anchor, _, server_to_query, buffer)) =
+        find_rust_analyzer_in_selection(editor, cx)
+    else {
+        return;
+    };
+
+    let project = project.clone();
+    let buffer_snapshot = buffer.read(cx).snapshot();
+    let position = trigger_anchor.text_anchor.to_point_utf16(&buffer_snapshot);
+    let server_to_query = project::LanguageServerToQuery::Other(server_to_query);
+    let ir_task = project.update(cx, |project, cx| match representation {
+        IntermediateRepresentation::Hir => project.request_lsp(
+            buffer,
+            server_to_query,
+            lsp_ext_command::ViewHir { position },
+            cx,
+        ),
+        IntermediateRepresentation::Mir => project.request_lsp(
+            buffer,
+            server_to_query,
+            lsp_ext_command::ViewMir { position },
+            cx,
+        ),
+    });
+    cx.spawn(|_editor, mut cx| async move {
+        let text = ir_task
+            .await
+            .with_context(|| format!("view {}", representation.title()))?;
+        if text.is_empty() {
+            log::info!("Empty {} for position {position:?}", representation.title());
+            return Ok(());
+        }
+
+        let buffer = project
+            .update(&mut cx, |project, cx| project.create_buffer(cx))?
+            .await?;
+        workspace.update(&mut cx, |workspace, cx| {
+            buffer.update(cx, |buffer, cx| {
+                buffer.edit([(0..0, text)], None, cx);
+            });
+            let multibuffer = cx.new_model(|cx| {
+                MultiBuffer::singleton(buffer, cx).with_title(representation.title().to_string())
+            });
+            let editor = cx.new_view(|cx| {
+                let mut editor = Editor::for_multibuffer(multibuffer, Some(project), true, cx);
+                editor.set_read_only(true);
+                editor
+            });
+            workspace.add_item_to_active_pane(Box::new(editor), None, cx);
+        })
+    })
+    .detach_and_log_err(cx);
+}
+
+pub fn run_related_tests(
+    editor: &mut Editor,
+    _: &RunRelatedTests,
+    cx: &mut ViewContext<'_, Editor>,
+) {
+    if editor.selections.count() == 0 {
+        return;
+    }
+    let Some(project) = &editor.project else {
+        return;
+    };
+    let Some(workspace) = editor.workspace() else {
+        return;
+    };
+
+    let Some((trigger_anchor, rust_language, server_to_query, buffer)) =
+        find_rust_analyzer_in_selection(editor, cx)
+    else {
+        return;
+    };
+
+    let buffer_snapshot = buffer.read(cx).snapshot();
+    let position = trigger_anchor.text_anchor.to_point_utf16(&buffer_snapshot);
+    let related_tests_task = project.update(cx, |project, cx| {
+        project.request_lsp(
+            buffer,
+            project::LanguageServerToQuery::Other(server_to_query),
+            RelatedTests { position },
+            cx,
+        )
+    });
+    cx.spawn(|_editor, mut cx| async move {
+        let related_tests = related_tests_task.await.context("related tests")?;
+        if related_tests.is_empty() {
+            log::info!("No related tests for position {position:?}");
+            return Ok(());
+        }
+
+        let task_context = workspace
+            .update(&mut cx, |workspace, cx| task_context(workspace, cx))?
+            .await;
+        workspace.update(&mut cx, |workspace, cx| {
+            let task_source_kind = TaskSourceKind::Language {
+                name: rust_language.name(),
+            };
+            for test in &related_tests {
+                schedule_task(
+                    workspace,
+                    task_source_kind.clone(),
+                    test,
+                    &task_context,
+                    false,
+                    cx,
+                );
+            }
+        })
+    })
+    .detach_and_log_err(cx);
+}
+
+pub fn reload_workspace(
+    editor: &mut Editor,
+    _: &ReloadWorkspace,
+    cx: &mut ViewContext<'_, Editor>,
+) {
+    let Some(project) = &editor.project else {
+        return;
+    };
+    let Some((_, _, server_to_query, buffer)) = find_rust_analyzer_in_selection(editor, cx) else {
+        return;
+    };
+
+    let reload_task = project.update(cx, |project, cx| {
+        project.request_lsp(
+            buffer,
+            project::LanguageServerToQuery::Other(server_to_query),
+            lsp_ext_command::ReloadWorkspace,
+            cx,
+        )
+    });
+    cx.spawn(|_editor, _cx| async move { reload_task.await.context("reload workspace") })
+        .detach_and_log_err(cx);
+}
+
+pub fn rebuild_proc_macros(
+    editor: &mut Editor,
+    _: &RebuildProcMacros,
+    cx: &mut ViewContext<'_, Editor>,
+) {
+    let Some(project) = &editor.project else {
+        return;
+    };
+    let Some((_, _, server_to_query, buffer)) = find_rust_analyzer_in_selection(editor, cx) else {
+        return;
+    };
+
+    let rebuild_task = project.update(cx, |project, cx| {
+        project.request_lsp(
+            buffer,
+            project::LanguageServerToQuery::Other(server_to_query),
+            lsp_ext_command::RebuildProcMacros,
+            cx,
+        )
+    });
+    cx.spawn(|_editor, _cx| async move { rebuild_task.await.context("rebuild proc macros") })
+        .detach_and_log_err(cx);
+}
+
+pub fn open_cargo_toml(editor: &mut Editor, _: &OpenCargoToml, cx: &mut ViewContext<'_, Editor>) {
+    let Some(project) = &editor.project else {
+        return;
+    };
+    let Some((_, _, server_to_query, buffer)) = find_rust_analyzer_in_selection(editor, cx) else {
+        return;
+    };
+
+    let cargo_toml_task = project.update(cx, |project, cx| {
+        project.request_lsp(
+            buffer,
+            project::LanguageServerToQuery::Other(server_to_query),
+            lsp_ext_command::OpenCargoToml,
+            cx,
+        )
+    });
+    navigate_to_links(cargo_toml_task, "open Cargo.toml", cx);
+}
+
+pub fn go_to_parent_module(
+    editor: &mut Editor,
+    _: &GoToParentModule,
+    cx: &mut ViewContext<'_, Editor>,
+) {
+    if editor.selections.count() == 0 {
+        return;
+    }
+    let Some(project) = &editor.project else {
+        return;
+    };
+    let Some((trigger_anchor, _, server_to_query, buffer)) =
+        find_rust_analyzer_in_selection(editor, cx)
+    else {
+        return;
+    };
+
+    let buffer_snapshot = buffer.read(cx).snapshot();
+    let position = trigger_anchor.text_anchor.to_point_utf16(&buffer_snapshot);
+    let parent_module_task = project.update(cx, |project, cx| {
+        project.request_lsp(
+            buffer,
+            project::LanguageServerToQuery::Other(server_to_query),
+            lsp_ext_command::ParentModule { position },
+            cx,
+        )
+    });
+    navigate_to_links(parent_module_task, "go to parent module", cx);
+}
+
+fn navigate_to_links(
+    links_task: Task<anyhow::Result<Vec<LocationLink>>>,
+    description: &'static str,
+    cx: &mut ViewContext<'_, Editor>,
+) {
+    cx.spawn(|editor, mut cx| async move {
+        let links = links_task.await.context(description)?;
+        if links.is_empty() {
+            log::info!("No locations to {description}");
+            return Ok(());
+        }
+        editor
+            .update(&mut cx, |editor, cx| {
+                editor.navigate_to_hover_links(
+                    None,
+                    links.into_iter().map(HoverLink::Text).collect(),
+                    false,
+                    cx,
+                )
+            })?
+            .await?;
+        anyhow::Ok(())
+    })
+    .detach_and_log_err(cx);
+}
+
+fn find_rust_analyzer_in_selection(
+    editor: &Editor,
+    cx: &mut ViewContext<'_, Editor>,
+) -> Option<(Anchor, Arc<Language>, LanguageServerId, Model<Buffer>)> {
+    let project = editor.project.as_ref()?;
+    let multibuffer = editor.buffer().read(cx);
+    editor
+        .selections
+        .disjoint_anchors()
+        .into_iter()
+        .filter(|selection| selection.start == selection.end)
+        .filter_map(|selection| Some((selection.start.buffer_id?, selection.start)))
+        .filter_map(|(buffer_id, trigger_anchor)| {
+            let buffer = multibuffer.buffer(buffer_id)?;
+            let rust_language = buffer.read(cx).language_at(trigger_anchor.text_anchor)?;
+            if !is_rust_language(&rust_language) {
+                return None;
+            }
+            Some((trigger_anchor, rust_language, buffer))
+        })
+        .find_map(|(trigger_anchor, rust_language, buffer)| {
+            project
+                .read(cx)
+                .language_servers_for_buffer(buffer.read(cx), cx)
+                .find_map(|(adapter, server)| {
+                    if adapter.name.0.as_ref() == "rust-analyzer" {
+                        Some((
+                            trigger_anchor,
+                            Arc::clone(&rust_language),
+                            server.server_id(),
+                            buffer.clone(),
+                        ))
+                    } else {
+                        None
+                    }
+                })
+        })
+}
+
 fn is_rust_language(language: &Language) -> bool {
     language.name().as_ref() == "Rust"
 }
@@ -0,0 +1,145 @@
+use collections::HashMap;
+use gpui::{HighlightStyle, Model};
+use language::{language_settings::language_settings, Buffer};
+use multi_buffer::Anchor;
+use project::{BufferSemanticTokens, Project, SemanticToken};
+use theme::{ActiveTheme, SyntaxTheme};
+use ui::ViewContext;
+
+use crate::{lsp_decorations::request_buffer_decorations, Editor, EditorMode};
+
+/// Text highlights of the semantic tokens, keyed by the index of their style.
+pub(super) enum SemanticTokenHighlight {}
+
+/// Requests the semantic tokens of all buffers of the editor that have them enabled, and highlights them
+/// over the syntax highlighting.
+pub(super) fn refresh_semantic_tokens(
+    editor: &mut Editor,
+    debounce: bool,
+    cx: &mut ViewContext<Editor>,
+) {
+    if editor.mode != EditorMode::Full {
+        return;
+    }
+    let Some(project) = editor.project.clone() else {
+        return;
+    };
+    let buffers = editor
+        .buffer
+        .read(cx)
+        .all_buffers()
+        .into_iter()
+        .filter(|buffer| {
+            let buffer = buffer.read(cx);
+            language_settings(buffer.language(), buffer.file(), cx).semantic_tokens
+        })
+        .collect::<Vec<_>>();
+    if buffers.is_empty() {
+        editor.semantic_tokens_task = None;
+        editor.clear_highlights::<SemanticTokenHighlight>(cx);
+        return;
+    }
+
+    editor.semantic_tokens_task = Some(request_buffer_decorations(
+        project,
+        buffers,
+        debounce,
+        Project::semantic_tokens,
+        highlight_semantic_tokens,
+        cx,
+    ));
+}
+
+fn highlight_semantic_tokens(
+    editor: &mut Editor,
+    semantic_tokens: Vec<(Model<Buffer>, BufferSemanticTokens)>,
+    cx: &mut ViewContext<Editor>,
+) {
+    let syntax = cx.theme().syntax().clone();
+    let multi_buffer = editor.buffer.read(cx);
+    let snapshot = multi_buffer.snapshot(cx);
+    let mut highlights = Vec::<(HighlightStyle, Vec<std::ops::Range<Anchor>>)>::new();
+    let mut highlight_ixs_by_name = HashMap::<&'static str, usize>::default();
+    for (buffer, tokens) in &semantic_tokens {
+        let buffer_snapshot = buffer.read(cx).snapshot();
+        for (excerpt_id, excerpt_range) in multi_buffer.excerpts_for_buffer(buffer, cx) {
+            let context = excerpt_range.context;
+            let first_token_ix = tokens.tokens.partition_point(|token| {
+                token
+                    .range
+                    .end
+                    .cmp(&context.start, &buffer_snapshot)
+                    .is_le()
+            });
+            for token in &tokens.tokens[first_token_ix..] {
+                if token
+                    .range
+                    .start
+                    .cmp(&context.end, &buffer_snapshot)
+                    .is_ge()
+                {
+                    break;
+                }
+                let Some(name) = highlight_name(token, &syntax) else {
+                    continue;
+                };
+                let Some(range) = snapshot
+                    .anchor_in_excerpt(excerpt_id, token.range.start)
+                    .zip(snapshot.anchor_in_excerpt(excerpt_id, token.range.end))
+                    .map(|(start, end)| start..end)
+                else {
+                    continue;
+                };
+                let ix = *highlight_ixs_by_name.entry(name).or_insert_with(|| {
+                    highlights.push((syntax.get(name), Vec::new()));
+                    highlights.len() - 1
+                });
+                highlights[ix].1.push(range);
+            }
+        }
+    }
+
+    editor.clear_highlights::<SemanticTokenHighlight>(cx);
+    for (key, (style, mut ranges)) in highlights.into_iter().enumerate() {
+        ranges.sort_by(|a, b| a.start.cmp(&b.start, &snapshot));
+        editor.highlight_text_key::<SemanticTokenHighlight>(key, ranges, style, cx);
+    }
+}
+
+/// Picks the syntax theme highlight for the token: the first of the fitting names that the theme defines.
+fn highlight_name(token: &SemanticToken, syntax: &SyntaxTheme) -> Option<&'static str> {
+    let has_modifier = |modifier: &str| {
+        token
+            .token_modifiers
+            .iter()
+            .any(|token_modifier| token_modifier.as_ref() == modifier)
+    };
+    let names: &[&'static str] = match token.token_type.as_ref() {
+        "namespace" => &["namespace"],
+        "type" | "class" | "interface" | "struct" | "typeParameter" | "typeAlias"
+        | "builtinType" => &["type"],
+        "enum" => &["enum", "type"],
+        "enumMember" => &["variant", "constant"],
+        "variable" if has_modifier("constant") => &["constant", "variable"],
+        "parameter" | "variable" => &["variable"],
+        "selfKeyword" => &["variable.special", "keyword"],
+        "property" => &["property"],
+        "function" | "method" => &["function"],
+        "macro" => &["function.special", "function"],
+        "keyword" | "modifier" => &["keyword"],
+        "comment" if has_modifier("documentation") => &["comment.doc", "comment"],
+        "comment" => &["comment"],
+        "string" => &["string"],
+        "regexp" => &["string.regex", "string"],
+        "number" => &["number"],
+        "boolean" => &["boolean"],
+        "operator" => &["operator"],
+        "decorator" | "attribute" => &["attribute"],
+        "lifetime" => &["lifetime"],
+        _ => return None,
+    };
+    names
+        .iter()
+        .copied()
+        .find(|name| syntax.highlight_id(name).is_some())
+}
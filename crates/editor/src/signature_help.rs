@@ -0,0 +1,301 @@
+use std::time::Duration;
+
+use gpui::{AnyElement, FontWeight, HighlightStyle, Size, StyledText, Task};
+use project::{SignatureHelp, SignatureHelpTrigger};
+use ui::prelude::*;
+use util::ResultExt as _;
+
+use crate::{Editor, EditorMode, EditorStyle, ShowSignatureHelp};
+
+/// How long signature help waits for the edits and cursor moves to settle before it's requested again.
+const SIGNATURE_HELP_DEBOUNCE: Duration = Duration::from_millis(75);
+
+/// The signatures of the call around the newest cursor, shown above it with the parameter at the cursor
+/// highlighted.
+///
+/// It's shown after the trigger characters declared by the language servers, like `(`, and while it's shown
+/// it's requested again after every edit and cursor move, so that it follows the parameter at the cursor through
+/// nested calls, and it's hidden once the servers no longer report any signature.
+#[derive(Default)]
+pub(crate) struct SignatureHelpState {
+    task: Option<Task<()>>,
+    signature_help: Option<SignatureHelp>,
+}
+
+impl SignatureHelpState {
+    pub(crate) fn is_shown(&self) -> bool {
+        self.signature_help.is_some()
+    }
+}
+
+impl Editor {
+    pub fn show_signature_help(&mut self, _: &ShowSignatureHelp, cx: &mut ViewContext<Self>) {
+        let trigger = SignatureHelpTrigger {
+            character: None,
+            is_retrigger: self.signature_help_state.is_shown(),
+        };
+        request_signature_help(self, trigger, false, cx);
+    }
+
+    /// Renders the shown signatures, the active one first. Each signature highlights its own active parameter.
+    pub(crate) fn render_signature_help(
+        &self,
+        style: &EditorStyle,
+        max_size: Size<Pixels>,
+        cx: &mut ViewContext<Editor>,
+    ) -> Option<AnyElement> {
+        let signature_help = self.signature_help_state.signature_help.as_ref()?;
+        let active_signature = signature_help
+            .signatures
+            .get(signature_help.active_signature)?;
+        let signatures = Some(active_signature).into_iter().chain(
+            signature_help
+                .signatures
+                .iter()
+                .enumerate()
+                .filter(|(ix, _)| *ix != signature_help.active_signature)
+                .map(|(_, signature)| signature),
+        );
+
+        let parameter_highlight = HighlightStyle {
+            color: Some(cx.theme().colors().text_accent),
+            font_weight: Some(FontWeight::BOLD),
+            ..HighlightStyle::default()
+        };
+        let element = v_flex()
+            .id("signature_help")
+            .elevation_2(cx)
+            .max_w(max_size.width)
+            .max_h(max_size.height)
+            .overflow_y_scroll()
+            .px_2()
+            .py_1()
+            .gap_1()
+            .children(signatures.map(|signature| {
+                let highlights = signature
+                    .active_parameter
+                    .and_then(|parameter| signature.parameters.get(parameter))
+                    .filter(|parameter| !parameter.is_empty())
+                    .map(|parameter| (parameter.clone(), parameter_highlight));
+                StyledText::new(signature.label.clone()).with_highlights(&style.text, highlights)
+            }))
+            .children(active_signature.documentation.clone().map(|documentation| {
+                div()
+                    .pt_1()
+                    .border_t_1()
+                    .border_color(cx.theme().colors().border_variant)
+                    .child(
+                        Label::new(documentation)
+                            .size(LabelSize::Small)
+                            .color(Color::Muted),
+                    )
+            }))
+            .into_any_element();
+        Some(element)
+    }
+}
+
+/// Shows signature help after a trigger character of the language servers of the buffer, or requests it again
+/// after any input while it's shown.
+pub(super) fn signature_help_on_input(
+    editor: &mut Editor,
+    text: &str,
+    cx: &mut ViewContext<Editor>,
+) {
+    let Some(character) = text.chars().last().map(String::from) else {
+        return;
+    };
+    let Some(project) = editor.project.as_ref() else {
+        return;
+    };
+    let Some(buffer) = editor
+        .buffer
+        .read(cx)
+        .text_anchor_for_position(editor.selections.newest_anchor().head(), cx)
+        .map(|(buffer, _)| buffer)
+    else {
+        return;
+    };
+    let triggers = project.read(cx).signature_help_triggers(&buffer, cx);
+    let is_shown = editor.signature_help_state.is_shown();
+    let trigger = if triggers.trigger_characters.contains(&character)
+        || (is_shown && triggers.retrigger_characters.contains(&character))
+    {
+        SignatureHelpTrigger {
+            character: Some(character),
+            is_retrigger: is_shown,
+        }
+    } else if is_shown {
+        SignatureHelpTrigger {
+            character: None,
+            is_retrigger: true,
+        }
+    } else {
+        return;
+    };
+    request_signature_help(editor, trigger, false, cx);
+}
+
+/// Requests the shown signature help again after the cursor moved, so that the active parameter follows it.
+pub(super) fn refresh_signature_help(editor: &mut Editor, cx: &mut ViewContext<Editor>) {
+    if editor.signature_help_state.is_shown() {
+        let trigger = SignatureHelpTrigger {
+            character: None,
+            is_retrigger: true,
+        };
+        request_signature_help(editor, trigger, true, cx);
+    }
+}
+
+/// Hides the signature help, returning whether it was shown.
+pub(super) fn hide_signature_help(editor: &mut Editor, cx: &mut ViewContext<Editor>) -> bool {
+    editor.signature_help_state.task = None;
+    let was_shown = editor.signature_help_state.signature_help.take().is_some();
+    if was_shown {
+        cx.notify();
+    }
+    was_shown
+}
+
+fn request_signature_help(
+    editor: &mut Editor,
+    trigger: SignatureHelpTrigger,
+    debounce: bool,
+    cx: &mut ViewContext<Editor>,
+) {
+    if editor.mode != EditorMode::Full {
+        return;
+    }
+    let Some(project) = editor.project.clone() else {
+        return;
+    };
+    let Some((buffer, position)) = editor
+        .buffer
+        .read(cx)
+        .text_anchor_for_position(editor.selections.newest_anchor().head(), cx)
+    else {
+        return;
+    };
+
+    editor.signature_help_state.task = Some(cx.spawn(|editor, mut cx| async move {
+        if debounce {
+            cx.background_executor()
+                .timer(SIGNATURE_HELP_DEBOUNCE)
+                .await;
+        }
+        let Some(signature_help) = project
+            .update(&mut cx, |project, cx| {
+                project.signature_help(&buffer, position, trigger, cx)
+            })
+            .log_err()
+        else {
+            return;
+        };
+        let signature_help = signature_help.await;
+        editor
+            .update(&mut cx, |editor, cx| {
+                editor.signature_help_state.signature_help = signature_help;
+                cx.notify();
+            })
+            .ok();
+    }));
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::{Arc, Mutex};
+
+    use futures::StreamExt as _;
+    use lsp::request::SignatureHelpRequest;
+
+    use crate::{
+        editor_tests::init_test, test::editor_lsp_test_context::EditorLspTestContext, Cancel,
+    };
+
+    #[gpui::test]
+    async fn test_signature_help_follows_typing(cx: &mut gpui::TestAppContext) {
+        init_test(cx, |_| {});
+        let mut cx = EditorLspTestContext::new_rust(
+            lsp::ServerCapabilities {
+                signature_help_provider: Some(lsp::SignatureHelpOptions {
+                    trigger_characters: Some(vec!["(".into()]),
+                    retrigger_characters: Some(vec![",".into()]),
+                    ..Default::default()
+                }),
+                ..Default::default()
+            },
+            cx,
+        )
+        .await;
+        cx.set_state("fn main() { fooˇ }");
+
+        let contexts = Arc::new(Mutex::new(Vec::new()));
+        let mut requests = cx.handle_request::<SignatureHelpRequest, _, _>({
+            let contexts = contexts.clone();
+            move |_, params, _| {
+                let context = params.context.unwrap();
+                contexts
+                    .lock()
+                    .unwrap()
+                    .push((context.trigger_character, context.is_retrigger));
+                let column = params.text_document_position_params.position.character;
+                async move {
+                    Ok(Some(lsp::SignatureHelp {
+                        signatures: vec![lsp::SignatureInformation {
+                            label: "fn foo(a: i32, b: i32)".into(),
+                            documentation: None,
+                            parameters: Some(vec![
+                                lsp::ParameterInformation {
+                                    label: lsp::ParameterLabel::Simple("a: i32".into()),
+                                    documentation: None,
+                                },
+                                lsp::ParameterInformation {
+                                    label: lsp::ParameterLabel::Simple("b: i32".into()),
+                                    documentation: None,
+                                },
+                            ]),
+                            active_parameter: None,
+                        }],
+                        active_signature: Some(0),
+                        active_parameter: Some(if column > 17 { 1 } else { 0 }),
+                    }))
+                }
+            }
+        });
+        let active_parameter = |cx: &mut EditorLspTestContext| {
+            cx.editor(|editor, _| {
+                editor
+                    .signature_help_state
+                    .signature_help
+                    .as_ref()
+                    .map(|help| help.signatures[help.active_signature].active_parameter)
+            })
+        };
+
+        cx.simulate_keystroke("(");
+        requests.next().await;
+        cx.run_until_parked();
+        assert_eq!(active_parameter(&mut cx), Some(Some(0)));
+
+        cx.simulate_keystroke("1");
+        requests.next().await;
+        cx.run_until_parked();
+        assert_eq!(active_parameter(&mut cx), Some(Some(0)));
+
+        cx.simulate_keystroke(",");
+        requests.next().await;
+        cx.run_until_parked();
+        assert_eq!(active_parameter(&mut cx), Some(Some(1)));
+        assert_eq!(
+            *contexts.lock().unwrap(),
+            [
+                (Some("(".to_string()), false),
+                (None, true),
+                (Some(",".to_string()), true),
+            ]
+        );
+
+        cx.update_editor(|editor, cx| editor.cancel(&Cancel, cx));
+        assert_eq!(active_parameter(&mut cx), None);
+    }
+}
@@ -0,0 +1,198 @@
+use std::{ops::Range, sync::Arc};
+
+use collections::HashMap;
+use fuzzy::{StringMatch, StringMatchCandidate};
+use gpui::UniformListScrollHandle;
+use language::{CodeLabel, Documentation, File as _, Point};
+use lsp::LanguageServerId;
+use multi_buffer::{Anchor, ToPoint as _};
+use parking_lot::{Mutex, RwLock};
+use project::Completion;
+use time::OffsetDateTime;
+use ui::ViewContext;
+use util::post_inc;
+
+use crate::{debounced_delay::DebouncedDelay, CompletionsMenu, ContextMenu, Editor, SnippetState};
+
+/// The values of the variables of the snippets inserted at the newest selection, like `$TM_FILENAME`.
+pub(super) fn snippet_variables(
+    editor: &Editor,
+    cx: &mut ViewContext<Editor>,
+) -> HashMap<&'static str, String> {
+    let mut variables = HashMap::default();
+    let snapshot = editor.buffer.read(cx).snapshot(cx);
+    let selection = editor.selections.newest::<Point>(cx);
+    let row = selection.head().row;
+    // Without a selection this is empty, so that the snippets insert their default for it.
+    variables.insert(
+        "TM_SELECTED_TEXT",
+        snapshot
+            .text_for_range(selection.start..selection.end)
+            .collect(),
+    );
+    variables.insert(
+        "TM_CURRENT_LINE",
+        snapshot
+            .text_for_range(Point::new(row, 0)..Point::new(row, snapshot.line_len(row.into())))
+            .collect(),
+    );
+    let (word_range, _) = snapshot.surrounding_word(selection.head());
+    variables.insert(
+        "TM_CURRENT_WORD",
+        snapshot.text_for_range(word_range).collect(),
+    );
+    variables.insert("TM_LINE_INDEX", row.to_string());
+    variables.insert("TM_LINE_NUMBER", (row + 1).to_string());
+
+    if let Some(file) = snapshot
+        .file_at(selection.head())
+        .and_then(|file| file.as_local())
+    {
+        let path = file.abs_path(cx);
+        if let Some(file_name) = path.file_name() {
+            variables.insert("TM_FILENAME", file_name.to_string_lossy().into_owned());
+        }
+        if let Some(file_stem) = path.file_stem() {
+            variables.insert("TM_FILENAME_BASE", file_stem.to_string_lossy().into_owned());
+        }
+        if let Some(directory) = path.parent() {
+            variables.insert("TM_DIRECTORY", directory.to_string_lossy().into_owned());
+        }
+        variables.insert("TM_FILEPATH", path.to_string_lossy().into_owned());
+        variables.insert(
+            "RELATIVE_FILEPATH",
+            file.path().to_string_lossy().into_owned(),
+        );
+        variables.insert("WORKSPACE_NAME", file.worktree_root_name(cx).into());
+    }
+    if let Some(clipboard) = cx.read_from_clipboard() {
+        variables.insert("CLIPBOARD", clipboard.text().clone());
+    }
+
+    let now = OffsetDateTime::now_utc().to_offset(cx.local_timezone());
+    variables.insert("CURRENT_YEAR", now.year().to_string());
+    variables.insert(
+        "CURRENT_YEAR_SHORT",
+        format!("{:02}", now.year().rem_euclid(100)),
+    );
+    variables.insert("CURRENT_MONTH", format!("{:02}", u8::from(now.month())));
+    variables.insert("CURRENT_MONTH_NAME", now.month().to_string());
+    variables.insert("CURRENT_DATE", format!("{:02}", now.day()));
+    variables.insert("CURRENT_DAY_NAME", now.weekday().to_string());
+    variables.insert("CURRENT_HOUR", format!("{:02}", now.hour()));
+    variables.insert("CURRENT_MINUTE", format!("{:02}", now.minute()));
+    variables.insert("CURRENT_SECOND", format!("{:02}", now.second()));
+    variables.insert("CURRENT_SECONDS_UNIX", now.unix_timestamp().to_string());
+    variables
+}
+
+/// Offers the choices of the active tabstop of the snippet, if it has some, in the completions menu.
+pub(super) fn show_snippet_choices(
+    editor: &mut Editor,
+    snippet: &SnippetState,
+    cx: &mut ViewContext<Editor>,
+) {
+    let Some(choices) = snippet.choices.get(&snippet.active_index) else {
+        return;
+    };
+    let Some(range) = snippet
+        .ranges
+        .get(snippet.active_index)
+        .and_then(|ranges| ranges.first())
+    else {
+        return;
+    };
+    let Some(buffer) = range
+        .start
+        .buffer_id
+        .and_then(|buffer_id| editor.buffer.read(cx).buffer(buffer_id))
+    else {
+        return;
+    };
+
+    let completions = choices
+        .iter()
+        .map(|choice| Completion {
+            old_range: range.start.text_anchor..range.end.text_anchor,
+            new_text: choice.clone(),
+            label: CodeLabel::plain(choice.clone(), None),
+            server_id: LanguageServerId(0),
+            documentation: Some(Documentation::Undocumented),
+            lsp_completion: Default::default(),
+            confirm: None,
+            show_new_completions_on_confirm: false,
+        })
+        .collect::<Vec<_>>();
+    let menu = CompletionsMenu {
+        id: post_inc(&mut editor.next_completion_id),
+        initial_position: range.start,
+        buffer,
+        match_candidates: choices
+            .iter()
+            .enumerate()
+            .map(|(ix, choice)| StringMatchCandidate::new(ix, choice.clone()))
+            .collect(),
+        matches: choices
+            .iter()
+            .enumerate()
+            .map(|(ix, choice)| StringMatch {
+                candidate_id: ix,
+                score: 0.,
+                positions: Vec::new(),
+                string: choice.clone(),
+            })
+            .collect(),
+        completions: Arc::new(RwLock::new(completions.into())),
+        selected_item: 0,
+        scroll_handle: UniformListScrollHandle::new(),
+        selected_completion_documentation_resolve_debounce: Arc::new(Mutex::new(
+            DebouncedDelay::new(),
+        )),
+        is_snippet_choice: true,
+        is_incomplete: false,
+        last_filter: None,
+    };
+    editor.completion_tasks.clear();
+    *editor.context_menu.write() = Some(ContextMenu::Completions(menu));
+    cx.notify();
+}
+
+/// Updates the ranges that mirror the active tabstop of the snippet through a transform, when the tabstop is left.
+pub(super) fn apply_snippet_transforms(
+    editor: &mut Editor,
+    snippet: &SnippetState,
+    cx: &mut ViewContext<Editor>,
+) {
+    let snapshot = editor.buffer.read(cx).snapshot(cx);
+    let Some(tabstop_range) = snippet
+        .ranges
+        .get(snippet.active_index)
+        .and_then(|ranges| ranges.first())
+    else {
+        return;
+    };
+    let tabstop_text = snapshot
+        .text_for_range(tabstop_range.clone())
+        .collect::<String>();
+
+    let mut edits = Vec::<(Range<Anchor>, Arc<str>)>::new();
+    for transform in &snippet.transforms {
+        if transform.tabstop != snippet.active_index {
+            continue;
+        }
+        let text: Arc<str> = transform.transform.apply(&tabstop_text).into();
+        for range in &transform.ranges {
+            let current = snapshot.text_for_range(range.clone()).collect::<String>();
+            if current != *text {
+                edits.push((range.clone(), text.clone()));
+            }
+        }
+    }
+    if edits.is_empty() {
+        return;
+    }
+    edits.sort_by_key(|(range, _)| range.start.to_point(&snapshot));
+    editor
+        .buffer
+        .update(cx, |buffer, cx| buffer.edit(edits, None, cx));
+}
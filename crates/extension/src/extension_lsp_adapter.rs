@@ -4,8 +4,8 @@ use crate::wasm_host::{
 };
 use anyhow::{anyhow, Context, Result};
 use async_trait::async_trait;
-use collections::HashMap;
-use futures::{Future, FutureExt};
+use collections::{HashMap, HashSet};
+use futures::{future::BoxFuture, Future, FutureExt};
 use gpui::AsyncAppContext;
 use language::{
     CodeLabel, HighlightId, Language, LanguageServerName, LspAdapter, LspAdapterDelegate,
@@ -165,6 +165,23 @@ impl LspAdapter for ExtensionLspAdapter {
             .unwrap_or_default()
     }
 
+    fn message_middleware(&self) -> Option<Arc<dyn lsp::MessageMiddleware>> {
+        let methods = &self
+            .extension
+            .manifest
+            .language_servers
+            .get(&self.language_server_id)?
+            .message_middleware;
+        if methods.is_empty() {
+            return None;
+        }
+        Some(Arc::new(ExtensionMessageMiddleware {
+            extension: self.extension.clone(),
+            language_server_id: self.language_server_id.clone(),
+            methods: methods.iter().cloned().collect(),
+        }))
+    }
+
     async fn initialization_options(
         self: Arc<Self>,
         delegate: &Arc<dyn LspAdapterDelegate>,
@@ -304,6 +321,70 @@ impl LspAdapter for ExtensionLspAdapter {
     }
 }
 
+/// Passes the messages of the methods that an extension declares in its manifest to the extension, for the language
+/// servers it provides.
+struct ExtensionMessageMiddleware {
+    extension: WasmExtension,
+    language_server_id: LanguageServerName,
+    methods: HashSet<String>,
+}
+
+impl lsp::MessageMiddleware for ExtensionMessageMiddleware {
+    fn intercepts(&self, method: &str) -> bool {
+        self.methods.contains(method)
+    }
+
+    fn rewrite(
+        &self,
+        message: lsp::InterceptedMessage,
+    ) -> BoxFuture<'static, Result<Option<Value>>> {
+        let extension = self.extension.clone();
+        let language_server_id = self.language_server_id.clone();
+        async move {
+            let message = wit::LanguageServerMessage {
+                direction: match message.direction {
+                    lsp::MessageDirection::ToServer => {
+                        wit::LanguageServerMessageDirection::ToServer
+                    }
+                    lsp::MessageDirection::FromServer => {
+                        wit::LanguageServerMessageDirection::FromServer
+                    }
+                },
+                kind: match message.kind {
+                    lsp::MessageKind::Request => wit::LanguageServerMessageKind::Request,
+                    lsp::MessageKind::Response => wit::LanguageServerMessageKind::Response,
+                    lsp::MessageKind::Notification => wit::LanguageServerMessageKind::Notification,
+                },
+                method: message.method,
+                payload: serde_json::to_string(&message.payload)?,
+            };
+            let payload = extension
+                .call(move |extension, store| {
+                    async move {
+                        extension
+                            .call_rewrite_language_server_message(
+                                store,
+                                &language_server_id,
+                                &message,
+                            )
+                            .await?
+                            .map_err(|e| anyhow!("{}", e))
+                    }
+                    .boxed()
+                })
+                .await?;
+            payload
+                .map(|payload| {
+                    serde_json::from_str(&payload).with_context(|| {
+                        format!("failed to parse rewritten LSP message from extension: {payload}")
+                    })
+                })
+                .transpose()
+        }
+        .boxed()
+    }
+}
+
 fn labels_from_wit(
     labels: Vec<Option<wit::CodeLabel>>,
     language: &Arc<Language>,
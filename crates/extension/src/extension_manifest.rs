@@ -110,6 +110,9 @@ pub struct LanguageServerManifestEntry {
     pub language_ids: HashMap<String, String>,
     #[serde(default)]
     pub code_action_kinds: Option<Vec<lsp::CodeActionKind>>,
+    /// The methods of the messages exchanged with the language server that the extension observes and rewrites.
+    #[serde(default)]
+    pub message_middleware: Vec<String>,
 }
 
 impl LanguageServerManifestEntry {
@@ -670,6 +670,7 @@ async fn test_extension_store_with_gleam_extension(cx: &mut TestAppContext) {
         })
         .await
         .unwrap()
+        .completions
         .into_iter()
         .map(|c| c.label.text)
         .collect::<Vec<_>>();
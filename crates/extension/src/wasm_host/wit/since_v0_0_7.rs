@@ -1,6 +1,6 @@
 use crate::wasm_host::{wit::ToWasmtimeResult, WasmState};
 use ::settings::Settings;
-use anyhow::{anyhow, bail, Result};
+use anyhow::{anyhow, bail, Context as _, Result};
 use async_compression::futures::bufread::GzipDecoder;
 use async_tar::Archive;
 use async_trait::async_trait;
@@ -92,6 +92,39 @@ impl HostWorktree for WasmState {
             .map(|path| path.to_string_lossy().to_string()))
     }
 
+    async fn execute_language_server_command(
+        &mut self,
+        delegate: Resource<Arc<dyn LspAdapterDelegate>>,
+        language_server_id: String,
+        command: String,
+        arguments: String,
+    ) -> wasmtime::Result<Result<Option<String>, String>> {
+        let delegate = self.table.get(&delegate)?.clone();
+        maybe!(async {
+            let arguments: Vec<serde_json::Value> =
+                serde_json::from_str(&arguments).context("invalid command arguments")?;
+            let response = self
+                .on_main_thread(move |cx| {
+                    async move {
+                        cx.update(|cx| {
+                            delegate.execute_language_server_command(
+                                language::LanguageServerName(language_server_id.into()),
+                                command,
+                                arguments,
+                                cx,
+                            )
+                        })?
+                        .await
+                    }
+                    .boxed_local()
+                })
+                .await?;
+            Ok(response.map(|response| response.to_string()))
+        })
+        .await
+        .to_wasmtime_result()
+    }
+
     fn drop(&mut self, _worktree: Resource<Worktree>) -> Result<()> {
         // We only ever hand out borrows of worktrees.
         Ok(())
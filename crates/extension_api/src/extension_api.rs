@@ -26,7 +26,8 @@ pub use wit::{
     zed::extension::platform::{current_platform, Architecture, Os},
     zed::extension::slash_command::{SlashCommand, SlashCommandOutput, SlashCommandOutputSection},
     CodeLabel, CodeLabelSpan, CodeLabelSpanLiteral, Command, DownloadedFileType, EnvVars,
-    LanguageServerInstallationStatus, Range, Worktree,
+    LanguageServerInstallationStatus, LanguageServerMessage, LanguageServerMessageDirection,
+    LanguageServerMessageKind, Range, Worktree,
 };
 
 // Undocumented WIT re-exports.
@@ -90,6 +91,18 @@ pub trait Extension: Send + Sync {
         Ok(None)
     }
 
+    /// Returns the payload to replace the one of the message exchanged with the language server with, if any.
+    ///
+    /// Only the messages of the methods listed in the `message_middleware` of the language server in
+    /// `extension.toml` are passed to the extension.
+    fn rewrite_language_server_message(
+        &mut self,
+        _language_server_id: &LanguageServerId,
+        _message: LanguageServerMessage,
+    ) -> Result<Option<serde_json::Value>> {
+        Ok(None)
+    }
+
     /// Returns the label for the given completion.
     fn label_for_completion(
         &self,
@@ -202,6 +215,16 @@ impl wit::Guest for Component {
             .and_then(|value| serde_json::to_string(&value).ok()))
     }
 
+    fn rewrite_language_server_message(
+        language_server_id: String,
+        message: LanguageServerMessage,
+    ) -> Result<Option<String>, String> {
+        let language_server_id = LanguageServerId(language_server_id);
+        Ok(extension()
+            .rewrite_language_server_message(&language_server_id, message)?
+            .and_then(|value| serde_json::to_string(&value).ok()))
+    }
+
     fn labels_for_completions(
         language_server_id: String,
         completions: Vec<Completion>,
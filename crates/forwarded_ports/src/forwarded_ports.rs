@@ -0,0 +1,375 @@
+mod forwarded_ports_settings;
+
+use std::sync::Arc;
+
+use anyhow::Context as _;
+use db::kvp::KEY_VALUE_STORE;
+use editor::{Editor, EditorEvent};
+use gpui::{
+    actions, Action, AppContext, AsyncWindowContext, EventEmitter, FocusHandle, FocusableView,
+    Model, Pixels, Render, Subscription, Task, View, ViewContext, VisualContext, WeakView,
+    WindowContext,
+};
+use project::{ForwardedPort, Fs, Project};
+use serde::{Deserialize, Serialize};
+use settings::Settings;
+use ui::{prelude::*, IconButton, ListItem, Tooltip};
+use util::ResultExt;
+use workspace::{
+    dock::{DockPosition, Panel, PanelEvent},
+    Workspace,
+};
+
+use forwarded_ports_settings::{ForwardedPortsDockPosition, ForwardedPortsSettings};
+
+actions!(forwarded_ports, [ToggleFocus]);
+
+const FORWARDED_PORTS_PANEL_KEY: &str = "ForwardedPortsPanel";
+
+/// A panel of the ports of a remote project that are forwarded to this machine, to forward more of them, open them in
+/// the browser or stop forwarding them.
+pub struct ForwardedPortsPanel {
+    fs: Arc<dyn Fs>,
+    project: Model<Project>,
+    width: Option<Pixels>,
+    active: bool,
+    focus_handle: FocusHandle,
+    port_editor: View<Editor>,
+    error: Option<SharedString>,
+    pending_serialization: Task<Option<()>>,
+    _subscriptions: Vec<Subscription>,
+}
+
+#[derive(Serialize, Deserialize)]
+struct SerializedForwardedPortsPanel {
+    width: Option<Pixels>,
+    active: Option<bool>,
+}
+
+pub fn init(cx: &mut AppContext) {
+    ForwardedPortsSettings::register(cx);
+
+    cx.observe_new_views(|workspace: &mut Workspace, _| {
+        workspace.register_action(|workspace, _: &ToggleFocus, cx| {
+            workspace.toggle_panel_focus::<ForwardedPortsPanel>(cx);
+        });
+    })
+    .detach();
+}
+
+impl ForwardedPortsPanel {
+    pub async fn load(
+        workspace: WeakView<Workspace>,
+        mut cx: AsyncWindowContext,
+    ) -> anyhow::Result<View<Self>> {
+        let serialized_panel = cx
+            .background_executor()
+            .spawn(async move { KEY_VALUE_STORE.read_kvp(FORWARDED_PORTS_PANEL_KEY) })
+            .await
+            .context("loading forwarded ports panel")
+            .log_err()
+            .flatten()
+            .map(|panel| serde_json::from_str::<SerializedForwardedPortsPanel>(&panel))
+            .transpose()
+            .log_err()
+            .flatten();
+
+        workspace.update(&mut cx, |workspace, cx| {
+            let panel = Self::new(workspace, cx);
+            if let Some(serialized_panel) = serialized_panel {
+                panel.update(cx, |panel, cx| {
+                    panel.width = serialized_panel.width.map(|px| px.round());
+                    panel.active = serialized_panel.active.unwrap_or(false);
+                    cx.notify();
+                });
+            }
+            panel
+        })
+    }
+
+    fn new(workspace: &mut Workspace, cx: &mut ViewContext<Workspace>) -> View<Self> {
+        let project = workspace.project().clone();
+        let fs = workspace.app_state().fs.clone();
+        cx.new_view(|cx| {
+            let port_editor = cx.new_view(|cx| {
+                let mut editor = Editor::single_line(cx);
+                editor.set_placeholder_text("Remote port, or remote:local ports", cx);
+                editor
+            });
+            let editor_subscription = cx.subscribe(&port_editor, |panel, _, event, cx| {
+                if let EditorEvent::BufferEdited = event {
+                    if panel.error.take().is_some() {
+                        cx.notify();
+                    }
+                }
+            });
+            let project_subscription = cx.observe(&project, |_, _, cx| cx.notify());
+            Self {
+                fs,
+                project,
+                width: None,
+                active: false,
+                focus_handle: cx.focus_handle(),
+                port_editor,
+                error: None,
+                pending_serialization: Task::ready(None),
+                _subscriptions: vec![editor_subscription, project_subscription],
+            }
+        })
+    }
+
+    fn serialize(&mut self, cx: &mut ViewContext<Self>) {
+        let width = self.width;
+        let active = Some(self.active);
+        self.pending_serialization = cx.background_executor().spawn(
+            async move {
+                KEY_VALUE_STORE
+                    .write_kvp(
+                        FORWARDED_PORTS_PANEL_KEY.into(),
+                        serde_json::to_string(&SerializedForwardedPortsPanel { width, active })?,
+                    )
+                    .await?;
+                anyhow::Ok(())
+            }
+            .log_err(),
+        );
+    }
+
+    /// Forwards the port typed in the editor: `8080` forwards the remote port 8080 to the same local port if it's
+    /// free, `8080:3000` forwards it to the local port 3000.
+    fn confirm(&mut self, _: &menu::Confirm, cx: &mut ViewContext<Self>) {
+        let text = self.port_editor.read(cx).text(cx);
+        let Some((remote_port, local_port)) = parse_ports(&text) else {
+            self.error = Some(format!("{:?} is not a port", text.trim()).into());
+            cx.notify();
+            return;
+        };
+        match self.project.update(cx, |project, cx| {
+            project.forward_port(remote_port, local_port, cx)
+        }) {
+            Ok(_) => {
+                self.error = None;
+                self.port_editor
+                    .update(cx, |editor, cx| editor.set_text("", cx));
+            }
+            Err(error) => self.error = Some(format!("{error:#}").into()),
+        }
+        cx.notify();
+    }
+
+    fn render_port(&self, ix: usize, port: &ForwardedPort, cx: &mut ViewContext<Self>) -> ListItem {
+        let remote_port = port.remote_port;
+        let url = format!("http://localhost:{}", port.local_port);
+        let detail = match &port.detected_in {
+            Some(label) => format!("Detected in {label}"),
+            None => "Forwarded manually".to_string(),
+        };
+        ListItem::new(("forwarded-port", ix))
+            .start_slot(Icon::new(IconName::Server).size(IconSize::Small))
+            .child(
+                v_flex()
+                    .child(Label::new(format!(
+                        "{remote_port} → localhost:{}",
+                        port.local_port
+                    )))
+                    .child(
+                        Label::new(detail)
+                            .size(LabelSize::Small)
+                            .color(Color::Muted)
+                            .single_line(),
+                    ),
+            )
+            .end_slot(
+                h_flex()
+                    .gap_1()
+                    .child(
+                        IconButton::new(("open-port", ix), IconName::ExternalLink)
+                            .icon_size(IconSize::Small)
+                            .tooltip(|cx| Tooltip::text("Open in Browser", cx))
+                            .on_click(move |_, cx| cx.open_url(&url)),
+                    )
+                    .child(
+                        IconButton::new(("stop-forwarding-port", ix), IconName::Close)
+                            .icon_size(IconSize::Small)
+                            .tooltip(|cx| Tooltip::text("Stop Forwarding", cx))
+                            .on_click(cx.listener(move |panel, _, cx| {
+                                panel.project.update(cx, |project, cx| {
+                                    project.stop_forwarding_port(remote_port, cx)
+                                });
+                            })),
+                    ),
+            )
+    }
+}
+
+/// Parses `remote` or `remote:local` ports.
+fn parse_ports(text: &str) -> Option<(u16, Option<u16>)> {
+    let text = text.trim();
+    let (remote_port, local_port) = match text.split_once(':') {
+        Some((remote_port, local_port)) => (remote_port, Some(local_port)),
+        None => (text, None),
+    };
+    let remote_port = remote_port.trim().parse().ok().filter(|port| *port != 0)?;
+    let local_port = match local_port {
+        Some(local_port) => Some(local_port.trim().parse().ok().filter(|port| *port != 0)?),
+        None => None,
+    };
+    Some((remote_port, local_port))
+}
+
+impl Panel for ForwardedPortsPanel {
+    fn persistent_name() -> &'static str {
+        "Forwarded Ports"
+    }
+
+    fn position(&self, cx: &WindowContext) -> DockPosition {
+        match ForwardedPortsSettings::get_global(cx).dock {
+            ForwardedPortsDockPosition::Left => DockPosition::Left,
+            ForwardedPortsDockPosition::Right => DockPosition::Right,
+        }
+    }
+
+    fn position_is_valid(&self, position: DockPosition) -> bool {
+        matches!(position, DockPosition::Left | DockPosition::Right)
+    }
+
+    fn set_position(&mut self, position: DockPosition, cx: &mut ViewContext<Self>) {
+        settings::update_settings_file::<ForwardedPortsSettings>(
+            self.fs.clone(),
+            cx,
+            move |settings| {
+                let dock = match position {
+                    DockPosition::Left => ForwardedPortsDockPosition::Left,
+                    DockPosition::Right | DockPosition::Bottom => ForwardedPortsDockPosition::Right,
+                };
+                settings.dock = Some(dock);
+            },
+        );
+    }
+
+    fn size(&self, cx: &WindowContext) -> Pixels {
+        self.width
+            .unwrap_or_else(|| ForwardedPortsSettings::get_global(cx).default_width)
+    }
+
+    fn set_size(&mut self, size: Option<Pixels>, cx: &mut ViewContext<Self>) {
+        self.width = size;
+        self.serialize(cx);
+        cx.notify();
+    }
+
+    fn icon(&self, cx: &WindowContext) -> Option<IconName> {
+        // Only remote projects with an SSH connection have ports to forward.
+        (ForwardedPortsSettings::get_global(cx).button
+            && self.project.read(cx).can_forward_ports(cx))
+        .then(|| IconName::Server)
+    }
+
+    fn icon_tooltip(&self, _: &WindowContext) -> Option<&'static str> {
+        Some("Forwarded Ports")
+    }
+
+    fn toggle_action(&self) -> Box<dyn Action> {
+        Box::new(ToggleFocus)
+    }
+
+    fn starts_open(&self, _: &WindowContext) -> bool {
+        self.active
+    }
+
+    fn set_active(&mut self, active: bool, cx: &mut ViewContext<Self>) {
+        self.active = active;
+        self.serialize(cx);
+    }
+}
+
+impl FocusableView for ForwardedPortsPanel {
+    fn focus_handle(&self, _cx: &AppContext) -> FocusHandle {
+        self.focus_handle.clone()
+    }
+}
+
+impl EventEmitter<PanelEvent> for ForwardedPortsPanel {}
+
+impl Render for ForwardedPortsPanel {
+    fn render(&mut self, cx: &mut ViewContext<Self>) -> impl IntoElement {
+        let panel = v_flex()
+            .id("forwarded-ports")
+            .size_full()
+            .key_context("ForwardedPortsPanel")
+            .track_focus(&self.focus_handle)
+            .child(
+                h_flex()
+                    .p_1()
+                    .child(Label::new("Forwarded Ports").size(LabelSize::Small)),
+            );
+        if !self.project.read(cx).can_forward_ports(cx) {
+            return panel.child(v_flex().p_4().child(Label::new(
+                "Ports can only be forwarded in remote projects with an SSH connection",
+            )));
+        }
+
+        let ports = self
+            .project
+            .read(cx)
+            .forwarded_ports()
+            .cloned()
+            .collect::<Vec<_>>();
+        panel
+            .on_action(cx.listener(Self::confirm))
+            .child(
+                v_flex()
+                    .p_1()
+                    .gap_1()
+                    .child(
+                        h_flex()
+                            .gap_1()
+                            .child(div().flex_1().child(self.port_editor.clone()))
+                            .child(
+                                IconButton::new("forward-port", IconName::Plus)
+                                    .icon_size(IconSize::Small)
+                                    .tooltip(|cx| Tooltip::text("Forward Port", cx))
+                                    .on_click(cx.listener(|panel, _, cx| {
+                                        panel.confirm(&menu::Confirm, cx)
+                                    })),
+                            ),
+                    )
+                    .children(
+                        self.error.clone().map(|error| {
+                            Label::new(error).size(LabelSize::Small).color(Color::Error)
+                        }),
+                    ),
+            )
+            .map(|panel| {
+                if ports.is_empty() {
+                    panel.child(
+                        v_flex()
+                            .p_4()
+                            .child(Label::new("No ports are forwarded").color(Color::Muted)),
+                    )
+                } else {
+                    panel.children(
+                        ports
+                            .iter()
+                            .enumerate()
+                            .map(|(ix, port)| self.render_port(ix, port, cx)),
+                    )
+                }
+            })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::parse_ports;
+
+    #[test]
+    fn test_parse_ports() {
+        assert_eq!(parse_ports("8080"), Some((8080, None)));
+        assert_eq!(parse_ports(" 8080:3000 "), Some((8080, Some(3000))));
+        assert_eq!(parse_ports("0"), None);
+        assert_eq!(parse_ports("8080:"), None);
+        assert_eq!(parse_ports("localhost"), None);
+        assert_eq!(parse_ports("70000"), None);
+    }
+}
@@ -0,0 +1,48 @@
+use anyhow;
+use gpui::Pixels;
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+use settings::{Settings, SettingsSources};
+
+#[derive(Clone, Debug, Serialize, Deserialize, JsonSchema, Copy, PartialEq)]
+#[serde(rename_all = "snake_case")]
+pub enum ForwardedPortsDockPosition {
+    Left,
+    Right,
+}
+
+#[derive(Deserialize, Debug, Clone, Copy, PartialEq)]
+pub struct ForwardedPortsSettings {
+    pub button: bool,
+    pub default_width: Pixels,
+    pub dock: ForwardedPortsDockPosition,
+}
+
+#[derive(Clone, Default, Serialize, Deserialize, JsonSchema, Debug)]
+pub struct ForwardedPortsSettingsContent {
+    /// Whether to show the forwarded ports panel button in the status bar.
+    ///
+    /// Default: true
+    pub button: Option<bool>,
+    /// Customise default width (in pixels) taken by the forwarded ports panel
+    ///
+    /// Default: 300
+    pub default_width: Option<f32>,
+    /// The position of the forwarded ports panel
+    ///
+    /// Default: right
+    pub dock: Option<ForwardedPortsDockPosition>,
+}
+
+impl Settings for ForwardedPortsSettings {
+    const KEY: Option<&'static str> = Some("forwarded_ports");
+
+    type FileContent = ForwardedPortsSettingsContent;
+
+    fn load(
+        sources: SettingsSources<Self::FileContent>,
+        _: &mut gpui::AppContext,
+    ) -> anyhow::Result<Self> {
+        sources.json_merge()
+    }
+}
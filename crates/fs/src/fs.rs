@@ -68,8 +68,12 @@ pub trait Fs: Send + Sync {
     }
     async fn open_sync(&self, path: &Path) -> Result<Box<dyn io::Read>>;
     async fn load(&self, path: &Path) -> Result<String>;
+    /// Loads the contents of a file without decoding them, like the files that aren't UTF-8.
+    async fn load_bytes(&self, path: &Path) -> Result<Vec<u8>>;
     async fn atomic_write(&self, path: PathBuf, text: String) -> Result<()>;
     async fn save(&self, path: &Path, text: &Rope, line_ending: LineEnding) -> Result<()>;
+    /// Saves already encoded contents to a file, creating its parent directories.
+    async fn save_bytes(&self, path: &Path, content: &[u8]) -> Result<()>;
     async fn canonicalize(&self, path: &Path) -> Result<PathBuf>;
     async fn is_file(&self, path: &Path) -> bool;
     async fn is_dir(&self, path: &Path) -> bool;
@@ -319,6 +323,12 @@ impl Fs for RealFs {
         Ok(text)
     }
 
+    async fn load_bytes(&self, path: &Path) -> Result<Vec<u8>> {
+        let path = path.to_path_buf();
+        let content = smol::unblock(|| std::fs::read(path)).await?;
+        Ok(content)
+    }
+
     async fn atomic_write(&self, path: PathBuf, data: String) -> Result<()> {
         smol::unblock(move || {
             let mut tmp_file = if cfg!(target_os = "linux") {
@@ -352,6 +362,14 @@ impl Fs for RealFs {
         Ok(())
     }
 
+    async fn save_bytes(&self, path: &Path, content: &[u8]) -> Result<()> {
+        if let Some(path) = path.parent() {
+            self.create_dir(path).await?;
+        }
+        smol::fs::write(path, content).await?;
+        Ok(())
+    }
+
     async fn canonicalize(&self, path: &Path) -> Result<PathBuf> {
         Ok(smol::fs::canonicalize(path).await?)
     }
@@ -971,6 +989,30 @@ impl FakeFs {
         })
     }
 
+    /// Moves the HEAD of the repository to the given commit, recording the paths changed since the previous
+    /// HEAD, and emits the events of a checkout: the ones of the repository and of the directories of the
+    /// changed paths. The changed files themselves are left to be written by the caller.
+    pub fn set_head_for_repo(&self, dot_git: &Path, sha: &str, changed_paths: &[&Path]) {
+        self.with_git_state(dot_git, false, |state| {
+            if let Some(old_sha) = state.head_sha.replace(sha.to_owned()) {
+                state.changed_paths.insert(
+                    (old_sha, sha.to_owned()),
+                    changed_paths.iter().map(|path| (*path).into()).collect(),
+                );
+            }
+        });
+        let work_directory = dot_git.parent().unwrap();
+        let mut dirs = changed_paths
+            .iter()
+            .filter_map(|path| Some(work_directory.join(path.parent()?)))
+            .collect::<Vec<_>>();
+        dirs.sort();
+        dirs.dedup();
+        self.state
+            .lock()
+            .emit_event(std::iter::once(dot_git.to_path_buf()).chain(dirs));
+    }
+
     pub fn set_index_for_repo(&self, dot_git: &Path, head_state: &[(&Path, String)]) {
         self.with_git_state(dot_git, true, |state| {
             state.index_contents.clear();
@@ -1433,6 +1475,10 @@ impl Fs for FakeFs {
         Ok(String::from_utf8(content.clone())?)
     }
 
+    async fn load_bytes(&self, path: &Path) -> Result<Vec<u8>> {
+        self.load_internal(path).await
+    }
+
     async fn atomic_write(&self, path: PathBuf, data: String) -> Result<()> {
         self.simulate_random_delay().await;
         let path = normalize_path(path.as_path());
@@ -1451,6 +1497,16 @@ impl Fs for FakeFs {
         Ok(())
     }
 
+    async fn save_bytes(&self, path: &Path, content: &[u8]) -> Result<()> {
+        self.simulate_random_delay().await;
+        let path = normalize_path(path);
+        if let Some(path) = path.parent() {
+            self.create_dir(path).await?;
+        }
+        self.write_file_internal(path, content.to_vec())?;
+        Ok(())
+    }
+
     async fn canonicalize(&self, path: &Path) -> Result<PathBuf> {
         let path = normalize_path(path);
         self.simulate_random_delay().await;
@@ -36,6 +36,10 @@ pub trait GitRepository: Send + Sync {
     /// Returns the SHA of the current HEAD.
     fn head_sha(&self) -> Option<String>;
 
+    /// Returns the paths of the files that differ between the trees of two commits, like the ones that
+    /// were written by a checkout from one to the other.
+    fn changed_paths(&self, old_sha: &str, new_sha: &str) -> Result<Vec<RepoPath>>;
+
     fn statuses(&self, path_prefix: &Path) -> Result<GitStatus>;
 
     fn status(&self, path: &Path) -> Option<GitFileStatus> {
@@ -123,6 +127,27 @@ impl GitRepository for RealGitRepository {
         Some(self.repository.lock().head().ok()?.target()?.to_string())
     }
 
+    fn changed_paths(&self, old_sha: &str, new_sha: &str) -> Result<Vec<RepoPath>> {
+        fn commit_tree<'a>(repo: &'a git2::Repository, sha: &str) -> Result<git2::Tree<'a>> {
+            let oid = git2::Oid::from_str(sha)?;
+            Ok(repo.find_commit(oid)?.tree()?)
+        }
+
+        let repo = self.repository.lock();
+        let old_tree = commit_tree(&repo, old_sha)?;
+        let new_tree = commit_tree(&repo, new_sha)?;
+        let diff = repo.diff_tree_to_tree(Some(&old_tree), Some(&new_tree), None)?;
+        let mut paths = diff
+            .deltas()
+            .flat_map(|delta| [delta.old_file().path(), delta.new_file().path()])
+            .flatten()
+            .map(RepoPath::from)
+            .collect::<Vec<_>>();
+        paths.sort_unstable();
+        paths.dedup();
+        Ok(paths)
+    }
+
     fn statuses(&self, path_prefix: &Path) -> Result<GitStatus> {
         let working_directory = self
             .repository
@@ -213,6 +238,9 @@ pub struct FakeGitRepositoryState {
     pub blames: HashMap<PathBuf, Blame>,
     pub worktree_statuses: HashMap<RepoPath, GitFileStatus>,
     pub branch_name: Option<String>,
+    pub head_sha: Option<String>,
+    /// The paths changed between two commits, by their SHAs.
+    pub changed_paths: HashMap<(String, String), Vec<RepoPath>>,
 }
 
 impl FakeGitRepository {
@@ -239,7 +267,17 @@ impl GitRepository for FakeGitRepository {
     }
 
     fn head_sha(&self) -> Option<String> {
-        None
+        let state = self.state.lock();
+        state.head_sha.clone()
+    }
+
+    fn changed_paths(&self, old_sha: &str, new_sha: &str) -> Result<Vec<RepoPath>> {
+        let state = self.state.lock();
+        state
+            .changed_paths
+            .get(&(old_sha.to_owned(), new_sha.to_owned()))
+            .with_context(|| format!("failed to diff commits {old_sha} and {new_sha}"))
+            .cloned()
     }
 
     fn statuses(&self, path_prefix: &Path) -> Result<GitStatus> {
@@ -24,6 +24,16 @@ pub struct GithubRelease {
 pub struct GithubReleaseAsset {
     pub name: String,
     pub browser_download_url: String,
+    /// The digest of the asset, like `sha256:<hex digest>`, which GitHub computes for the assets it receives.
+    #[serde(default)]
+    pub digest: Option<String>,
+}
+
+impl GithubReleaseAsset {
+    /// Returns the hex-encoded SHA-256 digest of the asset, if GitHub computed it.
+    pub fn sha256_digest(&self) -> Option<&str> {
+        self.digest.as_deref()?.strip_prefix("sha256:")
+    }
 }
 
 pub async fn latest_github_release(
@@ -183,6 +183,34 @@ impl Render for InlineCompletionButton {
                         ),
                 );
             }
+
+            InlineCompletionProvider::Local => {
+                let enabled = self.editor_enabled.unwrap_or_else(|| {
+                    all_language_settings.inline_completions_enabled(None, None)
+                });
+                let icon = if enabled {
+                    IconName::SparkleFilled
+                } else {
+                    IconName::Sparkle
+                };
+                let tooltip_text = format!(
+                    "Local Inline Completions ({})",
+                    all_language_settings.inline_completions.local.model
+                );
+                let this = cx.view().clone();
+
+                div().child(
+                    PopoverMenu::new("local-inline-completions")
+                        .menu(move |cx| {
+                            Some(this.update(cx, |this, cx| this.build_local_context_menu(cx)))
+                        })
+                        .anchor(AnchorCorner::BottomRight)
+                        .trigger(
+                            IconButton::new("local-inline-completions-icon", icon)
+                                .tooltip(move |cx| Tooltip::text(tooltip_text.clone(), cx)),
+                        ),
+                )
+            }
         }
     }
 }
@@ -302,6 +330,12 @@ impl InlineCompletionButton {
         })
     }
 
+    fn build_local_context_menu(&self, cx: &mut ViewContext<Self>) -> View<ContextMenu> {
+        ContextMenu::build(cx, |menu, cx| {
+            self.build_language_settings_menu(menu, cx).separator()
+        })
+    }
+
     pub fn update_enabled(&mut self, editor: View<Editor>, cx: &mut ViewContext<Self>) {
         let editor = editor.read(cx);
         let snapshot = editor.buffer().read(cx).snapshot(cx);
@@ -14,7 +14,7 @@ use crate::{
         SyntaxSnapshot, ToTreeSitterPoint,
     },
     task_context::RunnableRange,
-    LanguageScope, Outline, RunnableCapture, RunnableTag,
+    Encoding, LanguageScope, Outline, RunnableCapture, RunnableTag,
 };
 use anyhow::{anyhow, Context, Result};
 pub use clock::ReplicaId;
@@ -89,6 +89,9 @@ pub struct Buffer {
     diff_base: Option<Rope>,
     git_diff: git::diff::BufferDiff,
     file: Option<Arc<dyn File>>,
+    /// The encoding of the file, which this buffer is decoded from and
+    /// encoded to.
+    encoding: Encoding,
     /// The mtime of the file when this buffer was last loaded from
     /// or saved to disk.
     saved_mtime: Option<SystemTime>,
@@ -375,8 +378,9 @@ pub trait LocalFile: File {
     /// Returns the absolute path of this file.
     fn abs_path(&self, cx: &AppContext) -> PathBuf;
 
-    /// Loads the file's contents from disk.
-    fn load(&self, cx: &AppContext) -> Task<Result<String>>;
+    /// Loads the file's raw contents from disk, to be decoded with the
+    /// encoding of its buffer.
+    fn load_bytes(&self, cx: &AppContext) -> Task<Result<Vec<u8>>>;
 
     /// Returns true if the file should not be shared with collaborators.
     fn is_private(&self, _: &AppContext) -> bool {
@@ -598,6 +602,9 @@ impl Buffer {
             rpc::proto::LineEnding::from_i32(message.line_ending)
                 .ok_or_else(|| anyhow!("missing line_ending"))?,
         ));
+        if let Some(encoding) = message.encoding.as_deref().and_then(Encoding::from_name) {
+            this.encoding = encoding;
+        }
         this.saved_version = proto::deserialize_version(&message.saved_version);
         this.saved_mtime = message.saved_mtime.map(|time| time.into());
         Ok(this)
@@ -611,6 +618,7 @@ impl Buffer {
             base_text: self.base_text().to_string(),
             diff_base: self.diff_base.as_ref().map(|h| h.to_string()),
             line_ending: proto::serialize_line_ending(self.line_ending()) as i32,
+            encoding: Some(self.encoding.name().to_string()),
             saved_version: proto::serialize_version(&self.saved_version),
             saved_mtime: self.saved_mtime.map(|time| time.into()),
         }
@@ -705,6 +713,7 @@ impl Buffer {
             diff_base_version: 0,
             git_diff: git::diff::BufferDiff::new(),
             file,
+            encoding: Encoding::default(),
             capability,
             syntax_map: Mutex::new(SyntaxMap::new()),
             parsing_in_background: false,
@@ -759,6 +768,18 @@ impl Buffer {
         self.file.as_ref()
     }
 
+    /// The encoding of the buffer's file.
+    pub fn encoding(&self) -> Encoding {
+        self.encoding
+    }
+
+    /// Sets the encoding the buffer's file is saved with. The contents of the
+    /// buffer are left as they are, use [`Buffer::reload_with_encoding`] to
+    /// decode the file again with another encoding.
+    pub fn set_encoding(&mut self, encoding: Encoding) {
+        self.encoding = encoding;
+    }
+
     /// The version of the buffer that was last saved or reloaded from disk.
     pub fn saved_version(&self) -> &clock::Global {
         &self.saved_version
@@ -812,23 +833,56 @@ impl Buffer {
         cx.notify();
     }
 
-    /// Reloads the contents of the buffer from disk.
+    /// Reloads the contents of the buffer from disk. When they aren't valid
+    /// in the encoding of the buffer anymore, their encoding is detected again.
     pub fn reload(
         &mut self,
         cx: &mut ModelContext<Self>,
+    ) -> oneshot::Receiver<Option<Transaction>> {
+        self.reload_internal(self.encoding, true, cx)
+    }
+
+    /// Reloads the contents of the buffer from disk, decoding them with the
+    /// given encoding, which becomes the encoding of the buffer. When the
+    /// contents aren't valid in the encoding, the buffer is left unchanged
+    /// and the returned receiver is canceled.
+    pub fn reload_with_encoding(
+        &mut self,
+        encoding: Encoding,
+        cx: &mut ModelContext<Self>,
+    ) -> oneshot::Receiver<Option<Transaction>> {
+        self.reload_internal(encoding, false, cx)
+    }
+
+    fn reload_internal(
+        &mut self,
+        encoding: Encoding,
+        detect_encoding: bool,
+        cx: &mut ModelContext<Self>,
     ) -> oneshot::Receiver<Option<Transaction>> {
         let (tx, rx) = futures::channel::oneshot::channel();
         let prev_version = self.text.version();
         self.reload_task = Some(cx.spawn(|this, mut cx| async move {
-            let Some((new_mtime, new_text)) = this.update(&mut cx, |this, cx| {
+            let Some((new_mtime, new_content)) = this.update(&mut cx, |this, cx| {
                 let file = this.file.as_ref()?.as_local()?;
-                Some((file.mtime(), file.load(cx)))
+                Some((file.mtime(), file.load_bytes(cx)))
             })?
             else {
                 return Ok(());
             };
 
-            let new_text = new_text.await?;
+            let new_content = new_content.await?;
+            let (new_text, encoding) = cx
+                .background_executor()
+                .spawn(async move {
+                    match encoding.decode(&new_content) {
+                        Ok(text) => Ok((text, encoding)),
+                        // The file was rewritten in another encoding since it was loaded.
+                        Err(_) if detect_encoding => Encoding::detect(&new_content),
+                        Err(error) => Err(error),
+                    }
+                })
+                .await?;
             let diff = this
                 .update(&mut cx, |this, cx| this.diff(new_text.clone(), cx))?
                 .await;
@@ -838,6 +892,7 @@ impl Buffer {
                     this.apply_diff(diff, cx);
                     tx.send(this.finalize_last_transaction().cloned()).ok();
                     this.has_conflict = false;
+                    this.encoding = encoding;
                     this.did_reload(this.version(), this.line_ending(), new_mtime, cx);
                 } else {
                     if !diff.edits.is_empty()
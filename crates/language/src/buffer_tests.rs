@@ -703,6 +703,60 @@ async fn test_outline(cx: &mut gpui::TestAppContext) {
     }
 }
 
+#[gpui::test]
+async fn test_outline_items_for_text(cx: &mut gpui::TestAppContext) {
+    let text = r#"
+        struct Person {
+            name: String,
+        }
+
+        impl Drop for Person {
+            fn drop(&mut self) {}
+        }
+    "#
+    .unindent();
+
+    let language = Arc::new(rust_lang());
+    let buffer =
+        cx.new_model(|cx| Buffer::local(text.clone(), cx).with_language(language.clone(), cx));
+    let snapshot = buffer.update(cx, |buffer, _| buffer.snapshot());
+    let buffer_items = snapshot
+        .outline(None)
+        .unwrap()
+        .items
+        .into_iter()
+        .map(|item| {
+            (
+                item.text,
+                item.depth,
+                item.range.start.to_point(&snapshot)..item.range.end.to_point(&snapshot),
+                item.name_ranges,
+            )
+        })
+        .collect::<Vec<_>>();
+
+    let text_items = language
+        .grammar()
+        .unwrap()
+        .outline_items_for_text(&Rope::from(text.as_str()))
+        .into_iter()
+        .map(|item| (item.text, item.depth, item.range, item.name_ranges))
+        .collect::<Vec<_>>();
+    assert_eq!(text_items, buffer_items);
+    assert_eq!(
+        text_items
+            .iter()
+            .map(|(text, depth, _, _)| (text.as_str(), *depth))
+            .collect::<Vec<_>>(),
+        &[
+            ("struct Person", 0),
+            ("name", 1),
+            ("impl Drop for Person", 0),
+            ("fn drop", 1),
+        ]
+    );
+}
+
 #[gpui::test]
 async fn test_outline_nodes_with_newlines(cx: &mut gpui::TestAppContext) {
     let text = r#"
@@ -0,0 +1,344 @@
+//! The encodings of the files of buffers. The contents of files are decoded from their encoding when they're
+//! loaded, and encoded back to it when they're saved, so that the files in legacy encodings, like Shift_JIS or
+//! GBK, round-trip without being corrupted. The encoding of a file that doesn't say which it's in is detected
+//! from its contents.
+
+use std::fmt;
+
+use anyhow::{anyhow, Result};
+
+const UTF_8_BOM: &[u8] = b"\xEF\xBB\xBF";
+const UTF_16LE_BOM: &[u8] = b"\xFF\xFE";
+const UTF_16BE_BOM: &[u8] = b"\xFE\xFF";
+
+/// The legacy encodings a file that isn't valid UTF-8 is decoded with, in the order their ties are broken.
+const DETECTED_ENCODINGS: [Encoding; 5] = [
+    Encoding::ShiftJis,
+    Encoding::Gbk,
+    Encoding::EucJp,
+    Encoding::Big5,
+    Encoding::EucKr,
+];
+
+/// Some of the most frequent characters of the languages of the encodings, which the text decoded with the
+/// wrong encoding rarely contains.
+const COMMON_SIMPLIFIED_CHINESE: &str = "的一是不了在人有我他这个们中来上大为和国地到以说时要就出会可也你对生能而子那得于着下自之年过发后作里用道行所然家种事成方多经么去法学如都同现当没动面起看定天分还进好小部其些主样理心本前开但因只从想实件数据文值返回结果读取配置错误";
+const COMMON_TRADITIONAL_CHINESE: &str = "的一是不了在人有我他這個們中來上大為和國地到以說時要就出會可也你對生能而子那得於著下自之年過發後作裡用道行所然家種事成方多經麼去法學如都同現當沒動面起看定天分還進好小部其些主樣理心本前開但因只從想實件數據文值返回結果讀取設定錯誤";
+const COMMON_HANGUL: &str =
+    "이다는의에을한하가고지기서로를으리사자일도수적있인정시대어들해나그보아것없만주전부과요게면라내장경여";
+
+/// The share of control characters above which the contents of a file are considered binary.
+const MAX_CONTROL_CHARACTER_RATIO: f32 = 0.05;
+
+/// The encoding of the file of a buffer.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Hash)]
+pub enum Encoding {
+    #[default]
+    Utf8,
+    /// UTF-8 starting with a byte order mark, which is kept when the file is saved.
+    Utf8Bom,
+    /// UTF-16 in little endian, starting with a byte order mark.
+    Utf16Le,
+    /// UTF-16 in big endian, starting with a byte order mark.
+    Utf16Be,
+    /// The superset of Latin-1 used by Windows for western European languages.
+    Windows1252,
+    Windows1250,
+    Windows1251,
+    Iso8859_2,
+    Iso8859_15,
+    Koi8R,
+    ShiftJis,
+    EucJp,
+    Gbk,
+    Gb18030,
+    Big5,
+    EucKr,
+}
+
+impl Encoding {
+    pub const ALL: [Self; 16] = [
+        Self::Utf8,
+        Self::Utf8Bom,
+        Self::Utf16Le,
+        Self::Utf16Be,
+        Self::Windows1252,
+        Self::Windows1250,
+        Self::Windows1251,
+        Self::Iso8859_2,
+        Self::Iso8859_15,
+        Self::Koi8R,
+        Self::ShiftJis,
+        Self::EucJp,
+        Self::Gbk,
+        Self::Gb18030,
+        Self::Big5,
+        Self::EucKr,
+    ];
+
+    pub fn name(self) -> &'static str {
+        match self {
+            Self::Utf8 => "UTF-8",
+            Self::Utf8Bom => "UTF-8 with BOM",
+            Self::Utf16Le => "UTF-16 LE",
+            Self::Utf16Be => "UTF-16 BE",
+            Self::Windows1252 => "Windows-1252",
+            Self::Windows1250 => "Windows-1250",
+            Self::Windows1251 => "Windows-1251",
+            Self::Iso8859_2 => "ISO-8859-2",
+            Self::Iso8859_15 => "ISO-8859-15",
+            Self::Koi8R => "KOI8-R",
+            Self::ShiftJis => "Shift_JIS",
+            Self::EucJp => "EUC-JP",
+            Self::Gbk => "GBK",
+            Self::Gb18030 => "GB18030",
+            Self::Big5 => "Big5",
+            Self::EucKr => "EUC-KR",
+        }
+    }
+
+    /// Returns the encoding with the given name, or with the given label, like `latin1` or `sjis`.
+    pub fn from_name(name: &str) -> Option<Self> {
+        if let Some(encoding) = Self::ALL
+            .into_iter()
+            .find(|encoding| encoding.name().eq_ignore_ascii_case(name))
+        {
+            return Some(encoding);
+        }
+        let encoding = encoding_rs::Encoding::for_label(name.as_bytes())?;
+        Self::ALL
+            .into_iter()
+            .filter(|encoding| *encoding != Self::Utf8Bom)
+            .find(|candidate| candidate.encoding_rs() == encoding)
+    }
+
+    fn encoding_rs(self) -> &'static encoding_rs::Encoding {
+        match self {
+            Self::Utf8 | Self::Utf8Bom => encoding_rs::UTF_8,
+            Self::Utf16Le => encoding_rs::UTF_16LE,
+            Self::Utf16Be => encoding_rs::UTF_16BE,
+            Self::Windows1252 => encoding_rs::WINDOWS_1252,
+            Self::Windows1250 => encoding_rs::WINDOWS_1250,
+            Self::Windows1251 => encoding_rs::WINDOWS_1251,
+            Self::Iso8859_2 => encoding_rs::ISO_8859_2,
+            Self::Iso8859_15 => encoding_rs::ISO_8859_15,
+            Self::Koi8R => encoding_rs::KOI8_R,
+            Self::ShiftJis => encoding_rs::SHIFT_JIS,
+            Self::EucJp => encoding_rs::EUC_JP,
+            Self::Gbk => encoding_rs::GBK,
+            Self::Gb18030 => encoding_rs::GB18030,
+            Self::Big5 => encoding_rs::BIG5,
+            Self::EucKr => encoding_rs::EUC_KR,
+        }
+    }
+
+    fn bom(self) -> &'static [u8] {
+        match self {
+            Self::Utf8Bom => UTF_8_BOM,
+            Self::Utf16Le => UTF_16LE_BOM,
+            Self::Utf16Be => UTF_16BE_BOM,
+            _ => &[],
+        }
+    }
+
+    /// Returns the encoding indicated by the byte order mark the contents start with, if any.
+    pub fn for_bom(content: &[u8]) -> Option<Self> {
+        [Self::Utf8Bom, Self::Utf16Le, Self::Utf16Be]
+            .into_iter()
+            .find(|encoding| content.starts_with(encoding.bom()))
+    }
+
+    /// Decodes the contents of a file with the encoding they most likely are in, returning it.
+    ///
+    /// The files starting with a byte order mark are decoded with its encoding, and the valid UTF-8 ones as UTF-8.
+    /// The others are decoded with each of the multi-byte legacy encodings they're valid in, and the decoded text
+    /// that looks the most like the language of the encoding wins. The files that don't look like any of them, like
+    /// the Latin-1 ones, are decoded as Windows-1252 when they look like text, and fail to decode as UTF-8
+    /// otherwise, like the binary files.
+    pub fn detect(content: &[u8]) -> Result<(String, Self)> {
+        if let Some(encoding) = Self::for_bom(content) {
+            if let Ok(text) = encoding.decode(content) {
+                return Ok((text, encoding));
+            }
+        }
+        let utf8_error = match std::str::from_utf8(content) {
+            Ok(text) => return Ok((text.to_string(), Self::Utf8)),
+            Err(error) => error,
+        };
+
+        let mut best = None::<(String, Self, isize)>;
+        for encoding in DETECTED_ENCODINGS {
+            let Ok(text) = encoding.decode(content) else {
+                continue;
+            };
+            let score = text_score(&text, encoding);
+            if score > 0 && best.as_ref().map_or(true, |(_, _, best)| score > *best) {
+                best = Some((text, encoding, score));
+            }
+        }
+        if let Some((text, encoding, _)) = best {
+            return Ok((text, encoding));
+        }
+        if !looks_like_text(content) {
+            return Err(utf8_error.into());
+        }
+        Ok((Self::Windows1252.decode(content)?, Self::Windows1252))
+    }
+
+    /// Decodes the contents of a file, failing when they aren't valid in the encoding instead of replacing the
+    /// malformed sequences, which would corrupt the file when it's saved.
+    pub fn decode(self, content: &[u8]) -> Result<String> {
+        let content = content.strip_prefix(self.bom()).unwrap_or(content);
+        let text = self
+            .encoding_rs()
+            .decode_without_bom_handling_and_without_replacement(content)
+            .ok_or_else(|| anyhow!("the file is not valid {self}"))?;
+        Ok(text.into_owned())
+    }
+
+    /// Encodes the text of a buffer, failing when it contains characters the encoding can't represent.
+    pub fn encode(self, text: &str) -> Result<Vec<u8>> {
+        let mut content = self.bom().to_vec();
+        match self {
+            Self::Utf8 | Self::Utf8Bom => content.extend_from_slice(text.as_bytes()),
+            // The encoders of UTF-16 encode to UTF-8, as required by the web.
+            Self::Utf16Le => content.extend(text.encode_utf16().flat_map(u16::to_le_bytes)),
+            Self::Utf16Be => content.extend(text.encode_utf16().flat_map(u16::to_be_bytes)),
+            _ => {
+                let (encoded, _, has_unmappable_characters) = self.encoding_rs().encode(text);
+                if has_unmappable_characters {
+                    return Err(anyhow!(
+                        "the text contains characters that can't be encoded in {self}"
+                    ));
+                }
+                content.extend_from_slice(&encoded);
+            }
+        }
+        Ok(content)
+    }
+}
+
+/// Whether the contents look like text rather than binary data: they contain no NUL bytes, and few of the
+/// control characters that text files don't contain.
+fn looks_like_text(content: &[u8]) -> bool {
+    if content.contains(&0) {
+        return false;
+    }
+    let control_characters = content
+        .iter()
+        .filter(|&&byte| {
+            byte.is_ascii_control() && !matches!(byte, b'\t' | b'\n' | b'\r' | b'\x0C')
+        })
+        .count();
+    control_characters as f32 <= content.len() as f32 * MAX_CONTROL_CHARACTER_RATIO
+}
+
+/// Scores how much the text decoded with an encoding looks like the language of the encoding: its most common
+/// characters and the kana of Japanese count the most, the hangul of Korean, the ideographs of the other
+/// languages and the CJK punctuation count for it, and the other non-ASCII characters, like the hanja or the
+/// halfwidth katakana the wrong encodings tend to decode to, count against it.
+fn text_score(text: &str, encoding: Encoding) -> isize {
+    let is_japanese = matches!(encoding, Encoding::ShiftJis | Encoding::EucJp);
+    let is_korean = encoding == Encoding::EucKr;
+    let common_characters = match encoding {
+        Encoding::Gbk => COMMON_SIMPLIFIED_CHINESE,
+        Encoding::Big5 => COMMON_TRADITIONAL_CHINESE,
+        Encoding::EucKr => COMMON_HANGUL,
+        _ => "",
+    };
+    text.chars()
+        .filter(|character| !character.is_ascii())
+        .map(|character| match character {
+            _ if common_characters.contains(character) => 3,
+            '\u{3040}'..='\u{30FF}' if is_japanese => 2,
+            '\u{AC00}'..='\u{D7AF}' if is_korean => 1,
+            '\u{4E00}'..='\u{9FFF}' if !is_korean => 1,
+            '\u{3000}'..='\u{303F}' | '\u{FF01}'..='\u{FF60}' => 1,
+            _ => -1,
+        })
+        .sum()
+}
+
+impl fmt::Display for Encoding {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.name())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_round_trip() {
+        let text = "日本語のテキスト\n";
+        for encoding in [
+            Encoding::Utf8,
+            Encoding::Utf8Bom,
+            Encoding::Utf16Le,
+            Encoding::Utf16Be,
+            Encoding::ShiftJis,
+            Encoding::EucJp,
+            Encoding::Gb18030,
+        ] {
+            let content = encoding.encode(text).unwrap();
+            assert_eq!(
+                Encoding::for_bom(&content).is_some(),
+                !encoding.bom().is_empty()
+            );
+            assert_eq!(encoding.decode(&content).unwrap(), text, "{encoding}");
+        }
+
+        assert_eq!(Encoding::Windows1252.encode("café").unwrap(), b"caf\xE9");
+        assert!(Encoding::Windows1252.encode("日本").is_err());
+        assert!(Encoding::ShiftJis.decode(b"caf\xE9").is_err());
+    }
+
+    #[test]
+    fn test_from_name() {
+        assert_eq!(
+            Encoding::from_name("utf-8 with bom"),
+            Some(Encoding::Utf8Bom)
+        );
+        assert_eq!(Encoding::from_name("latin1"), Some(Encoding::Windows1252));
+        assert_eq!(Encoding::from_name("sjis"), Some(Encoding::ShiftJis));
+        assert_eq!(Encoding::from_name("utf8"), Some(Encoding::Utf8));
+        assert_eq!(Encoding::from_name("nope"), None);
+    }
+
+    #[test]
+    fn test_detect() {
+        let text = "// 日本語のコメント\nfn main() {}\n";
+        for encoding in [
+            Encoding::Utf8,
+            Encoding::Utf8Bom,
+            Encoding::Utf16Le,
+            Encoding::ShiftJis,
+            Encoding::EucJp,
+        ] {
+            let content = encoding.encode(text).unwrap();
+            assert_eq!(
+                Encoding::detect(&content).unwrap(),
+                (text.to_string(), encoding)
+            );
+        }
+
+        let text = "// 中文注释\n";
+        assert_eq!(
+            Encoding::detect(&Encoding::Gbk.encode(text).unwrap()).unwrap(),
+            (text.to_string(), Encoding::Gbk)
+        );
+        let text = "// 한국어 주석\n";
+        assert_eq!(
+            Encoding::detect(&Encoding::EucKr.encode(text).unwrap()).unwrap(),
+            (text.to_string(), Encoding::EucKr)
+        );
+        assert_eq!(
+            Encoding::detect(b"caf\xE9 cr\xE8me\n").unwrap(),
+            ("café crème\n".to_string(), Encoding::Windows1252)
+        );
+        // The binary files aren't decoded as Windows-1252.
+        assert!(Encoding::detect(b"\x7FELF\x02\x01\x01\x00\xE9").is_err());
+        assert!(Encoding::detect(b"\x1B\x02\x03\x04\xE9\x05\x06").is_err());
+    }
+}
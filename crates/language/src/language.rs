@@ -8,11 +8,13 @@
 //! Notably we do *not* assign a single language to a single file; in real world a single file can consist of multiple programming languages - HTML is a good example of that - and `language` crate tends to reflect that status quo in its API.
 mod buffer;
 mod diagnostic_set;
+mod encoding;
 mod highlight_map;
 mod language_registry;
 pub mod language_settings;
 mod outline;
 pub mod proto;
+mod server_artifact;
 mod syntax_map;
 mod task_context;
 
@@ -56,7 +58,7 @@ use std::{
         Arc,
     },
 };
-use syntax_map::{QueryCursorHandle, SyntaxSnapshot};
+use syntax_map::{QueryCursorHandle, SyntaxSnapshot, TextProvider};
 use task::RunnableTag;
 pub use task_context::{ContextProvider, RunnableRange};
 use theme::SyntaxTheme;
@@ -66,12 +68,17 @@ use util::serde::default_true;
 pub use buffer::Operation;
 pub use buffer::*;
 pub use diagnostic_set::DiagnosticEntry;
+pub use encoding::Encoding;
 pub use language_registry::{
     LanguageNotFound, LanguageQueries, LanguageRegistry, LanguageServerBinaryStatus,
     PendingLanguageServer, QUERY_FILENAME_PREFIXES,
 };
 pub use lsp::LanguageServerId;
 pub use outline::{render_item, Outline, OutlineItem};
+pub use server_artifact::{
+    cached_server_artifact, install_server_artifact, ArtifactChecksum, ArtifactDownloadSettings,
+    ArtifactFormat, ServerArtifact,
+};
 pub use syntax_map::{OwnedSyntaxLayer, SyntaxLayer};
 pub use text::{AnchorRangeExt, LineEnding};
 pub use tree_sitter::{Node, Parser, Tree, TreeCursor};
@@ -140,6 +147,48 @@ pub trait ToLspPosition {
 #[derive(Clone, Debug, PartialEq, Eq, PartialOrd, Ord, Hash, Deserialize, Serialize)]
 pub struct LanguageServerName(pub Arc<str>);
 
+/// Where the instances of a language server are rooted. Servers that only understand a single project, like `gopls`
+/// with Go modules, are started once for each project root of a worktree rather than once for the worktree.
+#[derive(Clone, Debug, Default, PartialEq, Eq, Deserialize, Serialize, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum LanguageServerRoots {
+    /// One instance for each worktree, rooted at the worktree.
+    #[default]
+    Worktree,
+    /// Rooted at the nearest directory above the buffer that contains the first of the marker files that one of
+    /// its ancestors contains, like `["go.work", "go.mod"]`.
+    Nearest(Vec<String>),
+    /// Rooted at the outermost directory above the buffer that contains one of the marker files, like the
+    /// `package.json` at the root of a monorepo.
+    Outermost(Vec<String>),
+}
+
+impl LanguageServerRoots {
+    /// The root, relative to the worktree, of the server for the given directory of the worktree, given whether
+    /// a path of the worktree exists. Directories without markers are served from the root of the worktree.
+    pub fn root_for_directory(
+        &self,
+        directory: &Path,
+        exists: impl Fn(&Path) -> bool,
+    ) -> Arc<Path> {
+        let contains_marker =
+            |dir: &Path, markers: &[String]| markers.iter().any(|marker| exists(&dir.join(marker)));
+        let root = match self {
+            Self::Worktree => None,
+            Self::Nearest(markers) => markers.iter().find_map(|marker| {
+                directory
+                    .ancestors()
+                    .find(|dir| contains_marker(dir, std::slice::from_ref(marker)))
+            }),
+            Self::Outermost(markers) => directory
+                .ancestors()
+                .filter(|dir| contains_marker(dir, markers))
+                .last(),
+        };
+        root.map_or_else(|| Arc::from(Path::new("")), Arc::from)
+    }
+}
+
 #[derive(Debug, Clone, PartialEq, Eq, Hash)]
 pub struct Location {
     pub buffer: Model<Buffer>,
@@ -154,6 +203,7 @@ pub struct CachedLspAdapter {
     pub disk_based_diagnostic_sources: Vec<String>,
     pub disk_based_diagnostics_progress_token: Option<String>,
     language_ids: HashMap<String, String>,
+    pub roots: LanguageServerRoots,
     pub adapter: Arc<dyn LspAdapter>,
     pub reinstall_attempt_count: AtomicU64,
     /// Indicates whether this language server is the primary language server
@@ -169,12 +219,14 @@ impl CachedLspAdapter {
         let disk_based_diagnostic_sources = adapter.disk_based_diagnostic_sources();
         let disk_based_diagnostics_progress_token = adapter.disk_based_diagnostics_progress_token();
         let language_ids = adapter.language_ids();
+        let roots = adapter.roots();
 
         Arc::new(CachedLspAdapter {
             name,
             disk_based_diagnostic_sources,
             disk_based_diagnostics_progress_token,
             language_ids,
+            roots,
             adapter,
             is_primary,
             cached_binary: Default::default(),
@@ -268,9 +320,19 @@ impl CachedLspAdapter {
 pub trait LspAdapterDelegate: Send + Sync {
     fn show_notification(&self, message: &str, cx: &mut AppContext);
     fn http_client(&self) -> Arc<dyn HttpClient>;
+    fn artifact_download_settings(&self) -> ArtifactDownloadSettings;
     fn worktree_id(&self) -> u64;
     fn worktree_root_path(&self) -> &Path;
     fn update_status(&self, language: LanguageServerName, status: LanguageServerBinaryStatus);
+    /// Invokes a `workspace/executeCommand` command on the language server with the name running in the
+    /// worktree.
+    fn execute_language_server_command(
+        &self,
+        server_name: LanguageServerName,
+        command: String,
+        arguments: Vec<serde_json::Value>,
+        cx: &mut AppContext,
+    ) -> Task<Result<Option<serde_json::Value>>>;
 
     async fn which(&self, command: &OsStr) -> Option<PathBuf>;
     async fn shell_env(&self) -> HashMap<String, String>;
@@ -480,6 +542,21 @@ pub trait LspAdapter: 'static + Send + Sync {
         Ok(serde_json::json!({}))
     }
 
+    /// Returns the configuration of the language server for a folder of the workspace whose local settings
+    /// configure the server differently, given the `settings` of the server that apply to that folder.
+    ///
+    /// By default, the settings are merged over the [`LspAdapter::workspace_configuration`].
+    async fn scoped_workspace_configuration(
+        self: Arc<Self>,
+        delegate: &Arc<dyn LspAdapterDelegate>,
+        settings: Value,
+        cx: &mut AsyncAppContext,
+    ) -> Result<Value> {
+        let mut configuration = self.workspace_configuration(delegate, cx).await?;
+        util::merge_json_value_into(settings, &mut configuration);
+        Ok(configuration)
+    }
+
     /// Returns a list of code actions supported by a given LspAdapter
     fn code_action_kinds(&self) -> Option<Vec<CodeActionKind>> {
         Some(vec![
@@ -503,6 +580,16 @@ pub trait LspAdapter: 'static + Send + Sync {
         Default::default()
     }
 
+    /// Where the instances of the server are rooted, which the `roots` of its settings override.
+    fn roots(&self) -> LanguageServerRoots {
+        LanguageServerRoots::Worktree
+    }
+
+    /// The middleware that observes and rewrites the messages exchanged with the instances of the server, if any.
+    fn message_middleware(&self) -> Option<Arc<dyn lsp::MessageMiddleware>> {
+        None
+    }
+
     #[cfg(any(test, feature = "test-support"))]
     fn as_fake(&self) -> Option<&FakeLspAdapter> {
         None
@@ -746,6 +833,7 @@ pub struct FakeLspAdapter {
     pub disk_based_diagnostics_sources: Vec<String>,
     pub prettier_plugins: Vec<&'static str>,
     pub language_server_binary: LanguageServerBinary,
+    pub roots: LanguageServerRoots,
 }
 
 /// Configuration of handling bracket pairs for a given language.
@@ -1517,6 +1605,87 @@ impl Grammar {
         })
     }
 
+    /// Returns the outline items of a text that isn't open in a buffer, like
+    /// [`BufferSnapshot::outline`] without the items of the injected languages
+    /// and the syntax highlighting.
+    pub fn outline_items_for_text(&self, text: &Rope) -> Vec<OutlineItem<Point>> {
+        let Some(config) = self.outline_config.as_ref() else {
+            return Vec::new();
+        };
+        let tree = self.parse_text(text, None);
+        let mut cursor = QueryCursorHandle::new();
+        let mut stack = Vec::<Range<usize>>::new();
+        let mut items = Vec::new();
+        for mat in cursor.matches(&config.query, tree.root_node(), TextProvider(text)) {
+            let Some(item_node) = mat
+                .captures
+                .iter()
+                .find(|capture| capture.index == config.item_capture_ix)
+                .map(|capture| capture.node)
+            else {
+                continue;
+            };
+
+            let mut text_ranges = Vec::new();
+            for capture in mat.captures {
+                let is_name = if capture.index == config.name_capture_ix {
+                    true
+                } else if Some(capture.index) == config.context_capture_ix {
+                    false
+                } else {
+                    continue;
+                };
+                let mut range = capture.node.byte_range();
+                let start = capture.node.start_position();
+                if capture.node.end_position().row > start.row {
+                    range.end =
+                        range.start + text.line_len(start.row as u32) as usize - start.column;
+                }
+                if !range.is_empty() {
+                    text_ranges.push((range, is_name));
+                }
+            }
+            if text_ranges.is_empty() {
+                continue;
+            }
+
+            let mut item_text = String::new();
+            let mut name_ranges = Vec::new();
+            let mut last_range_end = 0;
+            for (range, is_name) in text_ranges {
+                if !item_text.is_empty() && range.start > last_range_end {
+                    item_text.push(' ');
+                }
+                last_range_end = range.end;
+                if is_name {
+                    let mut start = item_text.len();
+                    if !name_ranges.is_empty() {
+                        start -= 1;
+                    }
+                    name_ranges.push(start..item_text.len() + range.len());
+                }
+                item_text.extend(text.chunks_in_range(range));
+            }
+
+            let item_range = item_node.byte_range();
+            while stack.last().map_or(false, |prev_range| {
+                prev_range.start > item_range.start || prev_range.end < item_range.end
+            }) {
+                stack.pop();
+            }
+            stack.push(item_range.clone());
+
+            items.push(OutlineItem {
+                depth: stack.len() - 1,
+                range: text.offset_to_point(item_range.start)..text.offset_to_point(item_range.end),
+                text: item_text,
+                highlight_ranges: Vec::new(),
+                name_ranges,
+            });
+        }
+        items
+    }
+
     pub fn highlight_map(&self) -> HighlightMap {
         self.highlight_map.lock().clone()
     }
@@ -1598,6 +1767,7 @@ impl Default for FakeLspAdapter {
                 arguments: vec![],
                 env: Default::default(),
             },
+            roots: LanguageServerRoots::Worktree,
         }
     }
 }
@@ -1658,6 +1828,10 @@ impl LspAdapter for FakeLspAdapter {
         self.disk_based_diagnostics_progress_token.clone()
     }
 
+    fn roots(&self) -> LanguageServerRoots {
+        self.roots.clone()
+    }
+
     async fn initialization_options(
         self: Arc<Self>,
         _: &Arc<dyn LspAdapterDelegate>,
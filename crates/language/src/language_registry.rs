@@ -833,17 +833,16 @@ impl LanguageRegistry {
                 }
 
                 drop(this);
-                Ok((
-                    lsp::LanguageServer::new(
-                        stderr_capture,
-                        server_id,
-                        binary,
-                        &root_path,
-                        adapter.code_action_kinds(),
-                        cx,
-                    )?,
-                    options,
-                ))
+                let server = lsp::LanguageServer::new(
+                    stderr_capture,
+                    server_id,
+                    binary,
+                    &root_path,
+                    adapter.code_action_kinds(),
+                    cx,
+                )?;
+                server.set_message_middleware(adapter.adapter.message_middleware());
+                Ok((server, options))
             }
         });
 
@@ -82,6 +82,9 @@ pub struct LanguageSettings {
     pub indent_guides: IndentGuideSettings,
     /// Whether or not to perform a buffer format before saving.
     pub format_on_save: FormatOnSave,
+    /// Whether to only format the lines changed since the last Git commit when saving,
+    /// leaving the untouched code as it is.
+    pub format_changed_lines_only: bool,
     /// Whether or not to remove any trailing whitespace from lines of a buffer
     /// before saving it.
     pub remove_trailing_whitespace_on_save: bool,
@@ -118,8 +121,16 @@ pub struct LanguageSettings {
     pub always_treat_brackets_as_autoclosed: bool,
     /// Which code actions to run on save
     pub code_actions_on_format: HashMap<String, bool>,
+    /// The kinds of code actions to run on save, in order, before formatting.
+    pub code_actions_on_save: Vec<CodeActionOnSave>,
     /// Whether to perform linked edits
     pub linked_edits: bool,
+    /// Whether to highlight the semantic tokens provided by the language servers.
+    pub semantic_tokens: bool,
+    /// Whether to build the outline from the document symbols of the language servers, rather than from tree-sitter.
+    pub outline_from_language_server: bool,
+    /// The external linters that check the buffers as they change.
+    pub linters: Vec<ExternalLinter>,
 }
 
 impl LanguageSettings {
@@ -177,6 +188,8 @@ pub enum InlineCompletionProvider {
     #[default]
     Copilot,
     Supermaven,
+    /// A model served locally over HTTP, e.g. by Ollama or llama.cpp, configured in `inline_completions.local`.
+    Local,
 }
 
 /// The settings for inline completions, such as [GitHub Copilot](https://github.com/features/copilot)
@@ -187,6 +200,79 @@ pub struct InlineCompletionSettings {
     pub provider: InlineCompletionProvider,
     /// A list of globs representing files that inline completions should be disabled for.
     pub disabled_globs: Vec<GlobMatcher>,
+    /// The local model used by the `local` provider.
+    pub local: LocalInlineCompletionSettings,
+}
+
+/// The HTTP API of a local completion server.
+#[derive(Copy, Clone, Debug, Default, Eq, PartialEq, Serialize, Deserialize, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum LocalCompletionApi {
+    /// Ollama's `/api/generate`, with the text after the cursor as the suffix.
+    #[default]
+    Ollama,
+    /// The `/infill` endpoint of the llama.cpp server.
+    LlamaCpp,
+}
+
+/// The settings of the local model that serves inline completions.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize, JsonSchema)]
+pub struct LocalInlineCompletionSettings {
+    /// The API of the server.
+    ///
+    /// Default: ollama
+    #[serde(default)]
+    pub api: LocalCompletionApi,
+    /// The URL of the server.
+    ///
+    /// Default: "http://localhost:11434"
+    #[serde(default = "LocalInlineCompletionSettings::default_api_url")]
+    pub api_url: String,
+    /// The name of the model. Only used by Ollama, llama.cpp serves a single model.
+    ///
+    /// Default: "codellama:7b-code"
+    #[serde(default = "LocalInlineCompletionSettings::default_model")]
+    pub model: String,
+    /// The maximum number of tokens of a completion.
+    ///
+    /// Default: 128
+    #[serde(default = "LocalInlineCompletionSettings::default_max_tokens")]
+    pub max_tokens: u32,
+    /// How many characters before and after the cursor are sent to the model.
+    ///
+    /// Default: 4096
+    #[serde(default = "LocalInlineCompletionSettings::default_context_length")]
+    pub context_length: usize,
+}
+
+impl Default for LocalInlineCompletionSettings {
+    fn default() -> Self {
+        Self {
+            api: LocalCompletionApi::default(),
+            api_url: Self::default_api_url(),
+            model: Self::default_model(),
+            max_tokens: Self::default_max_tokens(),
+            context_length: Self::default_context_length(),
+        }
+    }
+}
+
+impl LocalInlineCompletionSettings {
+    fn default_api_url() -> String {
+        "http://localhost:11434".into()
+    }
+
+    fn default_model() -> String {
+        "codellama:7b-code".into()
+    }
+
+    fn default_max_tokens() -> u32 {
+        128
+    }
+
+    fn default_context_length() -> usize {
+        4096
+    }
 }
 
 /// The settings for all languages.
@@ -256,6 +342,16 @@ pub struct LanguageSettingsContent {
     /// Default: on
     #[serde(default)]
     pub format_on_save: Option<FormatOnSave>,
+    /// Whether to only format the lines changed since the last Git commit when saving,
+    /// leaving the untouched code as it is.
+    ///
+    /// Only language servers that can format ranges support it: with the `auto` formatter
+    /// the language server is used, and the other formatters format the whole buffer.
+    /// Files that Git does not track are formatted entirely.
+    ///
+    /// Default: false
+    #[serde(default)]
+    pub format_changed_lines_only: Option<bool>,
     /// Whether or not to remove any trailing whitespace from lines of a buffer
     /// before saving it.
     ///
@@ -335,11 +431,29 @@ pub struct LanguageSettingsContent {
     ///
     /// Default: {} (or {"source.organizeImports": true} for Go).
     pub code_actions_on_format: Option<HashMap<String, bool>>,
+    /// The kinds of code actions to run on save, one after the other in their order, before formatting. They
+    /// run whether formatting on save is on or off, but not when saving without formatting.
+    ///
+    /// Default: []
+    pub code_actions_on_save: Option<Vec<CodeActionOnSave>>,
     /// Whether to perform linked edits of associated ranges, if the language server supports it.
     /// For example, when editing opening <html> tag, the contents of the closing </html> tag will be edited as well.
     ///
     /// Default: true
     pub linked_edits: Option<bool>,
+    /// Whether to refine the syntax highlighting with the semantic tokens, if the language server provides them.
+    ///
+    /// Default: false
+    pub semantic_tokens: Option<bool>,
+    /// Whether to build the outline panel and the breadcrumbs from the document symbols of the language server,
+    /// rather than from the tree-sitter outline, for languages whose outline queries are missing or incomplete.
+    ///
+    /// Default: false
+    pub outline_from_language_server: Option<bool>,
+    /// The external linters that check the buffers as they change, reading their unsaved contents from stdin.
+    ///
+    /// Default: []
+    pub linters: Option<Vec<ExternalLinter>>,
 }
 
 /// The contents of the inline completion settings.
@@ -348,6 +462,9 @@ pub struct InlineCompletionSettingsContent {
     /// A list of globs representing files that inline completions should be disabled for.
     #[serde(default)]
     pub disabled_globs: Option<Vec<String>>,
+    /// The local model used by the `local` provider.
+    #[serde(default)]
+    pub local: Option<LocalInlineCompletionSettings>,
 }
 
 /// The settings for enabling/disabling features.
@@ -395,6 +512,65 @@ pub enum FormatOnSave {
     CodeActions(HashMap<String, bool>),
 }
 
+/// A kind of code actions that the language servers of a buffer run when it's saved.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq, JsonSchema)]
+pub struct CodeActionOnSave {
+    /// The kind of the code actions, like `source.organizeImports` or `source.fixAll.eslint`.
+    pub kind: String,
+    /// How long to wait for the code actions, in milliseconds, before saving without them.
+    #[serde(default = "default_code_action_on_save_timeout_ms")]
+    pub timeout_ms: u64,
+}
+
+fn default_code_action_on_save_timeout_ms() -> u64 {
+    1000
+}
+
+/// An external program that checks a buffer, reading its contents from stdin.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq, JsonSchema)]
+pub struct ExternalLinter {
+    /// The external program to run.
+    pub command: Arc<str>,
+    /// The arguments to pass to the program, where `{buffer_path}` is replaced with the path of the buffer,
+    /// `{buffer_relative_path}` with its path relative to its worktree and `{worktree_path}` with the path of
+    /// its worktree.
+    #[serde(default)]
+    pub arguments: Arc<[String]>,
+    /// How to find the problems in the output of the program, when its output is matched against problem
+    /// matchers. Problems are reported for the buffer whatever file they name, since the program only checks it.
+    #[serde(default)]
+    pub problem_matchers: Vec<task::ProblemMatcher>,
+    /// The format of the output of the program.
+    #[serde(default)]
+    pub output: LinterOutput,
+    /// When to run the program.
+    #[serde(default)]
+    pub run_on: LintTrigger,
+}
+
+/// The format of the output of an external linter.
+#[derive(Copy, Clone, Debug, Default, Serialize, Deserialize, PartialEq, Eq, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum LinterOutput {
+    /// Any output, where the problem matchers of the linter find the problems.
+    #[default]
+    ProblemMatchers,
+    /// The JSON output of [Vale](https://vale.sh), with `--output=JSON`, whose suggestions can be applied as
+    /// quick fixes.
+    Vale,
+}
+
+/// When a linter checks a buffer.
+#[derive(Copy, Clone, Debug, Default, Hash, Serialize, Deserialize, PartialEq, Eq, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum LintTrigger {
+    /// When the buffer changes, shortly after the last change.
+    #[default]
+    Change,
+    /// When the buffer is saved.
+    Save,
+}
+
 /// Controls how whitespace should be displayedin the editor.
 #[derive(Copy, Clone, Debug, Serialize, Deserialize, PartialEq, Eq, JsonSchema)]
 #[serde(rename_all = "snake_case")]
@@ -435,6 +611,9 @@ pub enum Formatter {
     },
     /// Files should be formatted using code actions executed by language servers.
     CodeActions(HashMap<String, bool>),
+    /// Format code with each of the formatters in order, each one formatting the output of the previous one.
+    /// A formatter that fails is skipped.
+    Chain(Vec<Formatter>),
 }
 
 /// The settings for indent guides.
@@ -660,6 +839,10 @@ impl settings::Settings for AllLanguageSettings {
             .as_ref()
             .and_then(|c| c.disabled_globs.as_ref())
             .ok_or_else(Self::missing_default)?;
+        let mut local_completions = default_value
+            .inline_completions
+            .as_ref()
+            .and_then(|c| c.local.as_ref());
 
         let mut file_types: HashMap<Arc<str>, GlobSet> = HashMap::default();
 
@@ -691,6 +874,13 @@ impl settings::Settings for AllLanguageSettings {
             {
                 completion_globs = globs;
             }
+            if let Some(local) = user_settings
+                .inline_completions
+                .as_ref()
+                .and_then(|f| f.local.as_ref())
+            {
+                local_completions = Some(local);
+            }
 
             // A user's global settings override the default global settings and
             // all default language-specific settings.
@@ -742,6 +932,7 @@ impl settings::Settings for AllLanguageSettings {
                     .iter()
                     .filter_map(|g| Some(globset::Glob::new(g).ok()?.compile_matcher()))
                     .collect(),
+                local: local_completions.cloned().unwrap_or_default(),
             },
             defaults,
             languages,
@@ -823,6 +1014,16 @@ fn merge_settings(settings: &mut LanguageSettings, src: &LanguageSettingsContent
         src.code_actions_on_format.clone(),
     );
     merge(&mut settings.linked_edits, src.linked_edits);
+    merge(&mut settings.semantic_tokens, src.semantic_tokens);
+    merge(
+        &mut settings.outline_from_language_server,
+        src.outline_from_language_server,
+    );
+    merge(
+        &mut settings.code_actions_on_save,
+        src.code_actions_on_save.clone(),
+    );
+    merge(&mut settings.linters, src.linters.clone());
 
     merge(
         &mut settings.preferred_line_length,
@@ -831,6 +1032,10 @@ fn merge_settings(settings: &mut LanguageSettings, src: &LanguageSettingsContent
     merge(&mut settings.formatter, src.formatter.clone());
     merge(&mut settings.prettier, src.prettier.clone());
     merge(&mut settings.format_on_save, src.format_on_save.clone());
+    merge(
+        &mut settings.format_changed_lines_only,
+        src.format_changed_lines_only,
+    );
     merge(
         &mut settings.remove_trailing_whitespace_on_save,
         src.remove_trailing_whitespace_on_save,
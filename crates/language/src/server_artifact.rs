@@ -0,0 +1,454 @@
+//! Declarative installation of language server binaries: an adapter describes the artifact of a server version,
+//! like a gzipped binary or an archive attached to a GitHub release, and it's downloaded through the configured
+//! mirrors, verified against its SHA-256 checksum, and extracted into a directory named after its version.
+
+use std::path::{Path, PathBuf};
+
+use anyhow::{anyhow, bail, Context as _, Result};
+use async_compression::futures::bufread::GzipDecoder;
+use async_tar::Archive;
+use futures::{io::BufReader, AsyncReadExt as _, AsyncWriteExt as _, StreamExt as _};
+use http::HttpClient;
+use sha2::{Digest as _, Sha256};
+use smol::fs::{self, File};
+use util::{fs::remove_matching, ResultExt as _};
+
+use crate::LspAdapterDelegate;
+
+/// A version of a language server binary to download.
+#[derive(Clone, Debug)]
+pub struct ServerArtifact {
+    /// The version of the server, which names the directory it's installed in.
+    pub version: String,
+    pub url: String,
+    pub format: ArtifactFormat,
+    pub checksum: Option<ArtifactChecksum>,
+    /// The path of the server binary, relative to the directory the artifact is installed in.
+    pub binary_path: PathBuf,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ArtifactFormat {
+    /// The binary itself.
+    Binary,
+    /// The gzipped binary.
+    Gz,
+    /// A gzipped tarball containing the binary.
+    TarGz,
+    /// A zip archive containing the binary.
+    Zip,
+}
+
+impl ArtifactFormat {
+    /// Guesses the format of an artifact from the extension of its file name.
+    pub fn from_file_name(file_name: &str) -> Self {
+        if file_name.ends_with(".tar.gz") || file_name.ends_with(".tgz") {
+            Self::TarGz
+        } else if file_name.ends_with(".gz") {
+            Self::Gz
+        } else if file_name.ends_with(".zip") {
+            Self::Zip
+        } else {
+            Self::Binary
+        }
+    }
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum ArtifactChecksum {
+    /// The hex-encoded SHA-256 digest of the artifact.
+    Sha256(String),
+    /// The URL of a checksum file in the format of `sha256sum`, listing the digest of the artifact by its file name.
+    Sha256File(String),
+}
+
+/// How the artifacts of language servers are downloaded, from the `lsp_downloads` settings.
+#[derive(Clone, Debug, Default)]
+pub struct ArtifactDownloadSettings {
+    /// The URL prefixes to replace, and the prefixes of the mirrors that replace them.
+    pub mirrors: Vec<(String, String)>,
+    /// Whether artifacts without a checksum are refused.
+    pub require_checksums: bool,
+}
+
+impl ArtifactDownloadSettings {
+    /// Returns the URL to download from instead of the URL, using the mirror of its longest matching prefix.
+    pub fn mirror_url(&self, url: &str) -> String {
+        self.mirrors
+            .iter()
+            .filter(|(prefix, _)| url.starts_with(prefix.as_str()))
+            .max_by_key(|(prefix, _)| prefix.len())
+            .map_or_else(
+                || url.to_string(),
+                |(prefix, mirror)| format!("{mirror}{}", &url[prefix.len()..]),
+            )
+    }
+}
+
+/// Downloads, verifies and extracts the artifact into the container directory, unless its version is already
+/// installed there, and removes the other versions. Returns the path of the server binary.
+pub async fn install_server_artifact(
+    artifact: &ServerArtifact,
+    container_dir: &Path,
+    delegate: &dyn LspAdapterDelegate,
+) -> Result<PathBuf> {
+    install_artifact(
+        artifact,
+        container_dir,
+        &delegate.artifact_download_settings(),
+        delegate.http_client().as_ref(),
+    )
+    .await
+}
+
+async fn install_artifact(
+    artifact: &ServerArtifact,
+    container_dir: &Path,
+    settings: &ArtifactDownloadSettings,
+    http: &dyn HttpClient,
+) -> Result<PathBuf> {
+    let version_dir = container_dir.join(&artifact.version);
+    let binary_path = version_dir.join(&artifact.binary_path);
+    if fs::metadata(&binary_path).await.is_ok() {
+        return Ok(binary_path);
+    }
+
+    let expected_digest = match &artifact.checksum {
+        Some(ArtifactChecksum::Sha256(digest)) => Some(digest.to_ascii_lowercase()),
+        Some(ArtifactChecksum::Sha256File(checksum_url)) => {
+            let checksum_url = settings.mirror_url(checksum_url);
+            let contents = download_text(&checksum_url, http)
+                .await
+                .with_context(|| format!("downloading the checksums {checksum_url}"))?;
+            let file_name = artifact.url.rsplit('/').next().unwrap_or_default();
+            Some(
+                parse_checksum_file(&contents, file_name)
+                    .ok_or_else(|| anyhow!("{checksum_url} has no checksum for {file_name}"))?,
+            )
+        }
+        None if settings.require_checksums => {
+            bail!(
+                "{} has no checksum, and checksums are required",
+                artifact.url
+            )
+        }
+        None => None,
+    };
+
+    let url = settings.mirror_url(&artifact.url);
+    let download_path = container_dir.join(format!("{}.download", artifact.version));
+    let digest = download(&url, &download_path, http)
+        .await
+        .with_context(|| format!("downloading {url}"))?;
+    if let Some(expected_digest) = expected_digest {
+        if digest != expected_digest {
+            fs::remove_file(&download_path).await.log_err();
+            bail!("the checksum of {url} is {digest} instead of {expected_digest}");
+        }
+    }
+
+    let extract_dir = container_dir.join(format!("{}.extract", artifact.version));
+    fs::remove_dir_all(&extract_dir).await.ok();
+    fs::create_dir_all(&extract_dir).await?;
+    let extracted = extract(artifact, &download_path, &extract_dir).await;
+    fs::remove_file(&download_path).await.log_err();
+    extracted.with_context(|| format!("extracting {url}"))?;
+    // An installation of the version that was interrupted, without the binary.
+    fs::remove_dir_all(&version_dir).await.ok();
+    fs::rename(&extract_dir, &version_dir).await?;
+    // todo("windows")
+    #[cfg(not(windows))]
+    {
+        fs::set_permissions(
+            &binary_path,
+            <fs::Permissions as fs::unix::PermissionsExt>::from_mode(0o755),
+        )
+        .await?;
+    }
+
+    remove_matching(container_dir, |entry| entry != version_dir).await;
+    Ok(binary_path)
+}
+
+/// Returns the path of the server binary in the most recently installed version in the container directory.
+pub async fn cached_server_artifact(container_dir: &Path, binary_path: &Path) -> Option<PathBuf> {
+    let mut last = None;
+    let mut entries = fs::read_dir(container_dir).await.log_err()?;
+    while let Some(entry) = entries.next().await {
+        let Some(entry) = entry.log_err() else {
+            continue;
+        };
+        let path = entry.path().join(binary_path);
+        if fs::metadata(&path).await.is_ok() {
+            last = Some(path);
+        }
+    }
+    last
+}
+
+/// Downloads the URL to the file, and returns the hex-encoded SHA-256 digest of its contents.
+async fn download(url: &str, destination: &Path, http: &dyn HttpClient) -> Result<String> {
+    let mut response = http.get(url, Default::default(), true).await?;
+    if !response.status().is_success() {
+        bail!("status error {}", response.status().as_u16());
+    }
+    let mut file = File::create(destination).await?;
+    let mut hasher = Sha256::new();
+    let mut buffer = vec![0; 64 * 1024];
+    let body = response.body_mut();
+    loop {
+        let len = body.read(&mut buffer).await?;
+        if len == 0 {
+            break;
+        }
+        hasher.update(&buffer[..len]);
+        file.write_all(&buffer[..len]).await?;
+    }
+    file.flush().await?;
+    Ok(hex::encode(hasher.finalize()))
+}
+
+async fn download_text(url: &str, http: &dyn HttpClient) -> Result<String> {
+    let mut response = http.get(url, Default::default(), true).await?;
+    if !response.status().is_success() {
+        bail!("status error {}", response.status().as_u16());
+    }
+    let mut text = String::new();
+    response.body_mut().read_to_string(&mut text).await?;
+    Ok(text)
+}
+
+async fn extract(
+    artifact: &ServerArtifact,
+    download_path: &Path,
+    extract_dir: &Path,
+) -> Result<()> {
+    let reader = BufReader::new(File::open(download_path).await?);
+    match artifact.format {
+        ArtifactFormat::Binary | ArtifactFormat::Gz => {
+            let binary_path = extract_dir.join(&artifact.binary_path);
+            if let Some(parent) = binary_path.parent() {
+                fs::create_dir_all(parent).await?;
+            }
+            let mut file = File::create(&binary_path).await?;
+            if artifact.format == ArtifactFormat::Gz {
+                futures::io::copy(GzipDecoder::new(reader), &mut file).await?;
+            } else {
+                futures::io::copy(reader, &mut file).await?;
+            }
+            file.flush().await?;
+        }
+        ArtifactFormat::TarGz => {
+            Archive::new(GzipDecoder::new(reader))
+                .unpack(extract_dir)
+                .await?;
+        }
+        ArtifactFormat::Zip => node_runtime::extract_zip(extract_dir, reader).await?,
+    }
+    Ok(())
+}
+
+/// Finds the digest of the file in the contents of a checksum file, which lists a digest and a file name per line.
+/// A checksum file with a single digest and no file name is the checksum of the file.
+fn parse_checksum_file(contents: &str, file_name: &str) -> Option<String> {
+    let entries = contents
+        .lines()
+        .filter_map(|line| {
+            let mut parts = line.split_whitespace();
+            let digest = parts.next()?;
+            let name = parts.next().map(|name| {
+                let name = name.trim_start_matches('*');
+                name.rsplit('/').next().unwrap_or(name)
+            });
+            Some((digest, name))
+        })
+        .collect::<Vec<_>>();
+    let (digest, _) = entries
+        .iter()
+        .find(|(_, name)| *name == Some(file_name))
+        .or_else(|| match entries.as_slice() {
+            [entry @ (_, None)] => Some(entry),
+            _ => None,
+        })?;
+    Some(digest.to_ascii_lowercase())
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::{Arc, Mutex};
+
+    use http::{AsyncBody, FakeHttpClient, Response};
+    use serde_json::json;
+    use util::test::temp_tree;
+
+    use super::*;
+
+    #[test]
+    fn test_install_server_artifact() {
+        let requested_urls = Arc::new(Mutex::new(Vec::new()));
+        let http = FakeHttpClient::create({
+            let requested_urls = requested_urls.clone();
+            move |request| {
+                let url = request.uri().to_string();
+                requested_urls.lock().unwrap().push(url.clone());
+                async move {
+                    let body = match url.as_str() {
+                        "https://mirror.example.com/server-v1" => "server v1",
+                        "https://mirror.example.com/server-v2" => "server v2",
+                        _ => {
+                            return Ok(Response::builder()
+                                .status(404)
+                                .body(AsyncBody::default())
+                                .unwrap())
+                        }
+                    };
+                    Ok(Response::new(body.into()))
+                }
+            }
+        });
+        let settings = ArtifactDownloadSettings {
+            mirrors: vec![(
+                "https://github.com/".into(),
+                "https://mirror.example.com/".into(),
+            )],
+            require_checksums: true,
+        };
+        let container_dir = temp_tree(json!({
+            // A previous installation of the version that was interrupted.
+            "v1": { "partial": "" },
+        }));
+        let container_dir = container_dir.path();
+        let artifact = |version: &str, checksum: Option<&str>| ServerArtifact {
+            version: version.into(),
+            url: format!("https://github.com/server-{version}"),
+            format: ArtifactFormat::Binary,
+            checksum: checksum.map(|checksum| ArtifactChecksum::Sha256(checksum.into())),
+            binary_path: PathBuf::from("bin/server"),
+        };
+        let v1_digest = hex::encode(Sha256::digest(b"server v1"));
+
+        smol::block_on(async {
+            let error = install_artifact(&artifact("v1", None), container_dir, &settings, &*http)
+                .await
+                .unwrap_err();
+            assert!(error.to_string().contains("checksums are required"));
+            assert!(requested_urls.lock().unwrap().is_empty());
+
+            let error = install_artifact(
+                &artifact("v1", Some(&"0".repeat(64))),
+                container_dir,
+                &settings,
+                &*http,
+            )
+            .await
+            .unwrap_err();
+            assert!(error.to_string().contains("instead of"));
+            assert!(fs::metadata(container_dir.join("v1.download"))
+                .await
+                .is_err());
+
+            let binary_path = install_artifact(
+                &artifact("v1", Some(&v1_digest.to_ascii_uppercase())),
+                container_dir,
+                &settings,
+                &*http,
+            )
+            .await
+            .unwrap();
+            assert_eq!(binary_path, container_dir.join("v1/bin/server"));
+            assert_eq!(fs::read_to_string(&binary_path).await.unwrap(), "server v1");
+            assert!(fs::metadata(container_dir.join("v1/partial"))
+                .await
+                .is_err());
+            assert_eq!(
+                *requested_urls.lock().unwrap(),
+                [
+                    "https://mirror.example.com/server-v1",
+                    "https://mirror.example.com/server-v1"
+                ]
+            );
+
+            // The installed version isn't downloaded again.
+            let binary_path = install_artifact(
+                &artifact("v1", Some(&v1_digest)),
+                container_dir,
+                &settings,
+                &*http,
+            )
+            .await
+            .unwrap();
+            assert_eq!(binary_path, container_dir.join("v1/bin/server"));
+            assert_eq!(requested_urls.lock().unwrap().len(), 2);
+            assert_eq!(
+                cached_server_artifact(container_dir, Path::new("bin/server")).await,
+                Some(binary_path)
+            );
+
+            // Installing another version removes the previous one.
+            let v2_digest = hex::encode(Sha256::digest(b"server v2"));
+            let binary_path = install_artifact(
+                &artifact("v2", Some(&v2_digest)),
+                container_dir,
+                &settings,
+                &*http,
+            )
+            .await
+            .unwrap();
+            assert_eq!(fs::read_to_string(&binary_path).await.unwrap(), "server v2");
+            assert!(fs::metadata(container_dir.join("v1")).await.is_err());
+            assert_eq!(
+                cached_server_artifact(container_dir, Path::new("bin/server")).await,
+                Some(binary_path)
+            );
+        });
+    }
+
+    #[test]
+    fn test_mirror_url() {
+        let settings = ArtifactDownloadSettings {
+            mirrors: vec![
+                (
+                    "https://github.com/".into(),
+                    "https://mirror.example.com/github/".into(),
+                ),
+                (
+                    "https://github.com/rust-lang/".into(),
+                    "https://rust.example.com/".into(),
+                ),
+            ],
+            require_checksums: false,
+        };
+        assert_eq!(
+            settings.mirror_url("https://github.com/golang/tools/archive/v1.tar.gz"),
+            "https://mirror.example.com/github/golang/tools/archive/v1.tar.gz"
+        );
+        assert_eq!(
+            settings.mirror_url("https://github.com/rust-lang/rust-analyzer/releases/x.gz"),
+            "https://rust.example.com/rust-analyzer/releases/x.gz"
+        );
+        assert_eq!(
+            settings.mirror_url("https://registry.npmjs.org/typescript"),
+            "https://registry.npmjs.org/typescript"
+        );
+    }
+
+    #[test]
+    fn test_parse_checksum_file() {
+        let contents = "\
+            ABC123  server-x86_64-linux.tar.gz\n\
+            def456 *./server-aarch64-darwin.zip\n";
+        assert_eq!(
+            parse_checksum_file(contents, "server-x86_64-linux.tar.gz"),
+            Some("abc123".into())
+        );
+        assert_eq!(
+            parse_checksum_file(contents, "server-aarch64-darwin.zip"),
+            Some("def456".into())
+        );
+        assert_eq!(parse_checksum_file(contents, "server.exe"), None);
+        assert_eq!(
+            parse_checksum_file("abc123\n", "server.gz"),
+            Some("abc123".into())
+        );
+    }
+}
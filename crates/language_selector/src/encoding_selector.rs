@@ -0,0 +1,246 @@
+use editor::Editor;
+use fuzzy::{match_strings, StringMatch, StringMatchCandidate};
+use gpui::{
+    AppContext, DismissEvent, EventEmitter, FocusHandle, FocusableView, Model, ParentElement,
+    Render, Styled, View, ViewContext, VisualContext, WeakView,
+};
+use language::{Buffer, Encoding};
+use picker::{Picker, PickerDelegate};
+use project::Project;
+use std::sync::Arc;
+use ui::{prelude::*, HighlightedLabel, ListItem, ListItemSpacing};
+use util::ResultExt;
+use workspace::{notifications::DetachAndPromptErr, ModalView, Workspace};
+
+use crate::{ReopenWithEncoding, SaveWithEncoding};
+
+/// What the encoding picked for the buffer of the active editor is used for.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub(crate) enum EncodingSelectorMode {
+    /// The file is decoded again with the encoding.
+    Reopen,
+    /// The buffer is saved in the encoding.
+    Save,
+}
+
+/// Picks the encoding the buffer of the active editor is reopened or saved with.
+pub struct EncodingSelector {
+    picker: View<Picker<EncodingSelectorDelegate>>,
+}
+
+impl EncodingSelector {
+    pub(crate) fn register(workspace: &mut Workspace, _: &mut ViewContext<Workspace>) {
+        workspace.register_action(move |workspace, _: &ReopenWithEncoding, cx| {
+            Self::toggle(workspace, EncodingSelectorMode::Reopen, cx);
+        });
+        workspace.register_action(move |workspace, _: &SaveWithEncoding, cx| {
+            Self::toggle(workspace, EncodingSelectorMode::Save, cx);
+        });
+    }
+
+    fn toggle(
+        workspace: &mut Workspace,
+        mode: EncodingSelectorMode,
+        cx: &mut ViewContext<Workspace>,
+    ) -> Option<()> {
+        let (_, buffer, _) = workspace
+            .active_item(cx)?
+            .act_as::<Editor>(cx)?
+            .read(cx)
+            .active_excerpt(cx)?;
+        buffer.read(cx).file()?;
+        let project = workspace.project().clone();
+
+        workspace.toggle_modal(cx, move |cx| {
+            EncodingSelector::new(buffer, project, mode, cx)
+        });
+        Some(())
+    }
+
+    fn new(
+        buffer: Model<Buffer>,
+        project: Model<Project>,
+        mode: EncodingSelectorMode,
+        cx: &mut ViewContext<Self>,
+    ) -> Self {
+        let delegate =
+            EncodingSelectorDelegate::new(cx.view().downgrade(), buffer, project, mode, cx);
+        let picker = cx.new_view(|cx| Picker::uniform_list(delegate, cx));
+        Self { picker }
+    }
+}
+
+impl Render for EncodingSelector {
+    fn render(&mut self, _cx: &mut ViewContext<Self>) -> impl IntoElement {
+        v_flex().w(rems(34.)).child(self.picker.clone())
+    }
+}
+
+impl FocusableView for EncodingSelector {
+    fn focus_handle(&self, cx: &AppContext) -> FocusHandle {
+        self.picker.focus_handle(cx)
+    }
+}
+
+impl EventEmitter<DismissEvent> for EncodingSelector {}
+impl ModalView for EncodingSelector {}
+
+pub struct EncodingSelectorDelegate {
+    encoding_selector: WeakView<EncodingSelector>,
+    buffer: Model<Buffer>,
+    project: Model<Project>,
+    mode: EncodingSelectorMode,
+    current_encoding: Encoding,
+    candidates: Vec<StringMatchCandidate>,
+    matches: Vec<StringMatch>,
+    selected_index: usize,
+}
+
+impl EncodingSelectorDelegate {
+    fn new(
+        encoding_selector: WeakView<EncodingSelector>,
+        buffer: Model<Buffer>,
+        project: Model<Project>,
+        mode: EncodingSelectorMode,
+        cx: &mut ViewContext<EncodingSelector>,
+    ) -> Self {
+        let current_encoding = buffer.read(cx).encoding();
+        let candidates = Encoding::ALL
+            .iter()
+            .enumerate()
+            .map(|(candidate_id, encoding)| {
+                StringMatchCandidate::new(candidate_id, encoding.name().to_string())
+            })
+            .collect::<Vec<_>>();
+        let selected_index = Encoding::ALL
+            .iter()
+            .position(|encoding| *encoding == current_encoding)
+            .unwrap_or(0);
+
+        Self {
+            encoding_selector,
+            buffer,
+            project,
+            mode,
+            current_encoding,
+            candidates,
+            matches: vec![],
+            selected_index,
+        }
+    }
+}
+
+impl PickerDelegate for EncodingSelectorDelegate {
+    type ListItem = ListItem;
+
+    fn placeholder_text(&self, _cx: &mut WindowContext) -> Arc<str> {
+        match self.mode {
+            EncodingSelectorMode::Reopen => "Reopen with encoding...".into(),
+            EncodingSelectorMode::Save => "Save with encoding...".into(),
+        }
+    }
+
+    fn match_count(&self) -> usize {
+        self.matches.len()
+    }
+
+    fn confirm(&mut self, _: bool, cx: &mut ViewContext<Picker<Self>>) {
+        if let Some(mat) = self.matches.get(self.selected_index) {
+            let encoding = Encoding::ALL[mat.candidate_id];
+            let buffer = self.buffer.clone();
+            let mode = self.mode;
+            let task = self.project.update(cx, |project, cx| match mode {
+                EncodingSelectorMode::Reopen => {
+                    project.reopen_buffer_with_encoding(&buffer, encoding, cx)
+                }
+                EncodingSelectorMode::Save => {
+                    project.save_buffer_with_encoding(buffer, encoding, cx)
+                }
+            });
+            let message = match mode {
+                EncodingSelectorMode::Reopen => "Failed to reopen the file",
+                EncodingSelectorMode::Save => "Failed to save the file",
+            };
+            task.detach_and_prompt_err(message, cx, |error, _| Some(error.to_string()));
+        }
+        self.dismissed(cx);
+    }
+
+    fn dismissed(&mut self, cx: &mut ViewContext<Picker<Self>>) {
+        self.encoding_selector
+            .update(cx, |_, cx| cx.emit(DismissEvent))
+            .log_err();
+    }
+
+    fn selected_index(&self) -> usize {
+        self.selected_index
+    }
+
+    fn set_selected_index(&mut self, ix: usize, _: &mut ViewContext<Picker<Self>>) {
+        self.selected_index = ix;
+    }
+
+    fn update_matches(
+        &mut self,
+        query: String,
+        cx: &mut ViewContext<Picker<Self>>,
+    ) -> gpui::Task<()> {
+        let background = cx.background_executor().clone();
+        let candidates = self.candidates.clone();
+        cx.spawn(|this, mut cx| async move {
+            let matches = if query.is_empty() {
+                candidates
+                    .into_iter()
+                    .enumerate()
+                    .map(|(index, candidate)| StringMatch {
+                        candidate_id: index,
+                        string: candidate.string,
+                        positions: Vec::new(),
+                        score: 0.0,
+                    })
+                    .collect()
+            } else {
+                match_strings(
+                    &candidates,
+                    &query,
+                    false,
+                    100,
+                    &Default::default(),
+                    background,
+                )
+                .await
+            };
+
+            this.update(&mut cx, |this, cx| {
+                let delegate = &mut this.delegate;
+                delegate.matches = matches;
+                delegate.selected_index = delegate
+                    .selected_index
+                    .min(delegate.matches.len().saturating_sub(1));
+                cx.notify();
+            })
+            .log_err();
+        })
+    }
+
+    fn render_match(
+        &self,
+        ix: usize,
+        selected: bool,
+        _: &mut ViewContext<Picker<Self>>,
+    ) -> Option<Self::ListItem> {
+        let mat = &self.matches[ix];
+        let mut label = mat.string.clone();
+        if Encoding::ALL[mat.candidate_id] == self.current_encoding {
+            label.push_str(" (current)");
+        }
+
+        Some(
+            ListItem::new(ix)
+                .inset(true)
+                .spacing(ListItemSpacing::Sparse)
+                .selected(selected)
+                .child(HighlightedLabel::new(label, mat.positions.clone())),
+        )
+    }
+}
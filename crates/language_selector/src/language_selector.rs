@@ -1,14 +1,20 @@
+mod active_buffer_encoding;
 mod active_buffer_language;
+mod encoding_selector;
+mod language_server_selector;
 
+pub use active_buffer_encoding::ActiveBufferEncoding;
 pub use active_buffer_language::ActiveBufferLanguage;
 use anyhow::anyhow;
 use editor::Editor;
+pub use encoding_selector::EncodingSelector;
 use fuzzy::{match_strings, StringMatch, StringMatchCandidate};
 use gpui::{
     actions, AppContext, DismissEvent, EventEmitter, FocusHandle, FocusableView, Model,
     ParentElement, Render, Styled, View, ViewContext, VisualContext, WeakView,
 };
 use language::{Buffer, LanguageRegistry};
+pub use language_server_selector::LanguageServerSelector;
 use picker::{Picker, PickerDelegate};
 use project::Project;
 use std::sync::Arc;
@@ -16,10 +22,21 @@ use ui::{prelude::*, HighlightedLabel, ListItem, ListItemSpacing};
 use util::ResultExt;
 use workspace::{ModalView, Workspace};
 
-actions!(language_selector, [Toggle]);
+actions!(
+    language_selector,
+    [
+        Toggle,
+        PinLanguageServer,
+        ReopenWithEncoding,
+        SaveWithEncoding
+    ]
+);
 
 pub fn init(cx: &mut AppContext) {
     cx.observe_new_views(LanguageSelector::register).detach();
+    cx.observe_new_views(LanguageServerSelector::register)
+        .detach();
+    cx.observe_new_views(EncodingSelector::register).detach();
 }
 
 pub struct LanguageSelector {
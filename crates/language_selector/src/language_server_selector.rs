@@ -0,0 +1,235 @@
+use anyhow::anyhow;
+use editor::Editor;
+use fuzzy::{match_strings, StringMatch, StringMatchCandidate};
+use gpui::{
+    AppContext, DismissEvent, EventEmitter, FocusHandle, FocusableView, Model, ParentElement,
+    Render, Styled, View, ViewContext, VisualContext, WeakView,
+};
+use language::{Buffer, LanguageServerName};
+use picker::{Picker, PickerDelegate};
+use project::Project;
+use std::sync::Arc;
+use ui::{prelude::*, HighlightedLabel, ListItem, ListItemSpacing};
+use util::ResultExt;
+use workspace::{ModalView, Workspace};
+
+use crate::PinLanguageServer;
+
+const AUTOMATIC: &str = "Automatic";
+
+/// Picks the language server pinned for the buffer of the active editor, among the ones running for it.
+pub struct LanguageServerSelector {
+    picker: View<Picker<LanguageServerSelectorDelegate>>,
+}
+
+impl LanguageServerSelector {
+    pub(crate) fn register(workspace: &mut Workspace, _: &mut ViewContext<Workspace>) {
+        workspace.register_action(move |workspace, _: &PinLanguageServer, cx| {
+            Self::toggle(workspace, cx);
+        });
+    }
+
+    fn toggle(workspace: &mut Workspace, cx: &mut ViewContext<Workspace>) -> Option<()> {
+        let (_, buffer, _) = workspace
+            .active_item(cx)?
+            .act_as::<Editor>(cx)?
+            .read(cx)
+            .active_excerpt(cx)?;
+        let project = workspace.project().clone();
+
+        workspace.toggle_modal(cx, move |cx| {
+            LanguageServerSelector::new(buffer, project, cx)
+        });
+        Some(())
+    }
+
+    fn new(buffer: Model<Buffer>, project: Model<Project>, cx: &mut ViewContext<Self>) -> Self {
+        let delegate =
+            LanguageServerSelectorDelegate::new(cx.view().downgrade(), buffer, project, cx);
+        let picker = cx.new_view(|cx| Picker::uniform_list(delegate, cx));
+        Self { picker }
+    }
+}
+
+impl Render for LanguageServerSelector {
+    fn render(&mut self, _cx: &mut ViewContext<Self>) -> impl IntoElement {
+        v_flex().w(rems(34.)).child(self.picker.clone())
+    }
+}
+
+impl FocusableView for LanguageServerSelector {
+    fn focus_handle(&self, cx: &AppContext) -> FocusHandle {
+        self.picker.focus_handle(cx)
+    }
+}
+
+impl EventEmitter<DismissEvent> for LanguageServerSelector {}
+impl ModalView for LanguageServerSelector {}
+
+pub struct LanguageServerSelectorDelegate {
+    language_server_selector: WeakView<LanguageServerSelector>,
+    buffer: Model<Buffer>,
+    project: Model<Project>,
+    /// The running language servers of the buffer, preceded by `None` for unpinning it.
+    servers: Vec<Option<LanguageServerName>>,
+    pinned: Option<LanguageServerName>,
+    candidates: Vec<StringMatchCandidate>,
+    matches: Vec<StringMatch>,
+    selected_index: usize,
+}
+
+impl LanguageServerSelectorDelegate {
+    fn new(
+        language_server_selector: WeakView<LanguageServerSelector>,
+        buffer: Model<Buffer>,
+        project: Model<Project>,
+        cx: &mut ViewContext<LanguageServerSelector>,
+    ) -> Self {
+        let (servers, pinned) = {
+            let project = project.read(cx);
+            let buffer = buffer.read(cx);
+            let mut servers = vec![None];
+            for (_, name) in project.pinnable_language_servers_for_buffer(buffer, cx) {
+                if !servers.contains(&Some(name.clone())) {
+                    servers.push(Some(name));
+                }
+            }
+            (
+                servers,
+                project.pinned_language_server_for_buffer(buffer, cx),
+            )
+        };
+        let candidates = servers
+            .iter()
+            .enumerate()
+            .map(|(candidate_id, name)| {
+                let name = name.as_ref().map_or(AUTOMATIC, |name| name.0.as_ref());
+                StringMatchCandidate::new(candidate_id, name.to_string())
+            })
+            .collect::<Vec<_>>();
+
+        Self {
+            language_server_selector,
+            buffer,
+            project,
+            servers,
+            pinned,
+            candidates,
+            matches: vec![],
+            selected_index: 0,
+        }
+    }
+}
+
+impl PickerDelegate for LanguageServerSelectorDelegate {
+    type ListItem = ListItem;
+
+    fn placeholder_text(&self, _cx: &mut WindowContext) -> Arc<str> {
+        "Pin a language server for this buffer...".into()
+    }
+
+    fn match_count(&self) -> usize {
+        self.matches.len()
+    }
+
+    fn confirm(&mut self, _: bool, cx: &mut ViewContext<Picker<Self>>) {
+        if let Some(mat) = self.matches.get(self.selected_index) {
+            let server_name = self.servers[mat.candidate_id].clone();
+            let project = self.project.downgrade();
+            let buffer = self.buffer.downgrade();
+            cx.spawn(|_, mut cx| async move {
+                let project = project
+                    .upgrade()
+                    .ok_or_else(|| anyhow!("project was dropped"))?;
+                let buffer = buffer
+                    .upgrade()
+                    .ok_or_else(|| anyhow!("buffer was dropped"))?;
+                project
+                    .update(&mut cx, |project, cx| {
+                        project.pin_language_server_for_buffer(&buffer, server_name, cx)
+                    })?
+                    .await
+            })
+            .detach_and_log_err(cx);
+        }
+        self.dismissed(cx);
+    }
+
+    fn dismissed(&mut self, cx: &mut ViewContext<Picker<Self>>) {
+        self.language_server_selector
+            .update(cx, |_, cx| cx.emit(DismissEvent))
+            .log_err();
+    }
+
+    fn selected_index(&self) -> usize {
+        self.selected_index
+    }
+
+    fn set_selected_index(&mut self, ix: usize, _: &mut ViewContext<Picker<Self>>) {
+        self.selected_index = ix;
+    }
+
+    fn update_matches(
+        &mut self,
+        query: String,
+        cx: &mut ViewContext<Picker<Self>>,
+    ) -> gpui::Task<()> {
+        let background = cx.background_executor().clone();
+        let candidates = self.candidates.clone();
+        cx.spawn(|this, mut cx| async move {
+            let matches = if query.is_empty() {
+                candidates
+                    .into_iter()
+                    .enumerate()
+                    .map(|(index, candidate)| StringMatch {
+                        candidate_id: index,
+                        string: candidate.string,
+                        positions: Vec::new(),
+                        score: 0.0,
+                    })
+                    .collect()
+            } else {
+                match_strings(
+                    &candidates,
+                    &query,
+                    false,
+                    100,
+                    &Default::default(),
+                    background,
+                )
+                .await
+            };
+
+            this.update(&mut cx, |this, cx| {
+                let delegate = &mut this.delegate;
+                delegate.matches = matches;
+                delegate.selected_index = delegate
+                    .selected_index
+                    .min(delegate.matches.len().saturating_sub(1));
+                cx.notify();
+            })
+            .log_err();
+        })
+    }
+
+    fn render_match(
+        &self,
+        ix: usize,
+        selected: bool,
+        _: &mut ViewContext<Picker<Self>>,
+    ) -> Option<Self::ListItem> {
+        let mat = &self.matches[ix];
+        let mut label = mat.string.clone();
+        if self.servers[mat.candidate_id] == self.pinned {
+            label.push_str(" (current)");
+        }
+
+        Some(
+            ListItem::new(ix)
+                .inset(true)
+                .spacing(ListItemSpacing::Sparse)
+                .selected(selected)
+                .child(HighlightedLabel::new(label, mat.positions.clone())),
+        )
+    }
+}
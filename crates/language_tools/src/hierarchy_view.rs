@@ -0,0 +1,463 @@
+use editor::{
+    actions::{ShowCallHierarchy, ShowTypeHierarchy},
+    scroll::Autoscroll,
+    Editor,
+};
+use gpui::{
+    uniform_list, AnyElement, AppContext, EventEmitter, FocusHandle, FocusableView, IntoElement,
+    Model, ModelContext, Render, Task, UniformListScrollHandle, View, ViewContext, VisualContext,
+    WeakView, WindowContext,
+};
+use language::Location;
+use project::{CallHierarchyItem, Project, TypeHierarchyItem};
+use ui::{prelude::*, ListItem};
+use util::ResultExt;
+use workspace::{
+    item::{Item, TabContentParams},
+    SplitDirection, Workspace,
+};
+
+pub fn init(cx: &mut AppContext) {
+    cx.observe_new_views(|workspace: &mut Workspace, _| {
+        workspace.register_action(|workspace, _: &ShowTypeHierarchy, cx| {
+            HierarchyView::deploy(workspace, HierarchyKind::Type, cx);
+        });
+        workspace.register_action(|workspace, _: &ShowCallHierarchy, cx| {
+            HierarchyView::deploy(workspace, HierarchyKind::Call, cx);
+        });
+    })
+    .detach();
+}
+
+#[derive(Clone, Copy, PartialEq, Eq)]
+enum HierarchyKind {
+    Type,
+    Call,
+}
+
+impl HierarchyKind {
+    fn title(&self) -> &'static str {
+        match self {
+            Self::Type => "Type Hierarchy",
+            Self::Call => "Call Hierarchy",
+        }
+    }
+
+    fn directions(&self) -> [HierarchyDirection; 2] {
+        match self {
+            Self::Type => [HierarchyDirection::Supertypes, HierarchyDirection::Subtypes],
+            Self::Call => [
+                HierarchyDirection::IncomingCalls,
+                HierarchyDirection::OutgoingCalls,
+            ],
+        }
+    }
+}
+
+#[derive(Clone, Copy, PartialEq, Eq)]
+enum HierarchyDirection {
+    Supertypes,
+    Subtypes,
+    IncomingCalls,
+    OutgoingCalls,
+}
+
+impl HierarchyDirection {
+    fn label(&self) -> &'static str {
+        match self {
+            Self::Supertypes => "Supertypes",
+            Self::Subtypes => "Subtypes",
+            Self::IncomingCalls => "Incoming Calls",
+            Self::OutgoingCalls => "Outgoing Calls",
+        }
+    }
+}
+
+#[derive(Clone)]
+enum HierarchyItem {
+    Type(TypeHierarchyItem),
+    Call(CallHierarchyItem),
+}
+
+impl HierarchyItem {
+    fn name(&self) -> &str {
+        match self {
+            Self::Type(item) => &item.lsp_item.name,
+            Self::Call(item) => &item.lsp_item.name,
+        }
+    }
+
+    fn detail(&self) -> Option<&str> {
+        match self {
+            Self::Type(item) => item.lsp_item.detail.as_deref(),
+            Self::Call(item) => item.lsp_item.detail.as_deref(),
+        }
+    }
+
+    fn location(&self) -> &Location {
+        match self {
+            Self::Type(item) => &item.location,
+            Self::Call(item) => &item.location,
+        }
+    }
+
+    fn children(
+        &self,
+        direction: HierarchyDirection,
+        project: &Project,
+        cx: &mut ModelContext<Project>,
+    ) -> Task<anyhow::Result<Vec<HierarchyItem>>> {
+        let children = match (self, direction) {
+            (Self::Type(item), HierarchyDirection::Supertypes) => {
+                project.type_hierarchy_supertypes(item, cx)
+            }
+            (Self::Type(item), HierarchyDirection::Subtypes) => {
+                project.type_hierarchy_subtypes(item, cx)
+            }
+            (Self::Call(item), HierarchyDirection::IncomingCalls) => {
+                let calls = project.incoming_calls(item, cx);
+                return cx.background_executor().spawn(async move {
+                    Ok(calls
+                        .await?
+                        .into_iter()
+                        .map(|call| Self::Call(call.item))
+                        .collect())
+                });
+            }
+            (Self::Call(item), HierarchyDirection::OutgoingCalls) => {
+                let calls = project.outgoing_calls(item, cx);
+                return cx.background_executor().spawn(async move {
+                    Ok(calls
+                        .await?
+                        .into_iter()
+                        .map(|call| Self::Call(call.item))
+                        .collect())
+                });
+            }
+            _ => return Task::ready(Ok(Vec::new())),
+        };
+        cx.background_executor()
+            .spawn(async move { Ok(children.await?.into_iter().map(Self::Type).collect()) })
+    }
+}
+
+/// A tree of the super/subtypes or callers/callees of the item under the cursor, expanded level by level on demand.
+pub struct HierarchyView {
+    workspace: WeakView<Workspace>,
+    project: Model<Project>,
+    kind: HierarchyKind,
+    roots: Vec<HierarchyItem>,
+    direction: HierarchyDirection,
+    /// Visible entries of the tree, with children following their parents.
+    entries: Vec<HierarchyEntry>,
+    next_entry_id: usize,
+    list_scroll_handle: UniformListScrollHandle,
+    focus_handle: FocusHandle,
+}
+
+struct HierarchyEntry {
+    id: usize,
+    item: HierarchyItem,
+    depth: usize,
+    state: EntryState,
+}
+
+enum EntryState {
+    Collapsed,
+    Loading(Task<()>),
+    Expanded,
+    /// The entry was expanded, but the language server reported no children for it.
+    Leaf,
+}
+
+impl HierarchyView {
+    fn deploy(workspace: &mut Workspace, kind: HierarchyKind, cx: &mut ViewContext<Workspace>) {
+        let Some(editor) = workspace.active_item_as::<Editor>(cx) else {
+            return;
+        };
+        let Some((buffer, position)) = editor.update(cx, |editor, cx| {
+            let head = editor.selections.newest::<usize>(cx).head();
+            editor.buffer().read(cx).text_anchor_for_position(head, cx)
+        }) else {
+            return;
+        };
+
+        let project = workspace.project().clone();
+        let roots = project.update(cx, |project, cx| match kind {
+            HierarchyKind::Type => {
+                let roots = project.prepare_type_hierarchy(&buffer, position, cx);
+                cx.background_executor().spawn(async move {
+                    anyhow::Ok(
+                        roots
+                            .await?
+                            .into_iter()
+                            .map(HierarchyItem::Type)
+                            .collect::<Vec<_>>(),
+                    )
+                })
+            }
+            HierarchyKind::Call => {
+                let roots = project.prepare_call_hierarchy(&buffer, position, cx);
+                cx.background_executor().spawn(async move {
+                    anyhow::Ok(
+                        roots
+                            .await?
+                            .into_iter()
+                            .map(HierarchyItem::Call)
+                            .collect::<Vec<_>>(),
+                    )
+                })
+            }
+        });
+        cx.spawn(|workspace, mut cx| async move {
+            let roots = roots.await?;
+            if roots.is_empty() {
+                log::info!("No {} for position {position:?}", kind.title());
+                return anyhow::Ok(());
+            }
+            workspace.update(&mut cx, |workspace, cx| {
+                let workspace_handle = cx.view().downgrade();
+                let view = cx.new_view(|cx| Self::new(workspace_handle, project, kind, roots, cx));
+                workspace.split_item(SplitDirection::Right, Box::new(view), cx);
+            })
+        })
+        .detach_and_log_err(cx);
+    }
+
+    fn new(
+        workspace: WeakView<Workspace>,
+        project: Model<Project>,
+        kind: HierarchyKind,
+        roots: Vec<HierarchyItem>,
+        cx: &mut ViewContext<Self>,
+    ) -> Self {
+        let mut this = Self {
+            workspace,
+            project,
+            kind,
+            roots,
+            direction: kind.directions()[0],
+            entries: Vec::new(),
+            next_entry_id: 0,
+            list_scroll_handle: UniformListScrollHandle::new(),
+            focus_handle: cx.focus_handle(),
+        };
+        this.reset_entries();
+        this
+    }
+
+    fn set_direction(&mut self, direction: HierarchyDirection, cx: &mut ViewContext<Self>) {
+        if self.direction != direction {
+            self.direction = direction;
+            self.reset_entries();
+            cx.notify();
+        }
+    }
+
+    fn reset_entries(&mut self) {
+        self.entries.clear();
+        for item in self.roots.clone() {
+            let id = self.post_inc_entry_id();
+            self.entries.push(HierarchyEntry {
+                id,
+                item,
+                depth: 0,
+                state: EntryState::Collapsed,
+            });
+        }
+    }
+
+    fn post_inc_entry_id(&mut self) -> usize {
+        let id = self.next_entry_id;
+        self.next_entry_id += 1;
+        id
+    }
+
+    fn toggle_entry(&mut self, ix: usize, cx: &mut ViewContext<Self>) {
+        let Some(entry) = self.entries.get_mut(ix) else {
+            return;
+        };
+        match entry.state {
+            EntryState::Collapsed => {
+                let entry_id = entry.id;
+                let direction = self.direction;
+                let children = self.project.update(cx, |project, cx| {
+                    entry.item.children(direction, project, cx)
+                });
+                entry.state = EntryState::Loading(cx.spawn(|this, mut cx| async move {
+                    let children = children.await.log_err().unwrap_or_default();
+                    this.update(&mut cx, |this, cx| {
+                        this.insert_children(entry_id, children);
+                        cx.notify();
+                    })
+                    .ok();
+                }));
+            }
+            EntryState::Expanded => {
+                entry.state = EntryState::Collapsed;
+                let depth = entry.depth;
+                let descendants_end = self.entries[ix + 1..]
+                    .iter()
+                    .position(|descendant| descendant.depth <= depth)
+                    .map_or(self.entries.len(), |end| ix + 1 + end);
+                self.entries.drain(ix + 1..descendants_end);
+            }
+            EntryState::Loading(_) | EntryState::Leaf => return,
+        }
+        cx.notify();
+    }
+
+    fn insert_children(&mut self, entry_id: usize, children: Vec<HierarchyItem>) {
+        let Some(ix) = self.entries.iter().position(|entry| entry.id == entry_id) else {
+            return;
+        };
+        let depth = self.entries[ix].depth + 1;
+        self.entries[ix].state = if children.is_empty() {
+            EntryState::Leaf
+        } else {
+            EntryState::Expanded
+        };
+        let children = children
+            .into_iter()
+            .map(|item| HierarchyEntry {
+                id: self.post_inc_entry_id(),
+                item,
+                depth,
+                state: EntryState::Collapsed,
+            })
+            .collect::<Vec<_>>();
+        self.entries.splice(ix + 1..ix + 1, children);
+    }
+
+    fn open_entry(&mut self, ix: usize, cx: &mut ViewContext<Self>) {
+        let Some(entry) = self.entries.get(ix) else {
+            return;
+        };
+        let location = entry.item.location().clone();
+        self.workspace
+            .update(cx, |workspace, cx| {
+                let pane = workspace.adjacent_pane(cx);
+                let editor = workspace.open_project_item::<Editor>(pane, location.buffer, cx);
+                editor.update(cx, |editor, cx| {
+                    editor.change_selections(Some(Autoscroll::center()), cx, |s| {
+                        s.select_ranges([location.range.start..location.range.start])
+                    });
+                });
+            })
+            .log_err();
+    }
+
+    fn render_entry(&self, ix: usize, cx: &mut ViewContext<Self>) -> ListItem {
+        let entry = &self.entries[ix];
+        let toggle = match entry.state {
+            EntryState::Collapsed | EntryState::Loading(_) => Some(false),
+            EntryState::Expanded => Some(true),
+            EntryState::Leaf => None,
+        };
+        ListItem::new(entry.id)
+            .indent_level(entry.depth)
+            .toggle(toggle)
+            .on_toggle(cx.listener(move |this, _, cx| this.toggle_entry(ix, cx)))
+            .on_click(cx.listener(move |this, _, cx| this.open_entry(ix, cx)))
+            .child(
+                h_flex()
+                    .gap_2()
+                    .child(Label::new(entry.item.name().to_string()))
+                    .children(
+                        entry
+                            .item
+                            .detail()
+                            .map(|detail| Label::new(detail.to_string()).color(Color::Muted)),
+                    ),
+            )
+    }
+
+    fn render_direction_button(
+        &self,
+        direction: HierarchyDirection,
+        cx: &mut ViewContext<Self>,
+    ) -> Button {
+        Button::new(direction.label(), direction.label())
+            .selected(self.direction == direction)
+            .on_click(cx.listener(move |this, _, cx| this.set_direction(direction, cx)))
+    }
+}
+
+impl Render for HierarchyView {
+    fn render(&mut self, cx: &mut ViewContext<Self>) -> impl IntoElement {
+        v_flex()
+            .size_full()
+            .bg(cx.theme().colors().editor_background)
+            .child(
+                h_flex().p_1().gap_1().children(
+                    self.kind
+                        .directions()
+                        .map(|direction| self.render_direction_button(direction, cx)),
+                ),
+            )
+            .child(
+                uniform_list(
+                    cx.view().clone(),
+                    "HierarchyView",
+                    self.entries.len(),
+                    |this, range, cx| range.map(|ix| this.render_entry(ix, cx)).collect(),
+                )
+                .size_full()
+                .track_scroll(self.list_scroll_handle.clone()),
+            )
+    }
+}
+
+impl EventEmitter<()> for HierarchyView {}
+
+impl FocusableView for HierarchyView {
+    fn focus_handle(&self, _: &AppContext) -> FocusHandle {
+        self.focus_handle.clone()
+    }
+}
+
+impl Item for HierarchyView {
+    type Event = ();
+
+    fn to_item_events(_: &Self::Event, _: impl FnMut(workspace::item::ItemEvent)) {}
+
+    fn tab_content(&self, params: TabContentParams, _: &WindowContext<'_>) -> AnyElement {
+        let title = match self.roots.as_slice() {
+            [root] => format!("{}: {}", self.kind.title(), root.name()),
+            _ => self.kind.title().to_string(),
+        };
+        Label::new(title)
+            .color(if params.selected {
+                Color::Default
+            } else {
+                Color::Muted
+            })
+            .into_any_element()
+    }
+
+    fn telemetry_event_text(&self) -> Option<&'static str> {
+        None
+    }
+
+    fn clone_on_split(
+        &self,
+        _: Option<workspace::WorkspaceId>,
+        cx: &mut ViewContext<Self>,
+    ) -> Option<View<Self>>
+    where
+        Self: Sized,
+    {
+        Some(cx.new_view(|cx| {
+            let mut clone = Self::new(
+                self.workspace.clone(),
+                self.project.clone(),
+                self.kind,
+                self.roots.clone(),
+                cx,
+            );
+            clone.direction = self.direction;
+            clone.reset_entries();
+            clone
+        }))
+    }
+}
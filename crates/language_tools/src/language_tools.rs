@@ -1,4 +1,6 @@
+mod hierarchy_view;
 mod lsp_log;
+mod lsp_profiler;
 mod syntax_tree_view;
 
 #[cfg(test)]
@@ -6,10 +8,14 @@ mod lsp_log_tests;
 
 use gpui::AppContext;
 
+pub use hierarchy_view::HierarchyView;
 pub use lsp_log::{LogStore, LspLogToolbarItemView, LspLogView};
+pub use lsp_profiler::LspProfilerView;
 pub use syntax_tree_view::{SyntaxTreeToolbarItemView, SyntaxTreeView};
 
 pub fn init(cx: &mut AppContext) {
     lsp_log::init(cx);
+    lsp_profiler::init(cx);
     syntax_tree_view::init(cx);
+    hierarchy_view::init(cx);
 }
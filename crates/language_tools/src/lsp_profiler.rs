@@ -0,0 +1,229 @@
+use std::time::Duration;
+
+use gpui::{
+    actions, AnyElement, AppContext, EventEmitter, FocusHandle, FocusableView, IntoElement, Model,
+    Render, Task, View, ViewContext, VisualContext, WindowContext,
+};
+use lsp::{LanguageServerId, RequestOutcome, RequestRecord, RequestStats};
+use project::Project;
+use ui::prelude::*;
+use workspace::{
+    item::{Item, TabContentParams},
+    SplitDirection, Workspace,
+};
+
+actions!(debug, [OpenLanguageServerProfiler]);
+
+const REFRESH_INTERVAL: Duration = Duration::from_secs(1);
+const SLOWEST_REQUESTS_SHOWN: usize = 10;
+
+pub fn init(cx: &mut AppContext) {
+    cx.observe_new_views(|workspace: &mut Workspace, _| {
+        workspace.register_action(|workspace, _: &OpenLanguageServerProfiler, cx| {
+            let project = workspace.project().clone();
+            let profiler = cx.new_view(|cx| LspProfilerView::new(project, cx));
+            workspace.split_item(SplitDirection::Right, Box::new(profiler), cx)
+        });
+    })
+    .detach();
+}
+
+/// Shows the request latencies of the project's language servers: aggregates per method, and the slowest recent requests.
+pub struct LspProfilerView {
+    project: Model<Project>,
+    focus_handle: FocusHandle,
+    _refresh: Task<()>,
+}
+
+impl LspProfilerView {
+    fn new(project: Model<Project>, cx: &mut ViewContext<Self>) -> Self {
+        let refresh = cx.spawn(|this, mut cx| async move {
+            loop {
+                cx.background_executor().timer(REFRESH_INTERVAL).await;
+                if this.update(&mut cx, |_, cx| cx.notify()).is_err() {
+                    break;
+                }
+            }
+        });
+        Self {
+            project,
+            focus_handle: cx.focus_handle(),
+            _refresh: refresh,
+        }
+    }
+
+    fn clear_stats(&mut self, server_id: LanguageServerId, cx: &mut ViewContext<Self>) {
+        if let Some(server) = self.project.read(cx).language_server_for_id(server_id) {
+            server.clear_request_stats();
+        }
+        cx.notify();
+    }
+
+    fn render_server(
+        &self,
+        server_id: LanguageServerId,
+        name: String,
+        stats: RequestStats,
+        cx: &mut ViewContext<Self>,
+    ) -> impl IntoElement {
+        v_flex()
+            .gap_1()
+            .pb_4()
+            .child(
+                h_flex()
+                    .gap_2()
+                    .child(Label::new(name).size(LabelSize::Large))
+                    .child(
+                        Button::new(("clear-lsp-stats", server_id.0), "Clear").on_click(
+                            cx.listener(move |this, _, cx| this.clear_stats(server_id, cx)),
+                        ),
+                    ),
+            )
+            .child(render_row(
+                [
+                    "Method",
+                    "Count",
+                    "Mean",
+                    "Max",
+                    "Failed",
+                    "Cancelled",
+                    "Timed Out",
+                    "Sent",
+                    "Received",
+                ]
+                .map(String::from),
+                Color::Muted,
+            ))
+            .children(stats.methods().map(|(method, stats)| {
+                render_row(
+                    [
+                        method.to_string(),
+                        stats.count.to_string(),
+                        format!("{:?}", stats.mean_duration()),
+                        format!("{:?}", stats.max_duration),
+                        stats.failed.to_string(),
+                        stats.cancelled.to_string(),
+                        stats.timed_out.to_string(),
+                        format_size(stats.total_request_size),
+                        format_size(stats.total_response_size),
+                    ],
+                    Color::Default,
+                )
+            }))
+            .child(Label::new("Slowest recent requests").color(Color::Muted))
+            .children(
+                stats
+                    .slowest_requests(SLOWEST_REQUESTS_SHOWN)
+                    .into_iter()
+                    .map(render_request),
+            )
+    }
+}
+
+fn render_row<const N: usize>(cells: [String; N], color: Color) -> impl IntoElement {
+    h_flex()
+        .gap_2()
+        .children(cells.into_iter().enumerate().map(move |(ix, cell)| {
+            div()
+                .w(if ix == 0 { rems(20.) } else { rems(6.) })
+                .child(Label::new(cell).color(color))
+        }))
+}
+
+fn render_request(request: RequestRecord) -> impl IntoElement {
+    let (outcome, color) = match request.outcome {
+        RequestOutcome::Succeeded => ("", Color::Default),
+        RequestOutcome::Failed => ("failed", Color::Error),
+        RequestOutcome::Cancelled => ("cancelled", Color::Muted),
+        RequestOutcome::TimedOut => ("timed out", Color::Warning),
+    };
+    h_flex()
+        .gap_2()
+        .child(Label::new(format!("{:?}", request.duration)))
+        .child(Label::new(format!("{} #{}", request.method, request.id)))
+        .child(
+            Label::new(format!(
+                "{} / {}",
+                format_size(request.request_size),
+                format_size(request.response_size)
+            ))
+            .color(Color::Muted),
+        )
+        .child(Label::new(outcome).color(color))
+}
+
+fn format_size(bytes: usize) -> String {
+    if bytes < 1024 {
+        format!("{bytes} B")
+    } else if bytes < 1024 * 1024 {
+        format!("{:.1} KiB", bytes as f64 / 1024.)
+    } else {
+        format!("{:.1} MiB", bytes as f64 / (1024. * 1024.))
+    }
+}
+
+impl Render for LspProfilerView {
+    fn render(&mut self, cx: &mut ViewContext<Self>) -> impl IntoElement {
+        let project = self.project.read(cx);
+        let servers = project
+            .language_server_statuses()
+            .filter_map(|(server_id, status)| {
+                let stats = project.language_server_request_stats(server_id)?;
+                Some((server_id, status.name.clone(), stats))
+            })
+            .collect::<Vec<_>>();
+        v_flex()
+            .id("lsp-profiler")
+            .size_full()
+            .p_2()
+            .overflow_y_scroll()
+            .bg(cx.theme().colors().editor_background)
+            .when(servers.is_empty(), |this| {
+                this.child(Label::new("No local language servers are running").color(Color::Muted))
+            })
+            .children(
+                servers
+                    .into_iter()
+                    .map(|(server_id, name, stats)| self.render_server(server_id, name, stats, cx)),
+            )
+    }
+}
+
+impl EventEmitter<()> for LspProfilerView {}
+
+impl FocusableView for LspProfilerView {
+    fn focus_handle(&self, _: &AppContext) -> FocusHandle {
+        self.focus_handle.clone()
+    }
+}
+
+impl Item for LspProfilerView {
+    type Event = ();
+
+    fn to_item_events(_: &Self::Event, _: impl FnMut(workspace::item::ItemEvent)) {}
+
+    fn tab_content(&self, params: TabContentParams, _: &WindowContext<'_>) -> AnyElement {
+        Label::new("Language Server Profiler")
+            .color(if params.selected {
+                Color::Default
+            } else {
+                Color::Muted
+            })
+            .into_any_element()
+    }
+
+    fn telemetry_event_text(&self) -> Option<&'static str> {
+        None
+    }
+
+    fn clone_on_split(
+        &self,
+        _: Option<workspace::WorkspaceId>,
+        cx: &mut ViewContext<Self>,
+    ) -> Option<View<Self>>
+    where
+        Self: Sized,
+    {
+        Some(cx.new_view(|cx| Self::new(self.project.clone(), cx)))
+    }
+}
@@ -247,6 +247,11 @@ impl super::LspAdapter for GoLspAdapter {
         })))
     }
 
+    fn roots(&self) -> LanguageServerRoots {
+        // A workspace spans the modules it uses, which are otherwise served on their own.
+        LanguageServerRoots::Nearest(vec!["go.work".into(), "go.mod".into()])
+    }
+
     async fn label_for_completion(
         &self,
         completion: &lsp::CompletionItem,
@@ -1,22 +1,20 @@
 use anyhow::{anyhow, bail, Context, Result};
-use async_compression::futures::bufread::GzipDecoder;
-use async_tar::Archive;
 use async_trait::async_trait;
 use collections::HashMap;
 use feature_flags::FeatureFlagAppExt;
 use futures::StreamExt;
 use gpui::{AppContext, AsyncAppContext};
-use http::github::{latest_github_release, GitHubLspBinaryVersion};
-use language::{LanguageRegistry, LanguageServerName, LspAdapter, LspAdapterDelegate};
+use http::github::latest_github_release;
+use language::{
+    cached_server_artifact, install_server_artifact, ArtifactChecksum, ArtifactFormat,
+    LanguageRegistry, LanguageServerName, LspAdapter, LspAdapterDelegate, ServerArtifact,
+};
 use lsp::LanguageServerBinary;
 use node_runtime::NodeRuntime;
 use project::ContextProviderWithTasks;
 use serde_json::{json, Value};
 use settings::{KeymapFile, SettingsJsonSchemaParams, SettingsStore};
-use smol::{
-    fs::{self},
-    io::BufReader,
-};
+use smol::fs;
 use std::{
     any::Any,
     env::consts,
@@ -26,7 +24,7 @@ use std::{
     sync::{Arc, OnceLock},
 };
 use task::{TaskTemplate, TaskTemplates, VariableName};
-use util::{fs::remove_matching, maybe, ResultExt};
+use util::{maybe, ResultExt};
 
 const SERVER_PATH: &str =
     "node_modules/vscode-langservers-extracted/bin/vscode-json-language-server";
@@ -264,6 +262,8 @@ fn schema_file_match(path: &Path) -> String {
         .replace('\\', "/")
 }
 
+const VERSION_SERVER_BINARY_NAME: &str = "package-version-server";
+
 pub(super) struct NodeVersionAdapter;
 
 #[async_trait(?Send)]
@@ -300,9 +300,14 @@ impl LspAdapter for NodeVersionAdapter {
             .iter()
             .find(|asset| asset.name == asset_name)
             .with_context(|| format!("no asset found matching `{asset_name:?}`"))?;
-        Ok(Box::new(GitHubLspBinaryVersion {
-            name: release.tag_name,
+        Ok(Box::new(ServerArtifact {
+            version: release.tag_name,
             url: asset.browser_download_url.clone(),
+            format: ArtifactFormat::from_file_name(&asset.name),
+            checksum: asset
+                .sha256_digest()
+                .map(|digest| ArtifactChecksum::Sha256(digest.into())),
+            binary_path: PathBuf::from(VERSION_SERVER_BINARY_NAME),
         }))
     }
 
@@ -312,48 +317,10 @@ impl LspAdapter for NodeVersionAdapter {
         container_dir: PathBuf,
         delegate: &dyn LspAdapterDelegate,
     ) -> Result<LanguageServerBinary> {
-        let version = latest_version.downcast::<GitHubLspBinaryVersion>().unwrap();
-        let destination_path =
-            container_dir.join(format!("package-version-server-{}", version.name));
-        let destination_container_path =
-            container_dir.join(format!("package-version-server-{}-tmp", version.name));
-        if fs::metadata(&destination_path).await.is_err() {
-            let mut response = delegate
-                .http_client()
-                .get(&version.url, Default::default(), true)
-                .await
-                .map_err(|err| anyhow!("error downloading release: {}", err))?;
-            if version.url.ends_with(".zip") {
-                node_runtime::extract_zip(
-                    &destination_container_path,
-                    BufReader::new(response.body_mut()),
-                )
-                .await?;
-            } else if version.url.ends_with(".tar.gz") {
-                let decompressed_bytes = GzipDecoder::new(BufReader::new(response.body_mut()));
-                let archive = Archive::new(decompressed_bytes);
-                archive.unpack(&destination_container_path).await?;
-            }
-
-            fs::copy(
-                destination_container_path.join("package-version-server"),
-                &destination_path,
-            )
-            .await?;
-            // todo("windows")
-            #[cfg(not(windows))]
-            {
-                fs::set_permissions(
-                    &destination_path,
-                    <fs::Permissions as fs::unix::PermissionsExt>::from_mode(0o755),
-                )
-                .await?;
-            }
-            remove_matching(&container_dir, |entry| entry != destination_path).await;
-        }
-
+        let artifact = latest_version.downcast::<ServerArtifact>().unwrap();
+        let path = install_server_artifact(&artifact, &container_dir, delegate).await?;
         Ok(LanguageServerBinary {
-            path: destination_path,
+            path,
             env: None,
             arguments: Default::default(),
         })
@@ -381,19 +348,11 @@ impl LspAdapter for NodeVersionAdapter {
 }
 
 async fn get_cached_version_server_binary(container_dir: PathBuf) -> Option<LanguageServerBinary> {
-    maybe!(async {
-        let mut last = None;
-        let mut entries = fs::read_dir(&container_dir).await?;
-        while let Some(entry) = entries.next().await {
-            last = Some(entry?.path());
-        }
-
-        anyhow::Ok(LanguageServerBinary {
-            path: last.ok_or_else(|| anyhow!("no cached binary"))?,
-            env: None,
-            arguments: Default::default(),
-        })
+    let path =
+        cached_server_artifact(&container_dir, Path::new(VERSION_SERVER_BINARY_NAME)).await?;
+    Some(LanguageServerBinary {
+        path,
+        env: None,
+        arguments: Default::default(),
     })
-    .await
-    .log_err()
 }
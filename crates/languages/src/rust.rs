@@ -1,16 +1,13 @@
-use anyhow::{anyhow, bail, Context, Result};
-use async_compression::futures::bufread::GzipDecoder;
+use anyhow::{bail, Context, Result};
 use async_trait::async_trait;
-use futures::{io::BufReader, StreamExt};
 use gpui::AsyncAppContext;
-use http::github::{latest_github_release, GitHubLspBinaryVersion};
+use http::github::latest_github_release;
 pub use language::*;
 use lazy_static::lazy_static;
 use lsp::LanguageServerBinary;
 use project::project_settings::{BinarySettings, ProjectSettings};
 use regex::Regex;
 use settings::Settings;
-use smol::fs::{self, File};
 use std::{
     any::Any,
     borrow::Cow,
@@ -19,7 +16,7 @@ use std::{
     sync::Arc,
 };
 use task::{TaskTemplate, TaskTemplates, TaskVariables, VariableName};
-use util::{fs::remove_matching, maybe, ResultExt};
+use util::ResultExt;
 
 pub struct RustLspAdapter;
 
@@ -104,9 +101,14 @@ impl LspAdapter for RustLspAdapter {
             .iter()
             .find(|asset| asset.name == asset_name)
             .with_context(|| format!("no asset found matching `{asset_name:?}`"))?;
-        Ok(Box::new(GitHubLspBinaryVersion {
-            name: release.tag_name,
+        Ok(Box::new(ServerArtifact {
+            version: release.tag_name,
             url: asset.browser_download_url.clone(),
+            format: ArtifactFormat::Gz,
+            checksum: asset
+                .sha256_digest()
+                .map(|digest| ArtifactChecksum::Sha256(digest.into())),
+            binary_path: PathBuf::from(Self::SERVER_NAME),
         }))
     }
 
@@ -116,33 +118,10 @@ impl LspAdapter for RustLspAdapter {
         container_dir: PathBuf,
         delegate: &dyn LspAdapterDelegate,
     ) -> Result<LanguageServerBinary> {
-        let version = version.downcast::<GitHubLspBinaryVersion>().unwrap();
-        let destination_path = container_dir.join(format!("rust-analyzer-{}", version.name));
-
-        if fs::metadata(&destination_path).await.is_err() {
-            let mut response = delegate
-                .http_client()
-                .get(&version.url, Default::default(), true)
-                .await
-                .map_err(|err| anyhow!("error downloading release: {}", err))?;
-            let decompressed_bytes = GzipDecoder::new(BufReader::new(response.body_mut()));
-            let mut file = File::create(&destination_path).await?;
-            futures::io::copy(decompressed_bytes, &mut file).await?;
-            // todo("windows")
-            #[cfg(not(windows))]
-            {
-                fs::set_permissions(
-                    &destination_path,
-                    <fs::Permissions as fs::unix::PermissionsExt>::from_mode(0o755),
-                )
-                .await?;
-            }
-
-            remove_matching(&container_dir, |entry| entry != destination_path).await;
-        }
-
+        let artifact = version.downcast::<ServerArtifact>().unwrap();
+        let path = install_server_artifact(&artifact, &container_dir, delegate).await?;
         Ok(LanguageServerBinary {
-            path: destination_path,
+            path,
             env: None,
             arguments: Default::default(),
         })
@@ -614,21 +593,13 @@ fn package_name_from_pkgid(pkgid: &str) -> Option<&str> {
 }
 
 async fn get_cached_server_binary(container_dir: PathBuf) -> Option<LanguageServerBinary> {
-    maybe!(async {
-        let mut last = None;
-        let mut entries = fs::read_dir(&container_dir).await?;
-        while let Some(entry) = entries.next().await {
-            last = Some(entry?.path());
-        }
-
-        anyhow::Ok(LanguageServerBinary {
-            path: last.ok_or_else(|| anyhow!("no cached binary"))?,
-            env: None,
-            arguments: Default::default(),
-        })
+    let path =
+        cached_server_artifact(&container_dir, Path::new(RustLspAdapter::SERVER_NAME)).await?;
+    Some(LanguageServerBinary {
+        path,
+        env: None,
+        arguments: Default::default(),
     })
-    .await
-    .log_err()
 }
 
 #[cfg(test)]
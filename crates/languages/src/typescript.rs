@@ -284,25 +284,9 @@ impl EsLintLspAdapter {
     pub fn new(node: Arc<dyn NodeRuntime>) -> Self {
         EsLintLspAdapter { node }
     }
-}
-
-#[async_trait(?Send)]
-impl LspAdapter for EsLintLspAdapter {
-    async fn workspace_configuration(
-        self: Arc<Self>,
-        delegate: &Arc<dyn LspAdapterDelegate>,
-        cx: &mut AsyncAppContext,
-    ) -> Result<Value> {
-        let workspace_root = delegate.worktree_root_path();
-
-        let eslint_user_settings = cx.update(|cx| {
-            ProjectSettings::get_global(cx)
-                .lsp
-                .get(Self::SERVER_NAME)
-                .and_then(|s| s.settings.clone())
-                .unwrap_or_default()
-        })?;
 
+    /// The configuration of the server, from the `eslint` settings of the `lsp` setting.
+    fn configuration(workspace_root: &Path, eslint_user_settings: &Value) -> Value {
         let mut code_action_on_save = json!({
             // We enable this, but without also configuring `code_actions_on_format`
             // in the Zed configuration, it doesn't have an effect.
@@ -339,7 +323,7 @@ impl LspAdapter for EsLintLspAdapter {
             .iter()
             .any(|file| workspace_root.join(file).is_file());
 
-        Ok(json!({
+        json!({
             "": {
                 "validate": "on",
                 "rulesCustomizations": rules_customizations,
@@ -366,7 +350,40 @@ impl LspAdapter for EsLintLspAdapter {
                     "useFlatConfig": use_flat_config,
                 },
             }
-        }))
+        })
+    }
+}
+
+#[async_trait(?Send)]
+impl LspAdapter for EsLintLspAdapter {
+    async fn workspace_configuration(
+        self: Arc<Self>,
+        delegate: &Arc<dyn LspAdapterDelegate>,
+        cx: &mut AsyncAppContext,
+    ) -> Result<Value> {
+        let workspace_root = delegate.worktree_root_path();
+
+        let eslint_user_settings = cx.update(|cx| {
+            ProjectSettings::get_global(cx)
+                .lsp
+                .get(Self::SERVER_NAME)
+                .and_then(|s| s.settings.clone())
+                .unwrap_or_default()
+        })?;
+
+        Ok(Self::configuration(workspace_root, &eslint_user_settings))
+    }
+
+    async fn scoped_workspace_configuration(
+        self: Arc<Self>,
+        delegate: &Arc<dyn LspAdapterDelegate>,
+        settings: Value,
+        _: &mut AsyncAppContext,
+    ) -> Result<Value> {
+        Ok(Self::configuration(
+            delegate.worktree_root_path(),
+            &settings,
+        ))
     }
 
     fn name(&self) -> LanguageServerName {
@@ -0,0 +1,280 @@
+use anyhow::{anyhow, Context as _, Result};
+use editor::{Direction, InlineCompletionProvider};
+use futures::{io::BufReader, stream::BoxStream, AsyncBufReadExt, AsyncReadExt, StreamExt};
+use gpui::{AppContext, EntityId, Model, ModelContext, Task};
+use http::{AsyncBody, HttpClient, Method, Request as HttpRequest};
+use language::{
+    language_settings::{all_language_settings, LocalCompletionApi, LocalInlineCompletionSettings},
+    Anchor, Buffer, ToOffset,
+};
+use serde::{Deserialize, Serialize};
+use std::{sync::Arc, time::Duration};
+use text::Bias;
+
+pub const DEBOUNCE_TIMEOUT: Duration = Duration::from_millis(150);
+
+/// Serves inline completions from a model running on a local HTTP server, like Ollama or llama.cpp.
+pub struct LocalCompletionProvider {
+    http_client: Arc<dyn HttpClient>,
+    completion: Option<LocalCompletion>,
+    pending_refresh: Task<Result<()>>,
+}
+
+/// A completion at a position of a buffer, growing as the server streams its tokens.
+struct LocalCompletion {
+    buffer_id: EntityId,
+    position: Anchor,
+    text: String,
+}
+
+impl LocalCompletionProvider {
+    pub fn new(http_client: Arc<dyn HttpClient>) -> Self {
+        Self {
+            http_client,
+            completion: None,
+            pending_refresh: Task::ready(Ok(())),
+        }
+    }
+}
+
+impl InlineCompletionProvider for LocalCompletionProvider {
+    fn name() -> &'static str {
+        "local"
+    }
+
+    fn is_enabled(&self, buffer: &Model<Buffer>, cursor_position: Anchor, cx: &AppContext) -> bool {
+        let buffer = buffer.read(cx);
+        let file = buffer.file();
+        let language = buffer.language_at(cursor_position);
+        let settings = all_language_settings(file, cx);
+        settings.inline_completions_enabled(language.as_ref(), file.map(|f| f.path().as_ref()))
+    }
+
+    fn refresh(
+        &mut self,
+        buffer_handle: Model<Buffer>,
+        cursor_position: Anchor,
+        debounce: bool,
+        cx: &mut ModelContext<Self>,
+    ) {
+        let settings = all_language_settings(None, cx)
+            .inline_completions
+            .local
+            .clone();
+        let snapshot = buffer_handle.read(cx).snapshot();
+        let offset = cursor_position.to_offset(&snapshot);
+        let prefix_start =
+            snapshot.clip_offset(offset.saturating_sub(settings.context_length), Bias::Left);
+        let suffix_end = snapshot.clip_offset(
+            (offset + settings.context_length).min(snapshot.len()),
+            Bias::Right,
+        );
+        let prefix = snapshot
+            .text_for_range(prefix_start..offset)
+            .collect::<String>();
+        let suffix = snapshot
+            .text_for_range(offset..suffix_end)
+            .collect::<String>();
+        let position = snapshot.anchor_before(offset);
+        let buffer_id = buffer_handle.entity_id();
+        let http_client = self.http_client.clone();
+
+        self.pending_refresh = cx.spawn(|this, mut cx| async move {
+            if debounce {
+                cx.background_executor().timer(DEBOUNCE_TIMEOUT).await;
+            }
+
+            let mut tokens = stream_completion(http_client, &settings, prefix, suffix).await?;
+            this.update(&mut cx, |this, cx| {
+                this.completion = Some(LocalCompletion {
+                    buffer_id,
+                    position,
+                    text: String::new(),
+                });
+                cx.notify();
+            })?;
+            while let Some(token) = tokens.next().await {
+                let token = token?;
+                this.update(&mut cx, |this, cx| {
+                    if let Some(completion) = this.completion.as_mut() {
+                        completion.text.push_str(&token);
+                        cx.notify();
+                    }
+                })?;
+            }
+            Ok(())
+        });
+    }
+
+    fn cycle(
+        &mut self,
+        _buffer: Model<Buffer>,
+        _cursor_position: Anchor,
+        _direction: Direction,
+        _cx: &mut ModelContext<Self>,
+    ) {
+    }
+
+    fn accept(&mut self, _cx: &mut ModelContext<Self>) {
+        self.pending_refresh = Task::ready(Ok(()));
+        self.completion = None;
+    }
+
+    fn discard(
+        &mut self,
+        _should_report_inline_completion_event: bool,
+        _cx: &mut ModelContext<Self>,
+    ) {
+        self.pending_refresh = Task::ready(Ok(()));
+        self.completion = None;
+    }
+
+    fn active_completion_text<'a>(
+        &'a self,
+        buffer: &Model<Buffer>,
+        cursor_position: Anchor,
+        cx: &'a AppContext,
+    ) -> Option<&'a str> {
+        let completion = self.completion.as_ref()?;
+        if completion.buffer_id != buffer.entity_id() {
+            return None;
+        }
+        let text = completion_text_after_typing(completion, buffer, cursor_position, cx)?;
+        let text = text.trim_end();
+        if text.trim().is_empty() {
+            None
+        } else {
+            Some(text)
+        }
+    }
+}
+
+/// The rest of the completion, after the text typed since it was requested, if the typed text matches it.
+fn completion_text_after_typing<'a>(
+    completion: &'a LocalCompletion,
+    buffer: &Model<Buffer>,
+    cursor_position: Anchor,
+    cx: &AppContext,
+) -> Option<&'a str> {
+    let snapshot = buffer.read(cx).snapshot();
+    let start = completion.position.to_offset(&snapshot);
+    let end = cursor_position.to_offset(&snapshot);
+    if end < start {
+        return None;
+    }
+    let typed = snapshot.text_for_range(start..end).collect::<String>();
+    completion.text.strip_prefix(typed.as_str())
+}
+
+/// Requests a completion of the text between the prefix and the suffix, yielding its tokens as they are generated.
+async fn stream_completion(
+    http_client: Arc<dyn HttpClient>,
+    settings: &LocalInlineCompletionSettings,
+    prefix: String,
+    suffix: String,
+) -> Result<BoxStream<'static, Result<String>>> {
+    match settings.api {
+        LocalCompletionApi::Ollama => {
+            let request = ollama::GenerateRequest {
+                model: settings.model.clone(),
+                prompt: prefix,
+                suffix: Some(suffix),
+                stream: true,
+                keep_alive: ollama::KeepAlive::default(),
+                options: Some(ollama::ChatOptions {
+                    num_predict: Some(settings.max_tokens as isize),
+                    stop: Some(vec!["\n\n".into()]),
+                    temperature: Some(0.),
+                    ..Default::default()
+                }),
+            };
+            let deltas =
+                ollama::stream_generate(http_client.as_ref(), &settings.api_url, request, None)
+                    .await?;
+            Ok(deltas
+                .map(|delta| delta.map(|delta| delta.response))
+                .boxed())
+        }
+        LocalCompletionApi::LlamaCpp => {
+            stream_llama_cpp_infill(http_client.as_ref(), settings, prefix, suffix).await
+        }
+    }
+}
+
+#[derive(Serialize)]
+struct InfillRequest {
+    input_prefix: String,
+    input_suffix: String,
+    n_predict: u32,
+    stream: bool,
+}
+
+#[derive(Deserialize)]
+struct InfillEvent {
+    content: String,
+}
+
+async fn stream_llama_cpp_infill(
+    http_client: &dyn HttpClient,
+    settings: &LocalInlineCompletionSettings,
+    prefix: String,
+    suffix: String,
+) -> Result<BoxStream<'static, Result<String>>> {
+    let request = InfillRequest {
+        input_prefix: prefix,
+        input_suffix: suffix,
+        n_predict: settings.max_tokens,
+        stream: true,
+    };
+    let request = HttpRequest::builder()
+        .method(Method::POST)
+        .uri(format!("{}/infill", settings.api_url))
+        .header("Content-Type", "application/json")
+        .body(AsyncBody::from(serde_json::to_string(&request)?))?;
+    let mut response = http_client.send(request).await?;
+    if response.status().is_success() {
+        let reader = BufReader::new(response.into_body());
+        Ok(reader
+            .lines()
+            .filter_map(|line| async move {
+                match line {
+                    Ok(line) => parse_infill_event(&line).transpose(),
+                    Err(error) => Some(Err(error.into())),
+                }
+            })
+            .boxed())
+    } else {
+        let mut body = String::new();
+        response.body_mut().read_to_string(&mut body).await?;
+        Err(anyhow!(
+            "Failed to connect to the llama.cpp server: {} {}",
+            response.status(),
+            body,
+        ))
+    }
+}
+
+/// Parses a line of the server-sent events of `/infill`, returning the content of data lines.
+fn parse_infill_event(line: &str) -> Result<Option<String>> {
+    let Some(data) = line.strip_prefix("data: ") else {
+        return Ok(None);
+    };
+    let event = serde_json::from_str::<InfillEvent>(data)
+        .with_context(|| format!("Unable to parse infill event {data:?}"))?;
+    Ok(Some(event.content))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_infill_event() {
+        assert_eq!(
+            parse_infill_event(r#"data: {"content":"foo","stop":false}"#).unwrap(),
+            Some("foo".to_string())
+        );
+        assert_eq!(parse_infill_event("").unwrap(), None);
+        assert!(parse_infill_event("data: {").is_err());
+    }
+}
@@ -13,7 +13,8 @@ use parking_lot::Mutex;
 use smol::io::BufReader;
 
 use crate::{
-    AnyNotification, AnyResponse, IoHandler, IoKind, RequestId, ResponseHandler, CONTENT_LEN_HEADER,
+    middleware::MiddlewareState, AnyNotification, AnyResponse, IoHandler, IoKind, MessageDirection,
+    RequestId, ResponseHandler, CONTENT_LEN_HEADER,
 };
 
 const HEADER_DELIMITER: &'static [u8; 4] = b"\r\n\r\n";
@@ -48,13 +49,20 @@ impl LspStdoutHandler {
         stdout: Input,
         response_handlers: Arc<Mutex<Option<HashMap<RequestId, ResponseHandler>>>>,
         io_handlers: Arc<Mutex<HashMap<i32, IoHandler>>>,
+        middleware: Arc<MiddlewareState>,
         cx: BackgroundExecutor,
     ) -> Self
     where
         Input: AsyncRead + Unpin + Send + 'static,
     {
         let (tx, notifications_channel) = unbounded();
-        let loop_handle = cx.spawn(Self::handler(stdout, tx, response_handlers, io_handlers));
+        let loop_handle = cx.spawn(Self::handler(
+            stdout,
+            tx,
+            response_handlers,
+            io_handlers,
+            middleware,
+        ));
         Self {
             loop_handle,
             notifications_channel,
@@ -66,6 +74,7 @@ impl LspStdoutHandler {
         notifications_sender: UnboundedSender<AnyNotification>,
         response_handlers: Arc<Mutex<Option<HashMap<RequestId, ResponseHandler>>>>,
         io_handlers: Arc<Mutex<HashMap<i32, IoHandler>>>,
+        middleware: Arc<MiddlewareState>,
     ) -> anyhow::Result<()>
     where
         Input: AsyncRead + Unpin + Send + 'static,
@@ -99,6 +108,18 @@ impl LspStdoutHandler {
                 }
             }
 
+            let rewritten = match str::from_utf8(&buffer) {
+                Ok(message) => {
+                    middleware
+                        .rewrite(MessageDirection::FromServer, message)
+                        .await
+                }
+                Err(_) => None,
+            };
+            if let Some(message) = rewritten {
+                buffer = message.into_bytes();
+            }
+
             if let Ok(msg) = serde_json::from_slice::<AnyNotification>(&buffer) {
                 notifications_sender.unbounded_send(msg)?;
             } else if let Ok(AnyResponse {
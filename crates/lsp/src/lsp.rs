@@ -1,14 +1,20 @@
 mod input_handler;
+mod middleware;
+mod request_stats;
 
 pub use lsp_types::request::*;
 pub use lsp_types::*;
+pub use middleware::{InterceptedMessage, MessageDirection, MessageKind, MessageMiddleware};
+pub use request_stats::{MethodStats, RequestOutcome, RequestRecord, RequestStats};
 
 use anyhow::{anyhow, Context, Result};
 use collections::HashMap;
 use futures::{channel::oneshot, io::BufWriter, select, AsyncRead, AsyncWrite, Future, FutureExt};
 use gpui::{AppContext, AsyncAppContext, BackgroundExecutor, Task};
+use middleware::MiddlewareState;
 use parking_lot::Mutex;
 use postage::{barrier, prelude::Stream};
+use request_stats::PendingRequest;
 use serde::{de::DeserializeOwned, Deserialize, Serialize};
 use serde_json::{json, value::RawValue, Value};
 use smol::{
@@ -39,12 +45,14 @@ use util::{ResultExt, TryFutureExt};
 const JSON_RPC_VERSION: &str = "2.0";
 const CONTENT_LEN_HEADER: &str = "Content-Length: ";
 
-const LSP_REQUEST_TIMEOUT: Duration = Duration::from_secs(60 * 2);
+/// The timeout of requests without a configured one.
+pub const LSP_REQUEST_TIMEOUT: Duration = Duration::from_secs(60 * 2);
 const SERVER_SHUTDOWN_TIMEOUT: Duration = Duration::from_secs(5);
 
 type NotificationHandler = Box<dyn Send + FnMut(Option<RequestId>, Value, AsyncAppContext)>;
 type ResponseHandler = Box<dyn Send + FnOnce(Result<String, Error>)>;
 type IoHandler = Box<dyn Send + FnMut(IoKind, &str)>;
+type RequestTimeoutHandler = Box<dyn Send + FnMut(&'static str, Duration)>;
 
 /// Kind of language server stdio given to an IO handler.
 #[derive(Debug, Clone, Copy)]
@@ -63,6 +71,34 @@ pub struct LanguageServerBinary {
     pub env: Option<HashMap<String, String>>,
 }
 
+/// Deadlines of the requests by method, after which they are cancelled with `$/cancelRequest`.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct RequestTimeouts {
+    pub default: Duration,
+    pub methods: HashMap<String, Duration>,
+}
+
+impl Default for RequestTimeouts {
+    fn default() -> Self {
+        Self {
+            default: LSP_REQUEST_TIMEOUT,
+            methods: HashMap::default(),
+        }
+    }
+}
+
+impl RequestTimeouts {
+    pub fn for_method(&self, method: &str) -> Duration {
+        self.methods.get(method).copied().unwrap_or(self.default)
+    }
+}
+
+#[derive(Default)]
+struct RequestTimeoutState {
+    timeouts: RequestTimeouts,
+    on_timeout: Option<RequestTimeoutHandler>,
+}
+
 /// A running language server process.
 pub struct LanguageServer {
     server_id: LanguageServerId,
@@ -74,10 +110,14 @@ pub struct LanguageServer {
     notification_handlers: Arc<Mutex<HashMap<&'static str, NotificationHandler>>>,
     response_handlers: Arc<Mutex<Option<HashMap<RequestId, ResponseHandler>>>>,
     io_handlers: Arc<Mutex<HashMap<i32, IoHandler>>>,
+    middleware: Arc<MiddlewareState>,
+    request_stats: Arc<Mutex<RequestStats>>,
+    request_timeouts: Arc<Mutex<RequestTimeoutState>>,
     executor: BackgroundExecutor,
     #[allow(clippy::type_complexity)]
     io_tasks: Mutex<Option<(Task<Option<()>>, Task<Option<()>>)>>,
     output_done_rx: Mutex<Option<barrier::Receiver>>,
+    stopped_rx: barrier::Receiver,
     root_path: PathBuf,
     working_dir: PathBuf,
     server: Arc<Mutex<Option<Child>>>,
@@ -330,17 +370,20 @@ impl LanguageServer {
     {
         let (outbound_tx, outbound_rx) = channel::unbounded::<String>();
         let (output_done_tx, output_done_rx) = barrier::channel();
+        let (stopped_tx, stopped_rx) = barrier::channel();
         let notification_handlers =
             Arc::new(Mutex::new(HashMap::<_, NotificationHandler>::default()));
         let response_handlers =
             Arc::new(Mutex::new(Some(HashMap::<_, ResponseHandler>::default())));
         let io_handlers = Arc::new(Mutex::new(HashMap::default()));
+        let middleware = Arc::new(MiddlewareState::default());
 
         let stdout_input_task = cx.spawn({
             let on_unhandled_notification = on_unhandled_notification.clone();
             let notification_handlers = notification_handlers.clone();
             let response_handlers = response_handlers.clone();
             let io_handlers = io_handlers.clone();
+            let middleware = middleware.clone();
             move |cx| {
                 Self::handle_input(
                     stdout,
@@ -348,6 +391,7 @@ impl LanguageServer {
                     notification_handlers,
                     response_handlers,
                     io_handlers,
+                    middleware,
                     cx,
                 )
                 .log_err()
@@ -362,6 +406,7 @@ impl LanguageServer {
             .unwrap_or_else(|| Task::Ready(Some(None)));
         let input_task = cx.spawn(|_| async move {
             let (stdout, stderr) = futures::join!(stdout_input_task, stderr_input_task);
+            drop(stopped_tx);
             stdout.or(stderr)
         });
         let output_task = cx.background_executor().spawn({
@@ -371,6 +416,7 @@ impl LanguageServer {
                 output_done_tx,
                 response_handlers.clone(),
                 io_handlers.clone(),
+                middleware.clone(),
             )
             .log_err()
         });
@@ -380,6 +426,9 @@ impl LanguageServer {
             notification_handlers,
             response_handlers,
             io_handlers,
+            middleware,
+            request_stats: Default::default(),
+            request_timeouts: Default::default(),
             name: "".into(),
             capabilities: Default::default(),
             code_action_kinds,
@@ -388,12 +437,18 @@ impl LanguageServer {
             executor: cx.background_executor().clone(),
             io_tasks: Mutex::new(Some((input_task, output_task))),
             output_done_rx: Mutex::new(Some(output_done_rx)),
+            stopped_rx,
             root_path: root_path.to_path_buf(),
             working_dir: working_dir.to_path_buf(),
             server: Arc::new(Mutex::new(server)),
         }
     }
 
+    /// Sets the middleware that observes and rewrites the messages exchanged with the language server from now on.
+    pub fn set_message_middleware(&self, middleware: Option<Arc<dyn MessageMiddleware>>) {
+        self.middleware.set(middleware);
+    }
+
     /// List of code action kinds this language server reports being able to emit.
     pub fn code_action_kinds(&self) -> Option<Vec<CodeActionKind>> {
         self.code_action_kinds.clone()
@@ -405,6 +460,7 @@ impl LanguageServer {
         notification_handlers: Arc<Mutex<HashMap<&'static str, NotificationHandler>>>,
         response_handlers: Arc<Mutex<Option<HashMap<RequestId, ResponseHandler>>>>,
         io_handlers: Arc<Mutex<HashMap<i32, IoHandler>>>,
+        middleware: Arc<MiddlewareState>,
         cx: AsyncAppContext,
     ) -> anyhow::Result<()>
     where
@@ -423,6 +479,7 @@ impl LanguageServer {
             stdout,
             response_handlers,
             io_handlers,
+            middleware,
             cx.background_executor().clone(),
         );
 
@@ -484,6 +541,7 @@ impl LanguageServer {
         output_done_tx: barrier::Sender,
         response_handlers: Arc<Mutex<Option<HashMap<RequestId, ResponseHandler>>>>,
         io_handlers: Arc<Mutex<HashMap<i32, IoHandler>>>,
+        middleware: Arc<MiddlewareState>,
     ) -> anyhow::Result<()>
     where
         Stdin: AsyncWrite + Unpin + Send + 'static,
@@ -496,7 +554,13 @@ impl LanguageServer {
             }
         });
         let mut content_len_buffer = Vec::new();
-        while let Ok(message) = outbound_rx.recv().await {
+        while let Ok(mut message) = outbound_rx.recv().await {
+            if let Some(rewritten) = middleware
+                .rewrite(MessageDirection::ToServer, &message)
+                .await
+            {
+                message = rewritten;
+            }
             log::trace!("outgoing message:{}", message);
             for handler in io_handlers.lock().values_mut() {
                 handler(IoKind::StdIn, &message);
@@ -542,14 +606,22 @@ impl LanguageServer {
                     }),
                     workspace_folders: Some(true),
                     symbol: Some(WorkspaceSymbolClientCapabilities {
-                        resolve_support: None,
+                        resolve_support: Some(WorkspaceSymbolResolveSupportCapability {
+                            properties: vec!["location.range".to_string()],
+                        }),
                         ..WorkspaceSymbolClientCapabilities::default()
                     }),
                     inlay_hint: Some(InlayHintWorkspaceClientCapabilities {
                         refresh_support: Some(true),
                     }),
                     diagnostic: Some(DiagnosticWorkspaceClientCapabilities {
-                        refresh_support: None,
+                        refresh_support: Some(true),
+                    }),
+                    semantic_tokens: Some(SemanticTokensWorkspaceClientCapabilities {
+                        refresh_support: Some(true),
+                    }),
+                    inline_value: Some(InlineValueWorkspaceClientCapabilities {
+                        refresh_support: Some(true),
                     }),
                     workspace_edit: Some(WorkspaceEditClientCapabilities {
                         resource_operations: Some(vec![
@@ -561,6 +633,15 @@ impl LanguageServer {
                         snippet_edit_support: Some(true),
                         ..WorkspaceEditClientCapabilities::default()
                     }),
+                    file_operations: Some(WorkspaceFileOperationsClientCapabilities {
+                        will_create: Some(true),
+                        did_create: Some(true),
+                        will_rename: Some(true),
+                        did_rename: Some(true),
+                        will_delete: Some(true),
+                        did_delete: Some(true),
+                        ..WorkspaceFileOperationsClientCapabilities::default()
+                    }),
                     ..Default::default()
                 }),
                 text_document: Some(TextDocumentClientCapabilities {
@@ -609,6 +690,7 @@ impl LanguageServer {
                                 "commitCharacters".to_owned(),
                                 "editRange".to_owned(),
                                 "insertTextMode".to_owned(),
+                                "insertTextFormat".to_owned(),
                                 "data".to_owned(),
                             ]),
                         }),
@@ -623,6 +705,20 @@ impl LanguageServer {
                         content_format: Some(vec![MarkupKind::Markdown]),
                         dynamic_registration: None,
                     }),
+                    signature_help: Some(SignatureHelpClientCapabilities {
+                        signature_information: Some(SignatureInformationSettings {
+                            documentation_format: Some(vec![
+                                MarkupKind::Markdown,
+                                MarkupKind::PlainText,
+                            ]),
+                            parameter_information: Some(ParameterInformationSettings {
+                                label_offset_support: Some(true),
+                            }),
+                            active_parameter_support: Some(true),
+                        }),
+                        context_support: Some(true),
+                        dynamic_registration: None,
+                    }),
                     inlay_hint: Some(InlayHintClientCapabilities {
                         resolve_support: Some(InlayHintResolveClientCapabilities {
                             properties: vec![
@@ -639,12 +735,95 @@ impl LanguageServer {
                         related_information: Some(true),
                         ..Default::default()
                     }),
+                    diagnostic: Some(DiagnosticClientCapabilities {
+                        dynamic_registration: Some(false),
+                        related_document_support: Some(true),
+                    }),
                     formatting: Some(DynamicRegistrationClientCapabilities {
                         dynamic_registration: None,
                     }),
                     on_type_formatting: Some(DynamicRegistrationClientCapabilities {
                         dynamic_registration: None,
                     }),
+                    type_hierarchy: Some(DynamicRegistrationClientCapabilities {
+                        dynamic_registration: None,
+                    }),
+                    call_hierarchy: Some(DynamicRegistrationClientCapabilities {
+                        dynamic_registration: None,
+                    }),
+                    linked_editing_range: Some(DynamicRegistrationClientCapabilities {
+                        dynamic_registration: None,
+                    }),
+                    document_link: Some(DocumentLinkClientCapabilities {
+                        dynamic_registration: None,
+                        tooltip_support: Some(true),
+                    }),
+                    color_provider: Some(DynamicRegistrationClientCapabilities {
+                        dynamic_registration: None,
+                    }),
+                    inline_value: Some(InlineValueClientCapabilities {
+                        dynamic_registration: None,
+                    }),
+                    // Folds hide whole lines, so the ranges are only needed by line.
+                    folding_range: Some(FoldingRangeClientCapabilities {
+                        line_folding_only: Some(true),
+                        ..FoldingRangeClientCapabilities::default()
+                    }),
+                    document_symbol: Some(DocumentSymbolClientCapabilities {
+                        hierarchical_document_symbol_support: Some(true),
+                        tag_support: Some(TagSupport {
+                            value_set: vec![SymbolTag::DEPRECATED],
+                        }),
+                        ..DocumentSymbolClientCapabilities::default()
+                    }),
+                    semantic_tokens: Some(SemanticTokensClientCapabilities {
+                        dynamic_registration: Some(false),
+                        requests: SemanticTokensClientCapabilitiesRequests {
+                            range: Some(true),
+                            full: Some(SemanticTokensFullOptions::Delta { delta: Some(true) }),
+                        },
+                        token_types: vec![
+                            SemanticTokenType::NAMESPACE,
+                            SemanticTokenType::TYPE,
+                            SemanticTokenType::CLASS,
+                            SemanticTokenType::ENUM,
+                            SemanticTokenType::INTERFACE,
+                            SemanticTokenType::STRUCT,
+                            SemanticTokenType::TYPE_PARAMETER,
+                            SemanticTokenType::PARAMETER,
+                            SemanticTokenType::VARIABLE,
+                            SemanticTokenType::PROPERTY,
+                            SemanticTokenType::ENUM_MEMBER,
+                            SemanticTokenType::EVENT,
+                            SemanticTokenType::FUNCTION,
+                            SemanticTokenType::METHOD,
+                            SemanticTokenType::MACRO,
+                            SemanticTokenType::KEYWORD,
+                            SemanticTokenType::MODIFIER,
+                            SemanticTokenType::COMMENT,
+                            SemanticTokenType::STRING,
+                            SemanticTokenType::NUMBER,
+                            SemanticTokenType::REGEXP,
+                            SemanticTokenType::OPERATOR,
+                            SemanticTokenType::DECORATOR,
+                        ],
+                        token_modifiers: vec![
+                            SemanticTokenModifier::DECLARATION,
+                            SemanticTokenModifier::DEFINITION,
+                            SemanticTokenModifier::READONLY,
+                            SemanticTokenModifier::STATIC,
+                            SemanticTokenModifier::DEPRECATED,
+                            SemanticTokenModifier::ABSTRACT,
+                            SemanticTokenModifier::ASYNC,
+                            SemanticTokenModifier::MODIFICATION,
+                            SemanticTokenModifier::DOCUMENTATION,
+                            SemanticTokenModifier::DEFAULT_LIBRARY,
+                        ],
+                        formats: vec![TokenFormat::RELATIVE],
+                        overlapping_token_support: Some(false),
+                        multiline_token_support: Some(false),
+                        ..Default::default()
+                    }),
                     ..Default::default()
                 }),
                 experimental: Some(json!({
@@ -652,6 +831,7 @@ impl LanguageServer {
                 })),
                 window: Some(WindowClientCapabilities {
                     work_done_progress: Some(true),
+                    show_document: Some(ShowDocumentClientCapabilities { support: true }),
                     ..Default::default()
                 }),
                 general: None,
@@ -687,6 +867,8 @@ impl LanguageServer {
     pub fn shutdown(&self) -> Option<impl 'static + Send + Future<Output = Option<()>>> {
         if let Some(tasks) = self.io_tasks.lock().take() {
             let response_handlers = self.response_handlers.clone();
+            let request_stats = self.request_stats.clone();
+            let request_timeouts = self.request_timeouts.clone();
             let next_id = AtomicI32::new(self.next_id.load(SeqCst));
             let outbound_tx = self.outbound_tx.clone();
             let executor = self.executor.clone();
@@ -694,6 +876,8 @@ impl LanguageServer {
             let shutdown_request = Self::request_internal::<request::Shutdown>(
                 &next_id,
                 &response_handlers,
+                &request_stats,
+                &request_timeouts,
                 &outbound_tx,
                 &executor,
                 (),
@@ -734,6 +918,15 @@ impl LanguageServer {
         }
     }
 
+    /// Returns a future that resolves once the language server stops, either because its process exited and
+    /// closed its output or because it was shut down.
+    pub fn stopped(&self) -> impl 'static + Send + Future<Output = ()> {
+        let mut stopped = self.stopped_rx.clone();
+        async move {
+            stopped.recv().await;
+        }
+    }
+
     /// Register a handler to handle incoming LSP notifications.
     ///
     /// [LSP Specification](https://microsoft.github.io/language-server-protocol/specifications/lsp/3.17/specification/#notificationMessage)
@@ -900,6 +1093,29 @@ impl LanguageServer {
         self.server_id
     }
 
+    /// Get the latency and payload statistics of the requests sent to the language server so far.
+    pub fn request_stats(&self) -> RequestStats {
+        self.request_stats.lock().clone()
+    }
+
+    /// Forget the statistics of the requests sent so far.
+    pub fn clear_request_stats(&self) {
+        self.request_stats.lock().clear();
+    }
+
+    /// Set the deadlines of the requests sent from now on.
+    pub fn set_request_timeouts(&self, timeouts: RequestTimeouts) {
+        self.request_timeouts.lock().timeouts = timeouts;
+    }
+
+    /// Registers a handler called with the method and the timeout of every request that timed out and was cancelled.
+    pub fn on_request_timeout<F>(&self, f: F)
+    where
+        F: 'static + Send + FnMut(&'static str, Duration),
+    {
+        self.request_timeouts.lock().on_timeout = Some(Box::new(f));
+    }
+
     /// Get the root path of the project the language server is running against.
     pub fn root_path(&self) -> &PathBuf {
         &self.root_path
@@ -918,6 +1134,8 @@ impl LanguageServer {
         Self::request_internal::<T>(
             &self.next_id,
             &self.response_handlers,
+            &self.request_stats,
+            &self.request_timeouts,
             &self.outbound_tx,
             &self.executor,
             params,
@@ -927,6 +1145,8 @@ impl LanguageServer {
     fn request_internal<T: request::Request>(
         next_id: &AtomicI32,
         response_handlers: &Mutex<Option<HashMap<RequestId, ResponseHandler>>>,
+        request_stats: &Arc<Mutex<RequestStats>>,
+        request_timeouts: &Arc<Mutex<RequestTimeoutState>>,
         outbound_tx: &channel::Sender<String>,
         executor: &BackgroundExecutor,
         params: T::Params,
@@ -955,6 +1175,10 @@ impl LanguageServer {
                     Box::new(move |result| {
                         executor
                             .spawn(async move {
+                                let response_size = match &result {
+                                    Ok(response) => response.len(),
+                                    Err(error) => error.message.len(),
+                                };
                                 let response = match result {
                                     Ok(response) => match serde_json::from_str(&response) {
                                         Ok(deserialized) => Ok(deserialized),
@@ -965,23 +1189,29 @@ impl LanguageServer {
                                     }
                                     Err(error) => Err(anyhow!("{}", error.message)),
                                 };
-                                _ = tx.send(response);
+                                _ = tx.send((response_size, response));
                             })
                             .detach();
                     }),
                 );
             });
 
+        let pending_request =
+            PendingRequest::new(request_stats.clone(), id, T::METHOD, message.len());
         let send = outbound_tx
             .try_send(message)
             .context("failed to write to language server's stdin");
 
         let outbound_tx = outbound_tx.downgrade();
-        let mut timeout = executor.timer(LSP_REQUEST_TIMEOUT).fuse();
+        let request_timeout = request_timeouts.lock().timeouts.for_method(T::METHOD);
+        let mut timeout = executor.timer(request_timeout).fuse();
+        let request_timeouts = request_timeouts.clone();
         let started = Instant::now();
         LspRequest::new(id, async move {
-            handle_response?;
-            send?;
+            if let Err(error) = handle_response.and(send) {
+                pending_request.finish(RequestOutcome::Failed, 0);
+                return Err(error);
+            }
 
             let cancel_on_drop = util::defer(move || {
                 if let Some(outbound_tx) = outbound_tx.upgrade() {
@@ -1001,11 +1231,22 @@ impl LanguageServer {
                     let elapsed = started.elapsed();
                     log::trace!("Took {elapsed:?} to receive response to {method:?} id {id}");
                     cancel_on_drop.abort();
-                    response?
+                    let (response_size, response) = response?;
+                    let outcome = if response.is_ok() {
+                        RequestOutcome::Succeeded
+                    } else {
+                        RequestOutcome::Failed
+                    };
+                    pending_request.finish(outcome, response_size);
+                    response
                 }
 
                 _ = timeout => {
-                    log::error!("Cancelled LSP request task for {method:?} id {id} which took over {LSP_REQUEST_TIMEOUT:?}");
+                    pending_request.finish(RequestOutcome::TimedOut, 0);
+                    log::error!("Cancelled LSP request task for {method:?} id {id} which took over {request_timeout:?}");
+                    if let Some(on_timeout) = request_timeouts.lock().on_timeout.as_mut() {
+                        on_timeout(method, request_timeout);
+                    }
                     anyhow::bail!("LSP request timeout");
                 }
             }
@@ -1404,6 +1645,205 @@ mod tests {
         fake.receive_notification::<notification::Exit>().await;
     }
 
+    #[gpui::test]
+    async fn test_request_stats(cx: &mut TestAppContext) {
+        cx.update(|cx| {
+            release_channel::init(SemanticVersion::default(), cx);
+        });
+        let (server, mut fake) = FakeLanguageServer::new(
+            LanguageServerId(0),
+            LanguageServerBinary {
+                path: "path/to/language-server".into(),
+                arguments: vec![],
+                env: None,
+            },
+            "the-lsp".to_string(),
+            Default::default(),
+            cx.to_async(),
+        );
+        let server = cx.update(|cx| server.initialize(None, cx)).await.unwrap();
+        server.clear_request_stats();
+
+        fake.handle_request::<request::HoverRequest, _, _>(|_, _| async move { Ok(None) });
+        fake.handle_request::<request::Formatting, _, _>(|_, _| async move {
+            Err(anyhow!("cannot format"))
+        });
+        let text_document = TextDocumentIdentifier::new(Url::from_str("file://a/b").unwrap());
+        server
+            .request::<request::HoverRequest>(HoverParams {
+                text_document_position_params: TextDocumentPositionParams {
+                    text_document: text_document.clone(),
+                    position: Position::new(0, 0),
+                },
+                work_done_progress_params: Default::default(),
+            })
+            .await
+            .unwrap();
+        server
+            .request::<request::Formatting>(DocumentFormattingParams {
+                text_document,
+                options: Default::default(),
+                work_done_progress_params: Default::default(),
+            })
+            .await
+            .unwrap_err();
+        drop(server.request::<request::WorkspaceSymbolRequest>(WorkspaceSymbolParams::default()));
+        fake.receive_notification::<notification::Cancel>().await;
+
+        let stats = server.request_stats();
+        let hover = stats.method(request::HoverRequest::METHOD).unwrap();
+        assert_eq!((hover.count, hover.failed), (1, 0));
+        assert!(hover.total_request_size > 0);
+        assert!(hover.total_response_size > 0);
+        let formatting = stats.method(request::Formatting::METHOD).unwrap();
+        assert_eq!((formatting.count, formatting.failed), (1, 1));
+        let symbols = stats
+            .method(request::WorkspaceSymbolRequest::METHOD)
+            .unwrap();
+        assert_eq!((symbols.count, symbols.cancelled), (1, 1));
+        assert_eq!(stats.slowest_requests(10).len(), 3);
+
+        fake.handle_request::<request::Shutdown, _, _>(|_, _| async move { Ok(()) });
+        drop(server);
+        fake.receive_notification::<notification::Exit>().await;
+    }
+
+    #[gpui::test]
+    async fn test_message_middleware(cx: &mut TestAppContext) {
+        struct Middleware {
+            messages: Arc<Mutex<Vec<(MessageDirection, MessageKind, String)>>>,
+        }
+
+        impl MessageMiddleware for Middleware {
+            fn intercepts(&self, method: &str) -> bool {
+                method == request::HoverRequest::METHOD
+                    || method == notification::ShowMessage::METHOD
+            }
+
+            fn rewrite(
+                &self,
+                message: InterceptedMessage,
+            ) -> futures::future::BoxFuture<'static, Result<Option<Value>>> {
+                self.messages.lock().push((
+                    message.direction,
+                    message.kind,
+                    message.method.clone(),
+                ));
+                let mut payload = message.payload;
+                let rewritten = match message.kind {
+                    MessageKind::Request => {
+                        payload["position"] = json!({ "line": 0, "character": 2 });
+                        Some(payload)
+                    }
+                    MessageKind::Response => {
+                        payload["contents"] = json!("rewritten");
+                        Some(payload)
+                    }
+                    MessageKind::Notification => None,
+                };
+                async move { Ok(rewritten) }.boxed()
+            }
+        }
+
+        cx.update(|cx| {
+            release_channel::init(SemanticVersion::default(), cx);
+        });
+        let (server, mut fake) = FakeLanguageServer::new(
+            LanguageServerId(0),
+            LanguageServerBinary {
+                path: "path/to/language-server".into(),
+                arguments: vec![],
+                env: None,
+            },
+            "the-lsp".to_string(),
+            Default::default(),
+            cx.to_async(),
+        );
+        let (message_tx, message_rx) = channel::unbounded();
+        server
+            .on_notification::<notification::ShowMessage, _>(move |params, _| {
+                message_tx.try_send(params).unwrap()
+            })
+            .detach();
+        let server = cx.update(|cx| server.initialize(None, cx)).await.unwrap();
+        let messages = Arc::new(Mutex::new(Vec::new()));
+        server.set_message_middleware(Some(Arc::new(Middleware {
+            messages: messages.clone(),
+        })));
+
+        fake.handle_request::<request::HoverRequest, _, _>(|params, _| async move {
+            assert_eq!(
+                params.text_document_position_params.position,
+                Position::new(0, 2)
+            );
+            Ok(Some(Hover {
+                contents: HoverContents::Scalar(MarkedString::String("original".to_string())),
+                range: None,
+            }))
+        });
+        let hover = server
+            .request::<request::HoverRequest>(HoverParams {
+                text_document_position_params: TextDocumentPositionParams {
+                    text_document: TextDocumentIdentifier::new(
+                        Url::from_str("file://a/b").unwrap(),
+                    ),
+                    position: Position::new(0, 0),
+                },
+                work_done_progress_params: Default::default(),
+            })
+            .await
+            .unwrap()
+            .unwrap();
+        assert_eq!(
+            hover.contents,
+            HoverContents::Scalar(MarkedString::String("rewritten".to_string()))
+        );
+
+        fake.notify::<notification::ShowMessage>(ShowMessageParams {
+            typ: MessageType::INFO,
+            message: "unchanged".to_string(),
+        });
+        assert_eq!(message_rx.recv().await.unwrap().message, "unchanged");
+
+        // Messages of the other methods are not passed to the middleware.
+        server
+            .notify::<notification::DidOpenTextDocument>(DidOpenTextDocumentParams {
+                text_document: TextDocumentItem::new(
+                    Url::from_str("file://a/b").unwrap(),
+                    "rust".to_string(),
+                    0,
+                    "".to_string(),
+                ),
+            })
+            .unwrap();
+        fake.receive_notification::<notification::DidOpenTextDocument>()
+            .await;
+        assert_eq!(
+            *messages.lock(),
+            [
+                (
+                    MessageDirection::ToServer,
+                    MessageKind::Request,
+                    request::HoverRequest::METHOD.to_string()
+                ),
+                (
+                    MessageDirection::FromServer,
+                    MessageKind::Response,
+                    request::HoverRequest::METHOD.to_string()
+                ),
+                (
+                    MessageDirection::FromServer,
+                    MessageKind::Notification,
+                    notification::ShowMessage::METHOD.to_string()
+                ),
+            ]
+        );
+
+        fake.handle_request::<request::Shutdown, _, _>(|_, _| async move { Ok(()) });
+        drop(server);
+        fake.receive_notification::<notification::Exit>().await;
+    }
+
     #[gpui::test]
     fn test_deserialize_string_digit_id() {
         let json = r#"{"jsonrpc":"2.0","id":"2","method":"workspace/configuration","params":{"items":[{"scopeUri":"file:///Users/mph/Devel/personal/hello-scala/","section":"metals"}]}}"#;
@@ -0,0 +1,140 @@
+//! Middleware that observes and rewrites the messages exchanged with a language server, e.g. to patch the positions
+//! reported by a buggy server, before they are handled by Zed or written to the server.
+
+use std::sync::Arc;
+
+use anyhow::Result;
+use collections::HashMap;
+use futures::future::BoxFuture;
+use parking_lot::Mutex;
+use serde_json::Value;
+
+use crate::RequestId;
+
+/// Whether a message is sent to the language server or received from it.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum MessageDirection {
+    ToServer,
+    FromServer,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum MessageKind {
+    Request,
+    Response,
+    Notification,
+}
+
+/// A message passed to a [`MessageMiddleware`].
+#[derive(Clone, Debug, PartialEq)]
+pub struct InterceptedMessage {
+    pub direction: MessageDirection,
+    pub kind: MessageKind,
+    /// The method of the message, or of the request that a response answers.
+    pub method: String,
+    /// The params of requests and notifications, or the result of responses.
+    pub payload: Value,
+}
+
+/// Observes and rewrites the messages of the methods it intercepts.
+pub trait MessageMiddleware: 'static + Send + Sync {
+    /// Whether the messages of the method, and the responses to its requests, are passed to the middleware.
+    fn intercepts(&self, method: &str) -> bool;
+
+    /// Returns the payload to replace the one of the message with, if any.
+    ///
+    /// Errors are logged and leave the message unchanged.
+    fn rewrite(&self, message: InterceptedMessage) -> BoxFuture<'static, Result<Option<Value>>>;
+}
+
+/// The middleware of a language server, along with the methods of the intercepted requests whose responses are
+/// still pending.
+#[derive(Default)]
+pub(crate) struct MiddlewareState {
+    middleware: Mutex<Option<Arc<dyn MessageMiddleware>>>,
+    requests_to_server: Mutex<HashMap<RequestId, String>>,
+    requests_from_server: Mutex<HashMap<RequestId, String>>,
+}
+
+impl MiddlewareState {
+    pub(crate) fn set(&self, middleware: Option<Arc<dyn MessageMiddleware>>) {
+        *self.middleware.lock() = middleware;
+        self.requests_to_server.lock().clear();
+        self.requests_from_server.lock().clear();
+    }
+
+    /// Returns the message rewritten by the middleware, or `None` if it is unchanged.
+    pub(crate) async fn rewrite(
+        &self,
+        direction: MessageDirection,
+        message: &str,
+    ) -> Option<String> {
+        let middleware = self.middleware.lock().clone()?;
+        let mut message = serde_json::from_str::<Value>(message).ok()?;
+        let object = message.as_object_mut()?;
+        let id = object
+            .get("id")
+            .and_then(|id| serde_json::from_value::<RequestId>(id.clone()).ok());
+
+        let (kind, method) = match (object.get("method").and_then(Value::as_str), id) {
+            (Some(method), Some(id)) => {
+                if !middleware.intercepts(method) {
+                    return None;
+                }
+                self.requests(direction)
+                    .lock()
+                    .insert(id, method.to_string());
+                (MessageKind::Request, method.to_string())
+            }
+            (Some(method), None) => {
+                if !middleware.intercepts(method) {
+                    return None;
+                }
+                (MessageKind::Notification, method.to_string())
+            }
+            // Responses answer the requests sent in the other direction.
+            (None, Some(id)) => {
+                let requests = match direction {
+                    MessageDirection::ToServer => &self.requests_from_server,
+                    MessageDirection::FromServer => &self.requests_to_server,
+                };
+                let method = requests.lock().remove(&id)?;
+                (MessageKind::Response, method)
+            }
+            (None, None) => return None,
+        };
+
+        let payload_key = match kind {
+            MessageKind::Response => "result",
+            MessageKind::Request | MessageKind::Notification => "params",
+        };
+        if kind == MessageKind::Response && object.contains_key("error") {
+            return None;
+        }
+        let payload = object.get(payload_key).cloned().unwrap_or(Value::Null);
+        let intercepted = InterceptedMessage {
+            direction,
+            kind,
+            method,
+            payload,
+        };
+        match middleware.rewrite(intercepted).await {
+            Ok(Some(payload)) => {
+                object.insert(payload_key.to_string(), payload);
+                serde_json::to_string(&message).ok()
+            }
+            Ok(None) => None,
+            Err(error) => {
+                log::error!("failed to rewrite LSP message: {error:#}");
+                None
+            }
+        }
+    }
+
+    fn requests(&self, direction: MessageDirection) -> &Mutex<HashMap<RequestId, String>> {
+        match direction {
+            MessageDirection::ToServer => &self.requests_to_server,
+            MessageDirection::FromServer => &self.requests_from_server,
+        }
+    }
+}
@@ -0,0 +1,163 @@
+//! Latency and payload statistics of the requests sent to a language server.
+
+use std::{
+    collections::{BTreeMap, VecDeque},
+    sync::Arc,
+    time::{Duration, Instant},
+};
+
+use parking_lot::Mutex;
+
+/// How many finished requests are kept per server to find the slowest ones.
+const MAX_RECENT_REQUESTS: usize = 1024;
+
+/// How an LSP request round-trip ended.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum RequestOutcome {
+    Succeeded,
+    /// The server responded with an error, or the response could not be deserialized.
+    Failed,
+    /// The request future was dropped before the response arrived, and `$/cancelRequest` was sent.
+    Cancelled,
+    TimedOut,
+}
+
+/// A single finished request.
+#[derive(Clone, Debug)]
+pub struct RequestRecord {
+    pub id: i32,
+    pub method: &'static str,
+    pub duration: Duration,
+    /// The length of the serialized request, in bytes.
+    pub request_size: usize,
+    /// The length of the serialized response, in bytes. Zero if no response arrived.
+    pub response_size: usize,
+    pub outcome: RequestOutcome,
+}
+
+/// Aggregates of all requests with the same method.
+#[derive(Clone, Debug, Default)]
+pub struct MethodStats {
+    pub count: usize,
+    pub failed: usize,
+    pub cancelled: usize,
+    pub timed_out: usize,
+    pub total_duration: Duration,
+    pub max_duration: Duration,
+    pub total_request_size: usize,
+    pub total_response_size: usize,
+}
+
+impl MethodStats {
+    pub fn mean_duration(&self) -> Duration {
+        if self.count == 0 {
+            Duration::ZERO
+        } else {
+            self.total_duration / self.count as u32
+        }
+    }
+}
+
+/// The statistics of one language server: per-method aggregates over its lifetime and the most recent requests.
+#[derive(Clone, Debug, Default)]
+pub struct RequestStats {
+    methods: BTreeMap<&'static str, MethodStats>,
+    recent: VecDeque<RequestRecord>,
+}
+
+impl RequestStats {
+    pub fn record(&mut self, record: RequestRecord) {
+        let stats = self.methods.entry(record.method).or_default();
+        stats.count += 1;
+        match record.outcome {
+            RequestOutcome::Succeeded => {}
+            RequestOutcome::Failed => stats.failed += 1,
+            RequestOutcome::Cancelled => stats.cancelled += 1,
+            RequestOutcome::TimedOut => stats.timed_out += 1,
+        }
+        stats.total_duration += record.duration;
+        stats.max_duration = stats.max_duration.max(record.duration);
+        stats.total_request_size += record.request_size;
+        stats.total_response_size += record.response_size;
+
+        if self.recent.len() == MAX_RECENT_REQUESTS {
+            self.recent.pop_front();
+        }
+        self.recent.push_back(record);
+    }
+
+    /// The aggregates per method, sorted by method name.
+    pub fn methods(&self) -> impl Iterator<Item = (&'static str, &MethodStats)> {
+        self.methods.iter().map(|(method, stats)| (*method, stats))
+    }
+
+    pub fn method(&self, method: &str) -> Option<&MethodStats> {
+        self.methods.get(method)
+    }
+
+    /// The most recent requests, oldest first.
+    pub fn recent_requests(&self) -> impl DoubleEndedIterator<Item = &RequestRecord> {
+        self.recent.iter()
+    }
+
+    /// The `limit` slowest of the recent requests, slowest first.
+    pub fn slowest_requests(&self, limit: usize) -> Vec<RequestRecord> {
+        let mut requests = self.recent.iter().cloned().collect::<Vec<_>>();
+        requests.sort_by(|a, b| b.duration.cmp(&a.duration));
+        requests.truncate(limit);
+        requests
+    }
+
+    pub fn clear(&mut self) {
+        self.methods.clear();
+        self.recent.clear();
+    }
+}
+
+/// A request in flight. Records itself into the stats when dropped, as cancelled unless finished before.
+pub(crate) struct PendingRequest {
+    stats: Arc<Mutex<RequestStats>>,
+    id: i32,
+    method: &'static str,
+    started_at: Instant,
+    request_size: usize,
+    response_size: usize,
+    outcome: RequestOutcome,
+}
+
+impl PendingRequest {
+    pub(crate) fn new(
+        stats: Arc<Mutex<RequestStats>>,
+        id: i32,
+        method: &'static str,
+        request_size: usize,
+    ) -> Self {
+        Self {
+            stats,
+            id,
+            method,
+            started_at: Instant::now(),
+            request_size,
+            response_size: 0,
+            outcome: RequestOutcome::Cancelled,
+        }
+    }
+
+    pub(crate) fn finish(mut self, outcome: RequestOutcome, response_size: usize) {
+        self.outcome = outcome;
+        self.response_size = response_size;
+    }
+}
+
+impl Drop for PendingRequest {
+    fn drop(&mut self) {
+        self.stats.lock().record(RequestRecord {
+            id: self.id,
+            method: self.method,
+            duration: self.started_at.elapsed(),
+            request_size: self.request_size,
+            response_size: self.response_size,
+            outcome: self.outcome,
+        });
+    }
+}
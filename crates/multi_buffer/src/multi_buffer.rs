@@ -1078,6 +1078,25 @@ impl MultiBuffer {
         ranges: Vec<Range<text::Anchor>>,
         context_line_count: u32,
         cx: &mut ModelContext<Self>,
+    ) -> mpsc::Receiver<Range<Anchor>> {
+        self.stream_excerpts_with_context_lines_after(
+            ExcerptId::max(),
+            buffer,
+            ranges,
+            context_line_count,
+            cx,
+        )
+    }
+
+    /// Like [`Self::stream_excerpts_with_context_lines`], inserting the excerpts after the excerpt with the id
+    /// instead of at the end of the multibuffer.
+    pub fn stream_excerpts_with_context_lines_after(
+        &mut self,
+        mut prev_excerpt_id: ExcerptId,
+        buffer: Model<Buffer>,
+        ranges: Vec<Range<text::Anchor>>,
+        context_line_count: u32,
+        cx: &mut ModelContext<Self>,
     ) -> mpsc::Receiver<Range<Anchor>> {
         let (buffer_id, buffer_snapshot) =
             buffer.update(cx, |buffer, _| (buffer.remote_id(), buffer.snapshot()));
@@ -1101,11 +1120,19 @@ impl MultiBuffer {
             let mut range_counts = range_counts.into_iter();
             for excerpt_ranges in excerpt_ranges.chunks(100) {
                 let excerpt_ids = match this.update(&mut cx, |this, cx| {
-                    this.push_excerpts(buffer.clone(), excerpt_ranges.iter().cloned(), cx)
+                    this.insert_excerpts_after(
+                        prev_excerpt_id,
+                        buffer.clone(),
+                        excerpt_ranges.iter().cloned(),
+                        cx,
+                    )
                 }) {
                     Ok(excerpt_ids) => excerpt_ids,
                     Err(_) => return,
                 };
+                if let Some(last_excerpt_id) = excerpt_ids.last() {
+                    prev_excerpt_id = *last_excerpt_id;
+                }
 
                 for (excerpt_id, range_count) in excerpt_ids.into_iter().zip(range_counts.by_ref())
                 {
@@ -5328,6 +5355,55 @@ mod tests {
         );
     }
 
+    #[gpui::test]
+    async fn test_stream_excerpts_with_context_lines_after(cx: &mut TestAppContext) {
+        let buffer_1 = cx.new_model(|cx| Buffer::local(sample_text(10, 3, 'a'), cx));
+        let buffer_2 = cx.new_model(|cx| Buffer::local(sample_text(10, 3, 'm'), cx));
+        let multibuffer = cx.new_model(|_| MultiBuffer::new(0, Capability::ReadWrite));
+        let ranges_1 = multibuffer.update(cx, |multibuffer, cx| {
+            let snapshot = buffer_1.read(cx);
+            let ranges = vec![
+                snapshot.anchor_before(Point::new(1, 0))..snapshot.anchor_before(Point::new(1, 1)),
+                snapshot.anchor_before(Point::new(8, 0))..snapshot.anchor_before(Point::new(8, 1)),
+            ];
+            multibuffer.stream_excerpts_with_context_lines(buffer_1.clone(), ranges, 0, cx)
+        });
+        let ranges_1 = ranges_1.collect::<Vec<_>>().await;
+
+        // The excerpts are inserted between the ones of the first buffer.
+        let ranges_2 = multibuffer.update(cx, |multibuffer, cx| {
+            let snapshot = buffer_2.read(cx);
+            let ranges = vec![
+                snapshot.anchor_before(Point::new(2, 0))..snapshot.anchor_before(Point::new(2, 1)),
+                snapshot.anchor_before(Point::new(4, 0))..snapshot.anchor_before(Point::new(4, 1)),
+            ];
+            multibuffer.stream_excerpts_with_context_lines_after(
+                ranges_1[0].start.excerpt_id,
+                buffer_2.clone(),
+                ranges,
+                0,
+                cx,
+            )
+        });
+        let ranges_2 = ranges_2.collect::<Vec<_>>().await;
+
+        let snapshot = multibuffer.update(cx, |multibuffer, cx| multibuffer.snapshot(cx));
+        assert_eq!(snapshot.text(), "bbb\nooo\nqqq\niii");
+        assert_eq!(
+            ranges_1
+                .iter()
+                .chain(&ranges_2)
+                .map(|range| range.to_point(&snapshot))
+                .collect::<Vec<_>>(),
+            vec![
+                Point::new(0, 0)..Point::new(0, 1),
+                Point::new(3, 0)..Point::new(3, 1),
+                Point::new(1, 0)..Point::new(1, 1),
+                Point::new(2, 0)..Point::new(2, 1),
+            ]
+        );
+    }
+
     #[gpui::test]
     fn test_empty_multibuffer(cx: &mut AppContext) {
         let multibuffer = cx.new_model(|_| MultiBuffer::new(0, Capability::ReadWrite));
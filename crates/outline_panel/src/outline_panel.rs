@@ -21,17 +21,19 @@ use file_icons::FileIcons;
 use gpui::{
     actions, anchored, deferred, div, px, uniform_list, Action, AnyElement, AppContext,
     AssetSource, AsyncWindowContext, ClipboardItem, DismissEvent, Div, ElementId, EntityId,
-    EventEmitter, FocusHandle, FocusableView, InteractiveElement, IntoElement, KeyContext, Model,
-    MouseButton, MouseDownEvent, ParentElement, Pixels, Point, Render, SharedString, Stateful,
-    Styled, Subscription, Task, UniformListScrollHandle, View, ViewContext, VisualContext,
-    WeakView, WindowContext,
+    EventEmitter, FocusHandle, FocusableView, HighlightStyle, InteractiveElement, IntoElement,
+    KeyContext, Model, MouseButton, MouseDownEvent, ParentElement, Pixels, Point, Render,
+    SharedString, Stateful, StrikethroughStyle, Styled, Subscription, Task,
+    UniformListScrollHandle, View, ViewContext, VisualContext, WeakView, WindowContext,
 };
 use itertools::Itertools;
-use language::{BufferId, BufferSnapshot, OffsetRangeExt, OutlineItem};
+use language::{
+    language_settings::language_settings, BufferId, BufferSnapshot, OffsetRangeExt, OutlineItem,
+};
 use menu::{SelectFirst, SelectLast, SelectNext, SelectPrev};
 
 use outline_panel_settings::{OutlinePanelDockPosition, OutlinePanelSettings};
-use project::{File, Fs, Item, Project};
+use project::{DocumentSymbol, File, Fs, Item, Project};
 use serde::{Deserialize, Serialize};
 use settings::{Settings, SettingsStore};
 use util::{RangeExt, ResultExt, TryFutureExt};
@@ -2161,23 +2163,50 @@ impl OutlinePanel {
         }
 
         let syntax_theme = cx.theme().syntax().clone();
+        let symbol_styles = SymbolStyles::new(cx);
         for (buffer_id, (buffer_snapshot, excerpt_ranges)) in excerpt_fetch_ranges {
+            let outline_from_language_server =
+                language_settings(buffer_snapshot.language(), buffer_snapshot.file(), cx)
+                    .outline_from_language_server;
             for (excerpt_id, excerpt_range) in excerpt_ranges {
                 let syntax_theme = syntax_theme.clone();
                 let buffer_snapshot = buffer_snapshot.clone();
+                let document_symbols = outline_from_language_server
+                    .then(|| self.project.read(cx).buffer_for_id(buffer_id))
+                    .flatten()
+                    .map(|buffer| {
+                        self.project
+                            .update(cx, |project, cx| project.document_symbols(&buffer, cx))
+                    });
                 self.outline_fetch_tasks.insert(
                     (buffer_id, excerpt_id),
                     cx.spawn(|outline_panel, mut cx| async move {
+                        // The tree-sitter outline is shown when the language server has no symbols.
+                        let document_symbols = match document_symbols {
+                            Some(document_symbols) => document_symbols
+                                .await
+                                .log_err()
+                                .filter(|document_symbols| !document_symbols.symbols.is_empty()),
+                            None => None,
+                        };
                         let fetched_outlines = cx
                             .background_executor()
                             .spawn(async move {
-                                buffer_snapshot
-                                    .outline_items_containing(
-                                        excerpt_range.context,
-                                        false,
-                                        Some(&syntax_theme),
-                                    )
-                                    .unwrap_or_default()
+                                match document_symbols {
+                                    Some(document_symbols) => outline_items_from_document_symbols(
+                                        &document_symbols.symbols,
+                                        &excerpt_range.context,
+                                        &buffer_snapshot,
+                                        &symbol_styles,
+                                    ),
+                                    None => buffer_snapshot
+                                        .outline_items_containing(
+                                            excerpt_range.context,
+                                            false,
+                                            Some(&syntax_theme),
+                                        )
+                                        .unwrap_or_default(),
+                                }
                             })
                             .await;
                         outline_panel
@@ -2754,6 +2783,87 @@ fn subscribe_for_editor_events(
     )
 }
 
+/// How the outline items built from document symbols render their details and deprecation.
+#[derive(Clone, Copy)]
+struct SymbolStyles {
+    detail: HighlightStyle,
+    deprecated: HighlightStyle,
+}
+
+impl SymbolStyles {
+    fn new(cx: &AppContext) -> Self {
+        Self {
+            detail: HighlightStyle {
+                color: Some(cx.theme().colors().text_muted),
+                ..HighlightStyle::default()
+            },
+            deprecated: HighlightStyle {
+                strikethrough: Some(StrikethroughStyle {
+                    thickness: px(1.),
+                    color: None,
+                }),
+                ..HighlightStyle::default()
+            },
+        }
+    }
+}
+
+/// Flattens the document symbols that intersect the range into outline items, nested by their depths, with the
+/// details of the symbols after their names.
+fn outline_items_from_document_symbols(
+    symbols: &[DocumentSymbol],
+    range: &Range<language::Anchor>,
+    buffer_snapshot: &BufferSnapshot,
+    styles: &SymbolStyles,
+) -> Vec<Outline> {
+    fn push_items(
+        symbols: &[DocumentSymbol],
+        depth: usize,
+        range: &Range<language::Anchor>,
+        buffer_snapshot: &BufferSnapshot,
+        styles: &SymbolStyles,
+        items: &mut Vec<Outline>,
+    ) {
+        for symbol in symbols {
+            if symbol.range.end.cmp(&range.start, buffer_snapshot).is_lt()
+                || symbol.range.start.cmp(&range.end, buffer_snapshot).is_gt()
+            {
+                continue;
+            }
+            let mut text = symbol.name.clone();
+            let mut highlight_ranges = Vec::new();
+            if symbol.deprecated {
+                highlight_ranges.push((0..text.len(), styles.deprecated));
+            }
+            if let Some(detail) = &symbol.detail {
+                text.push(' ');
+                let detail_start = text.len();
+                text.push_str(detail);
+                highlight_ranges.push((detail_start..text.len(), styles.detail));
+            }
+            items.push(OutlineItem {
+                depth,
+                range: symbol.range.clone(),
+                name_ranges: vec![0..symbol.name.len()],
+                text,
+                highlight_ranges,
+            });
+            push_items(
+                &symbol.children,
+                depth + 1,
+                range,
+                buffer_snapshot,
+                styles,
+                items,
+            );
+        }
+    }
+
+    let mut items = Vec::new();
+    push_items(symbols, 0, range, buffer_snapshot, styles, &mut items);
+    items
+}
+
 fn empty_icon() -> AnyElement {
     h_flex()
         .size(IconSize::default().rems())
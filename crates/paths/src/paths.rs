@@ -224,6 +224,14 @@ pub fn default_prettier_dir() -> &'static PathBuf {
     DEFAULT_PRETTIER_DIR.get_or_init(|| support_dir().join("prettier"))
 }
 
+/// Returns the path to the directory of the cached language server responses.
+///
+/// This is where the definitions and hovers cached by the `lsp_response_cache` setting are stored.
+pub fn lsp_response_cache_dir() -> &'static PathBuf {
+    static LSP_RESPONSE_CACHE_DIR: OnceLock<PathBuf> = OnceLock::new();
+    LSP_RESPONSE_CACHE_DIR.get_or_init(|| support_dir().join("lsp_response_cache"))
+}
+
 /// Returns the relative path to a `.zed` folder within a project.
 pub fn local_settings_folder_relative_path() -> &'static Path {
     static LOCAL_SETTINGS_FOLDER_RELATIVE_PATH: OnceLock<&Path> = OnceLock::new();
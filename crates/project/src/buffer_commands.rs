@@ -0,0 +1,248 @@
+//! External commands that read the contents of a buffer from stdin, so that they see its unsaved changes:
+//! the external formatters, and the external linters, the diagnostic sources that check the buffers of local
+//! projects as they change or when they're saved.
+//!
+//! The arguments of the commands can refer to the buffer with placeholders: `{buffer_path}`,
+//! `{buffer_relative_path}` and `{worktree_path}`.
+
+use std::path::{Path, PathBuf};
+
+use anyhow::{anyhow, Context as _, Result};
+use async_trait::async_trait;
+use futures::AsyncWriteExt;
+use gpui::{AsyncAppContext, Model};
+use language::{
+    language_settings::{ExternalLinter, LintTrigger, LinterOutput},
+    Buffer, File as _, PointUtf16,
+};
+use lsp::DiagnosticSeverity;
+use task::ProblemSeverity;
+
+use crate::{vale, DiagnosticSource, File, Project, SourceDiagnostic};
+
+/// The paths that the placeholders of the arguments of a buffer command are replaced with.
+struct BufferPaths {
+    buffer_path: Option<PathBuf>,
+    buffer_relative_path: Option<PathBuf>,
+    worktree_path: Option<PathBuf>,
+}
+
+impl BufferPaths {
+    fn new(buffer: &Buffer, cx: &gpui::AppContext) -> Self {
+        let Some(file) = File::from_dyn(buffer.file()) else {
+            return Self {
+                buffer_path: None,
+                buffer_relative_path: None,
+                worktree_path: None,
+            };
+        };
+        let worktree = file.worktree.read(cx);
+        let mut worktree_path = worktree.abs_path().to_path_buf();
+        if worktree.root_entry().map_or(false, |entry| entry.is_file()) {
+            worktree_path.pop();
+        }
+        Self {
+            buffer_path: file.as_local().map(|file| file.abs_path(cx)),
+            buffer_relative_path: Some(file.path.to_path_buf()),
+            worktree_path: Some(worktree_path),
+        }
+    }
+
+    fn substitute(&self, argument: &str) -> String {
+        let path_or_untitled = |path: &Option<PathBuf>| {
+            path.as_ref().map_or_else(
+                || "Untitled".to_string(),
+                |path| path.to_string_lossy().into_owned(),
+            )
+        };
+        argument
+            .replace("{buffer_path}", &path_or_untitled(&self.buffer_path))
+            .replace(
+                "{buffer_relative_path}",
+                &path_or_untitled(&self.buffer_relative_path),
+            )
+            .replace(
+                "{worktree_path}",
+                &self
+                    .worktree_path
+                    .as_deref()
+                    .map_or_else(String::new, |path| path.to_string_lossy().into_owned()),
+            )
+    }
+}
+
+impl Project {
+    /// Runs a command in the worktree of the buffer with the current contents of the buffer on stdin, replacing the
+    /// placeholders of its arguments, and returns its output whatever its exit status.
+    pub(crate) async fn run_command_with_buffer_stdin(
+        buffer: &Model<Buffer>,
+        buffer_abs_path: Option<&Path>,
+        command: &str,
+        arguments: &[String],
+        cx: &mut AsyncAppContext,
+    ) -> Result<std::process::Output> {
+        let (mut paths, text) = buffer.update(cx, |buffer, cx| {
+            (BufferPaths::new(buffer, cx), buffer.as_rope().clone())
+        })?;
+        if let Some(buffer_abs_path) = buffer_abs_path {
+            paths.buffer_path = Some(buffer_abs_path.to_path_buf());
+        }
+
+        let mut child = smol::process::Command::new(command);
+        if let Some(worktree_path) = &paths.worktree_path {
+            child.current_dir(worktree_path);
+        }
+        let mut child = child
+            .args(arguments.iter().map(|argument| paths.substitute(argument)))
+            .stdin(smol::process::Stdio::piped())
+            .stdout(smol::process::Stdio::piped())
+            .stderr(smol::process::Stdio::piped())
+            .spawn()
+            .with_context(|| format!("failed to spawn {command:?}"))?;
+
+        let stdin = child
+            .stdin
+            .as_mut()
+            .ok_or_else(|| anyhow!("failed to acquire stdin"))?;
+        for chunk in text.chunks() {
+            stdin.write_all(chunk.as_bytes()).await?;
+        }
+        stdin.flush().await?;
+
+        Ok(child.output().await?)
+    }
+}
+
+#[async_trait(?Send)]
+impl DiagnosticSource for ExternalLinter {
+    fn name(&self) -> &str {
+        &self.command
+    }
+
+    fn trigger(&self) -> LintTrigger {
+        self.run_on
+    }
+
+    async fn check(
+        &self,
+        buffer: &Model<Buffer>,
+        cx: &mut AsyncAppContext,
+    ) -> Result<Vec<SourceDiagnostic>> {
+        match self.output {
+            LinterOutput::ProblemMatchers => run_linter(self, buffer, cx).await,
+            LinterOutput::Vale => {
+                // The command reads the same contents, as it's spawned before the buffer can change.
+                let text = buffer.update(cx, |buffer, _| buffer.as_rope().clone())?;
+                let output = Project::run_command_with_buffer_stdin(
+                    buffer,
+                    None,
+                    &self.command,
+                    &self.arguments,
+                    cx,
+                )
+                .await?;
+                vale::parse_vale_output(&output.stdout, &text)
+            }
+        }
+    }
+}
+
+/// Runs the linter on the contents of the buffer, returning the problems that its output reports.
+pub(crate) async fn run_linter(
+    linter: &ExternalLinter,
+    buffer: &Model<Buffer>,
+    cx: &mut AsyncAppContext,
+) -> Result<Vec<SourceDiagnostic>> {
+    let output = Project::run_command_with_buffer_stdin(
+        buffer,
+        None,
+        &linter.command,
+        &linter.arguments,
+        cx,
+    )
+    .await?;
+    run_problem_matchers(linter, output, cx).await
+}
+
+/// Returns the problems that the problem matchers of the linter find in its output.
+async fn run_problem_matchers(
+    linter: &ExternalLinter,
+    output: std::process::Output,
+    cx: &mut AsyncAppContext,
+) -> Result<Vec<SourceDiagnostic>> {
+    let matchers = linter
+        .problem_matchers
+        .iter()
+        .map(|matcher| matcher.compile())
+        .collect::<Result<Vec<_>>>()?
+        .into_iter()
+        .flatten()
+        .collect::<Vec<_>>();
+    let mut text = String::from_utf8_lossy(&output.stdout).into_owned();
+    text.push('\n');
+    text.push_str(&String::from_utf8_lossy(&output.stderr));
+
+    let problems = cx
+        .background_executor()
+        .spawn(async move {
+            matchers
+                .iter()
+                .flat_map(|matcher| matcher.problems(&text))
+                .collect::<Vec<_>>()
+        })
+        .await;
+    Ok(problems
+        .into_iter()
+        .map(|problem| {
+            let start = PointUtf16::new(problem.start.0, problem.start.1);
+            let end = problem
+                .end
+                .map_or(start, |(row, column)| PointUtf16::new(row, column));
+            let severity = match problem.severity {
+                ProblemSeverity::Error => DiagnosticSeverity::ERROR,
+                ProblemSeverity::Warning => DiagnosticSeverity::WARNING,
+                ProblemSeverity::Information => DiagnosticSeverity::INFORMATION,
+                ProblemSeverity::Hint => DiagnosticSeverity::HINT,
+            };
+            SourceDiagnostic {
+                range: start..end,
+                severity,
+                source: problem.source,
+                code: problem.code,
+                message: problem.message,
+                fixes: Vec::new(),
+            }
+        })
+        .collect())
+}
+
+#[cfg(test)]
+mod tests {
+    use std::path::PathBuf;
+
+    use super::BufferPaths;
+
+    #[test]
+    fn test_buffer_command_placeholders() {
+        let paths = BufferPaths {
+            buffer_path: Some(PathBuf::from("/code/app/src/main.rs")),
+            buffer_relative_path: Some(PathBuf::from("src/main.rs")),
+            worktree_path: Some(PathBuf::from("/code/app")),
+        };
+        assert_eq!(
+            paths.substitute("--stdin-filename={buffer_path}"),
+            "--stdin-filename=/code/app/src/main.rs"
+        );
+        assert_eq!(
+            paths.substitute("{worktree_path}/{buffer_relative_path}"),
+            "/code/app/src/main.rs"
+        );
+
+        let untitled = BufferPaths {
+            buffer_path: None,
+            buffer_relative_path: None,
+            worktree_path: None,
+        };
+        assert_eq!(untitled.substitute("{buffer_path}"), "Untitled");
+    }
+}
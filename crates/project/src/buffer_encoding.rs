@@ -0,0 +1,115 @@
+//! The encodings of the files of local buffers: they're detected when the files are opened, with
+//! [`Encoding::detect`], and the buffers are saved back in the same encoding, so that the files of legacy
+//! codebases aren't corrupted by being saved as UTF-8. The encoding of a buffer can also be changed explicitly,
+//! by reopening its file or saving it with another one.
+
+use std::{path::Path, sync::Arc};
+
+use anyhow::{anyhow, Result};
+use gpui::{Model, ModelContext, Task};
+use language::{Buffer, Encoding, File as _};
+use worktree::{File, Worktree};
+
+use crate::Project;
+
+/// Writes the contents of a buffer to its file, encoded with the encoding of the buffer.
+pub(crate) fn write_buffer_file(
+    worktree: &Model<Worktree>,
+    buffer: &Model<Buffer>,
+    path: Arc<Path>,
+    cx: &mut ModelContext<Project>,
+) -> Task<Result<Arc<File>>> {
+    let buffer = buffer.read(cx);
+    let text = buffer.as_rope().clone();
+    let line_ending = buffer.line_ending();
+    let encoding = buffer.encoding();
+    if encoding == Encoding::Utf8 {
+        return worktree.update(cx, |worktree, cx| {
+            worktree.write_file(path.as_ref(), text, line_ending, cx)
+        });
+    }
+
+    let encode = cx.background_executor().spawn(async move {
+        let mut content = String::with_capacity(text.len());
+        for chunk in text.chunks() {
+            for (ix, line) in chunk.split('\n').enumerate() {
+                if ix > 0 {
+                    content.push_str(line_ending.as_str());
+                }
+                content.push_str(line);
+            }
+        }
+        encoding.encode(&content)
+    });
+    let worktree = worktree.clone();
+    cx.spawn(|_, mut cx| async move {
+        let content = encode.await?;
+        worktree
+            .update(&mut cx, |worktree, cx| {
+                worktree.write_binary_file(path.as_ref(), content, cx)
+            })?
+            .await
+    })
+}
+
+impl Project {
+    /// Decodes the file of the buffer again with the given encoding, which becomes the encoding the buffer is
+    /// saved with. The buffers with unsaved changes aren't reopened, as their changes would be lost.
+    pub fn reopen_buffer_with_encoding(
+        &mut self,
+        buffer: &Model<Buffer>,
+        encoding: Encoding,
+        cx: &mut ModelContext<Self>,
+    ) -> Task<Result<()>> {
+        if !self.is_local() {
+            return Task::ready(Err(anyhow!(
+                "the buffers of remote projects can't be reopened with another encoding"
+            )));
+        }
+        let (path, is_dirty) = buffer.read_with(cx, |buffer, cx| {
+            let path = buffer
+                .file()
+                .map(|file| file.full_path(cx).display().to_string());
+            (path, buffer.is_dirty())
+        });
+        let Some(path) = path else {
+            return Task::ready(Err(anyhow!("the buffer doesn't have a file")));
+        };
+        if is_dirty {
+            return Task::ready(Err(anyhow!(
+                "{path} has unsaved changes, save or discard them before reopening it"
+            )));
+        }
+
+        let reload = buffer.update(cx, |buffer, cx| buffer.reload_with_encoding(encoding, cx));
+        cx.background_executor().spawn(async move {
+            reload
+                .await
+                .map_err(|_| anyhow!("{path} couldn't be decoded as {encoding}"))?;
+            Ok(())
+        })
+    }
+
+    /// Saves the buffer in the given encoding, which becomes the encoding of the buffer. When the buffer can't
+    /// be saved, like when it contains characters the encoding can't represent, it keeps its previous encoding.
+    pub fn save_buffer_with_encoding(
+        &mut self,
+        buffer: Model<Buffer>,
+        encoding: Encoding,
+        cx: &mut ModelContext<Self>,
+    ) -> Task<Result<()>> {
+        let previous_encoding = buffer.update(cx, |buffer, _| {
+            let previous_encoding = buffer.encoding();
+            buffer.set_encoding(encoding);
+            previous_encoding
+        });
+        let save = self.save_buffer(buffer.clone(), cx);
+        cx.spawn(|_, mut cx| async move {
+            if let Err(error) = save.await {
+                buffer.update(&mut cx, |buffer, _| buffer.set_encoding(previous_encoding))?;
+                return Err(error);
+            }
+            Ok(())
+        })
+    }
+}
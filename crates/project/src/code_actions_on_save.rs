@@ -0,0 +1,70 @@
+//! Code actions on save: the kinds of code actions configured with `code_actions_on_save`, like
+//! `source.organizeImports` or `source.fixAll.eslint`, run in their order before the buffer is formatted, so that
+//! the formatter has the last word on the layout of their edits.
+//!
+//! Each kind has a timeout, after which the buffer is saved without waiting for its actions any longer. A kind
+//! whose actions fail or time out is logged and skipped, rather than preventing the buffer from being saved.
+
+use std::{sync::Arc, time::Duration};
+
+use futures::FutureExt as _;
+use gpui::{AsyncAppContext, Model, WeakModel};
+use language::{language_settings::CodeActionOnSave, Buffer, CachedLspAdapter};
+use lsp::LanguageServer;
+
+use crate::{Project, ProjectTransaction};
+
+impl Project {
+    /// Runs the code actions on save of the buffer with all of its language servers, one kind after the other.
+    pub(crate) async fn execute_code_actions_on_save(
+        project: &WeakModel<Project>,
+        adapters_and_servers: &Vec<(Arc<CachedLspAdapter>, Arc<LanguageServer>)>,
+        code_actions_on_save: &[CodeActionOnSave],
+        buffer: &Model<Buffer>,
+        push_to_history: bool,
+        project_transaction: &mut ProjectTransaction,
+        cx: &mut AsyncAppContext,
+    ) {
+        for code_action in code_actions_on_save {
+            let timeout = Duration::from_millis(code_action.timeout_ms);
+            let mut timer = cx.background_executor().timer(timeout).fuse();
+            let mut transaction = ProjectTransaction::default();
+            let result = {
+                let mut execute = Box::pin(
+                    Self::execute_code_actions_on_servers(
+                        project,
+                        adapters_and_servers,
+                        vec![code_action.kind.clone().into()],
+                        buffer,
+                        push_to_history,
+                        &mut transaction,
+                        cx,
+                    )
+                    .fuse(),
+                );
+                futures::select_biased! {
+                    result = execute => Some(result),
+                    _ = timer => None,
+                }
+            };
+            match result {
+                Some(Ok(())) => {}
+                Some(Err(error)) => {
+                    log::error!(
+                        "code actions on save of kind {}: {error:#}",
+                        code_action.kind
+                    );
+                }
+                None => log::warn!(
+                    "code actions on save of kind {} timed out after {timeout:?}",
+                    code_action.kind
+                ),
+            }
+            // The edits applied before a failure or a timeout are kept. The first transaction of each buffer is
+            // kept, so that the formatting is grouped with all the code actions in the undo history.
+            for (buffer, transaction) in transaction.0 {
+                project_transaction.0.entry(buffer).or_insert(transaction);
+            }
+        }
+    }
+}
@@ -0,0 +1,615 @@
+//! Jupyter-style code cells: the parts of scripts that start with a `# %%` marker, and the fenced code
+//! blocks of Markdown, can be run in a REPL configured with `code_cells.kernels`.
+//!
+//! A kernel is started for each language on the first run of one of its cells, and is kept running, so
+//! that the cells share their state. The output of the cells is streamed back into their executions,
+//! which the editor renders below the cells. Besides text, kernels can display images, like plots, by
+//! printing a line starting with [`DISPLAY_PREFIX`].
+
+use std::{ops::Range, path::PathBuf, sync::Arc};
+
+use anyhow::{anyhow, bail, Context as _, Result};
+use collections::HashMap;
+use futures::{
+    channel::mpsc, io::BufReader, AsyncBufReadExt as _, AsyncWriteExt as _, Stream, StreamExt as _,
+};
+use gpui::{AsyncAppContext, ImageData, Model, ModelContext, Task, WeakModel};
+use language::{Anchor, Buffer, BufferSnapshot, Point};
+use settings::Settings;
+use text::BufferId;
+
+use crate::{
+    project_settings::{KernelSettings, ProjectSettings},
+    Event, File, Project,
+};
+
+/// The markers that start a cell in scripts, after the comment token of the language.
+const CELL_MARKERS: &[&str] = &["# %%", "// %%", "-- %%"];
+
+/// The start of the lines with which kernels display rich output, followed by the MIME type of the output
+/// and its base64-encoded data, e.g. `__zed_display__ image/png iVBORw0KGgo...`.
+pub const DISPLAY_PREFIX: &str = "__zed_display__ ";
+
+/// A runnable range of a buffer.
+#[derive(Clone, Debug, PartialEq)]
+pub struct CodeCell {
+    /// The range of the cell, including its marker or fences.
+    pub range: Range<Anchor>,
+    /// The language of the code, from the buffer, or from the info string of a fenced code block.
+    pub language: Option<String>,
+    pub code: String,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum CellExecutionStatus {
+    Queued,
+    Running,
+    Finished,
+    Failed(String),
+}
+
+/// An output of a code cell.
+#[derive(Clone, Debug)]
+pub enum CellOutput {
+    /// Lines printed by the kernel.
+    Text(String),
+    /// An image displayed by the kernel, decoded for rendering.
+    Image(Arc<ImageData>),
+}
+
+/// A run of a code cell, with its outputs so far, in the order they were written.
+#[derive(Clone, Debug)]
+pub struct CellExecution {
+    pub id: usize,
+    pub range: Range<Anchor>,
+    pub outputs: Vec<CellOutput>,
+    pub status: CellExecutionStatus,
+}
+
+#[derive(Default)]
+pub(crate) struct CodeCellKernels {
+    kernels: HashMap<String, Kernel>,
+    executions: HashMap<BufferId, Vec<CellExecution>>,
+    next_execution_id: usize,
+}
+
+impl CodeCellKernels {
+    pub(crate) fn buffer_removed(&mut self, buffer_id: BufferId) {
+        self.executions.remove(&buffer_id);
+    }
+
+    fn execution_mut(&mut self, buffer_id: BufferId, id: usize) -> Option<&mut CellExecution> {
+        self.executions
+            .get_mut(&buffer_id)?
+            .iter_mut()
+            .find(|execution| execution.id == id)
+    }
+}
+
+/// A running REPL, which runs the cells sent to it one after the other.
+struct Kernel {
+    requests: mpsc::UnboundedSender<CellRequest>,
+    _task: Task<()>,
+}
+
+struct CellRequest {
+    buffer_id: BufferId,
+    execution_id: usize,
+    code: String,
+}
+
+/// Finds the code cells of the buffer, in order.
+pub fn code_cells(snapshot: &BufferSnapshot) -> Vec<CodeCell> {
+    let is_markdown = snapshot
+        .language()
+        .map_or(false, |language| language.name().as_ref() == "Markdown");
+    if is_markdown {
+        fenced_code_blocks(snapshot)
+    } else {
+        script_cells(snapshot)
+    }
+}
+
+fn line_text(snapshot: &BufferSnapshot, row: u32) -> String {
+    snapshot
+        .text_for_range(Point::new(row, 0)..Point::new(row, snapshot.line_len(row)))
+        .collect()
+}
+
+fn text_between_rows(snapshot: &BufferSnapshot, start_row: u32, end_row: u32) -> String {
+    if start_row >= end_row {
+        return String::new();
+    }
+    snapshot
+        .text_for_range(Point::new(start_row, 0)..Point::new(end_row, 0))
+        .collect()
+}
+
+/// The cells of a script, each running from its marker to the next one. The lines before the first marker
+/// aren't a cell.
+fn script_cells(snapshot: &BufferSnapshot) -> Vec<CodeCell> {
+    let language = snapshot
+        .language()
+        .map(|language| language.name().to_string());
+    let row_count = snapshot.max_point().row + 1;
+    let marker_rows = (0..row_count)
+        .filter(|&row| {
+            let line = line_text(snapshot, row);
+            let line = line.trim_start();
+            CELL_MARKERS.iter().any(|marker| line.starts_with(marker))
+        })
+        .collect::<Vec<_>>();
+
+    marker_rows
+        .iter()
+        .enumerate()
+        .map(|(ix, &start_row)| {
+            let end = match marker_rows.get(ix + 1) {
+                Some(&next_row) => Point::new(next_row, 0),
+                None => snapshot.max_point(),
+            };
+            let code_start = Point::new(start_row + 1, 0).min(end);
+            let code = snapshot.text_for_range(code_start..end).collect();
+            CodeCell {
+                range: snapshot.anchor_before(Point::new(start_row, 0))..snapshot.anchor_after(end),
+                language: language.clone(),
+                code,
+            }
+        })
+        .collect()
+}
+
+/// The fenced code blocks of a Markdown buffer, opened with ```` ``` ```` or `~~~` and an info string
+/// naming their language.
+fn fenced_code_blocks(snapshot: &BufferSnapshot) -> Vec<CodeCell> {
+    let row_count = snapshot.max_point().row + 1;
+    let mut cells = Vec::new();
+    let mut open_fence = None::<(u32, String, Option<String>)>;
+    for row in 0..row_count {
+        let line = line_text(snapshot, row);
+        let line = line.trim_start();
+        let Some(fence_char) = line.chars().next().filter(|&c| c == '`' || c == '~') else {
+            continue;
+        };
+        let fence_len = line.chars().take_while(|&c| c == fence_char).count();
+        if fence_len < 3 {
+            continue;
+        }
+        let fence = &line[..fence_len];
+        match &open_fence {
+            Some((start_row, open, language))
+                if fence.starts_with(open.as_str()) && line[fence_len..].trim().is_empty() =>
+            {
+                let end = Point::new(row, snapshot.line_len(row));
+                cells.push(CodeCell {
+                    range: snapshot.anchor_before(Point::new(*start_row, 0))
+                        ..snapshot.anchor_after(end),
+                    language: language.clone(),
+                    code: text_between_rows(snapshot, start_row + 1, row),
+                });
+                open_fence = None;
+            }
+            Some(_) => {}
+            None => {
+                let language = line[fence_len..]
+                    .split_whitespace()
+                    .next()
+                    .map(|info| info.trim_matches(|c| c == '{' || c == '}').to_string())
+                    .filter(|info| !info.is_empty());
+                open_fence = Some((row, fence.to_string(), language));
+            }
+        }
+    }
+    cells
+}
+
+impl Project {
+    /// Runs the code cell that contains the position in its language's kernel, starting the kernel if needed.
+    ///
+    /// Replaces the previous executions of the cell, and returns the id of the new one.
+    pub fn run_code_cell(
+        &mut self,
+        buffer: &Model<Buffer>,
+        position: Anchor,
+        cx: &mut ModelContext<Self>,
+    ) -> Result<usize> {
+        if !self.is_local() {
+            bail!("Code cells can only be run by the host of the project");
+        }
+        let worktree_id = File::from_dyn(buffer.read(cx).file()).map(|file| file.worktree_id(cx));
+        if let Some(worktree_id) = worktree_id {
+            if !self.check_worktree_trust(worktree_id, cx) {
+                bail!("Code cells can't run until the worktree is trusted");
+            }
+        }
+        let buffer = buffer.read(cx);
+        let buffer_id = buffer.remote_id();
+        let snapshot = buffer.snapshot();
+        let working_dir = File::from_dyn(buffer.file()).map(|file| {
+            let worktree = file.worktree.read(cx);
+            let mut path = worktree.abs_path().to_path_buf();
+            if worktree.root_entry().map_or(false, |entry| entry.is_file()) {
+                path.pop();
+            }
+            path
+        });
+        let cell = code_cells(&snapshot)
+            .into_iter()
+            .find(|cell| {
+                cell.range.start.cmp(&position, &snapshot).is_le()
+                    && cell.range.end.cmp(&position, &snapshot).is_ge()
+            })
+            .ok_or_else(|| anyhow!("No code cell at the cursor"))?;
+        let language = cell
+            .language
+            .clone()
+            .ok_or_else(|| anyhow!("The code cell has no language"))?;
+        let (kernel_name, settings) = ProjectSettings::get_global(cx)
+            .code_cells
+            .kernels
+            .iter()
+            .find(|(name, _)| name.eq_ignore_ascii_case(&language))
+            .map(|(name, settings)| (name.clone(), settings.clone()))
+            .ok_or_else(|| anyhow!("No kernel is configured for {language}"))?;
+
+        if !self.code_cell_kernels.kernels.contains_key(&kernel_name) {
+            let kernel = self.start_kernel(kernel_name.clone(), settings, working_dir, cx)?;
+            self.code_cell_kernels
+                .kernels
+                .insert(kernel_name.clone(), kernel);
+        }
+
+        let id = self.code_cell_kernels.next_execution_id;
+        self.code_cell_kernels.next_execution_id += 1;
+        let executions = self
+            .code_cell_kernels
+            .executions
+            .entry(buffer_id)
+            .or_default();
+        executions.retain(|execution| {
+            execution
+                .range
+                .end
+                .cmp(&cell.range.start, &snapshot)
+                .is_lt()
+                || execution
+                    .range
+                    .start
+                    .cmp(&cell.range.end, &snapshot)
+                    .is_gt()
+        });
+        executions.push(CellExecution {
+            id,
+            range: cell.range.clone(),
+            outputs: Vec::new(),
+            status: CellExecutionStatus::Queued,
+        });
+        executions.sort_by(|a, b| a.range.start.cmp(&b.range.start, &snapshot));
+
+        let request = CellRequest {
+            buffer_id,
+            execution_id: id,
+            code: cell.code,
+        };
+        let sent = self
+            .code_cell_kernels
+            .kernels
+            .get(&kernel_name)
+            .map_or(false, |kernel| {
+                kernel.requests.unbounded_send(request).is_ok()
+            });
+        if !sent {
+            self.code_cell_kernels.kernels.remove(&kernel_name);
+            self.finish_cell_execution(
+                buffer_id,
+                id,
+                CellExecutionStatus::Failed("The kernel exited".to_string()),
+                cx,
+            );
+            bail!("The {kernel_name} kernel exited");
+        }
+        cx.emit(Event::CodeCellOutputsChanged(buffer_id));
+        Ok(id)
+    }
+
+    /// The executions of the buffer's code cells, sorted by their position.
+    pub fn code_cell_executions(&self, buffer_id: BufferId) -> &[CellExecution] {
+        self.code_cell_kernels
+            .executions
+            .get(&buffer_id)
+            .map_or(&[], |executions| executions.as_slice())
+    }
+
+    pub fn clear_code_cell_outputs(&mut self, buffer_id: BufferId, cx: &mut ModelContext<Self>) {
+        if self
+            .code_cell_kernels
+            .executions
+            .remove(&buffer_id)
+            .is_some()
+        {
+            cx.emit(Event::CodeCellOutputsChanged(buffer_id));
+        }
+    }
+
+    /// Stops all kernels, dropping their state. They are started again on the next run of a cell.
+    pub fn restart_kernels(&mut self, cx: &mut ModelContext<Self>) {
+        self.code_cell_kernels.kernels.clear();
+        for (buffer_id, executions) in &mut self.code_cell_kernels.executions {
+            let mut changed = false;
+            for execution in executions.iter_mut() {
+                if matches!(
+                    execution.status,
+                    CellExecutionStatus::Queued | CellExecutionStatus::Running
+                ) {
+                    execution.status =
+                        CellExecutionStatus::Failed("The kernel was restarted".to_string());
+                    changed = true;
+                }
+            }
+            if changed {
+                cx.emit(Event::CodeCellOutputsChanged(*buffer_id));
+            }
+        }
+    }
+
+    fn start_kernel(
+        &self,
+        name: String,
+        settings: KernelSettings,
+        working_dir: Option<PathBuf>,
+        cx: &mut ModelContext<Self>,
+    ) -> Result<Kernel> {
+        let mut command = smol::process::Command::new(&settings.command);
+        if let Some(working_dir) = working_dir {
+            command.current_dir(working_dir);
+        }
+        let mut child = command
+            .args(&settings.args)
+            .stdin(smol::process::Stdio::piped())
+            .stdout(smol::process::Stdio::piped())
+            .stderr(smol::process::Stdio::piped())
+            .kill_on_drop(true)
+            .spawn()
+            .with_context(|| format!("starting the {name} kernel `{}`", settings.command))?;
+        let mut stdin = child
+            .stdin
+            .take()
+            .ok_or_else(|| anyhow!("failed to acquire stdin"))?;
+        let stdout = child
+            .stdout
+            .take()
+            .ok_or_else(|| anyhow!("failed to acquire stdout"))?;
+        let stderr = child
+            .stderr
+            .take()
+            .ok_or_else(|| anyhow!("failed to acquire stderr"))?;
+
+        let kernel_name = name.clone();
+        cx.background_executor()
+            .spawn(async move {
+                let mut lines = BufReader::new(stderr).lines();
+                while let Some(Ok(line)) = lines.next().await {
+                    log::info!("{kernel_name} kernel stderr: {line}");
+                }
+            })
+            .detach();
+
+        let (requests_tx, mut requests_rx) = mpsc::unbounded::<CellRequest>();
+        let task = cx.spawn(|this, mut cx| async move {
+            let _child = child;
+            let mut stdout = BufReader::new(stdout).lines();
+            while let Some(request) = requests_rx.next().await {
+                let buffer_id = request.buffer_id;
+                let execution_id = request.execution_id;
+                if this
+                    .update(&mut cx, |this, cx| {
+                        this.set_cell_execution_status(
+                            buffer_id,
+                            execution_id,
+                            CellExecutionStatus::Running,
+                            cx,
+                        )
+                    })
+                    .is_err()
+                {
+                    return;
+                }
+                let result = run_cell(
+                    &this,
+                    &settings.execute_template,
+                    &request,
+                    &mut stdin,
+                    &mut stdout,
+                    &mut cx,
+                )
+                .await;
+                let status = match &result {
+                    Ok(()) => CellExecutionStatus::Finished,
+                    Err(error) => CellExecutionStatus::Failed(error.to_string()),
+                };
+                if this
+                    .update(&mut cx, |this, cx| {
+                        this.finish_cell_execution(buffer_id, execution_id, status, cx)
+                    })
+                    .is_err()
+                {
+                    return;
+                }
+                if result.is_err() {
+                    break;
+                }
+            }
+
+            // The kernel exited: fail the cells still waiting for it, and start it again on the next run.
+            requests_rx.close();
+            let mut pending = Vec::new();
+            while let Ok(Some(request)) = requests_rx.try_next() {
+                pending.push(request);
+            }
+            this.update(&mut cx, |this, cx| {
+                for request in pending {
+                    this.finish_cell_execution(
+                        request.buffer_id,
+                        request.execution_id,
+                        CellExecutionStatus::Failed("The kernel exited".to_string()),
+                        cx,
+                    );
+                }
+                this.code_cell_kernels.kernels.remove(&name);
+            })
+            .ok();
+        });
+
+        Ok(Kernel {
+            requests: requests_tx,
+            _task: task,
+        })
+    }
+
+    fn set_cell_execution_status(
+        &mut self,
+        buffer_id: BufferId,
+        execution_id: usize,
+        status: CellExecutionStatus,
+        cx: &mut ModelContext<Self>,
+    ) {
+        if let Some(execution) = self
+            .code_cell_kernels
+            .execution_mut(buffer_id, execution_id)
+        {
+            execution.status = status;
+            cx.emit(Event::CodeCellOutputsChanged(buffer_id));
+        }
+    }
+
+    fn finish_cell_execution(
+        &mut self,
+        buffer_id: BufferId,
+        execution_id: usize,
+        status: CellExecutionStatus,
+        cx: &mut ModelContext<Self>,
+    ) {
+        if let Some(execution) = self
+            .code_cell_kernels
+            .execution_mut(buffer_id, execution_id)
+        {
+            // A cell that was run again or restarted keeps its new status.
+            if matches!(
+                execution.status,
+                CellExecutionStatus::Queued | CellExecutionStatus::Running
+            ) {
+                execution.status = status;
+                cx.emit(Event::CodeCellOutputsChanged(buffer_id));
+            }
+        }
+    }
+
+    fn append_cell_output(
+        &mut self,
+        buffer_id: BufferId,
+        execution_id: usize,
+        line: &str,
+        cx: &mut ModelContext<Self>,
+    ) {
+        if let Some(execution) = self
+            .code_cell_kernels
+            .execution_mut(buffer_id, execution_id)
+        {
+            if let Some(CellOutput::Text(text)) = execution.outputs.last_mut() {
+                text.push_str(line);
+                text.push('\n');
+            } else {
+                execution
+                    .outputs
+                    .push(CellOutput::Text(format!("{line}\n")));
+            }
+            cx.emit(Event::CodeCellOutputsChanged(buffer_id));
+        }
+    }
+
+    fn append_cell_image(
+        &mut self,
+        buffer_id: BufferId,
+        execution_id: usize,
+        image: ImageData,
+        cx: &mut ModelContext<Self>,
+    ) {
+        if let Some(execution) = self
+            .code_cell_kernels
+            .execution_mut(buffer_id, execution_id)
+        {
+            execution.outputs.push(CellOutput::Image(Arc::new(image)));
+            cx.emit(Event::CodeCellOutputsChanged(buffer_id));
+        }
+    }
+}
+
+/// Decodes the output displayed by a kernel, from the rest of a line starting with [`DISPLAY_PREFIX`].
+pub(crate) fn decode_displayed_image(display: &str) -> Result<ImageData> {
+    let (mime_type, data) = display
+        .split_once(' ')
+        .ok_or_else(|| anyhow!("The displayed output has no data"))?;
+    let format = image::ImageFormat::from_mime_type(mime_type)
+        .ok_or_else(|| anyhow!("Can't display outputs of type {mime_type}"))?;
+    let bytes = base64::decode(data.trim())?;
+    let mut image = image::load_from_memory_with_format(&bytes, format)?.into_rgba8();
+    // GPUI renders images as BGRA.
+    for pixel in image.chunks_exact_mut(4) {
+        pixel.swap(0, 2);
+    }
+    Ok(ImageData::new(image))
+}
+
+/// Writes the cell to the kernel, and streams its output into the execution until the kernel prints the marker.
+async fn run_cell(
+    this: &WeakModel<Project>,
+    execute_template: &str,
+    request: &CellRequest,
+    stdin: &mut smol::process::ChildStdin,
+    stdout: &mut (impl Stream<Item = std::io::Result<String>> + Unpin),
+    cx: &mut AsyncAppContext,
+) -> Result<()> {
+    let marker = format!("__zed_cell_done_{}__", request.execution_id);
+    let input = execute_template
+        .replace("{code}", &serde_json::to_string(&request.code)?)
+        .replace("{marker}", &serde_json::to_string(&marker)?);
+    stdin.write_all(input.as_bytes()).await?;
+    stdin.write_all(b"\n").await?;
+    stdin.flush().await?;
+
+    loop {
+        let line = stdout
+            .next()
+            .await
+            .ok_or_else(|| anyhow!("The kernel exited"))??;
+        let (line, done) = match line.strip_suffix(marker.as_str()) {
+            Some(rest) => (rest, true),
+            None => (line.as_str(), false),
+        };
+        if let Some(display) = line.strip_prefix(DISPLAY_PREFIX) {
+            let display = display.to_string();
+            let image = cx
+                .background_executor()
+                .spawn(async move { decode_displayed_image(&display) })
+                .await;
+            this.update(cx, |this, cx| match image {
+                Ok(image) => {
+                    this.append_cell_image(request.buffer_id, request.execution_id, image, cx)
+                }
+                Err(error) => this.append_cell_output(
+                    request.buffer_id,
+                    request.execution_id,
+                    &format!("Failed to display the output: {error}"),
+                    cx,
+                ),
+            })?;
+        } else if !(done && line.is_empty()) {
+            this.update(cx, |this, cx| {
+                this.append_cell_output(request.buffer_id, request.execution_id, line, cx)
+            })?;
+        }
+        if done {
+            return Ok(());
+        }
+    }
+}
@@ -0,0 +1,543 @@
+//! Support for precomputed code indexes: LSIF dumps and SCIP indexes produced by indexers like `scip-typescript`
+//! or `rust-analyzer lsif`, loaded from the paths in the `code_indexes` project setting.
+//!
+//! They answer go to definition and find references when the language servers are unavailable
+//! or have not indexed the code, e.g. for dependencies.
+
+use std::{
+    io::Read as _,
+    ops::Range,
+    path::{Path, PathBuf},
+    sync::Arc,
+    time::SystemTime,
+};
+
+use anyhow::{anyhow, Context as _, Result};
+use collections::HashMap;
+use gpui::{AsyncAppContext, Model, ModelContext, Task, WeakModel};
+use language::{Bias, Buffer, Location, PointUtf16, Unclipped};
+use serde::Deserialize;
+use settings::{Settings, SettingsLocation};
+use util::ResultExt;
+
+use crate::{project_settings::ProjectSettings, File, LocationLink, Project};
+
+#[derive(Default)]
+pub(crate) struct CodeIndexes {
+    indexes: HashMap<PathBuf, (SystemTime, Arc<CodeIndex>)>,
+}
+
+/// The definitions and references of the symbols of an index, by the documents they occur in.
+#[derive(Debug, Default)]
+pub(crate) struct CodeIndex {
+    documents: HashMap<PathBuf, Vec<Occurrence>>,
+    symbols: Vec<IndexedSymbol>,
+}
+
+#[derive(Debug)]
+struct Occurrence {
+    range: Range<PointUtf16>,
+    symbol: usize,
+}
+
+#[derive(Debug, Default)]
+struct IndexedSymbol {
+    definitions: Vec<IndexLocation>,
+    references: Vec<IndexLocation>,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub(crate) struct IndexLocation {
+    pub path: PathBuf,
+    pub range: Range<PointUtf16>,
+}
+
+impl CodeIndex {
+    /// Parses a SCIP index if the file has the `.scip` extension, an LSIF dump otherwise.
+    ///
+    /// Relative paths of the SCIP documents are resolved against the project root of the index, or the worktree root.
+    pub(crate) fn parse(index_path: &Path, bytes: &[u8], worktree_root: &Path) -> Result<Self> {
+        if index_path
+            .extension()
+            .map_or(false, |extension| extension == "scip")
+        {
+            Self::from_scip(bytes, worktree_root)
+        } else {
+            Self::from_lsif(std::str::from_utf8(bytes).context("LSIF dump is not UTF-8")?)
+        }
+    }
+
+    fn from_scip(bytes: &[u8], worktree_root: &Path) -> Result<Self> {
+        use prost::Message as _;
+
+        let index = scip::Index::decode(bytes).context("decoding SCIP index")?;
+        let project_root = index
+            .metadata
+            .and_then(|metadata| lsp::Url::parse(&metadata.project_root).ok())
+            .and_then(|root| root.to_file_path().ok())
+            .unwrap_or_else(|| worktree_root.to_path_buf());
+
+        let mut this = Self::default();
+        let mut symbol_ixs = HashMap::<String, usize>::default();
+        for document in index.documents {
+            let path = project_root.join(&document.relative_path);
+            for occurrence in document.occurrences {
+                if occurrence.symbol.is_empty() {
+                    continue;
+                }
+                let range = match occurrence.range[..] {
+                    [start_row, start_column, end_column] => {
+                        scip_point(start_row, start_column)..scip_point(start_row, end_column)
+                    }
+                    [start_row, start_column, end_row, end_column] => {
+                        scip_point(start_row, start_column)..scip_point(end_row, end_column)
+                    }
+                    _ => continue,
+                };
+                // Local symbols are only unique within their document.
+                let key = if occurrence.symbol.starts_with("local ") {
+                    format!("{}:{}", document.relative_path, occurrence.symbol)
+                } else {
+                    occurrence.symbol
+                };
+                let symbol = *symbol_ixs.entry(key).or_insert_with(|| {
+                    this.symbols.push(IndexedSymbol::default());
+                    this.symbols.len() - 1
+                });
+                let location = IndexLocation {
+                    path: path.clone(),
+                    range: range.clone(),
+                };
+                if occurrence.symbol_roles & scip::SYMBOL_ROLE_DEFINITION != 0 {
+                    this.symbols[symbol].definitions.push(location.clone());
+                }
+                this.symbols[symbol].references.push(location);
+                this.documents
+                    .entry(path.clone())
+                    .or_default()
+                    .push(Occurrence { range, symbol });
+            }
+        }
+        Ok(this)
+    }
+
+    fn from_lsif(text: &str) -> Result<Self> {
+        let elements = if text.trim_start().starts_with('[') {
+            serde_json::from_str::<Vec<LsifElement>>(text).context("parsing LSIF dump")?
+        } else {
+            text.lines()
+                .filter(|line| !line.trim().is_empty())
+                .map(serde_json::from_str::<LsifElement>)
+                .collect::<Result<_, _>>()
+                .context("parsing LSIF dump")?
+        };
+
+        let mut documents = HashMap::<String, PathBuf>::default();
+        let mut ranges = HashMap::<String, Range<PointUtf16>>::default();
+        let mut range_documents = HashMap::<String, String>::default();
+        let mut next = HashMap::<String, String>::default();
+        let mut definition_results = HashMap::<String, String>::default();
+        let mut reference_results = HashMap::<String, String>::default();
+        let mut items = HashMap::<String, Vec<String>>::default();
+        for element in elements {
+            let id = element.id.to_string();
+            let out_v = element.out_v.as_ref().map(ToString::to_string);
+            let in_v = element.in_v.as_ref().map(ToString::to_string);
+            match (element.kind.as_str(), element.label.as_str()) {
+                ("vertex", "document") => {
+                    if let Some(path) = element
+                        .uri
+                        .and_then(|uri| lsp::Url::parse(&uri).ok())
+                        .and_then(|uri| uri.to_file_path().ok())
+                    {
+                        documents.insert(id, path);
+                    }
+                }
+                ("vertex", "range") => {
+                    if let Some((start, end)) = element.start.zip(element.end) {
+                        ranges.insert(id, lsif_point(start)..lsif_point(end));
+                    }
+                }
+                ("edge", "contains") => {
+                    if let Some(out_v) = out_v {
+                        for in_v in &element.in_vs {
+                            range_documents.insert(in_v.to_string(), out_v.clone());
+                        }
+                    }
+                }
+                ("edge", "next") => {
+                    if let Some((out_v, in_v)) = out_v.zip(in_v) {
+                        next.insert(out_v, in_v);
+                    }
+                }
+                ("edge", "textDocument/definition") => {
+                    if let Some((out_v, in_v)) = out_v.zip(in_v) {
+                        definition_results.insert(out_v, in_v);
+                    }
+                }
+                ("edge", "textDocument/references") => {
+                    if let Some((out_v, in_v)) = out_v.zip(in_v) {
+                        reference_results.insert(out_v, in_v);
+                    }
+                }
+                ("edge", "item") => {
+                    if let Some(out_v) = out_v {
+                        items
+                            .entry(out_v)
+                            .or_default()
+                            .extend(element.in_vs.iter().map(ToString::to_string));
+                    }
+                }
+                _ => {}
+            }
+        }
+
+        let location = |range_id: &String| {
+            let path = documents.get(range_documents.get(range_id)?)?;
+            Some(IndexLocation {
+                path: path.clone(),
+                range: ranges.get(range_id)?.clone(),
+            })
+        };
+        let item_locations = |result_id: Option<&String>| {
+            result_id
+                .and_then(|result_id| items.get(result_id))
+                .into_iter()
+                .flatten()
+                .filter_map(location)
+                .collect::<Vec<_>>()
+        };
+
+        let mut this = Self::default();
+        let mut symbol_ixs = HashMap::<(Option<&String>, Option<&String>), usize>::default();
+        for (range_id, range) in &ranges {
+            let Some(path) = range_documents
+                .get(range_id)
+                .and_then(|document_id| documents.get(document_id))
+            else {
+                continue;
+            };
+            // The results are attached to the range or to one of the result sets it is chained to.
+            let mut chain = vec![range_id];
+            while let Some(next_id) = next.get(*chain.last().unwrap()) {
+                if chain.contains(&next_id) {
+                    break;
+                }
+                chain.push(next_id);
+            }
+            let definition_result = chain.iter().find_map(|id| definition_results.get(*id));
+            let reference_result = chain.iter().find_map(|id| reference_results.get(*id));
+            if definition_result.is_none() && reference_result.is_none() {
+                continue;
+            }
+            let symbol = *symbol_ixs
+                .entry((definition_result, reference_result))
+                .or_insert_with(|| {
+                    this.symbols.push(IndexedSymbol {
+                        definitions: item_locations(definition_result),
+                        references: item_locations(reference_result),
+                    });
+                    this.symbols.len() - 1
+                });
+            this.documents
+                .entry(path.clone())
+                .or_default()
+                .push(Occurrence {
+                    range: range.clone(),
+                    symbol,
+                });
+        }
+        Ok(this)
+    }
+
+    /// Returns the range of the innermost symbol occurrence at the position and the symbol.
+    fn symbol_at(
+        &self,
+        path: &Path,
+        position: PointUtf16,
+    ) -> Option<(Range<PointUtf16>, &IndexedSymbol)> {
+        self.documents
+            .get(path)?
+            .iter()
+            .filter(|occurrence| {
+                occurrence.range.start <= position && position <= occurrence.range.end
+            })
+            // Of the nested occurrences, the innermost one starts last.
+            .max_by_key(|occurrence| occurrence.range.start)
+            .map(|occurrence| (occurrence.range.clone(), &self.symbols[occurrence.symbol]))
+    }
+
+    pub(crate) fn definitions(
+        &self,
+        path: &Path,
+        position: PointUtf16,
+    ) -> Option<(Range<PointUtf16>, Vec<IndexLocation>)> {
+        let (range, symbol) = self.symbol_at(path, position)?;
+        Some((range, symbol.definitions.clone())).filter(|(_, definitions)| !definitions.is_empty())
+    }
+
+    pub(crate) fn references(
+        &self,
+        path: &Path,
+        position: PointUtf16,
+    ) -> Option<Vec<IndexLocation>> {
+        let (_, symbol) = self.symbol_at(path, position)?;
+        Some(symbol.references.clone()).filter(|references| !references.is_empty())
+    }
+}
+
+impl Project {
+    /// Falls back to the definitions from the code indexes of the worktree, if the language servers found none.
+    pub(crate) fn definition_with_index_fallback(
+        &self,
+        buffer: &Model<Buffer>,
+        position: PointUtf16,
+        definitions: Task<Result<Vec<LocationLink>>>,
+        cx: &mut ModelContext<Self>,
+    ) -> Task<Result<Vec<LocationLink>>> {
+        if !self.is_local() {
+            return definitions;
+        }
+        let buffer = buffer.clone();
+        cx.spawn(move |this, mut cx| async move {
+            let definitions = definitions.await;
+            if matches!(&definitions, Ok(definitions) if !definitions.is_empty()) {
+                return definitions;
+            }
+            let indexed = this
+                .update(&mut cx, |this, cx| {
+                    this.query_code_indexes(
+                        &buffer,
+                        move |index, path| index.definitions(path, position),
+                        cx,
+                    )
+                })?
+                .await
+                .log_err()
+                .flatten();
+            let Some((origin_range, targets)) = indexed else {
+                return definitions;
+            };
+            let origin = Location {
+                range: buffer.read_with(&cx, |buffer, _| anchor_range(buffer, &origin_range))?,
+                buffer,
+            };
+            let mut links = Vec::new();
+            for target in targets {
+                if let Some(target) = open_index_location(&this, target, &mut cx).await.log_err() {
+                    links.push(LocationLink {
+                        origin: Some(origin.clone()),
+                        target,
+                    });
+                }
+            }
+            Ok(links)
+        })
+    }
+
+    /// Falls back to the references from the code indexes of the worktree, if the language servers found none.
+    pub(crate) fn references_with_index_fallback(
+        &self,
+        buffer: &Model<Buffer>,
+        position: PointUtf16,
+        references: Task<Result<Vec<Location>>>,
+        cx: &mut ModelContext<Self>,
+    ) -> Task<Result<Vec<Location>>> {
+        if !self.is_local() {
+            return references;
+        }
+        let buffer = buffer.clone();
+        cx.spawn(move |this, mut cx| async move {
+            let references = references.await;
+            if matches!(&references, Ok(references) if !references.is_empty()) {
+                return references;
+            }
+            let indexed = this
+                .update(&mut cx, |this, cx| {
+                    this.query_code_indexes(
+                        &buffer,
+                        move |index, path| index.references(path, position),
+                        cx,
+                    )
+                })?
+                .await
+                .log_err()
+                .flatten();
+            let Some(targets) = indexed else {
+                return references;
+            };
+            let mut locations = Vec::new();
+            for target in targets {
+                if let Some(location) = open_index_location(&this, target, &mut cx).await.log_err()
+                {
+                    locations.push(location);
+                }
+            }
+            Ok(locations)
+        })
+    }
+
+    /// Returns the first result of the query over the code indexes configured for the worktree of the buffer.
+    fn query_code_indexes<R: Send + 'static>(
+        &mut self,
+        buffer: &Model<Buffer>,
+        query: impl Fn(&CodeIndex, &Path) -> Option<R> + Send + 'static,
+        cx: &mut ModelContext<Self>,
+    ) -> Task<Result<Option<R>>> {
+        let Some(file) = File::from_dyn(buffer.read(cx).file()).and_then(File::as_local) else {
+            return Task::ready(Ok(None));
+        };
+        let abs_path = file.abs_path(cx);
+        let worktree = file.worktree.read(cx);
+        let worktree_root = worktree.abs_path().to_path_buf();
+        let index_paths = ProjectSettings::get(
+            Some(SettingsLocation {
+                worktree_id: worktree.id().to_proto() as usize,
+                path: &file.path,
+            }),
+            cx,
+        )
+        .code_indexes
+        .iter()
+        .map(|path| worktree_root.join(path))
+        .collect::<Vec<_>>();
+        let loads = index_paths
+            .into_iter()
+            .map(|index_path| self.load_code_index(index_path, worktree_root.clone(), cx))
+            .collect::<Vec<_>>();
+        cx.background_executor().spawn(async move {
+            for load in loads {
+                if let Some(index) = load.await.log_err() {
+                    if let Some(result) = query(&index, &abs_path) {
+                        return Ok(Some(result));
+                    }
+                }
+            }
+            Ok(None)
+        })
+    }
+
+    /// Loads the index from the file, reusing the cached one if the file was not modified since.
+    fn load_code_index(
+        &mut self,
+        index_path: PathBuf,
+        worktree_root: PathBuf,
+        cx: &mut ModelContext<Self>,
+    ) -> Task<Result<Arc<CodeIndex>>> {
+        let fs = self.fs.clone();
+        let cached = self.code_indexes.indexes.get(&index_path).cloned();
+        cx.spawn(move |this, mut cx| async move {
+            let mtime = fs
+                .metadata(&index_path)
+                .await?
+                .ok_or_else(|| anyhow!("code index {index_path:?} does not exist"))?
+                .mtime;
+            if let Some((cached_mtime, index)) = cached {
+                if cached_mtime == mtime {
+                    return Ok(index);
+                }
+            }
+
+            let index = cx
+                .background_executor()
+                .spawn({
+                    let index_path = index_path.clone();
+                    async move {
+                        let mut bytes = Vec::new();
+                        fs.open_sync(&index_path).await?.read_to_end(&mut bytes)?;
+                        CodeIndex::parse(&index_path, &bytes, &worktree_root)
+                            .with_context(|| format!("loading code index {index_path:?}"))
+                    }
+                })
+                .await?;
+            let index = Arc::new(index);
+            this.update(&mut cx, |this, _| {
+                this.code_indexes
+                    .indexes
+                    .insert(index_path, (mtime, index.clone()));
+            })?;
+            Ok(index)
+        })
+    }
+}
+
+async fn open_index_location(
+    this: &WeakModel<Project>,
+    location: IndexLocation,
+    cx: &mut AsyncAppContext,
+) -> Result<Location> {
+    let buffer = this
+        .update(cx, |this, cx| this.open_local_buffer(&location.path, cx))?
+        .await?;
+    let range = buffer.read_with(cx, |buffer, _| anchor_range(buffer, &location.range))?;
+    Ok(Location { buffer, range })
+}
+
+fn anchor_range(buffer: &Buffer, range: &Range<PointUtf16>) -> Range<language::Anchor> {
+    let start = buffer.clip_point_utf16(Unclipped(range.start), Bias::Left);
+    let end = buffer.clip_point_utf16(Unclipped(range.end), Bias::Left);
+    buffer.anchor_after(start)..buffer.anchor_before(end)
+}
+
+fn scip_point(row: i32, column: i32) -> PointUtf16 {
+    PointUtf16::new(row.max(0) as u32, column.max(0) as u32)
+}
+
+fn lsif_point(position: lsp::Position) -> PointUtf16 {
+    PointUtf16::new(position.line, position.character)
+}
+
+/// A vertex or an edge of an LSIF dump, with the fields used by the index.
+#[derive(Deserialize)]
+struct LsifElement {
+    id: serde_json::Value,
+    #[serde(rename = "type")]
+    kind: String,
+    label: String,
+    uri: Option<String>,
+    start: Option<lsp::Position>,
+    end: Option<lsp::Position>,
+    #[serde(rename = "outV")]
+    out_v: Option<serde_json::Value>,
+    #[serde(rename = "inV")]
+    in_v: Option<serde_json::Value>,
+    #[serde(rename = "inVs", default)]
+    in_vs: Vec<serde_json::Value>,
+}
+
+/// The subset of the SCIP schema (https://github.com/sourcegraph/scip/blob/main/scip.proto) needed for navigation.
+pub(crate) mod scip {
+    pub const SYMBOL_ROLE_DEFINITION: i32 = 0x1;
+
+    #[derive(Clone, PartialEq, prost::Message)]
+    pub struct Index {
+        #[prost(message, optional, tag = "1")]
+        pub metadata: Option<Metadata>,
+        #[prost(message, repeated, tag = "2")]
+        pub documents: Vec<Document>,
+    }
+
+    #[derive(Clone, PartialEq, prost::Message)]
+    pub struct Metadata {
+        #[prost(string, tag = "3")]
+        pub project_root: String,
+    }
+
+    #[derive(Clone, PartialEq, prost::Message)]
+    pub struct Document {
+        #[prost(string, tag = "1")]
+        pub relative_path: String,
+        #[prost(message, repeated, tag = "2")]
+        pub occurrences: Vec<Occurrence>,
+    }
+
+    #[derive(Clone, PartialEq, prost::Message)]
+    pub struct Occurrence {
+        #[prost(int32, repeated, tag = "1")]
+        pub range: Vec<i32>,
+        #[prost(string, tag = "2")]
+        pub symbol: String,
+        #[prost(int32, tag = "3")]
+        pub symbol_roles: i32,
+    }
+}
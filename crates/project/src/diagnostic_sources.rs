@@ -0,0 +1,349 @@
+//! Diagnostic sources: the linters other than language servers, like prose linters, that check the buffers of
+//! local projects as they change or when they're saved. Their diagnostics are stored with the ones of the language
+//! servers, each source being given its own language server id, and the fixes they suggest are offered as quick
+//! fix code actions.
+//!
+//! The external linters of the language settings are diagnostic sources, and more can be registered with
+//! [`Project::register_diagnostic_source`].
+
+use std::{ops::Range, sync::Arc, time::Duration};
+
+use anyhow::{anyhow, Context as _, Result};
+use async_trait::async_trait;
+use collections::HashMap;
+use futures::future::join_all;
+use gpui::{AppContext, AsyncAppContext, Model, ModelContext, Task};
+use language::{
+    language_settings::{language_settings, LintTrigger},
+    Anchor, Bias, Buffer, Diagnostic, DiagnosticEntry, PointUtf16, ToOffset as _, Unclipped,
+};
+use lsp::{DiagnosticSeverity, LanguageServerId};
+use text::BufferId;
+use util::{post_inc, ResultExt};
+
+use crate::{CodeAction, File, Project, ProjectTransaction};
+
+const LINT_DEBOUNCE: Duration = Duration::from_millis(500);
+
+/// The key of the id of a fix in the data of its code action.
+const FIX_ID_KEY: &str = "diagnosticSourceFix";
+
+/// A linter that isn't a language server, checking the buffers of local projects.
+#[async_trait(?Send)]
+pub trait DiagnosticSource: 'static {
+    /// The name of the source, shown as the source of its diagnostics.
+    fn name(&self) -> &str;
+
+    /// When the source checks buffers.
+    fn trigger(&self) -> LintTrigger {
+        LintTrigger::Change
+    }
+
+    /// Whether the source checks the buffer.
+    fn applies_to(&self, _buffer: &Buffer, _cx: &AppContext) -> bool {
+        true
+    }
+
+    /// Checks the current contents of the buffer.
+    async fn check(
+        &self,
+        buffer: &Model<Buffer>,
+        cx: &mut AsyncAppContext,
+    ) -> Result<Vec<SourceDiagnostic>>;
+}
+
+/// A problem that a diagnostic source found in a buffer.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct SourceDiagnostic {
+    pub range: Range<PointUtf16>,
+    pub severity: DiagnosticSeverity,
+    /// The source of the problem, when it's more specific than the name of the diagnostic source.
+    pub source: Option<String>,
+    pub code: Option<String>,
+    pub message: String,
+    /// The fixes of the problem, offered as code actions where it's reported.
+    pub fixes: Vec<DiagnosticFix>,
+}
+
+/// A fix of a problem found by a diagnostic source.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct DiagnosticFix {
+    pub title: String,
+    /// The edits of the fix, in the contents of the buffer that was checked.
+    pub edits: Vec<(Range<PointUtf16>, String)>,
+}
+
+/// A fix of the last check of a buffer, anchored in it.
+struct BufferFix {
+    id: usize,
+    range: Range<Anchor>,
+    title: String,
+    edits: Vec<(Range<Anchor>, String)>,
+}
+
+#[derive(Default)]
+pub(crate) struct DiagnosticSources {
+    registered: Vec<Arc<dyn DiagnosticSource>>,
+    /// The scheduled or running checks, by buffer and trigger.
+    lints: HashMap<(BufferId, LintTrigger), Task<()>>,
+    /// The diagnostics server ids of the sources, by name.
+    server_ids: HashMap<String, LanguageServerId>,
+    /// The fixes of the last checks of the buffers, by source.
+    fixes: HashMap<BufferId, HashMap<LanguageServerId, Vec<BufferFix>>>,
+    next_fix_id: usize,
+}
+
+impl DiagnosticSources {
+    pub(crate) fn buffer_removed(&mut self, buffer_id: BufferId) {
+        self.lints
+            .retain(|(lint_buffer_id, _), _| *lint_buffer_id != buffer_id);
+        self.fixes.remove(&buffer_id);
+    }
+
+    fn is_source(&self, server_id: LanguageServerId) -> bool {
+        self.server_ids.values().any(|id| *id == server_id)
+    }
+}
+
+impl Project {
+    /// Registers a source of diagnostics, which then checks the buffers of the project it applies to.
+    pub fn register_diagnostic_source(
+        &mut self,
+        source: Arc<dyn DiagnosticSource>,
+        cx: &mut ModelContext<Self>,
+    ) {
+        self.diagnostic_sources.registered.push(source);
+        for buffer in self.opened_buffers() {
+            self.schedule_lint(&buffer, LintTrigger::Change, cx);
+        }
+    }
+
+    /// Checks the buffer with its diagnostic sources run on the trigger after a short delay, cancelling the
+    /// previously scheduled check, if any.
+    pub(crate) fn schedule_lint(
+        &mut self,
+        buffer: &Model<Buffer>,
+        trigger: LintTrigger,
+        cx: &mut ModelContext<Self>,
+    ) {
+        if !self.is_local() {
+            return;
+        }
+        let buffer_ref = buffer.read(cx);
+        let Some(file) = File::from_dyn(buffer_ref.file()) else {
+            return;
+        };
+        if file.as_local().is_none() {
+            return;
+        }
+        let mut sources = Vec::<Arc<dyn DiagnosticSource>>::new();
+        // The linters of the settings may be configured by the worktree.
+        if self.is_worktree_trusted(file.worktree_id(cx), cx) {
+            sources.extend(
+                language_settings(buffer_ref.language(), buffer_ref.file(), cx)
+                    .linters
+                    .iter()
+                    .map(|linter| Arc::new(linter.clone()) as Arc<dyn DiagnosticSource>),
+            );
+        }
+        sources.extend(
+            self.diagnostic_sources
+                .registered
+                .iter()
+                .filter(|source| source.applies_to(buffer_ref, cx))
+                .cloned(),
+        );
+        sources.retain(|source| source.trigger() == trigger);
+        if sources.is_empty() {
+            return;
+        }
+
+        let buffer_id = buffer_ref.remote_id();
+        let buffer = buffer.downgrade();
+        let lint = cx.spawn(move |this, mut cx| async move {
+            if trigger == LintTrigger::Change {
+                cx.background_executor().timer(LINT_DEBOUNCE).await;
+            }
+            let Some(buffer) = buffer.upgrade() else {
+                return;
+            };
+            let Ok(version) = buffer.update(&mut cx, |buffer, _| buffer.version()) else {
+                return;
+            };
+            let runs = sources.iter().map(|source| {
+                let buffer = buffer.clone();
+                let mut cx = cx.clone();
+                async move {
+                    let diagnostics = source
+                        .check(&buffer, &mut cx)
+                        .await
+                        .with_context(|| format!("running diagnostic source {:?}", source.name()))
+                        .log_err()?;
+                    Some((source.name().to_string(), diagnostics))
+                }
+            });
+            let results = join_all(runs).await;
+            this.update(&mut cx, |this, cx| {
+                this.diagnostic_sources.lints.remove(&(buffer_id, trigger));
+                // The buffer changed while the sources ran, so their diagnostics may be misplaced.
+                if buffer.read(cx).version() != version {
+                    return;
+                }
+                for (name, diagnostics) in results.into_iter().flatten() {
+                    this.publish_source_diagnostics(&buffer, name, diagnostics, cx)
+                        .log_err();
+                }
+            })
+            .ok();
+        });
+        self.diagnostic_sources
+            .lints
+            .insert((buffer_id, trigger), lint);
+    }
+
+    fn publish_source_diagnostics(
+        &mut self,
+        buffer: &Model<Buffer>,
+        name: String,
+        diagnostics: Vec<SourceDiagnostic>,
+        cx: &mut ModelContext<Self>,
+    ) -> Result<()> {
+        let abs_path = File::from_dyn(buffer.read(cx).file())
+            .and_then(|file| Some(file.as_local()?.abs_path(cx)))
+            .context("checked buffer has no local path")?;
+        let server_id = match self.diagnostic_sources.server_ids.get(&name) {
+            Some(server_id) => *server_id,
+            None => {
+                let server_id = self.languages.next_language_server_id();
+                self.diagnostic_sources
+                    .server_ids
+                    .insert(name.clone(), server_id);
+                server_id
+            }
+        };
+
+        let snapshot = buffer.read(cx).snapshot();
+        let anchor = |point: PointUtf16, bias: Bias| {
+            snapshot.anchor_at(snapshot.clip_point_utf16(Unclipped(point), bias), bias)
+        };
+        let mut fixes = Vec::new();
+        let mut entries = Vec::new();
+        for diagnostic in diagnostics {
+            let range = anchor(diagnostic.range.start, Bias::Left)
+                ..anchor(diagnostic.range.end, Bias::Right);
+            for fix in diagnostic.fixes {
+                fixes.push(BufferFix {
+                    id: post_inc(&mut self.diagnostic_sources.next_fix_id),
+                    range: range.clone(),
+                    title: fix.title,
+                    edits: fix
+                        .edits
+                        .into_iter()
+                        .map(|(range, text)| {
+                            (
+                                anchor(range.start, Bias::Left)..anchor(range.end, Bias::Right),
+                                text,
+                            )
+                        })
+                        .collect(),
+                });
+            }
+            entries.push(DiagnosticEntry {
+                range: Unclipped(diagnostic.range.start)..Unclipped(diagnostic.range.end),
+                diagnostic: Diagnostic {
+                    source: Some(diagnostic.source.unwrap_or_else(|| name.clone())),
+                    code: diagnostic.code,
+                    severity: diagnostic.severity,
+                    message: diagnostic.message,
+                    group_id: post_inc(&mut self.next_diagnostic_group_id),
+                    is_primary: true,
+                    is_disk_based: false,
+                    is_unnecessary: false,
+                },
+            });
+        }
+        self.diagnostic_sources
+            .fixes
+            .entry(snapshot.remote_id())
+            .or_default()
+            .insert(server_id, fixes);
+        self.update_diagnostic_entries(server_id, abs_path, None, entries, cx)
+    }
+
+    /// Returns the fixes of the diagnostic sources that intersect the range, as quick fix code actions.
+    pub(crate) fn diagnostic_source_fixes(
+        &self,
+        buffer: &Model<Buffer>,
+        range: &Range<Anchor>,
+        cx: &AppContext,
+    ) -> Vec<CodeAction> {
+        let buffer = buffer.read(cx);
+        let Some(fixes) = self.diagnostic_sources.fixes.get(&buffer.remote_id()) else {
+            return Vec::new();
+        };
+        let range = range.start.to_offset(buffer)..range.end.to_offset(buffer);
+        fixes
+            .iter()
+            .flat_map(|(server_id, fixes)| fixes.iter().map(move |fix| (*server_id, fix)))
+            .filter(|(_, fix)| {
+                fix.range.start.to_offset(buffer) <= range.end
+                    && range.start <= fix.range.end.to_offset(buffer)
+            })
+            .map(|(server_id, fix)| CodeAction {
+                server_id,
+                range: fix.range.clone(),
+                lsp_action: lsp::CodeAction {
+                    title: fix.title.clone(),
+                    kind: Some(lsp::CodeActionKind::QUICKFIX),
+                    data: Some(serde_json::json!({ FIX_ID_KEY: fix.id })),
+                    ..Default::default()
+                },
+            })
+            .collect()
+    }
+
+    /// Applies the code action if it's the fix of a diagnostic source, returning `None` otherwise.
+    pub(crate) fn apply_diagnostic_source_fix(
+        &self,
+        buffer: &Model<Buffer>,
+        action: &CodeAction,
+        push_to_history: bool,
+        cx: &mut ModelContext<Self>,
+    ) -> Option<Task<Result<ProjectTransaction>>> {
+        if !self.diagnostic_sources.is_source(action.server_id) {
+            return None;
+        }
+        let fix_id = action
+            .lsp_action
+            .data
+            .as_ref()
+            .and_then(|data| data.get(FIX_ID_KEY)?.as_u64())?;
+        let Some(fix) = self
+            .diagnostic_sources
+            .fixes
+            .get(&buffer.read(cx).remote_id())
+            .and_then(|fixes| fixes.get(&action.server_id))
+            .and_then(|fixes| fixes.iter().find(|fix| fix.id as u64 == fix_id))
+        else {
+            return Some(Task::ready(Err(anyhow!(
+                "the fix {:?} is outdated",
+                action.lsp_action.title
+            ))));
+        };
+
+        let mut project_transaction = ProjectTransaction::default();
+        buffer.update(cx, |buffer, cx| {
+            buffer.finalize_last_transaction();
+            buffer.start_transaction();
+            buffer.edit(fix.edits.iter().cloned(), None, cx);
+            if buffer.end_transaction(cx).is_some() {
+                let transaction = buffer.finalize_last_transaction().unwrap().clone();
+                if !push_to_history {
+                    buffer.forget_transaction(transaction.id);
+                }
+                project_transaction.0.insert(cx.handle(), transaction);
+            }
+        });
+        Some(Task::ready(Ok(project_transaction)))
+    }
+}
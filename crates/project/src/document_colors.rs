@@ -0,0 +1,442 @@
+//! Support for document colors: language servers report the color literals of the document with
+//! `textDocument/documentColor`, e.g. the colors in CSS or the color classes of Tailwind, which are shown as swatches.
+//!
+//! A color can be rewritten in the formats the server offers with `textDocument/colorPresentation`.
+
+use std::{ops::Range, sync::Arc};
+
+use anyhow::{anyhow, Context as _, Result};
+use client::TypedEnvelope;
+use collections::HashMap;
+use futures::future::join_all;
+use gpui::{AsyncAppContext, Model, ModelContext, Task};
+use language::{
+    point_from_lsp, point_to_lsp,
+    proto::{deserialize_anchor, deserialize_version, serialize_anchor, serialize_version},
+    Anchor, Bias, Buffer, BufferSnapshot, ToPointUtf16,
+};
+use lsp::LanguageServerId;
+use rpc::proto;
+use text::BufferId;
+use util::ResultExt;
+
+use crate::{File, Project};
+
+/// A range of the buffer that denotes a color.
+#[derive(Clone, Debug)]
+pub struct DocumentColor {
+    pub range: Range<Anchor>,
+    pub color: lsp::Color,
+    pub server_id: LanguageServerId,
+}
+
+/// Document colors of a buffer, sorted by their position.
+#[derive(Clone, Debug, Default)]
+pub struct BufferDocumentColors {
+    /// The version of the buffer the colors were computed for.
+    pub version: clock::Global,
+    pub colors: Arc<[DocumentColor]>,
+}
+
+impl BufferDocumentColors {
+    /// Returns the color that contains the given position, if any.
+    pub fn color_at(&self, position: Anchor, snapshot: &BufferSnapshot) -> Option<&DocumentColor> {
+        self.colors.iter().find(|color| {
+            color.range.start.cmp(&position, snapshot).is_le()
+                && color.range.end.cmp(&position, snapshot).is_ge()
+        })
+    }
+}
+
+/// A way of writing a color, offered by the language server.
+#[derive(Clone, Debug)]
+pub struct ColorPresentation {
+    pub label: String,
+    /// The edit that writes the color, replacing the color's range with the label if `None`.
+    pub text_edit: Option<(Range<Anchor>, String)>,
+    /// Further edits to make along with the color's, e.g. an import.
+    pub additional_text_edits: Vec<(Range<Anchor>, String)>,
+}
+
+impl ColorPresentation {
+    /// The edits that apply the presentation to the given color.
+    pub fn edits(&self, color: &DocumentColor) -> Vec<(Range<Anchor>, String)> {
+        let edit = self
+            .text_edit
+            .clone()
+            .unwrap_or_else(|| (color.range.clone(), self.label.clone()));
+        std::iter::once(edit)
+            .chain(self.additional_text_edits.iter().cloned())
+            .collect()
+    }
+}
+
+#[derive(Default)]
+pub(crate) struct DocumentColorsCache {
+    buffers: HashMap<BufferId, BufferDocumentColors>,
+}
+
+impl DocumentColorsCache {
+    pub(crate) fn language_server_removed(&mut self, server_id: LanguageServerId) {
+        self.buffers.retain(|_, cached| {
+            cached
+                .colors
+                .iter()
+                .all(|color| color.server_id != server_id)
+        });
+    }
+
+    pub(crate) fn buffer_edited(&mut self, buffer_id: BufferId) {
+        self.buffers.remove(&buffer_id);
+    }
+
+    pub(crate) fn buffer_removed(&mut self, buffer_id: BufferId) {
+        self.buffers.remove(&buffer_id);
+    }
+}
+
+impl Project {
+    /// Returns the colors of the buffer, from all of its language servers that provide them.
+    ///
+    /// The colors are cached until the buffer is edited.
+    pub fn document_colors(
+        &mut self,
+        buffer: &Model<Buffer>,
+        cx: &mut ModelContext<Self>,
+    ) -> Task<Result<BufferDocumentColors>> {
+        let buffer_id = buffer.read(cx).remote_id();
+        let version = buffer.read(cx).version();
+        if let Some(cached) = self.document_colors.buffers.get(&buffer_id) {
+            if cached.version == version {
+                return Task::ready(Ok(cached.clone()));
+            }
+        }
+
+        if let Some(project_id) = self.remote_id().filter(|_| !self.is_local()) {
+            let request = self.client.request(proto::GetDocumentColors {
+                project_id,
+                buffer_id: buffer_id.to_proto(),
+                version: serialize_version(&version),
+            });
+            let buffer = buffer.clone();
+            return cx.spawn(move |this, mut cx| async move {
+                let response = request.await?;
+                let version = deserialize_version(&response.version);
+                buffer
+                    .update(&mut cx, |buffer, _| {
+                        buffer.wait_for_version(version.clone())
+                    })?
+                    .await?;
+                let colors = BufferDocumentColors {
+                    version,
+                    colors: response
+                        .colors
+                        .into_iter()
+                        .filter_map(|color| deserialize_document_color(color).log_err())
+                        .collect(),
+                };
+                this.update(&mut cx, |this, _| {
+                    this.document_colors
+                        .buffers
+                        .insert(buffer_id, colors.clone());
+                })?;
+                Ok(colors)
+            });
+        } else if !self.is_local() {
+            return Task::ready(Ok(BufferDocumentColors::default()));
+        }
+
+        let servers = self
+            .language_servers_for_buffer(buffer.read(cx), cx)
+            .map(|(_, server)| server)
+            .filter(|server| server.capabilities().color_provider.is_some())
+            .cloned()
+            .collect::<Vec<_>>();
+        let Some(uri) = File::from_dyn(buffer.read(cx).file())
+            .and_then(File::as_local)
+            .and_then(|file| lsp::Url::from_file_path(file.abs_path(cx)).ok())
+        else {
+            return Task::ready(Ok(BufferDocumentColors::default()));
+        };
+        if servers.is_empty() {
+            return Task::ready(Ok(BufferDocumentColors::default()));
+        }
+
+        let snapshot = buffer.read(cx).snapshot();
+        let requests = servers
+            .iter()
+            .map(|server| {
+                let server_id = server.server_id();
+                let request =
+                    server.request::<lsp::request::DocumentColor>(lsp::DocumentColorParams {
+                        text_document: lsp::TextDocumentIdentifier::new(uri.clone()),
+                        work_done_progress_params: Default::default(),
+                        partial_result_params: Default::default(),
+                    });
+                async move { (server_id, request.await) }
+            })
+            .collect::<Vec<_>>();
+        cx.spawn(move |this, mut cx| async move {
+            let mut colors = Vec::new();
+            for (server_id, response) in join_all(requests).await {
+                let Some(lsp_colors) = response.context("document colors LSP request").log_err()
+                else {
+                    continue;
+                };
+                colors.extend(
+                    lsp_colors
+                        .into_iter()
+                        .map(|lsp_color| document_color_from_lsp(lsp_color, server_id, &snapshot)),
+                );
+            }
+            colors.sort_by(|a, b| a.range.start.cmp(&b.range.start, &snapshot));
+            let colors = BufferDocumentColors {
+                version: snapshot.version().clone(),
+                colors: colors.into(),
+            };
+            this.update(&mut cx, |this, _| {
+                this.document_colors
+                    .buffers
+                    .insert(buffer_id, colors.clone());
+            })?;
+            Ok(colors)
+        })
+    }
+
+    /// Returns the ways of writing the color that its language server offers.
+    pub fn color_presentations(
+        &self,
+        buffer: &Model<Buffer>,
+        color: &DocumentColor,
+        cx: &mut ModelContext<Self>,
+    ) -> Task<Result<Vec<ColorPresentation>>> {
+        if self.is_local() {
+            let Some((_, server)) =
+                self.language_server_for_buffer(buffer.read(cx), color.server_id, cx)
+            else {
+                return Task::ready(Ok(Vec::new()));
+            };
+            let Some(uri) = File::from_dyn(buffer.read(cx).file())
+                .and_then(File::as_local)
+                .and_then(|file| lsp::Url::from_file_path(file.abs_path(cx)).ok())
+            else {
+                return Task::ready(Ok(Vec::new()));
+            };
+
+            let snapshot = buffer.read(cx).snapshot();
+            let range = lsp::Range::new(
+                point_to_lsp(color.range.start.to_point_utf16(&snapshot)),
+                point_to_lsp(color.range.end.to_point_utf16(&snapshot)),
+            );
+            let request = server.request::<lsp::request::ColorPresentationRequest>(
+                lsp::ColorPresentationParams {
+                    text_document: lsp::TextDocumentIdentifier::new(uri),
+                    color: color.color,
+                    range,
+                    work_done_progress_params: Default::default(),
+                    partial_result_params: Default::default(),
+                },
+            );
+            cx.background_executor().spawn(async move {
+                let presentations = request.await.context("color presentation LSP request")?;
+                Ok(presentations
+                    .into_iter()
+                    .map(|presentation| color_presentation_from_lsp(presentation, &snapshot))
+                    .collect())
+            })
+        } else if let Some(project_id) = self.remote_id() {
+            let request = self.client.request(proto::GetColorPresentations {
+                project_id,
+                buffer_id: buffer.read(cx).remote_id().to_proto(),
+                color: Some(serialize_document_color(color)),
+            });
+            cx.background_executor().spawn(async move {
+                let response = request.await?;
+                response
+                    .presentations
+                    .into_iter()
+                    .map(deserialize_color_presentation)
+                    .collect()
+            })
+        } else {
+            Task::ready(Err(anyhow!("project does not have a remote id")))
+        }
+    }
+
+    pub(crate) async fn handle_get_document_colors(
+        this: Model<Self>,
+        envelope: TypedEnvelope<proto::GetDocumentColors>,
+        mut cx: AsyncAppContext,
+    ) -> Result<proto::GetDocumentColorsResponse> {
+        let buffer = Self::buffer_for_document_colors(&this, envelope.payload.buffer_id, &mut cx)?;
+        buffer
+            .update(&mut cx, |buffer, _| {
+                buffer.wait_for_version(deserialize_version(&envelope.payload.version))
+            })?
+            .await?;
+        let colors = this
+            .update(&mut cx, |this, cx| this.document_colors(&buffer, cx))?
+            .await?;
+        Ok(proto::GetDocumentColorsResponse {
+            colors: colors.colors.iter().map(serialize_document_color).collect(),
+            version: serialize_version(&colors.version),
+        })
+    }
+
+    pub(crate) async fn handle_get_color_presentations(
+        this: Model<Self>,
+        envelope: TypedEnvelope<proto::GetColorPresentations>,
+        mut cx: AsyncAppContext,
+    ) -> Result<proto::GetColorPresentationsResponse> {
+        let buffer = Self::buffer_for_document_colors(&this, envelope.payload.buffer_id, &mut cx)?;
+        let color = envelope
+            .payload
+            .color
+            .ok_or_else(|| anyhow!("missing document color"))?;
+        let color = deserialize_document_color(color)?;
+        let presentations = this
+            .update(&mut cx, |this, cx| {
+                this.color_presentations(&buffer, &color, cx)
+            })?
+            .await?;
+        Ok(proto::GetColorPresentationsResponse {
+            presentations: presentations
+                .iter()
+                .map(serialize_color_presentation)
+                .collect(),
+        })
+    }
+
+    fn buffer_for_document_colors(
+        this: &Model<Self>,
+        buffer_id: u64,
+        cx: &mut AsyncAppContext,
+    ) -> Result<Model<Buffer>> {
+        let buffer_id = BufferId::new(buffer_id)?;
+        this.update(cx, |this, _| {
+            this.opened_buffers
+                .get(&buffer_id)
+                .and_then(|buffer| buffer.upgrade())
+                .ok_or_else(|| anyhow!("unknown buffer id {}", buffer_id))
+        })?
+    }
+}
+
+fn range_from_lsp(range: lsp::Range, snapshot: &BufferSnapshot) -> Range<Anchor> {
+    let start = snapshot.clip_point_utf16(point_from_lsp(range.start), Bias::Left);
+    let end = snapshot.clip_point_utf16(point_from_lsp(range.end), Bias::Left);
+    snapshot.anchor_after(start)..snapshot.anchor_before(end)
+}
+
+fn document_color_from_lsp(
+    lsp_color: lsp::ColorInformation,
+    server_id: LanguageServerId,
+    snapshot: &BufferSnapshot,
+) -> DocumentColor {
+    DocumentColor {
+        range: range_from_lsp(lsp_color.range, snapshot),
+        color: lsp_color.color,
+        server_id,
+    }
+}
+
+fn color_presentation_from_lsp(
+    presentation: lsp::ColorPresentation,
+    snapshot: &BufferSnapshot,
+) -> ColorPresentation {
+    let edit_from_lsp = |edit: lsp::TextEdit| (range_from_lsp(edit.range, snapshot), edit.new_text);
+    ColorPresentation {
+        label: presentation.label,
+        text_edit: presentation.text_edit.map(edit_from_lsp),
+        additional_text_edits: presentation
+            .additional_text_edits
+            .unwrap_or_default()
+            .into_iter()
+            .map(edit_from_lsp)
+            .collect(),
+    }
+}
+
+fn serialize_document_color(color: &DocumentColor) -> proto::DocumentColor {
+    proto::DocumentColor {
+        start: Some(serialize_anchor(&color.range.start)),
+        end: Some(serialize_anchor(&color.range.end)),
+        red: color.color.red,
+        green: color.color.green,
+        blue: color.color.blue,
+        alpha: color.color.alpha,
+        language_server_id: color.server_id.0 as u64,
+    }
+}
+
+fn deserialize_document_color(color: proto::DocumentColor) -> Result<DocumentColor> {
+    let start = color
+        .start
+        .and_then(deserialize_anchor)
+        .ok_or_else(|| anyhow!("invalid document color start"))?;
+    let end = color
+        .end
+        .and_then(deserialize_anchor)
+        .ok_or_else(|| anyhow!("invalid document color end"))?;
+    Ok(DocumentColor {
+        range: start..end,
+        color: lsp::Color {
+            red: color.red,
+            green: color.green,
+            blue: color.blue,
+            alpha: color.alpha,
+        },
+        server_id: LanguageServerId(color.language_server_id as usize),
+    })
+}
+
+fn serialize_color_edit(
+    (range, new_text): &(Range<Anchor>, String),
+) -> proto::ColorPresentationEdit {
+    proto::ColorPresentationEdit {
+        start: Some(serialize_anchor(&range.start)),
+        end: Some(serialize_anchor(&range.end)),
+        new_text: new_text.clone(),
+    }
+}
+
+fn deserialize_color_edit(edit: proto::ColorPresentationEdit) -> Result<(Range<Anchor>, String)> {
+    let start = edit
+        .start
+        .and_then(deserialize_anchor)
+        .ok_or_else(|| anyhow!("invalid color presentation edit start"))?;
+    let end = edit
+        .end
+        .and_then(deserialize_anchor)
+        .ok_or_else(|| anyhow!("invalid color presentation edit end"))?;
+    Ok((start..end, edit.new_text))
+}
+
+fn serialize_color_presentation(presentation: &ColorPresentation) -> proto::ColorPresentation {
+    proto::ColorPresentation {
+        label: presentation.label.clone(),
+        text_edit: presentation.text_edit.as_ref().map(serialize_color_edit),
+        additional_text_edits: presentation
+            .additional_text_edits
+            .iter()
+            .map(serialize_color_edit)
+            .collect(),
+    }
+}
+
+fn deserialize_color_presentation(
+    presentation: proto::ColorPresentation,
+) -> Result<ColorPresentation> {
+    Ok(ColorPresentation {
+        label: presentation.label,
+        text_edit: presentation
+            .text_edit
+            .map(deserialize_color_edit)
+            .transpose()?,
+        additional_text_edits: presentation
+            .additional_text_edits
+            .into_iter()
+            .map(deserialize_color_edit)
+            .collect::<Result<_>>()?,
+    })
+}
@@ -0,0 +1,319 @@
+//! Support for document links: language servers report the ranges of the document that link to URLs or other files
+//! with `textDocument/documentLink`, e.g. the links in Markdown or the included files in CMake.
+//!
+//! Servers may omit the targets of the links and compute them lazily on `documentLink/resolve`.
+
+use std::{ops::Range, sync::Arc};
+
+use anyhow::{anyhow, Context as _, Result};
+use client::TypedEnvelope;
+use collections::HashMap;
+use gpui::{AsyncAppContext, Model, ModelContext, Task};
+use language::{
+    point_from_lsp,
+    proto::{deserialize_anchor, deserialize_version, serialize_anchor, serialize_version},
+    Anchor, Bias, Buffer, BufferSnapshot,
+};
+use lsp::LanguageServerId;
+use rpc::proto;
+use text::BufferId;
+use util::ResultExt;
+
+use crate::{File, Project};
+
+/// A range of the buffer that links to a URL or a file.
+#[derive(Clone, Debug)]
+pub struct DocumentLink {
+    pub range: Range<Anchor>,
+    /// The URI the link points to, `None` if it has to be resolved first.
+    pub target: Option<String>,
+    pub tooltip: Option<String>,
+    pub server_id: LanguageServerId,
+    /// The link as reported by the language server, sent back to it to resolve the target.
+    lsp_link: lsp::DocumentLink,
+}
+
+/// Document links of a buffer, sorted by their position.
+#[derive(Clone, Debug, Default)]
+pub struct BufferDocumentLinks {
+    /// The version of the buffer the links were computed for.
+    pub version: clock::Global,
+    pub links: Arc<[DocumentLink]>,
+}
+
+impl BufferDocumentLinks {
+    /// Returns the link that contains the given position, if any.
+    pub fn link_at(&self, position: Anchor, snapshot: &BufferSnapshot) -> Option<&DocumentLink> {
+        self.links.iter().find(|link| {
+            link.range.start.cmp(&position, snapshot).is_le()
+                && link.range.end.cmp(&position, snapshot).is_ge()
+        })
+    }
+}
+
+#[derive(Default)]
+pub(crate) struct DocumentLinksCache {
+    buffers: HashMap<BufferId, BufferDocumentLinks>,
+}
+
+impl DocumentLinksCache {
+    pub(crate) fn language_server_removed(&mut self, server_id: LanguageServerId) {
+        self.buffers
+            .retain(|_, cached| cached.links.iter().all(|link| link.server_id != server_id));
+    }
+
+    pub(crate) fn buffer_removed(&mut self, buffer_id: BufferId) {
+        self.buffers.remove(&buffer_id);
+    }
+}
+
+impl Project {
+    /// Returns the document links of the buffer, from the first of its language servers that provides them.
+    ///
+    /// The links are cached until the buffer changes, so this can be called on every hover.
+    pub fn document_links(
+        &mut self,
+        buffer: &Model<Buffer>,
+        cx: &mut ModelContext<Self>,
+    ) -> Task<Result<BufferDocumentLinks>> {
+        let buffer_id = buffer.read(cx).remote_id();
+        let version = buffer.read(cx).version();
+        if let Some(cached) = self.document_links.buffers.get(&buffer_id) {
+            if cached.version == version {
+                return Task::ready(Ok(cached.clone()));
+            }
+        }
+
+        if let Some(project_id) = self.remote_id().filter(|_| !self.is_local()) {
+            let request = self.client.request(proto::GetDocumentLinks {
+                project_id,
+                buffer_id: buffer_id.to_proto(),
+                version: serialize_version(&version),
+            });
+            let buffer = buffer.clone();
+            return cx.spawn(move |this, mut cx| async move {
+                let response = request.await?;
+                let version = deserialize_version(&response.version);
+                buffer
+                    .update(&mut cx, |buffer, _| {
+                        buffer.wait_for_version(version.clone())
+                    })?
+                    .await?;
+                let links = BufferDocumentLinks {
+                    version,
+                    links: response
+                        .links
+                        .into_iter()
+                        .filter_map(|link| deserialize_document_link(link).log_err())
+                        .collect(),
+                };
+                this.update(&mut cx, |this, _| {
+                    this.document_links.buffers.insert(buffer_id, links.clone());
+                })?;
+                Ok(links)
+            });
+        } else if !self.is_local() {
+            return Task::ready(Ok(BufferDocumentLinks::default()));
+        }
+
+        let Some(server) = self
+            .language_servers_for_buffer(buffer.read(cx), cx)
+            .map(|(_, server)| server)
+            .find(|server| server.capabilities().document_link_provider.is_some())
+            .cloned()
+        else {
+            return Task::ready(Ok(BufferDocumentLinks::default()));
+        };
+        let Some(uri) = File::from_dyn(buffer.read(cx).file())
+            .and_then(File::as_local)
+            .and_then(|file| lsp::Url::from_file_path(file.abs_path(cx)).ok())
+        else {
+            return Task::ready(Ok(BufferDocumentLinks::default()));
+        };
+
+        let server_id = server.server_id();
+        let snapshot = buffer.read(cx).snapshot();
+        let request =
+            server.request::<lsp::request::DocumentLinkRequest>(lsp::DocumentLinkParams {
+                text_document: lsp::TextDocumentIdentifier::new(uri),
+                work_done_progress_params: Default::default(),
+                partial_result_params: Default::default(),
+            });
+        cx.spawn(move |this, mut cx| async move {
+            let lsp_links = request
+                .await
+                .context("document links LSP request")?
+                .unwrap_or_default();
+            let mut links = lsp_links
+                .into_iter()
+                .map(|lsp_link| document_link_from_lsp(lsp_link, server_id, &snapshot))
+                .collect::<Vec<_>>();
+            links.sort_by(|a, b| a.range.start.cmp(&b.range.start, &snapshot));
+            let links = BufferDocumentLinks {
+                version: snapshot.version().clone(),
+                links: links.into(),
+            };
+            this.update(&mut cx, |this, _| {
+                this.document_links.buffers.insert(buffer_id, links.clone());
+            })?;
+            Ok(links)
+        })
+    }
+
+    /// Resolves the target of the link with `documentLink/resolve`, if the server left it out.
+    pub fn resolve_document_link(
+        &self,
+        buffer: &Model<Buffer>,
+        link: DocumentLink,
+        cx: &mut ModelContext<Self>,
+    ) -> Task<Result<DocumentLink>> {
+        if link.target.is_some() {
+            return Task::ready(Ok(link));
+        }
+
+        if self.is_local() {
+            let Some((_, server)) =
+                self.language_server_for_buffer(buffer.read(cx), link.server_id, cx)
+            else {
+                return Task::ready(Ok(link));
+            };
+            let can_resolve = server
+                .capabilities()
+                .document_link_provider
+                .as_ref()
+                .and_then(|options| options.resolve_provider)
+                .unwrap_or(false);
+            if !can_resolve {
+                return Task::ready(Ok(link));
+            }
+
+            let request =
+                server.request::<lsp::request::DocumentLinkResolve>(link.lsp_link.clone());
+            cx.spawn(move |_, _| async move {
+                let resolved = request.await.context("document link resolve LSP request")?;
+                Ok(DocumentLink {
+                    target: resolved.target.as_ref().map(|target| target.to_string()),
+                    tooltip: resolved.tooltip.clone().or(link.tooltip),
+                    lsp_link: resolved,
+                    ..link
+                })
+            })
+        } else if let Some(project_id) = self.remote_id() {
+            let request = self.client.request(proto::ResolveDocumentLink {
+                project_id,
+                buffer_id: buffer.read(cx).remote_id().to_proto(),
+                link: Some(serialize_document_link(&link)),
+            });
+            cx.background_executor().spawn(async move {
+                let response = request.await?;
+                match response.link {
+                    Some(resolved) => deserialize_document_link(resolved),
+                    None => Ok(link),
+                }
+            })
+        } else {
+            Task::ready(Err(anyhow!("project does not have a remote id")))
+        }
+    }
+
+    pub(crate) async fn handle_get_document_links(
+        this: Model<Self>,
+        envelope: TypedEnvelope<proto::GetDocumentLinks>,
+        mut cx: AsyncAppContext,
+    ) -> Result<proto::GetDocumentLinksResponse> {
+        let buffer = Self::buffer_for_document_links(&this, envelope.payload.buffer_id, &mut cx)?;
+        buffer
+            .update(&mut cx, |buffer, _| {
+                buffer.wait_for_version(deserialize_version(&envelope.payload.version))
+            })?
+            .await?;
+        let links = this
+            .update(&mut cx, |this, cx| this.document_links(&buffer, cx))?
+            .await?;
+        Ok(proto::GetDocumentLinksResponse {
+            links: links.links.iter().map(serialize_document_link).collect(),
+            version: serialize_version(&links.version),
+        })
+    }
+
+    pub(crate) async fn handle_resolve_document_link(
+        this: Model<Self>,
+        envelope: TypedEnvelope<proto::ResolveDocumentLink>,
+        mut cx: AsyncAppContext,
+    ) -> Result<proto::ResolveDocumentLinkResponse> {
+        let buffer = Self::buffer_for_document_links(&this, envelope.payload.buffer_id, &mut cx)?;
+        let link = envelope
+            .payload
+            .link
+            .ok_or_else(|| anyhow!("missing document link"))?;
+        let link = deserialize_document_link(link)?;
+        let resolved = this
+            .update(&mut cx, |this, cx| {
+                this.resolve_document_link(&buffer, link, cx)
+            })?
+            .await?;
+        Ok(proto::ResolveDocumentLinkResponse {
+            link: Some(serialize_document_link(&resolved)),
+        })
+    }
+
+    fn buffer_for_document_links(
+        this: &Model<Self>,
+        buffer_id: u64,
+        cx: &mut AsyncAppContext,
+    ) -> Result<Model<Buffer>> {
+        let buffer_id = BufferId::new(buffer_id)?;
+        this.update(cx, |this, _| {
+            this.opened_buffers
+                .get(&buffer_id)
+                .and_then(|buffer| buffer.upgrade())
+                .ok_or_else(|| anyhow!("unknown buffer id {}", buffer_id))
+        })?
+    }
+}
+
+fn document_link_from_lsp(
+    lsp_link: lsp::DocumentLink,
+    server_id: LanguageServerId,
+    snapshot: &BufferSnapshot,
+) -> DocumentLink {
+    let start = snapshot.clip_point_utf16(point_from_lsp(lsp_link.range.start), Bias::Left);
+    let end = snapshot.clip_point_utf16(point_from_lsp(lsp_link.range.end), Bias::Left);
+    DocumentLink {
+        range: snapshot.anchor_after(start)..snapshot.anchor_before(end),
+        target: lsp_link.target.as_ref().map(|target| target.to_string()),
+        tooltip: lsp_link.tooltip.clone(),
+        server_id,
+        lsp_link,
+    }
+}
+
+fn serialize_document_link(link: &DocumentLink) -> proto::DocumentLink {
+    proto::DocumentLink {
+        start: Some(serialize_anchor(&link.range.start)),
+        end: Some(serialize_anchor(&link.range.end)),
+        target: link.target.clone(),
+        tooltip: link.tooltip.clone(),
+        language_server_id: link.server_id.0 as u64,
+        lsp_link: serde_json::to_vec(&link.lsp_link).unwrap_or_default(),
+    }
+}
+
+fn deserialize_document_link(link: proto::DocumentLink) -> Result<DocumentLink> {
+    let start = link
+        .start
+        .and_then(deserialize_anchor)
+        .ok_or_else(|| anyhow!("invalid document link start"))?;
+    let end = link
+        .end
+        .and_then(deserialize_anchor)
+        .ok_or_else(|| anyhow!("invalid document link end"))?;
+    Ok(DocumentLink {
+        range: start..end,
+        target: link.target,
+        tooltip: link.tooltip,
+        server_id: LanguageServerId(link.language_server_id as usize),
+        lsp_link: serde_json::from_slice(&link.lsp_link)
+            .context("deserializing the LSP document link")?,
+    })
+}
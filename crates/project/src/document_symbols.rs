@@ -0,0 +1,156 @@
+//! Document symbols: language servers report the hierarchy of the symbols of a document with
+//! `textDocument/documentSymbol`, which the outline panel shows instead of the tree-sitter outline for the
+//! languages configured to.
+
+use std::{mem, ops::Range, sync::Arc};
+
+use anyhow::{anyhow, Result};
+use collections::HashMap;
+use futures::{future::Shared, FutureExt as _};
+use gpui::{Model, ModelContext, Task};
+use language::{
+    proto::{deserialize_anchor, serialize_anchor},
+    Anchor, Buffer,
+};
+use rpc::proto;
+use text::BufferId;
+
+use crate::{lsp_command::GetDocumentSymbols, LanguageServerToQuery, Project};
+
+/// A symbol of a document, along with the symbols nested in it.
+#[derive(Clone, Debug, PartialEq)]
+pub struct DocumentSymbol {
+    pub name: String,
+    /// More information about the symbol, like the signature of a function.
+    pub detail: Option<String>,
+    pub kind: lsp::SymbolKind,
+    pub deprecated: bool,
+    /// The range of the whole symbol, including its body and its doc comments.
+    pub range: Range<Anchor>,
+    /// The range of the name of the symbol.
+    pub selection_range: Range<Anchor>,
+    pub children: Vec<DocumentSymbol>,
+}
+
+/// Document symbols of a buffer, sorted by their start.
+#[derive(Clone, Debug)]
+pub struct BufferDocumentSymbols {
+    /// The version of the buffer the symbols were computed for.
+    pub version: clock::Global,
+    pub symbols: Arc<[DocumentSymbol]>,
+}
+
+type PendingDocumentSymbols = Shared<Task<Result<BufferDocumentSymbols, Arc<anyhow::Error>>>>;
+
+#[derive(Default)]
+pub(crate) struct DocumentSymbolsCache {
+    buffers: HashMap<BufferId, BufferDocumentSymbols>,
+    /// The requests in flight, shared by the callers asking for the same version of the buffer, like the
+    /// excerpts of the buffer in the outline panel.
+    pending: HashMap<BufferId, (clock::Global, PendingDocumentSymbols)>,
+}
+
+impl DocumentSymbolsCache {
+    pub(crate) fn buffer_removed(&mut self, buffer_id: BufferId) {
+        self.buffers.remove(&buffer_id);
+        self.pending.remove(&buffer_id);
+    }
+}
+
+impl DocumentSymbol {
+    pub(crate) fn to_proto(&self) -> proto::DocumentSymbol {
+        proto::DocumentSymbol {
+            name: self.name.clone(),
+            detail: self.detail.clone(),
+            kind: unsafe { mem::transmute::<lsp::SymbolKind, i32>(self.kind) },
+            deprecated: self.deprecated,
+            start: Some(serialize_anchor(&self.range.start)),
+            end: Some(serialize_anchor(&self.range.end)),
+            selection_start: Some(serialize_anchor(&self.selection_range.start)),
+            selection_end: Some(serialize_anchor(&self.selection_range.end)),
+            children: self.children.iter().map(Self::to_proto).collect(),
+        }
+    }
+
+    pub(crate) fn from_proto(symbol: proto::DocumentSymbol) -> Result<Self> {
+        let anchor = |anchor: Option<proto::Anchor>| {
+            anchor
+                .and_then(deserialize_anchor)
+                .ok_or_else(|| anyhow!("invalid document symbol range"))
+        };
+        Ok(Self {
+            kind: unsafe { mem::transmute::<i32, lsp::SymbolKind>(symbol.kind) },
+            range: anchor(symbol.start)?..anchor(symbol.end)?,
+            selection_range: anchor(symbol.selection_start)?..anchor(symbol.selection_end)?,
+            children: symbol
+                .children
+                .into_iter()
+                .map(Self::from_proto)
+                .collect::<Result<_>>()?,
+            name: symbol.name,
+            detail: symbol.detail,
+            deprecated: symbol.deprecated,
+        })
+    }
+}
+
+impl Project {
+    /// Returns the document symbols of the buffer, from its primary language server.
+    ///
+    /// The symbols are cached until the buffer changes.
+    pub fn document_symbols(
+        &mut self,
+        buffer: &Model<Buffer>,
+        cx: &mut ModelContext<Self>,
+    ) -> Task<Result<BufferDocumentSymbols>> {
+        let buffer_id = buffer.read(cx).remote_id();
+        let version = buffer.read(cx).version();
+        if let Some(cached) = self.document_symbols.buffers.get(&buffer_id) {
+            if cached.version == version {
+                return Task::ready(Ok(cached.clone()));
+            }
+        }
+
+        let pending = match self.document_symbols.pending.get(&buffer_id) {
+            Some((pending_version, pending)) if *pending_version == version => pending.clone(),
+            _ => {
+                let request = self.request_lsp(
+                    buffer.clone(),
+                    LanguageServerToQuery::Primary,
+                    GetDocumentSymbols,
+                    cx,
+                );
+                let pending_version = version.clone();
+                let pending = cx
+                    .spawn(move |this, mut cx| async move {
+                        let symbols = request.await;
+                        this.update(&mut cx, |this, _| {
+                            let cache = &mut this.document_symbols;
+                            if cache
+                                .pending
+                                .get(&buffer_id)
+                                .map_or(false, |(version, _)| *version == pending_version)
+                            {
+                                cache.pending.remove(&buffer_id);
+                            }
+                            let symbols = BufferDocumentSymbols {
+                                version: pending_version,
+                                symbols: symbols?.into(),
+                            };
+                            cache.buffers.insert(buffer_id, symbols.clone());
+                            anyhow::Ok(symbols)
+                        })
+                        .and_then(|symbols| symbols)
+                        .map_err(Arc::new)
+                    })
+                    .shared();
+                self.document_symbols
+                    .pending
+                    .insert(buffer_id, (version, pending.clone()));
+                pending
+            }
+        };
+        cx.background_executor()
+            .spawn(async move { pending.await.map_err(|error| anyhow!("{error:#}")) })
+    }
+}
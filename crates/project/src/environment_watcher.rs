@@ -0,0 +1,292 @@
+//! Watching the files that tools like direnv and nix build the environment of a worktree from, so that the
+//! language servers started with a stale environment can be restarted with the new one when they change.
+
+use std::{path::Path, sync::Arc, time::Duration};
+
+use collections::HashMap;
+use futures::StreamExt;
+use gpui::{Model, ModelContext, PromptLevel, Task};
+use lsp::{LanguageServerId, MessageActionItem};
+use util::ResultExt;
+use worktree::{PathChange, UpdatedEntriesSet, Worktree, WorktreeId};
+
+use crate::{
+    load_shell_environment, Event, LanguageServerPromptRequest, LanguageServerState, Project,
+    ProjectLspAdapterDelegate,
+};
+
+/// The files at the root of a worktree whose changes may change the environment loaded in it.
+const ENVIRONMENT_FILES: &[&str] = &[
+    ".envrc",
+    ".env",
+    ".tool-versions",
+    ".mise.toml",
+    "mise.toml",
+    "flake.nix",
+    "flake.lock",
+    "shell.nix",
+    "default.nix",
+    "devenv.nix",
+];
+
+/// How long to wait for more changes to the environment files before loading the environment again.
+const ENVIRONMENT_CHANGE_DEBOUNCE: Duration = Duration::from_millis(500);
+
+const RESTART_ACTION: &str = "Restart";
+
+#[derive(Default)]
+pub(crate) struct EnvironmentWatcher {
+    /// The delegates of the running language servers, which hold the environment they were started with.
+    delegates: HashMap<LanguageServerId, (WorktreeId, Arc<ProjectLspAdapterDelegate>)>,
+    /// The pending reloads of the environment of the worktrees, replaced by further changes.
+    reloads: HashMap<WorktreeId, Task<()>>,
+}
+
+impl EnvironmentWatcher {
+    pub(crate) fn language_server_started(
+        &mut self,
+        server_id: LanguageServerId,
+        worktree_id: WorktreeId,
+        delegate: Arc<ProjectLspAdapterDelegate>,
+    ) {
+        self.delegates.insert(server_id, (worktree_id, delegate));
+    }
+
+    /// Replaces the environment that the language server was started with.
+    #[cfg(test)]
+    pub(crate) fn set_language_server_environment(
+        &self,
+        server_id: LanguageServerId,
+        environment: HashMap<String, String>,
+    ) {
+        if let Some((_, delegate)) = self.delegates.get(&server_id) {
+            *delegate.shell_env.lock() = Some(environment);
+        }
+    }
+
+    pub(crate) fn worktree_removed(&mut self, worktree_id: WorktreeId) {
+        self.delegates
+            .retain(|_, (server_worktree_id, _)| *server_worktree_id != worktree_id);
+        self.reloads.remove(&worktree_id);
+    }
+}
+
+impl Project {
+    /// Loads the environment of the worktree again when its environment files change, and offers to restart the
+    /// language servers whose environment is then stale.
+    pub(crate) fn update_worktree_environment(
+        &mut self,
+        worktree: &Model<Worktree>,
+        changes: &UpdatedEntriesSet,
+        cx: &mut ModelContext<Self>,
+    ) {
+        let changed = changes
+            .iter()
+            .any(|(path, _, change)| *change != PathChange::Loaded && is_environment_file(path));
+        if !changed {
+            return;
+        }
+        let worktree_id = worktree.read(cx).id();
+        let language_servers = &self.language_servers;
+        self.environment_watcher
+            .delegates
+            .retain(|server_id, _| language_servers.contains_key(server_id));
+        if !self
+            .environment_watcher
+            .delegates
+            .values()
+            .any(|(server_worktree_id, _)| *server_worktree_id == worktree_id)
+        {
+            return;
+        }
+
+        let abs_path = worktree.read(cx).abs_path();
+        let worktree = worktree.downgrade();
+        let reload = cx.spawn(move |this, mut cx| async move {
+            cx.background_executor()
+                .timer(ENVIRONMENT_CHANGE_DEBOUNCE)
+                .await;
+            let Some(environment) = load_shell_environment(&abs_path).await.log_err() else {
+                return;
+            };
+            let Some(worktree) = worktree.upgrade() else {
+                return;
+            };
+            let Some(prompt) = this
+                .update(&mut cx, |this, cx| {
+                    this.prompt_to_restart_for_environment(&worktree, &environment, cx)
+                })
+                .ok()
+                .flatten()
+            else {
+                return;
+            };
+            prompt.await;
+        });
+        self.environment_watcher.reloads.insert(worktree_id, reload);
+    }
+
+    /// Asks the user whether to restart the language servers of the worktree that were started with another
+    /// environment than the given one, and restarts them if so.
+    pub(crate) fn prompt_to_restart_for_environment(
+        &mut self,
+        worktree: &Model<Worktree>,
+        environment: &HashMap<String, String>,
+        cx: &mut ModelContext<Self>,
+    ) -> Option<Task<()>> {
+        let worktree_id = worktree.read(cx).id();
+        let mut names = Vec::new();
+        let mut stale_server_ids = Vec::new();
+        for (server_id, (server_worktree_id, delegate)) in &self.environment_watcher.delegates {
+            // Servers that never loaded the environment don't depend on it.
+            if *server_worktree_id != worktree_id
+                || delegate
+                    .shell_env
+                    .lock()
+                    .as_ref()
+                    .map_or(true, |server_environment| server_environment == environment)
+            {
+                continue;
+            }
+            let Some(LanguageServerState::Running { adapter, .. }) =
+                self.language_servers.get(server_id)
+            else {
+                continue;
+            };
+            names.push(adapter.name.0.to_string());
+            stale_server_ids.push(*server_id);
+        }
+        if names.is_empty() {
+            return None;
+        }
+        names.sort();
+        names.dedup();
+
+        let (tx, mut rx) = smol::channel::bounded(1);
+        let request = LanguageServerPromptRequest {
+            level: PromptLevel::Info,
+            message: format!(
+                "The environment of {} changed. Restart {} to use the new one?",
+                worktree.read(cx).root_name(),
+                names.join(", ")
+            ),
+            actions: vec![
+                MessageActionItem {
+                    title: RESTART_ACTION.to_string(),
+                    properties: Default::default(),
+                },
+                MessageActionItem {
+                    title: "Dismiss".to_string(),
+                    properties: Default::default(),
+                },
+            ],
+            lsp_name: names.join(", "),
+            response_channel: tx,
+        };
+        cx.emit(Event::LanguageServerPrompt(request));
+
+        let worktree = worktree.downgrade();
+        Some(cx.spawn(move |this, mut cx| async move {
+            let Some(response) = rx.next().await else {
+                return;
+            };
+            if response.title != RESTART_ACTION {
+                return;
+            }
+            let Some(worktree) = worktree.upgrade() else {
+                return;
+            };
+            this.update(&mut cx, |this, cx| {
+                this.restart_language_servers_with_ids(worktree, stale_server_ids, cx)
+            })
+            .ok();
+        }))
+    }
+
+    /// Restarts the given running language servers of the worktree at their roots, leaving the other servers of
+    /// their languages running.
+    fn restart_language_servers_with_ids(
+        &mut self,
+        worktree: Model<Worktree>,
+        server_ids: Vec<LanguageServerId>,
+        cx: &mut ModelContext<Self>,
+    ) {
+        let worktree_id = worktree.read(cx).id();
+        let servers_to_restart = self
+            .language_server_ids
+            .iter()
+            .filter(|((server_worktree_id, _, _), server_id)| {
+                *server_worktree_id == worktree_id && server_ids.contains(server_id)
+            })
+            .filter_map(|((_, server_name, root), server_id)| {
+                let LanguageServerState::Running {
+                    language, adapter, ..
+                } = self.language_servers.get(server_id)?
+                else {
+                    return None;
+                };
+                Some((
+                    server_name.clone(),
+                    root.clone(),
+                    adapter.clone(),
+                    language.clone(),
+                ))
+            })
+            .collect::<Vec<_>>();
+        let stop_tasks = servers_to_restart
+            .into_iter()
+            .map(|(server_name, root, adapter, language)| {
+                let stop_task =
+                    self.stop_language_server(worktree_id, server_name.clone(), root.clone(), cx);
+                (stop_task, server_name, root, adapter, language)
+            })
+            .collect::<Vec<_>>();
+
+        cx.spawn(move |this, mut cx| async move {
+            let mut affected_worktrees = Vec::new();
+            let mut stopped_servers = Vec::new();
+            for (stop_task, server_name, root, adapter, language) in stop_tasks {
+                for affected_worktree_id in stop_task.await {
+                    if affected_worktree_id != worktree_id {
+                        affected_worktrees.push((
+                            affected_worktree_id,
+                            server_name.clone(),
+                            root.clone(),
+                        ));
+                    }
+                }
+                stopped_servers.push((root, adapter, language));
+            }
+
+            this.update(&mut cx, |this, cx| {
+                for (root, adapter, language) in stopped_servers {
+                    this.start_language_server(&worktree, adapter, language, root, cx);
+                }
+
+                // The other worktrees that the stopped servers served are served by their new instances.
+                for (affected_worktree_id, server_name, root) in affected_worktrees {
+                    if let Some(new_server_id) = this
+                        .language_server_ids
+                        .get(&(worktree_id, server_name.clone(), root))
+                        .cloned()
+                    {
+                        this.language_server_ids.insert(
+                            (affected_worktree_id, server_name, Arc::from(Path::new(""))),
+                            new_server_id,
+                        );
+                    }
+                }
+            })
+            .ok();
+        })
+        .detach();
+    }
+}
+
+fn is_environment_file(path: &Path) -> bool {
+    path.parent() == Some(Path::new(""))
+        && path
+            .file_name()
+            .and_then(|name| name.to_str())
+            .map_or(false, |name| ENVIRONMENT_FILES.contains(&name))
+}
@@ -0,0 +1,595 @@
+//! LSP file operations: before files are created, renamed or deleted, the language servers interested in them are
+//! asked for the edits that go along with the operation (e.g. updated import paths or module declarations) with
+//! `workspace/willCreateFiles`, `workspace/willRenameFiles` and `workspace/willDeleteFiles`, and they are told
+//! about the completed operation with the corresponding `workspace/did*Files` notification.
+
+use std::{
+    path::{Path, PathBuf},
+    slice,
+    sync::Arc,
+    time::Duration,
+};
+
+use anyhow::{anyhow, Context as _, Result};
+use futures::{select, FutureExt as _};
+use gpui::{AppContext, AsyncAppContext, Model, ModelContext, PromptLevel, Task};
+use language::CachedLspAdapter;
+use lsp::{LanguageServer, MessageActionItem};
+use rpc::proto;
+use util::ResultExt;
+use worktree::{CreatedEntry, ProjectEntryId, Worktree};
+
+use crate::{Event, LanguageServerPromptRequest, LanguageServerState, Project, ProjectPath};
+
+/// How long the servers may take to compute the edits of a file operation, before it proceeds without them.
+const WILL_OPERATE_TIMEOUT: Duration = Duration::from_secs(5);
+
+const APPLY_ACTION: &str = "Apply";
+const SKIP_ACTION: &str = "Skip";
+
+#[derive(Clone, Debug)]
+pub(crate) struct FileRename {
+    old_abs_path: PathBuf,
+    new_abs_path: PathBuf,
+    is_dir: bool,
+}
+
+impl FileRename {
+    fn to_lsp(&self) -> Option<lsp::FileRename> {
+        Some(lsp::FileRename {
+            old_uri: lsp::Url::from_file_path(&self.old_abs_path)
+                .ok()?
+                .to_string(),
+            new_uri: lsp::Url::from_file_path(&self.new_abs_path)
+                .ok()?
+                .to_string(),
+        })
+    }
+
+    fn matches(&self, options: Option<&lsp::FileOperationRegistrationOptions>) -> bool {
+        file_operation_matches(options, &self.old_abs_path, self.is_dir)
+            || file_operation_matches(options, &self.new_abs_path, self.is_dir)
+    }
+}
+
+/// A file or a directory that is created or deleted.
+#[derive(Clone, Debug)]
+pub(crate) struct FileOperationPath {
+    abs_path: PathBuf,
+    is_dir: bool,
+}
+
+impl FileOperationPath {
+    fn uri(&self) -> Option<String> {
+        Some(lsp::Url::from_file_path(&self.abs_path).ok()?.to_string())
+    }
+
+    fn matches(&self, options: Option<&lsp::FileOperationRegistrationOptions>) -> bool {
+        file_operation_matches(options, &self.abs_path, self.is_dir)
+    }
+}
+
+fn file_operation_path(
+    worktree: &Model<Worktree>,
+    entry_id: ProjectEntryId,
+    cx: &AppContext,
+) -> Option<FileOperationPath> {
+    let worktree = worktree.read(cx);
+    let entry = worktree.entry_for_id(entry_id)?;
+    Some(FileOperationPath {
+        abs_path: worktree.absolutize(&entry.path).log_err()?,
+        is_dir: entry.is_dir(),
+    })
+}
+
+fn file_creates(files: &[&FileOperationPath]) -> Vec<lsp::FileCreate> {
+    files
+        .iter()
+        .filter_map(|file| Some(lsp::FileCreate { uri: file.uri()? }))
+        .collect()
+}
+
+fn file_deletes(files: &[&FileOperationPath]) -> Vec<lsp::FileDelete> {
+    files
+        .iter()
+        .filter_map(|file| Some(lsp::FileDelete { uri: file.uri()? }))
+        .collect()
+}
+
+/// Describes the operation on the files in the prompts, e.g. "the deletion of 3 files".
+fn describe_files(operation: &str, files: &[FileOperationPath]) -> String {
+    match files {
+        [file] => format!("the {operation} of {}", file.abs_path.display()),
+        files => format!("the {operation} of {} files", files.len()),
+    }
+}
+
+fn file_rename(
+    worktree: &Model<Worktree>,
+    entry_id: ProjectEntryId,
+    new_path: &Path,
+    cx: &AppContext,
+) -> Option<FileRename> {
+    let worktree = worktree.read(cx);
+    let entry = worktree.entry_for_id(entry_id)?;
+    Some(FileRename {
+        old_abs_path: worktree.absolutize(&entry.path).log_err()?,
+        new_abs_path: worktree.absolutize(new_path).log_err()?,
+        is_dir: entry.is_dir(),
+    })
+}
+
+/// Whether the path passes any of the filters a server registered for a file operation.
+fn file_operation_matches(
+    options: Option<&lsp::FileOperationRegistrationOptions>,
+    abs_path: &Path,
+    is_dir: bool,
+) -> bool {
+    let Some(options) = options else {
+        return false;
+    };
+    options.filters.iter().any(|filter| {
+        if filter
+            .scheme
+            .as_deref()
+            .map_or(false, |scheme| scheme != "file")
+        {
+            return false;
+        }
+        match filter.pattern.matches {
+            Some(lsp::FileOperationPatternKind::File) if is_dir => return false,
+            Some(lsp::FileOperationPatternKind::Folder) if !is_dir => return false,
+            _ => {}
+        }
+        let ignore_case = filter
+            .pattern
+            .options
+            .as_ref()
+            .and_then(|options| options.ignore_case)
+            .unwrap_or(false);
+        globset::GlobBuilder::new(&filter.pattern.glob)
+            .case_insensitive(ignore_case)
+            .literal_separator(true)
+            .build()
+            .log_err()
+            .map_or(false, |glob| glob.compile_matcher().is_match(abs_path))
+    })
+}
+
+fn file_operations(
+    server: &LanguageServer,
+) -> Option<&lsp::WorkspaceFileOperationsServerCapabilities> {
+    server
+        .capabilities()
+        .workspace
+        .as_ref()?
+        .file_operations
+        .as_ref()
+}
+
+fn is_empty_workspace_edit(edit: &lsp::WorkspaceEdit) -> bool {
+    edit.changes
+        .as_ref()
+        .map_or(true, |changes| changes.is_empty())
+        && edit
+            .document_changes
+            .as_ref()
+            .map_or(true, |changes| match changes {
+                lsp::DocumentChanges::Edits(edits) => edits.is_empty(),
+                lsp::DocumentChanges::Operations(operations) => operations.is_empty(),
+            })
+}
+
+fn edited_files_count(edit: &lsp::WorkspaceEdit) -> usize {
+    match &edit.document_changes {
+        Some(lsp::DocumentChanges::Edits(edits)) => edits.len(),
+        Some(lsp::DocumentChanges::Operations(operations)) => operations.len(),
+        None => edit.changes.as_ref().map_or(0, |changes| changes.len()),
+    }
+}
+
+impl Project {
+    /// Renames a local entry, applying the edits the language servers want to make for it once the user confirms them.
+    pub(crate) fn rename_local_entry(
+        &mut self,
+        entry_id: ProjectEntryId,
+        new_path: Arc<Path>,
+        cx: &mut ModelContext<Self>,
+    ) -> Task<Result<CreatedEntry>> {
+        let Some(worktree) = self.worktree_for_entry(entry_id, cx) else {
+            return Task::ready(Err(anyhow!("No worktree for entry {entry_id:?}")));
+        };
+        let rename = file_rename(&worktree, entry_id, &new_path, cx);
+        let will_rename = rename
+            .as_ref()
+            .map(|rename| self.will_rename_files(rename, cx));
+        cx.spawn(move |this, mut cx| async move {
+            if let Some(will_rename) = will_rename {
+                will_rename.await;
+            }
+            let entry = worktree
+                .update(&mut cx, |worktree, cx| {
+                    worktree.rename_entry(entry_id, new_path, cx)
+                })?
+                .await?;
+            if let Some(rename) = rename {
+                this.update(&mut cx, |this, _| this.did_rename_files(&rename))?;
+            }
+            Ok(entry)
+        })
+    }
+
+    /// Creates a local entry, applying the edits the language servers want to make for it once the user confirms
+    /// them.
+    pub(crate) fn create_local_entry(
+        &mut self,
+        project_path: ProjectPath,
+        is_directory: bool,
+        cx: &mut ModelContext<Self>,
+    ) -> Task<Result<CreatedEntry>> {
+        let Some(worktree) = self.worktree_for_id(project_path.worktree_id, cx) else {
+            return Task::ready(Err(anyhow!("No worktree for path {project_path:?}")));
+        };
+        let file = worktree
+            .read(cx)
+            .absolutize(&project_path.path)
+            .log_err()
+            .map(|abs_path| FileOperationPath {
+                abs_path,
+                is_dir: is_directory,
+            });
+        let will_create = file
+            .as_ref()
+            .map(|file| self.will_create_files(slice::from_ref(file), cx));
+        cx.spawn(move |this, mut cx| async move {
+            if let Some(will_create) = will_create {
+                will_create.await;
+            }
+            let entry = worktree
+                .update(&mut cx, |worktree, cx| {
+                    worktree.create_entry(project_path.path, is_directory, cx)
+                })?
+                .await?;
+            if let Some(file) = file {
+                this.update(&mut cx, |this, _| {
+                    this.did_create_files(slice::from_ref(&file))
+                })?;
+            }
+            Ok(entry)
+        })
+    }
+
+    /// Deletes local entries, applying the edits the language servers want to make for all of them once the user
+    /// confirms them.
+    ///
+    /// The entries are deleted one after the other, and the deletion stops at the first one that fails. The servers
+    /// are told about the entries that were deleted in any case.
+    pub(crate) fn delete_local_entries(
+        &mut self,
+        entry_ids: Vec<ProjectEntryId>,
+        trash: bool,
+        cx: &mut ModelContext<Self>,
+    ) -> Task<Result<()>> {
+        let entries = entry_ids
+            .into_iter()
+            .map(|entry_id| {
+                let file = self
+                    .worktree_for_entry(entry_id, cx)
+                    .and_then(|worktree| file_operation_path(&worktree, entry_id, cx));
+                (entry_id, file)
+            })
+            .collect::<Vec<_>>();
+        let files = entries
+            .iter()
+            .filter_map(|(_, file)| file.clone())
+            .collect::<Vec<_>>();
+        let will_delete = self.will_delete_files(&files, cx);
+        cx.spawn(move |this, mut cx| async move {
+            will_delete.await;
+            let mut deleted = Vec::new();
+            let result = async {
+                for (entry_id, file) in entries {
+                    this.update(&mut cx, |this, cx| {
+                        let worktree = this
+                            .worktree_for_entry(entry_id, cx)
+                            .ok_or_else(|| anyhow!("No worktree for entry {entry_id:?}"))?;
+                        cx.emit(Event::DeletedEntry(entry_id));
+                        worktree
+                            .update(cx, |worktree, cx| {
+                                worktree.delete_entry(entry_id, trash, cx)
+                            })
+                            .ok_or_else(|| anyhow!("No entry {entry_id:?}"))
+                    })??
+                    .await?;
+                    deleted.extend(file);
+                }
+                anyhow::Ok(())
+            }
+            .await;
+            this.update(&mut cx, |this, _| this.did_delete_files(&deleted))?;
+            result
+        })
+    }
+
+    /// Tells the language servers about the creation of an entry by a guest.
+    ///
+    /// The servers aren't asked for the edits of the creation, as they would have to be confirmed by the host.
+    pub(crate) async fn create_entry_for_guest(
+        this: Model<Self>,
+        worktree: Model<Worktree>,
+        envelope: proto::CreateProjectEntry,
+        mut cx: AsyncAppContext,
+    ) -> Result<proto::ProjectEntryResponse> {
+        let file = worktree.read_with(&cx, |worktree, _| {
+            worktree
+                .absolutize(Path::new(&envelope.path))
+                .log_err()
+                .map(|abs_path| FileOperationPath {
+                    abs_path,
+                    is_dir: envelope.is_directory,
+                })
+        })?;
+        let response = Worktree::handle_create_entry(worktree, envelope, cx.clone()).await?;
+        if let Some(file) = file {
+            this.update(&mut cx, |this, _| {
+                this.did_create_files(slice::from_ref(&file))
+            })?;
+        }
+        Ok(response)
+    }
+
+    /// Tells the language servers about the deletion of an entry by a guest.
+    pub(crate) async fn delete_entry_for_guest(
+        this: Model<Self>,
+        worktree: Model<Worktree>,
+        envelope: proto::DeleteProjectEntry,
+        mut cx: AsyncAppContext,
+    ) -> Result<proto::ProjectEntryResponse> {
+        let file = cx.update(|cx| {
+            file_operation_path(&worktree, ProjectEntryId::from_proto(envelope.entry_id), cx)
+        })?;
+        let response = Worktree::handle_delete_entry(worktree, envelope, cx.clone()).await?;
+        if let Some(file) = file {
+            this.update(&mut cx, |this, _| {
+                this.did_delete_files(slice::from_ref(&file))
+            })?;
+        }
+        Ok(response)
+    }
+
+    /// Tells the language servers about the rename of an entry by a guest.
+    ///
+    /// The servers aren't asked for the edits of the rename, as they would have to be confirmed by the host.
+    pub(crate) async fn rename_entry_for_guest(
+        this: Model<Self>,
+        worktree: Model<Worktree>,
+        envelope: proto::RenameProjectEntry,
+        mut cx: AsyncAppContext,
+    ) -> Result<proto::ProjectEntryResponse> {
+        let rename = cx.update(|cx| {
+            file_rename(
+                &worktree,
+                ProjectEntryId::from_proto(envelope.entry_id),
+                Path::new(&envelope.new_path),
+                cx,
+            )
+        })?;
+        let response = Worktree::handle_rename_entry(worktree, envelope, cx.clone()).await?;
+        if let Some(rename) = rename {
+            this.update(&mut cx, |this, _| this.did_rename_files(&rename))?;
+        }
+        Ok(response)
+    }
+
+    fn running_language_servers(&self) -> Vec<(Arc<CachedLspAdapter>, Arc<LanguageServer>)> {
+        self.language_servers
+            .values()
+            .filter_map(|state| match state {
+                LanguageServerState::Running {
+                    adapter, server, ..
+                } => Some((adapter.clone(), server.clone())),
+                LanguageServerState::Starting(_) => None,
+            })
+            .collect()
+    }
+
+    fn will_create_files(
+        &self,
+        files: &[FileOperationPath],
+        cx: &mut ModelContext<Self>,
+    ) -> Task<()> {
+        let requests = self
+            .servers_for_files(files, |ops| ops.will_create.as_ref())
+            .into_iter()
+            .map(|(adapter, server, files)| {
+                let params = lsp::CreateFilesParams {
+                    files: file_creates(&files),
+                };
+                (adapter, server, params)
+            })
+            .collect();
+        self.will_operate_on_files::<lsp::request::WillCreateFiles>(
+            requests,
+            describe_files("creation", files),
+            cx,
+        )
+    }
+
+    fn will_rename_files(&self, rename: &FileRename, cx: &mut ModelContext<Self>) -> Task<()> {
+        let Some(lsp_rename) = rename.to_lsp() else {
+            return Task::ready(());
+        };
+        let requests = self
+            .running_language_servers()
+            .into_iter()
+            .filter(|(_, server)| {
+                rename.matches(file_operations(server).and_then(|ops| ops.will_rename.as_ref()))
+            })
+            .map(|(adapter, server)| {
+                let params = lsp::RenameFilesParams {
+                    files: vec![lsp_rename.clone()],
+                };
+                (adapter, server, params)
+            })
+            .collect();
+        self.will_operate_on_files::<lsp::request::WillRenameFiles>(
+            requests,
+            format!("the rename of {}", rename.old_abs_path.display()),
+            cx,
+        )
+    }
+
+    fn will_delete_files(
+        &self,
+        files: &[FileOperationPath],
+        cx: &mut ModelContext<Self>,
+    ) -> Task<()> {
+        let requests = self
+            .servers_for_files(files, |ops| ops.will_delete.as_ref())
+            .into_iter()
+            .map(|(adapter, server, files)| {
+                let params = lsp::DeleteFilesParams {
+                    files: file_deletes(&files),
+                };
+                (adapter, server, params)
+            })
+            .collect();
+        self.will_operate_on_files::<lsp::request::WillDeleteFiles>(
+            requests,
+            describe_files("deletion", files),
+            cx,
+        )
+    }
+
+    /// Returns the running servers that registered for the file operation, with the files that pass their filters.
+    fn servers_for_files<'a>(
+        &self,
+        files: &'a [FileOperationPath],
+        registration: fn(
+            &lsp::WorkspaceFileOperationsServerCapabilities,
+        ) -> Option<&lsp::FileOperationRegistrationOptions>,
+    ) -> Vec<(
+        Arc<CachedLspAdapter>,
+        Arc<LanguageServer>,
+        Vec<&'a FileOperationPath>,
+    )> {
+        self.running_language_servers()
+            .into_iter()
+            .filter_map(|(adapter, server)| {
+                let options = file_operations(&server).and_then(registration);
+                let files = files
+                    .iter()
+                    .filter(|file| file.matches(options))
+                    .collect::<Vec<_>>();
+                (!files.is_empty()).then(|| (adapter, server, files))
+            })
+            .collect()
+    }
+
+    /// Asks the servers for the edits of the file operation, and applies the ones the user confirms.
+    fn will_operate_on_files<R>(
+        &self,
+        requests: Vec<(Arc<CachedLspAdapter>, Arc<LanguageServer>, R::Params)>,
+        description: String,
+        cx: &mut ModelContext<Self>,
+    ) -> Task<()>
+    where
+        R: lsp::request::Request<Result = Option<lsp::WorkspaceEdit>>,
+    {
+        if requests.is_empty() {
+            return Task::ready(());
+        }
+
+        cx.spawn(move |this, mut cx| async move {
+            for (adapter, server, params) in requests {
+                let request = server.request::<R>(params);
+                let mut timeout = cx.background_executor().timer(WILL_OPERATE_TIMEOUT).fuse();
+                let edit = select! {
+                    edit = request.fuse() => edit.with_context(|| format!("{} LSP request", R::METHOD)),
+                    _ = timeout => Err(anyhow!("{} did not respond to {} in time", server.name(), R::METHOD)),
+                };
+                let Some(edit) = edit.log_err().flatten() else {
+                    continue;
+                };
+                if is_empty_workspace_edit(&edit) {
+                    continue;
+                }
+
+                let (tx, rx) = smol::channel::bounded(1);
+                let prompt = LanguageServerPromptRequest {
+                    level: PromptLevel::Info,
+                    message: format!(
+                        "{} wants to update {} file(s) for {description}.",
+                        server.name(),
+                        edited_files_count(&edit),
+                    ),
+                    actions: [APPLY_ACTION, SKIP_ACTION]
+                        .into_iter()
+                        .map(|title| MessageActionItem {
+                            title: title.to_string(),
+                            properties: Default::default(),
+                        })
+                        .collect(),
+                    lsp_name: server.name().to_string(),
+                    response_channel: tx,
+                };
+                if this
+                    .update(&mut cx, |_, cx| cx.emit(Event::LanguageServerPrompt(prompt)))
+                    .is_err()
+                {
+                    return;
+                }
+                let confirmed = rx
+                    .recv()
+                    .await
+                    .map_or(false, |action| action.title == APPLY_ACTION);
+                if !confirmed {
+                    continue;
+                }
+
+                let Some(this) = this.upgrade() else {
+                    return;
+                };
+                Self::deserialize_workspace_edit(this, edit, true, adapter, server, &mut cx)
+                    .await
+                    .with_context(|| format!("applying the edits of {}", R::METHOD))
+                    .log_err();
+            }
+        })
+    }
+
+    fn did_create_files(&self, files: &[FileOperationPath]) {
+        for (_, server, files) in self.servers_for_files(files, |ops| ops.did_create.as_ref()) {
+            server
+                .notify::<lsp::notification::DidCreateFiles>(lsp::CreateFilesParams {
+                    files: file_creates(&files),
+                })
+                .log_err();
+        }
+    }
+
+    fn did_delete_files(&self, files: &[FileOperationPath]) {
+        for (_, server, files) in self.servers_for_files(files, |ops| ops.did_delete.as_ref()) {
+            server
+                .notify::<lsp::notification::DidDeleteFiles>(lsp::DeleteFilesParams {
+                    files: file_deletes(&files),
+                })
+                .log_err();
+        }
+    }
+
+    fn did_rename_files(&self, rename: &FileRename) {
+        let Some(lsp_rename) = rename.to_lsp() else {
+            return;
+        };
+        for (_, server) in self.running_language_servers() {
+            if rename.matches(file_operations(&server).and_then(|ops| ops.did_rename.as_ref())) {
+                server
+                    .notify::<lsp::notification::DidRenameFiles>(lsp::RenameFilesParams {
+                        files: vec![lsp_rename.clone()],
+                    })
+                    .log_err();
+            }
+        }
+    }
+}
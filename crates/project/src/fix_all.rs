@@ -0,0 +1,146 @@
+//! Fixing all the diagnostics with the same code across the project at once, by applying the quick fix that the
+//! language server offers for each of them.
+
+use anyhow::Result;
+use collections::{HashMap, HashSet};
+use futures::future::join_all;
+use gpui::{Model, ModelContext, Task};
+use language::{Buffer, Diagnostic, Transaction};
+use util::ResultExt;
+
+use crate::{CodeAction, Project, ProjectTransaction};
+
+/// The outcome of fixing all the diagnostics with a code.
+#[derive(Debug, Default)]
+pub struct FixAllReport {
+    /// The edits of the fixes, as a single transaction of each buffer.
+    pub transaction: ProjectTransaction,
+    /// The diagnostics that were fixed.
+    pub fixed: usize,
+    /// The diagnostics that no quick fix was offered for.
+    pub unfixable: usize,
+    /// The diagnostics whose quick fix failed to apply.
+    pub failed: usize,
+}
+
+impl Project {
+    /// Applies a quick fix to each primary diagnostic of the project with the same source and code as the given
+    /// one, merging the edits of each buffer into a single transaction, so that they're undone at once.
+    pub fn fix_all_diagnostics(
+        &mut self,
+        diagnostic: &Diagnostic,
+        cx: &mut ModelContext<Self>,
+    ) -> Task<Result<FixAllReport>> {
+        let Some(code) = diagnostic.code.clone() else {
+            return Task::ready(Ok(FixAllReport::default()));
+        };
+        let source = diagnostic.source.clone();
+        let paths = self
+            .diagnostic_summaries(false, cx)
+            .map(|(path, _, _)| path)
+            .collect::<HashSet<_>>();
+        let buffers = paths
+            .into_iter()
+            .map(|path| self.open_buffer(path, cx))
+            .collect::<Vec<_>>();
+
+        cx.spawn(move |this, mut cx| async move {
+            let buffers = join_all(buffers)
+                .await
+                .into_iter()
+                .filter_map(|buffer| buffer.log_err())
+                .collect::<Vec<_>>();
+
+            let mut report = FixAllReport::default();
+            let mut transactions = HashMap::<Model<Buffer>, Transaction>::default();
+            for buffer in buffers {
+                let ranges = buffer.update(&mut cx, |buffer, _| {
+                    let snapshot = buffer.snapshot();
+                    snapshot
+                        .diagnostics_in_range::<_, usize>(0..snapshot.len(), false)
+                        .filter(|entry| {
+                            entry.diagnostic.is_primary
+                                && entry.diagnostic.code.as_ref() == Some(&code)
+                                && entry.diagnostic.source == source
+                        })
+                        .map(|entry| {
+                            snapshot.anchor_before(entry.range.start)
+                                ..snapshot.anchor_after(entry.range.end)
+                        })
+                        .collect::<Vec<_>>()
+                })?;
+
+                // The fixes are requested one after the other, so that each one is computed for the buffer edited
+                // by the previous ones.
+                for range in ranges {
+                    let actions = this
+                        .update(&mut cx, |this, cx| {
+                            this.code_actions(&buffer, range.clone(), cx)
+                        })?
+                        .await;
+                    let Some(action) = quick_fix(actions, &code) else {
+                        report.unfixable += 1;
+                        continue;
+                    };
+                    let applied = this
+                        .update(&mut cx, |this, cx| {
+                            this.apply_code_action(buffer.clone(), action, true, cx)
+                        })?
+                        .await;
+                    let Some(transaction) = applied.log_err() else {
+                        report.failed += 1;
+                        continue;
+                    };
+                    report.fixed += 1;
+                    for (buffer, transaction) in transaction.0 {
+                        match transactions.get_mut(&buffer) {
+                            Some(merged) => {
+                                buffer.update(&mut cx, |buffer, _| {
+                                    buffer.merge_transactions(transaction.id, merged.id)
+                                })?;
+                                merged.edit_ids.extend(transaction.edit_ids);
+                            }
+                            None => {
+                                transactions.insert(buffer, transaction);
+                            }
+                        }
+                    }
+                }
+            }
+
+            report.transaction = ProjectTransaction(transactions);
+            Ok(report)
+        })
+    }
+}
+
+/// Picks the quick fix for a diagnostic with the code among the code actions of its range: the one that the
+/// language server prefers, or else the first one.
+fn quick_fix(actions: Vec<CodeAction>, code: &str) -> Option<CodeAction> {
+    let fixes_diagnostic = |action: &CodeAction| {
+        action.lsp_action.kind.as_ref().map_or(true, |kind| {
+            kind.as_str()
+                .starts_with(lsp::CodeActionKind::QUICKFIX.as_str())
+        }) && action
+            .lsp_action
+            .diagnostics
+            .iter()
+            .flatten()
+            .any(|diagnostic| match &diagnostic.code {
+                Some(lsp::NumberOrString::String(diagnostic_code)) => diagnostic_code == code,
+                Some(lsp::NumberOrString::Number(diagnostic_code)) => {
+                    diagnostic_code.to_string() == code
+                }
+                None => false,
+            })
+    };
+    let mut candidates = actions
+        .into_iter()
+        .filter(fixes_diagnostic)
+        .collect::<Vec<_>>();
+    let preferred = candidates
+        .iter()
+        .position(|action| action.lsp_action.is_preferred == Some(true))
+        .unwrap_or(0);
+    (!candidates.is_empty()).then(|| candidates.swap_remove(preferred))
+}
@@ -0,0 +1,128 @@
+//! Folding ranges: language servers report the ranges of the document that can be folded with
+//! `textDocument/foldingRange`, like the bodies of items, the imports and the comments, which the editor folds
+//! along with the ranges it finds from the indentation.
+
+use std::{ops::Range, sync::Arc};
+
+use anyhow::{anyhow, Result};
+use collections::HashMap;
+use gpui::{Model, ModelContext, Task};
+use language::{
+    proto::{deserialize_anchor, serialize_anchor},
+    Anchor, Buffer,
+};
+use rpc::proto;
+use text::BufferId;
+
+use crate::{lsp_command::GetFoldingRanges, LanguageServerToQuery, Project};
+
+/// A range of the buffer that the language server says can be folded.
+#[derive(Clone, Debug, PartialEq)]
+pub struct FoldingRange {
+    /// The folded range, from the end of its first line to the end of its last line.
+    pub range: Range<Anchor>,
+    pub kind: Option<FoldingRangeKind>,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum FoldingRangeKind {
+    Comment,
+    Imports,
+    Region,
+}
+
+/// Folding ranges of a buffer, sorted by their start.
+#[derive(Clone, Debug)]
+pub struct BufferFoldingRanges {
+    /// The version of the buffer the ranges were computed for.
+    pub version: clock::Global,
+    pub ranges: Arc<[FoldingRange]>,
+}
+
+#[derive(Default)]
+pub(crate) struct FoldingRangesCache {
+    buffers: HashMap<BufferId, BufferFoldingRanges>,
+}
+
+impl FoldingRangesCache {
+    pub(crate) fn buffer_removed(&mut self, buffer_id: BufferId) {
+        self.buffers.remove(&buffer_id);
+    }
+}
+
+impl FoldingRange {
+    pub(crate) fn to_proto(&self) -> proto::FoldingRange {
+        proto::FoldingRange {
+            start: Some(serialize_anchor(&self.range.start)),
+            end: Some(serialize_anchor(&self.range.end)),
+            kind: self.kind.map(|kind| {
+                match kind {
+                    FoldingRangeKind::Comment => proto::folding_range::Kind::Comment,
+                    FoldingRangeKind::Imports => proto::folding_range::Kind::Imports,
+                    FoldingRangeKind::Region => proto::folding_range::Kind::Region,
+                }
+                .into()
+            }),
+        }
+    }
+
+    pub(crate) fn from_proto(range: proto::FoldingRange) -> Result<Self> {
+        let kind = match range.kind.map(proto::folding_range::Kind::from_i32) {
+            Some(Some(proto::folding_range::Kind::Comment)) => Some(FoldingRangeKind::Comment),
+            Some(Some(proto::folding_range::Kind::Imports)) => Some(FoldingRangeKind::Imports),
+            Some(Some(proto::folding_range::Kind::Region)) => Some(FoldingRangeKind::Region),
+            Some(None) | None => None,
+        };
+        let start = range
+            .start
+            .and_then(deserialize_anchor)
+            .ok_or_else(|| anyhow!("invalid folding range start"))?;
+        let end = range
+            .end
+            .and_then(deserialize_anchor)
+            .ok_or_else(|| anyhow!("invalid folding range end"))?;
+        Ok(Self {
+            range: start..end,
+            kind,
+        })
+    }
+}
+
+impl Project {
+    /// Returns the folding ranges of the buffer, from its primary language server.
+    ///
+    /// The ranges are cached until the buffer changes.
+    pub fn folding_ranges(
+        &mut self,
+        buffer: &Model<Buffer>,
+        cx: &mut ModelContext<Self>,
+    ) -> Task<Result<BufferFoldingRanges>> {
+        let buffer_id = buffer.read(cx).remote_id();
+        let version = buffer.read(cx).version();
+        if let Some(cached) = self.folding_ranges.buffers.get(&buffer_id) {
+            if cached.version == version {
+                return Task::ready(Ok(cached.clone()));
+            }
+        }
+
+        let request = self.request_lsp(
+            buffer.clone(),
+            LanguageServerToQuery::Primary,
+            GetFoldingRanges,
+            cx,
+        );
+        cx.spawn(move |this, mut cx| async move {
+            let ranges = request.await?;
+            let ranges = BufferFoldingRanges {
+                version,
+                ranges: ranges.into(),
+            };
+            this.update(&mut cx, |this, _| {
+                this.folding_ranges
+                    .buffers
+                    .insert(buffer_id, ranges.clone());
+            })?;
+            Ok(ranges)
+        })
+    }
+}
@@ -0,0 +1,188 @@
+//! Formatter chains: the formatters of a `{"chain": [...]}` formatter setting run one after the other, each one
+//! formatting the output of the previous one.
+//!
+//! Every stage is applied to the buffer before the next one runs, so that language servers and external commands
+//! see the text formatted so far. A stage that fails is logged and skipped, and the lines changed by each stage
+//! are written to the log.
+
+use std::ops::Range;
+
+use anyhow::Result;
+use gpui::{AsyncAppContext, WeakModel};
+use itertools::Itertools;
+use language::{language_settings::Formatter, Point, TransactionId};
+
+use crate::{deserialize_code_actions, FormatOperation, FormatTarget, Project, ProjectTransaction};
+
+impl Project {
+    /// Runs the stages of a formatter chain on the buffer, grouping their edits with the whitespace formatting of
+    /// the buffer in its undo history.
+    pub(crate) async fn format_via_chain(
+        project: &WeakModel<Self>,
+        target: &FormatTarget<'_>,
+        formatters: &[Formatter],
+        whitespace_transaction_id: Option<TransactionId>,
+        push_to_history: bool,
+        project_transaction: &mut ProjectTransaction,
+        cx: &mut AsyncAppContext,
+    ) -> Result<()> {
+        let buffer = target.buffer;
+        let path = buffer.update(cx, |buffer, cx| {
+            buffer
+                .file()
+                .map(|file| file.full_path(cx).display().to_string())
+                .unwrap_or_else(|| "untitled buffer".to_string())
+        })?;
+        let mut first_transaction_id = whitespace_transaction_id;
+
+        for (ix, formatter) in chain_stages(formatters).into_iter().enumerate() {
+            let stage = format!(
+                "formatter chain stage {} ({})",
+                ix + 1,
+                stage_label(formatter)
+            );
+            let last_transaction_id = buffer.update(cx, |buffer, _| {
+                buffer.peek_undo_stack().map(|entry| entry.transaction_id())
+            })?;
+
+            let transaction_id = if let Formatter::CodeActions(code_actions) = formatter {
+                let code_actions = deserialize_code_actions(code_actions);
+                if code_actions.is_empty() {
+                    continue;
+                }
+                if let Err(error) = Self::execute_code_actions_on_servers(
+                    project,
+                    target.adapters_and_servers,
+                    code_actions,
+                    buffer,
+                    push_to_history,
+                    project_transaction,
+                    cx,
+                )
+                .await
+                {
+                    log::warn!("Formatting {path}: {stage} failed: {error:#}");
+                    continue;
+                }
+                buffer.update(cx, |buffer, _| {
+                    buffer
+                        .peek_undo_stack()
+                        .map(|entry| entry.transaction_id())
+                        .filter(|id| Some(*id) != last_transaction_id)
+                })?
+            } else {
+                let operation = match Self::format_with(project, target, formatter, cx).await {
+                    Ok(Some(operation)) => operation,
+                    Ok(None) => {
+                        log::info!("Formatting {path}: {stage} had nothing to format");
+                        continue;
+                    }
+                    Err(error) => {
+                        log::warn!("Formatting {path}: {stage} failed: {error:#}");
+                        continue;
+                    }
+                };
+                let edited = buffer.update(cx, |buffer, _| {
+                    buffer.peek_undo_stack().map(|entry| entry.transaction_id())
+                        != last_transaction_id
+                })?;
+                // The remaining stages would format an outdated text, and their edits can't be grouped with the
+                // ones of the previous stages.
+                if edited {
+                    log::warn!(
+                        "Formatting {path}: the buffer was edited while running {stage}, stopping the chain"
+                    );
+                    break;
+                }
+                buffer.update(cx, |buffer, cx| {
+                    buffer.finalize_last_transaction();
+                    buffer.start_transaction();
+                    match operation {
+                        FormatOperation::Lsp(edits) => {
+                            buffer.edit(edits, None, cx);
+                        }
+                        FormatOperation::External(diff) | FormatOperation::Prettier(diff) => {
+                            buffer.apply_diff(diff, cx);
+                        }
+                    }
+                    buffer.end_transaction(cx)
+                })?
+            };
+
+            let Some(transaction_id) = transaction_id else {
+                log::info!("Formatting {path}: {stage} made no changes");
+                continue;
+            };
+            buffer.update(cx, |buffer, _| {
+                let rows = buffer
+                    .edited_ranges_for_transaction_id::<Point>(transaction_id)
+                    .collect::<Vec<_>>();
+                log::info!(
+                    "Formatting {path}: {stage} changed {}",
+                    describe_edited_rows(&rows)
+                );
+                match first_transaction_id {
+                    Some(first_transaction_id) => {
+                        buffer.group_until_transaction(first_transaction_id)
+                    }
+                    None => first_transaction_id = Some(transaction_id),
+                }
+            })?;
+        }
+
+        Ok(())
+    }
+}
+
+/// The stages of a formatter chain, with the nested chains flattened.
+fn chain_stages(formatters: &[Formatter]) -> Vec<&Formatter> {
+    formatters
+        .iter()
+        .flat_map(|formatter| match formatter {
+            Formatter::Chain(formatters) => chain_stages(formatters),
+            formatter => vec![formatter],
+        })
+        .collect()
+}
+
+fn stage_label(formatter: &Formatter) -> String {
+    match formatter {
+        Formatter::Auto => "auto".to_string(),
+        Formatter::LanguageServer => "language server".to_string(),
+        Formatter::Prettier => "prettier".to_string(),
+        Formatter::External { command, .. } => format!("external command {command:?}"),
+        Formatter::CodeActions(_) => "code actions".to_string(),
+        Formatter::Chain(_) => "chain".to_string(),
+    }
+}
+
+/// Describes the rows touched by the edited ranges, one-based, e.g. `lines 1-3, 7`.
+pub(crate) fn describe_edited_rows(ranges: &[Range<Point>]) -> String {
+    let mut rows = ranges
+        .iter()
+        .map(|range| range.start.row + 1..=range.end.row + 1)
+        .collect::<Vec<_>>();
+    rows.sort_by_key(|rows| *rows.start());
+    let mut merged = Vec::<(u32, u32)>::new();
+    for rows in rows {
+        match merged.last_mut() {
+            Some((_, end)) if *rows.start() <= *end + 1 => *end = (*end).max(*rows.end()),
+            _ => merged.push((*rows.start(), *rows.end())),
+        }
+    }
+    let description = merged
+        .iter()
+        .map(|(start, end)| {
+            if start == end {
+                start.to_string()
+            } else {
+                format!("{start}-{end}")
+            }
+        })
+        .join(", ");
+    if merged.len() == 1 && merged[0].0 == merged[0].1 {
+        format!("line {description}")
+    } else {
+        format!("lines {description}")
+    }
+}
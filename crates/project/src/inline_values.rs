@@ -0,0 +1,147 @@
+//! Inline values: during a debug session, language servers report with `textDocument/inlineValue` what to show at
+//! the end of the lines of the visible range for the stack frame the debuggee stopped at, either as text or as the
+//! variables and expressions for the debugger to evaluate.
+//!
+//! The values depend on the stopped location, so they are requested again whenever the debuggee stops; servers
+//! ask for that themselves with `workspace/inlineValue/refresh`.
+
+use std::ops::Range;
+
+use anyhow::{anyhow, Result};
+use client::TypedEnvelope;
+use gpui::{AsyncAppContext, Model, ModelContext, Task};
+use language::{
+    proto::{deserialize_anchor, serialize_anchor},
+    Anchor, Buffer, ToOffset,
+};
+use rpc::proto;
+
+use crate::{lsp_command::GetInlineValues, Event, LanguageServerToQuery, Project};
+
+/// A value to show at the end of a line for the stopped stack frame.
+#[derive(Clone, Debug, PartialEq)]
+pub struct InlineValue {
+    /// The range the value is for, the line of which the value is shown at the end of.
+    pub range: Range<Anchor>,
+    pub kind: InlineValueKind,
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub enum InlineValueKind {
+    /// Text to show as is.
+    Text(String),
+    /// A variable whose value the debugger looks up, named by the text of the range if `variable_name` is `None`.
+    VariableLookup {
+        variable_name: Option<String>,
+        case_sensitive: bool,
+    },
+    /// An expression for the debugger to evaluate, which is the text of the range if `expression` is `None`.
+    EvaluatableExpression { expression: Option<String> },
+}
+
+impl InlineValue {
+    pub(crate) fn to_proto(&self) -> proto::InlineValue {
+        let kind = match &self.kind {
+            InlineValueKind::Text(text) => proto::inline_value::Kind::Text(text.clone()),
+            InlineValueKind::VariableLookup {
+                variable_name,
+                case_sensitive,
+            } => proto::inline_value::Kind::VariableLookup(proto::InlineValueVariableLookup {
+                variable_name: variable_name.clone(),
+                case_sensitive: *case_sensitive,
+            }),
+            InlineValueKind::EvaluatableExpression { expression } => {
+                proto::inline_value::Kind::EvaluatableExpression(
+                    proto::InlineValueEvaluatableExpression {
+                        expression: expression.clone(),
+                    },
+                )
+            }
+        };
+        proto::InlineValue {
+            start: Some(serialize_anchor(&self.range.start)),
+            end: Some(serialize_anchor(&self.range.end)),
+            kind: Some(kind),
+        }
+    }
+
+    pub(crate) fn from_proto(value: proto::InlineValue) -> Result<Self> {
+        let start = value
+            .start
+            .and_then(deserialize_anchor)
+            .ok_or_else(|| anyhow!("invalid inline value start"))?;
+        let end = value
+            .end
+            .and_then(deserialize_anchor)
+            .ok_or_else(|| anyhow!("invalid inline value end"))?;
+        let kind = match value
+            .kind
+            .ok_or_else(|| anyhow!("missing inline value kind"))?
+        {
+            proto::inline_value::Kind::Text(text) => InlineValueKind::Text(text),
+            proto::inline_value::Kind::VariableLookup(lookup) => InlineValueKind::VariableLookup {
+                variable_name: lookup.variable_name,
+                case_sensitive: lookup.case_sensitive,
+            },
+            proto::inline_value::Kind::EvaluatableExpression(expression) => {
+                InlineValueKind::EvaluatableExpression {
+                    expression: expression.expression,
+                }
+            }
+        };
+        Ok(Self {
+            range: start..end,
+            kind,
+        })
+    }
+}
+
+impl Project {
+    /// Asks the primary language server of the buffer for the inline values of a range, usually the visible one,
+    /// while the debuggee is stopped at `stopped_location` in the stack frame `frame_id` of the debug adapter.
+    pub fn inline_values<T: ToOffset>(
+        &self,
+        buffer: &Model<Buffer>,
+        range: Range<T>,
+        frame_id: i32,
+        stopped_location: Range<T>,
+        cx: &mut ModelContext<Self>,
+    ) -> Task<Result<Vec<InlineValue>>> {
+        let snapshot = buffer.read(cx);
+        let range = snapshot.anchor_before(range.start)..snapshot.anchor_after(range.end);
+        let stopped_location = snapshot.anchor_before(stopped_location.start)
+            ..snapshot.anchor_after(stopped_location.end);
+        self.request_lsp(
+            buffer.clone(),
+            LanguageServerToQuery::Primary,
+            GetInlineValues {
+                range,
+                frame_id,
+                stopped_location,
+            },
+            cx,
+        )
+    }
+
+    /// Handles `workspace/inlineValue/refresh` requests: all editors showing inline values are asked to request
+    /// them again.
+    pub(crate) fn refresh_inline_values(&mut self, cx: &mut ModelContext<Self>) -> Result<()> {
+        cx.emit(Event::RefreshInlineValues);
+        if let Some(project_id) = self.remote_id() {
+            self.client
+                .send(proto::RefreshInlineValues { project_id })?;
+        }
+        Ok(())
+    }
+
+    pub(crate) async fn handle_refresh_inline_values(
+        this: Model<Self>,
+        _: TypedEnvelope<proto::RefreshInlineValues>,
+        mut cx: AsyncAppContext,
+    ) -> Result<proto::Ack> {
+        this.update(&mut cx, |_, cx| {
+            cx.emit(Event::RefreshInlineValues);
+        })?;
+        Ok(proto::Ack {})
+    }
+}
@@ -0,0 +1,121 @@
+//! Language server pins: when several language servers are eligible for a buffer, the requests that go to a
+//! single server, like go to definition, rename or formatting, go to the one pinned for the buffer instead of the
+//! primary language server of its language.
+//!
+//! Pins are stored in the `pinned_language_servers` setting of the worktree's `.zed/settings.json`, by the path of
+//! the buffer relative to the worktree root, so they're kept across sessions and shared with the project.
+
+use std::{path::Path, sync::Arc};
+
+use anyhow::{anyhow, Context as _, Result};
+use gpui::{AppContext, Model, ModelContext, Task};
+use language::{Buffer, CachedLspAdapter, LanguageServerName};
+use lsp::{LanguageServer, LanguageServerId};
+use paths::local_settings_file_relative_path;
+use settings::{initial_local_settings_content, Settings, SettingsLocation, SettingsStore};
+
+use crate::{project_settings::ProjectSettings, File, Project};
+
+impl Project {
+    /// The language server pinned for the buffer, if any.
+    pub fn pinned_language_server_for_buffer(
+        &self,
+        buffer: &Buffer,
+        cx: &AppContext,
+    ) -> Option<LanguageServerName> {
+        let file = File::from_dyn(buffer.file())?;
+        let settings = ProjectSettings::get(
+            Some(SettingsLocation {
+                worktree_id: file.worktree_id(cx).to_proto() as usize,
+                path: &file.path,
+            }),
+            cx,
+        );
+        let path: &Path = &file.path;
+        settings
+            .pinned_language_servers
+            .get(path)
+            .map(|name| LanguageServerName(name.as_str().into()))
+    }
+
+    /// The running language servers of the buffer that it can be pinned to.
+    pub fn pinnable_language_servers_for_buffer(
+        &self,
+        buffer: &Buffer,
+        cx: &AppContext,
+    ) -> Vec<(LanguageServerId, LanguageServerName)> {
+        self.language_servers_for_buffer(buffer, cx)
+            .map(|(adapter, server)| (server.server_id(), adapter.name.clone()))
+            .collect()
+    }
+
+    /// Pins the language server of the buffer, or unpins it if `server_name` is `None`, by updating the local
+    /// settings of its worktree.
+    pub fn pin_language_server_for_buffer(
+        &mut self,
+        buffer: &Model<Buffer>,
+        server_name: Option<LanguageServerName>,
+        cx: &mut ModelContext<Self>,
+    ) -> Task<Result<()>> {
+        if !self.is_local() {
+            return Task::ready(Err(anyhow!(
+                "language servers can only be pinned in local projects"
+            )));
+        }
+        let Some(file) = File::from_dyn(buffer.read(cx).file()) else {
+            return Task::ready(Err(anyhow!("buffer has no file")));
+        };
+        let path = file.path.to_path_buf();
+        let settings_path = file
+            .worktree
+            .read(cx)
+            .abs_path()
+            .join(local_settings_file_relative_path());
+        let fs = self.fs.clone();
+        cx.spawn(|_, cx| async move {
+            let old_text = if fs.is_file(&settings_path).await {
+                fs.load(&settings_path).await?
+            } else {
+                initial_local_settings_content().to_string()
+            };
+            let new_text = cx.read_global(|store: &SettingsStore, _| {
+                store.new_text_for_update::<ProjectSettings>(old_text, |settings| match server_name
+                {
+                    Some(server_name) => {
+                        settings
+                            .pinned_language_servers
+                            .insert(path, server_name.0.to_string());
+                    }
+                    None => {
+                        settings.pinned_language_servers.remove(&path);
+                    }
+                })
+            })?;
+            if let Some(settings_dir) = settings_path.parent() {
+                fs.create_dir(settings_dir).await?;
+            }
+            fs.atomic_write(settings_path.clone(), new_text)
+                .await
+                .with_context(|| format!("writing settings to {settings_path:?}"))
+        })
+    }
+
+    /// The language server that handles the requests of the buffer that go to a single server: the pinned one if
+    /// it's running, or the primary one of its language.
+    pub(crate) fn primary_language_server_for_buffer(
+        &self,
+        buffer: &Buffer,
+        cx: &AppContext,
+    ) -> Option<(&Arc<CachedLspAdapter>, &Arc<LanguageServer>)> {
+        if let Some(pinned) = self.pinned_language_server_for_buffer(buffer, cx) {
+            if let Some(server) = self
+                .language_servers_for_buffer(buffer, cx)
+                .find(|(adapter, _)| adapter.name == pinned)
+            {
+                return Some(server);
+            }
+        }
+        self.language_servers_for_buffer(buffer, cx)
+            .find(|(adapter, _)| adapter.is_primary)
+    }
+}
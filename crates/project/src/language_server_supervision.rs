@@ -0,0 +1,415 @@
+//! Supervision of the language servers that crash, on start or while running.
+//!
+//! Crashed servers are restarted with an exponential backoff. When a server keeps crashing, the user is notified
+//! with the stderr of its last crashes, and can stop it from being restarted again for the rest of the session.
+
+use std::{
+    collections::VecDeque,
+    path::Path,
+    sync::Arc,
+    time::{Duration, Instant},
+};
+
+use collections::{HashMap, HashSet};
+use futures::StreamExt as _;
+use gpui::{ModelContext, PromptLevel};
+use language::{CachedLspAdapter, Language, LanguageServerName};
+use lsp::{IoKind, LanguageServer, LanguageServerId, MessageActionItem};
+use parking_lot::Mutex;
+use worktree::WorktreeId;
+
+use crate::{Event, LanguageServerPromptRequest, LanguageServerState, Project};
+
+/// The delay before a server is restarted after its first crash, which doubles with each consecutive crash.
+const CRASH_BACKOFF_BASE: Duration = Duration::from_secs(1);
+const MAX_CRASH_BACKOFF: Duration = Duration::from_secs(5 * 60);
+/// How long a server has to run for its earlier crashes not to count as consecutive anymore.
+const STABLE_UPTIME: Duration = Duration::from_secs(2 * 60);
+/// The number of consecutive crashes after which the user is notified.
+const CRASHES_BEFORE_NOTIFICATION: usize = 3;
+/// The number of last crashes of each server whose stderr is kept.
+const MAX_CAPTURED_CRASHES: usize = 3;
+/// The length of the end of the stderr of a run of a server that is kept, in bytes.
+const MAX_CAPTURED_STDERR_LEN: usize = 16 * 1024;
+/// The number of the last lines of the stderr of each crash shown in the notification.
+const MAX_NOTIFIED_STDERR_LINES: usize = 10;
+
+const DISABLE_RESTARTS_ACTION: &str = "Don't Restart Again This Session";
+
+/// A crash of a language server, on start or while it was running.
+#[derive(Clone, Debug, PartialEq)]
+pub(crate) struct LanguageServerCrash {
+    pub error: String,
+    /// The end of what the server wrote to stderr before crashing.
+    pub stderr: String,
+}
+
+#[derive(Default)]
+pub(crate) struct LanguageServerSupervision {
+    crashes: HashMap<LanguageServerName, ServerCrashes>,
+    running: HashMap<LanguageServerId, RunningServer>,
+    disabled: HashSet<LanguageServerName>,
+}
+
+#[derive(Default)]
+struct ServerCrashes {
+    consecutive: usize,
+    last: VecDeque<LanguageServerCrash>,
+}
+
+struct RunningServer {
+    started_at: Instant,
+    stderr: Arc<Mutex<String>>,
+    _stderr_subscription: lsp::Subscription,
+}
+
+impl LanguageServerSupervision {
+    fn is_disabled(&self, name: &LanguageServerName) -> bool {
+        self.disabled.contains(name)
+    }
+
+    /// Lets the server be restarted again, forgetting its crashes.
+    fn enable(&mut self, name: &LanguageServerName) {
+        self.disabled.remove(name);
+        self.crashes.remove(name);
+    }
+
+    /// Records a crash of the server, which ran for the given duration if it had started, and returns the
+    /// number of consecutive crashes of the server along with the delay before restarting it.
+    fn record_crash(
+        &mut self,
+        name: &LanguageServerName,
+        crash: LanguageServerCrash,
+        ran_for: Option<Duration>,
+    ) -> (usize, Duration) {
+        let crashes = self.crashes.entry(name.clone()).or_default();
+        if ran_for.map_or(false, |ran_for| ran_for >= STABLE_UPTIME) {
+            crashes.consecutive = 0;
+        }
+        crashes.consecutive += 1;
+        if crashes.last.len() == MAX_CAPTURED_CRASHES {
+            crashes.last.pop_front();
+        }
+        crashes.last.push_back(crash);
+        (crashes.consecutive, crash_backoff(crashes.consecutive))
+    }
+
+    fn last_crashes(
+        &self,
+        name: &LanguageServerName,
+    ) -> impl Iterator<Item = &LanguageServerCrash> {
+        self.crashes
+            .get(name)
+            .into_iter()
+            .flat_map(|crashes| crashes.last.iter())
+    }
+}
+
+/// Returns the delay before restarting a server after its consecutive crashes.
+fn crash_backoff(consecutive_crashes: usize) -> Duration {
+    let exponent = consecutive_crashes.saturating_sub(1).min(16) as u32;
+    (CRASH_BACKOFF_BASE * 2u32.pow(exponent)).min(MAX_CRASH_BACKOFF)
+}
+
+/// Appends the message to the captured stderr, dropping its start past the maximum length.
+fn append_stderr(stderr: &mut String, message: &str) {
+    stderr.push_str(message);
+    if stderr.len() > MAX_CAPTURED_STDERR_LEN {
+        let mut start = stderr.len() - MAX_CAPTURED_STDERR_LEN;
+        while !stderr.is_char_boundary(start) {
+            start += 1;
+        }
+        stderr.drain(..start);
+    }
+}
+
+impl Project {
+    /// Returns whether the user chose not to restart the server again in this session.
+    pub(crate) fn language_server_restarts_disabled(&self, name: &LanguageServerName) -> bool {
+        self.language_server_supervision.is_disabled(name)
+    }
+
+    /// Lets the server be started again after the user asked to restart it.
+    pub(crate) fn enable_language_server_restarts(&mut self, name: &LanguageServerName) {
+        self.language_server_supervision.enable(name);
+    }
+
+    /// Captures the stderr of the newly running server, and restarts it when its process exits without being
+    /// stopped.
+    #[allow(clippy::too_many_arguments)]
+    pub(crate) fn supervise_language_server(
+        &mut self,
+        language: Arc<Language>,
+        adapter: Arc<CachedLspAdapter>,
+        language_server: &LanguageServer,
+        server_id: LanguageServerId,
+        worktree_id: WorktreeId,
+        root: Arc<Path>,
+        cx: &mut ModelContext<Self>,
+    ) {
+        let stderr = Arc::new(Mutex::new(String::new()));
+        let stderr_subscription = language_server.on_io({
+            let stderr = stderr.clone();
+            move |kind, message| {
+                if let IoKind::StdErr = kind {
+                    append_stderr(&mut stderr.lock(), message);
+                }
+            }
+        });
+        self.language_server_supervision.running.insert(
+            server_id,
+            RunningServer {
+                started_at: Instant::now(),
+                stderr,
+                _stderr_subscription: stderr_subscription,
+            },
+        );
+
+        let stopped = language_server.stopped();
+        cx.spawn(move |this, mut cx| async move {
+            stopped.await;
+            this.update(&mut cx, |this, cx| {
+                this.language_server_stopped(language, adapter, server_id, worktree_id, root, cx)
+            })
+            .ok();
+        })
+        .detach();
+    }
+
+    fn language_server_stopped(
+        &mut self,
+        language: Arc<Language>,
+        adapter: Arc<CachedLspAdapter>,
+        server_id: LanguageServerId,
+        worktree_id: WorktreeId,
+        root: Arc<Path>,
+        cx: &mut ModelContext<Self>,
+    ) {
+        let Some(running) = self.language_server_supervision.running.remove(&server_id) else {
+            return;
+        };
+        // Servers that are stopped on purpose are removed before they're shut down.
+        if !matches!(
+            self.language_servers.get(&server_id),
+            Some(LanguageServerState::Running { .. })
+        ) {
+            return;
+        }
+
+        let name = adapter.name.clone();
+        let stderr = running.stderr.lock().clone();
+        log::error!("language server {} exited unexpectedly", name.0);
+        log::error!("server stderr: {stderr:?}");
+        let delay = self.record_language_server_crash(
+            &name,
+            LanguageServerCrash {
+                error: "exited unexpectedly".to_string(),
+                stderr,
+            },
+            Some(running.started_at.elapsed()),
+            cx,
+        );
+        let stop = self.stop_language_server(worktree_id, name.clone(), root.clone(), cx);
+        let Some(delay) = delay else {
+            return;
+        };
+
+        log::info!(
+            "restarting language server {} in {}s",
+            name.0,
+            delay.as_secs()
+        );
+        cx.spawn(move |this, mut cx| async move {
+            let orphaned_worktrees = stop.await;
+            cx.background_executor().timer(delay).await;
+            this.update(&mut cx, |this, cx| {
+                let Some(worktree) = this.worktree_for_id(worktree_id, cx) else {
+                    return;
+                };
+                this.start_language_server(&worktree, adapter, language, root.clone(), cx);
+
+                // Let the other worktrees that used the crashed server use the new one.
+                let key = (worktree_id, name.clone(), root.clone());
+                if let Some(new_server_id) = this.language_server_ids.get(&key).copied() {
+                    for orphaned_worktree_id in orphaned_worktrees {
+                        if orphaned_worktree_id != worktree_id {
+                            this.language_server_ids.insert(
+                                (orphaned_worktree_id, name.clone(), root.clone()),
+                                new_server_id,
+                            );
+                        }
+                    }
+                }
+            })
+            .ok();
+        })
+        .detach();
+    }
+
+    /// Records a crash of the server, notifying the user when it keeps crashing, and returns the delay before
+    /// restarting it, or `None` if it shouldn't be restarted again.
+    pub(crate) fn record_language_server_crash(
+        &mut self,
+        name: &LanguageServerName,
+        crash: LanguageServerCrash,
+        ran_for: Option<Duration>,
+        cx: &mut ModelContext<Self>,
+    ) -> Option<Duration> {
+        if self.language_server_supervision.is_disabled(name) {
+            return None;
+        }
+        let (consecutive_crashes, delay) = self
+            .language_server_supervision
+            .record_crash(name, crash, ran_for);
+        if consecutive_crashes == CRASHES_BEFORE_NOTIFICATION {
+            self.notify_language_server_crashes(name, consecutive_crashes, delay, cx);
+        }
+        Some(delay)
+    }
+
+    fn notify_language_server_crashes(
+        &self,
+        name: &LanguageServerName,
+        consecutive_crashes: usize,
+        delay: Duration,
+        cx: &mut ModelContext<Self>,
+    ) {
+        let mut message = format!(
+            "Language server {} crashed {consecutive_crashes} times in a row. It will be restarted in {}s.",
+            name.0,
+            delay.as_secs()
+        );
+        for crash in self.language_server_supervision.last_crashes(name) {
+            message.push_str(&format!("\n\nThe server {}", crash.error));
+            let lines = crash.stderr.lines().collect::<Vec<_>>();
+            let lines = &lines[lines.len().saturating_sub(MAX_NOTIFIED_STDERR_LINES)..];
+            if !lines.is_empty() {
+                message.push_str(" after writing to stderr:\n");
+                message.push_str(&lines.join("\n"));
+            }
+        }
+
+        let (tx, mut rx) = smol::channel::bounded(1);
+        cx.emit(Event::LanguageServerPrompt(LanguageServerPromptRequest {
+            level: PromptLevel::Critical,
+            message,
+            actions: vec![
+                MessageActionItem {
+                    title: DISABLE_RESTARTS_ACTION.to_string(),
+                    properties: Default::default(),
+                },
+                MessageActionItem {
+                    title: "Dismiss".to_string(),
+                    properties: Default::default(),
+                },
+            ],
+            lsp_name: name.0.to_string(),
+            response_channel: tx,
+        }));
+
+        let name = name.clone();
+        cx.spawn(move |this, mut cx| async move {
+            let Some(response) = rx.next().await else {
+                return;
+            };
+            if response.title != DISABLE_RESTARTS_ACTION {
+                return;
+            }
+            this.update(&mut cx, |this, cx| {
+                this.disable_language_server_restarts(name, cx)
+            })
+            .ok();
+        })
+        .detach();
+    }
+
+    /// Stops the servers with the name, and doesn't start them again for the rest of the session.
+    fn disable_language_server_restarts(
+        &mut self,
+        name: LanguageServerName,
+        cx: &mut ModelContext<Self>,
+    ) {
+        log::info!("not restarting language server {} again", name.0);
+        self.language_server_supervision
+            .disabled
+            .insert(name.clone());
+        let keys = self
+            .language_server_ids
+            .keys()
+            .filter(|(_, server_name, _)| *server_name == name)
+            .cloned()
+            .collect::<Vec<_>>();
+        for (worktree_id, server_name, root) in keys {
+            self.stop_language_server(worktree_id, server_name, root, cx)
+                .detach();
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use language::LanguageServerName;
+
+    use super::{
+        append_stderr, crash_backoff, LanguageServerCrash, LanguageServerSupervision,
+        MAX_CAPTURED_CRASHES, MAX_CAPTURED_STDERR_LEN, MAX_CRASH_BACKOFF, STABLE_UPTIME,
+    };
+
+    fn crash(stderr: &str) -> LanguageServerCrash {
+        LanguageServerCrash {
+            error: "exited unexpectedly".to_string(),
+            stderr: stderr.to_string(),
+        }
+    }
+
+    #[test]
+    fn test_crash_backoff() {
+        let name = LanguageServerName("the-server".into());
+        let mut supervision = LanguageServerSupervision::default();
+
+        assert_eq!(
+            supervision.record_crash(&name, crash("a"), None),
+            (1, Duration::from_secs(1))
+        );
+        assert_eq!(
+            supervision.record_crash(&name, crash("b"), Some(Duration::from_secs(1))),
+            (2, Duration::from_secs(2))
+        );
+        assert_eq!(
+            supervision.record_crash(&name, crash("c"), None),
+            (3, Duration::from_secs(4))
+        );
+        assert_eq!(crash_backoff(100), MAX_CRASH_BACKOFF);
+
+        // Crashes after running for a while start a new streak.
+        assert_eq!(
+            supervision.record_crash(&name, crash("d"), Some(STABLE_UPTIME)),
+            (1, Duration::from_secs(1))
+        );
+        let stderrs = supervision
+            .last_crashes(&name)
+            .map(|crash| crash.stderr.as_str())
+            .collect::<Vec<_>>();
+        assert_eq!(stderrs.len(), MAX_CAPTURED_CRASHES);
+        assert_eq!(stderrs, ["b", "c", "d"]);
+
+        supervision.disabled.insert(name.clone());
+        assert!(supervision.is_disabled(&name));
+        supervision.enable(&name);
+        assert!(!supervision.is_disabled(&name));
+        assert_eq!(supervision.last_crashes(&name).count(), 0);
+    }
+
+    #[test]
+    fn test_append_stderr() {
+        let mut stderr = String::new();
+        append_stderr(&mut stderr, "panicked at 'oops'\n");
+        assert_eq!(stderr, "panicked at 'oops'\n");
+
+        append_stderr(&mut stderr, &"é".repeat(MAX_CAPTURED_STDERR_LEN));
+        assert!(stderr.len() <= MAX_CAPTURED_STDERR_LEN);
+        assert!(stderr.chars().all(|character| character == 'é'));
+    }
+}
@@ -1,27 +1,34 @@
 use crate::{
-    CodeAction, CoreCompletion, DocumentHighlight, Hover, HoverBlock, HoverBlockKind, InlayHint,
-    InlayHintLabel, InlayHintLabelPart, InlayHintLabelPartTooltip, InlayHintTooltip, Location,
-    LocationLink, MarkupContent, Project, ProjectTransaction, ResolveState,
+    CallHierarchyCall, CallHierarchyItem, CodeAction, CoreCompletion, CoreCompletionResponse,
+    DocumentHighlight, DocumentSymbol, File, FoldingRange, FoldingRangeKind, Hover, HoverBlock,
+    HoverBlockKind, InlayHint, InlayHintLabel, InlayHintLabelPart, InlayHintLabelPartCommand,
+    InlayHintLabelPartTooltip, InlayHintTooltip, InlineValue, InlineValueKind,
+    LanguageServerToQuery, Location, LocationLink, MarkupContent, Project, ProjectTransaction,
+    ResolveState, SignatureHelp, SignatureHelpTrigger, TypeHierarchyItem,
 };
 use anyhow::{anyhow, Context, Result};
 use async_trait::async_trait;
 use client::proto::{self, PeerId};
-use futures::future;
+use collections::{HashMap, HashSet};
+use futures::{channel::mpsc, future};
 use gpui::{AppContext, AsyncAppContext, Model};
+use itertools::Itertools as _;
 use language::{
     language_settings::{language_settings, InlayHintKind},
     point_from_lsp, point_to_lsp,
     proto::{deserialize_anchor, deserialize_version, serialize_anchor, serialize_version},
-    range_from_lsp, range_to_lsp, Anchor, Bias, Buffer, BufferSnapshot, CachedLspAdapter, CharKind,
-    OffsetRangeExt, PointUtf16, ToOffset, ToPointUtf16, Transaction, Unclipped,
+    range_from_lsp, range_to_lsp, Anchor, AnchorRangeExt, Bias, Buffer, BufferSnapshot,
+    CachedLspAdapter, CharKind, OffsetRangeExt, Point, PointUtf16, ToOffset, ToPointUtf16,
+    Transaction, Unclipped,
 };
 use lsp::{
     CompletionContext, CompletionListItemDefaultsEditRange, CompletionTriggerKind,
     DocumentHighlightKind, LanguageServer, LanguageServerId, LinkedEditingRangeServerCapabilities,
     OneOf, ServerCapabilities,
 };
-use std::{cmp::Reverse, ops::Range, path::Path, sync::Arc};
+use std::{cmp::Reverse, iter, ops::Range, path::Path, sync::Arc};
 use text::{BufferId, LineEnding};
+use util::ResultExt;
 
 pub fn lsp_formatting_options(tab_size: u32) -> lsp::FormattingOptions {
     lsp::FormattingOptions {
@@ -46,6 +53,11 @@ pub trait LspCommand: 'static + Sized + Send {
         None
     }
 
+    /// The language server that the request is sent to when it's received from a guest.
+    fn language_server_to_query(&self) -> LanguageServerToQuery {
+        LanguageServerToQuery::Primary
+    }
+
     fn to_lsp(
         &self,
         path: &Path,
@@ -115,6 +127,12 @@ pub(crate) struct GetImplementation {
 
 pub(crate) struct GetReferences {
     pub position: PointUtf16,
+    /// The token of the `$/progress` notifications to report the references found so far with, before the
+    /// rest of them are part of the response.
+    pub partial_result_token: Option<String>,
+    /// Where the locations of the response are sent as they're anchored, instead of being part of the response,
+    /// so that the first ones can be shown before all of them are anchored.
+    pub anchored_chunks: Option<mpsc::UnboundedSender<Vec<Location>>>,
 }
 
 pub(crate) struct GetDocumentHighlights {
@@ -164,6 +182,74 @@ pub(crate) struct LinkedEditingRange {
     pub position: Anchor,
 }
 
+#[derive(Default)]
+pub(crate) struct GetDocumentDiagnostics {
+    pub identifier: Option<String>,
+    pub previous_result_id: Option<String>,
+    /// The version of the document that the server knows when the pull is issued.
+    pub document_version: Option<i32>,
+}
+
+/// Diagnostics of a single document, pulled from a language server with `textDocument/diagnostic` or `workspace/diagnostic`.
+#[derive(Clone, Debug)]
+pub struct LspPullDiagnostics {
+    pub server_id: LanguageServerId,
+    pub uri: lsp::Url,
+    /// Id of the report, to pass to the server in subsequent pulls.
+    pub result_id: Option<String>,
+    /// The version of the document that the diagnostics were computed for, if known.
+    pub version: Option<i32>,
+    /// `None` if the server reported the document diagnostics unchanged since the previous result id.
+    pub diagnostics: Option<Vec<lsp::Diagnostic>>,
+}
+
+/// An arbitrary `workspace/executeCommand` request, for server-specific commands that have no dedicated [`LspCommand`].
+#[derive(Clone, Debug)]
+pub struct ExecuteLspCommand {
+    pub command: String,
+    pub arguments: Vec<serde_json::Value>,
+    /// The language server to run the command on, like the one that provided the inlay hint it comes from. When
+    /// unset, it's run on the first language server of the buffer that supports it.
+    pub server_id: Option<LanguageServerId>,
+}
+
+pub(crate) struct PrepareTypeHierarchy {
+    pub position: PointUtf16,
+}
+
+pub(crate) struct GetTypeHierarchySupertypes {
+    pub item: lsp::TypeHierarchyItem,
+    pub server_id: LanguageServerId,
+}
+
+pub(crate) struct GetTypeHierarchySubtypes {
+    pub item: lsp::TypeHierarchyItem,
+    pub server_id: LanguageServerId,
+}
+
+pub(crate) struct PrepareCallHierarchy {
+    pub position: PointUtf16,
+}
+
+pub(crate) struct GetIncomingCalls {
+    pub item: lsp::CallHierarchyItem,
+    pub server_id: LanguageServerId,
+}
+
+pub(crate) struct GetOutgoingCalls {
+    pub item: lsp::CallHierarchyItem,
+    pub server_id: LanguageServerId,
+}
+
+impl ExecuteLspCommand {
+    pub fn is_supported_by(&self, capabilities: &ServerCapabilities) -> bool {
+        capabilities
+            .execute_command_provider
+            .as_ref()
+            .map_or(false, |provider| provider.commands.contains(&self.command))
+    }
+}
+
 #[async_trait(?Send)]
 impl LspCommand for PrepareRename {
     type Response = Option<Range<Anchor>>;
@@ -716,7 +802,7 @@ fn language_server_for_buffer(
         .ok_or_else(|| anyhow!("no language server found for buffer"))
 }
 
-async fn location_links_from_proto(
+pub(crate) async fn location_links_from_proto(
     proto_links: Vec<proto::LocationLink>,
     project: Model<Project>,
     mut cx: AsyncAppContext,
@@ -780,7 +866,7 @@ async fn location_links_from_proto(
     Ok(links)
 }
 
-async fn location_links_from_lsp(
+pub(crate) async fn location_links_from_lsp(
     message: Option<lsp::GotoDefinitionResponse>,
     project: Model<Project>,
     buffer: Model<Buffer>,
@@ -815,54 +901,180 @@ async fn location_links_from_lsp(
 
     let (lsp_adapter, language_server) =
         language_server_for_buffer(&project, &buffer, server_id, &mut cx)?;
-    let mut definitions = Vec::new();
-    for (origin_range, target_uri, target_range) in unresolved_links {
-        let target_buffer_handle = project
-            .update(&mut cx, |this, cx| {
-                this.open_local_buffer_via_lsp(
-                    target_uri,
-                    language_server.server_id(),
-                    lsp_adapter.name.clone(),
-                    cx,
-                )
-            })?
-            .await?;
-
-        cx.update(|cx| {
-            let origin_location = origin_range.map(|origin_range| {
-                let origin_buffer = buffer.read(cx);
+    let (origin_ranges, targets): (Vec<_>, Vec<_>) = unresolved_links
+        .into_iter()
+        .map(|(origin_range, target_uri, target_range)| (origin_range, (target_uri, target_range)))
+        .unzip();
+    let targets = locations_from_lsp(
+        targets,
+        &project,
+        &lsp_adapter,
+        &language_server,
+        None,
+        &mut cx,
+    )
+    .await?;
+    let origins = buffer.read_with(&cx, |origin_buffer, _| {
+        origin_ranges
+            .into_iter()
+            .map(|origin_range| {
+                let origin_range = origin_range?;
                 let origin_start =
                     origin_buffer.clip_point_utf16(point_from_lsp(origin_range.start), Bias::Left);
                 let origin_end =
                     origin_buffer.clip_point_utf16(point_from_lsp(origin_range.end), Bias::Left);
-                Location {
+                Some(Location {
                     buffer: buffer.clone(),
                     range: origin_buffer.anchor_after(origin_start)
                         ..origin_buffer.anchor_before(origin_end),
-                }
-            });
+                })
+            })
+            .collect::<Vec<_>>()
+    })?;
+    Ok(origins
+        .into_iter()
+        .zip(targets)
+        .map(|(origin, target)| LocationLink { origin, target })
+        .collect())
+}
 
-            let target_buffer = target_buffer_handle.read(cx);
-            let target_start =
-                target_buffer.clip_point_utf16(point_from_lsp(target_range.start), Bias::Left);
-            let target_end =
-                target_buffer.clip_point_utf16(point_from_lsp(target_range.end), Bias::Left);
-            let target_location = Location {
-                buffer: target_buffer_handle,
-                range: target_buffer.anchor_after(target_start)
-                    ..target_buffer.anchor_before(target_end),
-            };
+/// How many ranges of a response are anchored at once on the background executor.
+const LOCATIONS_CHUNK_LEN: usize = 1024;
+
+/// Opens the buffers of the locations of a response and anchors their ranges, keeping their order.
+///
+/// Responses with thousands of locations, like the references of a common symbol, would block the main thread if
+/// they were anchored there: each buffer is opened once, and the ranges are anchored in chunks on the background
+/// executor, using snapshots of the buffers. When `chunks` is given, each chunk is sent to it as soon as it's
+/// anchored, in the order of the buffers, and no locations are returned.
+pub(crate) async fn locations_from_lsp(
+    locations: Vec<(lsp::Url, lsp::Range)>,
+    project: &Model<Project>,
+    lsp_adapter: &Arc<CachedLspAdapter>,
+    language_server: &Arc<LanguageServer>,
+    chunks: Option<&mpsc::UnboundedSender<Vec<Location>>>,
+    cx: &mut AsyncAppContext,
+) -> Result<Vec<Location>> {
+    let location_count = locations.len();
+    let mut ranges_by_uri = Vec::<(lsp::Url, Vec<(usize, lsp::Range)>)>::new();
+    let mut uri_indices = HashMap::default();
+    for (ix, (uri, range)) in locations.into_iter().enumerate() {
+        let uri_ix = *uri_indices.entry(uri.clone()).or_insert_with(|| {
+            ranges_by_uri.push((uri, Vec::new()));
+            ranges_by_uri.len() - 1
+        });
+        ranges_by_uri[uri_ix].1.push((ix, range));
+    }
 
-            definitions.push(LocationLink {
-                origin: origin_location,
-                target: target_location,
-            })
-        })?;
+    let mut anchored = iter::repeat_with(|| None)
+        .take(location_count)
+        .collect::<Vec<Option<Location>>>();
+    for (uri, ranges) in ranges_by_uri {
+        let target_buffer = project
+            .update(cx, |this, cx| {
+                this.open_local_buffer_via_lsp(
+                    uri,
+                    language_server.server_id(),
+                    lsp_adapter.name.clone(),
+                    cx,
+                )
+            })?
+            .await?;
+        let snapshot =
+            target_buffer.read_with(cx, |target_buffer, _| target_buffer.text_snapshot())?;
+        for chunk in ranges.chunks(LOCATIONS_CHUNK_LEN) {
+            let chunk = chunk.to_vec();
+            let snapshot = snapshot.clone();
+            let anchored_chunk = cx
+                .background_executor()
+                .spawn(async move {
+                    chunk
+                        .into_iter()
+                        .map(|(ix, range)| {
+                            let start =
+                                snapshot.clip_point_utf16(point_from_lsp(range.start), Bias::Left);
+                            let end =
+                                snapshot.clip_point_utf16(point_from_lsp(range.end), Bias::Left);
+                            (
+                                ix,
+                                snapshot.anchor_after(start)..snapshot.anchor_before(end),
+                            )
+                        })
+                        .collect::<Vec<_>>()
+                })
+                .await;
+            if let Some(chunks) = chunks {
+                let locations = anchored_chunk
+                    .into_iter()
+                    .map(|(_, range)| Location {
+                        buffer: target_buffer.clone(),
+                        range,
+                    })
+                    .collect();
+                chunks.unbounded_send(locations).ok();
+                continue;
+            }
+            for (ix, range) in anchored_chunk {
+                anchored[ix] = Some(Location {
+                    buffer: target_buffer.clone(),
+                    range,
+                });
+            }
+        }
+    }
+    Ok(anchored.into_iter().flatten().collect())
+}
+
+pub(crate) fn locations_to_proto(
+    locations: Vec<Location>,
+    project: &mut Project,
+    peer_id: PeerId,
+    cx: &mut AppContext,
+) -> Vec<proto::Location> {
+    locations
+        .into_iter()
+        .map(|location| {
+            let buffer_id = project.create_buffer_for_peer(&location.buffer, peer_id, cx);
+            proto::Location {
+                start: Some(serialize_anchor(&location.range.start)),
+                end: Some(serialize_anchor(&location.range.end)),
+                buffer_id: buffer_id.into(),
+            }
+        })
+        .collect()
+}
+
+pub(crate) async fn locations_from_proto(
+    proto_locations: Vec<proto::Location>,
+    project: &Model<Project>,
+    cx: &mut AsyncAppContext,
+) -> Result<Vec<Location>> {
+    let mut locations = Vec::new();
+    for location in proto_locations {
+        let buffer_id = BufferId::new(location.buffer_id)?;
+        let target_buffer = project
+            .update(cx, |this, cx| this.wait_for_remote_buffer(buffer_id, cx))?
+            .await?;
+        let start = location
+            .start
+            .and_then(deserialize_anchor)
+            .ok_or_else(|| anyhow!("missing target start"))?;
+        let end = location
+            .end
+            .and_then(deserialize_anchor)
+            .ok_or_else(|| anyhow!("missing target end"))?;
+        target_buffer
+            .update(cx, |buffer, _| buffer.wait_for_anchors([start, end]))?
+            .await?;
+        locations.push(Location {
+            buffer: target_buffer,
+            range: start..end,
+        })
     }
-    Ok(definitions)
+    Ok(locations)
 }
 
-fn location_links_to_proto(
+pub(crate) fn location_links_to_proto(
     links: Vec<LocationLink>,
     project: &mut Project,
     peer_id: PeerId,
@@ -932,7 +1144,12 @@ impl LspCommand for GetReferences {
                 position: point_to_lsp(self.position),
             },
             work_done_progress_params: Default::default(),
-            partial_result_params: Default::default(),
+            partial_result_params: lsp::PartialResultParams {
+                partial_result_token: self
+                    .partial_result_token
+                    .clone()
+                    .map(lsp::NumberOrString::String),
+            },
             context: lsp::ReferenceContext {
                 include_declaration: true,
             },
@@ -947,40 +1164,22 @@ impl LspCommand for GetReferences {
         server_id: LanguageServerId,
         mut cx: AsyncAppContext,
     ) -> Result<Vec<Location>> {
-        let mut references = Vec::new();
         let (lsp_adapter, language_server) =
             language_server_for_buffer(&project, &buffer, server_id, &mut cx)?;
-
-        if let Some(locations) = locations {
-            for lsp_location in locations {
-                let target_buffer_handle = project
-                    .update(&mut cx, |this, cx| {
-                        this.open_local_buffer_via_lsp(
-                            lsp_location.uri,
-                            language_server.server_id(),
-                            lsp_adapter.name.clone(),
-                            cx,
-                        )
-                    })?
-                    .await?;
-
-                target_buffer_handle
-                    .clone()
-                    .update(&mut cx, |target_buffer, _| {
-                        let target_start = target_buffer
-                            .clip_point_utf16(point_from_lsp(lsp_location.range.start), Bias::Left);
-                        let target_end = target_buffer
-                            .clip_point_utf16(point_from_lsp(lsp_location.range.end), Bias::Left);
-                        references.push(Location {
-                            buffer: target_buffer_handle,
-                            range: target_buffer.anchor_after(target_start)
-                                ..target_buffer.anchor_before(target_end),
-                        });
-                    })?;
-            }
-        }
-
-        Ok(references)
+        let locations = locations
+            .unwrap_or_default()
+            .into_iter()
+            .map(|location| (location.uri, location.range))
+            .collect();
+        locations_from_lsp(
+            locations,
+            &project,
+            &lsp_adapter,
+            &language_server,
+            self.anchored_chunks.as_ref(),
+            &mut cx,
+        )
+        .await
     }
 
     fn to_proto(&self, project_id: u64, buffer: &Buffer) -> proto::GetReferences {
@@ -991,6 +1190,7 @@ impl LspCommand for GetReferences {
                 &buffer.anchor_before(self.position),
             )),
             version: serialize_version(&buffer.version()),
+            partial_result_token: self.partial_result_token.clone(),
         }
     }
 
@@ -1011,6 +1211,8 @@ impl LspCommand for GetReferences {
             .await?;
         Ok(Self {
             position: buffer.update(&mut cx, |buffer, _| position.to_point_utf16(buffer))?,
+            partial_result_token: message.partial_result_token,
+            anchored_chunks: None,
         })
     }
 
@@ -1021,18 +1223,9 @@ impl LspCommand for GetReferences {
         _: &clock::Global,
         cx: &mut AppContext,
     ) -> proto::GetReferencesResponse {
-        let locations = response
-            .into_iter()
-            .map(|definition| {
-                let buffer_id = project.create_buffer_for_peer(&definition.buffer, peer_id, cx);
-                proto::Location {
-                    start: Some(serialize_anchor(&definition.range.start)),
-                    end: Some(serialize_anchor(&definition.range.end)),
-                    buffer_id: buffer_id.into(),
-                }
-            })
-            .collect();
-        proto::GetReferencesResponse { locations }
+        proto::GetReferencesResponse {
+            locations: locations_to_proto(response, project, peer_id, cx),
+        }
     }
 
     async fn response_from_proto(
@@ -1042,31 +1235,7 @@ impl LspCommand for GetReferences {
         _: Model<Buffer>,
         mut cx: AsyncAppContext,
     ) -> Result<Vec<Location>> {
-        let mut locations = Vec::new();
-        for location in message.locations {
-            let buffer_id = BufferId::new(location.buffer_id)?;
-            let target_buffer = project
-                .update(&mut cx, |this, cx| {
-                    this.wait_for_remote_buffer(buffer_id, cx)
-                })?
-                .await?;
-            let start = location
-                .start
-                .and_then(deserialize_anchor)
-                .ok_or_else(|| anyhow!("missing target start"))?;
-            let end = location
-                .end
-                .and_then(deserialize_anchor)
-                .ok_or_else(|| anyhow!("missing target end"))?;
-            target_buffer
-                .update(&mut cx, |buffer, _| buffer.wait_for_anchors([start, end]))?
-                .await?;
-            locations.push(Location {
-                buffer: target_buffer,
-                range: start..end,
-            })
-        }
-        Ok(locations)
+        locations_from_proto(message.locations, &project, &mut cx).await
     }
 
     fn buffer_id_from_proto(message: &proto::GetReferences) -> Result<BufferId> {
@@ -1450,7 +1619,7 @@ impl LspCommand for GetHover {
 
 #[async_trait(?Send)]
 impl LspCommand for GetCompletions {
-    type Response = Vec<CoreCompletion>;
+    type Response = CoreCompletionResponse;
     type LspRequest = lsp::request::Completion;
     type ProtoRequest = proto::GetCompletions;
 
@@ -1480,7 +1649,7 @@ impl LspCommand for GetCompletions {
         server_id: LanguageServerId,
         mut cx: AsyncAppContext,
     ) -> Result<Self::Response> {
-        let mut response_list = None;
+        let mut response_list = None::<lsp::CompletionList>;
         let mut completions = if let Some(completions) = completions {
             match completions {
                 lsp::CompletionResponse::Array(completions) => completions,
@@ -1505,25 +1674,21 @@ impl LspCommand for GetCompletions {
             .as_ref()
             .and_then(|list| list.item_defaults.as_ref());
 
+        // The values of the items take precedence over the defaults of the list.
         if let Some(item_defaults) = item_defaults {
-            let default_data = item_defaults.data.as_ref();
-            let default_commit_characters = item_defaults.commit_characters.as_ref();
-            let default_insert_text_mode = item_defaults.insert_text_mode.as_ref();
-
-            if default_data.is_some()
-                || default_commit_characters.is_some()
-                || default_insert_text_mode.is_some()
-            {
-                for item in completions.iter_mut() {
-                    if let Some(data) = default_data {
-                        item.data = Some(data.clone())
-                    }
-                    if let Some(characters) = default_commit_characters {
-                        item.commit_characters = Some(characters.clone())
-                    }
-                    if let Some(text_mode) = default_insert_text_mode {
-                        item.insert_text_mode = Some(*text_mode)
-                    }
+            for item in completions.iter_mut() {
+                if item.data.is_none() {
+                    item.data.clone_from(&item_defaults.data);
+                }
+                if item.commit_characters.is_none() {
+                    item.commit_characters
+                        .clone_from(&item_defaults.commit_characters);
+                }
+                if item.insert_text_mode.is_none() {
+                    item.insert_text_mode = item_defaults.insert_text_mode;
+                }
+                if item.insert_text_format.is_none() {
+                    item.insert_text_format = item_defaults.insert_text_format;
                 }
             }
         }
@@ -1534,6 +1699,9 @@ impl LspCommand for GetCompletions {
             let clipped_position = buffer.clip_point_utf16(Unclipped(self.position), Bias::Left);
 
             let mut range_for_token = None;
+            // Servers completing all the symbols of a project may report the same item more than once, like
+            // the ones of the headers included by several files.
+            let mut seen_completions = HashSet::default();
             completions.retain_mut(|lsp_completion| {
                 let edit = match lsp_completion.text_edit.as_ref() {
                     // If the language server provides a range to overwrite, then
@@ -1557,9 +1725,12 @@ impl LspCommand for GetCompletions {
                             .as_ref()
                             .and_then(|list| list.item_defaults.as_ref())
                             .and_then(|defaults| defaults.edit_range.as_ref())
-                            .and_then(|range| match range {
-                                CompletionListItemDefaultsEditRange::Range(r) => Some(r),
-                                _ => None,
+                            .map(|range| match range {
+                                CompletionListItemDefaultsEditRange::Range(range) => range,
+                                CompletionListItemDefaultsEditRange::InsertAndReplace {
+                                    insert,
+                                    ..
+                                } => insert,
                             });
 
                         let range = if let Some(range) = default_edit_range {
@@ -1598,6 +1769,15 @@ impl LspCommand for GetCompletions {
                     }
                 };
 
+                let is_new = seen_completions.insert((
+                    lsp_completion.label.clone(),
+                    lsp_completion.detail.clone(),
+                    edit.0.start.to_offset(&snapshot)..edit.0.end.to_offset(&snapshot),
+                    edit.1.clone(),
+                ));
+                if !is_new {
+                    return false;
+                }
                 completion_edits.push(edit);
                 true
             });
@@ -1607,7 +1787,7 @@ impl LspCommand for GetCompletions {
             .process_completions(&mut completions)
             .await;
 
-        Ok(completions
+        let completions = completions
             .into_iter()
             .zip(completion_edits)
             .map(|(lsp_completion, (old_range, mut new_text))| {
@@ -1619,7 +1799,11 @@ impl LspCommand for GetCompletions {
                     lsp_completion,
                 }
             })
-            .collect())
+            .collect();
+        Ok(CoreCompletionResponse {
+            completions,
+            is_incomplete: response_list.map_or(false, |list| list.is_incomplete),
+        })
     }
 
     fn to_proto(&self, project_id: u64, buffer: &Buffer) -> proto::GetCompletions {
@@ -1661,18 +1845,20 @@ impl LspCommand for GetCompletions {
     }
 
     fn response_to_proto(
-        completions: Vec<CoreCompletion>,
+        response: CoreCompletionResponse,
         _: &mut Project,
         _: PeerId,
         buffer_version: &clock::Global,
         _: &mut AppContext,
     ) -> proto::GetCompletionsResponse {
         proto::GetCompletionsResponse {
-            completions: completions
+            completions: response
+                .completions
                 .iter()
                 .map(Project::serialize_completion)
                 .collect(),
             version: serialize_version(buffer_version),
+            is_incomplete: response.is_incomplete,
         }
     }
 
@@ -1689,11 +1875,14 @@ impl LspCommand for GetCompletions {
             })?
             .await?;
 
-        message
-            .completions
-            .into_iter()
-            .map(Project::deserialize_completion)
-            .collect()
+        Ok(CoreCompletionResponse {
+            completions: message
+                .completions
+                .into_iter()
+                .map(Project::deserialize_completion)
+                .collect::<Result<_>>()?,
+            is_incomplete: message.is_incomplete,
+        })
     }
 
     fn buffer_id_from_proto(message: &proto::GetCompletions) -> Result<BufferId> {
@@ -2125,6 +2314,21 @@ impl InlayHints {
                             }
                         }),
                         location: Some(server_id).zip(lsp_part.location),
+                        command: lsp_part.command.map(|command| {
+                            (
+                                server_id,
+                                InlayHintLabelPartCommand {
+                                    title: command.title,
+                                    command: command.command,
+                                    arguments: command
+                                        .arguments
+                                        .unwrap_or_default()
+                                        .iter()
+                                        .map(|argument| argument.to_string())
+                                        .collect(),
+                                },
+                            )
+                        }),
                     });
                 }
                 InlayHintLabel::LabelParts(parts)
@@ -2183,7 +2387,13 @@ impl InlayHints {
                                 location_url,
                                 location_range_start,
                                 location_range_end,
-                                language_server_id: label_part.location.as_ref().map(|(server_id, _)| server_id.0 as u64),
+                                language_server_id: label_part.location.as_ref().map(|(server_id, _)| server_id.0 as u64)
+                                    .or_else(|| label_part.command.as_ref().map(|(server_id, _)| server_id.0 as u64)),
+                                command: label_part.command.map(|(_, command)| proto::InlayHintLabelPartCommand {
+                                    title: command.title,
+                                    command: command.command,
+                                    arguments: command.arguments,
+                                }),
                             }}).collect()
                         })
                     }
@@ -2249,6 +2459,18 @@ impl InlayHints {
                 proto::inlay_hint_label::Label::LabelParts(parts) => {
                     let mut label_parts = Vec::new();
                     for part in parts.parts {
+                        let command = part.command.zip(part.language_server_id).map(
+                            |(command, server_id)| {
+                                (
+                                    LanguageServerId(server_id as usize),
+                                    InlayHintLabelPartCommand {
+                                        title: command.title,
+                                        command: command.command,
+                                        arguments: command.arguments,
+                                    },
+                                )
+                            },
+                        );
                         label_parts.push(InlayHintLabelPart {
                             value: part.value,
                             tooltip: part.tooltip.map(|tooltip| match tooltip.content {
@@ -2299,6 +2521,7 @@ impl InlayHints {
                                     None => None,
                                 }
                             },
+                            command,
                         });
                     }
 
@@ -2384,7 +2607,17 @@ impl InlayHints {
                                 })
                             }),
                             location: part.location.map(|(_, location)| location),
-                            command: None,
+                            command: part.command.map(|(_, command)| lsp::Command {
+                                title: command.title,
+                                command: command.command,
+                                arguments: (!command.arguments.is_empty()).then(|| {
+                                    command
+                                        .arguments
+                                        .iter()
+                                        .filter_map(|argument| serde_json::from_str(argument).ok())
+                                        .collect()
+                                }),
+                            }),
                         })
                         .collect(),
                 ),
@@ -2718,3 +2951,1797 @@ impl LspCommand for LinkedEditingRange {
         BufferId::new(message.buffer_id)
     }
 }
+
+#[async_trait(?Send)]
+impl LspCommand for ExecuteLspCommand {
+    type Response = Option<serde_json::Value>;
+    type LspRequest = lsp::request::ExecuteCommand;
+    type ProtoRequest = proto::ExecuteLspCommand;
+
+    fn check_capabilities(&self, capabilities: &ServerCapabilities) -> bool {
+        self.is_supported_by(capabilities)
+    }
+
+    fn language_server_to_query(&self) -> LanguageServerToQuery {
+        match self.server_id {
+            Some(server_id) => LanguageServerToQuery::Other(server_id),
+            None => LanguageServerToQuery::Primary,
+        }
+    }
+
+    fn to_lsp(
+        &self,
+        _: &Path,
+        _: &Buffer,
+        _: &Arc<LanguageServer>,
+        _: &AppContext,
+    ) -> lsp::ExecuteCommandParams {
+        lsp::ExecuteCommandParams {
+            command: self.command.clone(),
+            arguments: self.arguments.clone(),
+            work_done_progress_params: Default::default(),
+        }
+    }
+
+    async fn response_from_lsp(
+        self,
+        message: Option<serde_json::Value>,
+        _: Model<Project>,
+        _: Model<Buffer>,
+        _: LanguageServerId,
+        _: AsyncAppContext,
+    ) -> Result<Option<serde_json::Value>> {
+        Ok(message)
+    }
+
+    fn to_proto(&self, project_id: u64, buffer: &Buffer) -> proto::ExecuteLspCommand {
+        proto::ExecuteLspCommand {
+            project_id,
+            buffer_id: buffer.remote_id().into(),
+            command: self.command.clone(),
+            arguments: self
+                .arguments
+                .iter()
+                .map(|argument| argument.to_string())
+                .collect(),
+            language_server_id: self.server_id.map(|server_id| server_id.0 as u64),
+        }
+    }
+
+    async fn from_proto(
+        message: proto::ExecuteLspCommand,
+        _: Model<Project>,
+        _: Model<Buffer>,
+        _: AsyncAppContext,
+    ) -> Result<Self> {
+        let arguments = message
+            .arguments
+            .iter()
+            .map(|argument| serde_json::from_str(argument))
+            .collect::<Result<Vec<_>, _>>()
+            .context("invalid command arguments")?;
+        Ok(Self {
+            command: message.command,
+            arguments,
+            server_id: message
+                .language_server_id
+                .map(|server_id| LanguageServerId(server_id as usize)),
+        })
+    }
+
+    fn response_to_proto(
+        response: Option<serde_json::Value>,
+        _: &mut Project,
+        _: PeerId,
+        _: &clock::Global,
+        _: &mut AppContext,
+    ) -> proto::ExecuteLspCommandResponse {
+        proto::ExecuteLspCommandResponse {
+            result: response.map(|result| result.to_string()),
+        }
+    }
+
+    async fn response_from_proto(
+        self,
+        message: proto::ExecuteLspCommandResponse,
+        _: Model<Project>,
+        _: Model<Buffer>,
+        _: AsyncAppContext,
+    ) -> Result<Option<serde_json::Value>> {
+        message
+            .result
+            .map(|result| serde_json::from_str(&result).context("invalid command result"))
+            .transpose()
+    }
+
+    fn buffer_id_from_proto(message: &proto::ExecuteLspCommand) -> Result<BufferId> {
+        BufferId::new(message.buffer_id)
+    }
+}
+
+impl LspPullDiagnostics {
+    fn from_report_kind(
+        server_id: LanguageServerId,
+        uri: lsp::Url,
+        report: lsp::DocumentDiagnosticReportKind,
+    ) -> Self {
+        match report {
+            lsp::DocumentDiagnosticReportKind::Full(report) => Self {
+                server_id,
+                uri,
+                result_id: report.result_id,
+                version: None,
+                diagnostics: Some(report.items),
+            },
+            lsp::DocumentDiagnosticReportKind::Unchanged(report) => Self {
+                server_id,
+                uri,
+                result_id: Some(report.result_id),
+                version: None,
+                diagnostics: None,
+            },
+        }
+    }
+
+    fn from_related_documents(
+        server_id: LanguageServerId,
+        related_documents: Option<HashMap<lsp::Url, lsp::DocumentDiagnosticReportKind>>,
+    ) -> impl Iterator<Item = Self> {
+        related_documents
+            .into_iter()
+            .flatten()
+            .map(move |(uri, report)| Self::from_report_kind(server_id, uri, report))
+    }
+
+    pub(crate) fn from_workspace_report(
+        server_id: LanguageServerId,
+        report: lsp::WorkspaceDocumentDiagnosticReport,
+    ) -> Self {
+        match report {
+            lsp::WorkspaceDocumentDiagnosticReport::Full(report) => Self {
+                server_id,
+                uri: report.uri,
+                result_id: report.full_document_diagnostic_report.result_id,
+                version: report
+                    .version
+                    .and_then(|version| i32::try_from(version).ok()),
+                diagnostics: Some(report.full_document_diagnostic_report.items),
+            },
+            lsp::WorkspaceDocumentDiagnosticReport::Unchanged(report) => Self {
+                server_id,
+                uri: report.uri,
+                result_id: Some(report.unchanged_document_diagnostic_report.result_id),
+                version: report
+                    .version
+                    .and_then(|version| i32::try_from(version).ok()),
+                diagnostics: None,
+            },
+        }
+    }
+
+    fn to_proto(&self) -> proto::PulledDiagnostics {
+        proto::PulledDiagnostics {
+            server_id: self.server_id.0 as u64,
+            uri: self.uri.to_string(),
+            result_id: self.result_id.clone(),
+            changed: self.diagnostics.is_some(),
+            diagnostics: self
+                .diagnostics
+                .iter()
+                .flatten()
+                .filter_map(|diagnostic| serde_json::to_vec(diagnostic).log_err())
+                .collect(),
+        }
+    }
+
+    fn from_proto(message: proto::PulledDiagnostics) -> Result<Self> {
+        let diagnostics = if message.changed {
+            Some(
+                message
+                    .diagnostics
+                    .iter()
+                    .map(|diagnostic| serde_json::from_slice(diagnostic))
+                    .collect::<Result<Vec<_>, _>>()
+                    .context("invalid pulled diagnostic")?,
+            )
+        } else {
+            None
+        };
+        Ok(Self {
+            server_id: LanguageServerId(message.server_id as usize),
+            uri: lsp::Url::parse(&message.uri).context("invalid pulled diagnostics uri")?,
+            result_id: message.result_id,
+            version: None,
+            diagnostics,
+        })
+    }
+}
+
+#[async_trait(?Send)]
+impl LspCommand for GetDocumentDiagnostics {
+    type Response = Vec<LspPullDiagnostics>;
+    type LspRequest = lsp::request::DocumentDiagnosticRequest;
+    type ProtoRequest = proto::GetDocumentDiagnostics;
+
+    fn check_capabilities(&self, capabilities: &ServerCapabilities) -> bool {
+        capabilities.diagnostic_provider.is_some()
+    }
+
+    fn to_lsp(
+        &self,
+        path: &Path,
+        _: &Buffer,
+        _: &Arc<LanguageServer>,
+        _: &AppContext,
+    ) -> lsp::DocumentDiagnosticParams {
+        lsp::DocumentDiagnosticParams {
+            text_document: lsp::TextDocumentIdentifier {
+                uri: lsp::Url::from_file_path(path).unwrap(),
+            },
+            identifier: self.identifier.clone(),
+            previous_result_id: self.previous_result_id.clone(),
+            work_done_progress_params: Default::default(),
+            partial_result_params: Default::default(),
+        }
+    }
+
+    async fn response_from_lsp(
+        self,
+        message: lsp::DocumentDiagnosticReportResult,
+        project: Model<Project>,
+        buffer: Model<Buffer>,
+        server_id: LanguageServerId,
+        mut cx: AsyncAppContext,
+    ) -> Result<Vec<LspPullDiagnostics>> {
+        let abs_path = buffer
+            .update(&mut cx, |buffer, cx| {
+                File::from_dyn(buffer.file())
+                    .and_then(File::as_local)
+                    .map(|file| file.abs_path(cx))
+            })?
+            .context("buffer has no local file")?;
+        let uri = lsp::Url::from_file_path(abs_path)
+            .map_err(|()| anyhow!("buffer path is not absolute"))?;
+
+        let pulled_diagnostics = match message {
+            lsp::DocumentDiagnosticReportResult::Report(lsp::DocumentDiagnosticReport::Full(
+                report,
+            )) => iter::once(LspPullDiagnostics {
+                server_id,
+                uri,
+                result_id: report.full_document_diagnostic_report.result_id,
+                version: self.document_version,
+                diagnostics: Some(report.full_document_diagnostic_report.items),
+            })
+            .chain(LspPullDiagnostics::from_related_documents(
+                server_id,
+                report.related_documents,
+            ))
+            .collect::<Vec<_>>(),
+            lsp::DocumentDiagnosticReportResult::Report(
+                lsp::DocumentDiagnosticReport::Unchanged(report),
+            ) => iter::once(LspPullDiagnostics {
+                server_id,
+                uri,
+                result_id: Some(report.unchanged_document_diagnostic_report.result_id),
+                version: self.document_version,
+                diagnostics: None,
+            })
+            .chain(LspPullDiagnostics::from_related_documents(
+                server_id,
+                report.related_documents,
+            ))
+            .collect(),
+            lsp::DocumentDiagnosticReportResult::Partial(partial) => {
+                LspPullDiagnostics::from_related_documents(server_id, partial.related_documents)
+                    .collect()
+            }
+        };
+
+        project.update(&mut cx, |project, cx| {
+            project.apply_pulled_diagnostics(pulled_diagnostics.clone(), cx)
+        })?;
+        Ok(pulled_diagnostics)
+    }
+
+    fn to_proto(&self, project_id: u64, buffer: &Buffer) -> proto::GetDocumentDiagnostics {
+        proto::GetDocumentDiagnostics {
+            project_id,
+            buffer_id: buffer.remote_id().into(),
+            version: serialize_version(&buffer.version()),
+        }
+    }
+
+    async fn from_proto(
+        message: proto::GetDocumentDiagnostics,
+        _: Model<Project>,
+        buffer: Model<Buffer>,
+        mut cx: AsyncAppContext,
+    ) -> Result<Self> {
+        buffer
+            .update(&mut cx, |buffer, _| {
+                buffer.wait_for_version(deserialize_version(&message.version))
+            })?
+            .await?;
+        Ok(Self::default())
+    }
+
+    fn response_to_proto(
+        response: Vec<LspPullDiagnostics>,
+        _: &mut Project,
+        _: PeerId,
+        _: &clock::Global,
+        _: &mut AppContext,
+    ) -> proto::GetDocumentDiagnosticsResponse {
+        proto::GetDocumentDiagnosticsResponse {
+            pulled_diagnostics: response.iter().map(LspPullDiagnostics::to_proto).collect(),
+        }
+    }
+
+    async fn response_from_proto(
+        self,
+        message: proto::GetDocumentDiagnosticsResponse,
+        _: Model<Project>,
+        _: Model<Buffer>,
+        _: AsyncAppContext,
+    ) -> Result<Vec<LspPullDiagnostics>> {
+        message
+            .pulled_diagnostics
+            .into_iter()
+            .map(LspPullDiagnostics::from_proto)
+            .collect()
+    }
+
+    fn buffer_id_from_proto(message: &proto::GetDocumentDiagnostics) -> Result<BufferId> {
+        BufferId::new(message.buffer_id)
+    }
+}
+
+async fn hierarchy_item_locations_from_lsp(
+    ranges: Vec<(lsp::Url, lsp::Range)>,
+    project: &Model<Project>,
+    buffer: &Model<Buffer>,
+    server_id: LanguageServerId,
+    cx: &mut AsyncAppContext,
+) -> Result<Vec<Location>> {
+    let (lsp_adapter, language_server) =
+        language_server_for_buffer(project, buffer, server_id, cx)?;
+    let mut locations = Vec::new();
+    for (uri, range) in ranges {
+        let target_buffer_handle = project
+            .update(cx, |this, cx| {
+                this.open_local_buffer_via_lsp(
+                    uri,
+                    language_server.server_id(),
+                    lsp_adapter.name.clone(),
+                    cx,
+                )
+            })?
+            .await?;
+
+        target_buffer_handle
+            .clone()
+            .update(cx, |target_buffer, _| {
+                locations.push(Location {
+                    buffer: target_buffer_handle,
+                    range: anchor_range_from_lsp(target_buffer, range),
+                });
+            })?;
+    }
+    Ok(locations)
+}
+
+fn anchor_range_from_lsp(buffer: &Buffer, range: lsp::Range) -> Range<Anchor> {
+    let start = buffer.clip_point_utf16(point_from_lsp(range.start), Bias::Left);
+    let end = buffer.clip_point_utf16(point_from_lsp(range.end), Bias::Left);
+    buffer.anchor_after(start)..buffer.anchor_before(end)
+}
+
+fn hierarchy_item_location_to_proto(
+    location: &Location,
+    project: &mut Project,
+    peer_id: PeerId,
+    cx: &mut AppContext,
+) -> proto::Location {
+    let buffer_id = project.create_buffer_for_peer(&location.buffer, peer_id, cx);
+    proto::Location {
+        start: Some(serialize_anchor(&location.range.start)),
+        end: Some(serialize_anchor(&location.range.end)),
+        buffer_id: buffer_id.into(),
+    }
+}
+
+async fn hierarchy_item_location_from_proto(
+    location: Option<proto::Location>,
+    project: &Model<Project>,
+    cx: &mut AsyncAppContext,
+) -> Result<Location> {
+    let location = location.ok_or_else(|| anyhow!("missing location"))?;
+    let buffer_id = BufferId::new(location.buffer_id)?;
+    let target_buffer = project
+        .update(cx, |this, cx| this.wait_for_remote_buffer(buffer_id, cx))?
+        .await?;
+    let start = location
+        .start
+        .and_then(deserialize_anchor)
+        .ok_or_else(|| anyhow!("missing target start"))?;
+    let end = location
+        .end
+        .and_then(deserialize_anchor)
+        .ok_or_else(|| anyhow!("missing target end"))?;
+    target_buffer
+        .update(cx, |buffer, _| buffer.wait_for_anchors([start, end]))?
+        .await?;
+    Ok(Location {
+        buffer: target_buffer,
+        range: start..end,
+    })
+}
+
+async fn type_hierarchy_items_from_lsp(
+    items: Option<Vec<lsp::TypeHierarchyItem>>,
+    project: Model<Project>,
+    buffer: Model<Buffer>,
+    server_id: LanguageServerId,
+    mut cx: AsyncAppContext,
+) -> Result<Vec<TypeHierarchyItem>> {
+    let items = items.unwrap_or_default();
+    let locations = hierarchy_item_locations_from_lsp(
+        items
+            .iter()
+            .map(|item| (item.uri.clone(), item.selection_range))
+            .collect(),
+        &project,
+        &buffer,
+        server_id,
+        &mut cx,
+    )
+    .await?;
+    Ok(items
+        .into_iter()
+        .zip(locations)
+        .map(|(lsp_item, location)| TypeHierarchyItem {
+            location,
+            lsp_item,
+            language_server_id: server_id,
+        })
+        .collect())
+}
+
+fn type_hierarchy_items_to_proto(
+    items: Vec<TypeHierarchyItem>,
+    project: &mut Project,
+    peer_id: PeerId,
+    cx: &mut AppContext,
+) -> Vec<proto::TypeHierarchyItem> {
+    items
+        .into_iter()
+        .map(|item| proto::TypeHierarchyItem {
+            location: Some(hierarchy_item_location_to_proto(
+                &item.location,
+                project,
+                peer_id,
+                cx,
+            )),
+            lsp_item: serde_json::to_vec(&item.lsp_item).unwrap_or_default(),
+            language_server_id: item.language_server_id.0 as u64,
+        })
+        .collect()
+}
+
+async fn type_hierarchy_items_from_proto(
+    items: Vec<proto::TypeHierarchyItem>,
+    project: Model<Project>,
+    mut cx: AsyncAppContext,
+) -> Result<Vec<TypeHierarchyItem>> {
+    let mut hierarchy_items = Vec::new();
+    for item in items {
+        hierarchy_items.push(TypeHierarchyItem {
+            location: hierarchy_item_location_from_proto(item.location, &project, &mut cx).await?,
+            lsp_item: serde_json::from_slice(&item.lsp_item)
+                .context("invalid type hierarchy item")?,
+            language_server_id: LanguageServerId(item.language_server_id as usize),
+        });
+    }
+    Ok(hierarchy_items)
+}
+
+#[async_trait(?Send)]
+impl LspCommand for PrepareTypeHierarchy {
+    type Response = Vec<TypeHierarchyItem>;
+    type LspRequest = lsp::request::TypeHierarchyPrepare;
+    type ProtoRequest = proto::PrepareTypeHierarchy;
+
+    fn to_lsp(
+        &self,
+        path: &Path,
+        _: &Buffer,
+        _: &Arc<LanguageServer>,
+        _: &AppContext,
+    ) -> lsp::TypeHierarchyPrepareParams {
+        lsp::TypeHierarchyPrepareParams {
+            text_document_position_params: lsp::TextDocumentPositionParams {
+                text_document: lsp::TextDocumentIdentifier {
+                    uri: lsp::Url::from_file_path(path).unwrap(),
+                },
+                position: point_to_lsp(self.position),
+            },
+            work_done_progress_params: Default::default(),
+        }
+    }
+
+    async fn response_from_lsp(
+        self,
+        items: Option<Vec<lsp::TypeHierarchyItem>>,
+        project: Model<Project>,
+        buffer: Model<Buffer>,
+        server_id: LanguageServerId,
+        cx: AsyncAppContext,
+    ) -> Result<Vec<TypeHierarchyItem>> {
+        type_hierarchy_items_from_lsp(items, project, buffer, server_id, cx).await
+    }
+
+    fn to_proto(&self, project_id: u64, buffer: &Buffer) -> proto::PrepareTypeHierarchy {
+        proto::PrepareTypeHierarchy {
+            project_id,
+            buffer_id: buffer.remote_id().into(),
+            position: Some(language::proto::serialize_anchor(
+                &buffer.anchor_before(self.position),
+            )),
+            version: serialize_version(&buffer.version()),
+        }
+    }
+
+    async fn from_proto(
+        message: proto::PrepareTypeHierarchy,
+        _: Model<Project>,
+        buffer: Model<Buffer>,
+        mut cx: AsyncAppContext,
+    ) -> Result<Self> {
+        let position = message
+            .position
+            .and_then(deserialize_anchor)
+            .ok_or_else(|| anyhow!("invalid position"))?;
+        buffer
+            .update(&mut cx, |buffer, _| {
+                buffer.wait_for_version(deserialize_version(&message.version))
+            })?
+            .await?;
+        Ok(Self {
+            position: buffer.update(&mut cx, |buffer, _| position.to_point_utf16(buffer))?,
+        })
+    }
+
+    fn response_to_proto(
+        response: Vec<TypeHierarchyItem>,
+        project: &mut Project,
+        peer_id: PeerId,
+        _: &clock::Global,
+        cx: &mut AppContext,
+    ) -> proto::TypeHierarchyResponse {
+        proto::TypeHierarchyResponse {
+            items: type_hierarchy_items_to_proto(response, project, peer_id, cx),
+        }
+    }
+
+    async fn response_from_proto(
+        self,
+        message: proto::TypeHierarchyResponse,
+        project: Model<Project>,
+        _: Model<Buffer>,
+        cx: AsyncAppContext,
+    ) -> Result<Vec<TypeHierarchyItem>> {
+        type_hierarchy_items_from_proto(message.items, project, cx).await
+    }
+
+    fn buffer_id_from_proto(message: &proto::PrepareTypeHierarchy) -> Result<BufferId> {
+        BufferId::new(message.buffer_id)
+    }
+}
+
+#[async_trait(?Send)]
+impl LspCommand for GetTypeHierarchySupertypes {
+    type Response = Vec<TypeHierarchyItem>;
+    type LspRequest = lsp::request::TypeHierarchySupertypes;
+    type ProtoRequest = proto::GetTypeHierarchySupertypes;
+
+    fn language_server_to_query(&self) -> LanguageServerToQuery {
+        LanguageServerToQuery::Other(self.server_id)
+    }
+
+    fn to_lsp(
+        &self,
+        _: &Path,
+        _: &Buffer,
+        _: &Arc<LanguageServer>,
+        _: &AppContext,
+    ) -> lsp::TypeHierarchySupertypesParams {
+        lsp::TypeHierarchySupertypesParams {
+            item: self.item.clone(),
+            work_done_progress_params: Default::default(),
+            partial_result_params: Default::default(),
+        }
+    }
+
+    async fn response_from_lsp(
+        self,
+        items: Option<Vec<lsp::TypeHierarchyItem>>,
+        project: Model<Project>,
+        buffer: Model<Buffer>,
+        server_id: LanguageServerId,
+        cx: AsyncAppContext,
+    ) -> Result<Vec<TypeHierarchyItem>> {
+        type_hierarchy_items_from_lsp(items, project, buffer, server_id, cx).await
+    }
+
+    fn to_proto(&self, project_id: u64, buffer: &Buffer) -> proto::GetTypeHierarchySupertypes {
+        proto::GetTypeHierarchySupertypes {
+            project_id,
+            buffer_id: buffer.remote_id().into(),
+            item: serde_json::to_vec(&self.item).unwrap_or_default(),
+            language_server_id: self.server_id.0 as u64,
+        }
+    }
+
+    async fn from_proto(
+        message: proto::GetTypeHierarchySupertypes,
+        _: Model<Project>,
+        _: Model<Buffer>,
+        _: AsyncAppContext,
+    ) -> Result<Self> {
+        Ok(Self {
+            item: serde_json::from_slice(&message.item).context("invalid type hierarchy item")?,
+            server_id: LanguageServerId(message.language_server_id as usize),
+        })
+    }
+
+    fn response_to_proto(
+        response: Vec<TypeHierarchyItem>,
+        project: &mut Project,
+        peer_id: PeerId,
+        _: &clock::Global,
+        cx: &mut AppContext,
+    ) -> proto::TypeHierarchyResponse {
+        proto::TypeHierarchyResponse {
+            items: type_hierarchy_items_to_proto(response, project, peer_id, cx),
+        }
+    }
+
+    async fn response_from_proto(
+        self,
+        message: proto::TypeHierarchyResponse,
+        project: Model<Project>,
+        _: Model<Buffer>,
+        cx: AsyncAppContext,
+    ) -> Result<Vec<TypeHierarchyItem>> {
+        type_hierarchy_items_from_proto(message.items, project, cx).await
+    }
+
+    fn buffer_id_from_proto(message: &proto::GetTypeHierarchySupertypes) -> Result<BufferId> {
+        BufferId::new(message.buffer_id)
+    }
+}
+
+#[async_trait(?Send)]
+impl LspCommand for GetTypeHierarchySubtypes {
+    type Response = Vec<TypeHierarchyItem>;
+    type LspRequest = lsp::request::TypeHierarchySubtypes;
+    type ProtoRequest = proto::GetTypeHierarchySubtypes;
+
+    fn language_server_to_query(&self) -> LanguageServerToQuery {
+        LanguageServerToQuery::Other(self.server_id)
+    }
+
+    fn to_lsp(
+        &self,
+        _: &Path,
+        _: &Buffer,
+        _: &Arc<LanguageServer>,
+        _: &AppContext,
+    ) -> lsp::TypeHierarchySubtypesParams {
+        lsp::TypeHierarchySubtypesParams {
+            item: self.item.clone(),
+            work_done_progress_params: Default::default(),
+            partial_result_params: Default::default(),
+        }
+    }
+
+    async fn response_from_lsp(
+        self,
+        items: Option<Vec<lsp::TypeHierarchyItem>>,
+        project: Model<Project>,
+        buffer: Model<Buffer>,
+        server_id: LanguageServerId,
+        cx: AsyncAppContext,
+    ) -> Result<Vec<TypeHierarchyItem>> {
+        type_hierarchy_items_from_lsp(items, project, buffer, server_id, cx).await
+    }
+
+    fn to_proto(&self, project_id: u64, buffer: &Buffer) -> proto::GetTypeHierarchySubtypes {
+        proto::GetTypeHierarchySubtypes {
+            project_id,
+            buffer_id: buffer.remote_id().into(),
+            item: serde_json::to_vec(&self.item).unwrap_or_default(),
+            language_server_id: self.server_id.0 as u64,
+        }
+    }
+
+    async fn from_proto(
+        message: proto::GetTypeHierarchySubtypes,
+        _: Model<Project>,
+        _: Model<Buffer>,
+        _: AsyncAppContext,
+    ) -> Result<Self> {
+        Ok(Self {
+            item: serde_json::from_slice(&message.item).context("invalid type hierarchy item")?,
+            server_id: LanguageServerId(message.language_server_id as usize),
+        })
+    }
+
+    fn response_to_proto(
+        response: Vec<TypeHierarchyItem>,
+        project: &mut Project,
+        peer_id: PeerId,
+        _: &clock::Global,
+        cx: &mut AppContext,
+    ) -> proto::TypeHierarchyResponse {
+        proto::TypeHierarchyResponse {
+            items: type_hierarchy_items_to_proto(response, project, peer_id, cx),
+        }
+    }
+
+    async fn response_from_proto(
+        self,
+        message: proto::TypeHierarchyResponse,
+        project: Model<Project>,
+        _: Model<Buffer>,
+        cx: AsyncAppContext,
+    ) -> Result<Vec<TypeHierarchyItem>> {
+        type_hierarchy_items_from_proto(message.items, project, cx).await
+    }
+
+    fn buffer_id_from_proto(message: &proto::GetTypeHierarchySubtypes) -> Result<BufferId> {
+        BufferId::new(message.buffer_id)
+    }
+}
+
+fn supports_call_hierarchy(capabilities: &ServerCapabilities) -> bool {
+    match &capabilities.call_hierarchy_provider {
+        Some(lsp::CallHierarchyServerCapability::Simple(has_support)) => *has_support,
+        Some(lsp::CallHierarchyServerCapability::Options(_)) => true,
+        None => false,
+    }
+}
+
+async fn call_hierarchy_items_from_lsp(
+    items: Vec<lsp::CallHierarchyItem>,
+    project: &Model<Project>,
+    buffer: &Model<Buffer>,
+    server_id: LanguageServerId,
+    cx: &mut AsyncAppContext,
+) -> Result<Vec<CallHierarchyItem>> {
+    let locations = hierarchy_item_locations_from_lsp(
+        items
+            .iter()
+            .map(|item| (item.uri.clone(), item.selection_range))
+            .collect(),
+        project,
+        buffer,
+        server_id,
+        cx,
+    )
+    .await?;
+    Ok(items
+        .into_iter()
+        .zip(locations)
+        .map(|(lsp_item, location)| CallHierarchyItem {
+            location,
+            lsp_item,
+            language_server_id: server_id,
+        })
+        .collect())
+}
+
+/// Converts the calls reported by the language server, `ranges_buffer` being the buffer the call ranges are in,
+/// or `None` if they are in the buffer of the call item.
+async fn call_hierarchy_calls_from_lsp(
+    calls: Vec<(lsp::CallHierarchyItem, Vec<lsp::Range>)>,
+    ranges_buffer: Option<&Model<Buffer>>,
+    project: &Model<Project>,
+    buffer: &Model<Buffer>,
+    server_id: LanguageServerId,
+    cx: &mut AsyncAppContext,
+) -> Result<Vec<CallHierarchyCall>> {
+    let (items, ranges): (Vec<_>, Vec<_>) = calls.into_iter().unzip();
+    let items = call_hierarchy_items_from_lsp(items, project, buffer, server_id, cx).await?;
+    items
+        .into_iter()
+        .zip(ranges)
+        .map(|(item, ranges)| {
+            let ranges_buffer = ranges_buffer.unwrap_or(&item.location.buffer);
+            let ranges = ranges_buffer.update(cx, |ranges_buffer, _| {
+                ranges
+                    .into_iter()
+                    .map(|range| anchor_range_from_lsp(ranges_buffer, range))
+                    .collect()
+            })?;
+            Ok(CallHierarchyCall { item, ranges })
+        })
+        .collect()
+}
+
+fn call_hierarchy_item_to_proto(
+    item: &CallHierarchyItem,
+    project: &mut Project,
+    peer_id: PeerId,
+    cx: &mut AppContext,
+) -> proto::CallHierarchyItem {
+    proto::CallHierarchyItem {
+        location: Some(hierarchy_item_location_to_proto(
+            &item.location,
+            project,
+            peer_id,
+            cx,
+        )),
+        lsp_item: serde_json::to_vec(&item.lsp_item).unwrap_or_default(),
+        language_server_id: item.language_server_id.0 as u64,
+    }
+}
+
+async fn call_hierarchy_item_from_proto(
+    item: proto::CallHierarchyItem,
+    project: &Model<Project>,
+    cx: &mut AsyncAppContext,
+) -> Result<CallHierarchyItem> {
+    Ok(CallHierarchyItem {
+        location: hierarchy_item_location_from_proto(item.location, project, cx).await?,
+        lsp_item: serde_json::from_slice(&item.lsp_item).context("invalid call hierarchy item")?,
+        language_server_id: LanguageServerId(item.language_server_id as usize),
+    })
+}
+
+fn call_hierarchy_calls_to_proto(
+    calls: Vec<CallHierarchyCall>,
+    project: &mut Project,
+    peer_id: PeerId,
+    cx: &mut AppContext,
+) -> proto::CallHierarchyCallsResponse {
+    proto::CallHierarchyCallsResponse {
+        calls: calls
+            .into_iter()
+            .map(|call| proto::CallHierarchyCall {
+                item: Some(call_hierarchy_item_to_proto(
+                    &call.item, project, peer_id, cx,
+                )),
+                ranges: call
+                    .ranges
+                    .iter()
+                    .map(|range| proto::AnchorRange {
+                        start: Some(serialize_anchor(&range.start)),
+                        end: Some(serialize_anchor(&range.end)),
+                    })
+                    .collect(),
+            })
+            .collect(),
+    }
+}
+
+async fn call_hierarchy_calls_from_proto(
+    message: proto::CallHierarchyCallsResponse,
+    ranges_buffer: Option<&Model<Buffer>>,
+    project: &Model<Project>,
+    cx: &mut AsyncAppContext,
+) -> Result<Vec<CallHierarchyCall>> {
+    let mut calls = Vec::new();
+    for call in message.calls {
+        let item = call
+            .item
+            .ok_or_else(|| anyhow!("missing call hierarchy item"))?;
+        let item = call_hierarchy_item_from_proto(item, project, cx).await?;
+        let ranges = call
+            .ranges
+            .into_iter()
+            .map(|range| {
+                let start = range.start.and_then(deserialize_anchor);
+                let end = range.end.and_then(deserialize_anchor);
+                Some(start?..end?)
+            })
+            .collect::<Option<Vec<_>>>()
+            .ok_or_else(|| anyhow!("invalid call range"))?;
+        let anchors = ranges
+            .iter()
+            .flat_map(|range| [range.start, range.end])
+            .collect::<Vec<_>>();
+        ranges_buffer
+            .unwrap_or(&item.location.buffer)
+            .update(cx, |buffer, _| buffer.wait_for_anchors(anchors))?
+            .await?;
+        calls.push(CallHierarchyCall { item, ranges });
+    }
+    Ok(calls)
+}
+
+#[async_trait(?Send)]
+impl LspCommand for PrepareCallHierarchy {
+    type Response = Vec<CallHierarchyItem>;
+    type LspRequest = lsp::request::CallHierarchyPrepare;
+    type ProtoRequest = proto::PrepareCallHierarchy;
+
+    fn check_capabilities(&self, capabilities: &ServerCapabilities) -> bool {
+        supports_call_hierarchy(capabilities)
+    }
+
+    fn to_lsp(
+        &self,
+        path: &Path,
+        _: &Buffer,
+        _: &Arc<LanguageServer>,
+        _: &AppContext,
+    ) -> lsp::CallHierarchyPrepareParams {
+        lsp::CallHierarchyPrepareParams {
+            text_document_position_params: lsp::TextDocumentPositionParams {
+                text_document: lsp::TextDocumentIdentifier {
+                    uri: lsp::Url::from_file_path(path).unwrap(),
+                },
+                position: point_to_lsp(self.position),
+            },
+            work_done_progress_params: Default::default(),
+        }
+    }
+
+    async fn response_from_lsp(
+        self,
+        items: Option<Vec<lsp::CallHierarchyItem>>,
+        project: Model<Project>,
+        buffer: Model<Buffer>,
+        server_id: LanguageServerId,
+        mut cx: AsyncAppContext,
+    ) -> Result<Vec<CallHierarchyItem>> {
+        call_hierarchy_items_from_lsp(
+            items.unwrap_or_default(),
+            &project,
+            &buffer,
+            server_id,
+            &mut cx,
+        )
+        .await
+    }
+
+    fn to_proto(&self, project_id: u64, buffer: &Buffer) -> proto::PrepareCallHierarchy {
+        proto::PrepareCallHierarchy {
+            project_id,
+            buffer_id: buffer.remote_id().into(),
+            position: Some(language::proto::serialize_anchor(
+                &buffer.anchor_before(self.position),
+            )),
+            version: serialize_version(&buffer.version()),
+        }
+    }
+
+    async fn from_proto(
+        message: proto::PrepareCallHierarchy,
+        _: Model<Project>,
+        buffer: Model<Buffer>,
+        mut cx: AsyncAppContext,
+    ) -> Result<Self> {
+        let position = message
+            .position
+            .and_then(deserialize_anchor)
+            .ok_or_else(|| anyhow!("invalid position"))?;
+        buffer
+            .update(&mut cx, |buffer, _| {
+                buffer.wait_for_version(deserialize_version(&message.version))
+            })?
+            .await?;
+        Ok(Self {
+            position: buffer.update(&mut cx, |buffer, _| position.to_point_utf16(buffer))?,
+        })
+    }
+
+    fn response_to_proto(
+        response: Vec<CallHierarchyItem>,
+        project: &mut Project,
+        peer_id: PeerId,
+        _: &clock::Global,
+        cx: &mut AppContext,
+    ) -> proto::PrepareCallHierarchyResponse {
+        proto::PrepareCallHierarchyResponse {
+            items: response
+                .iter()
+                .map(|item| call_hierarchy_item_to_proto(item, project, peer_id, cx))
+                .collect(),
+        }
+    }
+
+    async fn response_from_proto(
+        self,
+        message: proto::PrepareCallHierarchyResponse,
+        project: Model<Project>,
+        _: Model<Buffer>,
+        mut cx: AsyncAppContext,
+    ) -> Result<Vec<CallHierarchyItem>> {
+        let mut items = Vec::new();
+        for item in message.items {
+            items.push(call_hierarchy_item_from_proto(item, &project, &mut cx).await?);
+        }
+        Ok(items)
+    }
+
+    fn buffer_id_from_proto(message: &proto::PrepareCallHierarchy) -> Result<BufferId> {
+        BufferId::new(message.buffer_id)
+    }
+}
+
+#[async_trait(?Send)]
+impl LspCommand for GetIncomingCalls {
+    type Response = Vec<CallHierarchyCall>;
+    type LspRequest = lsp::request::CallHierarchyIncomingCalls;
+    type ProtoRequest = proto::GetIncomingCalls;
+
+    fn check_capabilities(&self, capabilities: &ServerCapabilities) -> bool {
+        supports_call_hierarchy(capabilities)
+    }
+
+    fn language_server_to_query(&self) -> LanguageServerToQuery {
+        LanguageServerToQuery::Other(self.server_id)
+    }
+
+    fn to_lsp(
+        &self,
+        _: &Path,
+        _: &Buffer,
+        _: &Arc<LanguageServer>,
+        _: &AppContext,
+    ) -> lsp::CallHierarchyIncomingCallsParams {
+        lsp::CallHierarchyIncomingCallsParams {
+            item: self.item.clone(),
+            work_done_progress_params: Default::default(),
+            partial_result_params: Default::default(),
+        }
+    }
+
+    async fn response_from_lsp(
+        self,
+        calls: Option<Vec<lsp::CallHierarchyIncomingCall>>,
+        project: Model<Project>,
+        buffer: Model<Buffer>,
+        server_id: LanguageServerId,
+        mut cx: AsyncAppContext,
+    ) -> Result<Vec<CallHierarchyCall>> {
+        let calls = calls
+            .unwrap_or_default()
+            .into_iter()
+            .map(|call| (call.from, call.from_ranges))
+            .collect();
+        // The ranges of the incoming calls are in the callers.
+        call_hierarchy_calls_from_lsp(calls, None, &project, &buffer, server_id, &mut cx).await
+    }
+
+    fn to_proto(&self, project_id: u64, buffer: &Buffer) -> proto::GetIncomingCalls {
+        proto::GetIncomingCalls {
+            project_id,
+            buffer_id: buffer.remote_id().into(),
+            item: serde_json::to_vec(&self.item).unwrap_or_default(),
+            language_server_id: self.server_id.0 as u64,
+        }
+    }
+
+    async fn from_proto(
+        message: proto::GetIncomingCalls,
+        _: Model<Project>,
+        _: Model<Buffer>,
+        _: AsyncAppContext,
+    ) -> Result<Self> {
+        Ok(Self {
+            item: serde_json::from_slice(&message.item).context("invalid call hierarchy item")?,
+            server_id: LanguageServerId(message.language_server_id as usize),
+        })
+    }
+
+    fn response_to_proto(
+        response: Vec<CallHierarchyCall>,
+        project: &mut Project,
+        peer_id: PeerId,
+        _: &clock::Global,
+        cx: &mut AppContext,
+    ) -> proto::CallHierarchyCallsResponse {
+        call_hierarchy_calls_to_proto(response, project, peer_id, cx)
+    }
+
+    async fn response_from_proto(
+        self,
+        message: proto::CallHierarchyCallsResponse,
+        project: Model<Project>,
+        _: Model<Buffer>,
+        mut cx: AsyncAppContext,
+    ) -> Result<Vec<CallHierarchyCall>> {
+        call_hierarchy_calls_from_proto(message, None, &project, &mut cx).await
+    }
+
+    fn buffer_id_from_proto(message: &proto::GetIncomingCalls) -> Result<BufferId> {
+        BufferId::new(message.buffer_id)
+    }
+}
+
+#[async_trait(?Send)]
+impl LspCommand for GetOutgoingCalls {
+    type Response = Vec<CallHierarchyCall>;
+    type LspRequest = lsp::request::CallHierarchyOutgoingCalls;
+    type ProtoRequest = proto::GetOutgoingCalls;
+
+    fn check_capabilities(&self, capabilities: &ServerCapabilities) -> bool {
+        supports_call_hierarchy(capabilities)
+    }
+
+    fn language_server_to_query(&self) -> LanguageServerToQuery {
+        LanguageServerToQuery::Other(self.server_id)
+    }
+
+    fn to_lsp(
+        &self,
+        _: &Path,
+        _: &Buffer,
+        _: &Arc<LanguageServer>,
+        _: &AppContext,
+    ) -> lsp::CallHierarchyOutgoingCallsParams {
+        lsp::CallHierarchyOutgoingCallsParams {
+            item: self.item.clone(),
+            work_done_progress_params: Default::default(),
+            partial_result_params: Default::default(),
+        }
+    }
+
+    async fn response_from_lsp(
+        self,
+        calls: Option<Vec<lsp::CallHierarchyOutgoingCall>>,
+        project: Model<Project>,
+        buffer: Model<Buffer>,
+        server_id: LanguageServerId,
+        mut cx: AsyncAppContext,
+    ) -> Result<Vec<CallHierarchyCall>> {
+        let calls = calls
+            .unwrap_or_default()
+            .into_iter()
+            .map(|call| (call.to, call.from_ranges))
+            .collect();
+        // The ranges of the outgoing calls are in the caller, which is the item queried.
+        call_hierarchy_calls_from_lsp(calls, Some(&buffer), &project, &buffer, server_id, &mut cx)
+            .await
+    }
+
+    fn to_proto(&self, project_id: u64, buffer: &Buffer) -> proto::GetOutgoingCalls {
+        proto::GetOutgoingCalls {
+            project_id,
+            buffer_id: buffer.remote_id().into(),
+            item: serde_json::to_vec(&self.item).unwrap_or_default(),
+            language_server_id: self.server_id.0 as u64,
+        }
+    }
+
+    async fn from_proto(
+        message: proto::GetOutgoingCalls,
+        _: Model<Project>,
+        _: Model<Buffer>,
+        _: AsyncAppContext,
+    ) -> Result<Self> {
+        Ok(Self {
+            item: serde_json::from_slice(&message.item).context("invalid call hierarchy item")?,
+            server_id: LanguageServerId(message.language_server_id as usize),
+        })
+    }
+
+    fn response_to_proto(
+        response: Vec<CallHierarchyCall>,
+        project: &mut Project,
+        peer_id: PeerId,
+        _: &clock::Global,
+        cx: &mut AppContext,
+    ) -> proto::CallHierarchyCallsResponse {
+        call_hierarchy_calls_to_proto(response, project, peer_id, cx)
+    }
+
+    async fn response_from_proto(
+        self,
+        message: proto::CallHierarchyCallsResponse,
+        project: Model<Project>,
+        buffer: Model<Buffer>,
+        mut cx: AsyncAppContext,
+    ) -> Result<Vec<CallHierarchyCall>> {
+        call_hierarchy_calls_from_proto(message, Some(&buffer), &project, &mut cx).await
+    }
+
+    fn buffer_id_from_proto(message: &proto::GetOutgoingCalls) -> Result<BufferId> {
+        BufferId::new(message.buffer_id)
+    }
+}
+
+pub(crate) struct GetInlineValues {
+    pub range: Range<Anchor>,
+    pub frame_id: i32,
+    pub stopped_location: Range<Anchor>,
+}
+
+#[async_trait(?Send)]
+impl LspCommand for GetInlineValues {
+    type Response = Vec<InlineValue>;
+    type LspRequest = lsp::request::InlineValueRequest;
+    type ProtoRequest = proto::GetInlineValues;
+
+    fn check_capabilities(&self, capabilities: &ServerCapabilities) -> bool {
+        match &capabilities.inline_value_provider {
+            Some(OneOf::Left(enabled)) => *enabled,
+            Some(OneOf::Right(_)) => true,
+            None => false,
+        }
+    }
+
+    fn to_lsp(
+        &self,
+        path: &Path,
+        buffer: &Buffer,
+        _: &Arc<LanguageServer>,
+        _: &AppContext,
+    ) -> lsp::InlineValueParams {
+        lsp::InlineValueParams {
+            work_done_progress_params: Default::default(),
+            text_document: lsp::TextDocumentIdentifier {
+                uri: lsp::Url::from_file_path(path).unwrap(),
+            },
+            range: range_to_lsp(self.range.to_point_utf16(buffer)),
+            context: lsp::InlineValueContext {
+                frame_id: self.frame_id,
+                stopped_location: range_to_lsp(self.stopped_location.to_point_utf16(buffer)),
+            },
+        }
+    }
+
+    async fn response_from_lsp(
+        self,
+        message: Option<Vec<lsp::InlineValue>>,
+        _: Model<Project>,
+        buffer: Model<Buffer>,
+        _: LanguageServerId,
+        cx: AsyncAppContext,
+    ) -> Result<Vec<InlineValue>> {
+        buffer.read_with(&cx, |buffer, _| {
+            let anchor_range = |range: lsp::Range| {
+                let start = buffer.clip_point_utf16(point_from_lsp(range.start), Bias::Left);
+                let end = buffer.clip_point_utf16(point_from_lsp(range.end), Bias::Left);
+                buffer.anchor_after(start)..buffer.anchor_before(end)
+            };
+            message
+                .unwrap_or_default()
+                .into_iter()
+                .map(|value| match value {
+                    lsp::InlineValue::Text(value) => InlineValue {
+                        range: anchor_range(value.range),
+                        kind: InlineValueKind::Text(value.text),
+                    },
+                    lsp::InlineValue::VariableLookup(value) => InlineValue {
+                        range: anchor_range(value.range),
+                        kind: InlineValueKind::VariableLookup {
+                            variable_name: value.variable_name,
+                            case_sensitive: value.case_sensitive_lookup,
+                        },
+                    },
+                    lsp::InlineValue::EvaluatableExpression(value) => InlineValue {
+                        range: anchor_range(value.range),
+                        kind: InlineValueKind::EvaluatableExpression {
+                            expression: value.expression,
+                        },
+                    },
+                })
+                .collect()
+        })
+    }
+
+    fn to_proto(&self, project_id: u64, buffer: &Buffer) -> proto::GetInlineValues {
+        proto::GetInlineValues {
+            project_id,
+            buffer_id: buffer.remote_id().into(),
+            start: Some(serialize_anchor(&self.range.start)),
+            end: Some(serialize_anchor(&self.range.end)),
+            frame_id: self.frame_id,
+            stopped_location_start: Some(serialize_anchor(&self.stopped_location.start)),
+            stopped_location_end: Some(serialize_anchor(&self.stopped_location.end)),
+            version: serialize_version(&buffer.version()),
+        }
+    }
+
+    async fn from_proto(
+        message: proto::GetInlineValues,
+        _: Model<Project>,
+        buffer: Model<Buffer>,
+        mut cx: AsyncAppContext,
+    ) -> Result<Self> {
+        let anchor = |anchor: Option<proto::Anchor>| {
+            anchor
+                .and_then(deserialize_anchor)
+                .ok_or_else(|| anyhow!("invalid anchor"))
+        };
+        let range = anchor(message.start)?..anchor(message.end)?;
+        let stopped_location =
+            anchor(message.stopped_location_start)?..anchor(message.stopped_location_end)?;
+        buffer
+            .update(&mut cx, |buffer, _| {
+                buffer.wait_for_version(deserialize_version(&message.version))
+            })?
+            .await?;
+        Ok(Self {
+            range,
+            frame_id: message.frame_id,
+            stopped_location,
+        })
+    }
+
+    fn response_to_proto(
+        response: Vec<InlineValue>,
+        _: &mut Project,
+        _: PeerId,
+        buffer_version: &clock::Global,
+        _: &mut AppContext,
+    ) -> proto::GetInlineValuesResponse {
+        proto::GetInlineValuesResponse {
+            values: response.iter().map(InlineValue::to_proto).collect(),
+            version: serialize_version(buffer_version),
+        }
+    }
+
+    async fn response_from_proto(
+        self,
+        message: proto::GetInlineValuesResponse,
+        _: Model<Project>,
+        buffer: Model<Buffer>,
+        mut cx: AsyncAppContext,
+    ) -> Result<Vec<InlineValue>> {
+        buffer
+            .update(&mut cx, |buffer, _| {
+                buffer.wait_for_version(deserialize_version(&message.version))
+            })?
+            .await?;
+        message
+            .values
+            .into_iter()
+            .map(InlineValue::from_proto)
+            .collect()
+    }
+
+    fn buffer_id_from_proto(message: &proto::GetInlineValues) -> Result<BufferId> {
+        BufferId::new(message.buffer_id)
+    }
+}
+
+pub(crate) struct GetFoldingRanges;
+
+#[async_trait(?Send)]
+impl LspCommand for GetFoldingRanges {
+    type Response = Vec<FoldingRange>;
+    type LspRequest = lsp::request::FoldingRangeRequest;
+    type ProtoRequest = proto::GetFoldingRanges;
+
+    fn check_capabilities(&self, capabilities: &ServerCapabilities) -> bool {
+        match &capabilities.folding_range_provider {
+            Some(lsp::FoldingRangeProviderCapability::Simple(enabled)) => *enabled,
+            Some(_) => true,
+            None => false,
+        }
+    }
+
+    fn to_lsp(
+        &self,
+        path: &Path,
+        _: &Buffer,
+        _: &Arc<LanguageServer>,
+        _: &AppContext,
+    ) -> lsp::FoldingRangeParams {
+        lsp::FoldingRangeParams {
+            text_document: lsp::TextDocumentIdentifier {
+                uri: lsp::Url::from_file_path(path).unwrap(),
+            },
+            work_done_progress_params: Default::default(),
+            partial_result_params: Default::default(),
+        }
+    }
+
+    async fn response_from_lsp(
+        self,
+        message: Option<Vec<lsp::FoldingRange>>,
+        _: Model<Project>,
+        buffer: Model<Buffer>,
+        _: LanguageServerId,
+        cx: AsyncAppContext,
+    ) -> Result<Vec<FoldingRange>> {
+        let mut ranges = message.unwrap_or_default();
+        ranges.sort_by_key(|range| (range.start_line, Reverse(range.end_line)));
+        buffer.read_with(&cx, |buffer, _| {
+            let max_row = buffer.max_point().row;
+            ranges
+                .into_iter()
+                // The first line of a range stays visible, so ranges of a single line don't fold anything.
+                .filter(|range| range.start_line < range.end_line && range.start_line <= max_row)
+                .map(|range| {
+                    let start_row = range.start_line;
+                    let end_row = range.end_line.min(max_row);
+                    let start = Point::new(start_row, buffer.line_len(start_row));
+                    let end = Point::new(end_row, buffer.line_len(end_row));
+                    let kind = range.kind.map(|kind| match kind {
+                        lsp::FoldingRangeKind::Comment => FoldingRangeKind::Comment,
+                        lsp::FoldingRangeKind::Imports => FoldingRangeKind::Imports,
+                        lsp::FoldingRangeKind::Region => FoldingRangeKind::Region,
+                    });
+                    FoldingRange {
+                        range: buffer.anchor_after(start)..buffer.anchor_before(end),
+                        kind,
+                    }
+                })
+                .collect()
+        })
+    }
+
+    fn to_proto(&self, project_id: u64, buffer: &Buffer) -> proto::GetFoldingRanges {
+        proto::GetFoldingRanges {
+            project_id,
+            buffer_id: buffer.remote_id().into(),
+            version: serialize_version(&buffer.version()),
+        }
+    }
+
+    async fn from_proto(
+        message: proto::GetFoldingRanges,
+        _: Model<Project>,
+        buffer: Model<Buffer>,
+        mut cx: AsyncAppContext,
+    ) -> Result<Self> {
+        buffer
+            .update(&mut cx, |buffer, _| {
+                buffer.wait_for_version(deserialize_version(&message.version))
+            })?
+            .await?;
+        Ok(Self)
+    }
+
+    fn response_to_proto(
+        response: Vec<FoldingRange>,
+        _: &mut Project,
+        _: PeerId,
+        buffer_version: &clock::Global,
+        _: &mut AppContext,
+    ) -> proto::GetFoldingRangesResponse {
+        proto::GetFoldingRangesResponse {
+            ranges: response.iter().map(FoldingRange::to_proto).collect(),
+            version: serialize_version(buffer_version),
+        }
+    }
+
+    async fn response_from_proto(
+        self,
+        message: proto::GetFoldingRangesResponse,
+        _: Model<Project>,
+        buffer: Model<Buffer>,
+        mut cx: AsyncAppContext,
+    ) -> Result<Vec<FoldingRange>> {
+        buffer
+            .update(&mut cx, |buffer, _| {
+                buffer.wait_for_version(deserialize_version(&message.version))
+            })?
+            .await?;
+        message
+            .ranges
+            .into_iter()
+            .map(FoldingRange::from_proto)
+            .collect()
+    }
+
+    fn buffer_id_from_proto(message: &proto::GetFoldingRanges) -> Result<BufferId> {
+        BufferId::new(message.buffer_id)
+    }
+}
+
+pub(crate) struct GetDocumentSymbols;
+
+#[async_trait(?Send)]
+impl LspCommand for GetDocumentSymbols {
+    type Response = Vec<DocumentSymbol>;
+    type LspRequest = lsp::request::DocumentSymbolRequest;
+    type ProtoRequest = proto::GetDocumentSymbols;
+
+    fn check_capabilities(&self, capabilities: &ServerCapabilities) -> bool {
+        match &capabilities.document_symbol_provider {
+            Some(OneOf::Left(enabled)) => *enabled,
+            Some(OneOf::Right(_)) => true,
+            None => false,
+        }
+    }
+
+    fn to_lsp(
+        &self,
+        path: &Path,
+        _: &Buffer,
+        _: &Arc<LanguageServer>,
+        _: &AppContext,
+    ) -> lsp::DocumentSymbolParams {
+        lsp::DocumentSymbolParams {
+            text_document: lsp::TextDocumentIdentifier {
+                uri: lsp::Url::from_file_path(path).unwrap(),
+            },
+            work_done_progress_params: Default::default(),
+            partial_result_params: Default::default(),
+        }
+    }
+
+    async fn response_from_lsp(
+        self,
+        message: Option<lsp::DocumentSymbolResponse>,
+        _: Model<Project>,
+        buffer: Model<Buffer>,
+        _: LanguageServerId,
+        cx: AsyncAppContext,
+    ) -> Result<Vec<DocumentSymbol>> {
+        buffer.read_with(&cx, |buffer, _| match message {
+            Some(lsp::DocumentSymbolResponse::Nested(symbols)) => symbols
+                .into_iter()
+                .map(|symbol| document_symbol_from_lsp(symbol, buffer))
+                .sorted_by(|a, b| a.range.start.cmp(&b.range.start, buffer))
+                .collect(),
+            // Flat symbols are nested by their ranges.
+            Some(lsp::DocumentSymbolResponse::Flat(symbols)) => {
+                let symbols = symbols
+                    .into_iter()
+                    .map(|symbol| {
+                        #[allow(deprecated)]
+                        let deprecated = symbol.deprecated.unwrap_or(false)
+                            || symbol
+                                .tags
+                                .map_or(false, |tags| tags.contains(&lsp::SymbolTag::DEPRECATED));
+                        let range = anchor_range_from_lsp(buffer, symbol.location.range);
+                        DocumentSymbol {
+                            name: symbol.name,
+                            detail: None,
+                            kind: symbol.kind,
+                            deprecated,
+                            selection_range: range.clone(),
+                            range,
+                            children: Vec::new(),
+                        }
+                    })
+                    .sorted_by(|a, b| {
+                        a.range
+                            .start
+                            .cmp(&b.range.start, buffer)
+                            .then_with(|| b.range.end.cmp(&a.range.end, buffer))
+                    });
+
+                let mut roots = Vec::new();
+                let mut stack = Vec::<DocumentSymbol>::new();
+                let mut pop = |stack: &mut Vec<DocumentSymbol>| {
+                    if let Some(symbol) = stack.pop() {
+                        match stack.last_mut() {
+                            Some(parent) => parent.children.push(symbol),
+                            None => roots.push(symbol),
+                        }
+                    }
+                };
+                for symbol in symbols {
+                    while stack.last().map_or(false, |parent| {
+                        parent.range.end.cmp(&symbol.range.start, buffer).is_le()
+                    }) {
+                        pop(&mut stack);
+                    }
+                    stack.push(symbol);
+                }
+                while !stack.is_empty() {
+                    pop(&mut stack);
+                }
+                roots
+            }
+            None => Vec::new(),
+        })
+    }
+
+    fn to_proto(&self, project_id: u64, buffer: &Buffer) -> proto::GetDocumentSymbols {
+        proto::GetDocumentSymbols {
+            project_id,
+            buffer_id: buffer.remote_id().into(),
+            version: serialize_version(&buffer.version()),
+        }
+    }
+
+    async fn from_proto(
+        message: proto::GetDocumentSymbols,
+        _: Model<Project>,
+        buffer: Model<Buffer>,
+        mut cx: AsyncAppContext,
+    ) -> Result<Self> {
+        buffer
+            .update(&mut cx, |buffer, _| {
+                buffer.wait_for_version(deserialize_version(&message.version))
+            })?
+            .await?;
+        Ok(Self)
+    }
+
+    fn response_to_proto(
+        response: Vec<DocumentSymbol>,
+        _: &mut Project,
+        _: PeerId,
+        buffer_version: &clock::Global,
+        _: &mut AppContext,
+    ) -> proto::GetDocumentSymbolsResponse {
+        proto::GetDocumentSymbolsResponse {
+            symbols: response.iter().map(DocumentSymbol::to_proto).collect(),
+            version: serialize_version(buffer_version),
+        }
+    }
+
+    async fn response_from_proto(
+        self,
+        message: proto::GetDocumentSymbolsResponse,
+        _: Model<Project>,
+        buffer: Model<Buffer>,
+        mut cx: AsyncAppContext,
+    ) -> Result<Vec<DocumentSymbol>> {
+        buffer
+            .update(&mut cx, |buffer, _| {
+                buffer.wait_for_version(deserialize_version(&message.version))
+            })?
+            .await?;
+        message
+            .symbols
+            .into_iter()
+            .map(DocumentSymbol::from_proto)
+            .collect()
+    }
+
+    fn buffer_id_from_proto(message: &proto::GetDocumentSymbols) -> Result<BufferId> {
+        BufferId::new(message.buffer_id)
+    }
+}
+
+fn document_symbol_from_lsp(symbol: lsp::DocumentSymbol, buffer: &Buffer) -> DocumentSymbol {
+    #[allow(deprecated)]
+    let deprecated = symbol.deprecated.unwrap_or(false)
+        || symbol
+            .tags
+            .map_or(false, |tags| tags.contains(&lsp::SymbolTag::DEPRECATED));
+    DocumentSymbol {
+        name: symbol.name,
+        detail: symbol.detail.filter(|detail| !detail.is_empty()),
+        kind: symbol.kind,
+        deprecated,
+        range: anchor_range_from_lsp(buffer, symbol.range),
+        selection_range: anchor_range_from_lsp(buffer, symbol.selection_range),
+        children: symbol
+            .children
+            .unwrap_or_default()
+            .into_iter()
+            .map(|child| document_symbol_from_lsp(child, buffer))
+            .sorted_by(|a, b| a.range.start.cmp(&b.range.start, buffer))
+            .collect(),
+    }
+}
+
+#[derive(Clone)]
+pub(crate) struct GetSignatureHelp {
+    pub position: PointUtf16,
+    pub trigger: SignatureHelpTrigger,
+}
+
+impl GetSignatureHelp {
+    pub fn supports_signature_help(capabilities: &ServerCapabilities) -> bool {
+        capabilities.signature_help_provider.is_some()
+    }
+}
+
+#[async_trait(?Send)]
+impl LspCommand for GetSignatureHelp {
+    type Response = Option<SignatureHelp>;
+    type LspRequest = lsp::request::SignatureHelpRequest;
+    type ProtoRequest = proto::GetSignatureHelp;
+
+    fn check_capabilities(&self, capabilities: &ServerCapabilities) -> bool {
+        Self::supports_signature_help(capabilities)
+    }
+
+    fn to_lsp(
+        &self,
+        path: &Path,
+        _: &Buffer,
+        _: &Arc<LanguageServer>,
+        _: &AppContext,
+    ) -> lsp::SignatureHelpParams {
+        let trigger_kind = if self.trigger.character.is_some() {
+            lsp::SignatureHelpTriggerKind::TRIGGER_CHARACTER
+        } else if self.trigger.is_retrigger {
+            lsp::SignatureHelpTriggerKind::CONTENT_CHANGE
+        } else {
+            lsp::SignatureHelpTriggerKind::INVOKED
+        };
+        lsp::SignatureHelpParams {
+            context: Some(lsp::SignatureHelpContext {
+                trigger_kind,
+                trigger_character: self.trigger.character.clone(),
+                is_retrigger: self.trigger.is_retrigger,
+                active_signature_help: None,
+            }),
+            text_document_position_params: lsp::TextDocumentPositionParams {
+                text_document: lsp::TextDocumentIdentifier {
+                    uri: lsp::Url::from_file_path(path).unwrap(),
+                },
+                position: point_to_lsp(self.position),
+            },
+            work_done_progress_params: Default::default(),
+        }
+    }
+
+    async fn response_from_lsp(
+        self,
+        message: Option<lsp::SignatureHelp>,
+        _: Model<Project>,
+        _: Model<Buffer>,
+        _: LanguageServerId,
+        _: AsyncAppContext,
+    ) -> Result<Option<SignatureHelp>> {
+        Ok(message.and_then(SignatureHelp::from_lsp))
+    }
+
+    fn to_proto(&self, project_id: u64, buffer: &Buffer) -> proto::GetSignatureHelp {
+        proto::GetSignatureHelp {
+            project_id,
+            buffer_id: buffer.remote_id().into(),
+            position: Some(serialize_anchor(&buffer.anchor_before(self.position))),
+            version: serialize_version(&buffer.version()),
+            trigger_character: self.trigger.character.clone(),
+            is_retrigger: self.trigger.is_retrigger,
+        }
+    }
+
+    async fn from_proto(
+        message: proto::GetSignatureHelp,
+        _: Model<Project>,
+        buffer: Model<Buffer>,
+        mut cx: AsyncAppContext,
+    ) -> Result<Self> {
+        let position = message
+            .position
+            .and_then(deserialize_anchor)
+            .ok_or_else(|| anyhow!("invalid position"))?;
+        buffer
+            .update(&mut cx, |buffer, _| {
+                buffer.wait_for_version(deserialize_version(&message.version))
+            })?
+            .await?;
+        Ok(Self {
+            position: buffer.update(&mut cx, |buffer, _| position.to_point_utf16(buffer))?,
+            trigger: SignatureHelpTrigger {
+                character: message.trigger_character,
+                is_retrigger: message.is_retrigger,
+            },
+        })
+    }
+
+    fn response_to_proto(
+        response: Option<SignatureHelp>,
+        _: &mut Project,
+        _: PeerId,
+        _: &clock::Global,
+        _: &mut AppContext,
+    ) -> proto::GetSignatureHelpResponse {
+        proto::GetSignatureHelpResponse {
+            signature_help: response.as_ref().map(SignatureHelp::to_proto),
+        }
+    }
+
+    async fn response_from_proto(
+        self,
+        message: proto::GetSignatureHelpResponse,
+        _: Model<Project>,
+        _: Model<Buffer>,
+        _: AsyncAppContext,
+    ) -> Result<Option<SignatureHelp>> {
+        Ok(message.signature_help.and_then(SignatureHelp::from_proto))
+    }
+
+    fn buffer_id_from_proto(message: &proto::GetSignatureHelp) -> Result<BufferId> {
+        BufferId::new(message.buffer_id)
+    }
+}
@@ -1,15 +1,31 @@
-use std::{path::Path, sync::Arc};
+use std::{
+    ops::Range,
+    path::{Path, PathBuf},
+    sync::Arc,
+};
 
 use anyhow::{Context, Result};
 use async_trait::async_trait;
+use collections::HashMap;
 use gpui::{AppContext, AsyncAppContext, Model};
-use language::{point_to_lsp, proto::deserialize_anchor, Buffer};
+use language::{
+    point_from_lsp, point_to_lsp,
+    proto::{deserialize_anchor, serialize_anchor},
+    Buffer,
+};
 use lsp::{LanguageServer, LanguageServerId};
 use rpc::proto::{self, PeerId};
 use serde::{Deserialize, Serialize};
-use text::{BufferId, PointUtf16, ToPointUtf16};
+use task::TaskTemplate;
+use text::{Bias, BufferId, PointUtf16, ToPointUtf16};
 
-use crate::{lsp_command::LspCommand, Project};
+use crate::{
+    deserialize_task_template,
+    lsp_command::{
+        location_links_from_lsp, location_links_from_proto, location_links_to_proto, LspCommand,
+    },
+    serialize_task_template, LocationLink, Project,
+};
 
 pub enum LspExpandMacro {}
 
@@ -135,3 +151,1032 @@ impl LspCommand for ExpandMacro {
         BufferId::new(message.buffer_id)
     }
 }
+
+pub enum LspViewHir {}
+
+impl lsp::request::Request for LspViewHir {
+    type Params = lsp::TextDocumentPositionParams;
+    type Result = String;
+    const METHOD: &'static str = "rust-analyzer/viewHir";
+}
+
+pub struct ViewHir {
+    pub position: PointUtf16,
+}
+
+#[async_trait(?Send)]
+impl LspCommand for ViewHir {
+    type Response = String;
+    type LspRequest = LspViewHir;
+    type ProtoRequest = proto::LspExtViewHir;
+
+    fn to_lsp(
+        &self,
+        path: &Path,
+        _: &Buffer,
+        _: &Arc<LanguageServer>,
+        _: &AppContext,
+    ) -> lsp::TextDocumentPositionParams {
+        lsp::TextDocumentPositionParams {
+            text_document: lsp::TextDocumentIdentifier {
+                uri: lsp::Url::from_file_path(path).unwrap(),
+            },
+            position: point_to_lsp(self.position),
+        }
+    }
+
+    async fn response_from_lsp(
+        self,
+        message: String,
+        _: Model<Project>,
+        _: Model<Buffer>,
+        _: LanguageServerId,
+        _: AsyncAppContext,
+    ) -> anyhow::Result<String> {
+        Ok(message)
+    }
+
+    fn to_proto(&self, project_id: u64, buffer: &Buffer) -> proto::LspExtViewHir {
+        proto::LspExtViewHir {
+            project_id,
+            buffer_id: buffer.remote_id().into(),
+            position: Some(language::proto::serialize_anchor(
+                &buffer.anchor_before(self.position),
+            )),
+        }
+    }
+
+    async fn from_proto(
+        message: Self::ProtoRequest,
+        _: Model<Project>,
+        buffer: Model<Buffer>,
+        mut cx: AsyncAppContext,
+    ) -> anyhow::Result<Self> {
+        let position = message
+            .position
+            .and_then(deserialize_anchor)
+            .context("invalid position")?;
+        Ok(Self {
+            position: buffer.update(&mut cx, |buffer, _| position.to_point_utf16(buffer))?,
+        })
+    }
+
+    fn response_to_proto(
+        response: String,
+        _: &mut Project,
+        _: PeerId,
+        _: &clock::Global,
+        _: &mut AppContext,
+    ) -> proto::LspExtViewHirResponse {
+        proto::LspExtViewHirResponse { hir: response }
+    }
+
+    async fn response_from_proto(
+        self,
+        message: proto::LspExtViewHirResponse,
+        _: Model<Project>,
+        _: Model<Buffer>,
+        _: AsyncAppContext,
+    ) -> anyhow::Result<String> {
+        Ok(message.hir)
+    }
+
+    fn buffer_id_from_proto(message: &proto::LspExtViewHir) -> Result<BufferId> {
+        BufferId::new(message.buffer_id)
+    }
+}
+
+pub enum LspViewMir {}
+
+impl lsp::request::Request for LspViewMir {
+    type Params = lsp::TextDocumentPositionParams;
+    type Result = String;
+    const METHOD: &'static str = "rust-analyzer/viewMir";
+}
+
+pub struct ViewMir {
+    pub position: PointUtf16,
+}
+
+#[async_trait(?Send)]
+impl LspCommand for ViewMir {
+    type Response = String;
+    type LspRequest = LspViewMir;
+    type ProtoRequest = proto::LspExtViewMir;
+
+    fn to_lsp(
+        &self,
+        path: &Path,
+        _: &Buffer,
+        _: &Arc<LanguageServer>,
+        _: &AppContext,
+    ) -> lsp::TextDocumentPositionParams {
+        lsp::TextDocumentPositionParams {
+            text_document: lsp::TextDocumentIdentifier {
+                uri: lsp::Url::from_file_path(path).unwrap(),
+            },
+            position: point_to_lsp(self.position),
+        }
+    }
+
+    async fn response_from_lsp(
+        self,
+        message: String,
+        _: Model<Project>,
+        _: Model<Buffer>,
+        _: LanguageServerId,
+        _: AsyncAppContext,
+    ) -> anyhow::Result<String> {
+        Ok(message)
+    }
+
+    fn to_proto(&self, project_id: u64, buffer: &Buffer) -> proto::LspExtViewMir {
+        proto::LspExtViewMir {
+            project_id,
+            buffer_id: buffer.remote_id().into(),
+            position: Some(language::proto::serialize_anchor(
+                &buffer.anchor_before(self.position),
+            )),
+        }
+    }
+
+    async fn from_proto(
+        message: Self::ProtoRequest,
+        _: Model<Project>,
+        buffer: Model<Buffer>,
+        mut cx: AsyncAppContext,
+    ) -> anyhow::Result<Self> {
+        let position = message
+            .position
+            .and_then(deserialize_anchor)
+            .context("invalid position")?;
+        Ok(Self {
+            position: buffer.update(&mut cx, |buffer, _| position.to_point_utf16(buffer))?,
+        })
+    }
+
+    fn response_to_proto(
+        response: String,
+        _: &mut Project,
+        _: PeerId,
+        _: &clock::Global,
+        _: &mut AppContext,
+    ) -> proto::LspExtViewMirResponse {
+        proto::LspExtViewMirResponse { mir: response }
+    }
+
+    async fn response_from_proto(
+        self,
+        message: proto::LspExtViewMirResponse,
+        _: Model<Project>,
+        _: Model<Buffer>,
+        _: AsyncAppContext,
+    ) -> anyhow::Result<String> {
+        Ok(message.mir)
+    }
+
+    fn buffer_id_from_proto(message: &proto::LspExtViewMir) -> Result<BufferId> {
+        BufferId::new(message.buffer_id)
+    }
+}
+
+pub enum LspRelatedTests {}
+
+impl lsp::request::Request for LspRelatedTests {
+    type Params = lsp::TextDocumentPositionParams;
+    type Result = Vec<TestInfo>;
+    const METHOD: &'static str = "rust-analyzer/relatedTests";
+}
+
+#[derive(Deserialize, Serialize, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct TestInfo {
+    pub runnable: Runnable,
+}
+
+#[derive(Deserialize, Serialize, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct Runnable {
+    pub label: String,
+    #[serde(default)]
+    pub location: Option<lsp::LocationLink>,
+    #[serde(flatten)]
+    pub args: RunnableArgs,
+}
+
+#[derive(Deserialize, Serialize, Debug)]
+#[serde(tag = "kind", content = "args", rename_all = "camelCase")]
+pub enum RunnableArgs {
+    Cargo(CargoRunnableArgs),
+    Shell(ShellRunnableArgs),
+}
+
+#[derive(Deserialize, Serialize, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct CargoRunnableArgs {
+    #[serde(default)]
+    pub environment: HashMap<String, String>,
+    #[serde(default)]
+    pub cwd: Option<PathBuf>,
+    #[serde(default)]
+    pub workspace_root: Option<PathBuf>,
+    #[serde(default)]
+    pub override_cargo: Option<String>,
+    #[serde(default)]
+    pub cargo_args: Vec<String>,
+    #[serde(default)]
+    pub cargo_extra_args: Vec<String>,
+    #[serde(default)]
+    pub executable_args: Vec<String>,
+}
+
+#[derive(Deserialize, Serialize, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct ShellRunnableArgs {
+    #[serde(default)]
+    pub environment: HashMap<String, String>,
+    pub cwd: PathBuf,
+    pub program: String,
+    #[serde(default)]
+    pub args: Vec<String>,
+}
+
+impl Runnable {
+    /// Converts the runnable into a task template, that can be resolved and spawned as any other Zed task.
+    pub fn into_task_template(self) -> TaskTemplate {
+        match self.args {
+            RunnableArgs::Cargo(cargo) => {
+                let mut args = cargo.cargo_args;
+                args.extend(cargo.cargo_extra_args);
+                if !cargo.executable_args.is_empty() {
+                    args.push("--".to_string());
+                    args.extend(cargo.executable_args);
+                }
+                TaskTemplate {
+                    label: self.label,
+                    command: cargo.override_cargo.unwrap_or_else(|| "cargo".to_string()),
+                    args,
+                    env: cargo.environment,
+                    cwd: cargo
+                        .cwd
+                        .or(cargo.workspace_root)
+                        .map(|cwd| cwd.to_string_lossy().into_owned()),
+                    ..TaskTemplate::default()
+                }
+            }
+            RunnableArgs::Shell(shell) => TaskTemplate {
+                label: self.label,
+                command: shell.program,
+                args: shell.args,
+                env: shell.environment,
+                cwd: Some(shell.cwd.to_string_lossy().into_owned()),
+                ..TaskTemplate::default()
+            },
+        }
+    }
+}
+
+/// Queries the tests, related to the symbol at the given position, returned as task templates to spawn.
+pub struct RelatedTests {
+    pub position: PointUtf16,
+}
+
+#[async_trait(?Send)]
+impl LspCommand for RelatedTests {
+    type Response = Vec<TaskTemplate>;
+    type LspRequest = LspRelatedTests;
+    type ProtoRequest = proto::LspExtRelatedTests;
+
+    fn to_lsp(
+        &self,
+        path: &Path,
+        _: &Buffer,
+        _: &Arc<LanguageServer>,
+        _: &AppContext,
+    ) -> lsp::TextDocumentPositionParams {
+        lsp::TextDocumentPositionParams {
+            text_document: lsp::TextDocumentIdentifier {
+                uri: lsp::Url::from_file_path(path).unwrap(),
+            },
+            position: point_to_lsp(self.position),
+        }
+    }
+
+    async fn response_from_lsp(
+        self,
+        message: Vec<TestInfo>,
+        _: Model<Project>,
+        _: Model<Buffer>,
+        _: LanguageServerId,
+        _: AsyncAppContext,
+    ) -> anyhow::Result<Vec<TaskTemplate>> {
+        Ok(message
+            .into_iter()
+            .map(|test| test.runnable.into_task_template())
+            .collect())
+    }
+
+    fn to_proto(&self, project_id: u64, buffer: &Buffer) -> proto::LspExtRelatedTests {
+        proto::LspExtRelatedTests {
+            project_id,
+            buffer_id: buffer.remote_id().into(),
+            position: Some(language::proto::serialize_anchor(
+                &buffer.anchor_before(self.position),
+            )),
+        }
+    }
+
+    async fn from_proto(
+        message: Self::ProtoRequest,
+        _: Model<Project>,
+        buffer: Model<Buffer>,
+        mut cx: AsyncAppContext,
+    ) -> anyhow::Result<Self> {
+        let position = message
+            .position
+            .and_then(deserialize_anchor)
+            .context("invalid position")?;
+        Ok(Self {
+            position: buffer.update(&mut cx, |buffer, _| position.to_point_utf16(buffer))?,
+        })
+    }
+
+    fn response_to_proto(
+        response: Vec<TaskTemplate>,
+        _: &mut Project,
+        _: PeerId,
+        _: &clock::Global,
+        _: &mut AppContext,
+    ) -> proto::LspExtRelatedTestsResponse {
+        proto::LspExtRelatedTestsResponse {
+            tests: response.into_iter().map(serialize_task_template).collect(),
+        }
+    }
+
+    async fn response_from_proto(
+        self,
+        message: proto::LspExtRelatedTestsResponse,
+        _: Model<Project>,
+        _: Model<Buffer>,
+        _: AsyncAppContext,
+    ) -> anyhow::Result<Vec<TaskTemplate>> {
+        Ok(message
+            .tests
+            .into_iter()
+            .map(deserialize_task_template)
+            .collect())
+    }
+
+    fn buffer_id_from_proto(message: &proto::LspExtRelatedTests) -> Result<BufferId> {
+        BufferId::new(message.buffer_id)
+    }
+}
+
+pub enum LspReloadWorkspace {}
+
+impl lsp::request::Request for LspReloadWorkspace {
+    type Params = ();
+    type Result = ();
+    const METHOD: &'static str = "rust-analyzer/reloadWorkspace";
+}
+
+/// Makes rust-analyzer re-read the Cargo workspace metadata of the project the buffer belongs to.
+pub struct ReloadWorkspace;
+
+#[async_trait(?Send)]
+impl LspCommand for ReloadWorkspace {
+    type Response = ();
+    type LspRequest = LspReloadWorkspace;
+    type ProtoRequest = proto::LspExtReloadWorkspace;
+
+    fn to_lsp(&self, _: &Path, _: &Buffer, _: &Arc<LanguageServer>, _: &AppContext) {}
+
+    async fn response_from_lsp(
+        self,
+        _: (),
+        _: Model<Project>,
+        _: Model<Buffer>,
+        _: LanguageServerId,
+        _: AsyncAppContext,
+    ) -> anyhow::Result<()> {
+        Ok(())
+    }
+
+    fn to_proto(&self, project_id: u64, buffer: &Buffer) -> proto::LspExtReloadWorkspace {
+        proto::LspExtReloadWorkspace {
+            project_id,
+            buffer_id: buffer.remote_id().into(),
+        }
+    }
+
+    async fn from_proto(
+        _: Self::ProtoRequest,
+        _: Model<Project>,
+        _: Model<Buffer>,
+        _: AsyncAppContext,
+    ) -> anyhow::Result<Self> {
+        Ok(Self)
+    }
+
+    fn response_to_proto(
+        _: (),
+        _: &mut Project,
+        _: PeerId,
+        _: &clock::Global,
+        _: &mut AppContext,
+    ) -> proto::Ack {
+        proto::Ack {}
+    }
+
+    async fn response_from_proto(
+        self,
+        _: proto::Ack,
+        _: Model<Project>,
+        _: Model<Buffer>,
+        _: AsyncAppContext,
+    ) -> anyhow::Result<()> {
+        Ok(())
+    }
+
+    fn buffer_id_from_proto(message: &proto::LspExtReloadWorkspace) -> Result<BufferId> {
+        BufferId::new(message.buffer_id)
+    }
+}
+
+pub enum LspRebuildProcMacros {}
+
+impl lsp::request::Request for LspRebuildProcMacros {
+    type Params = ();
+    type Result = ();
+    const METHOD: &'static str = "rust-analyzer/rebuildProcMacros";
+}
+
+/// Makes rust-analyzer re-run the build scripts and rebuild the proc macros of the project the buffer belongs to.
+pub struct RebuildProcMacros;
+
+#[async_trait(?Send)]
+impl LspCommand for RebuildProcMacros {
+    type Response = ();
+    type LspRequest = LspRebuildProcMacros;
+    type ProtoRequest = proto::LspExtRebuildProcMacros;
+
+    fn to_lsp(&self, _: &Path, _: &Buffer, _: &Arc<LanguageServer>, _: &AppContext) {}
+
+    async fn response_from_lsp(
+        self,
+        _: (),
+        _: Model<Project>,
+        _: Model<Buffer>,
+        _: LanguageServerId,
+        _: AsyncAppContext,
+    ) -> anyhow::Result<()> {
+        Ok(())
+    }
+
+    fn to_proto(&self, project_id: u64, buffer: &Buffer) -> proto::LspExtRebuildProcMacros {
+        proto::LspExtRebuildProcMacros {
+            project_id,
+            buffer_id: buffer.remote_id().into(),
+        }
+    }
+
+    async fn from_proto(
+        _: Self::ProtoRequest,
+        _: Model<Project>,
+        _: Model<Buffer>,
+        _: AsyncAppContext,
+    ) -> anyhow::Result<Self> {
+        Ok(Self)
+    }
+
+    fn response_to_proto(
+        _: (),
+        _: &mut Project,
+        _: PeerId,
+        _: &clock::Global,
+        _: &mut AppContext,
+    ) -> proto::Ack {
+        proto::Ack {}
+    }
+
+    async fn response_from_proto(
+        self,
+        _: proto::Ack,
+        _: Model<Project>,
+        _: Model<Buffer>,
+        _: AsyncAppContext,
+    ) -> anyhow::Result<()> {
+        Ok(())
+    }
+
+    fn buffer_id_from_proto(message: &proto::LspExtRebuildProcMacros) -> Result<BufferId> {
+        BufferId::new(message.buffer_id)
+    }
+}
+
+pub enum LspClangdAst {}
+
+impl lsp::request::Request for LspClangdAst {
+    type Params = ClangdAstParams;
+    type Result = Option<AstNode>;
+    const METHOD: &'static str = "textDocument/ast";
+}
+
+#[derive(Deserialize, Serialize, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct ClangdAstParams {
+    pub text_document: lsp::TextDocumentIdentifier,
+    pub range: lsp::Range,
+}
+
+/// A node of the clang AST, as reported by clangd.
+#[derive(Deserialize, Serialize, Debug, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct AstNode {
+    /// The general kind of the node, e.g. `expression` or `declaration`.
+    pub role: String,
+    /// The specific kind of the node, e.g. `BinaryOperator` or `Function`.
+    pub kind: String,
+    #[serde(default)]
+    pub detail: Option<String>,
+    /// The clang dump of the node, for debugging.
+    #[serde(default)]
+    pub arcana: Option<String>,
+    #[serde(default)]
+    pub range: Option<lsp::Range>,
+    #[serde(default)]
+    pub children: Vec<AstNode>,
+}
+
+impl AstNode {
+    /// Renders the node and its descendants as an indented tree, one node per line.
+    pub fn render_tree(&self) -> String {
+        let mut tree = String::new();
+        self.render_subtree("", "", &mut tree);
+        tree
+    }
+
+    fn render_subtree(&self, prefix: &str, child_prefix: &str, tree: &mut String) {
+        tree.push_str(prefix);
+        tree.push_str(&self.role);
+        tree.push(' ');
+        tree.push_str(&self.kind);
+        if let Some(detail) = &self.detail {
+            tree.push_str(": ");
+            tree.push_str(detail);
+        }
+        tree.push('\n');
+        for (ix, child) in self.children.iter().enumerate() {
+            let (branch, indent) = if ix + 1 == self.children.len() {
+                ("└─ ", "   ")
+            } else {
+                ("├─ ", "│  ")
+            };
+            child.render_subtree(
+                &format!("{child_prefix}{branch}"),
+                &format!("{child_prefix}{indent}"),
+                tree,
+            );
+        }
+    }
+}
+
+/// Queries clangd for the AST of the given range: the innermost node that contains it, with its descendants.
+pub struct ClangdAst {
+    pub range: Range<PointUtf16>,
+}
+
+#[async_trait(?Send)]
+impl LspCommand for ClangdAst {
+    type Response = Option<AstNode>;
+    type LspRequest = LspClangdAst;
+    type ProtoRequest = proto::LspExtClangdAst;
+
+    fn to_lsp(
+        &self,
+        path: &Path,
+        _: &Buffer,
+        _: &Arc<LanguageServer>,
+        _: &AppContext,
+    ) -> ClangdAstParams {
+        ClangdAstParams {
+            text_document: lsp::TextDocumentIdentifier {
+                uri: lsp::Url::from_file_path(path).unwrap(),
+            },
+            range: lsp::Range::new(point_to_lsp(self.range.start), point_to_lsp(self.range.end)),
+        }
+    }
+
+    async fn response_from_lsp(
+        self,
+        message: Option<AstNode>,
+        _: Model<Project>,
+        _: Model<Buffer>,
+        _: LanguageServerId,
+        _: AsyncAppContext,
+    ) -> anyhow::Result<Option<AstNode>> {
+        Ok(message)
+    }
+
+    fn to_proto(&self, project_id: u64, buffer: &Buffer) -> proto::LspExtClangdAst {
+        proto::LspExtClangdAst {
+            project_id,
+            buffer_id: buffer.remote_id().into(),
+            start: Some(language::proto::serialize_anchor(
+                &buffer.anchor_before(self.range.start),
+            )),
+            end: Some(language::proto::serialize_anchor(
+                &buffer.anchor_after(self.range.end),
+            )),
+        }
+    }
+
+    async fn from_proto(
+        message: Self::ProtoRequest,
+        _: Model<Project>,
+        buffer: Model<Buffer>,
+        mut cx: AsyncAppContext,
+    ) -> anyhow::Result<Self> {
+        let start = message
+            .start
+            .and_then(deserialize_anchor)
+            .context("invalid start")?;
+        let end = message
+            .end
+            .and_then(deserialize_anchor)
+            .context("invalid end")?;
+        Ok(Self {
+            range: buffer.update(&mut cx, |buffer, _| {
+                start.to_point_utf16(buffer)..end.to_point_utf16(buffer)
+            })?,
+        })
+    }
+
+    fn response_to_proto(
+        response: Option<AstNode>,
+        _: &mut Project,
+        _: PeerId,
+        _: &clock::Global,
+        _: &mut AppContext,
+    ) -> proto::LspExtClangdAstResponse {
+        proto::LspExtClangdAstResponse {
+            ast: response.and_then(|ast| serde_json::to_string(&ast).ok()),
+        }
+    }
+
+    async fn response_from_proto(
+        self,
+        message: proto::LspExtClangdAstResponse,
+        _: Model<Project>,
+        _: Model<Buffer>,
+        _: AsyncAppContext,
+    ) -> anyhow::Result<Option<AstNode>> {
+        message
+            .ast
+            .map(|ast| serde_json::from_str(&ast).context("deserializing the clangd AST"))
+            .transpose()
+    }
+
+    fn buffer_id_from_proto(message: &proto::LspExtClangdAst) -> Result<BufferId> {
+        BufferId::new(message.buffer_id)
+    }
+}
+
+pub enum LspOpenCargoToml {}
+
+impl lsp::request::Request for LspOpenCargoToml {
+    type Params = OpenCargoTomlParams;
+    type Result = Option<lsp::Location>;
+    const METHOD: &'static str = "experimental/openCargoToml";
+}
+
+#[derive(Deserialize, Serialize, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct OpenCargoTomlParams {
+    pub text_document: lsp::TextDocumentIdentifier,
+}
+
+/// Locates the `Cargo.toml` of the crate the buffer belongs to.
+pub struct OpenCargoToml;
+
+#[async_trait(?Send)]
+impl LspCommand for OpenCargoToml {
+    type Response = Vec<LocationLink>;
+    type LspRequest = LspOpenCargoToml;
+    type ProtoRequest = proto::LspExtOpenCargoToml;
+
+    fn to_lsp(
+        &self,
+        path: &Path,
+        _: &Buffer,
+        _: &Arc<LanguageServer>,
+        _: &AppContext,
+    ) -> OpenCargoTomlParams {
+        OpenCargoTomlParams {
+            text_document: lsp::TextDocumentIdentifier {
+                uri: lsp::Url::from_file_path(path).unwrap(),
+            },
+        }
+    }
+
+    async fn response_from_lsp(
+        self,
+        message: Option<lsp::Location>,
+        project: Model<Project>,
+        buffer: Model<Buffer>,
+        server_id: LanguageServerId,
+        cx: AsyncAppContext,
+    ) -> anyhow::Result<Vec<LocationLink>> {
+        let message = message.map(lsp::GotoDefinitionResponse::Scalar);
+        location_links_from_lsp(message, project, buffer, server_id, cx).await
+    }
+
+    fn to_proto(&self, project_id: u64, buffer: &Buffer) -> proto::LspExtOpenCargoToml {
+        proto::LspExtOpenCargoToml {
+            project_id,
+            buffer_id: buffer.remote_id().into(),
+        }
+    }
+
+    async fn from_proto(
+        _: Self::ProtoRequest,
+        _: Model<Project>,
+        _: Model<Buffer>,
+        _: AsyncAppContext,
+    ) -> anyhow::Result<Self> {
+        Ok(Self)
+    }
+
+    fn response_to_proto(
+        response: Vec<LocationLink>,
+        project: &mut Project,
+        peer_id: PeerId,
+        _: &clock::Global,
+        cx: &mut AppContext,
+    ) -> proto::LspExtOpenCargoTomlResponse {
+        proto::LspExtOpenCargoTomlResponse {
+            links: location_links_to_proto(response, project, peer_id, cx),
+        }
+    }
+
+    async fn response_from_proto(
+        self,
+        message: proto::LspExtOpenCargoTomlResponse,
+        project: Model<Project>,
+        _: Model<Buffer>,
+        cx: AsyncAppContext,
+    ) -> anyhow::Result<Vec<LocationLink>> {
+        location_links_from_proto(message.links, project, cx).await
+    }
+
+    fn buffer_id_from_proto(message: &proto::LspExtOpenCargoToml) -> Result<BufferId> {
+        BufferId::new(message.buffer_id)
+    }
+}
+
+pub enum LspParentModule {}
+
+impl lsp::request::Request for LspParentModule {
+    type Params = lsp::TextDocumentPositionParams;
+    type Result = Option<lsp::GotoDefinitionResponse>;
+    const METHOD: &'static str = "experimental/parentModule";
+}
+
+/// Locates the module declarations of the module that contains the given position.
+pub struct ParentModule {
+    pub position: PointUtf16,
+}
+
+#[async_trait(?Send)]
+impl LspCommand for ParentModule {
+    type Response = Vec<LocationLink>;
+    type LspRequest = LspParentModule;
+    type ProtoRequest = proto::LspExtParentModule;
+
+    fn to_lsp(
+        &self,
+        path: &Path,
+        _: &Buffer,
+        _: &Arc<LanguageServer>,
+        _: &AppContext,
+    ) -> lsp::TextDocumentPositionParams {
+        lsp::TextDocumentPositionParams {
+            text_document: lsp::TextDocumentIdentifier {
+                uri: lsp::Url::from_file_path(path).unwrap(),
+            },
+            position: point_to_lsp(self.position),
+        }
+    }
+
+    async fn response_from_lsp(
+        self,
+        message: Option<lsp::GotoDefinitionResponse>,
+        project: Model<Project>,
+        buffer: Model<Buffer>,
+        server_id: LanguageServerId,
+        cx: AsyncAppContext,
+    ) -> anyhow::Result<Vec<LocationLink>> {
+        location_links_from_lsp(message, project, buffer, server_id, cx).await
+    }
+
+    fn to_proto(&self, project_id: u64, buffer: &Buffer) -> proto::LspExtParentModule {
+        proto::LspExtParentModule {
+            project_id,
+            buffer_id: buffer.remote_id().into(),
+            position: Some(language::proto::serialize_anchor(
+                &buffer.anchor_before(self.position),
+            )),
+        }
+    }
+
+    async fn from_proto(
+        message: Self::ProtoRequest,
+        _: Model<Project>,
+        buffer: Model<Buffer>,
+        mut cx: AsyncAppContext,
+    ) -> anyhow::Result<Self> {
+        let position = message
+            .position
+            .and_then(deserialize_anchor)
+            .context("invalid position")?;
+        Ok(Self {
+            position: buffer.update(&mut cx, |buffer, _| position.to_point_utf16(buffer))?,
+        })
+    }
+
+    fn response_to_proto(
+        response: Vec<LocationLink>,
+        project: &mut Project,
+        peer_id: PeerId,
+        _: &clock::Global,
+        cx: &mut AppContext,
+    ) -> proto::LspExtParentModuleResponse {
+        proto::LspExtParentModuleResponse {
+            links: location_links_to_proto(response, project, peer_id, cx),
+        }
+    }
+
+    async fn response_from_proto(
+        self,
+        message: proto::LspExtParentModuleResponse,
+        project: Model<Project>,
+        _: Model<Buffer>,
+        cx: AsyncAppContext,
+    ) -> anyhow::Result<Vec<LocationLink>> {
+        location_links_from_proto(message.links, project, cx).await
+    }
+
+    fn buffer_id_from_proto(message: &proto::LspExtParentModule) -> Result<BufferId> {
+        BufferId::new(message.buffer_id)
+    }
+}
+
+pub enum LspRunnables {}
+
+impl lsp::request::Request for LspRunnables {
+    type Params = RunnablesParams;
+    type Result = Vec<Runnable>;
+    const METHOD: &'static str = "experimental/runnables";
+}
+
+#[derive(Deserialize, Serialize, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct RunnablesParams {
+    pub text_document: lsp::TextDocumentIdentifier,
+    #[serde(default)]
+    pub position: Option<lsp::Position>,
+}
+
+/// A runnable of a buffer, like a test, a test module or a binary, at the position of the item it runs.
+#[derive(Clone, Debug, PartialEq)]
+pub struct BufferRunnable {
+    pub label: String,
+    pub position: Option<language::Anchor>,
+    pub task: TaskTemplate,
+}
+
+/// Queries the runnables of a buffer, returned as task templates to spawn: all of them, or only the ones
+/// containing the position, if any.
+pub struct Runnables {
+    pub position: Option<PointUtf16>,
+}
+
+#[async_trait(?Send)]
+impl LspCommand for Runnables {
+    type Response = Vec<BufferRunnable>;
+    type LspRequest = LspRunnables;
+    type ProtoRequest = proto::LspExtRunnables;
+
+    fn to_lsp(
+        &self,
+        path: &Path,
+        _: &Buffer,
+        _: &Arc<LanguageServer>,
+        _: &AppContext,
+    ) -> RunnablesParams {
+        RunnablesParams {
+            text_document: lsp::TextDocumentIdentifier {
+                uri: lsp::Url::from_file_path(path).unwrap(),
+            },
+            position: self.position.map(point_to_lsp),
+        }
+    }
+
+    async fn response_from_lsp(
+        self,
+        message: Vec<Runnable>,
+        _: Model<Project>,
+        buffer: Model<Buffer>,
+        _: LanguageServerId,
+        mut cx: AsyncAppContext,
+    ) -> anyhow::Result<Vec<BufferRunnable>> {
+        buffer.update(&mut cx, |buffer, _| {
+            message
+                .into_iter()
+                .map(|runnable| {
+                    let position = runnable.location.as_ref().map(|location| {
+                        let start = point_from_lsp(location.target_selection_range.start);
+                        buffer.anchor_before(buffer.clip_point_utf16(start, Bias::Left))
+                    });
+                    BufferRunnable {
+                        label: runnable.label.clone(),
+                        position,
+                        task: runnable.into_task_template(),
+                    }
+                })
+                .collect()
+        })
+    }
+
+    fn to_proto(&self, project_id: u64, buffer: &Buffer) -> proto::LspExtRunnables {
+        proto::LspExtRunnables {
+            project_id,
+            buffer_id: buffer.remote_id().into(),
+            position: self
+                .position
+                .map(|position| serialize_anchor(&buffer.anchor_before(position))),
+        }
+    }
+
+    async fn from_proto(
+        message: Self::ProtoRequest,
+        _: Model<Project>,
+        buffer: Model<Buffer>,
+        mut cx: AsyncAppContext,
+    ) -> anyhow::Result<Self> {
+        let position = message
+            .position
+            .map(|position| deserialize_anchor(position).context("invalid position"))
+            .transpose()?;
+        Ok(Self {
+            position: buffer.update(&mut cx, |buffer, _| {
+                position.map(|position| position.to_point_utf16(buffer))
+            })?,
+        })
+    }
+
+    fn response_to_proto(
+        response: Vec<BufferRunnable>,
+        _: &mut Project,
+        _: PeerId,
+        _: &clock::Global,
+        _: &mut AppContext,
+    ) -> proto::LspExtRunnablesResponse {
+        proto::LspExtRunnablesResponse {
+            runnables: response
+                .into_iter()
+                .map(|runnable| proto::LspExtRunnable {
+                    label: runnable.label,
+                    position: runnable.position.as_ref().map(serialize_anchor),
+                    task: Some(serialize_task_template(runnable.task)),
+                })
+                .collect(),
+        }
+    }
+
+    async fn response_from_proto(
+        self,
+        message: proto::LspExtRunnablesResponse,
+        _: Model<Project>,
+        _: Model<Buffer>,
+        _: AsyncAppContext,
+    ) -> anyhow::Result<Vec<BufferRunnable>> {
+        message
+            .runnables
+            .into_iter()
+            .map(|runnable| {
+                Ok(BufferRunnable {
+                    label: runnable.label,
+                    position: runnable.position.and_then(deserialize_anchor),
+                    task: deserialize_task_template(
+                        runnable.task.context("missing runnable task")?,
+                    ),
+                })
+            })
+            .collect()
+    }
+
+    fn buffer_id_from_proto(message: &proto::LspExtRunnables) -> Result<BufferId> {
+        BufferId::new(message.buffer_id)
+    }
+}
@@ -0,0 +1,116 @@
+//! Combining the results of the language servers of a buffer, e.g. of tailwind, tsserver and eslint,
+//! according to the `lsp_merging` settings.
+
+use collections::HashSet;
+use gpui::AppContext;
+use lsp::LanguageServerId;
+use settings::Settings;
+
+use crate::{
+    project_settings::{LspMergeStrategy, ProjectSettings},
+    Completion, Hover, Project, SignatureHelp,
+};
+
+impl Project {
+    /// Orders the results by the ranking of their servers. Results of unranked servers follow, in their original order.
+    fn rank_server_results<T>(&self, results: &mut [(LanguageServerId, T)], ranking: &[String]) {
+        results.sort_by_key(|(server_id, _)| {
+            self.language_server_statuses
+                .get(server_id)
+                .and_then(|status| ranking.iter().position(|name| *name == status.name))
+                .unwrap_or(ranking.len())
+        });
+    }
+
+    pub(crate) fn merge_hovers(
+        &self,
+        mut hovers: Vec<(LanguageServerId, Hover)>,
+        cx: &AppContext,
+    ) -> Vec<Hover> {
+        let settings = &ProjectSettings::get_global(cx).lsp_merging.hover;
+        self.rank_server_results(&mut hovers, &settings.server_ranking);
+        let hovers = hovers.into_iter().map(|(_, hover)| hover);
+        match settings.strategy {
+            LspMergeStrategy::Concatenate => hovers.collect(),
+            LspMergeStrategy::First => hovers.take(1).collect(),
+            LspMergeStrategy::Deduplicate => {
+                let mut seen_blocks = HashSet::default();
+                hovers
+                    .filter_map(|mut hover| {
+                        hover
+                            .contents
+                            .retain(|block| seen_blocks.insert(block.text.clone()));
+                        (!hover.contents.is_empty()).then_some(hover)
+                    })
+                    .collect()
+            }
+        }
+    }
+
+    pub(crate) fn merge_completions(
+        &self,
+        completions: Vec<Completion>,
+        cx: &AppContext,
+    ) -> Vec<Completion> {
+        let settings = &ProjectSettings::get_global(cx).lsp_merging.completions;
+        let mut completions = completions
+            .into_iter()
+            .map(|completion| (completion.server_id, completion))
+            .collect::<Vec<_>>();
+        self.rank_server_results(&mut completions, &settings.server_ranking);
+        let first_server_id = completions.first().map(|(server_id, _)| *server_id);
+        let completions = completions.into_iter().map(|(_, completion)| completion);
+        match settings.strategy {
+            LspMergeStrategy::Concatenate => completions.collect(),
+            LspMergeStrategy::First => completions
+                .filter(|completion| Some(completion.server_id) == first_server_id)
+                .collect(),
+            LspMergeStrategy::Deduplicate => {
+                let mut seen_completions = HashSet::default();
+                completions
+                    .filter(|completion| {
+                        seen_completions
+                            .insert((completion.label.text.clone(), completion.new_text.clone()))
+                    })
+                    .collect()
+            }
+        }
+    }
+
+    /// Merges the signature help of the servers into one, the active signature of which is the one of the
+    /// highest ranked server.
+    pub(crate) fn merge_signature_helps(
+        &self,
+        mut signature_helps: Vec<(LanguageServerId, SignatureHelp)>,
+        cx: &AppContext,
+    ) -> Option<SignatureHelp> {
+        let settings = &ProjectSettings::get_global(cx).lsp_merging.signature_help;
+        self.rank_server_results(&mut signature_helps, &settings.server_ranking);
+        let mut signature_helps = signature_helps.into_iter().map(|(_, help)| help);
+        let mut merged = signature_helps.next()?;
+        match settings.strategy {
+            LspMergeStrategy::Concatenate => {
+                for signature_help in signature_helps {
+                    merged.signatures.extend(signature_help.signatures);
+                }
+            }
+            LspMergeStrategy::First => {}
+            LspMergeStrategy::Deduplicate => {
+                let mut seen_labels = merged
+                    .signatures
+                    .iter()
+                    .map(|signature| signature.label.clone())
+                    .collect::<HashSet<_>>();
+                for signature_help in signature_helps {
+                    merged.signatures.extend(
+                        signature_help
+                            .signatures
+                            .into_iter()
+                            .filter(|signature| seen_labels.insert(signature.label.clone())),
+                    );
+                }
+            }
+        }
+        Some(merged)
+    }
+}
@@ -0,0 +1,538 @@
+//! The language server response cache: with the `lsp_response_cache` setting enabled, the definitions and hovers
+//! found by the language servers of local buffers are written to disk, keyed by the path and the content of the
+//! buffer. While the servers of a buffer are still starting or indexing, e.g. right after reopening a large
+//! project, the cached responses for the same content are shown instead of waiting for them.
+//!
+//! The cached responses are replaced by the ones of the servers once they're done indexing: the server is still
+//! asked when a cached response is shown, and its response is cached if it finished indexing meanwhile.
+
+use std::{
+    collections::BTreeMap,
+    ops::Range,
+    path::{Path, PathBuf},
+    time::{Duration, SystemTime},
+};
+
+use anyhow::Result;
+use collections::HashMap;
+use futures::{future::Shared, FutureExt, StreamExt};
+use gpui::{AppContext, AsyncAppContext, Model, ModelContext, Task, WeakModel};
+use language::{Bias, Buffer, File as _, Location, PointUtf16, ToPointUtf16, Unclipped};
+use serde::{Deserialize, Serialize};
+use settings::Settings;
+use sha2::{Digest, Sha256};
+use text::BufferId;
+use util::ResultExt;
+
+use crate::{
+    project_settings::ProjectSettings, File, Hover, HoverBlock, HoverBlockKind,
+    LanguageServerState, LocationLink, Project,
+};
+
+/// Cache files not written to for this long are deleted.
+const CACHE_FILE_MAX_AGE: Duration = Duration::from_secs(30 * 24 * 60 * 60);
+
+#[derive(Default)]
+pub(crate) struct LspResponseCache {
+    /// The cached responses read from or written to disk during this session, by their key.
+    files: HashMap<String, Shared<Task<CachedResponses>>>,
+    /// The key of the last version of each buffer it was computed for.
+    keys: HashMap<BufferId, (clock::Global, String)>,
+    pruned: bool,
+}
+
+impl LspResponseCache {
+    pub(crate) fn buffer_removed(&mut self, buffer_id: BufferId) {
+        self.keys.remove(&buffer_id);
+    }
+}
+
+/// The cached responses for one content of a file, by the `row:column` position they were requested at.
+#[derive(Clone, Debug, Default, Serialize, Deserialize)]
+struct CachedResponses {
+    #[serde(default)]
+    definitions: BTreeMap<String, CachedDefinitions>,
+    #[serde(default)]
+    hovers: BTreeMap<String, Vec<CachedHover>>,
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+struct CachedDefinitions {
+    origin: Option<CachedRange>,
+    targets: Vec<CachedLocation>,
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+struct CachedLocation {
+    path: PathBuf,
+    range: CachedRange,
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+struct CachedHover {
+    range: Option<CachedRange>,
+    blocks: Vec<CachedHoverBlock>,
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+struct CachedHoverBlock {
+    text: String,
+    /// The language of a code block, `None` for Markdown and `Some("")` for plain text.
+    language: Option<String>,
+}
+
+/// A range as zero-based `[row, column]` pairs, in UTF-16 code units.
+#[derive(Clone, Copy, Debug, Serialize, Deserialize)]
+struct CachedRange {
+    start: (u32, u32),
+    end: (u32, u32),
+}
+
+impl CachedRange {
+    fn new(buffer: &Buffer, range: &Range<language::Anchor>) -> Self {
+        let start = range.start.to_point_utf16(buffer);
+        let end = range.end.to_point_utf16(buffer);
+        Self {
+            start: (start.row, start.column),
+            end: (end.row, end.column),
+        }
+    }
+
+    fn to_anchors(self, buffer: &Buffer) -> Range<language::Anchor> {
+        let start = buffer.clip_point_utf16(
+            Unclipped(PointUtf16::new(self.start.0, self.start.1)),
+            Bias::Left,
+        );
+        let end = buffer.clip_point_utf16(
+            Unclipped(PointUtf16::new(self.end.0, self.end.1)),
+            Bias::Left,
+        );
+        buffer.anchor_after(start)..buffer.anchor_before(end)
+    }
+}
+
+impl CachedHoverBlock {
+    fn new(block: &HoverBlock) -> Self {
+        let language = match &block.kind {
+            HoverBlockKind::PlainText => Some(String::new()),
+            HoverBlockKind::Markdown => None,
+            HoverBlockKind::Code { language } => Some(language.clone()),
+        };
+        Self {
+            text: block.text.clone(),
+            language,
+        }
+    }
+
+    fn to_hover_block(&self) -> HoverBlock {
+        let kind = match self.language.as_deref() {
+            None => HoverBlockKind::Markdown,
+            Some("") => HoverBlockKind::PlainText,
+            Some(language) => HoverBlockKind::Code {
+                language: language.to_string(),
+            },
+        };
+        HoverBlock {
+            text: self.text.clone(),
+            kind,
+        }
+    }
+}
+
+impl Project {
+    /// Shows the cached definitions while the language servers of the buffer are busy, and caches the ones they
+    /// find otherwise.
+    pub(crate) fn definition_with_response_cache(
+        &self,
+        buffer: &Model<Buffer>,
+        position: PointUtf16,
+        definitions: Task<Result<Vec<LocationLink>>>,
+        cx: &mut ModelContext<Self>,
+    ) -> Task<Result<Vec<LocationLink>>> {
+        if !self.is_response_cache_enabled(cx) {
+            return definitions;
+        }
+        let buffer = buffer.clone();
+        let position = position_key(position);
+        cx.spawn(move |this, mut cx| async move {
+            let Some((key, busy, cached)) = response_cache_lookup(&this, &buffer, &mut cx).await
+            else {
+                return definitions.await;
+            };
+            if busy {
+                let cached = cached.await.definitions.get(&position).cloned();
+                if let Some(cached) = cached {
+                    let links = cached_definition_links(&this, &buffer, cached, &mut cx).await;
+                    if !links.is_empty() {
+                        cx.spawn(move |mut cx| async move {
+                            if let Some(definitions) = definitions.await.log_err() {
+                                cache_definitions(
+                                    &this,
+                                    &buffer,
+                                    key,
+                                    position,
+                                    &definitions,
+                                    &mut cx,
+                                )
+                                .log_err();
+                            }
+                        })
+                        .detach();
+                        return Ok(links);
+                    }
+                }
+            }
+            let definitions = definitions.await?;
+            cache_definitions(&this, &buffer, key, position, &definitions, &mut cx).log_err();
+            Ok(definitions)
+        })
+    }
+
+    /// Shows the cached hovers while the language servers of the buffer are busy, and caches the ones they find
+    /// otherwise.
+    pub(crate) fn hover_with_response_cache(
+        &self,
+        buffer: &Model<Buffer>,
+        position: PointUtf16,
+        hovers: Task<Vec<Hover>>,
+        cx: &mut ModelContext<Self>,
+    ) -> Task<Vec<Hover>> {
+        if !self.is_response_cache_enabled(cx) {
+            return hovers;
+        }
+        let buffer = buffer.clone();
+        let position = position_key(position);
+        cx.spawn(move |this, mut cx| async move {
+            let Some((key, busy, cached)) = response_cache_lookup(&this, &buffer, &mut cx).await
+            else {
+                return hovers.await;
+            };
+            if busy {
+                let cached = cached.await.hovers.get(&position).cloned();
+                let cached_hovers = cached
+                    .filter(|cached| !cached.is_empty())
+                    .and_then(|cached| {
+                        buffer
+                            .read_with(&cx, |buffer, _| {
+                                let language = buffer.language().cloned();
+                                cached
+                                    .iter()
+                                    .map(|hover| Hover {
+                                        contents: hover
+                                            .blocks
+                                            .iter()
+                                            .map(CachedHoverBlock::to_hover_block)
+                                            .collect(),
+                                        range: hover.range.map(|range| range.to_anchors(buffer)),
+                                        language: language.clone(),
+                                    })
+                                    .collect::<Vec<_>>()
+                            })
+                            .ok()
+                    });
+                if let Some(cached_hovers) = cached_hovers {
+                    cx.spawn(move |mut cx| async move {
+                        let hovers = hovers.await;
+                        cache_hovers(&this, &buffer, key, position, &hovers, &mut cx).log_err();
+                    })
+                    .detach();
+                    return cached_hovers;
+                }
+            }
+            let hovers = hovers.await;
+            cache_hovers(&this, &buffer, key, position, &hovers, &mut cx).log_err();
+            hovers
+        })
+    }
+
+    fn is_response_cache_enabled(&self, cx: &AppContext) -> bool {
+        self.is_local() && ProjectSettings::get_global(cx).lsp_response_cache.enabled
+    }
+
+    /// The key of the cached responses for the current content of a local buffer, hashed in the background once
+    /// per version of the buffer.
+    fn response_cache_key(
+        &mut self,
+        buffer: &Model<Buffer>,
+        cx: &mut ModelContext<Self>,
+    ) -> Option<Task<String>> {
+        let buffer = buffer.read(cx);
+        let file = File::from_dyn(buffer.file())?.as_local()?;
+        let buffer_id = buffer.remote_id();
+        let version = buffer.version();
+        if let Some((key_version, key)) = self.lsp_response_cache.keys.get(&buffer_id) {
+            if *key_version == version {
+                return Some(Task::ready(key.clone()));
+            }
+        }
+        let abs_path = file.abs_path(cx);
+        let text = buffer.as_rope().clone();
+        let key = cx.background_executor().spawn(async move {
+            let mut hasher = Sha256::new();
+            hasher.update(abs_path.to_string_lossy().as_bytes());
+            hasher.update([0]);
+            for chunk in text.chunks() {
+                hasher.update(chunk.as_bytes());
+            }
+            format!("{:x}", hasher.finalize())
+        });
+        Some(cx.spawn(move |this, mut cx| async move {
+            let key = key.await;
+            this.update(&mut cx, |this, _| {
+                this.lsp_response_cache
+                    .keys
+                    .insert(buffer_id, (version, key.clone()));
+            })
+            .ok();
+            key
+        }))
+    }
+
+    /// Whether the language servers of the buffer may not have indexed the project yet: they're starting, or
+    /// reporting work in progress.
+    fn language_servers_busy(&self, buffer: &Buffer, cx: &AppContext) -> bool {
+        let server_ids = self.language_server_ids_for_buffer(buffer, cx);
+        server_ids.is_empty()
+            || server_ids.into_iter().any(|server_id| {
+                matches!(
+                    self.language_servers.get(&server_id),
+                    None | Some(LanguageServerState::Starting(_))
+                ) || self
+                    .language_server_statuses
+                    .get(&server_id)
+                    .map_or(true, |status| {
+                        !status.pending_work.is_empty() || status.has_pending_diagnostic_updates
+                    })
+            })
+    }
+
+    /// The cached responses of a key, read from disk the first time they're needed.
+    fn cached_responses(
+        &mut self,
+        key: &str,
+        cx: &mut ModelContext<Self>,
+    ) -> Shared<Task<CachedResponses>> {
+        if let Some(cached) = self.lsp_response_cache.files.get(key) {
+            return cached.clone();
+        }
+        let fs = self.fs.clone();
+        let cache_path = cache_file_path(key);
+        let cached = cx
+            .background_executor()
+            .spawn(async move {
+                let Ok(content) = fs.load(&cache_path).await else {
+                    return CachedResponses::default();
+                };
+                serde_json::from_str(&content).log_err().unwrap_or_default()
+            })
+            .shared();
+        self.lsp_response_cache
+            .files
+            .insert(key.to_string(), cached.clone());
+        cached
+    }
+
+    /// Updates the cached responses of a key and writes them to disk.
+    fn update_cached_responses(
+        &mut self,
+        key: String,
+        update: impl FnOnce(&mut CachedResponses) + Send + 'static,
+        cx: &mut ModelContext<Self>,
+    ) {
+        let cached = self.cached_responses(&key, cx);
+        let fs = self.fs.clone();
+        let prune = !std::mem::replace(&mut self.lsp_response_cache.pruned, true);
+        let updated = cx
+            .background_executor()
+            .spawn(async move {
+                let mut responses = cached.await;
+                update(&mut responses);
+                let cache_dir = paths::lsp_response_cache_dir();
+                let write = async {
+                    fs.create_dir(cache_dir).await?;
+                    if prune {
+                        prune_cache_files(fs.as_ref(), cache_dir).await;
+                    }
+                    fs.atomic_write(cache_file_path(&key), serde_json::to_string(&responses)?)
+                        .await
+                };
+                write.await.log_err();
+                responses
+            })
+            .shared();
+        self.lsp_response_cache.files.insert(key, updated.clone());
+        cx.background_executor()
+            .spawn(async move {
+                updated.await;
+            })
+            .detach();
+    }
+}
+
+/// The key of the cached responses for the buffer, whether its language servers are busy, and the cached
+/// responses.
+async fn response_cache_lookup(
+    this: &WeakModel<Project>,
+    buffer: &Model<Buffer>,
+    cx: &mut AsyncAppContext,
+) -> Option<(String, bool, Shared<Task<CachedResponses>>)> {
+    let key = this
+        .update(cx, |this, cx| this.response_cache_key(buffer, cx))
+        .ok()??
+        .await;
+    this.update(cx, |this, cx| {
+        let busy = this.language_servers_busy(buffer.read(cx), cx);
+        let cached = this.cached_responses(&key, cx);
+        (key, busy, cached)
+    })
+    .ok()
+}
+
+fn cache_definitions(
+    this: &WeakModel<Project>,
+    buffer: &Model<Buffer>,
+    key: String,
+    position: String,
+    definitions: &[LocationLink],
+    cx: &mut AsyncAppContext,
+) -> Result<()> {
+    this.update(cx, |this, cx| {
+        if definitions.is_empty() || this.language_servers_busy(buffer.read(cx), cx) {
+            return;
+        }
+        let origin = definitions[0].origin.as_ref().and_then(|origin| {
+            (origin.buffer == *buffer).then(|| CachedRange::new(buffer.read(cx), &origin.range))
+        });
+        let targets = definitions
+            .iter()
+            .filter_map(|definition| {
+                let target = &definition.target;
+                let target_buffer = target.buffer.read(cx);
+                let path = File::from_dyn(target_buffer.file())?
+                    .as_local()?
+                    .abs_path(cx);
+                Some(CachedLocation {
+                    path,
+                    range: CachedRange::new(target_buffer, &target.range),
+                })
+            })
+            .collect::<Vec<_>>();
+        this.update_cached_responses(
+            key,
+            move |responses| {
+                responses
+                    .definitions
+                    .insert(position, CachedDefinitions { origin, targets });
+            },
+            cx,
+        );
+    })
+}
+
+fn cache_hovers(
+    this: &WeakModel<Project>,
+    buffer: &Model<Buffer>,
+    key: String,
+    position: String,
+    hovers: &[Hover],
+    cx: &mut AsyncAppContext,
+) -> Result<()> {
+    this.update(cx, |this, cx| {
+        if hovers.is_empty() || this.language_servers_busy(buffer.read(cx), cx) {
+            return;
+        }
+        let hovers = hovers
+            .iter()
+            .map(|hover| CachedHover {
+                range: hover
+                    .range
+                    .as_ref()
+                    .map(|range| CachedRange::new(buffer.read(cx), range)),
+                blocks: hover.contents.iter().map(CachedHoverBlock::new).collect(),
+            })
+            .collect::<Vec<_>>();
+        this.update_cached_responses(
+            key,
+            move |responses| {
+                responses.hovers.insert(position, hovers);
+            },
+            cx,
+        );
+    })
+}
+
+/// Opens the buffers of the cached definitions of the buffer.
+async fn cached_definition_links(
+    this: &WeakModel<Project>,
+    buffer: &Model<Buffer>,
+    cached: CachedDefinitions,
+    cx: &mut AsyncAppContext,
+) -> Vec<LocationLink> {
+    let origin = cached.origin.and_then(|range| {
+        let range = buffer
+            .read_with(cx, |buffer, _| range.to_anchors(buffer))
+            .ok()?;
+        Some(Location {
+            buffer: buffer.clone(),
+            range,
+        })
+    });
+    let mut links = Vec::new();
+    for target in cached.targets {
+        let Some(open) = this
+            .update(cx, |this, cx| this.open_local_buffer(&target.path, cx))
+            .log_err()
+        else {
+            break;
+        };
+        let Some(target_buffer) = open.await.log_err() else {
+            continue;
+        };
+        let Some(range) = target_buffer
+            .read_with(cx, |target_buffer, _| {
+                target.range.to_anchors(target_buffer)
+            })
+            .log_err()
+        else {
+            continue;
+        };
+        links.push(LocationLink {
+            origin: origin.clone(),
+            target: Location {
+                buffer: target_buffer,
+                range,
+            },
+        });
+    }
+    links
+}
+
+/// Deletes the cache files that weren't written to for a while, which are mostly for outdated contents of files.
+async fn prune_cache_files(fs: &dyn fs::Fs, cache_dir: &Path) {
+    let Some(mut paths) = fs.read_dir(cache_dir).await.log_err() else {
+        return;
+    };
+    let now = SystemTime::now();
+    while let Some(path) = paths.next().await {
+        let Some(path) = path.log_err() else {
+            continue;
+        };
+        let Some(Some(metadata)) = fs.metadata(&path).await.log_err() else {
+            continue;
+        };
+        let expired = now
+            .duration_since(metadata.mtime)
+            .map_or(false, |age| age > CACHE_FILE_MAX_AGE);
+        if expired {
+            fs.remove_file(&path, Default::default()).await.log_err();
+        }
+    }
+}
+
+fn cache_file_path(key: &str) -> PathBuf {
+    paths::lsp_response_cache_dir().join(format!("{key}.json"))
+}
+
+fn position_key(position: PointUtf16) -> String {
+    format!("{}:{}", position.row, position.column)
+}
@@ -0,0 +1,309 @@
+//! The [`VcsProvider`] of Mercurial repositories, running `hg`.
+
+use std::{
+    path::{Path, PathBuf},
+    process::{Command, Output},
+    str::FromStr as _,
+};
+
+use anyhow::{anyhow, Context as _, Result};
+use collections::HashMap;
+use git::{
+    blame::{Blame, BlameEntry},
+    repository::GitFileStatus,
+    Oid,
+};
+use serde::Deserialize;
+use text::Rope;
+
+use crate::VcsProvider;
+
+/// The node of the working directory in the output of `hg`, whose lines aren't committed yet.
+const WORKING_DIRECTORY_NODE: &str = "ffffffffffffffffffffffffffffffffffffffff";
+
+/// The id of the lines that aren't committed yet in the blames, the same as in the blames of git.
+fn uncommitted_oid() -> Oid {
+    Oid::from_bytes(&[0; 20]).expect("20 bytes are a valid oid")
+}
+
+pub(crate) struct Mercurial;
+
+impl VcsProvider for Mercurial {
+    fn name(&self) -> &'static str {
+        "Mercurial"
+    }
+
+    fn repository_root(&self, abs_path: &Path) -> Option<PathBuf> {
+        abs_path
+            .ancestors()
+            .skip(1)
+            .find(|directory| directory.join(".hg").is_dir())
+            .map(Path::to_path_buf)
+    }
+
+    fn diff_base(&self, repository_root: &Path, abs_path: &Path) -> Result<Option<String>> {
+        let output = hg(repository_root, &["cat", "--rev", "."], abs_path)?;
+        // Files that aren't tracked have no contents at the parent revision.
+        if !output.status.success() {
+            return Ok(None);
+        }
+        Ok(Some(String::from_utf8(output.stdout)?))
+    }
+
+    fn status(&self, repository_root: &Path, abs_path: &Path) -> Result<Option<GitFileStatus>> {
+        let output = hg(repository_root, &["status"], abs_path)?;
+        if !output.status.success() {
+            return Err(anyhow!(
+                "hg status failed: {}",
+                String::from_utf8_lossy(&output.stderr)
+            ));
+        }
+        Ok(parse_status(&String::from_utf8_lossy(&output.stdout)))
+    }
+
+    fn blame(&self, repository_root: &Path, abs_path: &Path, _: &Rope) -> Result<Blame> {
+        let output = hg(
+            repository_root,
+            &[
+                "annotate",
+                "--rev",
+                "wdir()",
+                "--user",
+                "--date",
+                "--changeset",
+                "--line-number",
+                "--template",
+                "json",
+            ],
+            abs_path,
+        )?;
+        if !output.status.success() {
+            return Err(anyhow!(
+                "hg annotate failed: {}",
+                String::from_utf8_lossy(&output.stderr)
+            ));
+        }
+        let filename = abs_path
+            .strip_prefix(repository_root)
+            .unwrap_or(abs_path)
+            .to_string_lossy()
+            .into_owned();
+        let mut entries = parse_annotate(&output.stdout, &filename)?;
+
+        let mut nodes = entries
+            .iter()
+            .filter(|entry| entry.sha != uncommitted_oid())
+            .map(|entry| entry.sha.to_string())
+            .collect::<Vec<_>>();
+        nodes.sort();
+        nodes.dedup();
+        let messages = if nodes.is_empty() {
+            HashMap::default()
+        } else {
+            let revset = nodes.join(" + ");
+            let output = hg(
+                repository_root,
+                &["log", "--rev", &revset, "--template", "json"],
+                Path::new(""),
+            )?;
+            parse_log(&output.stdout)?
+        };
+        for entry in &mut entries {
+            entry.summary = messages
+                .get(&entry.sha)
+                .and_then(|message| message.lines().next())
+                .map(ToString::to_string);
+        }
+
+        Ok(Blame {
+            entries,
+            messages,
+            permalinks: HashMap::default(),
+            remote_url: None,
+        })
+    }
+}
+
+/// Runs `hg` in the repository with plain output, passing the path of the file last if it isn't empty.
+fn hg(repository_root: &Path, arguments: &[&str], abs_path: &Path) -> Result<Output> {
+    let mut command = Command::new("hg");
+    command
+        .current_dir(repository_root)
+        .env("HGPLAIN", "1")
+        .args(arguments);
+    if !abs_path.as_os_str().is_empty() {
+        command.arg("--").arg(abs_path);
+    }
+    command.output().context("failed to run hg")
+}
+
+fn parse_status(output: &str) -> Option<GitFileStatus> {
+    match output.chars().next()? {
+        'M' => Some(GitFileStatus::Modified),
+        'A' | '?' => Some(GitFileStatus::Added),
+        _ => None,
+    }
+}
+
+#[derive(Deserialize)]
+struct AnnotatedFile {
+    lines: Vec<AnnotatedLine>,
+}
+
+#[derive(Deserialize)]
+struct AnnotatedLine {
+    node: String,
+    user: String,
+    /// The time of the changeset, in seconds since the epoch, and the offset of its timezone, in seconds west
+    /// of UTC.
+    date: (i64, i64),
+    lineno: u32,
+}
+
+#[derive(Deserialize)]
+struct Changeset {
+    node: String,
+    desc: String,
+}
+
+/// Parses the output of `hg annotate --template json`, merging the consecutive lines of the same changesets.
+fn parse_annotate(output: &[u8], filename: &str) -> Result<Vec<BlameEntry>> {
+    let files = serde_json::from_slice::<Vec<AnnotatedFile>>(output)
+        .context("parsing the output of hg annotate")?;
+    let mut entries = Vec::<BlameEntry>::new();
+    for (row, line) in files.into_iter().flat_map(|file| file.lines).enumerate() {
+        let row = row as u32;
+        let sha = if line.node == WORKING_DIRECTORY_NODE {
+            uncommitted_oid()
+        } else {
+            Oid::from_str(&line.node)?
+        };
+        if let Some(last) = entries.last_mut() {
+            if last.sha == sha
+                && last.range.end == row
+                && last.original_line_number + (row - last.range.start) == line.lineno
+            {
+                last.range.end += 1;
+                continue;
+            }
+        }
+
+        let (author, author_mail) = match line.user.split_once('<') {
+            Some((name, mail)) => (name.trim().to_string(), Some(format!("<{}", mail.trim()))),
+            None => (line.user.trim().to_string(), None),
+        };
+        let (time, offset_west) = line.date;
+        let offset = -offset_west;
+        let tz = format!(
+            "{}{:02}{:02}",
+            if offset < 0 { '-' } else { '+' },
+            offset.abs() / 3600,
+            offset.abs() % 3600 / 60
+        );
+        entries.push(BlameEntry {
+            sha,
+            range: row..row + 1,
+            original_line_number: line.lineno,
+            author: Some(author.clone()),
+            author_mail: author_mail.clone(),
+            author_time: Some(time),
+            author_tz: Some(tz.clone()),
+            committer: Some(author),
+            committer_mail: author_mail,
+            committer_time: Some(time),
+            committer_tz: Some(tz),
+            summary: None,
+            previous: None,
+            filename: filename.to_string(),
+        });
+    }
+    Ok(entries)
+}
+
+/// Parses the output of `hg log --template json` into the descriptions of the changesets.
+fn parse_log(output: &[u8]) -> Result<HashMap<Oid, String>> {
+    serde_json::from_slice::<Vec<Changeset>>(output)
+        .context("parsing the output of hg log")?
+        .into_iter()
+        .map(|changeset| Ok((Oid::from_str(&changeset.node)?, changeset.desc)))
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use std::str::FromStr as _;
+
+    use git::{repository::GitFileStatus, Oid};
+
+    use super::{parse_annotate, parse_log, parse_status, uncommitted_oid};
+
+    #[test]
+    fn test_parse_annotate() {
+        let output = r#"[
+            {
+                "abspath": "src/main.rs",
+                "lines": [
+                    {"date": [1700000000, -3600], "line": "fn main() {\n", "lineno": 1, "node": "1111111111111111111111111111111111111111", "user": "Jane Doe <jane@example.com>"},
+                    {"date": [1700000000, -3600], "line": "    run();\n", "lineno": 2, "node": "1111111111111111111111111111111111111111", "user": "Jane Doe <jane@example.com>"},
+                    {"date": [1710000000, 0], "line": "    stop();\n", "lineno": 3, "node": "ffffffffffffffffffffffffffffffffffffffff", "user": "john"},
+                    {"date": [1700000000, -3600], "line": "}\n", "lineno": 3, "node": "1111111111111111111111111111111111111111", "user": "Jane Doe <jane@example.com>"}
+                ],
+                "path": "src/main.rs"
+            }
+        ]"#;
+        let entries = parse_annotate(output.as_bytes(), "src/main.rs").unwrap();
+        let summary = entries
+            .iter()
+            .map(|entry| {
+                (
+                    entry.sha == uncommitted_oid(),
+                    entry.range.clone(),
+                    entry.original_line_number,
+                    entry.author.clone().unwrap(),
+                    entry.author_mail.clone(),
+                    entry.author_tz.clone().unwrap(),
+                )
+            })
+            .collect::<Vec<_>>();
+        assert_eq!(
+            summary,
+            vec![
+                (
+                    false,
+                    0..2,
+                    1,
+                    "Jane Doe".to_string(),
+                    Some("<jane@example.com>".to_string()),
+                    "+0100".to_string()
+                ),
+                (true, 2..3, 3, "john".to_string(), None, "+0000".to_string()),
+                (
+                    false,
+                    3..4,
+                    3,
+                    "Jane Doe".to_string(),
+                    Some("<jane@example.com>".to_string()),
+                    "+0100".to_string()
+                ),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_parse_log_and_status() {
+        let output =
+            r#"[{"node": "1111111111111111111111111111111111111111", "desc": "Add main"}]"#;
+        let messages = parse_log(output.as_bytes()).unwrap();
+        assert_eq!(
+            messages.get(&Oid::from_str("1111111111111111111111111111111111111111").unwrap()),
+            Some(&"Add main".to_string())
+        );
+
+        assert_eq!(
+            parse_status("M src/main.rs\n"),
+            Some(GitFileStatus::Modified)
+        );
+        assert_eq!(parse_status("? notes.txt\n"), Some(GitFileStatus::Added));
+        assert_eq!(parse_status(""), None);
+    }
+}
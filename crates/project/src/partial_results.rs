@@ -0,0 +1,491 @@
+//! Partial results: language servers can report the results of long requests found so far with `$/progress`
+//! notifications, before responding with the rest of them, like the references of a symbol used across a large
+//! codebase. The partial results are streamed to the callers, and forwarded by the host to the guests that made
+//! the requests, so that they don't have to wait for all of the results to show the first ones.
+
+use anyhow::{anyhow, Result};
+use client::TypedEnvelope;
+use collections::HashMap;
+use futures::{channel::mpsc, StreamExt as _};
+use gpui::{AsyncAppContext, Model, ModelContext, Task};
+use language::{Buffer, PointUtf16, ToPointUtf16};
+use rpc::proto;
+use util::ResultExt;
+
+use crate::{
+    lsp_command::{
+        locations_from_lsp, locations_from_proto, locations_to_proto, GetReferences, LspCommand,
+    },
+    lsp_workspace_symbols, populate_labels_for_symbols,
+    pull_diagnostics::PartialResultRequest,
+    serialize_symbol, CoreSymbol, LanguageServerToQuery, Location, Project, Symbol,
+};
+
+/// Results of a request, streamed as the language servers report them.
+pub struct StreamedResults<T> {
+    /// The batches of results reported before the responses.
+    pub partial: mpsc::UnboundedReceiver<Vec<T>>,
+    /// The results that were not reported in the batches, resolved once all of the batches were sent.
+    pub rest: Task<Result<Vec<T>>>,
+}
+
+/// The partial result tokens of the requests sent to the host. The ones of the requests sent to the local language
+/// servers are registered along with the ones of the workspace diagnostics pulls.
+#[derive(Default)]
+pub(crate) struct PartialResults {
+    host_tokens: HashMap<String, mpsc::UnboundedSender<proto::LspPartialResult>>,
+    next_token: usize,
+}
+
+impl PartialResults {
+    fn register_host_token(
+        &mut self,
+    ) -> (String, mpsc::UnboundedReceiver<proto::LspPartialResult>) {
+        let token = format!("zed-partial-result-{}", self.next_token);
+        self.next_token += 1;
+        let (tx, rx) = mpsc::unbounded();
+        self.host_tokens.insert(token.clone(), tx);
+        (token, rx)
+    }
+}
+
+impl Project {
+    /// Like [`Project::references`], but also reports the references the language server finds before its
+    /// response.
+    pub fn references_with_partial_results<T: ToPointUtf16>(
+        &mut self,
+        buffer: &Model<Buffer>,
+        position: T,
+        cx: &mut ModelContext<Self>,
+    ) -> StreamedResults<Location> {
+        let position = position.to_point_utf16(buffer.read(cx));
+        let (partial_tx, partial_rx) = mpsc::unbounded();
+        let rest = if self.is_local() {
+            self.local_references_with_partial_results(buffer, position, partial_tx, cx)
+        } else {
+            self.remote_references_with_partial_results(buffer, position, partial_tx, cx)
+        };
+        StreamedResults {
+            partial: partial_rx,
+            rest,
+        }
+    }
+
+    fn local_references_with_partial_results(
+        &mut self,
+        buffer: &Model<Buffer>,
+        position: PointUtf16,
+        partial_tx: mpsc::UnboundedSender<Vec<Location>>,
+        cx: &mut ModelContext<Self>,
+    ) -> Task<Result<Vec<Location>>> {
+        let Some((adapter, server)) = self
+            .primary_language_server_for_buffer(buffer.read(cx), cx)
+            .map(|(adapter, server)| (adapter.clone(), server.clone()))
+        else {
+            return Task::ready(Ok(Vec::new()));
+        };
+        let (lsp_partial_tx, mut partial_rx) = mpsc::unbounded();
+        let token = self.pull_diagnostics.register_partial_result_token(
+            server.server_id(),
+            PartialResultRequest::Streamed(lsp_partial_tx),
+        );
+        // The locations of the response are streamed too, as they're anchored.
+        let (anchored_tx, mut anchored_rx) = mpsc::unbounded();
+        let references = self.request_lsp(
+            buffer.clone(),
+            LanguageServerToQuery::Primary,
+            GetReferences {
+                position,
+                partial_result_token: Some(token.clone()),
+                anchored_chunks: Some(anchored_tx),
+            },
+            cx,
+        );
+        let send_anchored_chunks = cx.background_executor().spawn({
+            let partial_tx = partial_tx.clone();
+            async move {
+                let mut sent_any = false;
+                while let Some(locations) = anchored_rx.next().await {
+                    sent_any |= !locations.is_empty();
+                    partial_tx.unbounded_send(locations).ok();
+                }
+                sent_any
+            }
+        });
+
+        let send_partial_results = cx.spawn(move |this, mut cx| async move {
+            let mut sent_any = false;
+            while let Some(value) = partial_rx.next().await {
+                let Some(this) = this.upgrade() else {
+                    break;
+                };
+                let Some(lsp_locations) =
+                    serde_json::from_value::<Vec<lsp::Location>>(value).log_err()
+                else {
+                    continue;
+                };
+                let lsp_locations = lsp_locations
+                    .into_iter()
+                    .map(|location| (location.uri, location.range))
+                    .collect();
+                if let Some(locations) =
+                    locations_from_lsp(lsp_locations, &this, &adapter, &server, None, &mut cx)
+                        .await
+                        .log_err()
+                {
+                    sent_any |= !locations.is_empty();
+                    partial_tx.unbounded_send(locations).ok();
+                }
+            }
+            sent_any
+        });
+
+        let buffer = buffer.clone();
+        cx.spawn(move |this, mut cx| async move {
+            let references = references.await;
+            this.update(&mut cx, |this, _| {
+                this.pull_diagnostics
+                    .unregister_partial_result_token(&token);
+            })?;
+            let sent_partial_results = send_partial_results.await;
+            if send_anchored_chunks.await || sent_partial_results {
+                return references;
+            }
+            this.update(&mut cx, |this, cx| {
+                this.references_with_index_fallback(&buffer, position, Task::ready(references), cx)
+            })?
+            .await
+        })
+    }
+
+    fn remote_references_with_partial_results(
+        &mut self,
+        buffer: &Model<Buffer>,
+        position: PointUtf16,
+        partial_tx: mpsc::UnboundedSender<Vec<Location>>,
+        cx: &mut ModelContext<Self>,
+    ) -> Task<Result<Vec<Location>>> {
+        let (token, mut partial_rx) = self.partial_results.register_host_token();
+        let references = self.request_lsp(
+            buffer.clone(),
+            LanguageServerToQuery::Primary,
+            GetReferences {
+                position,
+                partial_result_token: Some(token.clone()),
+                anchored_chunks: None,
+            },
+            cx,
+        );
+
+        let send_partial_results = cx.spawn(move |this, mut cx| async move {
+            while let Some(message) = partial_rx.next().await {
+                let Some(this) = this.upgrade() else {
+                    break;
+                };
+                if let Some(locations) = locations_from_proto(message.locations, &this, &mut cx)
+                    .await
+                    .log_err()
+                {
+                    partial_tx.unbounded_send(locations).ok();
+                }
+            }
+        });
+
+        cx.spawn(move |this, mut cx| async move {
+            let references = references.await;
+            this.update(&mut cx, |this, _| {
+                this.partial_results.host_tokens.remove(&token);
+            })?;
+            send_partial_results.await;
+            references
+        })
+    }
+
+    /// Like [`Project::symbols`], but also reports the symbols the language servers find before their responses.
+    pub fn symbols_with_partial_results(
+        &mut self,
+        query: &str,
+        cx: &mut ModelContext<Self>,
+    ) -> StreamedResults<Symbol> {
+        let (partial_tx, partial_rx) = mpsc::unbounded();
+        let rest = if self.is_local() {
+            self.local_symbols_with_partial_results(query, partial_tx, cx)
+        } else if let Some(project_id) = self.remote_id() {
+            self.remote_symbols_with_partial_results(project_id, query, partial_tx, cx)
+        } else {
+            Task::ready(Ok(Vec::new()))
+        };
+        StreamedResults {
+            partial: partial_rx,
+            rest,
+        }
+    }
+
+    fn local_symbols_with_partial_results(
+        &mut self,
+        query: &str,
+        partial_tx: mpsc::UnboundedSender<Vec<Symbol>>,
+        cx: &mut ModelContext<Self>,
+    ) -> Task<Result<Vec<Symbol>>> {
+        let language_registry = self.languages.clone();
+        let mut tokens = Vec::new();
+        let mut requests = Vec::new();
+        for symbols_server in self.workspace_symbols_servers(cx) {
+            let (lsp_partial_tx, mut partial_rx) = mpsc::unbounded();
+            let token = self.pull_diagnostics.register_partial_result_token(
+                symbols_server.server.server_id(),
+                PartialResultRequest::Streamed(lsp_partial_tx),
+            );
+            let request = symbols_server
+                .server
+                .request::<lsp::request::WorkspaceSymbolRequest>(lsp::WorkspaceSymbolParams {
+                    query: query.to_string(),
+                    partial_result_params: lsp::PartialResultParams {
+                        partial_result_token: Some(lsp::NumberOrString::String(token.clone())),
+                    },
+                    ..Default::default()
+                });
+            tokens.push(token);
+
+            let language_registry = language_registry.clone();
+            let partial_tx = partial_tx.clone();
+            let send_partial_results = cx.spawn(move |this, mut cx| async move {
+                while let Some(value) = partial_rx.next().await {
+                    let Some(this) = this.upgrade() else {
+                        break;
+                    };
+                    let Some(response) =
+                        serde_json::from_value::<lsp::WorkspaceSymbolResponse>(value).log_err()
+                    else {
+                        continue;
+                    };
+                    let Some(core_symbols) = this
+                        .update(&mut cx, |this, cx| {
+                            this.core_symbols_from_lsp(
+                                &symbols_server,
+                                lsp_workspace_symbols(response),
+                                cx,
+                            )
+                        })
+                        .log_err()
+                    else {
+                        break;
+                    };
+                    let mut symbols = Vec::new();
+                    populate_labels_for_symbols(
+                        core_symbols,
+                        &language_registry,
+                        Some(symbols_server.language.clone()),
+                        Some(symbols_server.adapter.clone()),
+                        &mut symbols,
+                    )
+                    .await;
+                    partial_tx.unbounded_send(symbols).ok();
+                }
+                symbols_server
+            });
+            requests.push(async move {
+                let lsp_symbols = request
+                    .await
+                    .log_err()
+                    .flatten()
+                    .map(lsp_workspace_symbols)
+                    .unwrap_or_default();
+                (send_partial_results, lsp_symbols)
+            });
+        }
+
+        let indexed_symbols = self.symbol_index_matches(query, cx);
+        cx.spawn(move |this, mut cx| async move {
+            let responses = futures::future::join_all(requests).await;
+            this.update(&mut cx, |this, _| {
+                for token in &tokens {
+                    this.pull_diagnostics.unregister_partial_result_token(token);
+                }
+            })?;
+
+            let mut symbols = indexed_symbols;
+            for (send_partial_results, lsp_symbols) in responses {
+                let symbols_server = send_partial_results.await;
+                let core_symbols = this.update(&mut cx, |this, cx| {
+                    this.core_symbols_from_lsp(&symbols_server, lsp_symbols, cx)
+                })?;
+                populate_labels_for_symbols(
+                    core_symbols,
+                    &language_registry,
+                    Some(symbols_server.language),
+                    Some(symbols_server.adapter),
+                    &mut symbols,
+                )
+                .await;
+            }
+            Ok(symbols)
+        })
+    }
+
+    fn remote_symbols_with_partial_results(
+        &mut self,
+        project_id: u64,
+        query: &str,
+        partial_tx: mpsc::UnboundedSender<Vec<Symbol>>,
+        cx: &mut ModelContext<Self>,
+    ) -> Task<Result<Vec<Symbol>>> {
+        let language_registry = self.languages.clone();
+        let (token, mut partial_rx) = self.partial_results.register_host_token();
+        let request = self.client.request(proto::GetProjectSymbols {
+            project_id,
+            query: query.to_string(),
+            partial_result_token: Some(token.clone()),
+        });
+
+        let send_partial_results = cx.spawn({
+            let language_registry = language_registry.clone();
+            move |_, _| async move {
+                while let Some(message) = partial_rx.next().await {
+                    let mut symbols = Vec::new();
+                    populate_labels_for_symbols(
+                        deserialize_symbols(message.symbols),
+                        &language_registry,
+                        None,
+                        None,
+                        &mut symbols,
+                    )
+                    .await;
+                    partial_tx.unbounded_send(symbols).ok();
+                }
+            }
+        });
+
+        cx.spawn(move |this, mut cx| async move {
+            let response = request.await;
+            this.update(&mut cx, |this, _| {
+                this.partial_results.host_tokens.remove(&token);
+            })?;
+            send_partial_results.await;
+            let mut symbols = Vec::new();
+            populate_labels_for_symbols(
+                deserialize_symbols(response?.symbols),
+                &language_registry,
+                None,
+                None,
+                &mut symbols,
+            )
+            .await;
+            Ok(symbols)
+        })
+    }
+
+    pub(crate) async fn handle_get_references(
+        this: Model<Self>,
+        envelope: TypedEnvelope<proto::GetReferences>,
+        mut cx: AsyncAppContext,
+    ) -> Result<proto::GetReferencesResponse> {
+        let Some(guest_token) = envelope.payload.partial_result_token.clone() else {
+            return Self::handle_lsp_command::<GetReferences>(this, envelope, cx).await;
+        };
+        let sender_id = envelope.original_sender_id()?;
+        let project_id = envelope.payload.project_id;
+        let buffer_id = GetReferences::buffer_id_from_proto(&envelope.payload)?;
+        let buffer = this.update(&mut cx, |this, _| {
+            this.opened_buffers
+                .get(&buffer_id)
+                .and_then(|buffer| buffer.upgrade())
+                .ok_or_else(|| anyhow!("unknown buffer id {}", buffer_id))
+        })??;
+        let request =
+            GetReferences::from_proto(envelope.payload, this.clone(), buffer.clone(), cx.clone())
+                .await?;
+
+        let mut references = this.update(&mut cx, |this, cx| {
+            this.references_with_partial_results(&buffer, request.position, cx)
+        })?;
+        while let Some(locations) = references.partial.next().await {
+            this.update(&mut cx, |this, cx| {
+                let locations = locations_to_proto(locations, this, sender_id, cx);
+                this.client.send(proto::LspPartialResult {
+                    project_id,
+                    peer_id: Some(sender_id),
+                    token: guest_token.clone(),
+                    locations,
+                    symbols: Vec::new(),
+                })
+            })??;
+        }
+        let rest = references.rest.await?;
+        this.update(&mut cx, |this, cx| {
+            GetReferences::response_to_proto(rest, this, sender_id, &buffer.read(cx).version(), cx)
+        })
+    }
+
+    pub(crate) async fn handle_get_project_symbols(
+        this: Model<Self>,
+        envelope: TypedEnvelope<proto::GetProjectSymbols>,
+        mut cx: AsyncAppContext,
+    ) -> Result<proto::GetProjectSymbolsResponse> {
+        let Some(guest_token) = envelope.payload.partial_result_token.clone() else {
+            let symbols = this
+                .update(&mut cx, |this, cx| {
+                    this.symbols(&envelope.payload.query, cx)
+                })?
+                .await?;
+            return Ok(proto::GetProjectSymbolsResponse {
+                symbols: symbols
+                    .iter()
+                    .map(serialize_symbol)
+                    .collect::<Result<_>>()?,
+            });
+        };
+        let sender_id = envelope.original_sender_id()?;
+        let project_id = envelope.payload.project_id;
+
+        let mut symbols = this.update(&mut cx, |this, cx| {
+            this.symbols_with_partial_results(&envelope.payload.query, cx)
+        })?;
+        while let Some(partial_symbols) = symbols.partial.next().await {
+            let partial_symbols = partial_symbols
+                .iter()
+                .map(serialize_symbol)
+                .collect::<Result<_>>()?;
+            this.update(&mut cx, |this, _| {
+                this.client.send(proto::LspPartialResult {
+                    project_id,
+                    peer_id: Some(sender_id),
+                    token: guest_token.clone(),
+                    locations: Vec::new(),
+                    symbols: partial_symbols,
+                })
+            })??;
+        }
+        Ok(proto::GetProjectSymbolsResponse {
+            symbols: symbols
+                .rest
+                .await?
+                .iter()
+                .map(serialize_symbol)
+                .collect::<Result<_>>()?,
+        })
+    }
+
+    pub(crate) async fn handle_lsp_partial_result(
+        this: Model<Self>,
+        envelope: TypedEnvelope<proto::LspPartialResult>,
+        mut cx: AsyncAppContext,
+    ) -> Result<()> {
+        this.update(&mut cx, |this, _| {
+            if let Some(tx) = this
+                .partial_results
+                .host_tokens
+                .get(&envelope.payload.token)
+            {
+                tx.unbounded_send(envelope.payload).ok();
+            }
+        })
+    }
+}
+
+fn deserialize_symbols(symbols: Vec<proto::Symbol>) -> Vec<CoreSymbol> {
+    symbols
+        .into_iter()
+        .filter_map(|symbol| Project::deserialize_symbol(symbol).log_err())
+        .collect()
+}
@@ -0,0 +1,261 @@
+//! The [`VcsProvider`] of Perforce workspaces, running `p4`.
+//!
+//! Workspaces are found from their `P4CONFIG` files, so only the workspaces configured with one are detected.
+
+use std::{
+    env,
+    path::{Path, PathBuf},
+    process::{Command, Output},
+    str::FromStr as _,
+};
+
+use anyhow::{anyhow, Context as _, Result};
+use collections::HashMap;
+use git::{
+    blame::{Blame, BlameEntry},
+    repository::GitFileStatus,
+    Oid,
+};
+use text::Rope;
+
+use crate::VcsProvider;
+
+const DEFAULT_P4CONFIG: &str = ".p4config";
+
+pub(crate) struct Perforce;
+
+impl VcsProvider for Perforce {
+    fn name(&self) -> &'static str {
+        "Perforce"
+    }
+
+    fn repository_root(&self, abs_path: &Path) -> Option<PathBuf> {
+        let config = env::var("P4CONFIG").unwrap_or_else(|_| DEFAULT_P4CONFIG.to_string());
+        abs_path
+            .ancestors()
+            .skip(1)
+            .find(|directory| directory.join(&config).is_file())
+            .map(Path::to_path_buf)
+    }
+
+    fn diff_base(&self, repository_root: &Path, abs_path: &Path) -> Result<Option<String>> {
+        let revision = format!("{}#have", abs_path.display());
+        let output = p4(repository_root, &["print", "-q", &revision])?;
+        // Files that aren't synced, like the ones opened for add, have no revision to compare against.
+        if !output.status.success() || !output.stderr.is_empty() {
+            return Ok(None);
+        }
+        Ok(Some(String::from_utf8(output.stdout)?))
+    }
+
+    fn status(&self, repository_root: &Path, abs_path: &Path) -> Result<Option<GitFileStatus>> {
+        let path = abs_path.to_string_lossy();
+        let output = p4(repository_root, &["-ztag", "fstat", "-T", "action", &path])?;
+        Ok(parse_fstat_action(&String::from_utf8_lossy(&output.stdout)))
+    }
+
+    fn blame(&self, repository_root: &Path, abs_path: &Path, _: &Rope) -> Result<Blame> {
+        let path = abs_path.to_string_lossy();
+        let output = p4(repository_root, &["annotate", "-c", "-u", "-q", &path])?;
+        if !output.status.success() {
+            return Err(anyhow!(
+                "p4 annotate failed: {}",
+                String::from_utf8_lossy(&output.stderr)
+            ));
+        }
+        let filename = abs_path
+            .strip_prefix(repository_root)
+            .unwrap_or(abs_path)
+            .to_string_lossy()
+            .into_owned();
+        let entries = parse_annotate(&String::from_utf8_lossy(&output.stdout), &filename)?;
+
+        let mut changes = entries
+            .iter()
+            .map(|entry| entry.original_change)
+            .collect::<Vec<_>>();
+        changes.sort_unstable();
+        changes.dedup();
+        let mut messages = HashMap::default();
+        for change in changes {
+            let output = p4(repository_root, &["describe", "-s", &change.to_string()])?;
+            if let Some(description) = parse_describe(&String::from_utf8_lossy(&output.stdout)) {
+                messages.insert(change_oid(change)?, description);
+            }
+        }
+
+        Ok(Blame {
+            entries: entries
+                .into_iter()
+                .map(|mut entry| {
+                    entry.entry.summary = messages
+                        .get(&entry.entry.sha)
+                        .and_then(|message| message.lines().next())
+                        .map(ToString::to_string);
+                    entry.entry
+                })
+                .collect(),
+            messages,
+            permalinks: HashMap::default(),
+            remote_url: None,
+        })
+    }
+}
+
+fn p4(repository_root: &Path, arguments: &[&str]) -> Result<Output> {
+    Command::new("p4")
+        .current_dir(repository_root)
+        .args(arguments)
+        .output()
+        .context("failed to run p4")
+}
+
+/// Returns the id standing for a changelist in blames: its number, padded so that the short form of the id shows
+/// it when it has up to seven digits.
+fn change_oid(change: u32) -> Result<Oid> {
+    let change = format!("{change:0>7}");
+    Oid::from_str(&format!("{change:0<40}"))
+}
+
+fn parse_fstat_action(output: &str) -> Option<GitFileStatus> {
+    let action = output
+        .lines()
+        .find_map(|line| line.strip_prefix("... action "))?;
+    match action.trim() {
+        "add" | "branch" | "move/add" => Some(GitFileStatus::Added),
+        _ => Some(GitFileStatus::Modified),
+    }
+}
+
+/// A blame entry along with the changelist it stands for.
+struct AnnotatedEntry {
+    entry: BlameEntry,
+    original_change: u32,
+}
+
+/// Parses the output of `p4 annotate -c -u -q`, whose lines look like `12345: jane 2024/01/31 contents`, merging
+/// the consecutive lines of the same changelists.
+fn parse_annotate(output: &str, filename: &str) -> Result<Vec<AnnotatedEntry>> {
+    let mut entries = Vec::<AnnotatedEntry>::new();
+    for (row, line) in output.lines().enumerate() {
+        let row = row as u32;
+        let (change, rest) = line
+            .split_once(": ")
+            .with_context(|| format!("invalid p4 annotate line {line:?}"))?;
+        let change = change.trim().parse::<u32>()?;
+        if let Some(last) = entries.last_mut() {
+            if last.original_change == change && last.entry.range.end == row {
+                last.entry.range.end += 1;
+                continue;
+            }
+        }
+
+        let mut fields = rest.splitn(3, ' ');
+        let user = fields.next().map(ToString::to_string);
+        let time = fields.next().and_then(parse_date);
+        entries.push(AnnotatedEntry {
+            entry: BlameEntry {
+                sha: change_oid(change)?,
+                range: row..row + 1,
+                original_line_number: row + 1,
+                author: user.clone(),
+                author_mail: None,
+                author_time: time,
+                author_tz: time.map(|_| "+0000".to_string()),
+                committer: user,
+                committer_mail: None,
+                committer_time: time,
+                committer_tz: time.map(|_| "+0000".to_string()),
+                summary: None,
+                previous: None,
+                filename: filename.to_string(),
+            },
+            original_change: change,
+        });
+    }
+    Ok(entries)
+}
+
+/// Parses a date like `2024/01/31` into the time of its start, in seconds since the epoch.
+fn parse_date(date: &str) -> Option<i64> {
+    let mut parts = date.split('/').map(|part| part.parse::<i64>().ok());
+    let (year, month, day) = (parts.next()??, parts.next()??, parts.next()??);
+    // The number of days since the epoch of the date in the proleptic Gregorian calendar.
+    let year = if month <= 2 { year - 1 } else { year };
+    let era = year.div_euclid(400);
+    let year_of_era = year - era * 400;
+    let day_of_year = (153 * ((month + 9) % 12) + 2) / 5 + day - 1;
+    let day_of_era = year_of_era * 365 + year_of_era / 4 - year_of_era / 100 + day_of_year;
+    Some((era * 146097 + day_of_era - 719468) * 86400)
+}
+
+/// Parses the description of a changelist from the output of `p4 describe -s`, where its lines are indented by
+/// a tab between the header of the changelist and its affected files.
+fn parse_describe(output: &str) -> Option<String> {
+    let description = output
+        .lines()
+        .skip(1)
+        .take_while(|line| !line.starts_with("Affected files"))
+        .filter_map(|line| line.strip_prefix('\t'))
+        .collect::<Vec<_>>()
+        .join("\n");
+    let description = description.trim();
+    (!description.is_empty()).then(|| description.to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use git::repository::GitFileStatus;
+
+    use super::{change_oid, parse_annotate, parse_date, parse_describe, parse_fstat_action};
+
+    #[test]
+    fn test_parse_annotate() {
+        let output = "\
+            12: jane 2024/01/31 fn main() {\n\
+            12: jane 2024/01/31     run();\n\
+            40: john 2024/03/01     stop();\n\
+            12: jane 2024/01/31 }\n";
+        let entries = parse_annotate(output, "src/main.rs").unwrap();
+        let summary = entries
+            .iter()
+            .map(|entry| {
+                (
+                    entry.original_change,
+                    entry.entry.range.clone(),
+                    entry.entry.author.clone().unwrap(),
+                    entry.entry.author_time,
+                )
+            })
+            .collect::<Vec<_>>();
+        assert_eq!(
+            summary,
+            vec![
+                (12, 0..2, "jane".to_string(), Some(1706659200)),
+                (40, 2..3, "john".to_string(), Some(1709251200)),
+                (12, 3..4, "jane".to_string(), Some(1706659200)),
+            ]
+        );
+        assert_eq!(change_oid(40).unwrap().display_short(), "0000040");
+        assert_eq!(parse_date("1970/01/01"), Some(0));
+    }
+
+    #[test]
+    fn test_parse_describe_and_fstat() {
+        let output = "Change 40 by john@laptop on 2024/03/01 10:00:00\n\n\tStop after running.\n\tSee the design.\n\nAffected files ...\n\n... //depot/src/main.rs#2 edit\n";
+        assert_eq!(
+            parse_describe(output),
+            Some("Stop after running.\nSee the design.".to_string())
+        );
+
+        assert_eq!(
+            parse_fstat_action("... action edit\n"),
+            Some(GitFileStatus::Modified)
+        );
+        assert_eq!(
+            parse_fstat_action("... action add\n"),
+            Some(GitFileStatus::Added)
+        );
+        assert_eq!(parse_fstat_action(""), None);
+    }
+}
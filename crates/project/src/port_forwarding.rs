@@ -0,0 +1,280 @@
+//! Port forwarding of remote projects: the ports of the dev server of a project reached over SSH are forwarded to
+//! this machine with `ssh -L`, running the SSH command of the dev server like its terminals do. Ports are forwarded
+//! on request, or automatically when a terminal of the project prints that a server is listening on them.
+
+use std::{collections::BTreeMap, fs::File, io::Write, net::TcpListener, time::Duration};
+
+use anyhow::Context as _;
+use collections::{HashMap, HashSet};
+use futures::AsyncReadExt;
+use gpui::{Entity, EntityId, Model, ModelContext, Subscription, Task};
+use settings::Settings;
+use smol::process::{Command, Stdio};
+use task::TerminalWorkDir;
+use terminal::Terminal;
+use util::ResultExt;
+
+use crate::{project_settings::ProjectSettings, terminals::add_environment_path, Project};
+
+/// How long the output of a terminal has to stay unchanged before it's scanned for listening ports again.
+const PORT_DETECTION_DEBOUNCE: Duration = Duration::from_millis(500);
+
+#[derive(Default)]
+pub(crate) struct PortForwards {
+    forwards: BTreeMap<u16, PortForward>,
+    /// The remote ports whose forwarding was stopped or failed, which aren't forwarded automatically again.
+    dismissed_ports: HashSet<u16>,
+    terminal_watchers: HashMap<EntityId, TerminalPortsWatcher>,
+}
+
+struct PortForward {
+    port: ForwardedPort,
+    _tunnel: Task<()>,
+}
+
+struct TerminalPortsWatcher {
+    label: String,
+    scan: Task<()>,
+    _subscriptions: [Subscription; 2],
+}
+
+/// A port of the remote host that is forwarded to a port of this machine.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct ForwardedPort {
+    pub remote_port: u16,
+    pub local_port: u16,
+    /// The label of the terminal that printed the port, if it was forwarded automatically.
+    pub detected_in: Option<String>,
+}
+
+impl Project {
+    /// Whether the ports of the project can be forwarded: it's a remote project with an SSH connection.
+    pub fn can_forward_ports(&self, cx: &gpui::AppContext) -> bool {
+        self.port_forwarding_ssh_command(cx).is_some()
+    }
+
+    /// The ports of the remote host that are forwarded, by remote port.
+    pub fn forwarded_ports(&self) -> impl Iterator<Item = &ForwardedPort> {
+        self.port_forwards
+            .forwards
+            .values()
+            .map(|forward| &forward.port)
+    }
+
+    /// Forwards a port of the remote host to the given local port, or to the same port if it's free and to any
+    /// free port otherwise. Returns the local port.
+    pub fn forward_port(
+        &mut self,
+        remote_port: u16,
+        local_port: Option<u16>,
+        cx: &mut ModelContext<Self>,
+    ) -> anyhow::Result<u16> {
+        self.forward_port_internal(remote_port, local_port, None, cx)
+    }
+
+    /// Stops forwarding a port of the remote host, which then isn't forwarded automatically anymore.
+    pub fn stop_forwarding_port(&mut self, remote_port: u16, cx: &mut ModelContext<Self>) {
+        self.port_forwards.dismissed_ports.insert(remote_port);
+        if self.port_forwards.forwards.remove(&remote_port).is_some() {
+            cx.notify();
+        }
+    }
+
+    fn port_forwarding_ssh_command(&self, cx: &gpui::AppContext) -> Option<String> {
+        match self.terminal_work_dir_for(None, cx)? {
+            TerminalWorkDir::Ssh { ssh_command, .. } => Some(ssh_command),
+            TerminalWorkDir::Local(_) => None,
+        }
+    }
+
+    fn forward_port_internal(
+        &mut self,
+        remote_port: u16,
+        local_port: Option<u16>,
+        detected_in: Option<String>,
+        cx: &mut ModelContext<Self>,
+    ) -> anyhow::Result<u16> {
+        if let Some(forward) = self.port_forwards.forwards.get(&remote_port) {
+            return Ok(forward.port.local_port);
+        }
+        let ssh_command = self
+            .port_forwarding_ssh_command(cx)
+            .context("ports can only be forwarded for remote projects with an SSH connection")?;
+        let local_port = match local_port {
+            Some(local_port) => local_port,
+            None => free_local_port(remote_port)?,
+        };
+        let (mut command, wrapper_dir) =
+            port_forwarding_command(&ssh_command, remote_port, local_port)?;
+        let mut child = command
+            .spawn()
+            .with_context(|| format!("forwarding port {remote_port}"))?;
+
+        log::info!("Forwarding remote port {remote_port} to local port {local_port}");
+        let tunnel = cx.spawn(|project, mut cx| async move {
+            let _wrapper_dir = wrapper_dir;
+            let mut stderr = String::new();
+            if let Some(mut child_stderr) = child.stderr.take() {
+                child_stderr.read_to_string(&mut stderr).await.log_err();
+            }
+            let status = child.status().await;
+            log::error!(
+                "Forwarding of remote port {remote_port} stopped ({status:?}): {}",
+                stderr.trim()
+            );
+            project
+                .update(&mut cx, |project, cx| {
+                    project.port_forwards.dismissed_ports.insert(remote_port);
+                    project.port_forwards.forwards.remove(&remote_port);
+                    cx.notify();
+                })
+                .ok();
+        });
+        self.port_forwards.dismissed_ports.remove(&remote_port);
+        self.port_forwards.forwards.insert(
+            remote_port,
+            PortForward {
+                port: ForwardedPort {
+                    remote_port,
+                    local_port,
+                    detected_in,
+                },
+                _tunnel: tunnel,
+            },
+        );
+        cx.notify();
+        Ok(local_port)
+    }
+
+    /// Forwards the ports that the output of a terminal of a remote project says servers listen on, as it prints them.
+    pub(crate) fn detect_terminal_ports(
+        &mut self,
+        terminal: &Model<Terminal>,
+        label: String,
+        cx: &mut ModelContext<Self>,
+    ) {
+        if !ProjectSettings::get_global(cx).port_forwarding.auto_forward
+            || !self.can_forward_ports(cx)
+        {
+            return;
+        }
+        let terminal_id = terminal.entity_id();
+        let subscriptions = [
+            cx.subscribe(terminal, |project, terminal, event, cx| {
+                if matches!(event, terminal::Event::Wakeup) {
+                    project.schedule_terminal_ports_scan(terminal, cx);
+                }
+            }),
+            cx.observe_release(terminal, move |project, _, _| {
+                project.port_forwards.terminal_watchers.remove(&terminal_id);
+            }),
+        ];
+        self.port_forwards.terminal_watchers.insert(
+            terminal_id,
+            TerminalPortsWatcher {
+                label,
+                scan: Task::ready(()),
+                _subscriptions: subscriptions,
+            },
+        );
+    }
+
+    fn schedule_terminal_ports_scan(
+        &mut self,
+        terminal: Model<Terminal>,
+        cx: &mut ModelContext<Self>,
+    ) {
+        let Some(watcher) = self
+            .port_forwards
+            .terminal_watchers
+            .get_mut(&terminal.entity_id())
+        else {
+            return;
+        };
+        let label = watcher.label.clone();
+        watcher.scan = cx.spawn(|project, mut cx| async move {
+            cx.background_executor()
+                .timer(PORT_DETECTION_DEBOUNCE)
+                .await;
+            let Some(output) = terminal
+                .update(&mut cx, |terminal, cx| terminal.output_text(cx))
+                .ok()
+            else {
+                return;
+            };
+            let output = output.await;
+            let ports = cx
+                .background_executor()
+                .spawn(async move { terminal::listening_ports(&output) })
+                .await;
+            project
+                .update(&mut cx, |project, cx| {
+                    for port in ports {
+                        if project.port_forwards.forwards.contains_key(&port)
+                            || project.port_forwards.dismissed_ports.contains(&port)
+                        {
+                            continue;
+                        }
+                        project
+                            .forward_port_internal(port, None, Some(label.clone()), cx)
+                            .log_err();
+                    }
+                })
+                .ok();
+        });
+    }
+}
+
+/// The local port to forward a remote port to: the same one if it's free, any free one otherwise.
+fn free_local_port(remote_port: u16) -> anyhow::Result<u16> {
+    if TcpListener::bind(("127.0.0.1", remote_port)).is_ok() {
+        return Ok(remote_port);
+    }
+    let listener = TcpListener::bind(("127.0.0.1", 0)).context("finding a free local port")?;
+    Ok(listener.local_addr()?.port())
+}
+
+/// The SSH command of the dev server, run with the `ssh` of a wrapper script that adds the port forwarding options,
+/// so that commands wrapping `ssh` like `gh cs ssh` work too. The directory of the wrapper has to be kept while the
+/// command runs.
+fn port_forwarding_command(
+    ssh_command: &str,
+    remote_port: u16,
+    local_port: u16,
+) -> anyhow::Result<(Command, tempfile::TempDir)> {
+    let real_ssh = which::which("ssh")?;
+    let wrapper_dir = tempfile::tempdir()?;
+    let wrapper_path = wrapper_dir.path().join("ssh");
+    let mut wrapper = File::create(&wrapper_path)?;
+    write!(
+        &mut wrapper,
+        "#!/bin/sh\nexec {} \"$@\" -N -o ExitOnForwardFailure=yes -L {local_port}:localhost:{remote_port}",
+        shlex::try_quote(&real_ssh.to_string_lossy())?,
+    )?;
+    drop(wrapper);
+
+    // todo(windows)
+    #[cfg(not(target_os = "windows"))]
+    std::fs::set_permissions(
+        &wrapper_path,
+        smol::fs::unix::PermissionsExt::from_mode(0o755),
+    )?;
+
+    let mut env = HashMap::default();
+    add_environment_path(&mut env, wrapper_dir.path())?;
+    let mut args = shlex::split(ssh_command).unwrap_or_default();
+    let program = if args.is_empty() {
+        "ssh".to_string()
+    } else {
+        args.remove(0)
+    };
+    let mut command = Command::new(program);
+    command
+        .args(args)
+        .envs(env)
+        .stdin(Stdio::null())
+        .stdout(Stdio::null())
+        .stderr(Stdio::piped())
+        .kill_on_drop(true);
+    Ok((command, wrapper_dir))
+}
@@ -29,9 +29,14 @@ use crate::{
 pub fn prettier_plugins_for_language(
     language_settings: &LanguageSettings,
 ) -> Option<&HashSet<String>> {
-    match &language_settings.formatter {
-        Formatter::Prettier { .. } | Formatter::Auto => Some(&language_settings.prettier.plugins),
-        Formatter::LanguageServer | Formatter::External { .. } | Formatter::CodeActions(_) => None,
+    uses_prettier(&language_settings.formatter).then_some(&language_settings.prettier.plugins)
+}
+
+fn uses_prettier(formatter: &Formatter) -> bool {
+    match formatter {
+        Formatter::Prettier { .. } | Formatter::Auto => true,
+        Formatter::LanguageServer | Formatter::External { .. } | Formatter::CodeActions(_) => false,
+        Formatter::Chain(formatters) => formatters.iter().any(uses_prettier),
     }
 }
 
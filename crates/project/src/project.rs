@@ -1,12 +1,50 @@
+mod buffer_commands;
+mod buffer_encoding;
+mod code_actions_on_save;
+mod code_cells;
+mod code_index;
 pub mod connection_manager;
 pub mod debounced_delay;
+mod diagnostic_sources;
+mod document_colors;
+mod document_links;
+mod document_symbols;
+mod environment_watcher;
+mod file_operations;
+mod fix_all;
+mod folding_ranges;
+mod formatter_chain;
+mod inline_values;
+mod language_server_pins;
+mod language_server_supervision;
 pub mod lsp_command;
 pub mod lsp_ext_command;
+mod lsp_merging;
+mod lsp_response_cache;
+mod mercurial;
+mod partial_results;
+mod perforce;
+mod port_forwarding;
 mod prettier_support;
 pub mod project_settings;
+mod pull_diagnostics;
+mod rename_preview;
+mod request_timeouts;
 pub mod search;
+mod semantic_tokens;
+mod show_document;
+mod signature_help;
+mod symbol_index;
+mod task_diagnostics;
 mod task_inventory;
+mod task_variables;
 pub mod terminals;
+mod vale;
+mod vcs_providers;
+mod virtual_documents;
+mod workspace_configuration;
+mod workspace_edit_conflicts;
+mod worktree_trust;
 
 #[cfg(test)]
 mod project_tests;
@@ -19,8 +57,16 @@ use client::{
     TypedEnvelope, UserStore,
 };
 use clock::ReplicaId;
+use code_cells::CodeCellKernels;
+use code_index::CodeIndexes;
 use collections::{btree_map, hash_map, BTreeMap, HashMap, HashSet, VecDeque};
 use debounced_delay::DebouncedDelay;
+use diagnostic_sources::DiagnosticSources;
+use document_colors::DocumentColorsCache;
+use document_links::DocumentLinksCache;
+use document_symbols::DocumentSymbolsCache;
+use environment_watcher::EnvironmentWatcher;
+use folding_ranges::FoldingRangesCache;
 use futures::{
     channel::{
         mpsc::{self, UnboundedReceiver},
@@ -30,7 +76,7 @@ use futures::{
     prelude::future::BoxFuture,
     select,
     stream::FuturesUnordered,
-    AsyncWriteExt, Future, FutureExt, StreamExt, TryFutureExt,
+    Future, FutureExt, StreamExt, TryFutureExt,
 };
 use fuzzy::CharBag;
 use git::{blame::Blame, repository::GitRepository};
@@ -39,44 +85,52 @@ use gpui::{
     AnyModel, AppContext, AsyncAppContext, BackgroundExecutor, BorrowAppContext, Context, Entity,
     EventEmitter, Model, ModelContext, PromptLevel, SharedString, Task, WeakModel, WindowContext,
 };
-use http::{HttpClient, Url};
+use http::{HttpClient, HttpClientWithUrl, Url};
 use itertools::Itertools;
 use language::{
     language_settings::{
         language_settings, AllLanguageSettings, FormatOnSave, Formatter, InlayHintKind,
+        LanguageSettings, LintTrigger,
     },
     markdown, point_to_lsp, prepare_completion_documentation,
     proto::{
         deserialize_anchor, deserialize_line_ending, deserialize_version, serialize_anchor,
         serialize_line_ending, serialize_version, split_operations,
     },
-    range_from_lsp, Bias, Buffer, BufferSnapshot, CachedLspAdapter, Capability, CodeLabel,
-    ContextProvider, Diagnostic, DiagnosticEntry, DiagnosticSet, Diff, Documentation,
-    Event as BufferEvent, File as _, Language, LanguageRegistry, LanguageServerName, LocalFile,
-    LspAdapterDelegate, Operation, Patch, PendingLanguageServer, PointUtf16, TextBufferSnapshot,
-    ToOffset, ToPointUtf16, Transaction, Unclipped,
+    range_from_lsp, ArtifactDownloadSettings, Bias, Buffer, BufferSnapshot, CachedLspAdapter,
+    Capability, CodeLabel, ContextProvider, Diagnostic, DiagnosticEntry, DiagnosticSet, Diff,
+    Documentation, Encoding, Event as BufferEvent, File as _, Language, LanguageRegistry,
+    LanguageServerName, LocalFile, LspAdapterDelegate, Operation, Patch, PendingLanguageServer,
+    PointUtf16, TextBufferSnapshot, ToOffset, ToPointUtf16, Transaction, Unclipped,
 };
+use language_server_supervision::{LanguageServerCrash, LanguageServerSupervision};
 use log::error;
 use lsp::{
     CompletionContext, DiagnosticSeverity, DiagnosticTag, DidChangeWatchedFilesRegistrationOptions,
     DocumentHighlightKind, Edit, FileSystemWatcher, InsertTextFormat, LanguageServer,
     LanguageServerBinary, LanguageServerId, LspRequestFuture, MessageActionItem, OneOf,
-    ServerCapabilities, ServerHealthStatus, ServerStatus, TextEdit, WorkDoneProgressCancelParams,
+    RequestStats, ServerCapabilities, ServerHealthStatus, ServerStatus, TextEdit,
+    WorkDoneProgressCancelParams,
 };
 use lsp_command::*;
+use lsp_response_cache::LspResponseCache;
 use node_runtime::NodeRuntime;
 use parking_lot::{Mutex, RwLock};
+use partial_results::PartialResults;
 use paths::{
     local_settings_file_relative_path, local_tasks_file_relative_path,
     local_vscode_tasks_file_relative_path,
 };
+use port_forwarding::PortForwards;
 use postage::watch;
 use prettier_support::{DefaultPrettier, PrettierInstance};
 use project_settings::{LspSettings, ProjectSettings};
+use pull_diagnostics::{PullDiagnostics, RawProgress};
 use rand::prelude::*;
 use rpc::{ErrorCode, ErrorExt as _};
-use search::SearchQuery;
+use search::{SearchLimits, SearchQuery};
 use search_history::SearchHistory;
+use semantic_tokens::SemanticTokensCache;
 use serde::Serialize;
 use settings::{watch_config_file, Settings, SettingsLocation, SettingsStore};
 use sha2::{Digest, Sha256};
@@ -103,10 +157,12 @@ use std::{
     },
     time::{Duration, Instant},
 };
+use symbol_index::{SymbolIndex, SYMBOL_INDEX_SERVER_NAME};
 use task::{
     static_source::{StaticSource, TrackedFile},
     RevealStrategy, TaskContext, TaskTemplate, TaskVariables, VariableName,
 };
+use task_diagnostics::TaskDiagnostics;
 use terminals::Terminals;
 use text::{Anchor, BufferId, LineEnding};
 use unicase::UniCase;
@@ -114,15 +170,37 @@ use util::{
     debug_panic, defer, maybe, merge_json_value_into, parse_env_output, post_inc,
     NumericPrefixWithSuffix, ResultExt, TryFutureExt as _,
 };
+use vcs_providers::VcsStatuses;
+use virtual_documents::VirtualDocuments;
+use workspace_edit_conflicts::WorkspaceEditConflictResolution;
 use worktree::{CreatedEntry, RemoteWorktreeClient, Snapshot, Traversal};
+use worktree_trust::WorktreeTrust;
 
+pub use code_cells::{
+    code_cells, CellExecution, CellExecutionStatus, CellOutput, CodeCell, DISPLAY_PREFIX,
+};
+pub use diagnostic_sources::{DiagnosticFix, DiagnosticSource, SourceDiagnostic};
+pub use document_colors::{BufferDocumentColors, ColorPresentation, DocumentColor};
+pub use document_links::{BufferDocumentLinks, DocumentLink};
+pub use document_symbols::{BufferDocumentSymbols, DocumentSymbol};
+pub use fix_all::FixAllReport;
+pub use folding_ranges::{BufferFoldingRanges, FoldingRange, FoldingRangeKind};
 pub use fs::*;
+pub use inline_values::{InlineValue, InlineValueKind};
 pub use language::Location;
+pub use partial_results::StreamedResults;
+pub use port_forwarding::ForwardedPort;
 #[cfg(any(test, feature = "test-support"))]
 pub use prettier::FORMAT_SUFFIX as TEST_PRETTIER_FORMAT_SUFFIX;
+pub use rename_preview::PendingRenameEdit;
+pub use semantic_tokens::{BufferSemanticTokens, SemanticToken};
+pub use signature_help::{
+    SignatureHelp, SignatureHelpTrigger, SignatureHelpTriggers, SignatureInformation,
+};
 pub use task_inventory::{
     BasicContextProvider, ContextProviderWithTasks, Inventory, TaskSourceKind,
 };
+pub use vcs_providers::{VcsProvider, VcsProviderRegistry};
 pub use worktree::{
     Entry, EntryKind, File, LocalWorktree, PathChange, ProjectEntryId, RepositoryEntry,
     UpdatedEntriesSet, UpdatedGitRepositoriesSet, Worktree, WorktreeId, WorktreeSettings,
@@ -130,7 +208,6 @@ pub use worktree::{
 };
 
 const MAX_SERVER_REINSTALL_ATTEMPT_COUNT: u64 = 4;
-const SERVER_REINSTALL_DEBOUNCE_TIMEOUT: Duration = Duration::from_secs(1);
 const SERVER_LAUNCHING_BEFORE_SHUTDOWN_TIMEOUT: Duration = Duration::from_secs(5);
 pub const SERVER_PROGRESS_THROTTLE_TIMEOUT: Duration = Duration::from_millis(100);
 
@@ -169,7 +246,9 @@ pub struct Project {
     supplementary_language_servers:
         HashMap<LanguageServerId, (LanguageServerName, Arc<LanguageServer>)>,
     language_servers: HashMap<LanguageServerId, LanguageServerState>,
-    language_server_ids: HashMap<(WorktreeId, LanguageServerName), LanguageServerId>,
+    /// The local language servers by worktree, name and root: servers rooted at sub-directories of a worktree have
+    /// an instance for each of them, and the others are rooted at the root of the worktree, an empty path.
+    language_server_ids: HashMap<(WorktreeId, LanguageServerName, Arc<Path>), LanguageServerId>,
     language_server_statuses: BTreeMap<LanguageServerId, LanguageServerStatus>,
     last_formatting_failure: Option<String>,
     last_workspace_edits_by_language_server: HashMap<LanguageServerId, ProjectTransaction>,
@@ -225,10 +304,29 @@ pub struct Project {
     default_prettier: DefaultPrettier,
     prettiers_per_worktree: HashMap<WorktreeId, HashSet<Option<PathBuf>>>,
     prettier_instances: HashMap<PathBuf, PrettierInstance>,
+    pull_diagnostics: PullDiagnostics,
+    semantic_tokens: SemanticTokensCache,
+    document_links: DocumentLinksCache,
+    document_colors: DocumentColorsCache,
+    code_cell_kernels: CodeCellKernels,
+    lsp_response_cache: LspResponseCache,
+    task_diagnostics: TaskDiagnostics,
+    port_forwards: PortForwards,
+    diagnostic_sources: DiagnosticSources,
+    symbol_index: SymbolIndex,
+    folding_ranges: FoldingRangesCache,
+    document_symbols: DocumentSymbolsCache,
+    vcs_statuses: VcsStatuses,
+    worktree_trust: WorktreeTrust,
+    partial_results: PartialResults,
+    environment_watcher: EnvironmentWatcher,
+    language_server_supervision: LanguageServerSupervision,
+    code_indexes: CodeIndexes,
     tasks: Model<Inventory>,
     hosted_project_id: Option<ProjectId>,
     dev_server_project_id: Option<client::DevServerProjectId>,
     search_history: SearchHistory,
+    virtual_documents: VirtualDocuments,
 }
 
 pub enum LanguageServerToQuery {
@@ -354,8 +452,21 @@ pub enum Event {
     CollaboratorJoined(proto::PeerId),
     CollaboratorLeft(proto::PeerId),
     RefreshInlayHints,
+    RefreshSemanticTokens,
     RevealInProjectPanel(ProjectEntryId),
     SnippetEdit(BufferId, Vec<(lsp::Range, Snippet)>),
+    /// The edits of a file that conflicted with its unsaved changes, for the user to review before applying them.
+    ReviewConflictingEdits(Vec<PendingRenameEdit>),
+    /// A language server asked to show the buffer with `window/showDocument`.
+    ShowDocument {
+        buffer: Model<Buffer>,
+        selection: Option<Range<Anchor>>,
+        take_focus: bool,
+    },
+    CodeCellOutputsChanged(BufferId),
+    RefreshInlineValues,
+    /// Something configured by the worktree was held back because the worktree isn't trusted.
+    WorktreeTrustRequired(WorktreeId),
 }
 
 pub enum LanguageServerState {
@@ -375,6 +486,29 @@ pub struct LanguageServerStatus {
     pub pending_work: BTreeMap<String, LanguageServerProgress>,
     pub has_pending_diagnostic_updates: bool,
     progress_tokens: HashSet<String>,
+    /// The characters after which the server shows signature help, shared with the guests of the project.
+    pub signature_help_triggers: SignatureHelpTriggers,
+}
+
+impl LanguageServerStatus {
+    fn to_proto(&self, server_id: LanguageServerId) -> proto::LanguageServer {
+        proto::LanguageServer {
+            id: server_id.0 as u64,
+            name: self.name.clone(),
+            signature_help_trigger_characters: self
+                .signature_help_triggers
+                .trigger_characters
+                .iter()
+                .cloned()
+                .collect(),
+            signature_help_retrigger_characters: self
+                .signature_help_triggers
+                .retrigger_characters
+                .iter()
+                .cloned()
+                .collect(),
+        }
+    }
 }
 
 #[derive(Clone, Debug, Serialize)]
@@ -455,6 +589,15 @@ impl std::fmt::Debug for Completion {
     }
 }
 
+/// Completions, along with whether they're only some of the ones of their providers.
+#[derive(Debug, Default)]
+pub struct CompletionResponse {
+    pub completions: Vec<Completion>,
+    /// Whether the providers returned only some of their completions, so that they must be queried again as the
+    /// word being completed changes, rather than filtering these.
+    pub is_incomplete: bool,
+}
+
 /// A completion provided by a language server
 #[derive(Clone, Debug)]
 struct CoreCompletion {
@@ -464,6 +607,13 @@ struct CoreCompletion {
     lsp_completion: lsp::CompletionItem,
 }
 
+/// The completions of a language server.
+#[derive(Debug, Default)]
+struct CoreCompletionResponse {
+    completions: Vec<CoreCompletion>,
+    is_incomplete: bool,
+}
+
 /// A code action provided by a language server.
 #[derive(Clone, Debug)]
 pub struct CodeAction {
@@ -502,6 +652,17 @@ pub struct InlayHintLabelPart {
     pub value: String,
     pub tooltip: Option<InlayHintLabelPartTooltip>,
     pub location: Option<(LanguageServerId, lsp::Location)>,
+    /// The command to run when the part is clicked.
+    pub command: Option<(LanguageServerId, InlayHintLabelPartCommand)>,
+}
+
+/// A `workspace/executeCommand` command of an inlay hint label part.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct InlayHintLabelPartCommand {
+    pub title: String,
+    pub command: String,
+    /// The arguments of the command, serialized as JSON.
+    pub arguments: Vec<String>,
 }
 
 #[derive(Debug, Clone, PartialEq, Eq)]
@@ -528,6 +689,34 @@ pub struct LocationLink {
     pub target: Location,
 }
 
+/// A type from the `textDocument/prepareTypeHierarchy` response, or one of its super/subtypes.
+#[derive(Debug, Clone)]
+pub struct TypeHierarchyItem {
+    pub location: Location,
+    /// The item as reported by the language server, sent back to it to query the super/subtypes.
+    pub lsp_item: lsp::TypeHierarchyItem,
+    /// The language server that reported the item.
+    pub language_server_id: LanguageServerId,
+}
+
+/// A function from the `textDocument/prepareCallHierarchy` response, or one of its callers/callees.
+#[derive(Debug, Clone)]
+pub struct CallHierarchyItem {
+    pub location: Location,
+    /// The item as reported by the language server, sent back to it to query the calls.
+    pub lsp_item: lsp::CallHierarchyItem,
+    /// The language server that reported the item.
+    pub language_server_id: LanguageServerId,
+}
+
+#[derive(Debug, Clone)]
+pub struct CallHierarchyCall {
+    /// The caller for the incoming calls, the callee for the outgoing ones.
+    pub item: CallHierarchyItem,
+    /// Ranges of the calls, always in the buffer of the caller.
+    pub ranges: Vec<Range<language::Anchor>>,
+}
+
 #[derive(Debug)]
 pub struct DocumentHighlight {
     pub range: Range<language::Anchor>,
@@ -544,6 +733,9 @@ pub struct Symbol {
     pub kind: lsp::SymbolKind,
     pub range: Range<Unclipped<PointUtf16>>,
     pub signature: [u8; 32],
+    /// Set for the symbols the language server sent without a range, which has to be requested
+    /// with [`Project::resolve_symbol`] before the symbol is opened.
+    pub unresolved_symbol: Option<Arc<lsp::WorkspaceSymbol>>,
 }
 
 #[derive(Clone, Debug)]
@@ -555,6 +747,25 @@ struct CoreSymbol {
     pub kind: lsp::SymbolKind,
     pub range: Range<Unclipped<PointUtf16>>,
     pub signature: [u8; 32],
+    pub unresolved_symbol: Option<Arc<lsp::WorkspaceSymbol>>,
+}
+
+/// The name, kind and location of a symbol reported by `workspace/symbol`, along with the symbol itself if its
+/// range has to be resolved.
+type LspWorkspaceSymbol = (
+    String,
+    lsp::SymbolKind,
+    lsp::Location,
+    Option<Arc<lsp::WorkspaceSymbol>>,
+);
+
+/// A language server to query with `workspace/symbol`, and the worktree the symbols it reports are relative to.
+struct WorkspaceSymbolsServer {
+    adapter: Arc<CachedLspAdapter>,
+    language: Arc<Language>,
+    server: Arc<LanguageServer>,
+    worktree: WeakModel<Worktree>,
+    worktree_abs_path: Arc<Path>,
 }
 
 #[derive(Clone, Debug, PartialEq)]
@@ -601,6 +812,17 @@ enum FormatOperation {
     Prettier(Diff),
 }
 
+/// A buffer being formatted, with what its formatters need.
+struct FormatTarget<'a> {
+    buffer: &'a Model<Buffer>,
+    buffer_abs_path: Option<&'a Path>,
+    primary_language_server: Option<&'a Arc<LanguageServer>>,
+    adapters_and_servers: &'a Vec<(Arc<CachedLspAdapter>, Arc<LanguageServer>)>,
+    settings: &'a LanguageSettings,
+    /// Whether only the lines changed since the last Git commit should be formatted.
+    changed_lines_only: bool,
+}
+
 impl FormatTrigger {
     fn from_proto(value: i32) -> FormatTrigger {
         match value {
@@ -649,6 +871,7 @@ impl Project {
     pub fn init(client: &Arc<Client>, cx: &mut AppContext) {
         connection_manager::init(client.clone(), cx);
         Self::init_settings(cx);
+        VcsProviderRegistry::default_global(cx);
 
         client.add_model_message_handler(Self::handle_add_collaborator);
         client.add_model_message_handler(Self::handle_update_project_collaborator);
@@ -660,6 +883,7 @@ impl Project {
         client.add_model_message_handler(Self::handle_update_project);
         client.add_model_message_handler(Self::handle_unshare_project);
         client.add_model_message_handler(Self::handle_create_buffer_for_peer);
+        client.add_model_message_handler(Self::handle_lsp_partial_result);
         client.add_model_message_handler(Self::handle_update_buffer_file);
         client.add_model_request_handler(Self::handle_update_buffer);
         client.add_model_message_handler(Self::handle_update_diagnostic_summary);
@@ -667,6 +891,7 @@ impl Project {
         client.add_model_message_handler(Self::handle_update_worktree_settings);
         client.add_model_request_handler(Self::handle_create_project_entry);
         client.add_model_request_handler(Self::handle_rename_project_entry);
+        client.add_model_request_handler(Self::handle_cancel_language_server_work);
         client.add_model_request_handler(Self::handle_copy_project_entry);
         client.add_model_request_handler(Self::handle_delete_project_entry);
         client.add_model_request_handler(Self::handle_expand_project_entry);
@@ -677,6 +902,8 @@ impl Project {
         client.add_model_request_handler(Self::handle_inlay_hints);
         client.add_model_request_handler(Self::handle_resolve_inlay_hint);
         client.add_model_request_handler(Self::handle_refresh_inlay_hints);
+        client.add_model_request_handler(Self::handle_refresh_semantic_tokens);
+        client.add_model_request_handler(Self::handle_refresh_inline_values);
         client.add_model_request_handler(Self::handle_reload_buffers);
         client.add_model_request_handler(Self::handle_synchronize_buffers);
         client.add_model_request_handler(Self::handle_format_buffers);
@@ -686,24 +913,57 @@ impl Project {
         client.add_model_request_handler(Self::handle_lsp_command::<GetDefinition>);
         client.add_model_request_handler(Self::handle_lsp_command::<GetTypeDefinition>);
         client.add_model_request_handler(Self::handle_lsp_command::<GetDocumentHighlights>);
-        client.add_model_request_handler(Self::handle_lsp_command::<GetReferences>);
+        client.add_model_request_handler(Self::handle_get_references);
         client.add_model_request_handler(Self::handle_lsp_command::<PrepareRename>);
         client.add_model_request_handler(Self::handle_lsp_command::<PerformRename>);
         client.add_model_request_handler(Self::handle_search_project);
         client.add_model_request_handler(Self::handle_get_project_symbols);
         client.add_model_request_handler(Self::handle_open_buffer_for_symbol);
+        client.add_model_request_handler(Self::handle_resolve_workspace_symbol);
         client.add_model_request_handler(Self::handle_open_buffer_by_id);
         client.add_model_request_handler(Self::handle_open_buffer_by_path);
         client.add_model_request_handler(Self::handle_open_new_buffer);
         client.add_model_request_handler(Self::handle_save_buffer);
         client.add_model_message_handler(Self::handle_update_diff_base);
+        client.add_model_message_handler(Self::handle_show_document);
         client.add_model_request_handler(Self::handle_lsp_command::<lsp_ext_command::ExpandMacro>);
+        client.add_model_request_handler(Self::handle_lsp_command::<lsp_ext_command::ViewHir>);
+        client.add_model_request_handler(Self::handle_lsp_command::<lsp_ext_command::ViewMir>);
+        client.add_model_request_handler(Self::handle_lsp_command::<lsp_ext_command::RelatedTests>);
+        client.add_model_request_handler(
+            Self::handle_lsp_command::<lsp_ext_command::ReloadWorkspace>,
+        );
+        client.add_model_request_handler(Self::handle_lsp_command::<lsp_ext_command::ClangdAst>);
+        client
+            .add_model_request_handler(Self::handle_lsp_command::<lsp_ext_command::OpenCargoToml>);
+        client.add_model_request_handler(Self::handle_lsp_command::<lsp_ext_command::ParentModule>);
+        client.add_model_request_handler(Self::handle_lsp_command::<lsp_ext_command::Runnables>);
+        client.add_model_request_handler(Self::handle_execute_lsp_command);
+        client.add_model_request_handler(Self::handle_get_document_diagnostics);
+        client.add_model_request_handler(Self::handle_get_semantic_tokens);
+        client.add_model_request_handler(Self::handle_get_document_links);
+        client.add_model_request_handler(Self::handle_get_document_colors);
+        client.add_model_request_handler(Self::handle_get_color_presentations);
+        client.add_model_request_handler(Self::handle_resolve_document_link);
+        client.add_model_request_handler(
+            Self::handle_lsp_command::<lsp_ext_command::RebuildProcMacros>,
+        );
         client.add_model_request_handler(Self::handle_blame_buffer);
         client.add_model_request_handler(Self::handle_multi_lsp_query);
         client.add_model_request_handler(Self::handle_restart_language_servers);
         client.add_model_request_handler(Self::handle_task_context_for_location);
         client.add_model_request_handler(Self::handle_task_templates);
         client.add_model_request_handler(Self::handle_lsp_command::<LinkedEditingRange>);
+        client.add_model_request_handler(Self::handle_lsp_command::<PrepareTypeHierarchy>);
+        client.add_model_request_handler(Self::handle_lsp_command::<GetTypeHierarchySupertypes>);
+        client.add_model_request_handler(Self::handle_lsp_command::<GetTypeHierarchySubtypes>);
+        client.add_model_request_handler(Self::handle_lsp_command::<PrepareCallHierarchy>);
+        client.add_model_request_handler(Self::handle_lsp_command::<GetIncomingCalls>);
+        client.add_model_request_handler(Self::handle_lsp_command::<GetOutgoingCalls>);
+        client.add_model_request_handler(Self::handle_lsp_command::<GetInlineValues>);
+        client.add_model_request_handler(Self::handle_lsp_command::<GetFoldingRanges>);
+        client.add_model_request_handler(Self::handle_lsp_command::<GetDocumentSymbols>);
+        client.add_model_request_handler(Self::handle_lsp_command::<GetSignatureHelp>);
     }
 
     pub fn local(
@@ -776,7 +1036,26 @@ impl Project {
                 tasks,
                 hosted_project_id: None,
                 dev_server_project_id: None,
+                pull_diagnostics: PullDiagnostics::default(),
+                semantic_tokens: SemanticTokensCache::default(),
+                document_links: DocumentLinksCache::default(),
+                document_colors: DocumentColorsCache::default(),
+                code_cell_kernels: CodeCellKernels::default(),
+                lsp_response_cache: LspResponseCache::default(),
+                task_diagnostics: TaskDiagnostics::default(),
+                port_forwards: PortForwards::default(),
+                diagnostic_sources: DiagnosticSources::default(),
+                symbol_index: SymbolIndex::default(),
+                folding_ranges: FoldingRangesCache::default(),
+                document_symbols: DocumentSymbolsCache::default(),
+                vcs_statuses: VcsStatuses::default(),
+                worktree_trust: WorktreeTrust::default(),
+                partial_results: PartialResults::default(),
+                environment_watcher: EnvironmentWatcher::default(),
+                language_server_supervision: LanguageServerSupervision::default(),
+                code_indexes: CodeIndexes::default(),
                 search_history: Self::new_search_history(),
+                virtual_documents: VirtualDocuments::default(),
             }
         })
     }
@@ -906,6 +1185,7 @@ impl Project {
                         (
                             LanguageServerId(server.id as usize),
                             LanguageServerStatus {
+                                signature_help_triggers: SignatureHelpTriggers::from_proto(&server),
                                 name: server.name,
                                 pending_work: Default::default(),
                                 has_pending_diagnostic_updates: false,
@@ -938,7 +1218,26 @@ impl Project {
                     .payload
                     .dev_server_project_id
                     .map(|dev_server_project_id| DevServerProjectId(dev_server_project_id)),
+                pull_diagnostics: PullDiagnostics::default(),
+                semantic_tokens: SemanticTokensCache::default(),
+                document_links: DocumentLinksCache::default(),
+                document_colors: DocumentColorsCache::default(),
+                code_cell_kernels: CodeCellKernels::default(),
+                lsp_response_cache: LspResponseCache::default(),
+                task_diagnostics: TaskDiagnostics::default(),
+                port_forwards: PortForwards::default(),
+                diagnostic_sources: DiagnosticSources::default(),
+                symbol_index: SymbolIndex::default(),
+                folding_ranges: FoldingRangesCache::default(),
+                document_symbols: DocumentSymbolsCache::default(),
+                vcs_statuses: VcsStatuses::default(),
+                worktree_trust: WorktreeTrust::default(),
+                partial_results: PartialResults::default(),
+                environment_watcher: EnvironmentWatcher::default(),
+                language_server_supervision: LanguageServerSupervision::default(),
+                code_indexes: CodeIndexes::default(),
                 search_history: Self::new_search_history(),
+                virtual_documents: VirtualDocuments::default(),
             };
             this.set_role(role, cx);
             for worktree in worktrees {
@@ -1130,8 +1429,11 @@ impl Project {
                 if let Some(language) = buffer_language {
                     if settings.enable_language_server {
                         if let Some(file) = buffer_file {
-                            language_servers_to_start
-                                .push((file.worktree.clone(), Arc::clone(language)));
+                            language_servers_to_start.push((
+                                file.worktree.clone(),
+                                file.path.clone(),
+                                Arc::clone(language),
+                            ));
                         }
                     }
                     language_formatters_to_check
@@ -1146,7 +1448,7 @@ impl Project {
 
         let new_lsp_settings = ProjectSettings::get_global(cx).lsp.clone();
         let current_lsp_settings = &self.current_lsp_settings;
-        for (worktree_id, started_lsp_name) in self.language_server_ids.keys() {
+        for (worktree_id, started_lsp_name, root) in self.language_server_ids.keys() {
             let language = languages.iter().find_map(|l| {
                 let adapter = self
                     .languages
@@ -1162,31 +1464,43 @@ impl Project {
                     tree.update(cx, |tree, cx| tree.root_file(cx).map(|f| f as _))
                 });
                 if !language_settings(Some(language), file.as_ref(), cx).enable_language_server {
-                    language_servers_to_stop.push((*worktree_id, started_lsp_name.clone()));
+                    language_servers_to_stop.push((
+                        *worktree_id,
+                        started_lsp_name.clone(),
+                        root.clone(),
+                    ));
                 } else if let Some(worktree) = worktree {
                     let server_name = &adapter.name.0;
-                    match (
+                    let settings_changed = match (
                         current_lsp_settings.get(server_name),
                         new_lsp_settings.get(server_name),
                     ) {
-                        (None, None) => {}
-                        (Some(_), None) | (None, Some(_)) => {
-                            language_servers_to_restart.push((worktree, Arc::clone(language)));
-                        }
+                        (None, None) => false,
+                        (Some(_), None) | (None, Some(_)) => true,
                         (Some(current_lsp_settings), Some(new_lsp_settings)) => {
-                            if current_lsp_settings != new_lsp_settings {
-                                language_servers_to_restart.push((worktree, Arc::clone(language)));
-                            }
+                            current_lsp_settings != new_lsp_settings
                         }
+                    };
+                    // The servers of all roots of a worktree are restarted together.
+                    if settings_changed
+                        && !language_servers_to_restart.iter().any(
+                            |(restarted_worktree, restarted_language)| {
+                                *restarted_worktree == worktree
+                                    && Arc::ptr_eq(restarted_language, language)
+                            },
+                        )
+                    {
+                        language_servers_to_restart.push((worktree, Arc::clone(language)));
                     }
                 }
             }
         }
         self.current_lsp_settings = new_lsp_settings;
+        self.refresh_request_timeouts(cx);
 
         // Stop all newly-disabled language servers.
-        for (worktree_id, adapter_name) in language_servers_to_stop {
-            self.stop_language_server(worktree_id, adapter_name, cx)
+        for (worktree_id, adapter_name, root) in language_servers_to_stop {
+            self.stop_language_server(worktree_id, adapter_name, root, cx)
                 .detach();
         }
 
@@ -1210,13 +1524,13 @@ impl Project {
         }
 
         // Start all the newly-enabled language servers.
-        for (worktree, language) in language_servers_to_start {
-            self.start_language_servers(&worktree, language, cx);
+        for (worktree, path, language) in language_servers_to_start {
+            self.start_language_servers(&worktree, &path, language, cx);
         }
 
         // Restart all language servers with changed initialization options.
         for (worktree, language) in language_servers_to_restart {
-            self.restart_language_servers(worktree, language, cx);
+            self.restart_language_servers(worktree, Vec::new(), language, cx);
         }
 
         cx.notify();
@@ -1439,6 +1753,9 @@ impl Project {
         cx: &mut ModelContext<Self>,
     ) -> Task<Result<CreatedEntry>> {
         let project_path = project_path.into();
+        if self.is_local() {
+            return self.create_local_entry(project_path, is_directory, cx);
+        }
         let Some(worktree) = self.worktree_for_id(project_path.worktree_id, cx) else {
             return Task::ready(Err(anyhow!(format!(
                 "No worktree for path {project_path:?}"
@@ -1469,6 +1786,9 @@ impl Project {
         new_path: impl Into<Arc<Path>>,
         cx: &mut ModelContext<Self>,
     ) -> Task<Result<CreatedEntry>> {
+        if self.is_local() {
+            return self.rename_local_entry(entry_id, new_path.into(), cx);
+        }
         let Some(worktree) = self.worktree_for_entry(entry_id, cx) else {
             return Task::ready(Err(anyhow!(format!("No worktree for entry {entry_id:?}"))));
         };
@@ -1484,12 +1804,38 @@ impl Project {
         cx: &mut ModelContext<Self>,
     ) -> Option<Task<Result<()>>> {
         let worktree = self.worktree_for_entry(entry_id, cx)?;
+        if self.is_local() {
+            return Some(self.delete_local_entries(vec![entry_id], trash, cx));
+        }
         cx.emit(Event::DeletedEntry(entry_id));
         worktree.update(cx, |worktree, cx| {
             worktree.delete_entry(entry_id, trash, cx)
         })
     }
 
+    /// Deletes the entries one after the other. The language servers of a local project are asked for the edits
+    /// that go along with the deletion of all of them at once.
+    pub fn delete_entries(
+        &mut self,
+        entry_ids: Vec<ProjectEntryId>,
+        trash: bool,
+        cx: &mut ModelContext<Self>,
+    ) -> Task<Result<()>> {
+        if self.is_local() {
+            return self.delete_local_entries(entry_ids, trash, cx);
+        }
+        cx.spawn(move |this, mut cx| async move {
+            for entry_id in entry_ids {
+                this.update(&mut cx, |this, cx| {
+                    this.delete_entry(entry_id, trash, cx)
+                        .ok_or_else(|| anyhow!("No worktree for entry {entry_id:?}"))
+                })??
+                .await?;
+            }
+            Ok(())
+        })
+    }
+
     pub fn expand_entry(
         &mut self,
         worktree_id: WorktreeId,
@@ -1546,10 +1892,7 @@ impl Project {
             self.client
                 .send(proto::StartLanguageServer {
                     project_id,
-                    server: Some(proto::LanguageServer {
-                        id: server_id.0 as u64,
-                        name: status.name.clone(),
-                    }),
+                    server: Some(status.to_proto(*server_id)),
                 })
                 .log_err();
         }
@@ -1729,6 +2072,7 @@ impl Project {
                 (
                     LanguageServerId(server.id as usize),
                     LanguageServerStatus {
+                        signature_help_triggers: SignatureHelpTriggers::from_proto(&server),
                         name: server.name,
                         pending_work: Default::default(),
                         has_pending_diagnostic_updates: false,
@@ -1925,6 +2269,45 @@ impl Project {
         }
     }
 
+    /// Creates a buffer with the given text. The text buffers of local projects are built on the background
+    /// executor, so that large texts, like the expansions of macros, don't block the main thread. The buffers of
+    /// remote projects are created by the host, and the text is inserted once they're replicated.
+    pub fn create_buffer_with_text(
+        &mut self,
+        text: String,
+        language: Option<Arc<Language>>,
+        cx: &mut ModelContext<Self>,
+    ) -> Task<Result<Model<Buffer>>> {
+        if self.is_remote() {
+            let create = self.create_buffer(cx);
+            return cx.spawn(|_, mut cx| async move {
+                let buffer = create.await?;
+                buffer.update(&mut cx, |buffer, cx| {
+                    buffer.edit([(0..0, text)], None, cx);
+                    if let Some(language) = language {
+                        buffer.set_language(Some(language), cx);
+                    }
+                })?;
+                Ok(buffer)
+            });
+        }
+
+        let reservation = cx.reserve_model();
+        let buffer_id = BufferId::from(reservation.entity_id().as_non_zero_u64());
+        let text_buffer = cx
+            .background_executor()
+            .spawn(async move { text::Buffer::new(0, buffer_id, text) });
+        cx.spawn(move |this, mut cx| async move {
+            let text_buffer = text_buffer.await;
+            let buffer = cx.insert_model(reservation, |cx| {
+                Buffer::build(text_buffer, None, None, Capability::ReadWrite)
+                    .with_language(language.unwrap_or_else(|| language::PLAIN_TEXT.clone()), cx)
+            })?;
+            this.update(&mut cx, |this, cx| this.register_buffer(&buffer, cx))??;
+            Ok(buffer)
+        })
+    }
+
     pub fn create_local_buffer(
         &mut self,
         text: &str,
@@ -2060,22 +2443,27 @@ impl Project {
         cx: &mut ModelContext<Self>,
     ) -> Task<Result<Model<Buffer>>> {
         let load_buffer = worktree.update(cx, |worktree, cx| {
-            let load_file = worktree.load_file(path.as_ref(), cx);
+            let load_file = worktree.load_binary_file(path.as_ref(), cx);
             let reservation = cx.reserve_model();
             let buffer_id = BufferId::from(reservation.entity_id().as_non_zero_u64());
             cx.spawn(move |_, mut cx| async move {
                 let loaded = load_file.await?;
-                let text_buffer = cx
+                let (text_buffer, encoding) = cx
                     .background_executor()
-                    .spawn(async move { text::Buffer::new(0, buffer_id, loaded.text) })
-                    .await;
+                    .spawn(async move {
+                        let (text, encoding) = Encoding::detect(&loaded.content)?;
+                        anyhow::Ok((text::Buffer::new(0, buffer_id, text), encoding))
+                    })
+                    .await?;
                 cx.insert_model(reservation, |_| {
-                    Buffer::build(
+                    let mut buffer = Buffer::build(
                         text_buffer,
                         loaded.diff_base,
                         Some(loaded.file),
                         Capability::ReadWrite,
-                    )
+                    );
+                    buffer.set_encoding(encoding);
+                    buffer
                 })
             })
         });
@@ -2146,6 +2534,9 @@ impl Project {
         language_server_name: LanguageServerName,
         cx: &mut ModelContext<Self>,
     ) -> Task<Result<Model<Buffer>>> {
+        if abs_path.scheme() != "file" {
+            return self.open_virtual_document(abs_path, language_server_id, cx);
+        }
         cx.spawn(move |this, mut cx| async move {
             let abs_path = abs_path
                 .to_file_path()
@@ -2162,7 +2553,11 @@ impl Project {
                     .await?;
                 this.update(&mut cx, |this, cx| {
                     this.language_server_ids.insert(
-                        (worktree.read(cx).id(), language_server_name),
+                        (
+                            worktree.read(cx).id(),
+                            language_server_name,
+                            Arc::from(Path::new("")),
+                        ),
                         language_server_id,
                     );
                 })
@@ -2279,16 +2674,13 @@ impl Project {
     ) -> Task<Result<()>> {
         let buffer = buffer_handle.read(cx);
         let buffer_id = buffer.remote_id();
-        let text = buffer.as_rope().clone();
-        let line_ending = buffer.line_ending();
         let version = buffer.version();
+        let encoding = buffer.encoding();
         if buffer.file().is_some_and(|file| !file.is_created()) {
             has_changed_file = true;
         }
 
-        let save = worktree.update(cx, |worktree, cx| {
-            worktree.write_file(path.as_ref(), text, line_ending, cx)
-        });
+        let save = buffer_encoding::write_buffer_file(&worktree, &buffer_handle, path, cx);
 
         let client = self.client.clone();
         let project_id = self.remote_id();
@@ -2319,6 +2711,7 @@ impl Project {
                     buffer_id: buffer_id.into(),
                     version: serialize_version(&version),
                     mtime: mtime.map(|time| time.into()),
+                    encoding: Some(encoding.name().to_string()),
                 })?;
             }
 
@@ -2339,6 +2732,7 @@ impl Project {
         let buffer = buffer_handle.read(cx);
         let buffer_id = buffer.remote_id().into();
         let version = buffer.version();
+        let encoding = buffer.encoding();
         let rpc = self.client.clone();
         let project_id = self.remote_id();
         cx.spawn(move |_, mut cx| async move {
@@ -2348,12 +2742,17 @@ impl Project {
                     buffer_id,
                     new_path,
                     version: serialize_version(&version),
+                    encoding: Some(encoding.name().to_string()),
                 })
                 .await?;
             let version = deserialize_version(&response.version);
             let mtime = response.mtime.map(|mtime| mtime.into());
+            let encoding = response.encoding.as_deref().and_then(Encoding::from_name);
 
             buffer_handle.update(&mut cx, |buffer, cx| {
+                if let Some(encoding) = encoding {
+                    buffer.set_encoding(encoding);
+                }
                 buffer.did_save(version.clone(), mtime, cx);
             })?;
 
@@ -2454,6 +2853,15 @@ impl Project {
                     }
                 }
             }
+            this.semantic_tokens.buffer_removed(buffer.remote_id());
+            this.document_links.buffer_removed(buffer.remote_id());
+            this.document_colors.buffer_removed(buffer.remote_id());
+            this.code_cell_kernels.buffer_removed(buffer.remote_id());
+            this.lsp_response_cache.buffer_removed(buffer.remote_id());
+            this.diagnostic_sources.buffer_removed(buffer.remote_id());
+            this.folding_ranges.buffer_removed(buffer.remote_id());
+            this.document_symbols.buffer_removed(buffer.remote_id());
+            this.vcs_statuses.buffer_removed(buffer.remote_id());
         })
         .detach();
 
@@ -2485,6 +2893,8 @@ impl Project {
             let initial_snapshot = buffer.text_snapshot();
             let language = buffer.language().cloned();
             let worktree_id = file.worktree_id(cx);
+            let worktree = file.worktree.clone();
+            let path = file.path.clone();
 
             if let Some(diagnostics) = self.diagnostics.get(&worktree_id) {
                 for (server_id, diagnostics) in
@@ -2497,9 +2907,10 @@ impl Project {
 
             if let Some(language) = language {
                 for adapter in self.languages.lsp_adapters(&language) {
+                    let root = self.language_server_root(&worktree, &adapter, &path, cx);
                     let server = self
                         .language_server_ids
-                        .get(&(worktree_id, adapter.name.clone()))
+                        .get(&(worktree_id, adapter.name.clone(), root))
                         .and_then(|id| self.language_servers.get(id))
                         .and_then(|server_state| {
                             if let LanguageServerState::Running { server, .. } = server_state {
@@ -2548,6 +2959,11 @@ impl Project {
                         .insert(server.server_id(), vec![snapshot]);
                 }
             }
+
+            self.schedule_diagnostics_pull(buffer_handle, cx);
+            self.schedule_lint(buffer_handle, LintTrigger::Change, cx);
+            self.schedule_lint(buffer_handle, LintTrigger::Save, cx);
+            self.reload_vcs_diff_base(buffer_handle, cx);
         }
     }
 
@@ -2568,13 +2984,24 @@ impl Project {
 
             if let Some(language) = buffer.language().cloned() {
                 for adapter in self.languages.lsp_adapters(&language) {
-                    if let Some(server_id) = ids.get(&(worktree_id, adapter.name.clone())) {
+                    let root =
+                        self.language_server_root(&old_file.worktree, &adapter, &old_file.path, cx);
+                    if let Some(server_id) = ids.get(&(worktree_id, adapter.name.clone(), root)) {
                         buffer.update_diagnostics(*server_id, Default::default(), cx);
                     }
                 }
             }
 
             self.buffer_snapshots.remove(&buffer.remote_id());
+            self.semantic_tokens.buffer_removed(buffer.remote_id());
+            self.document_links.buffer_removed(buffer.remote_id());
+            self.document_colors.buffer_removed(buffer.remote_id());
+            self.code_cell_kernels.buffer_removed(buffer.remote_id());
+            self.lsp_response_cache.buffer_removed(buffer.remote_id());
+            self.diagnostic_sources.buffer_removed(buffer.remote_id());
+            self.folding_ranges.buffer_removed(buffer.remote_id());
+            self.document_symbols.buffer_removed(buffer.remote_id());
+            self.vcs_statuses.buffer_removed(buffer.remote_id());
             let file_url = lsp::Url::from_file_path(old_path).unwrap();
             for (_, language_server) in self.language_servers_for_buffer(buffer, cx) {
                 language_server
@@ -2720,6 +3147,8 @@ impl Project {
 
             BufferEvent::Reloaded => {
                 if self.is_local() {
+                    // The file may have been reverted or updated by its version control system.
+                    self.reload_vcs_diff_base(&buffer, cx);
                     if let Some(project_id) = self.remote_id() {
                         let buffer = buffer.read(cx);
                         self.client
@@ -2729,6 +3158,7 @@ impl Project {
                                 version: serialize_version(&buffer.version()),
                                 mtime: buffer.saved_mtime().map(|t| t.into()),
                                 line_ending: serialize_line_ending(buffer.line_ending()) as i32,
+                                encoding: Some(buffer.encoding().name().to_string()),
                             })
                             .log_err();
                     }
@@ -2736,6 +3166,12 @@ impl Project {
             }
 
             BufferEvent::Edited { .. } => {
+                self.document_colors
+                    .buffer_edited(buffer.read(cx).remote_id());
+                // The pull is debounced, so it's issued after all the changes below are sent.
+                self.schedule_diagnostics_pull(&buffer, cx);
+                self.schedule_lint(&buffer, LintTrigger::Change, cx);
+
                 let buffer = buffer.read(cx);
                 let file = File::from_dyn(buffer.file())?;
                 let abs_path = file.as_local()?.abs_path(cx);
@@ -2831,6 +3267,10 @@ impl Project {
             }
 
             BufferEvent::Saved => {
+                self.schedule_lint(&buffer, LintTrigger::Save, cx);
+                // Saving the file changes its status in the version control system.
+                self.reload_vcs_diff_base(&buffer, cx);
+
                 let file = File::from_dyn(buffer.read(cx).file())?;
                 let worktree_id = file.worktree_id(cx);
                 let abs_path = file.as_local()?.abs_path(cx);
@@ -3007,9 +3447,8 @@ impl Project {
         &self,
         worktree_id: WorktreeId,
     ) -> impl Iterator<Item = (&Arc<CachedLspAdapter>, &Arc<Language>, &Arc<LanguageServer>)> {
-        self.language_server_ids
-            .iter()
-            .filter_map(move |((language_server_worktree_id, _), id)| {
+        self.language_server_ids.iter().filter_map(
+            move |((language_server_worktree_id, _, _), id)| {
                 if *language_server_worktree_id == worktree_id {
                     if let Some(LanguageServerState::Running {
                         adapter,
@@ -3022,7 +3461,8 @@ impl Project {
                     }
                 }
                 None
-            })
+            },
+        )
     }
 
     fn maintain_buffer_languages(
@@ -3085,6 +3525,8 @@ impl Project {
                             for buffer in buffers_with_unknown_injections {
                                 buffer.update(cx, |buffer, cx| buffer.reparse(cx));
                             }
+
+                            project.reindex_symbols_of_new_languages(cx);
                         })
                         .ok();
                 }
@@ -3105,7 +3547,7 @@ impl Project {
                 let servers = this.update(&mut cx, |this, cx| {
                     this.language_server_ids
                         .iter()
-                        .filter_map(|((worktree_id, _), server_id)| {
+                        .filter_map(|((worktree_id, _, _), server_id)| {
                             let worktree = this.worktree_for_id(*worktree_id, cx)?;
                             let state = this.language_servers.get(server_id)?;
                             let delegate = ProjectLspAdapterDelegate::new(this, &worktree, cx);
@@ -3124,7 +3566,10 @@ impl Project {
                 })?;
 
                 for (adapter, server, delegate) in servers {
-                    let settings = adapter.workspace_configuration(&delegate, &mut cx).await?;
+                    let settings = Self::workspace_configuration_for_scope(
+                        &this, &adapter, &delegate, None, &mut cx,
+                    )
+                    .await?;
 
                     server
                         .notify::<lsp::notification::DidChangeConfiguration>(
@@ -3198,14 +3643,18 @@ impl Project {
         if let Some(file) = buffer_file {
             let worktree = file.worktree.clone();
             if worktree.read(cx).is_local() {
-                self.start_language_servers(&worktree, new_language, cx);
+                let path = file.path.clone();
+                self.start_language_servers(&worktree, &path, new_language, cx);
             }
         }
     }
 
+    /// Starts the language servers of the language for the given path of the worktree, each at the root that the
+    /// path belongs to.
     fn start_language_servers(
         &mut self,
         worktree: &Model<Worktree>,
+        path: &Path,
         language: Arc<Language>,
         cx: &mut ModelContext<Self>,
     ) {
@@ -3214,6 +3663,9 @@ impl Project {
         if !settings.enable_language_server {
             return;
         }
+        if !self.check_worktree_trust(worktree.read(cx).id(), cx) {
+            return;
+        }
 
         let available_lsp_adapters = self.languages.clone().lsp_adapters(&language);
         let available_language_servers = available_lsp_adapters
@@ -3260,25 +3712,59 @@ impl Project {
         );
 
         for adapter in enabled_lsp_adapters {
-            self.start_language_server(worktree, adapter, language.clone(), cx);
+            let root = self.language_server_root(worktree, &adapter, path, cx);
+            self.start_language_server(worktree, adapter, language.clone(), root, cx);
         }
     }
 
+    /// The root, relative to the worktree, of the instance of the language server that serves the given path of
+    /// the worktree.
+    fn language_server_root(
+        &self,
+        worktree: &Model<Worktree>,
+        adapter: &CachedLspAdapter,
+        path: &Path,
+        cx: &AppContext,
+    ) -> Arc<Path> {
+        let worktree = worktree.read(cx);
+        let project_settings = ProjectSettings::get(
+            Some(SettingsLocation {
+                worktree_id: worktree.id().to_proto() as usize,
+                path: Path::new(""),
+            }),
+            cx,
+        );
+        let roots = project_settings
+            .lsp
+            .get(&adapter.name.0)
+            .and_then(|settings| settings.roots.as_ref())
+            .unwrap_or(&adapter.roots);
+        let directory = path.parent().unwrap_or(Path::new(""));
+        roots.root_for_directory(directory, |path| worktree.entry_for_path(path).is_some())
+    }
+
     fn start_language_server(
         &mut self,
         worktree_handle: &Model<Worktree>,
         adapter: Arc<CachedLspAdapter>,
         language: Arc<Language>,
+        root: Arc<Path>,
         cx: &mut ModelContext<Self>,
     ) {
-        if adapter.reinstall_attempt_count.load(SeqCst) > MAX_SERVER_REINSTALL_ATTEMPT_COUNT {
+        if adapter.reinstall_attempt_count.load(SeqCst) > MAX_SERVER_REINSTALL_ATTEMPT_COUNT
+            || self.language_server_restarts_disabled(&adapter.name)
+        {
             return;
         }
 
         let worktree = worktree_handle.read(cx);
         let worktree_id = worktree.id();
-        let worktree_path = worktree.abs_path();
-        let key = (worktree_id, adapter.name.clone());
+        let root_path: Arc<Path> = if root.as_os_str().is_empty() {
+            worktree.abs_path()
+        } else {
+            worktree.abs_path().join(&root).into()
+        };
+        let key = (worktree_id, adapter.name.clone(), root);
         if self.language_server_ids.contains_key(&key) {
             return;
         }
@@ -3289,7 +3775,7 @@ impl Project {
             stderr_capture.clone(),
             language.clone(),
             adapter.clone(),
-            Arc::clone(&worktree_path),
+            root_path,
             lsp_adapter_delegate.clone(),
             cx,
         ) {
@@ -3308,6 +3794,11 @@ impl Project {
         let override_options = lsp.and_then(|s| s.initialization_options.clone());
 
         let server_id = pending_server.server_id;
+        self.environment_watcher.language_server_started(
+            server_id,
+            worktree_id,
+            lsp_adapter_delegate.clone(),
+        );
         let container_dir = pending_server.container_dir.clone();
         let state = LanguageServerState::Starting({
             let adapter = adapter.clone();
@@ -3337,9 +3828,23 @@ impl Project {
 
                     Err(err) => {
                         log::error!("failed to start language server {server_name:?}: {err}");
-                        log::error!("server stderr: {:?}", stderr_capture.lock().take());
+                        let stderr = stderr_capture.lock().take().unwrap_or_default();
+                        log::error!("server stderr: {stderr:?}");
 
                         let this = this.upgrade()?;
+                        let delay = this
+                            .update(&mut cx, |this, cx| {
+                                this.record_language_server_crash(
+                                    &adapter.name,
+                                    LanguageServerCrash {
+                                        error: format!("failed to start: {err}"),
+                                        stderr,
+                                    },
+                                    None,
+                                    cx,
+                                )
+                            })
+                            .ok()??;
                         let container_dir = container_dir?;
 
                         let attempt_count = adapter.reinstall_attempt_count.fetch_add(1, SeqCst);
@@ -3351,11 +3856,9 @@ impl Project {
 
                         log::info!(
                             "retrying installation of language server {server_name:?} in {}s",
-                            SERVER_REINSTALL_DEBOUNCE_TIMEOUT.as_secs()
+                            delay.as_secs()
                         );
-                        cx.background_executor()
-                            .timer(SERVER_REINSTALL_DEBOUNCE_TIMEOUT)
-                            .await;
+                        cx.background_executor().timer(delay).await;
 
                         let installation_test_binary = adapter
                             .installation_test_binary(container_dir.to_path_buf())
@@ -3396,12 +3899,16 @@ impl Project {
             _ => None,
         };
 
-        for worktree in &self.worktrees {
-            if let Some(worktree) = worktree.upgrade() {
-                let key = (worktree.read(cx).id(), adapter.name.clone());
-                self.language_server_ids.remove(&key);
-            }
-        }
+        let mut roots = Vec::new();
+        self.language_server_ids
+            .retain(|(worktree_id, server_name, root), _| {
+                if *server_name == adapter.name {
+                    roots.push((*worktree_id, root.clone()));
+                    false
+                } else {
+                    true
+                }
+            });
 
         Some(cx.spawn(move |this, mut cx| async move {
             if let Some(task) = existing_server.and_then(|server| server.shutdown()) {
@@ -3423,13 +3930,13 @@ impl Project {
             task.await;
 
             this.update(&mut cx, |this, cx| {
-                let worktrees = this.worktrees.clone();
-                for worktree in worktrees {
-                    if let Some(worktree) = worktree.upgrade() {
+                for (worktree_id, root) in roots {
+                    if let Some(worktree) = this.worktree_for_id(worktree_id, cx) {
                         this.start_language_server(
                             &worktree,
                             adapter.clone(),
                             language.clone(),
+                            root,
                             cx,
                         );
                     }
@@ -3448,7 +3955,7 @@ impl Project {
         adapter: Arc<CachedLspAdapter>,
         language: Arc<Language>,
         server_id: LanguageServerId,
-        key: (WorktreeId, LanguageServerName),
+        key: (WorktreeId, LanguageServerName, Arc<Path>),
         cx: &mut AsyncAppContext,
     ) -> Result<Option<Arc<LanguageServer>>> {
         let language_server = Self::setup_pending_language_server(
@@ -3490,11 +3997,9 @@ impl Project {
         server_id: LanguageServerId,
         cx: &mut AsyncAppContext,
     ) -> Result<Arc<LanguageServer>> {
-        let workspace_config = adapter
-            .adapter
-            .clone()
-            .workspace_configuration(&delegate, cx)
-            .await?;
+        let workspace_config =
+            Self::workspace_configuration_for_scope(&this, &adapter.adapter, &delegate, None, cx)
+                .await?;
         let (language_server, mut initialization_options) = pending_server.task.await?;
 
         let name = language_server.name();
@@ -3523,28 +4028,40 @@ impl Project {
 
         language_server
             .on_request::<lsp::request::WorkspaceConfiguration, _, _>({
+                let this = this.clone();
                 let adapter = adapter.adapter.clone();
                 let delegate = delegate.clone();
                 move |params, mut cx| {
+                    let this = this.clone();
                     let adapter = adapter.clone();
                     let delegate = delegate.clone();
                     async move {
-                        let workspace_config =
-                            adapter.workspace_configuration(&delegate, &mut cx).await?;
-                        Ok(params
-                            .items
-                            .into_iter()
-                            .map(|item| {
-                                if let Some(section) = &item.section {
-                                    workspace_config
-                                        .get(section)
-                                        .cloned()
-                                        .unwrap_or(serde_json::Value::Null)
-                                } else {
-                                    workspace_config.clone()
-                                }
-                            })
-                            .collect())
+                        // Items of the same scope share the configuration built for it.
+                        let mut workspace_configs = HashMap::default();
+                        let mut configs = Vec::new();
+                        for item in params.items {
+                            if !workspace_configs.contains_key(&item.scope_uri) {
+                                let workspace_config = Self::workspace_configuration_for_scope(
+                                    &this,
+                                    &adapter,
+                                    &delegate,
+                                    item.scope_uri.as_ref(),
+                                    &mut cx,
+                                )
+                                .await?;
+                                workspace_configs.insert(item.scope_uri.clone(), workspace_config);
+                            }
+                            let workspace_config = &workspace_configs[&item.scope_uri];
+                            configs.push(if let Some(section) = &item.section {
+                                workspace_config
+                                    .get(section)
+                                    .cloned()
+                                    .unwrap_or(serde_json::Value::Null)
+                            } else {
+                                workspace_config.clone()
+                            });
+                        }
+                        Ok(configs)
                     }
                 }
             })
@@ -3635,6 +4152,16 @@ impl Project {
             })
             .detach();
 
+        language_server
+            .on_request::<lsp::request::ShowDocument, _, _>({
+                let adapter = adapter.clone();
+                let this = this.clone();
+                move |params, cx| {
+                    Self::on_lsp_show_document(this.clone(), params, server_id, adapter.clone(), cx)
+                }
+            })
+            .detach();
+
         language_server
             .on_request::<lsp::request::InlayHintRefreshRequest, _, _>({
                 let this = this.clone();
@@ -3741,11 +4268,68 @@ impl Project {
             .detach();
 
         language_server
-            .on_notification::<lsp::notification::Progress, _>(move |params, mut cx| {
+            .on_request::<lsp::request::WorkspaceDiagnosticRefresh, _, _>({
+                let this = this.clone();
+                move |(), mut cx| {
+                    let this = this.clone();
+                    async move {
+                        this.update(&mut cx, |this, cx| {
+                            this.refresh_pulled_diagnostics(server_id, cx);
+                        })?;
+                        Ok(())
+                    }
+                }
+            })
+            .detach();
+
+        language_server
+            .on_request::<lsp::request::InlineValueRefreshRequest, _, _>({
+                let this = this.clone();
+                move |(), mut cx| {
+                    let this = this.clone();
+                    async move {
+                        this.update(&mut cx, |this, cx| this.refresh_inline_values(cx))??;
+                        Ok(())
+                    }
+                }
+            })
+            .detach();
+
+        language_server
+            .on_request::<lsp::request::SemanticTokensRefresh, _, _>({
+                let this = this.clone();
+                move |(), mut cx| {
+                    let this = this.clone();
+                    async move {
+                        this.update(&mut cx, |this, cx| {
+                            this.refresh_semantic_tokens(server_id, cx)
+                        })??;
+                        Ok(())
+                    }
+                }
+            })
+            .detach();
+
+        // Partial results of the requests are reported via `$/progress` too, so the value is not parsed
+        // as a work done progress until it's known not to belong to any of the requests.
+        language_server
+            .on_notification::<RawProgress, _>(move |params, mut cx| {
                 if let Some(this) = this.upgrade() {
                     this.update(&mut cx, |this, cx| {
+                        let value = params.value;
+                        if let lsp::NumberOrString::String(token) = &params.token {
+                            if this.on_lsp_partial_result(server_id, token, value.clone(), cx) {
+                                return;
+                            }
+                        }
+                        let Some(value) = serde_json::from_value(value).log_err() else {
+                            return;
+                        };
                         this.on_lsp_progress(
-                            params,
+                            lsp::ProgressParams {
+                                token: params.token,
+                                value,
+                            },
                             server_id,
                             disk_based_diagnostics_progress_token.clone(),
                             cx,
@@ -3784,7 +4368,7 @@ impl Project {
         adapter: Arc<CachedLspAdapter>,
         language_server: Arc<LanguageServer>,
         server_id: LanguageServerId,
-        key: (WorktreeId, LanguageServerName),
+        key: (WorktreeId, LanguageServerName, Arc<Path>),
         cx: &mut ModelContext<Self>,
     ) -> Result<()> {
         // If the language server for this key doesn't match the server id, don't store the
@@ -3817,18 +4401,31 @@ impl Project {
                 pending_work: Default::default(),
                 has_pending_diagnostic_updates: false,
                 progress_tokens: Default::default(),
+                signature_help_triggers: SignatureHelpTriggers::from_capabilities(
+                    language_server.capabilities(),
+                ),
             },
         );
 
         cx.emit(Event::LanguageServerAdded(server_id));
+        self.watch_request_timeouts(&language_server, cx);
+        self.supervise_language_server(
+            language.clone(),
+            adapter.clone(),
+            &language_server,
+            server_id,
+            key.0,
+            key.2.clone(),
+            cx,
+        );
 
-        if let Some(project_id) = self.remote_id() {
+        if let Some((project_id, status)) = self
+            .remote_id()
+            .zip(self.language_server_statuses.get(&server_id))
+        {
             self.client.send(proto::StartLanguageServer {
                 project_id,
-                server: Some(proto::LanguageServer {
-                    id: server_id.0 as u64,
-                    name: language_server.name().to_string(),
-                }),
+                server: Some(status.to_proto(server_id)),
             })?;
         }
 
@@ -3901,6 +4498,7 @@ impl Project {
             }
         }
 
+        self.refresh_pulled_diagnostics(server_id, cx);
         cx.notify();
         Ok(())
     }
@@ -3911,9 +4509,10 @@ impl Project {
         &mut self,
         worktree_id: WorktreeId,
         adapter_name: LanguageServerName,
+        root: Arc<Path>,
         cx: &mut ModelContext<Self>,
     ) -> Task<Vec<WorktreeId>> {
-        let key = (worktree_id, adapter_name);
+        let key = (worktree_id, adapter_name, root);
         if let Some(server_id) = self.language_server_ids.remove(&key) {
             let name = key.1 .0;
             log::info!("stopping language server {name}");
@@ -3976,6 +4575,10 @@ impl Project {
 
             self.language_server_watched_paths.remove(&server_id);
             self.language_server_statuses.remove(&server_id);
+            self.pull_diagnostics.language_server_removed(server_id);
+            self.semantic_tokens.language_server_removed(server_id);
+            self.document_links.language_server_removed(server_id);
+            self.document_colors.language_server_removed(server_id);
             cx.notify();
 
             let server_state = self.language_servers.remove(&server_id);
@@ -4062,72 +4665,124 @@ impl Project {
             return;
         }
 
-        let language_server_lookup_info: HashSet<(Model<Worktree>, Arc<Language>)> = buffers
-            .into_iter()
-            .filter_map(|buffer| {
-                let buffer = buffer.read(cx);
-                let file = buffer.file()?;
-                let worktree = File::from_dyn(Some(file))?.worktree.clone();
-                let language = self
-                    .languages
-                    .language_for_file(file, Some(buffer.as_rope()), cx)
-                    .now_or_never()?
-                    .ok()?;
-                Some((worktree, language))
-            })
-            .collect();
-        for (worktree, language) in language_server_lookup_info {
-            self.restart_language_servers(worktree, language, cx);
+        let mut language_server_lookup_info =
+            HashMap::<(Model<Worktree>, Arc<Language>), Vec<Arc<Path>>>::default();
+        for buffer in buffers {
+            let buffer = buffer.read(cx);
+            let Some(file) = buffer.file() else {
+                continue;
+            };
+            let Some(worktree_file) = File::from_dyn(Some(file)) else {
+                continue;
+            };
+            let Some(Ok(language)) = self
+                .languages
+                .language_for_file(file, Some(buffer.as_rope()), cx)
+                .now_or_never()
+            else {
+                continue;
+            };
+            language_server_lookup_info
+                .entry((worktree_file.worktree.clone(), language))
+                .or_default()
+                .push(worktree_file.path.clone());
+        }
+        for ((worktree, language), paths) in language_server_lookup_info {
+            for adapter in self.languages.lsp_adapters(&language) {
+                self.enable_language_server_restarts(&adapter.name);
+            }
+            self.restart_language_servers(worktree, paths, language, cx);
         }
     }
 
+    /// Restarts the running language servers of the language in the worktree, at all of their roots, and starts
+    /// the ones of the given paths of the worktree.
     fn restart_language_servers(
         &mut self,
         worktree: Model<Worktree>,
+        paths: Vec<Arc<Path>>,
         language: Arc<Language>,
         cx: &mut ModelContext<Self>,
     ) {
         let worktree_id = worktree.read(cx).id();
 
-        let stop_tasks = self
-            .languages
-            .clone()
-            .lsp_adapters(&language)
-            .iter()
-            .map(|adapter| {
-                let stop_task = self.stop_language_server(worktree_id, adapter.name.clone(), cx);
-                (stop_task, adapter.name.clone())
-            })
-            .collect::<Vec<_>>();
-        if stop_tasks.is_empty() {
+        let adapters = self.languages.clone().lsp_adapters(&language);
+        if adapters.is_empty() {
             return;
         }
+        let keys_to_stop = self
+            .language_server_ids
+            .keys()
+            .filter(|(server_worktree_id, server_name, _)| {
+                *server_worktree_id == worktree_id
+                    && adapters.iter().any(|adapter| adapter.name == *server_name)
+            })
+            .cloned()
+            .collect::<Vec<_>>();
+        let stop_tasks = keys_to_stop
+            .into_iter()
+            .map(|(_, server_name, root)| {
+                let stop_task =
+                    self.stop_language_server(worktree_id, server_name.clone(), root.clone(), cx);
+                (stop_task, server_name, root)
+            })
+            .collect::<Vec<_>>();
 
         cx.spawn(move |this, mut cx| async move {
             // For each stopped language server, record all of the worktrees with which
             // it was associated.
             let mut affected_worktrees = Vec::new();
-            for (stop_task, language_server_name) in stop_tasks {
+            let mut stopped_servers = Vec::new();
+            for (stop_task, language_server_name, root) in stop_tasks {
                 for affected_worktree_id in stop_task.await {
-                    affected_worktrees.push((affected_worktree_id, language_server_name.clone()));
+                    if affected_worktree_id != worktree_id {
+                        affected_worktrees.push((
+                            affected_worktree_id,
+                            language_server_name.clone(),
+                            root.clone(),
+                        ));
+                    }
                 }
+                stopped_servers.push((language_server_name, root));
             }
 
             this.update(&mut cx, |this, cx| {
-                // Restart the language server for the given worktree.
-                this.start_language_servers(&worktree, language.clone(), cx);
+                // Restart the stopped language servers at their roots, and start the ones of the paths.
+                for (language_server_name, root) in stopped_servers {
+                    if let Some(adapter) = adapters
+                        .iter()
+                        .find(|adapter| adapter.name == language_server_name)
+                    {
+                        this.start_language_server(
+                            &worktree,
+                            adapter.clone(),
+                            language.clone(),
+                            root,
+                            cx,
+                        );
+                    }
+                }
+                for path in paths {
+                    this.start_language_servers(&worktree, &path, language.clone(), cx);
+                }
 
                 // Lookup new server ids and set them for each of the orphaned worktrees
-                for (affected_worktree_id, language_server_name) in affected_worktrees {
+                for (affected_worktree_id, language_server_name, root) in affected_worktrees {
                     if let Some(new_server_id) = this
                         .language_server_ids
-                        .get(&(worktree_id, language_server_name.clone()))
+                        .get(&(worktree_id, language_server_name.clone(), root))
                         .cloned()
                     {
-                        this.language_server_ids
-                            .insert((affected_worktree_id, language_server_name), new_server_id);
-                    }
-                }
+                        this.language_server_ids.insert(
+                            (
+                                affected_worktree_id,
+                                language_server_name,
+                                Arc::from(Path::new("")),
+                            ),
+                            new_server_id,
+                        );
+                    }
+                }
             })
             .ok();
         })
@@ -4139,6 +4794,26 @@ impl Project {
         buffers: impl IntoIterator<Item = Model<Buffer>>,
         cx: &mut ModelContext<Self>,
     ) {
+        if !self.is_local() {
+            if let Some(project_id) = self.remote_id() {
+                let request = self.client.request(proto::CancelLanguageServerWork {
+                    project_id,
+                    work: Some(proto::cancel_language_server_work::Work::Buffers(
+                        proto::cancel_language_server_work::Buffers {
+                            buffer_ids: buffers
+                                .into_iter()
+                                .map(|buffer| buffer.read(cx).remote_id().to_proto())
+                                .collect(),
+                        },
+                    )),
+                });
+                cx.background_executor()
+                    .spawn(request)
+                    .detach_and_log_err(cx);
+            }
+            return;
+        }
+
         let servers = buffers
             .into_iter()
             .flat_map(|buffer| {
@@ -4152,12 +4827,35 @@ impl Project {
         }
     }
 
+    /// Cancels the given work of the server, or all of its cancellable work if no token is given.
+    ///
+    /// Guests forward the cancellation to the host, which runs the server.
     pub fn cancel_language_server_work(
         &mut self,
         server_id: LanguageServerId,
         token_to_cancel: Option<String>,
-        _cx: &mut ModelContext<Self>,
+        cx: &mut ModelContext<Self>,
     ) {
+        if !self.is_local() {
+            if let Some(project_id) = self.remote_id() {
+                let request = self.client.request(proto::CancelLanguageServerWork {
+                    project_id,
+                    work: Some(
+                        proto::cancel_language_server_work::Work::LanguageServerWork(
+                            proto::cancel_language_server_work::LanguageServerWork {
+                                language_server_id: server_id.0 as u64,
+                                token: token_to_cancel,
+                            },
+                        ),
+                    ),
+                });
+                cx.background_executor()
+                    .spawn(request)
+                    .detach_and_log_err(cx);
+            }
+            return;
+        }
+
         let status = self.language_server_statuses.get(&server_id);
         let server = self.language_servers.get(&server_id);
         if let Some((server, status)) = server.zip(status) {
@@ -4289,6 +4987,11 @@ impl Project {
             .map_or(false, |disk_based_token| {
                 token.starts_with(disk_based_token)
             });
+        // Reports may leave out the cancellability, which stays as it was.
+        let was_cancellable = language_server_status
+            .pending_work
+            .get(&token)
+            .map_or(false, |work| work.is_cancellable);
 
         match progress {
             lsp::WorkDoneProgress::Begin(report) => {
@@ -4316,7 +5019,7 @@ impl Project {
                     LanguageServerProgress {
                         title: None,
                         is_disk_based_diagnostics_progress,
-                        is_cancellable: report.cancellable.unwrap_or(false),
+                        is_cancellable: report.cancellable.unwrap_or(was_cancellable),
                         message: report.message.clone(),
                         percentage: report.percentage.map(|p| p as usize),
                         last_update_at: cx.background_executor().now(),
@@ -4331,6 +5034,9 @@ impl Project {
                                     token,
                                     message: report.message,
                                     percentage: report.percentage,
+                                    is_cancellable: Some(
+                                        report.cancellable.unwrap_or(was_cancellable),
+                                    ),
                                 },
                             ),
                         },
@@ -4368,6 +5074,7 @@ impl Project {
                     title: progress.title,
                     message: progress.message,
                     percentage: progress.percentage.map(|p| p as u32),
+                    is_cancellable: Some(progress.is_cancellable),
                 }),
             })
             .ok();
@@ -4390,10 +5097,13 @@ impl Project {
                 }
                 btree_map::Entry::Occupied(mut entry) => {
                     let entry = entry.get_mut();
-                    if (progress.last_update_at - entry.last_update_at)
-                        >= SERVER_PROGRESS_THROTTLE_TIMEOUT
+                    // Changes of the cancellability aren't throttled, so that cancel buttons follow them.
+                    if entry.is_cancellable != progress.is_cancellable
+                        || (progress.last_update_at - entry.last_update_at)
+                            >= SERVER_PROGRESS_THROTTLE_TIMEOUT
                     {
                         entry.last_update_at = progress.last_update_at;
+                        entry.is_cancellable = progress.is_cancellable;
                         if progress.message.is_some() {
                             entry.message = progress.message;
                         }
@@ -5111,6 +5821,27 @@ impl Project {
                 buffer.end_transaction(cx)
             })?;
 
+            // Apply the `code_actions_on_save` before the `code_actions_on_format` and the formatter, and group
+            // them with the whitespace formatting, so that the language-specific formatting can still be grouped
+            // with it below.
+            if trigger == FormatTrigger::Save && !settings.code_actions_on_save.is_empty() {
+                Self::execute_code_actions_on_save(
+                    &project,
+                    &adapters_and_servers,
+                    &settings.code_actions_on_save,
+                    buffer,
+                    push_to_history,
+                    &mut project_transaction,
+                    &mut cx,
+                )
+                .await;
+                if let Some(transaction_id) = whitespace_transaction_id {
+                    buffer.update(&mut cx, |buffer, _| {
+                        buffer.group_until_transaction(transaction_id)
+                    })?;
+                }
+            }
+
             // Apply the `code_actions_on_format` before we run the formatter.
             let code_actions = deserialize_code_actions(&settings.code_actions_on_format);
             #[allow(clippy::nonminimal_bool)]
@@ -5134,22 +5865,33 @@ impl Project {
             // Except for code actions, which are applied with all connected language servers.
             let primary_language_server =
                 primary_adapter_and_server.map(|(_adapter, server)| server.clone());
-            let server_and_buffer = primary_language_server
-                .as_ref()
-                .zip(buffer_abs_path.as_ref());
+            let target = FormatTarget {
+                buffer,
+                buffer_abs_path: buffer_abs_path.as_deref(),
+                primary_language_server: primary_language_server.as_ref(),
+                adapters_and_servers: &adapters_and_servers,
+                settings: &settings,
+                changed_lines_only: trigger == FormatTrigger::Save
+                    && settings.format_changed_lines_only,
+            };
 
             let mut format_operation = None;
-            let prettier_settings = buffer.read_with(&mut cx, |buffer, cx| {
-                language_settings(buffer.language(), buffer.file(), cx)
-                    .prettier
-                    .clone()
-            })?;
-            match (&settings.formatter, &settings.format_on_save) {
-                (_, FormatOnSave::Off) if trigger == FormatTrigger::Save => {}
-
-                (Formatter::CodeActions(code_actions), FormatOnSave::On | FormatOnSave::Off)
-                | (_, FormatOnSave::CodeActions(code_actions)) => {
-                    let code_actions = deserialize_code_actions(code_actions);
+            let formatter = match (&settings.formatter, &settings.format_on_save) {
+                (_, FormatOnSave::Off) if trigger == FormatTrigger::Save => None,
+                (_, FormatOnSave::CodeActions(code_actions)) => {
+                    Some(Formatter::CodeActions(code_actions.clone()))
+                }
+                (_, FormatOnSave::LanguageServer) => Some(Formatter::LanguageServer),
+                (_, FormatOnSave::External { command, arguments }) => Some(Formatter::External {
+                    command: command.clone(),
+                    arguments: arguments.clone(),
+                }),
+                (formatter, FormatOnSave::On | FormatOnSave::Off) => Some(formatter.clone()),
+            };
+            match formatter {
+                None => {}
+                Some(Formatter::CodeActions(code_actions)) => {
+                    let code_actions = deserialize_code_actions(&code_actions);
                     if !code_actions.is_empty() {
                         Self::execute_code_actions_on_servers(
                             &project,
@@ -5163,80 +5905,21 @@ impl Project {
                         .await?;
                     }
                 }
-                (Formatter::LanguageServer, FormatOnSave::On | FormatOnSave::Off)
-                | (_, FormatOnSave::LanguageServer) => {
-                    if let Some((language_server, buffer_abs_path)) = server_and_buffer {
-                        format_operation = Some(FormatOperation::Lsp(
-                            Self::format_via_lsp(
-                                &project,
-                                buffer,
-                                buffer_abs_path,
-                                language_server,
-                                tab_size,
-                                &mut cx,
-                            )
-                            .await
-                            .context("failed to format via language server")?,
-                        ));
-                    }
-                }
-
-                (
-                    Formatter::External { command, arguments },
-                    FormatOnSave::On | FormatOnSave::Off,
-                )
-                | (_, FormatOnSave::External { command, arguments }) => {
-                    let buffer_abs_path = buffer_abs_path.as_ref().map(|path| path.as_path());
-                    format_operation = Self::format_via_external_command(
-                        buffer,
-                        buffer_abs_path,
-                        command,
-                        arguments,
+                Some(Formatter::Chain(formatters)) => {
+                    Self::format_via_chain(
+                        &project,
+                        &target,
+                        &formatters,
+                        whitespace_transaction_id,
+                        push_to_history,
+                        &mut project_transaction,
                         &mut cx,
                     )
-                    .await
-                    .context(format!(
-                        "failed to format via external command {:?}",
-                        command
-                    ))?
-                    .map(FormatOperation::External);
-                }
-                (Formatter::Auto, FormatOnSave::On | FormatOnSave::Off) => {
-                    let prettier = if prettier_settings.allowed {
-                        prettier_support::format_with_prettier(&project, buffer, &mut cx)
-                            .await
-                            .transpose()
-                            .ok()
-                            .flatten()
-                    } else {
-                        None
-                    };
-
-                    if let Some(operation) = prettier {
-                        format_operation = Some(operation);
-                    } else if let Some((language_server, buffer_abs_path)) = server_and_buffer {
-                        format_operation = Some(FormatOperation::Lsp(
-                            Self::format_via_lsp(
-                                &project,
-                                buffer,
-                                buffer_abs_path,
-                                language_server,
-                                tab_size,
-                                &mut cx,
-                            )
-                            .await
-                            .context("failed to format via language server")?,
-                        ));
-                    }
+                    .await?;
                 }
-                (Formatter::Prettier, FormatOnSave::On | FormatOnSave::Off) => {
-                    if prettier_settings.allowed {
-                        if let Some(operation) =
-                            prettier_support::format_with_prettier(&project, buffer, &mut cx).await
-                        {
-                            format_operation = Some(operation?);
-                        }
-                    }
+                Some(formatter) => {
+                    format_operation =
+                        Self::format_with(&project, &target, &formatter, &mut cx).await?;
                 }
             };
 
@@ -5286,6 +5969,104 @@ impl Project {
         Ok(project_transaction)
     }
 
+    /// Computes the formatting of the buffer with a single formatter, other than code actions and chains,
+    /// which apply their edits as they go.
+    async fn format_with(
+        project: &WeakModel<Self>,
+        target: &FormatTarget<'_>,
+        formatter: &Formatter,
+        cx: &mut AsyncAppContext,
+    ) -> Result<Option<FormatOperation>> {
+        let buffer = target.buffer;
+        match formatter {
+            Formatter::LanguageServer => {
+                Self::format_with_primary_language_server(project, target, cx).await
+            }
+            Formatter::External { command, arguments } => Ok(Self::format_via_external_command(
+                buffer,
+                target.buffer_abs_path,
+                command,
+                arguments,
+                cx,
+            )
+            .await
+            .context(format!(
+                "failed to format via external command {:?}",
+                command
+            ))?
+            .map(FormatOperation::External)),
+            Formatter::Auto => {
+                // Prettier can't format parts of a buffer, so only the language server can format the changed
+                // lines.
+                let prettier = if target.settings.prettier.allowed && !target.changed_lines_only {
+                    prettier_support::format_with_prettier(project, buffer, cx)
+                        .await
+                        .transpose()
+                        .ok()
+                        .flatten()
+                } else {
+                    None
+                };
+
+                if let Some(operation) = prettier {
+                    Ok(Some(operation))
+                } else {
+                    Self::format_with_primary_language_server(project, target, cx).await
+                }
+            }
+            Formatter::Prettier => {
+                if target.settings.prettier.allowed {
+                    if let Some(operation) =
+                        prettier_support::format_with_prettier(project, buffer, cx).await
+                    {
+                        return Ok(Some(operation?));
+                    }
+                }
+                Ok(None)
+            }
+            Formatter::CodeActions(_) | Formatter::Chain(_) => {
+                Err(anyhow!("{formatter:?} can't be run as a single formatter"))
+            }
+        }
+    }
+
+    async fn format_with_primary_language_server(
+        project: &WeakModel<Self>,
+        target: &FormatTarget<'_>,
+        cx: &mut AsyncAppContext,
+    ) -> Result<Option<FormatOperation>> {
+        let Some((language_server, buffer_abs_path)) =
+            target.primary_language_server.zip(target.buffer_abs_path)
+        else {
+            return Ok(None);
+        };
+        let tab_size = target.settings.tab_size;
+        let edits = if target.changed_lines_only {
+            Self::format_changed_lines_via_lsp(
+                project,
+                target.buffer,
+                buffer_abs_path,
+                language_server,
+                tab_size,
+                cx,
+            )
+            .await
+        } else {
+            Self::format_via_lsp(
+                project,
+                target.buffer,
+                buffer_abs_path,
+                language_server,
+                tab_size,
+                cx,
+            )
+            .await
+        };
+        Ok(Some(FormatOperation::Lsp(
+            edits.context("failed to format via language server")?,
+        )))
+    }
+
     async fn format_via_lsp(
         this: &WeakModel<Self>,
         buffer: &Model<Buffer>,
@@ -5336,53 +6117,113 @@ impl Project {
         }
     }
 
-    async fn format_via_external_command(
+    /// Formats the lines of the buffer changed since the last Git commit, with a range formatting request
+    /// for each diff hunk. Buffers without a diff base, like the ones of untracked files, are formatted
+    /// entirely.
+    async fn format_changed_lines_via_lsp(
+        this: &WeakModel<Self>,
         buffer: &Model<Buffer>,
-        buffer_abs_path: Option<&Path>,
-        command: &str,
-        arguments: &[String],
+        abs_path: &Path,
+        language_server: &Arc<LanguageServer>,
+        tab_size: NonZeroU32,
         cx: &mut AsyncAppContext,
-    ) -> Result<Option<Diff>> {
-        let working_dir_path = buffer.update(cx, |buffer, cx| {
-            let file = File::from_dyn(buffer.file())?;
-            let worktree = file.worktree.read(cx);
-            let mut worktree_path = worktree.abs_path().to_path_buf();
-            if worktree.root_entry()?.is_file() {
-                worktree_path.pop();
-            }
-            Some(worktree_path)
-        })?;
-
-        let mut child = smol::process::Command::new(command);
-
-        if let Some(working_dir_path) = working_dir_path {
-            child.current_dir(working_dir_path);
+    ) -> Result<Vec<(Range<Anchor>, String)>> {
+        let has_diff_base = buffer.update(cx, |buffer, _| buffer.diff_base().is_some())?;
+        if !has_diff_base {
+            return Self::format_via_lsp(this, buffer, abs_path, language_server, tab_size, cx)
+                .await;
+        }
+        let range_formatting_provider = language_server
+            .capabilities()
+            .document_range_formatting_provider
+            .clone();
+        if !matches!(range_formatting_provider, Some(p) if p != OneOf::Left(false)) {
+            log::warn!(
+                "{} can't format ranges, not formatting the changed lines of {abs_path:?}",
+                language_server.name()
+            );
+            return Ok(Vec::new());
         }
 
-        let mut child = child
-            .args(arguments.iter().map(|arg| {
-                if let Some(buffer_abs_path) = buffer_abs_path {
-                    arg.replace("{buffer_path}", &buffer_abs_path.to_string_lossy())
-                } else {
-                    arg.replace("{buffer_path}", "Untitled")
-                }
-            }))
-            .stdin(smol::process::Stdio::piped())
-            .stdout(smol::process::Stdio::piped())
-            .stderr(smol::process::Stdio::piped())
-            .spawn()?;
+        // The diff is recomputed in the background after edits, so it may not include the latest ones yet.
+        let recalc = buffer.update(cx, |buffer, cx| buffer.git_diff_recalc(cx))?;
+        if let Some(recalc) = recalc {
+            recalc.await;
+        }
+        let ranges = buffer.update(cx, |buffer, _| {
+            let snapshot = buffer.snapshot();
+            snapshot
+                .git_diff_hunks_in_row_range(0..snapshot.max_point().row + 1)
+                .map(|hunk| {
+                    let rows = hunk.associated_range;
+                    // Hunks of deleted lines are empty, the line they were deleted at is formatted.
+                    let end_row = rows.end.saturating_sub(1).max(rows.start);
+                    let start = snapshot
+                        .clip_point_utf16(Unclipped(PointUtf16::new(rows.start, 0)), Bias::Left);
+                    let end = snapshot.clip_point_utf16(
+                        Unclipped(PointUtf16::new(end_row, u32::MAX)),
+                        Bias::Left,
+                    );
+                    lsp::Range::new(point_to_lsp(start), point_to_lsp(end))
+                })
+                .collect::<Vec<_>>()
+        })?;
 
-        let stdin = child
-            .stdin
-            .as_mut()
-            .ok_or_else(|| anyhow!("failed to acquire stdin"))?;
-        let text = buffer.update(cx, |buffer, _| buffer.as_rope().clone())?;
-        for chunk in text.chunks() {
-            stdin.write_all(chunk.as_bytes()).await?;
+        let uri = lsp::Url::from_file_path(abs_path)
+            .map_err(|_| anyhow!("failed to convert abs path to uri"))?;
+        let mut edits = Vec::new();
+        for range in ranges {
+            let lsp_edits = language_server
+                .request::<lsp::request::RangeFormatting>(lsp::DocumentRangeFormattingParams {
+                    text_document: lsp::TextDocumentIdentifier::new(uri.clone()),
+                    range,
+                    options: lsp_command::lsp_formatting_options(tab_size.get()),
+                    work_done_progress_params: Default::default(),
+                })
+                .await?;
+            if let Some(lsp_edits) = lsp_edits {
+                edits.extend(
+                    this.update(cx, |this, cx| {
+                        this.edits_from_lsp(
+                            buffer,
+                            lsp_edits,
+                            language_server.server_id(),
+                            None,
+                            cx,
+                        )
+                    })?
+                    .await?,
+                );
+            }
         }
-        stdin.flush().await?;
 
-        let output = child.output().await?;
+        // Each hunk is formatted on its own, so servers that format more than the requested range can
+        // return overlapping edits: only the first of them is kept.
+        buffer.update(cx, |buffer, _| {
+            let snapshot = buffer.snapshot();
+            edits.sort_by(|(a, _), (b, _)| a.start.cmp(&b.start, &snapshot));
+            let mut last_end: Option<Anchor> = None;
+            edits.retain(|(range, _)| {
+                if last_end.map_or(false, |end| range.start.cmp(&end, &snapshot).is_lt()) {
+                    return false;
+                }
+                last_end = Some(range.end);
+                true
+            });
+        })?;
+        Ok(edits)
+    }
+
+    async fn format_via_external_command(
+        buffer: &Model<Buffer>,
+        buffer_abs_path: Option<&Path>,
+        command: &str,
+        arguments: &[String],
+        cx: &mut AsyncAppContext,
+    ) -> Result<Option<Diff>> {
+        let output =
+            Self::run_command_with_buffer_stdin(buffer, buffer_abs_path, command, arguments, cx)
+                .await?;
         if !output.status.success() {
             return Err(anyhow!(
                 "command failed with exit code {:?}:\nstdout: {}\nstderr: {}",
@@ -5407,12 +6248,16 @@ impl Project {
         position: PointUtf16,
         cx: &mut ModelContext<Self>,
     ) -> Task<Result<Vec<LocationLink>>> {
-        self.request_lsp(
+        let definitions = self.request_lsp(
             buffer.clone(),
             LanguageServerToQuery::Primary,
             GetDefinition { position },
             cx,
-        )
+        );
+        let definitions = self.definition_with_index_fallback(buffer, position, definitions, cx);
+        let definitions =
+            self.definition_with_symbol_index_fallback(buffer, position, definitions, cx);
+        self.definition_with_response_cache(buffer, position, definitions, cx)
     }
     pub fn definition<T: ToPointUtf16>(
         &self,
@@ -5478,12 +6323,17 @@ impl Project {
         position: PointUtf16,
         cx: &mut ModelContext<Self>,
     ) -> Task<Result<Vec<Location>>> {
-        self.request_lsp(
+        let references = self.request_lsp(
             buffer.clone(),
             LanguageServerToQuery::Primary,
-            GetReferences { position },
+            GetReferences {
+                position,
+                partial_result_token: None,
+                anchored_chunks: None,
+            },
             cx,
-        )
+        );
+        self.references_with_index_fallback(buffer, position, references, cx)
     }
     pub fn references<T: ToPointUtf16>(
         &self,
@@ -5495,6 +6345,100 @@ impl Project {
         self.references_impl(buffer, position, cx)
     }
 
+    pub fn prepare_type_hierarchy<T: ToPointUtf16>(
+        &self,
+        buffer: &Model<Buffer>,
+        position: T,
+        cx: &mut ModelContext<Self>,
+    ) -> Task<Result<Vec<TypeHierarchyItem>>> {
+        let position = position.to_point_utf16(buffer.read(cx));
+        self.request_lsp(
+            buffer.clone(),
+            LanguageServerToQuery::Primary,
+            PrepareTypeHierarchy { position },
+            cx,
+        )
+    }
+
+    pub fn type_hierarchy_supertypes(
+        &self,
+        item: &TypeHierarchyItem,
+        cx: &mut ModelContext<Self>,
+    ) -> Task<Result<Vec<TypeHierarchyItem>>> {
+        self.request_lsp(
+            item.location.buffer.clone(),
+            LanguageServerToQuery::Other(item.language_server_id),
+            GetTypeHierarchySupertypes {
+                item: item.lsp_item.clone(),
+                server_id: item.language_server_id,
+            },
+            cx,
+        )
+    }
+
+    pub fn type_hierarchy_subtypes(
+        &self,
+        item: &TypeHierarchyItem,
+        cx: &mut ModelContext<Self>,
+    ) -> Task<Result<Vec<TypeHierarchyItem>>> {
+        self.request_lsp(
+            item.location.buffer.clone(),
+            LanguageServerToQuery::Other(item.language_server_id),
+            GetTypeHierarchySubtypes {
+                item: item.lsp_item.clone(),
+                server_id: item.language_server_id,
+            },
+            cx,
+        )
+    }
+
+    pub fn prepare_call_hierarchy<T: ToPointUtf16>(
+        &self,
+        buffer: &Model<Buffer>,
+        position: T,
+        cx: &mut ModelContext<Self>,
+    ) -> Task<Result<Vec<CallHierarchyItem>>> {
+        let position = position.to_point_utf16(buffer.read(cx));
+        self.request_lsp(
+            buffer.clone(),
+            LanguageServerToQuery::Primary,
+            PrepareCallHierarchy { position },
+            cx,
+        )
+    }
+
+    pub fn incoming_calls(
+        &self,
+        item: &CallHierarchyItem,
+        cx: &mut ModelContext<Self>,
+    ) -> Task<Result<Vec<CallHierarchyCall>>> {
+        self.request_lsp(
+            item.location.buffer.clone(),
+            LanguageServerToQuery::Other(item.language_server_id),
+            GetIncomingCalls {
+                item: item.lsp_item.clone(),
+                server_id: item.language_server_id,
+            },
+            cx,
+        )
+    }
+
+    pub fn outgoing_calls(
+        &self,
+        item: &CallHierarchyItem,
+        cx: &mut ModelContext<Self>,
+    ) -> Task<Result<Vec<CallHierarchyCall>>> {
+        self.request_lsp(
+            item.location.buffer.clone(),
+            LanguageServerToQuery::Other(item.language_server_id),
+            GetOutgoingCalls {
+                item: item.lsp_item.clone(),
+                server_id: item.language_server_id,
+            },
+            cx,
+        )
+    }
+
     fn document_highlights_impl(
         &self,
         buffer: &Model<Buffer>,
@@ -5524,29 +6468,10 @@ impl Project {
 
         if self.is_local() {
             let mut requests = Vec::new();
-            for ((worktree_id, _), server_id) in self.language_server_ids.iter() {
-                let Some(worktree_handle) = self.worktree_for_id(*worktree_id, cx) else {
-                    continue;
-                };
-                let worktree = worktree_handle.read(cx);
-                if !worktree.is_visible() {
-                    continue;
-                }
-                let worktree_abs_path = worktree.abs_path().clone();
-
-                let (adapter, language, server) = match self.language_servers.get(server_id) {
-                    Some(LanguageServerState::Running {
-                        adapter,
-                        language,
-                        server,
-                        ..
-                    }) => (adapter.clone(), language.clone(), server),
-
-                    _ => continue,
-                };
-
+            for symbols_server in self.workspace_symbols_servers(cx) {
                 requests.push(
-                    server
+                    symbols_server
+                        .server
                         .request::<lsp::request::WorkspaceSymbolRequest>(
                             lsp::WorkspaceSymbolParams {
                                 query: query.to_string(),
@@ -5555,37 +6480,16 @@ impl Project {
                         )
                         .log_err()
                         .map(move |response| {
-                            let lsp_symbols = response.flatten().map(|symbol_response| match symbol_response {
-                                lsp::WorkspaceSymbolResponse::Flat(flat_responses) => {
-                                    flat_responses.into_iter().map(|lsp_symbol| {
-                                        (lsp_symbol.name, lsp_symbol.kind, lsp_symbol.location)
-                                    }).collect::<Vec<_>>()
-                                }
-                                lsp::WorkspaceSymbolResponse::Nested(nested_responses) => {
-                                    nested_responses.into_iter().filter_map(|lsp_symbol| {
-                                        let location = match lsp_symbol.location {
-                                            OneOf::Left(location) => location,
-                                            OneOf::Right(_) => {
-                                                error!("Unexpected: client capabilities forbid symbol resolutions in workspace.symbol.resolveSupport");
-                                                return None
-                                            }
-                                        };
-                                        Some((lsp_symbol.name, lsp_symbol.kind, location))
-                                    }).collect::<Vec<_>>()
-                                }
-                            }).unwrap_or_default();
-
-                            (
-                                adapter,
-                                language,
-                                worktree_handle.downgrade(),
-                                worktree_abs_path,
-                                lsp_symbols,
-                            )
+                            let lsp_symbols = response
+                                .flatten()
+                                .map(lsp_workspace_symbols)
+                                .unwrap_or_default();
+                            (symbols_server, lsp_symbols)
                         }),
                 );
             }
 
+            let indexed_symbols = self.symbol_index_matches(query, cx);
             cx.spawn(move |this, mut cx| async move {
                 let responses = futures::future::join_all(requests).await;
                 let this = match this.upgrade() {
@@ -5593,54 +6497,17 @@ impl Project {
                     None => return Ok(Vec::new()),
                 };
 
-                let mut symbols = Vec::new();
-                for (adapter, adapter_language, source_worktree, worktree_abs_path, lsp_symbols) in
-                    responses
-                {
+                let mut symbols = indexed_symbols;
+                for (symbols_server, lsp_symbols) in responses {
                     let core_symbols = this.update(&mut cx, |this, cx| {
-                        lsp_symbols
-                            .into_iter()
-                            .filter_map(|(symbol_name, symbol_kind, symbol_location)| {
-                                let abs_path = symbol_location.uri.to_file_path().ok()?;
-                                let source_worktree = source_worktree.upgrade()?;
-                                let source_worktree_id = source_worktree.read(cx).id();
-
-                                let path;
-                                let worktree;
-                                if let Some((tree, rel_path)) =
-                                    this.find_local_worktree(&abs_path, cx)
-                                {
-                                    worktree = tree;
-                                    path = rel_path;
-                                } else {
-                                    worktree = source_worktree.clone();
-                                    path = relativize_path(&worktree_abs_path, &abs_path);
-                                }
-
-                                let worktree_id = worktree.read(cx).id();
-                                let project_path = ProjectPath {
-                                    worktree_id,
-                                    path: path.into(),
-                                };
-                                let signature = this.symbol_signature(&project_path);
-                                Some(CoreSymbol {
-                                    language_server_name: adapter.name.clone(),
-                                    source_worktree_id,
-                                    path: project_path,
-                                    kind: symbol_kind,
-                                    name: symbol_name,
-                                    range: range_from_lsp(symbol_location.range),
-                                    signature,
-                                })
-                            })
-                            .collect()
+                        this.core_symbols_from_lsp(&symbols_server, lsp_symbols, cx)
                     })?;
 
                     populate_labels_for_symbols(
                         core_symbols,
                         &language_registry,
-                        Some(adapter_language),
-                        Some(adapter),
+                        Some(symbols_server.language),
+                        Some(symbols_server.adapter),
                         &mut symbols,
                     )
                     .await;
@@ -5652,6 +6519,7 @@ impl Project {
             let request = self.client.request(proto::GetProjectSymbols {
                 project_id,
                 query: query.to_string(),
+                partial_result_token: None,
             });
             cx.foreground_executor().spawn(async move {
                 let response = request.await?;
@@ -5676,17 +6544,113 @@ impl Project {
         }
     }
 
+    /// The running language servers to query for the symbols of the visible worktrees.
+    fn workspace_symbols_servers(&self, cx: &AppContext) -> Vec<WorkspaceSymbolsServer> {
+        let mut servers = Vec::new();
+        for ((worktree_id, _, _), server_id) in self.language_server_ids.iter() {
+            let Some(worktree_handle) = self.worktree_for_id(*worktree_id, cx) else {
+                continue;
+            };
+            let worktree = worktree_handle.read(cx);
+            if !worktree.is_visible() {
+                continue;
+            }
+
+            let (adapter, language, server) = match self.language_servers.get(server_id) {
+                Some(LanguageServerState::Running {
+                    adapter,
+                    language,
+                    server,
+                    ..
+                }) => (adapter.clone(), language.clone(), server.clone()),
+
+                _ => continue,
+            };
+
+            servers.push(WorkspaceSymbolsServer {
+                adapter,
+                language,
+                server,
+                worktree: worktree_handle.downgrade(),
+                worktree_abs_path: worktree.abs_path().clone(),
+            });
+        }
+        servers
+    }
+
+    fn core_symbols_from_lsp(
+        &self,
+        symbols_server: &WorkspaceSymbolsServer,
+        lsp_symbols: Vec<LspWorkspaceSymbol>,
+        cx: &AppContext,
+    ) -> Vec<CoreSymbol> {
+        lsp_symbols
+            .into_iter()
+            .filter_map(
+                |(symbol_name, symbol_kind, symbol_location, unresolved_symbol)| {
+                    let abs_path = symbol_location.uri.to_file_path().ok()?;
+                    let source_worktree = symbols_server.worktree.upgrade()?;
+                    let source_worktree_id = source_worktree.read(cx).id();
+
+                    let path;
+                    let worktree;
+                    if let Some((tree, rel_path)) = self.find_local_worktree(&abs_path, cx) {
+                        worktree = tree;
+                        path = rel_path;
+                    } else {
+                        worktree = source_worktree.clone();
+                        path = relativize_path(&symbols_server.worktree_abs_path, &abs_path);
+                    }
+
+                    let worktree_id = worktree.read(cx).id();
+                    let project_path = ProjectPath {
+                        worktree_id,
+                        path: path.into(),
+                    };
+                    let signature = self.symbol_signature(&project_path);
+                    Some(CoreSymbol {
+                        language_server_name: symbols_server.adapter.name.clone(),
+                        source_worktree_id,
+                        path: project_path,
+                        kind: symbol_kind,
+                        name: symbol_name,
+                        range: range_from_lsp(symbol_location.range),
+                        signature,
+                        unresolved_symbol,
+                    })
+                },
+            )
+            .collect()
+    }
+
+    /// The local language server that reported the symbol: the one at the deepest root containing the symbol, if
+    /// the symbol is in the worktree the server was found for.
+    fn language_server_id_for_symbol(&self, symbol: &Symbol) -> Option<LanguageServerId> {
+        self.language_server_ids
+            .iter()
+            .filter(|((worktree_id, server_name, _), _)| {
+                *worktree_id == symbol.source_worktree_id
+                    && *server_name == symbol.language_server_name
+            })
+            .max_by_key(|((_, _, root), _)| {
+                let contains_symbol = symbol.path.worktree_id == symbol.source_worktree_id
+                    && symbol.path.path.starts_with(root);
+                (contains_symbol, root.components().count())
+            })
+            .map(|(_, server_id)| *server_id)
+    }
+
     pub fn open_buffer_for_symbol(
         &mut self,
         symbol: &Symbol,
         cx: &mut ModelContext<Self>,
     ) -> Task<Result<Model<Buffer>>> {
         if self.is_local() {
-            let language_server_id = if let Some(id) = self.language_server_ids.get(&(
-                symbol.source_worktree_id,
-                symbol.language_server_name.clone(),
-            )) {
-                *id
+            if symbol.language_server_name.0.as_ref() == SYMBOL_INDEX_SERVER_NAME {
+                return self.open_buffer(symbol.path.clone(), cx);
+            }
+            let language_server_id = if let Some(id) = self.language_server_id_for_symbol(symbol) {
+                id
             } else {
                 return Task::ready(Err(anyhow!(
                     "language server for worktree and language not found"
@@ -5716,9 +6680,13 @@ impl Project {
                 cx,
             )
         } else if let Some(project_id) = self.remote_id() {
+            let symbol = match serialize_symbol(symbol) {
+                Ok(symbol) => symbol,
+                Err(error) => return Task::ready(Err(error)),
+            };
             let request = self.client.request(proto::OpenBufferForSymbol {
                 project_id,
-                symbol: Some(serialize_symbol(symbol)),
+                symbol: Some(symbol),
             });
             cx.spawn(move |this, mut cx| async move {
                 let response = request.await?;
@@ -5733,6 +6701,64 @@ impl Project {
         }
     }
 
+    /// Requests the range of a symbol the language server sent without one, with `workspaceSymbol/resolve`.
+    /// Resolved symbols are returned unchanged.
+    pub fn resolve_symbol(
+        &mut self,
+        symbol: &Symbol,
+        cx: &mut ModelContext<Self>,
+    ) -> Task<Result<Symbol>> {
+        let Some(unresolved_symbol) = symbol.unresolved_symbol.clone() else {
+            return Task::ready(Ok(symbol.clone()));
+        };
+        let mut symbol = symbol.clone();
+        if self.is_local() {
+            let server = self
+                .language_server_id_for_symbol(&symbol)
+                .and_then(|server_id| self.language_servers.get(&server_id));
+            let Some(LanguageServerState::Running { server, .. }) = server else {
+                return Task::ready(Err(anyhow!(
+                    "language server for worktree and language not found"
+                )));
+            };
+            let request = server
+                .request::<lsp::request::WorkspaceSymbolResolve>((*unresolved_symbol).clone());
+            cx.spawn(move |_, _| async move {
+                let resolved = request.await.context("resolving workspace symbol")?;
+                match resolved.location {
+                    OneOf::Left(location) => {
+                        symbol.range = range_from_lsp(location.range);
+                        symbol.unresolved_symbol = None;
+                    }
+                    OneOf::Right(_) => {
+                        log::info!("no range resolved for symbol {}", symbol.name);
+                    }
+                }
+                Ok(symbol)
+            })
+        } else if let Some(project_id) = self.remote_id() {
+            let serialized_symbol = match serialize_symbol(&symbol) {
+                Ok(serialized_symbol) => serialized_symbol,
+                Err(error) => return Task::ready(Err(error)),
+            };
+            let request = self.client.request(proto::ResolveWorkspaceSymbol {
+                project_id,
+                symbol: Some(serialized_symbol),
+            });
+            cx.spawn(move |_, _| async move {
+                let response = request.await?;
+                let resolved = Self::deserialize_symbol(
+                    response.symbol.ok_or_else(|| anyhow!("invalid symbol"))?,
+                )?;
+                symbol.range = resolved.range;
+                symbol.unresolved_symbol = resolved.unresolved_symbol;
+                Ok(symbol)
+            })
+        } else {
+            Task::ready(Err(anyhow!("project does not have a remote id")))
+        }
+    }
+
     fn hover_impl(
         &self,
         buffer: &Model<Buffer>,
@@ -5751,12 +6777,16 @@ impl Project {
                 GetHover { position },
                 cx,
             );
-            cx.spawn(|_, _| async move {
-                all_actions_task
+            cx.spawn(|this, mut cx| async move {
+                let hovers = all_actions_task
                     .await
                     .into_iter()
-                    .filter_map(|hover| remove_empty_hover_blocks(hover?))
-                    .collect()
+                    .filter_map(|(server_id, hover)| {
+                        Some((server_id, remove_empty_hover_blocks(hover?)?))
+                    })
+                    .collect();
+                this.update(&mut cx, |this, cx| this.merge_hovers(hovers, cx))
+                    .unwrap_or_default()
             })
         } else if let Some(project_id) = self.remote_id() {
             let request_task = self.client().request(proto::MultiLspQuery {
@@ -5771,11 +6801,11 @@ impl Project {
                 )),
             });
             let buffer = buffer.clone();
-            cx.spawn(|weak_project, cx| async move {
+            cx.spawn(|weak_project, mut cx| async move {
                 let Some(project) = weak_project.upgrade() else {
                     return Vec::new();
                 };
-                join_all(
+                let hovers = join_all(
                     request_task
                         .await
                         .log_err()
@@ -5784,14 +6814,14 @@ impl Project {
                         .into_iter()
                         .filter_map(|lsp_response| match lsp_response.response? {
                             proto::lsp_response::Response::GetHoverResponse(response) => {
-                                Some(response)
+                                Some((LanguageServerId(lsp_response.server_id as usize), response))
                             }
                             unexpected => {
                                 debug_panic!("Unexpected response: {unexpected:?}");
                                 None
                             }
                         })
-                        .map(|hover_response| {
+                        .map(|(server_id, hover_response)| {
                             let response = GetHover { position }.response_from_proto(
                                 hover_response,
                                 project.clone(),
@@ -5799,18 +6829,22 @@ impl Project {
                                 cx.clone(),
                             );
                             async move {
-                                response
+                                let hover = response
                                     .await
                                     .log_err()
                                     .flatten()
-                                    .and_then(remove_empty_hover_blocks)
+                                    .and_then(remove_empty_hover_blocks)?;
+                                Some((server_id, hover))
                             }
                         }),
                 )
                 .await
                 .into_iter()
                 .flatten()
-                .collect()
+                .collect::<Vec<_>>();
+                project
+                    .update(&mut cx, |project, cx| project.merge_hovers(hovers, cx))
+                    .unwrap_or_default()
             })
         } else {
             log::error!("cannot show hovers: project does not have a remote id");
@@ -5825,7 +6859,8 @@ impl Project {
         cx: &mut ModelContext<Self>,
     ) -> Task<Vec<Hover>> {
         let position = position.to_point_utf16(buffer.read(cx));
-        self.hover_impl(buffer, position, cx)
+        let hovers = self.hover_impl(buffer, position, cx);
+        self.hover_with_response_cache(buffer, position, hovers, cx)
     }
 
     fn linked_edit_impl(
@@ -5867,21 +6902,91 @@ impl Project {
             return Task::ready(Ok(vec![]));
         };
 
-        self.request_lsp(
-            buffer.clone(),
-            server_id,
-            LinkedEditingRange { position },
-            cx,
-        )
+        self.request_lsp(
+            buffer.clone(),
+            server_id,
+            LinkedEditingRange { position },
+            cx,
+        )
+    }
+
+    pub fn linked_edit(
+        &self,
+        buffer: &Model<Buffer>,
+        position: Anchor,
+        cx: &mut ModelContext<Self>,
+    ) -> Task<Result<Vec<Range<Anchor>>>> {
+        self.linked_edit_impl(buffer, position, cx)
+    }
+
+    /// Invokes a server-specific `workspace/executeCommand` command on the first language server of the buffer
+    /// that declares support for it.
+    pub fn execute_lsp_command(
+        &self,
+        buffer: &Model<Buffer>,
+        command: ExecuteLspCommand,
+        cx: &mut ModelContext<Self>,
+    ) -> Task<Result<Option<serde_json::Value>>> {
+        if let Some(server_id) = command.server_id {
+            return self.request_lsp(
+                buffer.clone(),
+                LanguageServerToQuery::Other(server_id),
+                command,
+                cx,
+            );
+        }
+        let Some(server_id) = self
+            .language_servers_for_buffer(buffer.read(cx), cx)
+            .find(|(_, server)| command.is_supported_by(server.capabilities()))
+            .map(|(_, server)| LanguageServerToQuery::Other(server.server_id()))
+            .or_else(|| self.is_remote().then_some(LanguageServerToQuery::Primary))
+        else {
+            return Task::ready(Err(anyhow!(
+                "no language server supports command {:?}",
+                command.command
+            )));
+        };
+
+        self.request_lsp(buffer.clone(), server_id, command, cx)
     }
 
-    pub fn linked_edit(
+    /// Invokes a server-specific `workspace/executeCommand` command on the language server with the name running
+    /// in the worktree, for the extensions that provide the server.
+    pub fn execute_lsp_command_on_server(
         &self,
-        buffer: &Model<Buffer>,
-        position: Anchor,
+        worktree_id: WorktreeId,
+        server_name: &LanguageServerName,
+        command: ExecuteLspCommand,
         cx: &mut ModelContext<Self>,
-    ) -> Task<Result<Vec<Range<Anchor>>>> {
-        self.linked_edit_impl(buffer, position, cx)
+    ) -> Task<Result<Option<serde_json::Value>>> {
+        let server = self
+            .language_server_ids
+            .iter()
+            .filter(|((id, name, _), _)| *id == worktree_id && name == server_name)
+            .find_map(|(_, server_id)| self.language_server_for_id(*server_id));
+        let Some(server) = server else {
+            return Task::ready(Err(anyhow!(
+                "language server {} isn't running in the worktree",
+                server_name.0
+            )));
+        };
+        if !command.is_supported_by(server.capabilities()) {
+            return Task::ready(Err(anyhow!(
+                "language server {} doesn't support command {:?}",
+                server_name.0,
+                command.command
+            )));
+        }
+
+        cx.background_executor().spawn(async move {
+            server
+                .request::<lsp::request::ExecuteCommand>(lsp::ExecuteCommandParams {
+                    command: command.command,
+                    arguments: command.arguments,
+                    work_done_progress_params: Default::default(),
+                })
+                .await
+        })
     }
 
     #[inline(never)]
@@ -5891,7 +6996,7 @@ impl Project {
         position: PointUtf16,
         context: CompletionContext,
         cx: &mut ModelContext<Self>,
-    ) -> Task<Result<Vec<Completion>>> {
+    ) -> Task<Result<CompletionResponse>> {
         let language_registry = self.languages.clone();
 
         if self.is_local() {
@@ -5934,10 +7039,12 @@ impl Project {
                 })?;
 
                 let mut completions = Vec::new();
+                let mut is_incomplete = false;
                 for (lsp_adapter, task) in tasks {
-                    if let Ok(new_completions) = task.await {
+                    if let Ok(response) = task.await {
+                        is_incomplete |= response.is_incomplete;
                         populate_labels_for_completions(
-                            new_completions,
+                            response.completions,
                             &language_registry,
                             language.clone(),
                             lsp_adapter,
@@ -5947,7 +7054,10 @@ impl Project {
                     }
                 }
 
-                Ok(completions)
+                this.update(&mut cx, |this, cx| CompletionResponse {
+                    completions: this.merge_completions(completions, cx),
+                    is_incomplete,
+                })
             })
         } else if let Some(project_id) = self.remote_id() {
             let task = self.send_lsp_proto_request(
@@ -5965,18 +7075,21 @@ impl Project {
                 .as_ref()
                 .and_then(|language| language_registry.lsp_adapters(language).first().cloned());
 
-            cx.foreground_executor().spawn(async move {
-                let completions = task.await?;
+            cx.spawn(move |this, mut cx| async move {
+                let response = task.await?;
                 let mut result = Vec::new();
                 populate_labels_for_completions(
-                    completions,
+                    response.completions,
                     &language_registry,
                     language,
                     lsp_adapter,
                     &mut result,
                 )
                 .await;
-                Ok(result)
+                this.update(&mut cx, |this, cx| CompletionResponse {
+                    completions: this.merge_completions(result, cx),
+                    is_incomplete: response.is_incomplete,
+                })
             })
         } else {
             Task::ready(Ok(Default::default()))
@@ -5989,7 +7102,7 @@ impl Project {
         position: T,
         context: CompletionContext,
         cx: &mut ModelContext<Self>,
-    ) -> Task<Result<Vec<Completion>>> {
+    ) -> Task<Result<CompletionResponse>> {
         let position = position.to_point_utf16(buffer.read(cx));
         self.completions_impl(buffer, position, context, cx)
     }
@@ -6340,7 +7453,15 @@ impl Project {
                 },
                 cx,
             );
-            cx.spawn(|_, _| async move { all_actions_task.await.into_iter().flatten().collect() })
+            let fixes = self.diagnostic_source_fixes(buffer_handle, &range, cx);
+            cx.spawn(|_, _| async move {
+                all_actions_task
+                    .await
+                    .into_iter()
+                    .flat_map(|(_, actions)| actions)
+                    .chain(fixes)
+                    .collect()
+            })
         } else if let Some(project_id) = self.remote_id() {
             let request_task = self.client().request(proto::MultiLspQuery {
                 buffer_id: buffer_handle.read(cx).remote_id().into(),
@@ -6422,6 +7543,11 @@ impl Project {
         cx: &mut ModelContext<Self>,
     ) -> Task<Result<ProjectTransaction>> {
         if self.is_local() {
+            if let Some(apply_fix) =
+                self.apply_diagnostic_source_fix(&buffer_handle, &action, push_to_history, cx)
+            {
+                return apply_fix;
+            }
             let buffer = buffer_handle.read(cx);
             let (lsp_adapter, lang_server) = if let Some((adapter, server)) =
                 self.language_server_for_buffer(buffer, action.server_id, cx)
@@ -6612,25 +7738,19 @@ impl Project {
         cx: &mut AsyncAppContext,
     ) -> Result<ProjectTransaction> {
         let fs = this.update(cx, |this, _| this.fs.clone())?;
-        let mut operations = Vec::new();
-        if let Some(document_changes) = edit.document_changes {
-            match document_changes {
-                lsp::DocumentChanges::Edits(edits) => {
-                    operations.extend(edits.into_iter().map(lsp::DocumentChangeOperation::Edit))
-                }
-                lsp::DocumentChanges::Operations(ops) => operations = ops,
-            }
-        } else if let Some(changes) = edit.changes {
-            operations.extend(changes.into_iter().map(|(uri, edits)| {
-                lsp::DocumentChangeOperation::Edit(lsp::TextDocumentEdit {
-                    text_document: lsp::OptionalVersionedTextDocumentIdentifier {
-                        uri,
-                        version: None,
-                    },
-                    edits: edits.into_iter().map(Edit::Plain).collect(),
-                })
-            }));
-        }
+        let operations = workspace_edit_operations(edit);
+        let conflicts = this.update(cx, |this, cx| {
+            this.workspace_edit_conflicts(&operations, language_server.server_id(), cx)
+        })?;
+        // The edits that aren't pushed to the history of the host are the ones requested by guests.
+        let resolutions = Self::resolve_workspace_edit_conflicts(
+            &this,
+            conflicts,
+            &language_server,
+            !push_to_history,
+            cx,
+        )
+        .await?;
 
         let mut project_transaction = ProjectTransaction::default();
         for operation in operations {
@@ -6713,6 +7833,23 @@ impl Project {
                         })?
                         .await?;
 
+                    match resolutions.get(&op.text_document.uri) {
+                        Some(WorkspaceEditConflictResolution::Skip) => continue,
+                        Some(WorkspaceEditConflictResolution::OpenDiff) => {
+                            Self::review_conflicting_edits(
+                                &this,
+                                buffer_to_edit,
+                                op,
+                                language_server.server_id(),
+                                cx,
+                            )
+                            .await
+                            .log_err();
+                            continue;
+                        }
+                        Some(WorkspaceEditConflictResolution::Overwrite) | None => {}
+                    }
+
                     let edits = this
                         .update(cx, |this, cx| {
                             let path = buffer_to_edit.read(cx).project_path(cx);
@@ -7036,17 +8173,31 @@ impl Project {
         }
     }
 
-    #[allow(clippy::type_complexity)]
     pub fn search(
         &self,
         query: SearchQuery,
         cx: &mut ModelContext<Self>,
+    ) -> Receiver<SearchResult> {
+        self.search_with_limits(query, SearchLimits::default(), cx)
+    }
+
+    /// Searches the project, stopping at the limits, e.g. raised to load more results than a search that
+    /// reached the default ones.
+    pub fn search_with_limits(
+        &self,
+        query: SearchQuery,
+        limits: SearchLimits,
+        cx: &mut ModelContext<Self>,
     ) -> Receiver<SearchResult> {
         if self.is_local() {
-            self.search_local(query, cx)
+            self.search_local(query, limits, cx)
         } else if let Some(project_id) = self.remote_id() {
             let (tx, rx) = smol::channel::unbounded();
-            let request = self.client.request(query.to_proto(project_id));
+            let request = self.client.request(proto::SearchProject {
+                max_files: Some(limits.max_files as u64),
+                max_ranges: Some(limits.max_ranges as u64),
+                ..query.to_proto(project_id)
+            });
             cx.spawn(move |this, mut cx| async move {
                 let response = request.await?;
                 let mut result = HashMap::default();
@@ -7090,12 +8241,16 @@ impl Project {
     pub fn search_local(
         &self,
         query: SearchQuery,
+        limits: SearchLimits,
         cx: &mut ModelContext<Self>,
     ) -> Receiver<SearchResult> {
         // Local search is split into several phases.
         // TL;DR is that we do 2 passes; initial pass to pick files which contain at least one match
         // and the second phase that finds positions of all the matches found in the candidate files.
-        // The Receiver obtained from this function returns matches sorted by buffer path. Files without a buffer path are reported first.
+        // The Receiver obtained from this function streams the matches in batches, as soon as the
+        // candidates of a batch are found, instead of waiting for the whole project to be traversed.
+        // The batches arrive in no particular order, so their consumers have to order the matches themselves,
+        // with `compare_search_result_buffers`.
         //
         // It gets a bit hairy though, because we must account for files that do not have a persistent representation
         // on FS. Namely, if you have an untitled buffer or unsaved changes in a buffer, we want to scan that too.
@@ -7103,23 +8258,20 @@ impl Project {
         // 1. We initialize a queue of match candidates and feed all opened buffers into it (== unsaved files / untitled buffers).
         //    Then, we go through a worktree and check for files that do match a predicate. If the file had an opened version, we skip the scan
         //    of FS version for that file altogether - after all, what we have in memory is more up-to-date than what's in FS.
-        // 2. At this point, we have a list of all potentially matching buffers/files.
-        //    We sort that list by buffer path - this list is retained for later use.
-        //    We ensure that all buffers are now opened and available in project.
-        // 3. We run a scan over all the candidate buffers on multiple background threads.
+        // 2. As the potentially matching buffers/files arrive, we take them in batches of the ones found so far.
+        //    We sort each batch by buffer path, so that its buffers are opened in order, and ensure that they
+        //    are opened and available in project.
+        // 3. We run a scan over the candidate buffers of the batch on multiple background threads.
         //    We cannot assume that there will even be a match - while at least one match
         //    is guaranteed for files obtained from FS, the buffers we got from memory (unsaved files/unnamed buffers) might not have a match at all.
-        //    There is also an auxiliary background thread responsible for result gathering.
-        //    This is where the sorted list of buffers comes into play to maintain sorted order; Whenever this background thread receives a notification (buffer has/doesn't have matches),
-        //    it keeps it around. It reports matches in sorted order, though it accepts them in unsorted order as well.
-        //    As soon as the match info on next position in sorted order becomes available, it reports it (if it's a match) or skips to the next
-        //    entry - which might already be available thanks to out-of-order processing.
+        //    The matches of the batch are reported once all of its buffers are scanned.
         //
-        // We could also report matches fully out-of-order, without maintaining a sorted list of matching paths.
-        // This however would mean that project search (that is the main user of this function) would have to do the sorting itself, on the go.
-        // This isn't as straightforward as running an insertion sort sadly, and would also mean that it would have to care about maintaining match index
-        // in face of constantly updating list of sorted matches.
-        // Meanwhile, this implementation offers index stability, since the matches are already reported in a sorted order.
+        // Keeping the matches sorted across batches would mean waiting for the whole project to be traversed, as the
+        // worktrees are traversed by several workers at once. Project search (that is the main user of this function)
+        // inserts the matches of each buffer at the position of the buffer among the ones it shows instead.
+        //
+        // Once the receiver is dropped, e.g. because the query changed, the batch in progress is the last one, and the
+        // traversal of the worktrees stops as soon as it notices that its candidates aren't received anymore.
         let snapshots = self
             .visible_worktrees(cx)
             .filter_map(|tree| {
@@ -7197,46 +8349,41 @@ impl Project {
         let (result_tx, result_rx) = smol::channel::bounded(1024);
 
         cx.spawn(|this, mut cx| async move {
-            const MAX_SEARCH_RESULT_FILES: usize = 5_000;
-            const MAX_SEARCH_RESULT_RANGES: usize = 10_000;
-
-            let mut matching_paths = matching_paths_rx
-                .take(MAX_SEARCH_RESULT_FILES + 1)
-                .collect::<Vec<_>>()
-                .await;
-            let mut limit_reached = if matching_paths.len() > MAX_SEARCH_RESULT_FILES {
-                matching_paths.pop();
-                true
-            } else {
-                false
-            };
-            cx.update(|cx| {
-                sort_search_matches(&mut matching_paths, cx);
-            })?;
+            // At most this many buffers are loaded at a time, to avoid overwhelming the main thread.
+            const SEARCH_BATCH_SIZE: usize = 64;
 
-            let mut range_count = 0;
             let query = Arc::new(query);
+            let mut file_count = 0;
+            let mut range_count = 0;
+            let mut limit_reached = false;
+            let mut batches = matching_paths_rx.ready_chunks(SEARCH_BATCH_SIZE);
+
+            'outer: while let Some(mut batch) = batches.next().await {
+                if result_tx.is_closed() {
+                    break;
+                }
+                if file_count + batch.len() > limits.max_files {
+                    batch.truncate(limits.max_files - file_count);
+                    limit_reached = true;
+                }
+                file_count += batch.len();
+                cx.update(|cx| sort_search_matches(&mut batch, cx))?;
 
-            // Now that we know what paths match the query, we will load at most
-            // 64 buffers at a time to avoid overwhelming the main thread. For each
-            // opened buffer, we will spawn a background task that retrieves all the
-            // ranges in the buffer matched by the query.
-            'outer: for matching_paths_chunk in matching_paths.chunks(64) {
-                let mut chunk_results = Vec::new();
-                for matching_path in matching_paths_chunk {
+                // For each opened buffer of the batch, we spawn a background task that retrieves all the
+                // ranges in the buffer matched by the query.
+                let mut batch_results = Vec::new();
+                for matching_path in batch {
                     let query = query.clone();
                     let buffer = match matching_path {
-                        SearchMatchCandidate::OpenBuffer { buffer, .. } => {
-                            Task::ready(Ok(buffer.clone()))
-                        }
+                        SearchMatchCandidate::OpenBuffer { buffer, .. } => Task::ready(Ok(buffer)),
                         SearchMatchCandidate::Path {
                             worktree_id, path, ..
                         } => this.update(&mut cx, |this, cx| {
-                            this.open_buffer((*worktree_id, path.clone()), cx)
+                            this.open_buffer((worktree_id, path), cx)
                         })?,
                     };
 
-                    chunk_results.push(cx.spawn(|cx| async move {
+                    batch_results.push(cx.spawn(|cx| async move {
                         let buffer = buffer.await?;
                         let snapshot = buffer.read_with(&cx, |buffer, _| buffer.snapshot())?;
                         let ranges = cx
@@ -7257,19 +8404,22 @@ impl Project {
                     }));
                 }
 
-                let chunk_results = futures::future::join_all(chunk_results).await;
-                for result in chunk_results {
+                let batch_results = futures::future::join_all(batch_results).await;
+                for result in batch_results {
                     if let Some((buffer, ranges)) = result.log_err() {
                         range_count += ranges.len();
                         result_tx
                             .send(SearchResult::Buffer { buffer, ranges })
                             .await?;
-                        if range_count > MAX_SEARCH_RESULT_RANGES {
+                        if range_count > limits.max_ranges {
                             limit_reached = true;
                             break 'outer;
                         }
                     }
                 }
+                if limit_reached {
+                    break;
+                }
             }
 
             if limit_reached {
@@ -7480,7 +8630,7 @@ impl Project {
         server_capabilities_check: fn(&ServerCapabilities) -> bool,
         request: R,
         cx: &mut ModelContext<'_, Self>,
-    ) -> Task<Vec<R::Response>>
+    ) -> Task<Vec<(LanguageServerId, R::Response)>>
     where
         P: ToOffset,
         R: LspCommand + Clone,
@@ -7493,7 +8643,7 @@ impl Project {
         }
         let snapshot = buffer.read(cx).snapshot();
         let scope = position.and_then(|position| snapshot.language_scope_at(position));
-        let mut response_results = self
+        let response_results = self
             .language_servers_for_buffer(buffer.read(cx), cx)
             .filter(|(_, server)| server_capabilities_check(server.capabilities()))
             .filter(|(adapter, _)| {
@@ -7504,23 +8654,23 @@ impl Project {
             })
             .map(|(_, server)| server.server_id())
             .map(|server_id| {
-                self.request_lsp(
+                let response = self.request_lsp(
                     buffer.clone(),
                     LanguageServerToQuery::Other(server_id),
                     request.clone(),
                     cx,
-                )
+                );
+                async move { Some((server_id, response.await.log_err()?)) }
             })
-            .collect::<FuturesUnordered<_>>();
+            .collect::<Vec<_>>();
 
+        // The responses are kept in the order of the servers, for the merging to rank them.
         return cx.spawn(|_, _| async move {
-            let mut responses = Vec::with_capacity(response_results.len());
-            while let Some(response_result) = response_results.next().await {
-                if let Some(response) = response_result.log_err() {
-                    responses.push(response);
-                }
-            }
-            responses
+            join_all(response_results)
+                .await
+                .into_iter()
+                .flatten()
+                .collect()
         });
     }
 
@@ -7705,21 +8855,32 @@ impl Project {
     pub fn remove_worktree(&mut self, id_to_remove: WorktreeId, cx: &mut ModelContext<Self>) {
         self.diagnostics.remove(&id_to_remove);
         self.diagnostic_summaries.remove(&id_to_remove);
+        self.worktree_trust.worktree_removed(id_to_remove);
+        self.environment_watcher.worktree_removed(id_to_remove);
+        self.symbol_index.worktree_removed(id_to_remove);
 
         let mut servers_to_remove = HashMap::default();
         let mut servers_to_preserve = HashSet::default();
-        for ((worktree_id, server_name), &server_id) in &self.language_server_ids {
+        for ((worktree_id, server_name, root), &server_id) in &self.language_server_ids {
             if worktree_id == &id_to_remove {
-                servers_to_remove.insert(server_id, server_name.clone());
+                servers_to_remove.insert(server_id, (server_name.clone(), root.clone()));
             } else {
                 servers_to_preserve.insert(server_id);
             }
         }
         servers_to_remove.retain(|server_id, _| !servers_to_preserve.contains(server_id));
-        for (server_id_to_remove, server_name) in servers_to_remove {
+        for (server_id_to_remove, (server_name, root)) in servers_to_remove {
             self.language_server_ids
-                .remove(&(id_to_remove, server_name));
+                .remove(&(id_to_remove, server_name, root));
             self.language_server_statuses.remove(&server_id_to_remove);
+            self.pull_diagnostics
+                .language_server_removed(server_id_to_remove);
+            self.semantic_tokens
+                .language_server_removed(server_id_to_remove);
+            self.document_links
+                .language_server_removed(server_id_to_remove);
+            self.document_colors
+                .language_server_removed(server_id_to_remove);
             self.language_server_watched_paths
                 .remove(&server_id_to_remove);
             self.last_workspace_edits_by_language_server
@@ -7789,6 +8950,8 @@ impl Project {
                         this.update_local_worktree_language_servers(&worktree, changes, cx);
                         this.update_local_worktree_settings(&worktree, changes, cx);
                         this.update_prettier_settings(&worktree, changes, cx);
+                        this.update_symbol_index(&worktree, changes, cx);
+                        this.update_worktree_environment(&worktree, changes, cx);
                     }
 
                     cx.emit(Event::WorktreeUpdatedEntries(
@@ -7988,7 +9151,7 @@ impl Project {
         let mut language_server_ids = self
             .language_server_ids
             .iter()
-            .filter_map(|((server_worktree_id, _), server_id)| {
+            .filter_map(|((server_worktree_id, _, _), server_id)| {
                 (*server_worktree_id == worktree_id).then_some(*server_id)
             })
             .collect::<Vec<_>>();
@@ -8181,9 +9344,15 @@ impl Project {
         let worktree_id = worktree.entity_id();
         let remote_worktree_id = worktree.read(cx).id();
 
+        let is_trusted = self.is_worktree_trusted(remote_worktree_id, cx);
+        let mut skipped_configuration = false;
         let mut settings_contents = Vec::new();
         for (path, _, change) in changes.iter() {
             let removed = change == &PathChange::Removed;
+            if !is_trusted && worktree_trust::is_configuration_file(path) {
+                skipped_configuration |= !removed;
+                continue;
+            }
             let abs_path = match worktree.read(cx).absolutize(path) {
                 Ok(abs_path) => abs_path,
                 Err(e) => {
@@ -8258,6 +9427,9 @@ impl Project {
             }
         }
 
+        if skipped_configuration {
+            self.check_worktree_trust(remote_worktree_id, cx);
+        }
         if settings_contents.is_empty() {
             return;
         }
@@ -8495,6 +9667,9 @@ impl Project {
         cx: &AppContext,
     ) -> Task<Result<Blame>> {
         if self.is_local() {
+            if let Some(blame) = self.blame_buffer_with_vcs_provider(buffer, version.clone(), cx) {
+                return blame;
+            }
             let blame_params = maybe!({
                 let buffer = buffer.read(cx);
                 let buffer_project_path = buffer
@@ -8639,10 +9814,12 @@ impl Project {
                     })?
                     .await
                     .into_iter()
-                    .filter_map(|hover| remove_empty_hover_blocks(hover?));
+                    .filter_map(|(server_id, hover)| {
+                        Some((server_id, remove_empty_hover_blocks(hover?)?))
+                    });
                 project.update(&mut cx, |project, cx| proto::MultiLspQueryResponse {
                     responses: all_hovers
-                        .map(|hover| proto::LspResponse {
+                        .map(|(server_id, hover)| proto::LspResponse {
                             response: Some(proto::lsp_response::Response::GetHoverResponse(
                                 GetHover::response_to_proto(
                                     Some(hover),
@@ -8652,6 +9829,7 @@ impl Project {
                                     cx,
                                 ),
                             )),
+                            server_id: server_id.0 as u64,
                         })
                         .collect(),
                 })
@@ -8680,7 +9858,7 @@ impl Project {
 
                 project.update(&mut cx, |project, cx| proto::MultiLspQueryResponse {
                     responses: all_actions
-                        .map(|code_actions| proto::LspResponse {
+                        .map(|(server_id, code_actions)| proto::LspResponse {
                             response: Some(proto::lsp_response::Response::GetCodeActionsResponse(
                                 GetCodeActions::response_to_proto(
                                     code_actions,
@@ -8690,6 +9868,49 @@ impl Project {
                                     cx,
                                 ),
                             )),
+                            server_id: server_id.0 as u64,
+                        })
+                        .collect(),
+                })
+            }
+            Some(proto::multi_lsp_query::Request::GetSignatureHelp(get_signature_help)) => {
+                let get_signature_help = GetSignatureHelp::from_proto(
+                    get_signature_help,
+                    project.clone(),
+                    buffer.clone(),
+                    cx.clone(),
+                )
+                .await?;
+
+                let all_signature_helps = project
+                    .update(&mut cx, |project, cx| {
+                        project.request_multiple_lsp_locally(
+                            &buffer,
+                            Some(get_signature_help.position),
+                            GetSignatureHelp::supports_signature_help,
+                            get_signature_help,
+                            cx,
+                        )
+                    })?
+                    .await
+                    .into_iter()
+                    .filter_map(|(server_id, signature_help)| Some((server_id, signature_help?)));
+
+                project.update(&mut cx, |project, cx| proto::MultiLspQueryResponse {
+                    responses: all_signature_helps
+                        .map(|(server_id, signature_help)| proto::LspResponse {
+                            response: Some(
+                                proto::lsp_response::Response::GetSignatureHelpResponse(
+                                    GetSignatureHelp::response_to_proto(
+                                        Some(signature_help),
+                                        project,
+                                        sender_id,
+                                        &buffer_version,
+                                        cx,
+                                    ),
+                                ),
+                            ),
+                            server_id: server_id.0 as u64,
                         })
                         .collect(),
                 })
@@ -8876,7 +10097,7 @@ impl Project {
             this.worktree_for_id(worktree_id, cx)
                 .ok_or_else(|| anyhow!("worktree not found"))
         })??;
-        Worktree::handle_create_entry(worktree, envelope.payload, cx).await
+        Self::create_entry_for_guest(this, worktree, envelope.payload, cx).await
     }
 
     async fn handle_rename_project_entry(
@@ -8889,7 +10110,7 @@ impl Project {
             this.worktree_for_entry(entry_id, cx)
                 .ok_or_else(|| anyhow!("worktree not found"))
         })??;
-        Worktree::handle_rename_entry(worktree, envelope.payload, cx).await
+        Self::rename_entry_for_guest(this, worktree, envelope.payload, cx).await
     }
 
     async fn handle_copy_project_entry(
@@ -8916,7 +10137,7 @@ impl Project {
                 .ok_or_else(|| anyhow!("worktree not found"))
         })??;
         this.update(&mut cx, |_, cx| cx.emit(Event::DeletedEntry(entry_id)))?;
-        Worktree::handle_delete_entry(worktree, envelope.payload, cx).await
+        Self::delete_entry_for_guest(this, worktree, envelope.payload, cx).await
     }
 
     async fn handle_expand_project_entry(
@@ -8991,6 +10212,7 @@ impl Project {
             this.language_server_statuses.insert(
                 LanguageServerId(server.id as usize),
                 LanguageServerStatus {
+                    signature_help_triggers: SignatureHelpTriggers::from_proto(&server),
                     name: server.name,
                     pending_work: Default::default(),
                     has_pending_diagnostic_updates: false,
@@ -9002,6 +10224,40 @@ impl Project {
         Ok(())
     }
 
+    async fn handle_cancel_language_server_work(
+        this: Model<Self>,
+        envelope: TypedEnvelope<proto::CancelLanguageServerWork>,
+        mut cx: AsyncAppContext,
+    ) -> Result<proto::Ack> {
+        this.update(&mut cx, |this, cx| {
+            match envelope
+                .payload
+                .work
+                .ok_or_else(|| anyhow!("invalid work"))?
+            {
+                proto::cancel_language_server_work::Work::Buffers(buffers) => {
+                    let buffers = buffers
+                        .buffer_ids
+                        .into_iter()
+                        .filter_map(|buffer_id| {
+                            let buffer_id = BufferId::new(buffer_id).log_err()?;
+                            this.opened_buffers.get(&buffer_id)?.upgrade()
+                        })
+                        .collect::<Vec<_>>();
+                    this.cancel_language_server_work_for_buffers(buffers, cx);
+                }
+                proto::cancel_language_server_work::Work::LanguageServerWork(work) => {
+                    this.cancel_language_server_work(
+                        LanguageServerId(work.language_server_id as usize),
+                        work.token,
+                        cx,
+                    );
+                }
+            }
+            Ok(proto::Ack {})
+        })?
+    }
+
     async fn handle_update_language_server(
         this: Model<Self>,
         envelope: TypedEnvelope<proto::UpdateLanguageServer>,
@@ -9022,7 +10278,7 @@ impl Project {
                         LanguageServerProgress {
                             title: payload.title,
                             is_disk_based_diagnostics_progress: false,
-                            is_cancellable: false,
+                            is_cancellable: payload.is_cancellable.unwrap_or(false),
                             message: payload.message,
                             percentage: payload.percentage.map(|p| p as usize),
                             last_update_at: cx.background_executor().now(),
@@ -9038,7 +10294,7 @@ impl Project {
                         LanguageServerProgress {
                             title: None,
                             is_disk_based_diagnostics_progress: false,
-                            is_cancellable: false,
+                            is_cancellable: payload.is_cancellable.unwrap_or(false),
                             message: payload.message,
                             percentage: payload.percentage.map(|p| p as usize),
                             last_update_at: cx.background_executor().now(),
@@ -9258,7 +10514,18 @@ impl Project {
                 buffer.wait_for_version(deserialize_version(&envelope.payload.version))
             })?
             .await?;
-        let buffer_id = buffer.update(&mut cx, |buffer, _| buffer.remote_id())?;
+        let buffer_id = buffer.update(&mut cx, |buffer, _| {
+            // The guest saves the buffer in the encoding it chose for it.
+            if let Some(encoding) = envelope
+                .payload
+                .encoding
+                .as_deref()
+                .and_then(Encoding::from_name)
+            {
+                buffer.set_encoding(encoding);
+            }
+            buffer.remote_id()
+        })?;
 
         if let Some(new_path) = envelope.payload.new_path {
             let new_path = ProjectPath::from_proto(new_path);
@@ -9276,6 +10543,7 @@ impl Project {
             buffer_id: buffer_id.into(),
             version: serialize_version(buffer.saved_version()),
             mtime: buffer.saved_mtime().map(|time| time.into()),
+            encoding: Some(buffer.encoding().name().to_string()),
         })
     }
 
@@ -9369,6 +10637,7 @@ impl Project {
                             line_ending: language::proto::serialize_line_ending(
                                 buffer.line_ending(),
                             ) as i32,
+                            encoding: Some(buffer.encoding().name().to_string()),
                         })
                         .log_err();
 
@@ -9784,20 +11053,7 @@ impl Project {
                     }
                 });
                 let kind = Some(proto::TaskSourceKind { kind });
-                let template = Some(proto::TaskTemplate {
-                    label: template.label,
-                    command: template.command,
-                    args: template.args,
-                    env: template.env.into_iter().collect(),
-                    cwd: template.cwd,
-                    use_new_terminal: template.use_new_terminal,
-                    allow_concurrent_runs: template.allow_concurrent_runs,
-                    reveal: match template.reveal {
-                        RevealStrategy::Always => proto::RevealStrategy::Always as i32,
-                        RevealStrategy::Never => proto::RevealStrategy::Never as i32,
-                    },
-                    tags: template.tags,
-                });
+                let template = Some(serialize_task_template(template));
                 proto::TemplatePair { kind, template }
             })
             .collect();
@@ -9937,7 +11193,7 @@ impl Project {
             .update(&mut cx, |this, cx| {
                 this.request_lsp(
                     buffer_handle.clone(),
-                    LanguageServerToQuery::Primary,
+                    request.language_server_to_query(),
                     request,
                     cx,
                 )
@@ -9954,19 +11210,39 @@ impl Project {
         })?
     }
 
-    async fn handle_get_project_symbols(
+    async fn handle_execute_lsp_command(
         this: Model<Self>,
-        envelope: TypedEnvelope<proto::GetProjectSymbols>,
+        envelope: TypedEnvelope<proto::ExecuteLspCommand>,
         mut cx: AsyncAppContext,
-    ) -> Result<proto::GetProjectSymbolsResponse> {
-        let symbols = this
+    ) -> Result<proto::ExecuteLspCommandResponse> {
+        let sender_id = envelope.original_sender_id()?;
+        let buffer_id = ExecuteLspCommand::buffer_id_from_proto(&envelope.payload)?;
+        let buffer = this.update(&mut cx, |this, _| {
+            this.opened_buffers
+                .get(&buffer_id)
+                .and_then(|buffer| buffer.upgrade())
+                .ok_or_else(|| anyhow!("unknown buffer id {}", buffer_id))
+        })??;
+        let command = ExecuteLspCommand::from_proto(
+            envelope.payload,
+            this.clone(),
+            buffer.clone(),
+            cx.clone(),
+        )
+        .await?;
+        let response = this
             .update(&mut cx, |this, cx| {
-                this.symbols(&envelope.payload.query, cx)
+                this.execute_lsp_command(&buffer, command, cx)
             })?
             .await?;
-
-        Ok(proto::GetProjectSymbolsResponse {
-            symbols: symbols.iter().map(serialize_symbol).collect(),
+        this.update(&mut cx, |this, cx| {
+            ExecuteLspCommand::response_to_proto(
+                response,
+                this,
+                sender_id,
+                &buffer.read(cx).version(),
+                cx,
+            )
         })
     }
 
@@ -9976,8 +11252,11 @@ impl Project {
         mut cx: AsyncAppContext,
     ) -> Result<proto::SearchProjectResponse> {
         let peer_id = envelope.original_sender_id()?;
+        let limits = SearchLimits::from_proto(&envelope.payload);
         let query = SearchQuery::from_proto(envelope.payload)?;
-        let mut result = this.update(&mut cx, |this, cx| this.search(query, cx))?;
+        let mut result = this.update(&mut cx, |this, cx| {
+            this.search_with_limits(query, limits, cx)
+        })?;
 
         cx.spawn(move |mut cx| async move {
             let mut locations = Vec::new();
@@ -10044,6 +11323,7 @@ impl Project {
                             runs: Default::default(),
                             filter_range: Default::default(),
                         },
+                        unresolved_symbol: symbol.unresolved_symbol,
                     },
                     cx,
                 )
@@ -10066,6 +11346,47 @@ impl Project {
         })?
     }
 
+    async fn handle_resolve_workspace_symbol(
+        this: Model<Self>,
+        envelope: TypedEnvelope<proto::ResolveWorkspaceSymbol>,
+        mut cx: AsyncAppContext,
+    ) -> Result<proto::ResolveWorkspaceSymbolResponse> {
+        let symbol = envelope
+            .payload
+            .symbol
+            .ok_or_else(|| anyhow!("invalid symbol"))?;
+        let symbol = Self::deserialize_symbol(symbol)?;
+        let symbol = this.update(&mut cx, |this, _| {
+            let signature = this.symbol_signature(&symbol.path);
+            if signature == symbol.signature {
+                Ok(symbol)
+            } else {
+                Err(anyhow!("invalid symbol signature"))
+            }
+        })??;
+        let resolved = this
+            .update(&mut cx, |this, cx| {
+                this.resolve_symbol(
+                    &Symbol {
+                        language_server_name: symbol.language_server_name,
+                        source_worktree_id: symbol.source_worktree_id,
+                        path: symbol.path,
+                        label: CodeLabel::plain(symbol.name.clone(), None),
+                        name: symbol.name,
+                        kind: symbol.kind,
+                        range: symbol.range,
+                        signature: symbol.signature,
+                        unresolved_symbol: symbol.unresolved_symbol,
+                    },
+                    cx,
+                )
+            })?
+            .await?;
+        Ok(proto::ResolveWorkspaceSymbolResponse {
+            symbol: Some(serialize_symbol(&resolved)?),
+        })
+    }
+
     fn symbol_signature(&self, project_path: &ProjectPath) -> [u8; 32] {
         let mut hasher = Sha256::new();
         hasher.update(project_path.worktree_id.to_proto().to_be_bytes());
@@ -10444,6 +11765,10 @@ impl Project {
                 .signature
                 .try_into()
                 .map_err(|_| anyhow!("invalid signature"))?,
+            unresolved_symbol: serialized_symbol
+                .unresolved_lsp_symbol
+                .map(|lsp_symbol| serde_json::from_slice(&lsp_symbol).map(Arc::new))
+                .transpose()?,
         })
     }
 
@@ -10510,6 +11835,11 @@ impl Project {
         let version = deserialize_version(&envelope.payload.version);
         let buffer_id = BufferId::new(envelope.payload.buffer_id)?;
         let mtime = envelope.payload.mtime.map(|time| time.into());
+        let encoding = envelope
+            .payload
+            .encoding
+            .as_deref()
+            .and_then(Encoding::from_name);
 
         this.update(&mut cx, |this, cx| {
             let buffer = this
@@ -10519,6 +11849,9 @@ impl Project {
                 .or_else(|| this.incomplete_remote_buffers.get(&buffer_id).cloned());
             if let Some(buffer) = buffer {
                 buffer.update(cx, |buffer, cx| {
+                    if let Some(encoding) = encoding {
+                        buffer.set_encoding(encoding);
+                    }
                     buffer.did_save(version, mtime, cx);
                 });
             }
@@ -10538,6 +11871,7 @@ impl Project {
                 .ok_or_else(|| anyhow!("missing line ending"))?,
         );
         let mtime = payload.mtime.map(|time| time.into());
+        let encoding = payload.encoding.as_deref().and_then(Encoding::from_name);
         let buffer_id = BufferId::new(payload.buffer_id)?;
         this.update(&mut cx, |this, cx| {
             let buffer = this
@@ -10547,6 +11881,9 @@ impl Project {
                 .or_else(|| this.incomplete_remote_buffers.get(&buffer_id).cloned());
             if let Some(buffer) = buffer {
                 buffer.update(cx, |buffer, cx| {
+                    if let Some(encoding) = encoding {
+                        buffer.set_encoding(encoding);
+                    }
                     buffer.did_reload(version, line_ending, mtime, cx);
                 });
             }
@@ -10697,7 +12034,7 @@ impl Project {
     ) -> impl '_ + Iterator<Item = (LanguageServerId, LanguageServerName, WorktreeId)> {
         self.language_server_ids
             .iter()
-            .map(|((worktree_id, server_name), server_id)| {
+            .map(|((worktree_id, server_name, _), server_id)| {
                 (*server_id, server_name.clone(), *worktree_id)
             })
     }
@@ -10725,6 +12062,12 @@ impl Project {
         }
     }
 
+    /// Returns the latency and payload statistics of the requests sent to the local language server.
+    pub fn language_server_request_stats(&self, id: LanguageServerId) -> Option<RequestStats> {
+        self.language_server_for_id(id)
+            .map(|server| server.request_stats())
+    }
+
     pub fn language_server_for_id(&self, id: LanguageServerId) -> Option<Arc<LanguageServer>> {
         if let Some(LanguageServerState::Running { server, .. }) = self.language_servers.get(&id) {
             Some(server.clone())
@@ -10750,15 +12093,6 @@ impl Project {
             })
     }
 
-    fn primary_language_server_for_buffer(
-        &self,
-        buffer: &Buffer,
-        cx: &AppContext,
-    ) -> Option<(&Arc<CachedLspAdapter>, &Arc<LanguageServer>)> {
-        self.language_servers_for_buffer(buffer, cx)
-            .find(|s| s.0.is_primary)
-    }
-
     pub fn language_server_for_buffer(
         &self,
         buffer: &Buffer,
@@ -10780,7 +12114,8 @@ impl Project {
                 .lsp_adapters(&language)
                 .iter()
                 .flat_map(|adapter| {
-                    let key = (worktree_id, adapter.name.clone());
+                    let root = self.language_server_root(&file.worktree, adapter, &file.path, cx);
+                    let key = (worktree_id, adapter.name.clone(), root);
                     self.language_server_ids.get(&key).copied()
                 })
                 .collect()
@@ -10790,15 +12125,17 @@ impl Project {
     }
 
     pub fn task_context_for_location(
-        &self,
+        &mut self,
         captured_variables: TaskVariables,
         location: Location,
         cx: &mut ModelContext<'_, Project>,
     ) -> Task<Option<TaskContext>> {
         if self.is_local() {
             let cwd = self.task_cwd(cx).log_err().flatten();
+            let lsp_task_variables = self.lsp_task_variables(&location, cx);
 
             cx.spawn(|project, cx| async move {
+                let lsp_task_variables = lsp_task_variables.await;
                 let mut task_variables = cx
                     .update(|cx| {
                         combine_task_variables(
@@ -10811,6 +12148,8 @@ impl Project {
                     })
                     .ok()
                     .flatten()?;
+                // The language servers know the symbols better than the outline of the buffer does.
+                task_variables.extend(lsp_task_variables);
                 // Remove all custom entries starting with _, as they're not intended for use by the end user.
                 task_variables.sweep();
                 Some(TaskContext {
@@ -10923,24 +12262,7 @@ impl Project {
                         }
                     };
 
-                    let proto_template = template_pair.template?;
-                    let reveal = match proto::RevealStrategy::from_i32(proto_template.reveal)
-                        .unwrap_or(proto::RevealStrategy::Always)
-                    {
-                        proto::RevealStrategy::Always => RevealStrategy::Always,
-                        proto::RevealStrategy::Never => RevealStrategy::Never,
-                    };
-                    let task_template = TaskTemplate {
-                        label: proto_template.label,
-                        command: proto_template.command,
-                        args: proto_template.args,
-                        env: proto_template.env.into_iter().collect(),
-                        cwd: proto_template.cwd,
-                        use_new_terminal: proto_template.use_new_terminal,
-                        allow_concurrent_runs: proto_template.allow_concurrent_runs,
-                        reveal,
-                        tags: proto_template.tags,
-                    };
+                    let task_template = deserialize_task_template(template_pair.template?);
                     Some((task_source_kind, task_template))
                 })
                 .collect())
@@ -11007,6 +12329,33 @@ fn combine_task_variables(
     Ok(captured_variables)
 }
 
+fn lsp_workspace_symbols(response: lsp::WorkspaceSymbolResponse) -> Vec<LspWorkspaceSymbol> {
+    match response {
+        lsp::WorkspaceSymbolResponse::Flat(flat_responses) => flat_responses
+            .into_iter()
+            .map(|lsp_symbol| (lsp_symbol.name, lsp_symbol.kind, lsp_symbol.location, None))
+            .collect(),
+        lsp::WorkspaceSymbolResponse::Nested(nested_responses) => nested_responses
+            .into_iter()
+            .map(|lsp_symbol| match &lsp_symbol.location {
+                OneOf::Left(location) => (
+                    lsp_symbol.name.clone(),
+                    lsp_symbol.kind,
+                    location.clone(),
+                    None,
+                ),
+                // The range is requested with `workspaceSymbol/resolve`, once the symbol is picked.
+                OneOf::Right(workspace_location) => (
+                    lsp_symbol.name.clone(),
+                    lsp_symbol.kind,
+                    lsp::Location::new(workspace_location.uri.clone(), lsp::Range::default()),
+                    Some(Arc::new(lsp_symbol)),
+                ),
+            })
+            .collect(),
+    }
+}
+
 async fn populate_labels_for_symbols(
     symbols: Vec<CoreSymbol>,
     language_registry: &Arc<LanguageRegistry>,
@@ -11076,6 +12425,7 @@ async fn populate_labels_for_symbols(
                 kind: symbol.kind,
                 range: symbol.range,
                 signature: symbol.signature,
+                unresolved_symbol: symbol.unresolved_symbol,
             });
         }
     }
@@ -11296,6 +12646,27 @@ async fn search_ignored_entry(
     }
 }
 
+/// The operations of a workspace edit, in order, with its `changes` as text document edits.
+fn workspace_edit_operations(edit: lsp::WorkspaceEdit) -> Vec<lsp::DocumentChangeOperation> {
+    let mut operations = Vec::new();
+    if let Some(document_changes) = edit.document_changes {
+        match document_changes {
+            lsp::DocumentChanges::Edits(edits) => {
+                operations.extend(edits.into_iter().map(lsp::DocumentChangeOperation::Edit))
+            }
+            lsp::DocumentChanges::Operations(ops) => operations = ops,
+        }
+    } else if let Some(changes) = edit.changes {
+        operations.extend(changes.into_iter().map(|(uri, edits)| {
+            lsp::DocumentChangeOperation::Edit(lsp::TextDocumentEdit {
+                text_document: lsp::OptionalVersionedTextDocumentIdentifier { uri, version: None },
+                edits: edits.into_iter().map(Edit::Plain).collect(),
+            })
+        }));
+    }
+    operations
+}
+
 fn glob_literal_prefix(glob: &str) -> &str {
     let mut literal_end = 0;
     for (i, part) in glob.split(path::MAIN_SEPARATOR).enumerate() {
@@ -11449,6 +12820,7 @@ pub struct ProjectLspAdapterDelegate {
     worktree: worktree::Snapshot,
     fs: Arc<dyn Fs>,
     http_client: Arc<dyn HttpClient>,
+    artifact_download_settings: ArtifactDownloadSettings,
     language_registry: Arc<LanguageRegistry>,
     shell_env: Mutex<Option<HashMap<String, String>>>,
 }
@@ -11459,11 +12831,21 @@ impl ProjectLspAdapterDelegate {
         worktree: &Model<Worktree>,
         cx: &ModelContext<Project>,
     ) -> Arc<Self> {
+        let download_settings = &ProjectSettings::get_global(cx).lsp_downloads;
+        // Language server binaries can be downloaded through a proxy of their own.
+        let http_client: Arc<dyn HttpClient> = match &download_settings.proxy {
+            Some(proxy) => Arc::new(HttpClientWithUrl::new(
+                project.client.http_client().base_url(),
+                Some(proxy.clone()),
+            )),
+            None => project.client.http_client(),
+        };
         Arc::new(Self {
             project: cx.weak_model(),
             worktree: worktree.read(cx).snapshot(),
             fs: project.fs.clone(),
-            http_client: project.client.http_client(),
+            http_client,
+            artifact_download_settings: download_settings.artifact_download_settings(),
             language_registry: project.languages.clone(),
             shell_env: Default::default(),
         })
@@ -11494,6 +12876,10 @@ impl LspAdapterDelegate for ProjectLspAdapterDelegate {
         self.http_client.clone()
     }
 
+    fn artifact_download_settings(&self) -> ArtifactDownloadSettings {
+        self.artifact_download_settings.clone()
+    }
+
     fn worktree_id(&self) -> u64 {
         self.worktree.id().to_proto()
     }
@@ -11536,6 +12922,26 @@ impl LspAdapterDelegate for ProjectLspAdapterDelegate {
             .update_lsp_status(server_name, status);
     }
 
+    fn execute_language_server_command(
+        &self,
+        server_name: LanguageServerName,
+        command: String,
+        arguments: Vec<serde_json::Value>,
+        cx: &mut AppContext,
+    ) -> Task<Result<Option<serde_json::Value>>> {
+        let worktree_id = self.worktree.id();
+        let command = ExecuteLspCommand {
+            command,
+            arguments,
+            server_id: None,
+        };
+        self.project
+            .update(cx, |project, cx| {
+                project.execute_lsp_command_on_server(worktree_id, &server_name, command, cx)
+            })
+            .unwrap_or_else(|error| Task::ready(Err(error)))
+    }
+
     async fn read_text_file(&self, path: PathBuf) -> Result<String> {
         if self.worktree.entry_for_path(&path).is_none() {
             return Err(anyhow!("no such path {path:?}"));
@@ -11546,8 +12952,14 @@ impl LspAdapterDelegate for ProjectLspAdapterDelegate {
     }
 }
 
-fn serialize_symbol(symbol: &Symbol) -> proto::Symbol {
-    proto::Symbol {
+fn serialize_symbol(symbol: &Symbol) -> Result<proto::Symbol> {
+    let unresolved_lsp_symbol = symbol
+        .unresolved_symbol
+        .as_ref()
+        .map(serde_json::to_vec)
+        .transpose()
+        .with_context(|| format!("serializing the unresolved symbol {}", symbol.name))?;
+    Ok(proto::Symbol {
         language_server_name: symbol.language_server_name.0.to_string(),
         source_worktree_id: symbol.source_worktree_id.to_proto(),
         worktree_id: symbol.path.worktree_id.to_proto(),
@@ -11563,7 +12975,8 @@ fn serialize_symbol(symbol: &Symbol) -> proto::Symbol {
             column: symbol.range.end.0.column,
         }),
         signature: symbol.signature.to_vec(),
-    }
+        unresolved_lsp_symbol,
+    })
 }
 
 fn relativize_path(base: &Path, path: &Path) -> PathBuf {
@@ -11871,6 +13284,53 @@ impl std::fmt::Display for NoRepositoryError {
 
 impl std::error::Error for NoRepositoryError {}
 
+pub(crate) fn serialize_task_template(template: TaskTemplate) -> proto::TaskTemplate {
+    proto::TaskTemplate {
+        label: template.label,
+        command: template.command,
+        args: template.args,
+        env: template.env.into_iter().collect(),
+        cwd: template.cwd,
+        use_new_terminal: template.use_new_terminal,
+        allow_concurrent_runs: template.allow_concurrent_runs,
+        reveal: match template.reveal {
+            RevealStrategy::Always => proto::RevealStrategy::Always as i32,
+            RevealStrategy::Never => proto::RevealStrategy::Never as i32,
+        },
+        tags: template.tags,
+        problem_matchers: template
+            .problem_matchers
+            .iter()
+            .filter_map(|matcher| serde_json::to_string(matcher).log_err())
+            .collect(),
+    }
+}
+
+pub(crate) fn deserialize_task_template(template: proto::TaskTemplate) -> TaskTemplate {
+    let reveal = match proto::RevealStrategy::from_i32(template.reveal)
+        .unwrap_or(proto::RevealStrategy::Always)
+    {
+        proto::RevealStrategy::Always => RevealStrategy::Always,
+        proto::RevealStrategy::Never => RevealStrategy::Never,
+    };
+    TaskTemplate {
+        label: template.label,
+        command: template.command,
+        args: template.args,
+        env: template.env.into_iter().collect(),
+        cwd: template.cwd,
+        use_new_terminal: template.use_new_terminal,
+        allow_concurrent_runs: template.allow_concurrent_runs,
+        reveal,
+        tags: template.tags,
+        problem_matchers: template
+            .problem_matchers
+            .iter()
+            .filter_map(|matcher| serde_json::from_str(matcher).log_err())
+            .collect(),
+    }
+}
+
 fn serialize_location(location: &Location, cx: &AppContext) -> proto::Location {
     proto::Location {
         buffer_id: location.buffer.read(cx).remote_id().into(),
@@ -12033,6 +13493,27 @@ fn sort_search_matches(search_matches: &mut Vec<SearchMatchCandidate>, cx: &AppC
     });
 }
 
+/// Compares the buffers of search results in the order their matches are shown: the buffers without a file
+/// first, in the order they were created, and then the others by worktree and path.
+pub fn compare_search_result_buffers(buffer_a: &Buffer, buffer_b: &Buffer) -> Ordering {
+    match (buffer_a.file(), buffer_b.file()) {
+        (None, None) => buffer_a.remote_id().cmp(&buffer_b.remote_id()),
+        (None, Some(_)) => Ordering::Less,
+        (Some(_), None) => Ordering::Greater,
+        (Some(file_a), Some(file_b)) => {
+            file_a
+                .worktree_id()
+                .cmp(&file_b.worktree_id())
+                .then_with(|| {
+                    compare_paths(
+                        (file_a.path().as_ref(), true),
+                        (file_b.path().as_ref(), true),
+                    )
+                })
+        }
+    }
+}
+
 fn compare_paths(
     (path_a, a_is_file): (&Path, bool),
     (path_b, b_is_file): (&Path, bool),
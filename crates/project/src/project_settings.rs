@@ -1,9 +1,10 @@
 use collections::HashMap;
 use gpui::AppContext;
+use language::{ArtifactDownloadSettings, LanguageServerRoots};
 use schemars::JsonSchema;
 use serde::{Deserialize, Serialize};
 use settings::{Settings, SettingsSources};
-use std::{sync::Arc, time::Duration};
+use std::{path::PathBuf, sync::Arc, time::Duration};
 
 #[derive(Clone, Default, Serialize, Deserialize, JsonSchema)]
 pub struct ProjectSettings {
@@ -20,6 +21,259 @@ pub struct ProjectSettings {
     /// Configuration for Git-related features
     #[serde(default)]
     pub git: GitSettings,
+
+    /// Paths of the LSIF (JSON) or SCIP (`.scip`) index files of the worktree, relative to its root.
+    ///
+    /// Go to definition and find references fall back to them when the language servers find nothing.
+    /// Default: []
+    #[serde(default)]
+    pub code_indexes: Vec<PathBuf>,
+
+    /// How the results of several language servers of a buffer are combined.
+    #[serde(default)]
+    pub lsp_merging: LspMergingSettings,
+
+    /// Deadlines of the requests to language servers, after which the requests are cancelled
+    /// and a notification is shown.
+    #[serde(default)]
+    pub lsp_request_timeouts: LspRequestTimeoutSettings,
+
+    /// The kernels that run the code cells of buffers: the `# %%` cells of scripts and the fenced code blocks of Markdown.
+    #[serde(default)]
+    pub code_cells: CodeCellSettings,
+
+    /// The language servers pinned for files, by their paths relative to the worktree root.
+    ///
+    /// When several language servers are eligible for a file, the pinned one handles the requests
+    /// that go to a single server, like go to definition.
+    /// Default: {}
+    #[serde(default)]
+    pub pinned_language_servers: HashMap<PathBuf, String>,
+
+    /// Whether worktrees have to be trusted before their language servers, tasks and local settings are used.
+    ///
+    /// Only read from the user settings.
+    #[serde(default)]
+    pub worktree_trust: WorktreeTrustSettings,
+
+    /// Whether the definitions and hovers found by language servers are cached on disk, to be
+    /// shown while the language servers are still starting or indexing.
+    ///
+    /// Only read from the user settings.
+    #[serde(default)]
+    pub lsp_response_cache: LspResponseCacheSettings,
+
+    /// How the ports of remote projects are forwarded to this machine.
+    #[serde(default)]
+    pub port_forwarding: PortForwardingSettings,
+
+    /// How the binaries of language servers are downloaded.
+    #[serde(default)]
+    pub lsp_downloads: LspDownloadSettings,
+}
+
+#[derive(Clone, Debug, Default, Serialize, Deserialize, JsonSchema)]
+pub struct LspDownloadSettings {
+    /// The proxy that language server binaries are downloaded through, instead of the `proxy` setting.
+    ///
+    /// Default: null
+    #[serde(default)]
+    pub proxy: Option<String>,
+    /// Mirrors that language server binaries are downloaded from, keyed by the URL prefixes they replace,
+    /// e.g. `{ "https://github.com/": "https://mirror.example.com/github/" }`.
+    ///
+    /// Default: {}
+    #[serde(default)]
+    pub mirrors: HashMap<String, String>,
+    /// Whether language server binaries without a published checksum are refused.
+    ///
+    /// Default: false
+    #[serde(default)]
+    pub require_checksums: bool,
+}
+
+impl LspDownloadSettings {
+    pub fn artifact_download_settings(&self) -> ArtifactDownloadSettings {
+        ArtifactDownloadSettings {
+            mirrors: self
+                .mirrors
+                .iter()
+                .map(|(prefix, mirror)| (prefix.clone(), mirror.clone()))
+                .collect(),
+            require_checksums: self.require_checksums,
+        }
+    }
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize, JsonSchema)]
+pub struct PortForwardingSettings {
+    /// Whether the ports that the terminals of remote projects print that a server listens on, like
+    /// `Listening on port 3000`, are forwarded automatically.
+    ///
+    /// Default: true
+    #[serde(default = "PortForwardingSettings::default_auto_forward")]
+    pub auto_forward: bool,
+}
+
+impl Default for PortForwardingSettings {
+    fn default() -> Self {
+        Self {
+            auto_forward: Self::default_auto_forward(),
+        }
+    }
+}
+
+impl PortForwardingSettings {
+    fn default_auto_forward() -> bool {
+        true
+    }
+}
+
+#[derive(Clone, Debug, Default, Serialize, Deserialize, JsonSchema)]
+pub struct LspResponseCacheSettings {
+    /// Whether the definitions and hovers found by language servers are cached.
+    ///
+    /// Default: false
+    #[serde(default)]
+    pub enabled: bool,
+}
+
+#[derive(Clone, Debug, Default, Serialize, Deserialize, JsonSchema)]
+pub struct WorktreeTrustSettings {
+    /// Whether untrusted worktrees are kept from starting language servers, running tasks and
+    /// applying their local settings.
+    ///
+    /// Default: false
+    #[serde(default)]
+    pub enabled: bool,
+    /// The trusted directories, along with all the worktrees inside them.
+    ///
+    /// Default: []
+    #[serde(default)]
+    pub trusted_paths: Vec<PathBuf>,
+}
+
+#[derive(Clone, Debug, Default, Serialize, Deserialize, JsonSchema)]
+pub struct CodeCellSettings {
+    /// REPLs by the name of the language they run, e.g. `Python`.
+    ///
+    /// Default: { "Python": { "command": "python3", ... } }
+    #[serde(default)]
+    pub kernels: HashMap<String, KernelSettings>,
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize, JsonSchema)]
+pub struct KernelSettings {
+    /// The REPL program, started in the root of the worktree, which reads the code of the cells from stdin
+    /// and writes their output to stdout. Images are displayed with lines of
+    /// `__zed_display__ <MIME type> <base64 data>`.
+    pub command: String,
+    #[serde(default)]
+    pub args: Vec<String>,
+    /// The line written to the REPL to run a cell. `{code}` is replaced with the code of the cell
+    /// and `{marker}` with a line to print when the cell is done, both as JSON string literals.
+    pub execute_template: String,
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize, JsonSchema)]
+pub struct LspRequestTimeoutSettings {
+    /// The timeout of requests without a method-specific one, in milliseconds.
+    ///
+    /// Default: 120000
+    #[serde(default = "LspRequestTimeoutSettings::default_timeout")]
+    pub default: u64,
+    /// Timeouts by LSP method, in milliseconds, e.g. `{ "textDocument/references": 10000 }`.
+    ///
+    /// Default: {}
+    #[serde(default)]
+    pub methods: HashMap<String, u64>,
+}
+
+impl Default for LspRequestTimeoutSettings {
+    fn default() -> Self {
+        Self {
+            default: Self::default_timeout(),
+            methods: HashMap::default(),
+        }
+    }
+}
+
+impl LspRequestTimeoutSettings {
+    fn default_timeout() -> u64 {
+        lsp::LSP_REQUEST_TIMEOUT.as_millis() as u64
+    }
+
+    pub fn request_timeouts(&self) -> lsp::RequestTimeouts {
+        lsp::RequestTimeouts {
+            default: Duration::from_millis(self.default),
+            methods: self
+                .methods
+                .iter()
+                .map(|(method, timeout)| (method.clone(), Duration::from_millis(*timeout)))
+                .collect(),
+        }
+    }
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize, JsonSchema)]
+pub struct LspMergingSettings {
+    /// How the hovers of the language servers are combined.
+    ///
+    /// Default: { "strategy": "concatenate", "server_ranking": [] }
+    #[serde(default)]
+    pub hover: LspMergeSettings,
+    /// How the completions of the language servers are combined.
+    ///
+    /// Default: { "strategy": "deduplicate", "server_ranking": [] }
+    #[serde(default = "LspMergeSettings::deduplicate")]
+    pub completions: LspMergeSettings,
+    /// How the signatures of the signature help of the language servers are combined.
+    ///
+    /// Default: { "strategy": "deduplicate", "server_ranking": [] }
+    #[serde(default = "LspMergeSettings::deduplicate")]
+    pub signature_help: LspMergeSettings,
+}
+
+impl Default for LspMergingSettings {
+    fn default() -> Self {
+        Self {
+            hover: LspMergeSettings::default(),
+            completions: LspMergeSettings::deduplicate(),
+            signature_help: LspMergeSettings::deduplicate(),
+        }
+    }
+}
+
+#[derive(Clone, Debug, Default, Serialize, Deserialize, JsonSchema)]
+pub struct LspMergeSettings {
+    /// Which of the results of the language servers are kept.
+    #[serde(default)]
+    pub strategy: LspMergeStrategy,
+    /// Names of the language servers whose results come first, in this order.
+    /// The results of the other servers follow.
+    #[serde(default)]
+    pub server_ranking: Vec<String>,
+}
+
+impl LspMergeSettings {
+    fn deduplicate() -> Self {
+        Self {
+            strategy: LspMergeStrategy::Deduplicate,
+            server_ranking: Vec::new(),
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum LspMergeStrategy {
+    /// Keep the results of all servers.
+    #[default]
+    Concatenate,
+    /// Keep the results of all servers, except for the ones already reported by a higher ranked server.
+    Deduplicate,
+    /// Keep only the results of the highest ranked server that reported any.
+    First,
 }
 
 #[derive(Copy, Clone, Debug, Default, Serialize, Deserialize, JsonSchema)]
@@ -103,6 +357,8 @@ pub struct LspSettings {
     pub binary: Option<BinarySettings>,
     pub initialization_options: Option<serde_json::Value>,
     pub settings: Option<serde_json::Value>,
+    /// Where the instances of the language server are rooted, overriding its default.
+    pub roots: Option<LanguageServerRoots>,
 }
 
 impl Settings for ProjectSettings {
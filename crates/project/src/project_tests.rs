@@ -1,11 +1,18 @@
-use crate::{Event, *};
+use crate::{
+    formatter_chain::describe_edited_rows,
+    prettier_support::prettier_plugins_for_language,
+    project_settings::{LspMergeSettings, LspMergeStrategy},
+    pull_diagnostics::RawProgressParams,
+    task_variables::LSP_TASK_VARIABLES_TIMEOUT,
+    Event, *,
+};
 use fs::FakeFs;
 use futures::{future, StreamExt};
-use gpui::{AppContext, SemanticVersion, UpdateGlobal};
+use gpui::{size, AppContext, SemanticVersion, UpdateGlobal};
 use language::{
-    language_settings::{AllLanguageSettings, LanguageSettingsContent},
-    tree_sitter_rust, tree_sitter_typescript, Diagnostic, FakeLspAdapter, LanguageConfig,
-    LanguageMatcher, LineEnding, OffsetRangeExt, Point, ToPoint,
+    language_settings::{AllLanguageSettings, CodeActionOnSave, LanguageSettingsContent},
+    tree_sitter_rust, tree_sitter_typescript, Diagnostic, Encoding, FakeLspAdapter, LanguageConfig,
+    LanguageMatcher, LanguageServerRoots, LineEnding, OffsetRangeExt, Point, ToPoint,
 };
 use lsp::NumberOrString;
 use parking_lot::Mutex;
@@ -665,6 +672,95 @@ async fn test_managing_language_servers(cx: &mut gpui::TestAppContext) {
     );
 }
 
+#[gpui::test]
+async fn test_language_servers_rooted_at_markers(cx: &mut gpui::TestAppContext) {
+    init_test(cx);
+
+    let fs = FakeFs::new(cx.executor());
+    fs.insert_tree(
+        "/the-root",
+        json!({
+            "a": { "go.mod": "", "main.rs": "" },
+            "b": { "go.mod": "", "src": { "lib.rs": "", "other.rs": "" } },
+            "top.rs": "",
+        }),
+    )
+    .await;
+
+    let project = Project::test(fs.clone(), ["/the-root".as_ref()], cx).await;
+    let language_registry = project.read_with(cx, |project, _| project.languages().clone());
+    language_registry.add(rust_lang());
+    let mut fake_servers = language_registry.register_fake_lsp_adapter(
+        "Rust",
+        FakeLspAdapter {
+            roots: LanguageServerRoots::Nearest(vec!["go.mod".into()]),
+            ..Default::default()
+        },
+    );
+
+    async fn open_buffer(
+        project: &Model<Project>,
+        path: &str,
+        cx: &mut gpui::TestAppContext,
+    ) -> Model<Buffer> {
+        project
+            .update(cx, |project, cx| project.open_local_buffer(path, cx))
+            .await
+            .unwrap()
+    }
+    let a_buffer = open_buffer(&project, "/the-root/a/main.rs", cx).await;
+    let mut a_server = fake_servers.next().await.unwrap();
+    let b_buffer = open_buffer(&project, "/the-root/b/src/lib.rs", cx).await;
+    let mut b_server = fake_servers.next().await.unwrap();
+    let top_buffer = open_buffer(&project, "/the-root/top.rs", cx).await;
+    let mut top_server = fake_servers.next().await.unwrap();
+
+    // Each buffer is opened in the server of its root only.
+    for (server, path) in [
+        (&mut a_server, "/the-root/a/main.rs"),
+        (&mut b_server, "/the-root/b/src/lib.rs"),
+        (&mut top_server, "/the-root/top.rs"),
+    ] {
+        assert_eq!(
+            server
+                .receive_notification::<lsp::notification::DidOpenTextDocument>()
+                .await
+                .text_document
+                .uri,
+            lsp::Url::from_file_path(path).unwrap()
+        );
+    }
+
+    // Another buffer of a root goes to its running server.
+    let other_b_buffer = open_buffer(&project, "/the-root/b/src/other.rs", cx).await;
+    assert_eq!(
+        b_server
+            .receive_notification::<lsp::notification::DidOpenTextDocument>()
+            .await
+            .text_document
+            .uri,
+        lsp::Url::from_file_path("/the-root/b/src/other.rs").unwrap()
+    );
+
+    project.update(cx, |project, cx| {
+        let server_ids = [&a_buffer, &b_buffer, &other_b_buffer, &top_buffer].map(|buffer| {
+            project
+                .language_servers_for_buffer(buffer.read(cx), cx)
+                .map(|(_, server)| server.server_id())
+                .collect::<Vec<_>>()
+        });
+        assert_eq!(
+            server_ids,
+            [
+                vec![a_server.server.server_id()],
+                vec![b_server.server.server_id()],
+                vec![b_server.server.server_id()],
+                vec![top_server.server.server_id()],
+            ]
+        );
+    });
+}
+
 #[gpui::test]
 async fn test_reporting_fs_changes_to_language_servers(cx: &mut gpui::TestAppContext) {
     init_test(cx);
@@ -1299,6 +1395,97 @@ async fn test_restarting_server_with_diagnostics_running(cx: &mut gpui::TestAppC
     });
 }
 
+#[gpui::test]
+async fn test_restarting_servers_for_changed_environment(cx: &mut gpui::TestAppContext) {
+    init_test(cx);
+
+    let fs = FakeFs::new(cx.executor());
+    fs.insert_tree("/dir", json!({ "a.rs": "", ".envrc": "" }))
+        .await;
+
+    let project = Project::test(fs, ["/dir".as_ref()], cx).await;
+    let language_registry = project.read_with(cx, |project, _| project.languages().clone());
+    language_registry.add(rust_lang());
+    let _stale_servers = language_registry.register_fake_lsp_adapter(
+        "Rust",
+        FakeLspAdapter {
+            name: "stale-server",
+            ..Default::default()
+        },
+    );
+    let _fresh_servers = language_registry.register_fake_lsp_adapter(
+        "Rust",
+        FakeLspAdapter {
+            name: "fresh-server",
+            ..Default::default()
+        },
+    );
+
+    let buffer = project
+        .update(cx, |project, cx| project.open_local_buffer("/dir/a.rs", cx))
+        .await
+        .unwrap();
+    cx.executor().run_until_parked();
+    let server_ids = |cx: &mut gpui::TestAppContext| {
+        project.read_with(cx, |project, cx| {
+            let mut server_ids = project
+                .language_servers_for_buffer(buffer.read(cx), cx)
+                .map(|(adapter, server)| (adapter.name.0.to_string(), server.server_id()))
+                .collect::<Vec<_>>();
+            server_ids.sort();
+            server_ids
+        })
+    };
+    let initial_server_ids = server_ids(cx);
+    assert_eq!(
+        initial_server_ids,
+        [
+            ("fresh-server".to_string(), LanguageServerId(1)),
+            ("stale-server".to_string(), LanguageServerId(0)),
+        ]
+    );
+
+    // Only the server started with another environment is offered to be restarted.
+    let environment = |path: &str| HashMap::from_iter([("PATH".to_string(), path.to_string())]);
+    project.update(cx, |project, _| {
+        let watcher = &project.environment_watcher;
+        watcher.set_language_server_environment(LanguageServerId(0), environment("/old"));
+        watcher.set_language_server_environment(LanguageServerId(1), environment("/new"));
+    });
+    let mut events = cx.events(&project);
+    let worktree = project.read_with(cx, |project, _| project.worktrees().next().unwrap());
+    let restart = project
+        .update(cx, |project, cx| {
+            project.prompt_to_restart_for_environment(&worktree, &environment("/new"), cx)
+        })
+        .unwrap();
+    let Some(Event::LanguageServerPrompt(prompt)) = events.next().await else {
+        panic!("expected a prompt to restart the language servers");
+    };
+    assert_eq!(prompt.lsp_name, "stale-server");
+    prompt.respond(0).await.unwrap();
+    restart.await;
+    cx.executor().run_until_parked();
+
+    assert_eq!(
+        server_ids(cx),
+        [
+            ("fresh-server".to_string(), LanguageServerId(1)),
+            ("stale-server".to_string(), LanguageServerId(2)),
+        ]
+    );
+
+    // Once every server uses the environment, there's nothing to restart.
+    project.update(cx, |project, cx| {
+        project
+            .environment_watcher
+            .set_language_server_environment(LanguageServerId(2), environment("/new"));
+        assert!(project
+            .prompt_to_restart_for_environment(&worktree, &environment("/new"), cx)
+            .is_none());
+    });
+}
+
 #[gpui::test]
 async fn test_restarting_server_with_diagnostics_published(cx: &mut gpui::TestAppContext) {
     init_test(cx);
@@ -1481,6 +1668,71 @@ async fn test_cancel_language_server_work(cx: &mut gpui::TestAppContext) {
     );
 }
 
+#[gpui::test]
+async fn test_language_server_work_becoming_cancellable(cx: &mut gpui::TestAppContext) {
+    init_test(cx);
+
+    let progress_token = "the-progress-token";
+
+    let fs = FakeFs::new(cx.executor());
+    fs.insert_tree("/dir", json!({ "a.rs": "" })).await;
+
+    let project = Project::test(fs, ["/dir".as_ref()], cx).await;
+
+    let language_registry = project.read_with(cx, |project, _| project.languages().clone());
+    language_registry.add(rust_lang());
+    let mut fake_servers = language_registry.register_fake_lsp_adapter("Rust", Default::default());
+
+    let buffer = project
+        .update(cx, |project, cx| project.open_local_buffer("/dir/a.rs", cx))
+        .await
+        .unwrap();
+
+    let mut fake_server = fake_servers.next().await.unwrap();
+    fake_server
+        .start_progress_with(
+            progress_token,
+            lsp::WorkDoneProgressBegin {
+                cancellable: Some(false),
+                ..Default::default()
+            },
+        )
+        .await;
+    let report_progress = |cancellable| {
+        fake_server.notify::<lsp::notification::Progress>(lsp::ProgressParams {
+            token: NumberOrString::String(progress_token.into()),
+            value: lsp::ProgressParamsValue::WorkDone(lsp::WorkDoneProgress::Report(
+                lsp::WorkDoneProgressReport {
+                    cancellable,
+                    ..Default::default()
+                },
+            )),
+        })
+    };
+    report_progress(Some(true));
+    cx.executor().run_until_parked();
+    // A report that leaves out the cancellability keeps the work cancellable.
+    report_progress(None);
+    cx.executor().run_until_parked();
+
+    let server_id = fake_server.server.server_id();
+    project.read_with(cx, |project, _| {
+        let status = project.language_server_statuses.get(&server_id).unwrap();
+        assert!(status.pending_work[progress_token].is_cancellable);
+    });
+
+    project.update(cx, |project, cx| {
+        project.cancel_language_server_work_for_buffers([buffer.clone()], cx)
+    });
+    let cancel_notification = fake_server
+        .receive_notification::<lsp::notification::WorkDoneProgressCancel>()
+        .await;
+    assert_eq!(
+        cancel_notification.token,
+        NumberOrString::String(progress_token.into())
+    );
+}
+
 #[gpui::test]
 async fn test_toggling_enable_language_server(cx: &mut gpui::TestAppContext) {
     init_test(cx);
@@ -2001,480 +2253,2933 @@ async fn test_diagnostics_from_multiple_language_servers(cx: &mut gpui::TestAppC
 }
 
 #[gpui::test]
-async fn test_edits_from_lsp2_with_past_version(cx: &mut gpui::TestAppContext) {
+async fn test_pulled_diagnostics(cx: &mut gpui::TestAppContext) {
     init_test(cx);
 
-    let text = "
-        fn a() {
-            f1();
-        }
-        fn b() {
-            f2();
-        }
-        fn c() {
-            f3();
-        }
-    "
-    .unindent();
-
     let fs = FakeFs::new(cx.executor());
-    fs.insert_tree(
-        "/dir",
-        json!({
-            "a.rs": text.clone(),
-        }),
-    )
-    .await;
+    fs.insert_tree("/dir", json!({ "a.rs": "let a = 1;" }))
+        .await;
 
     let project = Project::test(fs, ["/dir".as_ref()], cx).await;
-
     let language_registry = project.read_with(cx, |project, _| project.languages().clone());
     language_registry.add(rust_lang());
-    let mut fake_servers =
-        language_registry.register_fake_lsp_adapter("Rust", FakeLspAdapter::default());
+    let mut fake_servers = language_registry.register_fake_lsp_adapter(
+        "Rust",
+        FakeLspAdapter {
+            capabilities: lsp::ServerCapabilities {
+                diagnostic_provider: Some(lsp::DiagnosticServerCapabilities::Options(
+                    lsp::DiagnosticOptions::default(),
+                )),
+                ..Default::default()
+            },
+            ..Default::default()
+        },
+    );
 
     let buffer = project
         .update(cx, |project, cx| project.open_local_buffer("/dir/a.rs", cx))
         .await
         .unwrap();
+    let fake_server = fake_servers.next().await.unwrap();
 
-    let mut fake_server = fake_servers.next().await.unwrap();
-    let lsp_document_version = fake_server
-        .receive_notification::<lsp::notification::DidOpenTextDocument>()
-        .await
-        .text_document
-        .version;
-
-    // Simulate editing the buffer after the language server computes some edits.
-    buffer.update(cx, |buffer, cx| {
-        buffer.edit(
-            [(
-                Point::new(0, 0)..Point::new(0, 0),
-                "// above first function\n",
-            )],
-            None,
-            cx,
-        );
-        buffer.edit(
-            [(
-                Point::new(2, 0)..Point::new(2, 0),
-                "    // inside first function\n",
-            )],
-            None,
-            cx,
-        );
-        buffer.edit(
-            [(
-                Point::new(6, 4)..Point::new(6, 4),
-                "// inside second function ",
-            )],
-            None,
-            cx,
-        );
+    let previous_result_ids = Arc::new(Mutex::new(Vec::new()));
+    fake_server.handle_request::<lsp::request::DocumentDiagnosticRequest, _, _>({
+        let previous_result_ids = previous_result_ids.clone();
+        move |params, _| {
+            let previous_result_ids = previous_result_ids.clone();
+            async move {
+                let previous_result_id = params.previous_result_id;
+                previous_result_ids.lock().push(previous_result_id.clone());
+                let report = match previous_result_id {
+                    Some(result_id) => lsp::DocumentDiagnosticReport::Unchanged(
+                        lsp::RelatedUnchangedDocumentDiagnosticReport {
+                            related_documents: None,
+                            unchanged_document_diagnostic_report:
+                                lsp::UnchangedDocumentDiagnosticReport { result_id },
+                        },
+                    ),
+                    None => lsp::DocumentDiagnosticReport::Full(
+                        lsp::RelatedFullDocumentDiagnosticReport {
+                            related_documents: None,
+                            full_document_diagnostic_report: lsp::FullDocumentDiagnosticReport {
+                                result_id: Some("1".to_string()),
+                                items: vec![lsp::Diagnostic {
+                                    range: lsp::Range::new(
+                                        lsp::Position::new(0, 4),
+                                        lsp::Position::new(0, 5),
+                                    ),
+                                    severity: Some(lsp::DiagnosticSeverity::ERROR),
+                                    message: "pulled error".to_string(),
+                                    ..Default::default()
+                                }],
+                            },
+                        },
+                    ),
+                };
+                Ok(lsp::DocumentDiagnosticReportResult::Report(report))
+            }
+        }
+    });
 
+    project
+        .update(cx, |project, cx| {
+            project.pull_diagnostics(buffer.clone(), cx)
+        })
+        .await
+        .unwrap();
+    cx.executor().run_until_parked();
+    buffer.update(cx, |buffer, _| {
         assert_eq!(
-            buffer.text(),
-            "
-                // above first function
-                fn a() {
-                    // inside first function
-                    f1();
-                }
-                fn b() {
-                    // inside second function f2();
-                }
-                fn c() {
-                    f3();
-                }
-            "
-            .unindent()
+            chunks_with_diagnostics(buffer, 0..buffer.len()),
+            [
+                ("let ".to_string(), None),
+                ("a".to_string(), Some(DiagnosticSeverity::ERROR)),
+                (" = 1;".to_string(), None),
+            ]
         );
     });
 
-    let edits = project
-        .update(cx, |project, cx| {
+    // Editing the buffer pulls the diagnostics again, after a delay.
+    // The server reports them unchanged, so they are kept.
+    buffer.update(cx, |buffer, cx| buffer.edit([(0..0, "\n")], None, cx));
+    cx.executor().advance_clock(Duration::from_secs(1));
+    cx.executor().run_until_parked();
+    assert_eq!(
+        previous_result_ids.lock().as_slice(),
+        [None, Some("1".to_string())]
+    );
+    project.update(cx, |project, cx| {
+        assert_eq!(
+            project.diagnostic_summary(false, cx),
+            DiagnosticSummary {
+                error_count: 1,
+                warning_count: 0,
+            }
+        );
+    });
+}
+
+#[gpui::test]
+async fn test_pulled_diagnostics_of_edited_buffer(cx: &mut gpui::TestAppContext) {
+    init_test(cx);
+
+    let fs = FakeFs::new(cx.executor());
+    fs.insert_tree("/dir", json!({ "a.rs": "let a = 1;" }))
+        .await;
+
+    let project = Project::test(fs, ["/dir".as_ref()], cx).await;
+    let language_registry = project.read_with(cx, |project, _| project.languages().clone());
+    language_registry.add(rust_lang());
+    let mut fake_servers = language_registry.register_fake_lsp_adapter(
+        "Rust",
+        FakeLspAdapter {
+            capabilities: lsp::ServerCapabilities {
+                diagnostic_provider: Some(lsp::DiagnosticServerCapabilities::Options(
+                    lsp::DiagnosticOptions::default(),
+                )),
+                ..Default::default()
+            },
+            ..Default::default()
+        },
+    );
+
+    let buffer = project
+        .update(cx, |project, cx| project.open_local_buffer("/dir/a.rs", cx))
+        .await
+        .unwrap();
+    let fake_server = fake_servers.next().await.unwrap();
+
+    // The server replies after the buffer is edited, with diagnostics for the contents it was asked about.
+    let (reply_tx, reply_rx) = futures::channel::oneshot::channel::<()>();
+    let reply_rx = Arc::new(Mutex::new(Some(reply_rx)));
+    fake_server.handle_request::<lsp::request::DocumentDiagnosticRequest, _, _>(move |_, _| {
+        let reply_rx = reply_rx.lock().take();
+        async move {
+            if let Some(reply_rx) = reply_rx {
+                reply_rx.await.ok();
+            }
+            Ok(lsp::DocumentDiagnosticReportResult::Report(
+                lsp::DocumentDiagnosticReport::Full(lsp::RelatedFullDocumentDiagnosticReport {
+                    related_documents: None,
+                    full_document_diagnostic_report: lsp::FullDocumentDiagnosticReport {
+                        result_id: None,
+                        items: vec![lsp::Diagnostic {
+                            range: lsp::Range::new(
+                                lsp::Position::new(0, 4),
+                                lsp::Position::new(0, 5),
+                            ),
+                            severity: Some(lsp::DiagnosticSeverity::ERROR),
+                            message: "pulled error".to_string(),
+                            ..Default::default()
+                        }],
+                    },
+                }),
+            ))
+        }
+    });
+
+    let pull = project.update(cx, |project, cx| {
+        project.pull_diagnostics(buffer.clone(), cx)
+    });
+    cx.executor().run_until_parked();
+    buffer.update(cx, |buffer, cx| buffer.edit([(0..0, "\n")], None, cx));
+    cx.executor().run_until_parked();
+    reply_tx.send(()).unwrap();
+    pull.await.unwrap();
+    cx.executor().run_until_parked();
+
+    buffer.update(cx, |buffer, _| {
+        assert_eq!(
+            chunks_with_diagnostics(buffer, 0..buffer.len()),
+            [
+                ("\nlet ".to_string(), None),
+                ("a".to_string(), Some(DiagnosticSeverity::ERROR)),
+                (" = 1;".to_string(), None),
+            ]
+        );
+    });
+}
+
+#[gpui::test]
+async fn test_semantic_tokens(cx: &mut gpui::TestAppContext) {
+    init_test(cx);
+
+    let fs = FakeFs::new(cx.executor());
+    fs.insert_tree("/dir", json!({ "a.rs": "fn a() { b(); }" }))
+        .await;
+
+    let project = Project::test(fs, ["/dir".as_ref()], cx).await;
+    let language_registry = project.read_with(cx, |project, _| project.languages().clone());
+    language_registry.add(rust_lang());
+    let mut fake_servers = language_registry.register_fake_lsp_adapter(
+        "Rust",
+        FakeLspAdapter {
+            capabilities: lsp::ServerCapabilities {
+                semantic_tokens_provider: Some(
+                    serde_json::from_value(json!({
+                        "legend": {
+                            "tokenTypes": ["function", "variable"],
+                            "tokenModifiers": ["declaration"],
+                        },
+                        "full": { "delta": true },
+                    }))
+                    .unwrap(),
+                ),
+                ..Default::default()
+            },
+            ..Default::default()
+        },
+    );
+
+    let buffer = project
+        .update(cx, |project, cx| project.open_local_buffer("/dir/a.rs", cx))
+        .await
+        .unwrap();
+    let fake_server = fake_servers.next().await.unwrap();
+
+    let full_requests = Arc::new(Mutex::new(0));
+    fake_server.handle_request::<lsp::request::SemanticTokensFullRequest, _, _>({
+        let full_requests = full_requests.clone();
+        move |_, _| {
+            *full_requests.lock() += 1;
+            async move {
+                Ok(Some(lsp::SemanticTokensResult::Tokens(
+                    serde_json::from_value(json!({
+                        "resultId": "1",
+                        "data": [0, 3, 1, 0, 1, 0, 6, 1, 0, 0],
+                    }))
+                    .unwrap(),
+                )))
+            }
+        }
+    });
+    let previous_result_ids = Arc::new(Mutex::new(Vec::new()));
+    fake_server.handle_request::<lsp::request::SemanticTokensFullDeltaRequest, _, _>({
+        let previous_result_ids = previous_result_ids.clone();
+        move |params, _| {
+            previous_result_ids.lock().push(params.previous_result_id);
+            async move {
+                Ok(Some(lsp::SemanticTokensFullDeltaResult::TokensDelta(
+                    serde_json::from_value(json!({
+                        "resultId": "2",
+                        "edits": [{
+                            "start": 5,
+                            "deleteCount": 5,
+                            "data": [0, 6, 1, 0, 0, 0, 5, 1, 0, 0],
+                        }],
+                    }))
+                    .unwrap(),
+                )))
+            }
+        }
+    });
+
+    assert_eq!(
+        semantic_token_texts(&project, &buffer, cx).await,
+        [
+            (
+                "a".to_string(),
+                "function".to_string(),
+                vec!["declaration".to_string()]
+            ),
+            ("b".to_string(), "function".to_string(), vec![]),
+        ]
+    );
+    // The tokens are cached until the buffer changes.
+    semantic_token_texts(&project, &buffer, cx).await;
+    assert_eq!(*full_requests.lock(), 1);
+    assert!(previous_result_ids.lock().is_empty());
+
+    // After an edit, the delta is requested and applied to the previous tokens.
+    buffer.update(cx, |buffer, cx| buffer.edit([(9..9, "c(); ")], None, cx));
+    assert_eq!(
+        semantic_token_texts(&project, &buffer, cx).await,
+        [
+            (
+                "a".to_string(),
+                "function".to_string(),
+                vec!["declaration".to_string()]
+            ),
+            ("c".to_string(), "function".to_string(), vec![]),
+            ("b".to_string(), "function".to_string(), vec![]),
+        ]
+    );
+    assert_eq!(*full_requests.lock(), 1);
+    assert_eq!(previous_result_ids.lock().as_slice(), ["1".to_string()]);
+}
+
+async fn semantic_token_texts(
+    project: &Model<Project>,
+    buffer: &Model<Buffer>,
+    cx: &mut gpui::TestAppContext,
+) -> Vec<(String, String, Vec<String>)> {
+    let tokens = project
+        .update(cx, |project, cx| project.semantic_tokens(buffer, cx))
+        .await
+        .unwrap();
+    buffer.read_with(cx, |buffer, _| {
+        tokens
+            .tokens
+            .iter()
+            .map(|token| {
+                (
+                    buffer
+                        .text_for_range(token.range.clone())
+                        .collect::<String>(),
+                    token.token_type.to_string(),
+                    token
+                        .token_modifiers
+                        .iter()
+                        .map(|modifier| modifier.to_string())
+                        .collect(),
+                )
+            })
+            .collect()
+    })
+}
+
+#[gpui::test]
+async fn test_resolving_workspace_symbols(cx: &mut gpui::TestAppContext) {
+    init_test(cx);
+
+    let fs = FakeFs::new(cx.executor());
+    fs.insert_tree(
+        "/dir",
+        json!({ "a.rs": "", "b.rs": "fn one() {}\nfn two() {}" }),
+    )
+    .await;
+
+    let project = Project::test(fs, ["/dir".as_ref()], cx).await;
+    let language_registry = project.read_with(cx, |project, _| project.languages().clone());
+    language_registry.add(rust_lang());
+    let mut fake_servers = language_registry.register_fake_lsp_adapter("Rust", Default::default());
+
+    let _buffer = project
+        .update(cx, |project, cx| project.open_local_buffer("/dir/a.rs", cx))
+        .await
+        .unwrap();
+    let fake_server = fake_servers.next().await.unwrap();
+    fake_server.handle_request::<lsp::WorkspaceSymbolRequest, _, _>(|_, _| async move {
+        Ok(Some(lsp::WorkspaceSymbolResponse::Nested(vec![
+            serde_json::from_value(json!({
+                "name": "two",
+                "kind": 12,
+                "location": { "uri": "file:///dir/b.rs" },
+                "data": 2,
+            }))
+            .unwrap(),
+        ])))
+    });
+    fake_server.handle_request::<lsp::request::WorkspaceSymbolResolve, _, _>(
+        |mut symbol, _| async move {
+            assert_eq!(symbol.data, Some(json!(2)));
+            symbol.location = lsp::OneOf::Left(lsp::Location::new(
+                lsp::Url::from_file_path("/dir/b.rs").unwrap(),
+                lsp::Range::new(lsp::Position::new(1, 3), lsp::Position::new(1, 6)),
+            ));
+            Ok(symbol)
+        },
+    );
+
+    let symbols = project
+        .update(cx, |project, cx| project.symbols("two", cx))
+        .await
+        .unwrap();
+    assert_eq!(symbols.len(), 1);
+    assert_eq!(symbols[0].path.path.as_ref(), Path::new("b.rs"));
+    assert!(symbols[0].unresolved_symbol.is_some());
+
+    let symbol = project
+        .update(cx, |project, cx| project.resolve_symbol(&symbols[0], cx))
+        .await
+        .unwrap();
+    assert!(symbol.unresolved_symbol.is_none());
+    assert_eq!(
+        symbol.range,
+        Unclipped(PointUtf16::new(1, 3))..Unclipped(PointUtf16::new(1, 6))
+    );
+}
+
+#[gpui::test]
+async fn test_document_links(cx: &mut gpui::TestAppContext) {
+    init_test(cx);
+
+    let fs = FakeFs::new(cx.executor());
+    fs.insert_tree(
+        "/dir",
+        json!({ "a.rs": "// see https://zed.dev and b.rs", "b.rs": "" }),
+    )
+    .await;
+
+    let project = Project::test(fs, ["/dir".as_ref()], cx).await;
+    let language_registry = project.read_with(cx, |project, _| project.languages().clone());
+    language_registry.add(rust_lang());
+    let mut fake_servers = language_registry.register_fake_lsp_adapter(
+        "Rust",
+        FakeLspAdapter {
+            capabilities: lsp::ServerCapabilities {
+                document_link_provider: Some(lsp::DocumentLinkOptions {
+                    resolve_provider: Some(true),
+                    work_done_progress_options: Default::default(),
+                }),
+                ..Default::default()
+            },
+            ..Default::default()
+        },
+    );
+
+    let buffer = project
+        .update(cx, |project, cx| project.open_local_buffer("/dir/a.rs", cx))
+        .await
+        .unwrap();
+    let fake_server = fake_servers.next().await.unwrap();
+    let link_requests = Arc::new(Mutex::new(0));
+    fake_server.handle_request::<lsp::request::DocumentLinkRequest, _, _>({
+        let link_requests = link_requests.clone();
+        move |_, _| {
+            *link_requests.lock() += 1;
+            async move {
+                Ok(Some(vec![
+                    serde_json::from_value(json!({
+                        "range": { "start": { "line": 0, "character": 7 }, "end": { "line": 0, "character": 22 } },
+                        "target": "https://zed.dev/",
+                    }))
+                    .unwrap(),
+                    serde_json::from_value(json!({
+                        "range": { "start": { "line": 0, "character": 27 }, "end": { "line": 0, "character": 31 } },
+                        "data": "b.rs",
+                    }))
+                    .unwrap(),
+                ]))
+            }
+        }
+    });
+    fake_server.handle_request::<lsp::request::DocumentLinkResolve, _, _>(
+        |mut link, _| async move {
+            assert_eq!(link.data, Some(json!("b.rs")));
+            link.target = Some(lsp::Url::from_file_path("/dir/b.rs").unwrap());
+            Ok(link)
+        },
+    );
+
+    let links = project
+        .update(cx, |project, cx| project.document_links(&buffer, cx))
+        .await
+        .unwrap();
+    let link_texts = buffer.read_with(cx, |buffer, _| {
+        links
+            .links
+            .iter()
+            .map(|link| {
+                (
+                    buffer
+                        .text_for_range(link.range.clone())
+                        .collect::<String>(),
+                    link.target.clone(),
+                )
+            })
+            .collect::<Vec<_>>()
+    });
+    assert_eq!(
+        link_texts,
+        [
+            (
+                "https://zed.dev".to_string(),
+                Some("https://zed.dev/".to_string())
+            ),
+            ("b.rs".to_string(), None),
+        ]
+    );
+
+    // The links are cached until the buffer changes.
+    project
+        .update(cx, |project, cx| project.document_links(&buffer, cx))
+        .await
+        .unwrap();
+    assert_eq!(*link_requests.lock(), 1);
+
+    let link = project
+        .update(cx, |project, cx| {
+            project.resolve_document_link(&buffer, links.links[1].clone(), cx)
+        })
+        .await
+        .unwrap();
+    assert_eq!(link.target.as_deref(), Some("file:///dir/b.rs"));
+
+    buffer.update(cx, |buffer, cx| buffer.edit([(0..0, "\n")], None, cx));
+    project
+        .update(cx, |project, cx| project.document_links(&buffer, cx))
+        .await
+        .unwrap();
+    assert_eq!(*link_requests.lock(), 2);
+}
+
+#[gpui::test]
+async fn test_document_colors(cx: &mut gpui::TestAppContext) {
+    init_test(cx);
+
+    let fs = FakeFs::new(cx.executor());
+    fs.insert_tree("/dir", json!({ "a.rs": "let red = \"#ff0000\";" }))
+        .await;
+
+    let project = Project::test(fs, ["/dir".as_ref()], cx).await;
+    let language_registry = project.read_with(cx, |project, _| project.languages().clone());
+    language_registry.add(rust_lang());
+    let mut fake_servers = language_registry.register_fake_lsp_adapter(
+        "Rust",
+        FakeLspAdapter {
+            capabilities: lsp::ServerCapabilities {
+                color_provider: Some(lsp::ColorProviderCapability::Simple(true)),
+                ..Default::default()
+            },
+            ..Default::default()
+        },
+    );
+
+    let buffer = project
+        .update(cx, |project, cx| project.open_local_buffer("/dir/a.rs", cx))
+        .await
+        .unwrap();
+    let fake_server = fake_servers.next().await.unwrap();
+    let color_requests = Arc::new(Mutex::new(0));
+    fake_server.handle_request::<lsp::request::DocumentColor, _, _>({
+        let color_requests = color_requests.clone();
+        move |_, _| {
+            *color_requests.lock() += 1;
+            async move {
+                Ok(vec![lsp::ColorInformation {
+                    range: lsp::Range::new(lsp::Position::new(0, 11), lsp::Position::new(0, 18)),
+                    color: lsp::Color {
+                        red: 1.,
+                        green: 0.,
+                        blue: 0.,
+                        alpha: 1.,
+                    },
+                }])
+            }
+        }
+    });
+    fake_server.handle_request::<lsp::request::ColorPresentationRequest, _, _>(
+        |params, _| async move {
+            assert_eq!(params.color.red, 1.);
+            Ok(vec![
+                lsp::ColorPresentation {
+                    label: "rgb(255, 0, 0)".to_string(),
+                    text_edit: None,
+                    additional_text_edits: None,
+                },
+                lsp::ColorPresentation {
+                    label: "red".to_string(),
+                    text_edit: Some(lsp::TextEdit::new(
+                        lsp::Range::new(lsp::Position::new(0, 10), lsp::Position::new(0, 19)),
+                        "\"red\"".to_string(),
+                    )),
+                    additional_text_edits: None,
+                },
+            ])
+        },
+    );
+
+    let colors = project
+        .update(cx, |project, cx| project.document_colors(&buffer, cx))
+        .await
+        .unwrap();
+    assert_eq!(colors.colors.len(), 1);
+    let color = colors.colors[0].clone();
+    buffer.read_with(cx, |buffer, _| {
+        assert_eq!(
+            buffer
+                .text_for_range(color.range.clone())
+                .collect::<String>(),
+            "#ff0000"
+        );
+    });
+
+    // The colors are cached until the buffer is edited.
+    project
+        .update(cx, |project, cx| project.document_colors(&buffer, cx))
+        .await
+        .unwrap();
+    assert_eq!(*color_requests.lock(), 1);
+
+    let presentations = project
+        .update(cx, |project, cx| {
+            project.color_presentations(&buffer, &color, cx)
+        })
+        .await
+        .unwrap();
+    assert_eq!(
+        presentations
+            .iter()
+            .map(|presentation| presentation.label.as_str())
+            .collect::<Vec<_>>(),
+        ["rgb(255, 0, 0)", "red"]
+    );
+    buffer.update(cx, |buffer, cx| {
+        buffer.edit(presentations[0].edits(&color), None, cx);
+        assert_eq!(buffer.text(), "let red = \"rgb(255, 0, 0)\";");
+    });
+
+    project
+        .update(cx, |project, cx| project.document_colors(&buffer, cx))
+        .await
+        .unwrap();
+    assert_eq!(*color_requests.lock(), 2);
+}
+
+#[gpui::test]
+async fn test_folding_ranges(cx: &mut gpui::TestAppContext) {
+    init_test(cx);
+
+    let fs = FakeFs::new(cx.executor());
+    fs.insert_tree(
+        "/dir",
+        json!({ "a.rs": "use a;\nuse b;\n\nfn main() {\n    a();\n}\n" }),
+    )
+    .await;
+
+    let project = Project::test(fs, ["/dir".as_ref()], cx).await;
+    let language_registry = project.read_with(cx, |project, _| project.languages().clone());
+    language_registry.add(rust_lang());
+    let mut fake_servers = language_registry.register_fake_lsp_adapter(
+        "Rust",
+        FakeLspAdapter {
+            capabilities: lsp::ServerCapabilities {
+                folding_range_provider: Some(lsp::FoldingRangeProviderCapability::Simple(true)),
+                ..Default::default()
+            },
+            ..Default::default()
+        },
+    );
+
+    let buffer = project
+        .update(cx, |project, cx| project.open_local_buffer("/dir/a.rs", cx))
+        .await
+        .unwrap();
+    let fake_server = fake_servers.next().await.unwrap();
+    fake_server.handle_request::<lsp::request::FoldingRangeRequest, _, _>(|_, _| async move {
+        Ok(Some(vec![
+            lsp::FoldingRange {
+                start_line: 3,
+                end_line: 5,
+                ..Default::default()
+            },
+            lsp::FoldingRange {
+                start_line: 4,
+                end_line: 4,
+                ..Default::default()
+            },
+            lsp::FoldingRange {
+                start_line: 0,
+                end_line: 1,
+                kind: Some(lsp::FoldingRangeKind::Imports),
+                ..Default::default()
+            },
+        ]))
+    });
+
+    let ranges = project
+        .update(cx, |project, cx| project.folding_ranges(&buffer, cx))
+        .await
+        .unwrap();
+    buffer.read_with(cx, |buffer, _| {
+        let ranges = ranges
+            .ranges
+            .iter()
+            .map(|range| (range.range.to_point(buffer), range.kind))
+            .collect::<Vec<_>>();
+        // The single-line range is dropped, and each range is folded after its first line.
+        assert_eq!(
+            ranges,
+            [
+                (
+                    Point::new(0, 6)..Point::new(1, 6),
+                    Some(FoldingRangeKind::Imports)
+                ),
+                (Point::new(3, 11)..Point::new(5, 1), None),
+            ]
+        );
+    });
+}
+
+#[gpui::test]
+async fn test_flat_document_symbols(cx: &mut gpui::TestAppContext) {
+    init_test(cx);
+
+    let fs = FakeFs::new(cx.executor());
+    fs.insert_tree(
+        "/dir",
+        json!({ "a.rs": "struct A {\n    b: u8,\n}\nfn c() {}\n" }),
+    )
+    .await;
+
+    let project = Project::test(fs, ["/dir".as_ref()], cx).await;
+    let language_registry = project.read_with(cx, |project, _| project.languages().clone());
+    language_registry.add(rust_lang());
+    let mut fake_servers = language_registry.register_fake_lsp_adapter(
+        "Rust",
+        FakeLspAdapter {
+            capabilities: lsp::ServerCapabilities {
+                document_symbol_provider: Some(lsp::OneOf::Left(true)),
+                ..Default::default()
+            },
+            ..Default::default()
+        },
+    );
+
+    let buffer = project
+        .update(cx, |project, cx| project.open_local_buffer("/dir/a.rs", cx))
+        .await
+        .unwrap();
+    let fake_server = fake_servers.next().await.unwrap();
+    fake_server.handle_request::<lsp::request::DocumentSymbolRequest, _, _>(
+        |params, _| async move {
+            #[allow(deprecated)]
+            let symbol = |name: &str, kind, range| lsp::SymbolInformation {
+                name: name.to_string(),
+                kind,
+                tags: None,
+                deprecated: None,
+                location: lsp::Location::new(params.text_document.uri.clone(), range),
+                container_name: None,
+            };
+            Ok(Some(lsp::DocumentSymbolResponse::Flat(vec![
+                symbol(
+                    "c",
+                    lsp::SymbolKind::FUNCTION,
+                    lsp::Range::new(lsp::Position::new(3, 0), lsp::Position::new(3, 9)),
+                ),
+                symbol(
+                    "b",
+                    lsp::SymbolKind::FIELD,
+                    lsp::Range::new(lsp::Position::new(1, 4), lsp::Position::new(1, 10)),
+                ),
+                symbol(
+                    "A",
+                    lsp::SymbolKind::STRUCT,
+                    lsp::Range::new(lsp::Position::new(0, 0), lsp::Position::new(2, 1)),
+                ),
+            ])))
+        },
+    );
+
+    let symbols = project
+        .update(cx, |project, cx| project.document_symbols(&buffer, cx))
+        .await
+        .unwrap();
+    buffer.read_with(cx, |buffer, _| {
+        fn names(symbols: &[DocumentSymbol], buffer: &Buffer) -> Vec<(String, Range<Point>)> {
+            symbols
+                .iter()
+                .flat_map(|symbol| {
+                    let prefix = format!("{}.", symbol.name);
+                    std::iter::once((symbol.name.clone(), symbol.range.to_point(buffer))).chain(
+                        names(&symbol.children, buffer)
+                            .into_iter()
+                            .map(move |(name, range)| (format!("{prefix}{name}"), range)),
+                    )
+                })
+                .collect()
+        }
+        // The symbols contained in others are nested in them.
+        assert_eq!(
+            names(&symbols.symbols, buffer),
+            [
+                ("A".to_string(), Point::new(0, 0)..Point::new(2, 1)),
+                ("A.b".to_string(), Point::new(1, 4)..Point::new(1, 10)),
+                ("c".to_string(), Point::new(3, 0)..Point::new(3, 9)),
+            ]
+        );
+    });
+}
+
+#[gpui::test]
+async fn test_task_context_without_lsp_response(cx: &mut gpui::TestAppContext) {
+    init_test(cx);
+
+    let fs = FakeFs::new(cx.executor());
+    fs.insert_tree("/dir", json!({ "a.rs": "fn a() {}\n" }))
+        .await;
+
+    let project = Project::test(fs, ["/dir".as_ref()], cx).await;
+    let language_registry = project.read_with(cx, |project, _| project.languages().clone());
+    language_registry.add(rust_lang());
+    let mut fake_servers = language_registry.register_fake_lsp_adapter(
+        "Rust",
+        FakeLspAdapter {
+            capabilities: lsp::ServerCapabilities {
+                document_symbol_provider: Some(lsp::OneOf::Left(true)),
+                ..Default::default()
+            },
+            ..Default::default()
+        },
+    );
+
+    let buffer = project
+        .update(cx, |project, cx| project.open_local_buffer("/dir/a.rs", cx))
+        .await
+        .unwrap();
+    let fake_server = fake_servers.next().await.unwrap();
+    // The server never responds.
+    fake_server
+        .handle_request::<lsp::request::DocumentSymbolRequest, _, _>(|_, _| future::pending());
+
+    let position = buffer.read_with(cx, |buffer, _| buffer.anchor_before(Point::new(0, 4)));
+    let location = Location {
+        buffer: buffer.clone(),
+        range: position..position,
+    };
+    let task_context = project.update(cx, |project, cx| {
+        project.task_context_for_location(TaskVariables::default(), location, cx)
+    });
+    cx.executor().advance_clock(LSP_TASK_VARIABLES_TIMEOUT);
+    let task_context = task_context.await.unwrap();
+    assert_eq!(
+        task_context.task_variables.get(&VariableName::File),
+        Some("/dir/a.rs")
+    );
+    assert_eq!(task_context.task_variables.get(&VariableName::Symbol), None);
+}
+
+#[gpui::test]
+fn test_code_cells(cx: &mut gpui::AppContext) {
+    let script = cx.new_model(|cx| {
+        Buffer::local(
+            "use std::fmt;\n// %% first\nlet a = 1;\n  // %% second\nlet b = 2;",
+            cx,
+        )
+        .with_language(rust_lang(), cx)
+    });
+    let snapshot = script.read(cx).snapshot();
+    let cells = code_cells(&snapshot);
+    assert_eq!(
+        cells
+            .iter()
+            .map(|cell| (
+                cell.range.to_point(&snapshot),
+                cell.language.as_deref(),
+                cell.code.as_str()
+            ))
+            .collect::<Vec<_>>(),
+        [
+            (
+                Point::new(1, 0)..Point::new(3, 0),
+                Some("Rust"),
+                "let a = 1;\n"
+            ),
+            (
+                Point::new(3, 0)..Point::new(4, 10),
+                Some("Rust"),
+                "let b = 2;"
+            ),
+        ]
+    );
+
+    let markdown_lang = Arc::new(Language::new(
+        LanguageConfig {
+            name: "Markdown".into(),
+            ..Default::default()
+        },
+        None,
+    ));
+    let markdown = cx.new_model(|cx| {
+        Buffer::local(
+            "# Title\n```python\nprint(1)\n```\ntext\n~~~sh {.class}\n```\necho hi\n~~~\n```\n",
+            cx,
+        )
+        .with_language(markdown_lang, cx)
+    });
+    let snapshot = markdown.read(cx).snapshot();
+    let cells = code_cells(&snapshot);
+    assert_eq!(
+        cells
+            .iter()
+            .map(|cell| (
+                cell.range.to_point(&snapshot),
+                cell.language.as_deref(),
+                cell.code.as_str()
+            ))
+            .collect::<Vec<_>>(),
+        [
+            (
+                Point::new(1, 0)..Point::new(3, 3),
+                Some("python"),
+                "print(1)\n"
+            ),
+            (
+                Point::new(5, 0)..Point::new(8, 3),
+                Some("sh"),
+                "```\necho hi\n"
+            ),
+        ]
+    );
+}
+
+#[test]
+fn test_decode_displayed_image() {
+    let image = crate::code_cells::decode_displayed_image(
+        "image/png iVBORw0KGgoAAAANSUhEUgAAAAIAAAABCAYAAAD0In+KAAAADklEQVR4nGP4z8AAQv8BD/kD/YURmXYAAAAASUVORK5CYII=",
+    )
+    .unwrap();
+    assert_eq!(image.size(), size(2u32.into(), 1u32.into()));
+    // A red and a blue pixel, as BGRA.
+    assert_eq!(image.as_bytes(), [0, 0, 255, 255, 255, 0, 0, 255]);
+
+    assert!(crate::code_cells::decode_displayed_image("image/png").is_err());
+    assert!(crate::code_cells::decode_displayed_image("text/html PGI+PC9iPg==").is_err());
+}
+
+#[gpui::test]
+async fn test_lsp_request_timeouts(cx: &mut gpui::TestAppContext) {
+    init_test(cx);
+    cx.update(|cx| {
+        SettingsStore::update_global(cx, |settings, cx| {
+            settings.update_user_settings::<ProjectSettings>(cx, |settings| {
+                settings
+                    .lsp_request_timeouts
+                    .methods
+                    .insert("textDocument/references".to_string(), 100);
+            });
+        })
+    });
+
+    let fs = FakeFs::new(cx.executor());
+    fs.insert_tree("/dir", json!({ "a.rs": "fn one() {}" }))
+        .await;
+
+    let project = Project::test(fs, ["/dir".as_ref()], cx).await;
+    let language_registry = project.read_with(cx, |project, _| project.languages().clone());
+    language_registry.add(rust_lang());
+    let mut fake_servers = language_registry.register_fake_lsp_adapter(
+        "Rust",
+        FakeLspAdapter {
+            capabilities: lsp::ServerCapabilities {
+                references_provider: Some(OneOf::Left(true)),
+                hover_provider: Some(lsp::HoverProviderCapability::Simple(true)),
+                ..Default::default()
+            },
+            ..Default::default()
+        },
+    );
+
+    let buffer = project
+        .update(cx, |project, cx| project.open_local_buffer("/dir/a.rs", cx))
+        .await
+        .unwrap();
+    let mut fake_server = fake_servers.next().await.unwrap();
+    cx.executor().run_until_parked();
+    fake_server.handle_request::<lsp::request::References, _, _>(|_, _| {
+        futures::future::pending::<Result<Option<Vec<lsp::Location>>>>()
+    });
+    fake_server.handle_request::<lsp::request::HoverRequest, _, _>(|_, _| async move {
+        Ok(Some(lsp::Hover {
+            contents: lsp::HoverContents::Scalar(lsp::MarkedString::String("one".into())),
+            range: None,
+        }))
+    });
+
+    let mut events = cx.events(&project);
+    let references = project.update(cx, |project, cx| project.references(&buffer, 3, cx));
+    cx.executor().advance_clock(Duration::from_millis(200));
+    fake_server
+        .receive_notification::<lsp::notification::Cancel>()
+        .await;
+    assert!(references.await.is_err());
+    loop {
+        if let Event::Notification(message) = events.next().await.unwrap() {
+            assert!(message.contains("textDocument/references"), "{message}");
+            break;
+        }
+    }
+
+    // Requests of other methods keep the default timeout.
+    let hover = project.update(cx, |project, cx| project.hover(&buffer, 3, cx));
+    cx.executor().advance_clock(Duration::from_millis(200));
+    assert_eq!(hover.await.len(), 1);
+}
+
+#[gpui::test]
+async fn test_worktree_trust(cx: &mut gpui::TestAppContext) {
+    init_test(cx);
+    cx.update(|cx| {
+        SettingsStore::update_global(cx, |settings, cx| {
+            settings.update_user_settings::<ProjectSettings>(cx, |settings| {
+                settings.worktree_trust.enabled = true;
+            });
+        })
+    });
+
+    let fs = FakeFs::new(cx.executor());
+    fs.insert_tree(
+        "/dir",
+        json!({
+            ".zed": { "settings.json": r#"{ "tab_size": 8 }"# },
+            "a.rs": "fn one() {}",
+        }),
+    )
+    .await;
+
+    let project = Project::test(fs, ["/dir".as_ref()], cx).await;
+    let language_registry = project.read_with(cx, |project, _| project.languages().clone());
+    language_registry.add(rust_lang());
+    let mut fake_servers = language_registry.register_fake_lsp_adapter("Rust", Default::default());
+
+    let buffer = project
+        .update(cx, |project, cx| project.open_local_buffer("/dir/a.rs", cx))
+        .await
+        .unwrap();
+    cx.executor().run_until_parked();
+    let worktree_id = project.read_with(cx, |project, cx| {
+        project.worktrees().next().unwrap().read(cx).id()
+    });
+    let tab_size = |cx: &mut gpui::TestAppContext| {
+        cx.update(|cx| {
+            language_settings(None, buffer.read(cx).file(), cx)
+                .tab_size
+                .get()
+        })
+    };
+
+    // Neither the language server nor the local settings of an untrusted worktree are used.
+    assert!(!project.read_with(cx, |project, cx| project
+        .is_worktree_trusted(worktree_id, cx)));
+    assert!(fake_servers.try_next().is_err());
+    assert_eq!(tab_size(cx), 4);
+
+    project.update(cx, |project, cx| project.trust_worktree(worktree_id, cx));
+    fake_servers.next().await.unwrap();
+    cx.executor().run_until_parked();
+    assert!(project.read_with(cx, |project, cx| project
+        .is_worktree_trusted(worktree_id, cx)));
+    assert_eq!(tab_size(cx), 8);
+}
+
+#[gpui::test]
+async fn test_vcs_providers_in_untrusted_worktree(cx: &mut gpui::TestAppContext) {
+    use git::{blame::Blame, repository::GitFileStatus};
+    use std::sync::atomic::{AtomicUsize, Ordering::SeqCst};
+    use text::Rope;
+
+    struct FakeVcsProvider {
+        runs: Arc<AtomicUsize>,
+    }
+
+    impl VcsProvider for FakeVcsProvider {
+        fn name(&self) -> &'static str {
+            "Fake"
+        }
+
+        fn repository_root(&self, abs_path: &Path) -> Option<PathBuf> {
+            abs_path.starts_with("/dir").then(|| PathBuf::from("/dir"))
+        }
+
+        fn diff_base(&self, _: &Path, _: &Path) -> Result<Option<String>> {
+            self.runs.fetch_add(1, SeqCst);
+            Ok(Some("fn two() {}".to_string()))
+        }
+
+        fn status(&self, _: &Path, _: &Path) -> Result<Option<GitFileStatus>> {
+            self.runs.fetch_add(1, SeqCst);
+            Ok(Some(GitFileStatus::Modified))
+        }
+
+        fn blame(&self, _: &Path, _: &Path, _: &Rope) -> Result<Blame> {
+            self.runs.fetch_add(1, SeqCst);
+            Err(anyhow!("blame isn't supported"))
+        }
+    }
+
+    init_test(cx);
+    cx.update(|cx| {
+        SettingsStore::update_global(cx, |settings, cx| {
+            settings.update_user_settings::<ProjectSettings>(cx, |settings| {
+                settings.worktree_trust.enabled = true;
+            });
+        })
+    });
+    let runs = Arc::new(AtomicUsize::new(0));
+    cx.update(|cx| {
+        VcsProviderRegistry::default_global(cx)
+            .register_provider(Arc::new(FakeVcsProvider { runs: runs.clone() }))
+    });
+
+    let fs = FakeFs::new(cx.executor());
+    fs.insert_tree("/dir", json!({ "a.rs": "fn one() {}" }))
+        .await;
+    let project = Project::test(fs, ["/dir".as_ref()], cx).await;
+    let buffer = project
+        .update(cx, |project, cx| project.open_local_buffer("/dir/a.rs", cx))
+        .await
+        .unwrap();
+    cx.executor().run_until_parked();
+    let worktree_id = project.read_with(cx, |project, cx| {
+        project.worktrees().next().unwrap().read(cx).id()
+    });
+
+    // The version control system isn't run in an untrusted worktree.
+    assert!(project.read_with(cx, |project, cx| project
+        .blame_buffer_with_vcs_provider(&buffer, None, cx)
+        .is_none()));
+    assert_eq!(runs.load(SeqCst), 0);
+    buffer.read_with(cx, |buffer, _| assert!(buffer.diff_base().is_none()));
+    project.read_with(cx, |project, cx| {
+        assert_eq!(project.vcs_status(&buffer, cx), None)
+    });
+
+    // Trusting the worktree loads the diff base and the status of its buffers.
+    project.update(cx, |project, cx| project.trust_worktree(worktree_id, cx));
+    cx.executor().run_until_parked();
+    assert_eq!(runs.load(SeqCst), 2);
+    buffer.read_with(cx, |buffer, _| {
+        assert_eq!(
+            buffer.diff_base().map(|base| base.to_string()).as_deref(),
+            Some("fn two() {}")
+        )
+    });
+    project.read_with(cx, |project, cx| {
+        assert_eq!(
+            project.vcs_status(&buffer, cx),
+            Some(GitFileStatus::Modified)
+        )
+    });
+}
+
+#[gpui::test]
+async fn test_inline_values(cx: &mut gpui::TestAppContext) {
+    init_test(cx);
+
+    let fs = FakeFs::new(cx.executor());
+    fs.insert_tree("/dir", json!({ "a.rs": "let a = 1;\nlet b = a + 1;\n" }))
+        .await;
+
+    let project = Project::test(fs, ["/dir".as_ref()], cx).await;
+    let language_registry = project.read_with(cx, |project, _| project.languages().clone());
+    language_registry.add(rust_lang());
+    let mut fake_servers = language_registry.register_fake_lsp_adapter(
+        "Rust",
+        FakeLspAdapter {
+            capabilities: lsp::ServerCapabilities {
+                inline_value_provider: Some(OneOf::Left(true)),
+                ..Default::default()
+            },
+            ..Default::default()
+        },
+    );
+
+    let buffer = project
+        .update(cx, |project, cx| project.open_local_buffer("/dir/a.rs", cx))
+        .await
+        .unwrap();
+    let fake_server = fake_servers.next().await.unwrap();
+    fake_server.handle_request::<lsp::request::InlineValueRequest, _, _>(|params, _| async move {
+        assert_eq!(params.context.frame_id, 7);
+        assert_eq!(
+            params.context.stopped_location,
+            lsp::Range::new(lsp::Position::new(1, 0), lsp::Position::new(1, 14))
+        );
+        Ok(Some(vec![
+            lsp::InlineValue::Text(lsp::InlineValueText {
+                range: lsp::Range::new(lsp::Position::new(0, 4), lsp::Position::new(0, 5)),
+                text: "a = 1".to_string(),
+            }),
+            lsp::InlineValue::VariableLookup(lsp::InlineValueVariableLookup {
+                range: lsp::Range::new(lsp::Position::new(1, 8), lsp::Position::new(1, 9)),
+                variable_name: None,
+                case_sensitive_lookup: true,
+            }),
+        ]))
+    });
+
+    let values = project
+        .update(cx, |project, cx| {
+            project.inline_values(&buffer, 0..25, 7, 11..25, cx)
+        })
+        .await
+        .unwrap();
+    buffer.read_with(cx, |buffer, _| {
+        assert_eq!(
+            values
+                .iter()
+                .map(|value| (value.range.to_point(buffer), value.kind.clone()))
+                .collect::<Vec<_>>(),
+            [
+                (
+                    Point::new(0, 4)..Point::new(0, 5),
+                    InlineValueKind::Text("a = 1".to_string())
+                ),
+                (
+                    Point::new(1, 8)..Point::new(1, 9),
+                    InlineValueKind::VariableLookup {
+                        variable_name: None,
+                        case_sensitive: true
+                    }
+                ),
+            ]
+        );
+    });
+}
+
+#[gpui::test]
+async fn test_lsp_response_cache(cx: &mut gpui::TestAppContext) {
+    init_test(cx);
+    cx.update(|cx| {
+        SettingsStore::update_global(cx, |settings, cx| {
+            settings.update_user_settings::<ProjectSettings>(cx, |settings| {
+                settings.lsp_response_cache.enabled = true;
+            });
+        })
+    });
+
+    let fs = FakeFs::new(cx.executor());
+    fs.insert_tree(
+        "/dir",
+        json!({
+            "a.rs": "const fn a() { A }",
+            "b.rs": "const y: i32 = crate::a()",
+        }),
+    )
+    .await;
+
+    let project = Project::test(fs, ["/dir".as_ref()], cx).await;
+    let language_registry = project.read_with(cx, |project, _| project.languages().clone());
+    language_registry.add(rust_lang());
+    let mut fake_servers =
+        language_registry.register_fake_lsp_adapter("Rust", FakeLspAdapter::default());
+
+    let buffer = project
+        .update(cx, |project, cx| project.open_local_buffer("/dir/b.rs", cx))
+        .await
+        .unwrap();
+    let fake_server = fake_servers.next().await.unwrap();
+    cx.executor().run_until_parked();
+    let definition_requests = Arc::new(AtomicUsize::new(0));
+    fake_server.handle_request::<lsp::request::GotoDefinition, _, _>({
+        let definition_requests = definition_requests.clone();
+        move |_, _| {
+            let indexed = definition_requests.fetch_add(1, SeqCst) == 0;
+            async move {
+                Ok(indexed.then(|| {
+                    lsp::GotoDefinitionResponse::Scalar(lsp::Location::new(
+                        lsp::Url::from_file_path("/dir/a.rs").unwrap(),
+                        lsp::Range::new(lsp::Position::new(0, 9), lsp::Position::new(0, 10)),
+                    ))
+                }))
+            }
+        }
+    });
+    let target_ranges = |definitions: Vec<LocationLink>, cx: &mut gpui::TestAppContext| {
+        cx.update(|cx| {
+            definitions
+                .iter()
+                .map(|definition| {
+                    let target_buffer = definition.target.buffer.read(cx);
+                    (
+                        target_buffer.file().unwrap().path().to_path_buf(),
+                        definition.target.range.to_offset(target_buffer),
+                    )
+                })
+                .collect::<Vec<_>>()
+        })
+    };
+
+    // The definitions found by an idle server are cached.
+    let definitions = project
+        .update(cx, |project, cx| project.definition(&buffer, 22, cx))
+        .await
+        .unwrap();
+    cx.executor().run_until_parked();
+    assert_eq!(
+        target_ranges(definitions, cx),
+        [(PathBuf::from("a.rs"), 9..10)]
+    );
+
+    // While the server is indexing, the cached definitions are shown, although it doesn't find any yet.
+    fake_server.start_progress("indexing").await;
+    cx.executor().run_until_parked();
+    let definitions = project
+        .update(cx, |project, cx| project.definition(&buffer, 22, cx))
+        .await
+        .unwrap();
+    assert_eq!(
+        target_ranges(definitions, cx),
+        [(PathBuf::from("a.rs"), 9..10)]
+    );
+
+    // Once it's done indexing, its own responses are shown.
+    fake_server.end_progress("indexing");
+    cx.executor().run_until_parked();
+    let definitions = project
+        .update(cx, |project, cx| project.definition(&buffer, 22, cx))
+        .await
+        .unwrap();
+    assert!(definitions.is_empty());
+    assert_eq!(definition_requests.load(SeqCst), 3);
+}
+
+#[gpui::test]
+async fn test_pinned_language_server(cx: &mut gpui::TestAppContext) {
+    init_test(cx);
+
+    let fs = FakeFs::new(cx.executor());
+    fs.insert_tree(
+        "/dir",
+        json!({
+            "a.rs": "const fn a() { A }",
+            "b.rs": "const y: i32 = crate::a()",
+        }),
+    )
+    .await;
+
+    let project = Project::test(fs.clone(), ["/dir".as_ref()], cx).await;
+    let language_registry = project.read_with(cx, |project, _| project.languages().clone());
+    language_registry.add(rust_lang());
+    let mut primary_servers = language_registry.register_specific_fake_lsp_adapter(
+        "Rust",
+        true,
+        FakeLspAdapter {
+            name: "primary-server",
+            ..FakeLspAdapter::default()
+        },
+    );
+    let mut secondary_servers = language_registry.register_specific_fake_lsp_adapter(
+        "Rust",
+        false,
+        FakeLspAdapter {
+            name: "secondary-server",
+            ..FakeLspAdapter::default()
+        },
+    );
+
+    let buffer = project
+        .update(cx, |project, cx| project.open_local_buffer("/dir/b.rs", cx))
+        .await
+        .unwrap();
+    for (fake_server, column) in [
+        (primary_servers.next().await.unwrap(), 1),
+        (secondary_servers.next().await.unwrap(), 2),
+    ] {
+        fake_server.handle_request::<lsp::request::GotoDefinition, _, _>(move |_, _| async move {
+            Ok(Some(lsp::GotoDefinitionResponse::Scalar(
+                lsp::Location::new(
+                    lsp::Url::from_file_path("/dir/a.rs").unwrap(),
+                    lsp::Range::new(
+                        lsp::Position::new(0, column),
+                        lsp::Position::new(0, column + 1),
+                    ),
+                ),
+            )))
+        });
+    }
+    cx.executor().run_until_parked();
+
+    assert_eq!(definition_start(&project, &buffer, cx).await, 1);
+
+    project
+        .update(cx, |project, cx| {
+            project.pin_language_server_for_buffer(
+                &buffer,
+                Some(LanguageServerName("secondary-server".into())),
+                cx,
+            )
+        })
+        .await
+        .unwrap();
+    cx.executor().run_until_parked();
+    assert!(fs
+        .load("/dir/.zed/settings.json".as_ref())
+        .await
+        .unwrap()
+        .contains(r#""b.rs": "secondary-server""#));
+    project.read_with(cx, |project, cx| {
+        assert_eq!(
+            project.pinned_language_server_for_buffer(buffer.read(cx), cx),
+            Some(LanguageServerName("secondary-server".into()))
+        );
+    });
+    assert_eq!(definition_start(&project, &buffer, cx).await, 2);
+
+    project
+        .update(cx, |project, cx| {
+            project.pin_language_server_for_buffer(&buffer, None, cx)
+        })
+        .await
+        .unwrap();
+    cx.executor().run_until_parked();
+    assert_eq!(definition_start(&project, &buffer, cx).await, 1);
+
+    async fn definition_start(
+        project: &Model<Project>,
+        buffer: &Model<Buffer>,
+        cx: &mut gpui::TestAppContext,
+    ) -> usize {
+        let definitions = project
+            .update(cx, |project, cx| project.definition(buffer, 22, cx))
+            .await
+            .unwrap();
+        cx.update(|cx| {
+            definitions[0]
+                .target
+                .range
+                .start
+                .to_offset(definitions[0].target.buffer.read(cx))
+        })
+    }
+}
+
+#[gpui::test]
+async fn test_scoped_workspace_configuration(cx: &mut gpui::TestAppContext) {
+    init_test(cx);
+    cx.update(|cx| {
+        SettingsStore::update_global(cx, |settings, cx| {
+            settings.update_user_settings::<ProjectSettings>(cx, |settings| {
+                settings.lsp.insert(
+                    "the-server".into(),
+                    LspSettings {
+                        binary: None,
+                        roots: None,
+                        initialization_options: None,
+                        settings: Some(json!({ "rules": "recommended" })),
+                    },
+                );
+            });
+        })
+    });
+
+    let fs = FakeFs::new(cx.executor());
+    fs.insert_tree(
+        "/dir",
+        json!({
+            "packages": {
+                "a": {
+                    ".zed": {
+                        "settings.json": r#"{ "lsp": { "the-server": { "settings": { "rules": "strict" } } } }"#
+                    },
+                    "a.rs": "",
+                },
+                "b": { "b.rs": "" },
+            },
+        }),
+    )
+    .await;
+
+    let project = Project::test(fs, ["/dir".as_ref()], cx).await;
+    let language_registry = project.read_with(cx, |project, _| project.languages().clone());
+    language_registry.add(rust_lang());
+    let mut fake_servers = language_registry.register_fake_lsp_adapter(
+        "Rust",
+        FakeLspAdapter {
+            name: "the-server",
+            ..FakeLspAdapter::default()
+        },
+    );
+
+    let _buffer = project
+        .update(cx, |project, cx| {
+            project.open_local_buffer("/dir/packages/b/b.rs", cx)
+        })
+        .await
+        .unwrap();
+    let fake_server = fake_servers.next().await.unwrap();
+    cx.executor().run_until_parked();
+
+    let item = |scope: Option<&str>| lsp::ConfigurationItem {
+        scope_uri: scope.map(|scope| lsp::Url::from_file_path(scope).unwrap()),
+        section: Some("rules".to_string()),
+    };
+    let configs = fake_server
+        .request::<lsp::request::WorkspaceConfiguration>(lsp::ConfigurationParams {
+            items: vec![
+                item(Some("/dir/packages/a")),
+                item(Some("/dir/packages/a/a.rs")),
+                item(Some("/dir/packages/b")),
+                item(None),
+            ],
+        })
+        .await
+        .unwrap();
+    // The fake adapter doesn't configure the server from the user settings, so only the folder configured
+    // differently by its local settings gets them.
+    assert_eq!(
+        configs,
+        [json!("strict"), json!("strict"), json!(null), json!(null)]
+    );
+}
+
+#[gpui::test]
+async fn test_lsp_file_renames(cx: &mut gpui::TestAppContext) {
+    init_test(cx);
+
+    let fs = FakeFs::new(cx.executor());
+    fs.insert_tree(
+        "/dir",
+        json!({ "a.rs": "mod old;", "old.rs": "", "notes.txt": "" }),
+    )
+    .await;
+
+    let project = Project::test(fs, ["/dir".as_ref()], cx).await;
+    let language_registry = project.read_with(cx, |project, _| project.languages().clone());
+    language_registry.add(rust_lang());
+    let rust_files = lsp::FileOperationRegistrationOptions {
+        filters: vec![lsp::FileOperationFilter {
+            scheme: Some("file".into()),
+            pattern: lsp::FileOperationPattern {
+                glob: "**/*.rs".into(),
+                matches: Some(lsp::FileOperationPatternKind::File),
+                options: None,
+            },
+        }],
+    };
+    let mut fake_servers = language_registry.register_fake_lsp_adapter(
+        "Rust",
+        FakeLspAdapter {
+            capabilities: lsp::ServerCapabilities {
+                workspace: Some(lsp::WorkspaceServerCapabilities {
+                    workspace_folders: None,
+                    file_operations: Some(lsp::WorkspaceFileOperationsServerCapabilities {
+                        will_rename: Some(rust_files.clone()),
+                        did_rename: Some(rust_files),
+                        ..Default::default()
+                    }),
+                }),
+                ..Default::default()
+            },
+            ..Default::default()
+        },
+    );
+
+    let buffer = project
+        .update(cx, |project, cx| project.open_local_buffer("/dir/a.rs", cx))
+        .await
+        .unwrap();
+    let mut fake_server = fake_servers.next().await.unwrap();
+    cx.executor().run_until_parked();
+    let will_rename_requests = Arc::new(Mutex::new(0));
+    fake_server.handle_request::<lsp::request::WillRenameFiles, _, _>({
+        let will_rename_requests = will_rename_requests.clone();
+        move |params, _| {
+            *will_rename_requests.lock() += 1;
+            async move {
+                assert_eq!(params.files[0].old_uri, "file:///dir/old.rs");
+                assert_eq!(params.files[0].new_uri, "file:///dir/new.rs");
+                Ok(Some(lsp::WorkspaceEdit {
+                    changes: Some(
+                        [(
+                            lsp::Url::from_file_path("/dir/a.rs").unwrap(),
+                            vec![lsp::TextEdit::new(
+                                lsp::Range::new(lsp::Position::new(0, 4), lsp::Position::new(0, 7)),
+                                "new".into(),
+                            )],
+                        )]
+                        .into_iter()
+                        .collect(),
+                    ),
+                    ..Default::default()
+                }))
+            }
+        }
+    });
+
+    let entry_id = |path: &'static str, cx: &mut gpui::TestAppContext| {
+        project.update(cx, |project, cx| {
+            let worktree = project.worktrees().next().unwrap();
+            worktree.read(cx).entry_for_path(path).unwrap().id
+        })
+    };
+
+    // The edits of the server are applied once the user confirms them, before the file is renamed.
+    let mut events = cx.events(&project);
+    let old_id = entry_id("old.rs", cx);
+    let rename = project.update(cx, |project, cx| {
+        project.rename_entry(old_id, Path::new("new.rs"), cx)
+    });
+    let prompt = loop {
+        if let Event::LanguageServerPrompt(prompt) = events.next().await.unwrap() {
+            break prompt;
+        }
+    };
+    assert!(prompt.message.contains("1 file(s)"), "{}", prompt.message);
+    assert_eq!(prompt.actions[0].title, "Apply");
+    prompt.respond(0).await.unwrap();
+    rename.await.unwrap();
+    buffer.update(cx, |buffer, _| assert_eq!(buffer.text(), "mod new;"));
+    let did_rename = fake_server
+        .receive_notification::<lsp::notification::DidRenameFiles>()
+        .await;
+    assert_eq!(did_rename.files[0].new_uri, "file:///dir/new.rs");
+
+    // Files the server isn't interested in are renamed without asking it.
+    let notes_id = entry_id("notes.txt", cx);
+    project
+        .update(cx, |project, cx| {
+            project.rename_entry(notes_id, Path::new("todo.txt"), cx)
+        })
+        .await
+        .unwrap();
+    assert_eq!(*will_rename_requests.lock(), 1);
+}
+
+#[gpui::test]
+async fn test_lsp_file_creates_and_deletes(cx: &mut gpui::TestAppContext) {
+    init_test(cx);
+
+    let fs = FakeFs::new(cx.executor());
+    fs.insert_tree(
+        "/dir",
+        json!({ "lib.rs": "mod a;\nmod b;\n", "a.rs": "", "b.rs": "", "notes.txt": "" }),
+    )
+    .await;
+
+    let project = Project::test(fs, ["/dir".as_ref()], cx).await;
+    let language_registry = project.read_with(cx, |project, _| project.languages().clone());
+    language_registry.add(rust_lang());
+    let rust_files = lsp::FileOperationRegistrationOptions {
+        filters: vec![lsp::FileOperationFilter {
+            scheme: Some("file".into()),
+            pattern: lsp::FileOperationPattern {
+                glob: "**/*.rs".into(),
+                matches: Some(lsp::FileOperationPatternKind::File),
+                options: None,
+            },
+        }],
+    };
+    let mut fake_servers = language_registry.register_fake_lsp_adapter(
+        "Rust",
+        FakeLspAdapter {
+            capabilities: lsp::ServerCapabilities {
+                workspace: Some(lsp::WorkspaceServerCapabilities {
+                    workspace_folders: None,
+                    file_operations: Some(lsp::WorkspaceFileOperationsServerCapabilities {
+                        will_create: Some(rust_files.clone()),
+                        did_create: Some(rust_files.clone()),
+                        will_delete: Some(rust_files.clone()),
+                        did_delete: Some(rust_files),
+                        ..Default::default()
+                    }),
+                }),
+                ..Default::default()
+            },
+            ..Default::default()
+        },
+    );
+
+    let buffer = project
+        .update(cx, |project, cx| {
+            project.open_local_buffer("/dir/lib.rs", cx)
+        })
+        .await
+        .unwrap();
+    let mut fake_server = fake_servers.next().await.unwrap();
+    cx.executor().run_until_parked();
+    let lib_uri = lsp::Url::from_file_path("/dir/lib.rs").unwrap();
+    fake_server.handle_request::<lsp::request::WillCreateFiles, _, _>({
+        let lib_uri = lib_uri.clone();
+        move |params, _| {
+            let lib_uri = lib_uri.clone();
+            async move {
+                assert_eq!(params.files.len(), 1);
+                assert_eq!(params.files[0].uri, "file:///dir/c.rs");
+                Ok(Some(lsp::WorkspaceEdit {
+                    changes: Some(
+                        [(
+                            lib_uri,
+                            vec![lsp::TextEdit::new(
+                                lsp::Range::new(lsp::Position::new(2, 0), lsp::Position::new(2, 0)),
+                                "mod c;\n".into(),
+                            )],
+                        )]
+                        .into_iter()
+                        .collect(),
+                    ),
+                    ..Default::default()
+                }))
+            }
+        }
+    });
+    fake_server.handle_request::<lsp::request::WillDeleteFiles, _, _>(move |params, _| {
+        let lib_uri = lib_uri.clone();
+        async move {
+            // The files the server isn't interested in aren't sent to it.
+            let uris = params
+                .files
+                .iter()
+                .map(|file| file.uri.as_str())
+                .collect::<Vec<_>>();
+            assert_eq!(uris, ["file:///dir/a.rs", "file:///dir/b.rs"]);
+            Ok(Some(lsp::WorkspaceEdit {
+                changes: Some(
+                    [(
+                        lib_uri,
+                        vec![lsp::TextEdit::new(
+                            lsp::Range::new(lsp::Position::new(0, 0), lsp::Position::new(2, 0)),
+                            "".into(),
+                        )],
+                    )]
+                    .into_iter()
+                    .collect(),
+                ),
+                ..Default::default()
+            }))
+        }
+    });
+
+    // The edits of the server are applied once the user confirms them, before the file is created.
+    let mut events = cx.events(&project);
+    let worktree_id = project.read_with(cx, |project, cx| {
+        project.worktrees().next().unwrap().read(cx).id()
+    });
+    let create = project.update(cx, |project, cx| {
+        project.create_entry((worktree_id, Path::new("c.rs")), false, cx)
+    });
+    let prompt = loop {
+        if let Event::LanguageServerPrompt(prompt) = events.next().await.unwrap() {
+            break prompt;
+        }
+    };
+    assert!(
+        prompt.message.contains("the creation of /dir/c.rs"),
+        "{}",
+        prompt.message
+    );
+    prompt.respond(0).await.unwrap();
+    create.await.unwrap();
+    buffer.update(cx, |buffer, _| {
+        assert_eq!(buffer.text(), "mod a;\nmod b;\nmod c;\n")
+    });
+    let did_create = fake_server
+        .receive_notification::<lsp::notification::DidCreateFiles>()
+        .await;
+    assert_eq!(did_create.files[0].uri, "file:///dir/c.rs");
+
+    // The server is asked for the edits of the deletion of several entries at once.
+    let entry_ids = project.read_with(cx, |project, cx| {
+        let worktree = project.worktrees().next().unwrap();
+        ["a.rs", "b.rs", "notes.txt"]
+            .into_iter()
+            .map(|path| worktree.read(cx).entry_for_path(path).unwrap().id)
+            .collect::<Vec<_>>()
+    });
+    let delete = project.update(cx, |project, cx| {
+        project.delete_entries(entry_ids, false, cx)
+    });
+    let prompt = loop {
+        if let Event::LanguageServerPrompt(prompt) = events.next().await.unwrap() {
+            break prompt;
+        }
+    };
+    assert!(
+        prompt.message.contains("the deletion of 3 files"),
+        "{}",
+        prompt.message
+    );
+    prompt.respond(0).await.unwrap();
+    delete.await.unwrap();
+    buffer.update(cx, |buffer, _| assert_eq!(buffer.text(), "mod c;\n"));
+    let did_delete = fake_server
+        .receive_notification::<lsp::notification::DidDeleteFiles>()
+        .await;
+    assert_eq!(did_delete.files.len(), 2);
+    project.read_with(cx, |project, cx| {
+        let worktree = project.worktrees().next().unwrap();
+        assert!(worktree.read(cx).entry_for_path("notes.txt").is_none());
+    });
+}
+
+#[gpui::test]
+async fn test_lsp_show_document(cx: &mut gpui::TestAppContext) {
+    init_test(cx);
+
+    let fs = FakeFs::new(cx.executor());
+    fs.insert_tree(
+        "/dir",
+        json!({ "a.rs": "fn main() {}", "b.rs": "fn one() {}\nfn two() {}\n" }),
+    )
+    .await;
+
+    let project = Project::test(fs, ["/dir".as_ref()], cx).await;
+    let language_registry = project.read_with(cx, |project, _| project.languages().clone());
+    language_registry.add(rust_lang());
+    let mut fake_servers = language_registry.register_fake_lsp_adapter("Rust", Default::default());
+    let _buffer = project
+        .update(cx, |project, cx| project.open_local_buffer("/dir/a.rs", cx))
+        .await
+        .unwrap();
+    let fake_server = fake_servers.next().await.unwrap();
+    cx.executor().run_until_parked();
+
+    let mut events = cx.events(&project);
+    let result = fake_server
+        .request::<lsp::request::ShowDocument>(lsp::ShowDocumentParams {
+            uri: lsp::Url::from_file_path("/dir/b.rs").unwrap(),
+            external: None,
+            take_focus: Some(true),
+            selection: Some(lsp::Range::new(
+                lsp::Position::new(1, 3),
+                lsp::Position::new(1, 6),
+            )),
+        })
+        .await
+        .unwrap();
+    assert!(result.success);
+    let (buffer, selection, take_focus) = loop {
+        if let Event::ShowDocument {
+            buffer,
+            selection,
+            take_focus,
+        } = events.next().await.unwrap()
+        {
+            break (buffer, selection, take_focus);
+        }
+    };
+    assert!(take_focus);
+    buffer.read_with(cx, |buffer, cx| {
+        assert_eq!(buffer.file().unwrap().full_path(cx), Path::new("dir/b.rs"));
+        let selection = selection.unwrap().to_offset(buffer);
+        assert_eq!(&buffer.text()[selection], "two");
+    });
+
+    // Web pages are opened in the browser once the user agrees to it.
+    let (result, ()) = futures::join!(
+        fake_server.request::<lsp::request::ShowDocument>(lsp::ShowDocumentParams {
+            uri: lsp::Url::parse("https://docs.rs/").unwrap(),
+            external: Some(true),
+            take_focus: None,
+            selection: None,
+        }),
+        async {
+            let prompt = loop {
+                if let Event::LanguageServerPrompt(prompt) = events.next().await.unwrap() {
+                    break prompt;
+                }
+            };
+            assert_eq!(
+                prompt
+                    .actions
+                    .iter()
+                    .map(|action| action.title.as_str())
+                    .collect::<Vec<_>>(),
+                ["Open", "Cancel"]
+            );
+            prompt.respond(0).await.unwrap();
+        }
+    );
+    assert!(result.unwrap().success);
+    assert_eq!(cx.opened_url().as_deref(), Some("https://docs.rs/"));
+
+    // Other external documents aren't opened.
+    let result = fake_server
+        .request::<lsp::request::ShowDocument>(lsp::ShowDocumentParams {
+            uri: lsp::Url::parse("file:///usr/bin/true").unwrap(),
+            external: Some(true),
+            take_focus: None,
+            selection: None,
+        })
+        .await
+        .unwrap();
+    assert!(!result.success);
+    assert_eq!(cx.opened_url().as_deref(), Some("https://docs.rs/"));
+}
+
+#[gpui::test]
+async fn test_code_index_fallback(cx: &mut gpui::TestAppContext) {
+    init_test(cx);
+
+    let lsif = [
+        json!({ "id": 1, "type": "vertex", "label": "document", "uri": "file:///dir/a.rs" }),
+        json!({ "id": 2, "type": "vertex", "label": "document", "uri": "file:///dir/b.rs" }),
+        json!({ "id": 3, "type": "vertex", "label": "range", "start": { "line": 0, "character": 12 }, "end": { "line": 0, "character": 18 } }),
+        json!({ "id": 4, "type": "vertex", "label": "range", "start": { "line": 0, "character": 3 }, "end": { "line": 0, "character": 9 } }),
+        json!({ "id": 5, "type": "edge", "label": "contains", "outV": 1, "inVs": [3] }),
+        json!({ "id": 6, "type": "edge", "label": "contains", "outV": 2, "inVs": [4] }),
+        json!({ "id": 7, "type": "vertex", "label": "resultSet" }),
+        json!({ "id": 8, "type": "edge", "label": "next", "outV": 3, "inV": 7 }),
+        json!({ "id": 9, "type": "edge", "label": "next", "outV": 4, "inV": 7 }),
+        json!({ "id": 10, "type": "vertex", "label": "definitionResult" }),
+        json!({ "id": 11, "type": "edge", "label": "textDocument/definition", "outV": 7, "inV": 10 }),
+        json!({ "id": 12, "type": "edge", "label": "item", "outV": 10, "inVs": [4], "document": 2 }),
+    ]
+    .iter()
+    .map(|element| element.to_string())
+    .collect::<Vec<_>>()
+    .join("\n");
+    let fs = FakeFs::new(cx.executor());
+    fs.insert_tree(
+        "/dir",
+        json!({
+            ".zed": { "settings.json": r#"{ "code_indexes": ["dump.lsif", "index.scip"] }"# },
+            "a.rs": "fn main() { helper(); }",
+            "b.rs": "fn helper() {}",
+            "dump.lsif": lsif,
+        }),
+    )
+    .await;
+    let scip = code_index::scip::Index {
+        metadata: None,
+        documents: vec![code_index::scip::Document {
+            relative_path: "b.rs".into(),
+            occurrences: vec![code_index::scip::Occurrence {
+                range: vec![0, 3, 9],
+                symbol: "rust . . helper().".into(),
+                symbol_roles: code_index::scip::SYMBOL_ROLE_DEFINITION,
+            }],
+        }],
+    };
+    fs.insert_file("/dir/index.scip", prost::Message::encode_to_vec(&scip))
+        .await;
+
+    let project = Project::test(fs, ["/dir".as_ref()], cx).await;
+    cx.executor().run_until_parked();
+    let buffer_a = project
+        .update(cx, |project, cx| project.open_local_buffer("/dir/a.rs", cx))
+        .await
+        .unwrap();
+    let buffer_b = project
+        .update(cx, |project, cx| project.open_local_buffer("/dir/b.rs", cx))
+        .await
+        .unwrap();
+
+    // Without language servers, the definition is taken from the LSIF dump.
+    let definitions = project
+        .update(cx, |project, cx| project.definition(&buffer_a, 14, cx))
+        .await
+        .unwrap();
+    assert_eq!(definitions.len(), 1);
+    cx.update(|cx| {
+        let origin = definitions[0].origin.as_ref().unwrap();
+        let origin_range = origin.range.to_offset(origin.buffer.read(cx));
+        assert_eq!(origin_range, 12..18);
+
+        let target = &definitions[0].target;
+        assert_eq!(target.buffer, buffer_b);
+        assert_eq!(target.range.to_offset(target.buffer.read(cx)), 3..9);
+    });
+
+    // The references of the symbol are missing from the LSIF dump, so they are taken from the SCIP index.
+    let references = project
+        .update(cx, |project, cx| project.references(&buffer_b, 5, cx))
+        .await
+        .unwrap();
+    assert_eq!(references.len(), 1);
+    cx.update(|cx| {
+        assert_eq!(references[0].buffer, buffer_b);
+        assert_eq!(references[0].range.to_offset(buffer_b.read(cx)), 3..9);
+    });
+}
+
+#[gpui::test]
+async fn test_edits_from_lsp2_with_past_version(cx: &mut gpui::TestAppContext) {
+    init_test(cx);
+
+    let text = "
+        fn a() {
+            f1();
+        }
+        fn b() {
+            f2();
+        }
+        fn c() {
+            f3();
+        }
+    "
+    .unindent();
+
+    let fs = FakeFs::new(cx.executor());
+    fs.insert_tree(
+        "/dir",
+        json!({
+            "a.rs": text.clone(),
+        }),
+    )
+    .await;
+
+    let project = Project::test(fs, ["/dir".as_ref()], cx).await;
+
+    let language_registry = project.read_with(cx, |project, _| project.languages().clone());
+    language_registry.add(rust_lang());
+    let mut fake_servers =
+        language_registry.register_fake_lsp_adapter("Rust", FakeLspAdapter::default());
+
+    let buffer = project
+        .update(cx, |project, cx| project.open_local_buffer("/dir/a.rs", cx))
+        .await
+        .unwrap();
+
+    let mut fake_server = fake_servers.next().await.unwrap();
+    let lsp_document_version = fake_server
+        .receive_notification::<lsp::notification::DidOpenTextDocument>()
+        .await
+        .text_document
+        .version;
+
+    // Simulate editing the buffer after the language server computes some edits.
+    buffer.update(cx, |buffer, cx| {
+        buffer.edit(
+            [(
+                Point::new(0, 0)..Point::new(0, 0),
+                "// above first function\n",
+            )],
+            None,
+            cx,
+        );
+        buffer.edit(
+            [(
+                Point::new(2, 0)..Point::new(2, 0),
+                "    // inside first function\n",
+            )],
+            None,
+            cx,
+        );
+        buffer.edit(
+            [(
+                Point::new(6, 4)..Point::new(6, 4),
+                "// inside second function ",
+            )],
+            None,
+            cx,
+        );
+
+        assert_eq!(
+            buffer.text(),
+            "
+                // above first function
+                fn a() {
+                    // inside first function
+                    f1();
+                }
+                fn b() {
+                    // inside second function f2();
+                }
+                fn c() {
+                    f3();
+                }
+            "
+            .unindent()
+        );
+    });
+
+    let edits = project
+        .update(cx, |project, cx| {
+            project.edits_from_lsp(
+                &buffer,
+                vec![
+                    // replace body of first function
+                    lsp::TextEdit {
+                        range: lsp::Range::new(lsp::Position::new(0, 0), lsp::Position::new(3, 0)),
+                        new_text: "
+                            fn a() {
+                                f10();
+                            }
+                            "
+                        .unindent(),
+                    },
+                    // edit inside second function
+                    lsp::TextEdit {
+                        range: lsp::Range::new(lsp::Position::new(4, 6), lsp::Position::new(4, 6)),
+                        new_text: "00".into(),
+                    },
+                    // edit inside third function via two distinct edits
+                    lsp::TextEdit {
+                        range: lsp::Range::new(lsp::Position::new(7, 5), lsp::Position::new(7, 5)),
+                        new_text: "4000".into(),
+                    },
+                    lsp::TextEdit {
+                        range: lsp::Range::new(lsp::Position::new(7, 5), lsp::Position::new(7, 6)),
+                        new_text: "".into(),
+                    },
+                ],
+                LanguageServerId(0),
+                Some(lsp_document_version),
+                cx,
+            )
+        })
+        .await
+        .unwrap();
+
+    buffer.update(cx, |buffer, cx| {
+        for (range, new_text) in edits {
+            buffer.edit([(range, new_text)], None, cx);
+        }
+        assert_eq!(
+            buffer.text(),
+            "
+                // above first function
+                fn a() {
+                    // inside first function
+                    f10();
+                }
+                fn b() {
+                    // inside second function f200();
+                }
+                fn c() {
+                    f4000();
+                }
+                "
+            .unindent()
+        );
+    });
+}
+
+#[gpui::test]
+async fn test_edits_from_lsp2_with_edits_on_adjacent_lines(cx: &mut gpui::TestAppContext) {
+    init_test(cx);
+
+    let text = "
+        use a::b;
+        use a::c;
+
+        fn f() {
+            b();
+            c();
+        }
+    "
+    .unindent();
+
+    let fs = FakeFs::new(cx.executor());
+    fs.insert_tree(
+        "/dir",
+        json!({
+            "a.rs": text.clone(),
+        }),
+    )
+    .await;
+
+    let project = Project::test(fs, ["/dir".as_ref()], cx).await;
+    let buffer = project
+        .update(cx, |project, cx| project.open_local_buffer("/dir/a.rs", cx))
+        .await
+        .unwrap();
+
+    // Simulate the language server sending us a small edit in the form of a very large diff.
+    // Rust-analyzer does this when performing a merge-imports code action.
+    let edits = project
+        .update(cx, |project, cx| {
+            project.edits_from_lsp(
+                &buffer,
+                [
+                    // Replace the first use statement without editing the semicolon.
+                    lsp::TextEdit {
+                        range: lsp::Range::new(lsp::Position::new(0, 4), lsp::Position::new(0, 8)),
+                        new_text: "a::{b, c}".into(),
+                    },
+                    // Reinsert the remainder of the file between the semicolon and the final
+                    // newline of the file.
+                    lsp::TextEdit {
+                        range: lsp::Range::new(lsp::Position::new(0, 9), lsp::Position::new(0, 9)),
+                        new_text: "\n\n".into(),
+                    },
+                    lsp::TextEdit {
+                        range: lsp::Range::new(lsp::Position::new(0, 9), lsp::Position::new(0, 9)),
+                        new_text: "
+                            fn f() {
+                                b();
+                                c();
+                            }"
+                        .unindent(),
+                    },
+                    // Delete everything after the first newline of the file.
+                    lsp::TextEdit {
+                        range: lsp::Range::new(lsp::Position::new(1, 0), lsp::Position::new(7, 0)),
+                        new_text: "".into(),
+                    },
+                ],
+                LanguageServerId(0),
+                None,
+                cx,
+            )
+        })
+        .await
+        .unwrap();
+
+    buffer.update(cx, |buffer, cx| {
+        let edits = edits
+            .into_iter()
+            .map(|(range, text)| {
+                (
+                    range.start.to_point(buffer)..range.end.to_point(buffer),
+                    text,
+                )
+            })
+            .collect::<Vec<_>>();
+
+        assert_eq!(
+            edits,
+            [
+                (Point::new(0, 4)..Point::new(0, 8), "a::{b, c}".into()),
+                (Point::new(1, 0)..Point::new(2, 0), "".into())
+            ]
+        );
+
+        for (range, new_text) in edits {
+            buffer.edit([(range, new_text)], None, cx);
+        }
+        assert_eq!(
+            buffer.text(),
+            "
+                use a::{b, c};
+
+                fn f() {
+                    b();
+                    c();
+                }
+            "
+            .unindent()
+        );
+    });
+}
+
+#[gpui::test]
+async fn test_invalid_edits_from_lsp2(cx: &mut gpui::TestAppContext) {
+    init_test(cx);
+
+    let text = "
+        use a::b;
+        use a::c;
+
+        fn f() {
+            b();
+            c();
+        }
+    "
+    .unindent();
+
+    let fs = FakeFs::new(cx.executor());
+    fs.insert_tree(
+        "/dir",
+        json!({
+            "a.rs": text.clone(),
+        }),
+    )
+    .await;
+
+    let project = Project::test(fs, ["/dir".as_ref()], cx).await;
+    let buffer = project
+        .update(cx, |project, cx| project.open_local_buffer("/dir/a.rs", cx))
+        .await
+        .unwrap();
+
+    // Simulate the language server sending us edits in a non-ordered fashion,
+    // with ranges sometimes being inverted or pointing to invalid locations.
+    let edits = project
+        .update(cx, |project, cx| {
             project.edits_from_lsp(
                 &buffer,
-                vec![
-                    // replace body of first function
-                    lsp::TextEdit {
-                        range: lsp::Range::new(lsp::Position::new(0, 0), lsp::Position::new(3, 0)),
-                        new_text: "
-                            fn a() {
-                                f10();
-                            }
-                            "
-                        .unindent(),
-                    },
-                    // edit inside second function
-                    lsp::TextEdit {
-                        range: lsp::Range::new(lsp::Position::new(4, 6), lsp::Position::new(4, 6)),
-                        new_text: "00".into(),
-                    },
-                    // edit inside third function via two distinct edits
-                    lsp::TextEdit {
-                        range: lsp::Range::new(lsp::Position::new(7, 5), lsp::Position::new(7, 5)),
-                        new_text: "4000".into(),
-                    },
-                    lsp::TextEdit {
-                        range: lsp::Range::new(lsp::Position::new(7, 5), lsp::Position::new(7, 6)),
-                        new_text: "".into(),
-                    },
-                ],
-                LanguageServerId(0),
-                Some(lsp_document_version),
+                [
+                    lsp::TextEdit {
+                        range: lsp::Range::new(lsp::Position::new(0, 9), lsp::Position::new(0, 9)),
+                        new_text: "\n\n".into(),
+                    },
+                    lsp::TextEdit {
+                        range: lsp::Range::new(lsp::Position::new(0, 8), lsp::Position::new(0, 4)),
+                        new_text: "a::{b, c}".into(),
+                    },
+                    lsp::TextEdit {
+                        range: lsp::Range::new(lsp::Position::new(1, 0), lsp::Position::new(99, 0)),
+                        new_text: "".into(),
+                    },
+                    lsp::TextEdit {
+                        range: lsp::Range::new(lsp::Position::new(0, 9), lsp::Position::new(0, 9)),
+                        new_text: "
+                            fn f() {
+                                b();
+                                c();
+                            }"
+                        .unindent(),
+                    },
+                ],
+                LanguageServerId(0),
+                None,
+                cx,
+            )
+        })
+        .await
+        .unwrap();
+
+    buffer.update(cx, |buffer, cx| {
+        let edits = edits
+            .into_iter()
+            .map(|(range, text)| {
+                (
+                    range.start.to_point(buffer)..range.end.to_point(buffer),
+                    text,
+                )
+            })
+            .collect::<Vec<_>>();
+
+        assert_eq!(
+            edits,
+            [
+                (Point::new(0, 4)..Point::new(0, 8), "a::{b, c}".into()),
+                (Point::new(1, 0)..Point::new(2, 0), "".into())
+            ]
+        );
+
+        for (range, new_text) in edits {
+            buffer.edit([(range, new_text)], None, cx);
+        }
+        assert_eq!(
+            buffer.text(),
+            "
+                use a::{b, c};
+
+                fn f() {
+                    b();
+                    c();
+                }
+            "
+            .unindent()
+        );
+    });
+}
+
+fn chunks_with_diagnostics<T: ToOffset + ToPoint>(
+    buffer: &Buffer,
+    range: Range<T>,
+) -> Vec<(String, Option<DiagnosticSeverity>)> {
+    let mut chunks: Vec<(String, Option<DiagnosticSeverity>)> = Vec::new();
+    for chunk in buffer.snapshot().chunks(range, true) {
+        if chunks.last().map_or(false, |prev_chunk| {
+            prev_chunk.1 == chunk.diagnostic_severity
+        }) {
+            chunks.last_mut().unwrap().0.push_str(chunk.text);
+        } else {
+            chunks.push((chunk.text.to_string(), chunk.diagnostic_severity));
+        }
+    }
+    chunks
+}
+
+#[gpui::test(iterations = 10)]
+async fn test_definition(cx: &mut gpui::TestAppContext) {
+    init_test(cx);
+
+    let fs = FakeFs::new(cx.executor());
+    fs.insert_tree(
+        "/dir",
+        json!({
+            "a.rs": "const fn a() { A }",
+            "b.rs": "const y: i32 = crate::a()",
+        }),
+    )
+    .await;
+
+    let project = Project::test(fs, ["/dir/b.rs".as_ref()], cx).await;
+
+    let language_registry = project.read_with(cx, |project, _| project.languages().clone());
+    language_registry.add(rust_lang());
+    let mut fake_servers =
+        language_registry.register_fake_lsp_adapter("Rust", FakeLspAdapter::default());
+
+    let buffer = project
+        .update(cx, |project, cx| project.open_local_buffer("/dir/b.rs", cx))
+        .await
+        .unwrap();
+
+    let fake_server = fake_servers.next().await.unwrap();
+    fake_server.handle_request::<lsp::request::GotoDefinition, _, _>(|params, _| async move {
+        let params = params.text_document_position_params;
+        assert_eq!(
+            params.text_document.uri.to_file_path().unwrap(),
+            Path::new("/dir/b.rs"),
+        );
+        assert_eq!(params.position, lsp::Position::new(0, 22));
+
+        Ok(Some(lsp::GotoDefinitionResponse::Scalar(
+            lsp::Location::new(
+                lsp::Url::from_file_path("/dir/a.rs").unwrap(),
+                lsp::Range::new(lsp::Position::new(0, 9), lsp::Position::new(0, 10)),
+            ),
+        )))
+    });
+
+    let mut definitions = project
+        .update(cx, |project, cx| project.definition(&buffer, 22, cx))
+        .await
+        .unwrap();
+
+    // Assert no new language server started
+    cx.executor().run_until_parked();
+    assert!(fake_servers.try_next().is_err());
+
+    assert_eq!(definitions.len(), 1);
+    let definition = definitions.pop().unwrap();
+    cx.update(|cx| {
+        let target_buffer = definition.target.buffer.read(cx);
+        assert_eq!(
+            target_buffer
+                .file()
+                .unwrap()
+                .as_local()
+                .unwrap()
+                .abs_path(cx),
+            Path::new("/dir/a.rs"),
+        );
+        assert_eq!(definition.target.range.to_offset(target_buffer), 9..10);
+        assert_eq!(
+            list_worktrees(&project, cx),
+            [("/dir/a.rs".as_ref(), false), ("/dir/b.rs".as_ref(), true)],
+        );
+
+        drop(definition);
+    });
+    cx.update(|cx| {
+        assert_eq!(list_worktrees(&project, cx), [("/dir/b.rs".as_ref(), true)]);
+    });
+
+    fn list_worktrees<'a>(
+        project: &'a Model<Project>,
+        cx: &'a AppContext,
+    ) -> Vec<(&'a Path, bool)> {
+        project
+            .read(cx)
+            .worktrees()
+            .map(|worktree| {
+                let worktree = worktree.read(cx);
+                (
+                    worktree.as_local().unwrap().abs_path().as_ref(),
+                    worktree.is_visible(),
+                )
+            })
+            .collect::<Vec<_>>()
+    }
+}
+
+#[gpui::test]
+async fn test_definition_in_virtual_document(cx: &mut gpui::TestAppContext) {
+    init_test(cx);
+
+    let fs = FakeFs::new(cx.executor());
+    fs.insert_tree(
+        "/dir",
+        json!({
+            "b.rs": "const y: i32 = crate::a()",
+        }),
+    )
+    .await;
+
+    let project = Project::test(fs, ["/dir/b.rs".as_ref()], cx).await;
+
+    let language_registry = project.read_with(cx, |project, _| project.languages().clone());
+    language_registry.add(rust_lang());
+    let mut fake_servers =
+        language_registry.register_fake_lsp_adapter("Rust", FakeLspAdapter::default());
+
+    let buffer = project
+        .update(cx, |project, cx| project.open_local_buffer("/dir/b.rs", cx))
+        .await
+        .unwrap();
+
+    let class_uri = "jdt://contents/rt.jar/crate/A.class";
+    let fake_server = fake_servers.next().await.unwrap();
+    fake_server.handle_request::<lsp::request::GotoDefinition, _, _>(move |_, _| async move {
+        Ok(Some(lsp::GotoDefinitionResponse::Scalar(
+            lsp::Location::new(
+                lsp::Url::parse(class_uri).unwrap(),
+                lsp::Range::new(lsp::Position::new(0, 9), lsp::Position::new(0, 10)),
+            ),
+        )))
+    });
+    let mut content_requests = fake_server
+        .handle_request::<crate::virtual_documents::ClassFileContents, _, _>(
+            move |params, _| async move {
+                assert_eq!(params.uri.as_str(), class_uri);
+                Ok(Some("const fn a() { A }".to_string()))
+            },
+        );
+
+    // The contents of the virtual document are requested from the server once, and its buffer is reused.
+    for _ in 0..2 {
+        let mut definitions = project
+            .update(cx, |project, cx| project.definition(&buffer, 22, cx))
+            .await
+            .unwrap();
+        assert_eq!(definitions.len(), 1);
+        let definition = definitions.pop().unwrap();
+        cx.update(|cx| {
+            let target_buffer = definition.target.buffer.read(cx);
+            assert_eq!(target_buffer.text(), "const fn a() { A }");
+            assert!(target_buffer.file().is_none());
+            assert_eq!(target_buffer.capability(), language::Capability::ReadOnly);
+            assert_eq!(target_buffer.language().unwrap().name().as_ref(), "Rust");
+            assert_eq!(definition.target.range.to_offset(target_buffer), 9..10);
+        });
+    }
+    cx.executor().run_until_parked();
+    assert!(content_requests.next().await.is_some());
+    assert!(content_requests.try_next().is_err());
+}
+#[gpui::test]
+async fn test_symbol_index_without_language_servers(cx: &mut gpui::TestAppContext) {
+    init_test(cx);
+
+    let fs = FakeFs::new(cx.executor());
+    fs.insert_tree(
+        "/dir",
+        json!({
+            "a.rs": "fn main() {\n    helper();\n}\n",
+            "b.rs": "struct Config;\n\nfn helper() {}\n",
+        }),
+    )
+    .await;
+
+    let project = Project::test(fs.clone(), ["/dir".as_ref()], cx).await;
+    let language_registry = project.read_with(cx, |project, _| project.languages().clone());
+    language_registry.add(Arc::new(
+        Arc::try_unwrap(rust_lang())
+            .unwrap()
+            .with_outline_query(
+                r#"
+                (struct_item "struct" @context name: (_) @name) @item
+                (function_item "fn" @context name: (_) @name) @item
+                "#,
+            )
+            .unwrap(),
+    ));
+    cx.executor().run_until_parked();
+
+    let symbols = project
+        .update(cx, |project, cx| project.symbols("hel", cx))
+        .await
+        .unwrap();
+    assert_eq!(
+        symbols
+            .iter()
+            .map(|symbol| (symbol.name.as_str(), symbol.path.path.as_ref(), symbol.kind))
+            .collect::<Vec<_>>(),
+        [("helper", Path::new("b.rs"), lsp::SymbolKind::FUNCTION)]
+    );
+
+    let buffer = project
+        .update(cx, |project, cx| project.open_local_buffer("/dir/a.rs", cx))
+        .await
+        .unwrap();
+    let definitions = project
+        .update(cx, |project, cx| {
+            project.definition(
+                &buffer,
+                Point::new(1, 6).to_point_utf16(buffer.read(cx)),
                 cx,
             )
         })
         .await
         .unwrap();
-
-    buffer.update(cx, |buffer, cx| {
-        for (range, new_text) in edits {
-            buffer.edit([(range, new_text)], None, cx);
-        }
+    assert_eq!(definitions.len(), 1);
+    cx.update(|cx| {
+        let target = &definitions[0].target;
+        let target_buffer = target.buffer.read(cx);
         assert_eq!(
-            buffer.text(),
-            "
-                // above first function
-                fn a() {
-                    // inside first function
-                    f10();
-                }
-                fn b() {
-                    // inside second function f200();
-                }
-                fn c() {
-                    f4000();
-                }
-                "
-            .unindent()
+            target_buffer.file().unwrap().path().as_ref(),
+            Path::new("b.rs")
+        );
+        assert_eq!(
+            target.range.to_point(target_buffer),
+            Point::new(2, 0)..Point::new(2, 14)
         );
     });
+
+    // Changed files are reindexed.
+    fs.save(
+        "/dir/b.rs".as_ref(),
+        &"fn renamed() {}\n".into(),
+        LineEnding::Unix,
+    )
+    .await
+    .unwrap();
+    cx.executor().run_until_parked();
+    let symbols = project
+        .update(cx, |project, cx| project.symbols("", cx))
+        .await
+        .unwrap();
+    let mut names = symbols
+        .iter()
+        .map(|symbol| symbol.name.as_str())
+        .collect::<Vec<_>>();
+    names.sort_unstable();
+    assert_eq!(names, ["main", "renamed"]);
 }
 
 #[gpui::test]
-async fn test_edits_from_lsp2_with_edits_on_adjacent_lines(cx: &mut gpui::TestAppContext) {
+async fn test_references_in_large_response(cx: &mut gpui::TestAppContext) {
     init_test(cx);
 
-    let text = "
-        use a::b;
-        use a::c;
+    let fs = FakeFs::new(cx.executor());
+    fs.insert_tree(
+        "/dir",
+        json!({
+            "a.rs": "fn a() {}\n".repeat(100),
+            "b.rs": "fn b() {}\n".repeat(100),
+        }),
+    )
+    .await;
 
-        fn f() {
-            b();
-            c();
+    let project = Project::test(fs, ["/dir".as_ref()], cx).await;
+    let language_registry = project.read_with(cx, |project, _| project.languages().clone());
+    language_registry.add(rust_lang());
+    let mut fake_servers =
+        language_registry.register_fake_lsp_adapter("Rust", FakeLspAdapter::default());
+
+    let buffer = project
+        .update(cx, |project, cx| project.open_local_buffer("/dir/a.rs", cx))
+        .await
+        .unwrap();
+    let fake_server = fake_servers.next().await.unwrap();
+    // More locations than are anchored at once, alternating between the files.
+    fake_server.handle_request::<lsp::request::References, _, _>(|_, _| async move {
+        Ok(Some(
+            (0..3000)
+                .map(|ix| {
+                    let path = if ix % 2 == 0 {
+                        "/dir/a.rs"
+                    } else {
+                        "/dir/b.rs"
+                    };
+                    let row = ix / 30;
+                    lsp::Location::new(
+                        lsp::Url::from_file_path(path).unwrap(),
+                        lsp::Range::new(lsp::Position::new(row, 3), lsp::Position::new(row, 4)),
+                    )
+                })
+                .collect(),
+        ))
+    });
+
+    let references = project
+        .update(cx, |project, cx| project.references(&buffer, 3, cx))
+        .await
+        .unwrap();
+    assert_eq!(references.len(), 3000);
+    cx.update(|cx| {
+        for (ix, reference) in references.iter().enumerate() {
+            let target_buffer = reference.buffer.read(cx);
+            let path = if ix % 2 == 0 { "a.rs" } else { "b.rs" };
+            let row = ix as u32 / 30;
+            assert_eq!(
+                target_buffer.file().unwrap().path().as_ref(),
+                Path::new(path)
+            );
+            assert_eq!(
+                reference.range.to_point(target_buffer),
+                Point::new(row, 3)..Point::new(row, 4)
+            );
         }
-    "
-    .unindent();
+        assert_eq!(references[0].buffer, buffer);
+        assert_eq!(references[1].buffer, references[3].buffer);
+    });
+}
+
+#[gpui::test]
+async fn test_references_with_partial_results(cx: &mut gpui::TestAppContext) {
+    init_test(cx);
 
     let fs = FakeFs::new(cx.executor());
     fs.insert_tree(
         "/dir",
         json!({
-            "a.rs": text.clone(),
+            "a.rs": "fn a() {}\nfn b() {}\n",
         }),
     )
     .await;
 
     let project = Project::test(fs, ["/dir".as_ref()], cx).await;
+    let language_registry = project.read_with(cx, |project, _| project.languages().clone());
+    language_registry.add(rust_lang());
+    let mut fake_servers =
+        language_registry.register_fake_lsp_adapter("Rust", FakeLspAdapter::default());
+
     let buffer = project
         .update(cx, |project, cx| project.open_local_buffer("/dir/a.rs", cx))
         .await
         .unwrap();
+    let fake_server = fake_servers.next().await.unwrap();
+    let location = |row| {
+        lsp::Location::new(
+            lsp::Url::from_file_path("/dir/a.rs").unwrap(),
+            lsp::Range::new(lsp::Position::new(row, 3), lsp::Position::new(row, 4)),
+        )
+    };
+    // The server reports the first reference, and responds with the other one once it's been received.
+    let (respond_tx, respond_rx) = smol::channel::bounded::<()>(1);
+    fake_server.handle_request::<lsp::request::References, _, _>({
+        let fake_server = fake_server.clone();
+        move |params, _| {
+            let fake_server = fake_server.clone();
+            let respond_rx = respond_rx.clone();
+            async move {
+                fake_server.notify::<RawProgress>(RawProgressParams {
+                    token: params.partial_result_params.partial_result_token.unwrap(),
+                    value: serde_json::to_value(vec![location(0)]).unwrap(),
+                });
+                respond_rx.recv().await.unwrap();
+                Ok(Some(vec![location(1)]))
+            }
+        }
+    });
 
-    // Simulate the language server sending us a small edit in the form of a very large diff.
-    // Rust-analyzer does this when performing a merge-imports code action.
-    let edits = project
-        .update(cx, |project, cx| {
-            project.edits_from_lsp(
-                &buffer,
-                [
-                    // Replace the first use statement without editing the semicolon.
-                    lsp::TextEdit {
-                        range: lsp::Range::new(lsp::Position::new(0, 4), lsp::Position::new(0, 8)),
-                        new_text: "a::{b, c}".into(),
-                    },
-                    // Reinsert the remainder of the file between the semicolon and the final
-                    // newline of the file.
-                    lsp::TextEdit {
-                        range: lsp::Range::new(lsp::Position::new(0, 9), lsp::Position::new(0, 9)),
-                        new_text: "\n\n".into(),
-                    },
-                    lsp::TextEdit {
-                        range: lsp::Range::new(lsp::Position::new(0, 9), lsp::Position::new(0, 9)),
-                        new_text: "
-                            fn f() {
-                                b();
-                                c();
-                            }"
-                        .unindent(),
-                    },
-                    // Delete everything after the first newline of the file.
-                    lsp::TextEdit {
-                        range: lsp::Range::new(lsp::Position::new(1, 0), lsp::Position::new(7, 0)),
-                        new_text: "".into(),
-                    },
-                ],
-                LanguageServerId(0),
-                None,
-                cx,
-            )
-        })
-        .await
-        .unwrap();
-
-    buffer.update(cx, |buffer, cx| {
-        let edits = edits
-            .into_iter()
-            .map(|(range, text)| {
-                (
-                    range.start.to_point(buffer)..range.end.to_point(buffer),
-                    text,
-                )
-            })
-            .collect::<Vec<_>>();
-
+    let mut references = project.update(cx, |project, cx| {
+        project.references_with_partial_results(&buffer, 3, cx)
+    });
+    let partial = references.partial.next().await.unwrap();
+    cx.update(|cx| {
         assert_eq!(
-            edits,
-            [
-                (Point::new(0, 4)..Point::new(0, 8), "a::{b, c}".into()),
-                (Point::new(1, 0)..Point::new(2, 0), "".into())
-            ]
+            partial
+                .iter()
+                .map(|location| location.range.to_point(location.buffer.read(cx)))
+                .collect::<Vec<_>>(),
+            [Point::new(0, 3)..Point::new(0, 4)]
         );
+    });
 
-        for (range, new_text) in edits {
-            buffer.edit([(range, new_text)], None, cx);
-        }
+    // The locations of the response are streamed as well, once they're anchored.
+    respond_tx.send(()).await.unwrap();
+    let anchored = references.partial.next().await.unwrap();
+    cx.update(|cx| {
         assert_eq!(
-            buffer.text(),
-            "
-                use a::{b, c};
-
-                fn f() {
-                    b();
-                    c();
-                }
-            "
-            .unindent()
+            anchored
+                .iter()
+                .map(|location| location.range.to_point(location.buffer.read(cx)))
+                .collect::<Vec<_>>(),
+            [Point::new(1, 3)..Point::new(1, 4)]
         );
     });
+    assert!(references.partial.next().await.is_none());
+    assert!(references.rest.await.unwrap().is_empty());
 }
 
 #[gpui::test]
-async fn test_invalid_edits_from_lsp2(cx: &mut gpui::TestAppContext) {
+async fn test_create_buffer_with_text(cx: &mut gpui::TestAppContext) {
     init_test(cx);
 
-    let text = "
-        use a::b;
-        use a::c;
+    let fs = FakeFs::new(cx.executor());
+    let project = Project::test(fs, [], cx).await;
+    let text = "macro_rules! m { () => {} }\n".repeat(1000);
+    let buffer = project
+        .update(cx, |project, cx| {
+            project.create_buffer_with_text(text.clone(), Some(rust_lang()), cx)
+        })
+        .await
+        .unwrap();
+    buffer.read_with(cx, |buffer, _| {
+        assert_eq!(buffer.text(), text);
+        assert_eq!(buffer.language().unwrap().name().as_ref(), "Rust");
+        assert!(!buffer.is_dirty());
+    });
+    let buffer_id = buffer.read_with(cx, |buffer, _| buffer.remote_id());
+    project.read_with(cx, |project, _| {
+        assert_eq!(project.buffer_for_id(buffer_id), Some(buffer.clone()));
+    });
+}
 
-        fn f() {
-            b();
-            c();
-        }
-    "
-    .unindent();
+#[gpui::test]
+async fn test_type_hierarchy(cx: &mut gpui::TestAppContext) {
+    init_test(cx);
 
     let fs = FakeFs::new(cx.executor());
     fs.insert_tree(
         "/dir",
         json!({
-            "a.rs": text.clone(),
+            "a.rs": "struct A; trait T {} impl T for A {}",
         }),
     )
     .await;
 
     let project = Project::test(fs, ["/dir".as_ref()], cx).await;
+    let language_registry = project.read_with(cx, |project, _| project.languages().clone());
+    language_registry.add(rust_lang());
+    let mut fake_servers =
+        language_registry.register_fake_lsp_adapter("Rust", FakeLspAdapter::default());
+
     let buffer = project
         .update(cx, |project, cx| project.open_local_buffer("/dir/a.rs", cx))
         .await
         .unwrap();
+    let fake_server = fake_servers.next().await.unwrap();
 
-    // Simulate the language server sending us edits in a non-ordered fashion,
-    // with ranges sometimes being inverted or pointing to invalid locations.
-    let edits = project
+    fn lsp_item(name: &str, start: u32, end: u32) -> lsp::TypeHierarchyItem {
+        let range = lsp::Range::new(lsp::Position::new(0, start), lsp::Position::new(0, end));
+        serde_json::from_value(json!({
+            "name": name,
+            "kind": lsp::SymbolKind::STRUCT,
+            "uri": lsp::Url::from_file_path("/dir/a.rs").unwrap(),
+            "range": range,
+            "selectionRange": range,
+        }))
+        .unwrap()
+    }
+
+    fake_server.handle_request::<lsp::request::TypeHierarchyPrepare, _, _>(
+        |params, _| async move {
+            assert_eq!(
+                params.text_document_position_params.position,
+                lsp::Position::new(0, 7)
+            );
+            Ok(Some(vec![lsp_item("A", 7, 8)]))
+        },
+    );
+    fake_server.handle_request::<lsp::request::TypeHierarchySupertypes, _, _>(
+        |params, _| async move {
+            assert_eq!(params.item.name, "A");
+            Ok(Some(vec![lsp_item("T", 16, 17)]))
+        },
+    );
+
+    let roots = project
         .update(cx, |project, cx| {
-            project.edits_from_lsp(
-                &buffer,
-                [
-                    lsp::TextEdit {
-                        range: lsp::Range::new(lsp::Position::new(0, 9), lsp::Position::new(0, 9)),
-                        new_text: "\n\n".into(),
-                    },
-                    lsp::TextEdit {
-                        range: lsp::Range::new(lsp::Position::new(0, 8), lsp::Position::new(0, 4)),
-                        new_text: "a::{b, c}".into(),
-                    },
-                    lsp::TextEdit {
-                        range: lsp::Range::new(lsp::Position::new(1, 0), lsp::Position::new(99, 0)),
-                        new_text: "".into(),
-                    },
-                    lsp::TextEdit {
-                        range: lsp::Range::new(lsp::Position::new(0, 9), lsp::Position::new(0, 9)),
-                        new_text: "
-                            fn f() {
-                                b();
-                                c();
-                            }"
-                        .unindent(),
-                    },
-                ],
-                LanguageServerId(0),
-                None,
-                cx,
-            )
+            project.prepare_type_hierarchy(&buffer, Point::new(0, 7), cx)
         })
         .await
         .unwrap();
+    assert_eq!(roots.len(), 1);
+    assert_eq!(roots[0].lsp_item.name, "A");
+    assert_eq!(roots[0].language_server_id, fake_server.server.server_id());
 
-    buffer.update(cx, |buffer, cx| {
-        let edits = edits
-            .into_iter()
-            .map(|(range, text)| {
-                (
-                    range.start.to_point(buffer)..range.end.to_point(buffer),
-                    text,
-                )
-            })
-            .collect::<Vec<_>>();
-
-        assert_eq!(
-            edits,
-            [
-                (Point::new(0, 4)..Point::new(0, 8), "a::{b, c}".into()),
-                (Point::new(1, 0)..Point::new(2, 0), "".into())
-            ]
-        );
-
-        for (range, new_text) in edits {
-            buffer.edit([(range, new_text)], None, cx);
-        }
+    let supertypes = project
+        .update(cx, |project, cx| {
+            project.type_hierarchy_supertypes(&roots[0], cx)
+        })
+        .await
+        .unwrap();
+    assert_eq!(supertypes.len(), 1);
+    cx.update(|cx| {
+        let supertype = &supertypes[0];
+        assert_eq!(supertype.lsp_item.name, "T");
+        assert_eq!(supertype.location.buffer, buffer);
         assert_eq!(
-            buffer.text(),
-            "
-                use a::{b, c};
-
-                fn f() {
-                    b();
-                    c();
-                }
-            "
-            .unindent()
+            supertype
+                .location
+                .range
+                .to_offset(supertype.location.buffer.read(cx)),
+            16..17
         );
     });
 }
 
-fn chunks_with_diagnostics<T: ToOffset + ToPoint>(
-    buffer: &Buffer,
-    range: Range<T>,
-) -> Vec<(String, Option<DiagnosticSeverity>)> {
-    let mut chunks: Vec<(String, Option<DiagnosticSeverity>)> = Vec::new();
-    for chunk in buffer.snapshot().chunks(range, true) {
-        if chunks.last().map_or(false, |prev_chunk| {
-            prev_chunk.1 == chunk.diagnostic_severity
-        }) {
-            chunks.last_mut().unwrap().0.push_str(chunk.text);
-        } else {
-            chunks.push((chunk.text.to_string(), chunk.diagnostic_severity));
-        }
-    }
-    chunks
-}
-
-#[gpui::test(iterations = 10)]
-async fn test_definition(cx: &mut gpui::TestAppContext) {
+#[gpui::test]
+async fn test_call_hierarchy(cx: &mut gpui::TestAppContext) {
     init_test(cx);
 
     let fs = FakeFs::new(cx.executor());
     fs.insert_tree(
         "/dir",
         json!({
-            "a.rs": "const fn a() { A }",
-            "b.rs": "const y: i32 = crate::a()",
+            "a.rs": "fn a() {} fn b() { a() }",
         }),
     )
     .await;
 
-    let project = Project::test(fs, ["/dir/b.rs".as_ref()], cx).await;
-
+    let project = Project::test(fs, ["/dir".as_ref()], cx).await;
     let language_registry = project.read_with(cx, |project, _| project.languages().clone());
     language_registry.add(rust_lang());
-    let mut fake_servers =
-        language_registry.register_fake_lsp_adapter("Rust", FakeLspAdapter::default());
+    let mut fake_servers = language_registry.register_fake_lsp_adapter(
+        "Rust",
+        FakeLspAdapter {
+            capabilities: lsp::ServerCapabilities {
+                call_hierarchy_provider: Some(lsp::CallHierarchyServerCapability::Simple(true)),
+                ..Default::default()
+            },
+            ..Default::default()
+        },
+    );
 
     let buffer = project
-        .update(cx, |project, cx| project.open_local_buffer("/dir/b.rs", cx))
+        .update(cx, |project, cx| project.open_local_buffer("/dir/a.rs", cx))
         .await
         .unwrap();
-
     let fake_server = fake_servers.next().await.unwrap();
-    fake_server.handle_request::<lsp::request::GotoDefinition, _, _>(|params, _| async move {
-        let params = params.text_document_position_params;
-        assert_eq!(
-            params.text_document.uri.to_file_path().unwrap(),
-            Path::new("/dir/b.rs"),
-        );
-        assert_eq!(params.position, lsp::Position::new(0, 22));
 
-        Ok(Some(lsp::GotoDefinitionResponse::Scalar(
-            lsp::Location::new(
-                lsp::Url::from_file_path("/dir/a.rs").unwrap(),
-                lsp::Range::new(lsp::Position::new(0, 9), lsp::Position::new(0, 10)),
-            ),
-        )))
+    fn lsp_item(name: &str, start: u32, end: u32) -> lsp::CallHierarchyItem {
+        let range = lsp::Range::new(lsp::Position::new(0, start), lsp::Position::new(0, end));
+        serde_json::from_value(json!({
+            "name": name,
+            "kind": lsp::SymbolKind::FUNCTION,
+            "uri": lsp::Url::from_file_path("/dir/a.rs").unwrap(),
+            "range": range,
+            "selectionRange": range,
+        }))
+        .unwrap()
+    }
+
+    fake_server.handle_request::<lsp::request::CallHierarchyPrepare, _, _>(|_, _| async move {
+        Ok(Some(vec![lsp_item("a", 3, 4)]))
     });
+    fake_server.handle_request::<lsp::request::CallHierarchyIncomingCalls, _, _>(
+        |params, _| async move {
+            assert_eq!(params.item.name, "a");
+            Ok(Some(vec![lsp::CallHierarchyIncomingCall {
+                from: lsp_item("b", 13, 14),
+                from_ranges: vec![lsp::Range::new(
+                    lsp::Position::new(0, 19),
+                    lsp::Position::new(0, 20),
+                )],
+            }]))
+        },
+    );
 
-    let mut definitions = project
-        .update(cx, |project, cx| project.definition(&buffer, 22, cx))
+    let roots = project
+        .update(cx, |project, cx| {
+            project.prepare_call_hierarchy(&buffer, Point::new(0, 3), cx)
+        })
         .await
         .unwrap();
+    assert_eq!(roots.len(), 1);
+    assert_eq!(roots[0].lsp_item.name, "a");
+    assert_eq!(roots[0].language_server_id, fake_server.server.server_id());
 
-    // Assert no new language server started
-    cx.executor().run_until_parked();
-    assert!(fake_servers.try_next().is_err());
-
-    assert_eq!(definitions.len(), 1);
-    let definition = definitions.pop().unwrap();
+    let calls = project
+        .update(cx, |project, cx| project.incoming_calls(&roots[0], cx))
+        .await
+        .unwrap();
+    assert_eq!(calls.len(), 1);
     cx.update(|cx| {
-        let target_buffer = definition.target.buffer.read(cx);
-        assert_eq!(
-            target_buffer
-                .file()
-                .unwrap()
-                .as_local()
-                .unwrap()
-                .abs_path(cx),
-            Path::new("/dir/a.rs"),
-        );
-        assert_eq!(definition.target.range.to_offset(target_buffer), 9..10);
+        let call = &calls[0];
+        assert_eq!(call.item.lsp_item.name, "b");
+        let buffer = call.item.location.buffer.read(cx);
+        assert_eq!(call.item.location.range.to_offset(buffer), 13..14);
         assert_eq!(
-            list_worktrees(&project, cx),
-            [("/dir/a.rs".as_ref(), false), ("/dir/b.rs".as_ref(), true)],
+            call.ranges
+                .iter()
+                .map(|range| range.to_offset(buffer))
+                .collect::<Vec<_>>(),
+            [19..20]
         );
-
-        drop(definition);
-    });
-    cx.update(|cx| {
-        assert_eq!(list_worktrees(&project, cx), [("/dir/b.rs".as_ref(), true)]);
     });
-
-    fn list_worktrees<'a>(
-        project: &'a Model<Project>,
-        cx: &'a AppContext,
-    ) -> Vec<(&'a Path, bool)> {
-        project
-            .read(cx)
-            .worktrees()
-            .map(|worktree| {
-                let worktree = worktree.read(cx);
-                (
-                    worktree.as_local().unwrap().abs_path().as_ref(),
-                    worktree.is_visible(),
-                )
-            })
-            .collect::<Vec<_>>()
-    }
 }
 
 #[gpui::test]
@@ -2533,7 +5238,7 @@ async fn test_completions_without_edit_ranges(cx: &mut gpui::TestAppContext) {
         })
         .next()
         .await;
-    let completions = completions.await.unwrap();
+    let completions = completions.await.unwrap().completions;
     let snapshot = buffer.update(cx, |buffer, _| buffer.snapshot());
     assert_eq!(completions.len(), 1);
     assert_eq!(completions[0].new_text, "fullyQualifiedName");
@@ -2559,7 +5264,7 @@ async fn test_completions_without_edit_ranges(cx: &mut gpui::TestAppContext) {
         })
         .next()
         .await;
-    let completions = completions.await.unwrap();
+    let completions = completions.await.unwrap().completions;
     let snapshot = buffer.update(cx, |buffer, _| buffer.snapshot());
     assert_eq!(completions.len(), 1);
     assert_eq!(completions[0].new_text, "component");
@@ -2625,11 +5330,116 @@ async fn test_completions_with_carriage_returns(cx: &mut gpui::TestAppContext) {
         })
         .next()
         .await;
-    let completions = completions.await.unwrap();
+    let completions = completions.await.unwrap().completions;
     assert_eq!(completions.len(), 1);
     assert_eq!(completions[0].new_text, "fully\nQualified\nName");
 }
 
+#[gpui::test]
+async fn test_completion_list_defaults_and_duplicates(cx: &mut gpui::TestAppContext) {
+    init_test(cx);
+
+    let fs = FakeFs::new(cx.executor());
+    fs.insert_tree(
+        "/dir",
+        json!({
+            "a.ts": "",
+        }),
+    )
+    .await;
+
+    let project = Project::test(fs, ["/dir".as_ref()], cx).await;
+
+    let language_registry = project.read_with(cx, |project, _| project.languages().clone());
+    language_registry.add(typescript_lang());
+    let mut fake_language_servers = language_registry.register_fake_lsp_adapter(
+        "TypeScript",
+        FakeLspAdapter {
+            capabilities: lsp::ServerCapabilities {
+                completion_provider: Some(lsp::CompletionOptions::default()),
+                ..Default::default()
+            },
+            ..Default::default()
+        },
+    );
+
+    let buffer = project
+        .update(cx, |p, cx| p.open_local_buffer("/dir/a.ts", cx))
+        .await
+        .unwrap();
+
+    let fake_server = fake_language_servers.next().await.unwrap();
+
+    let text = "let a = b.fqn";
+    buffer.update(cx, |buffer, cx| buffer.set_text(text, cx));
+    let completions = project.update(cx, |project, cx| {
+        project.completions(&buffer, text.len(), DEFAULT_COMPLETION_CONTEXT, cx)
+    });
+
+    fake_server
+        .handle_request::<lsp::request::Completion, _, _>(|_, _| async move {
+            let item = lsp::CompletionItem {
+                label: "fullyQualifiedName".into(),
+                ..Default::default()
+            };
+            Ok(Some(lsp::CompletionResponse::List(lsp::CompletionList {
+                is_incomplete: true,
+                item_defaults: Some(lsp::CompletionListItemDefaults {
+                    edit_range: Some(lsp::CompletionListItemDefaultsEditRange::InsertAndReplace {
+                        insert: lsp::Range::new(
+                            lsp::Position::new(0, 10),
+                            lsp::Position::new(0, 13),
+                        ),
+                        replace: lsp::Range::new(
+                            lsp::Position::new(0, 10),
+                            lsp::Position::new(0, 13),
+                        ),
+                    }),
+                    insert_text_format: Some(lsp::InsertTextFormat::SNIPPET),
+                    ..Default::default()
+                }),
+                items: vec![
+                    item.clone(),
+                    item,
+                    lsp::CompletionItem {
+                        label: "fastQuery".into(),
+                        insert_text_format: Some(lsp::InsertTextFormat::PLAIN_TEXT),
+                        ..Default::default()
+                    },
+                ],
+            })))
+        })
+        .next()
+        .await;
+    let response = completions.await.unwrap();
+    let snapshot = buffer.update(cx, |buffer, _| buffer.snapshot());
+    assert!(response.is_incomplete);
+    assert_eq!(
+        response
+            .completions
+            .iter()
+            .map(|completion| (
+                completion.new_text.as_str(),
+                completion.old_range.to_offset(&snapshot),
+                completion.lsp_completion.insert_text_format,
+            ))
+            .collect::<Vec<_>>(),
+        [
+            (
+                "fullyQualifiedName",
+                text.len() - 3..text.len(),
+                Some(lsp::InsertTextFormat::SNIPPET)
+            ),
+            (
+                "fastQuery",
+                text.len() - 3..text.len(),
+                Some(lsp::InsertTextFormat::PLAIN_TEXT)
+            ),
+        ],
+        "The list defaults should only apply to the items without their own values, and duplicate items should be dropped"
+    );
+}
+
 #[gpui::test(iterations = 10)]
 async fn test_apply_code_actions_with_commands(cx: &mut gpui::TestAppContext) {
     init_test(cx);
@@ -2763,6 +5573,220 @@ async fn test_apply_code_actions_with_commands(cx: &mut gpui::TestAppContext) {
     });
 }
 
+#[gpui::test]
+async fn test_fix_all_diagnostics(cx: &mut gpui::TestAppContext) {
+    init_test(cx);
+
+    let fs = FakeFs::new(cx.executor());
+    fs.insert_tree(
+        "/dir",
+        json!({
+            "a.ts": "var a;\nvar b;\n",
+            "b.ts": "var c;\n",
+        }),
+    )
+    .await;
+
+    let project = Project::test(fs, ["/dir".as_ref()], cx).await;
+
+    let language_registry = project.read_with(cx, |project, _| project.languages().clone());
+    language_registry.add(typescript_lang());
+    let mut fake_language_servers = language_registry.register_fake_lsp_adapter(
+        "TypeScript",
+        FakeLspAdapter {
+            capabilities: lsp::ServerCapabilities {
+                code_action_provider: Some(lsp::CodeActionProviderCapability::Simple(true)),
+                ..lsp::ServerCapabilities::default()
+            },
+            ..FakeLspAdapter::default()
+        },
+    );
+
+    let buffer_a = project
+        .update(cx, |p, cx| p.open_local_buffer("/dir/a.ts", cx))
+        .await
+        .unwrap();
+    let fake_server = fake_language_servers.next().await.unwrap();
+
+    let diagnostic = |row, code: &str| lsp::Diagnostic {
+        range: lsp::Range::new(lsp::Position::new(row, 0), lsp::Position::new(row, 3)),
+        severity: Some(lsp::DiagnosticSeverity::WARNING),
+        code: Some(lsp::NumberOrString::String(code.to_string())),
+        source: Some("lint".to_string()),
+        message: format!("{code} violation"),
+        ..Default::default()
+    };
+    fake_server.notify::<lsp::notification::PublishDiagnostics>(lsp::PublishDiagnosticsParams {
+        uri: Url::from_file_path("/dir/a.ts").unwrap(),
+        version: None,
+        diagnostics: vec![diagnostic(0, "no-var"), diagnostic(1, "no-var")],
+    });
+    fake_server.notify::<lsp::notification::PublishDiagnostics>(lsp::PublishDiagnosticsParams {
+        uri: Url::from_file_path("/dir/b.ts").unwrap(),
+        version: None,
+        diagnostics: vec![diagnostic(0, "no-var")],
+    });
+    cx.executor().run_until_parked();
+
+    // Each diagnostic is fixed by replacing the `var` in its range.
+    fake_server.handle_request::<lsp::request::CodeActionRequest, _, _>(move |params, _| {
+        let diagnostic = diagnostic(params.range.start.line, "no-var");
+        async move {
+            Ok(Some(vec![lsp::CodeActionOrCommand::CodeAction(
+                lsp::CodeAction {
+                    title: "Use let".into(),
+                    kind: Some(lsp::CodeActionKind::QUICKFIX),
+                    diagnostics: Some(vec![diagnostic]),
+                    edit: Some(lsp::WorkspaceEdit {
+                        changes: Some(
+                            [(
+                                params.text_document.uri,
+                                vec![lsp::TextEdit {
+                                    range: params.range,
+                                    new_text: "let".into(),
+                                }],
+                            )]
+                            .into_iter()
+                            .collect(),
+                        ),
+                        ..Default::default()
+                    }),
+                    ..lsp::CodeAction::default()
+                },
+            )]))
+        }
+    });
+
+    let diagnostic = buffer_a.read_with(cx, |buffer, _| {
+        buffer
+            .snapshot()
+            .diagnostics_in_range::<_, usize>(0..buffer.len(), false)
+            .next()
+            .unwrap()
+            .diagnostic
+    });
+    let report = project
+        .update(cx, |project, cx| {
+            project.fix_all_diagnostics(&diagnostic, cx)
+        })
+        .await
+        .unwrap();
+    assert_eq!((report.fixed, report.unfixable, report.failed), (3, 0, 0));
+    assert_eq!(report.transaction.0.len(), 2);
+
+    let buffer_b = project
+        .update(cx, |p, cx| p.open_local_buffer("/dir/b.ts", cx))
+        .await
+        .unwrap();
+    buffer_b.read_with(cx, |buffer, _| assert_eq!(buffer.text(), "let c;\n"));
+
+    // The fixes of a buffer are undone at once.
+    buffer_a.update(cx, |buffer, cx| {
+        assert_eq!(buffer.text(), "let a;\nlet b;\n");
+        buffer.undo(cx);
+        assert_eq!(buffer.text(), "var a;\nvar b;\n");
+    });
+}
+
+#[gpui::test]
+async fn test_diagnostic_sources(cx: &mut gpui::TestAppContext) {
+    init_test(cx);
+
+    /// Reports each `teh`, fixed by replacing it with `the`.
+    struct TypoSource;
+
+    #[async_trait(?Send)]
+    impl DiagnosticSource for TypoSource {
+        fn name(&self) -> &str {
+            "typos"
+        }
+
+        async fn check(
+            &self,
+            buffer: &Model<Buffer>,
+            cx: &mut AsyncAppContext,
+        ) -> Result<Vec<SourceDiagnostic>> {
+            let text = buffer.update(cx, |buffer, _| buffer.text())?;
+            Ok(text
+                .match_indices("teh")
+                .map(|(offset, _)| {
+                    let range =
+                        PointUtf16::new(0, offset as u32)..PointUtf16::new(0, offset as u32 + 3);
+                    SourceDiagnostic {
+                        range: range.clone(),
+                        severity: lsp::DiagnosticSeverity::WARNING,
+                        source: None,
+                        code: None,
+                        message: "typo".to_string(),
+                        fixes: vec![DiagnosticFix {
+                            title: "Replace with \"the\"".to_string(),
+                            edits: vec![(range, "the".to_string())],
+                        }],
+                    }
+                })
+                .collect())
+        }
+    }
+
+    let fs = FakeFs::new(cx.executor());
+    fs.insert_tree("/dir", json!({ "a.txt": "teh end" })).await;
+    let project = Project::test(fs, ["/dir".as_ref()], cx).await;
+    project.update(cx, |project, cx| {
+        project.register_diagnostic_source(Arc::new(TypoSource), cx)
+    });
+    let buffer = project
+        .update(cx, |project, cx| {
+            project.open_local_buffer("/dir/a.txt", cx)
+        })
+        .await
+        .unwrap();
+    cx.executor().advance_clock(Duration::from_secs(1));
+    cx.executor().run_until_parked();
+
+    buffer.update(cx, |buffer, _| {
+        let diagnostics = buffer
+            .snapshot()
+            .diagnostics_in_range::<_, usize>(0..buffer.len(), false)
+            .collect::<Vec<_>>();
+        assert_eq!(diagnostics.len(), 1);
+        assert_eq!(diagnostics[0].range, 0..3);
+        assert_eq!(diagnostics[0].diagnostic.source.as_deref(), Some("typos"));
+        assert_eq!(diagnostics[0].diagnostic.message, "typo");
+    });
+
+    let actions = project
+        .update(cx, |project, cx| project.code_actions(&buffer, 1..1, cx))
+        .await;
+    assert_eq!(actions.len(), 1);
+    assert_eq!(actions[0].lsp_action.title, "Replace with \"the\"");
+    project
+        .update(cx, |project, cx| {
+            project.apply_code_action(buffer.clone(), actions[0].clone(), true, cx)
+        })
+        .await
+        .unwrap();
+    buffer.read_with(cx, |buffer, _| assert_eq!(buffer.text(), "the end"));
+
+    // Once the buffer is checked again, its previous fixes are outdated.
+    cx.executor().advance_clock(Duration::from_secs(1));
+    cx.executor().run_until_parked();
+    buffer.read_with(cx, |buffer, _| {
+        assert_eq!(
+            buffer
+                .snapshot()
+                .diagnostics_in_range::<_, usize>(0..buffer.len(), false)
+                .count(),
+            0
+        );
+    });
+    assert!(project
+        .update(cx, |project, cx| {
+            project.apply_code_action(buffer.clone(), actions[0].clone(), true, cx)
+        })
+        .await
+        .is_err());
+}
+
 #[gpui::test(iterations = 10)]
 async fn test_save_file(cx: &mut gpui::TestAppContext) {
     init_test(cx);
@@ -2778,21 +5802,120 @@ async fn test_save_file(cx: &mut gpui::TestAppContext) {
 
     let project = Project::test(fs.clone(), ["/dir".as_ref()], cx).await;
     let buffer = project
-        .update(cx, |p, cx| p.open_local_buffer("/dir/file1", cx))
+        .update(cx, |p, cx| p.open_local_buffer("/dir/file1", cx))
+        .await
+        .unwrap();
+    buffer.update(cx, |buffer, cx| {
+        assert_eq!(buffer.text(), "the old contents");
+        buffer.edit([(0..0, "a line of text.\n".repeat(10 * 1024))], None, cx);
+    });
+
+    project
+        .update(cx, |project, cx| project.save_buffer(buffer.clone(), cx))
+        .await
+        .unwrap();
+
+    let new_text = fs.load(Path::new("/dir/file1")).await.unwrap();
+    assert_eq!(new_text, buffer.update(cx, |buffer, _| buffer.text()));
+}
+
+#[gpui::test]
+async fn test_save_file_in_its_encoding(cx: &mut gpui::TestAppContext) {
+    init_test(cx);
+
+    let fs = FakeFs::new(cx.executor());
+    fs.insert_tree("/dir", json!({})).await;
+    let content = Encoding::ShiftJis
+        .encode("// 日本語のコメント\r\nfn main() {}\r\n")
+        .unwrap();
+    fs.insert_file("/dir/main.rs", content).await;
+
+    let project = Project::test(fs.clone(), ["/dir".as_ref()], cx).await;
+    let buffer = project
+        .update(cx, |p, cx| p.open_local_buffer("/dir/main.rs", cx))
         .await
         .unwrap();
     buffer.update(cx, |buffer, cx| {
-        assert_eq!(buffer.text(), "the old contents");
-        buffer.edit([(0..0, "a line of text.\n".repeat(10 * 1024))], None, cx);
+        assert_eq!(buffer.encoding(), Encoding::ShiftJis);
+        assert_eq!(buffer.line_ending(), LineEnding::Windows);
+        assert_eq!(buffer.text(), "// 日本語のコメント\nfn main() {}\n");
+        buffer.edit([(0..0, "// 設定\n")], None, cx);
     });
-
     project
         .update(cx, |project, cx| project.save_buffer(buffer.clone(), cx))
         .await
         .unwrap();
+    assert_eq!(
+        fs.read_file_sync("/dir/main.rs").unwrap(),
+        Encoding::ShiftJis
+            .encode("// 設定\r\n// 日本語のコメント\r\nfn main() {}\r\n")
+            .unwrap()
+    );
 
-    let new_text = fs.load(Path::new("/dir/file1")).await.unwrap();
-    assert_eq!(new_text, buffer.update(cx, |buffer, _| buffer.text()));
+    // The characters the encoding can't represent aren't saved, and the buffer keeps its encoding.
+    buffer.update(cx, |buffer, cx| {
+        buffer.edit([(0..0, "// 한국어\n")], None, cx)
+    });
+    assert!(project
+        .update(cx, |project, cx| {
+            project.save_buffer_with_encoding(buffer.clone(), Encoding::Windows1252, cx)
+        })
+        .await
+        .is_err());
+    assert_eq!(
+        buffer.update(cx, |buffer, _| buffer.encoding()),
+        Encoding::ShiftJis
+    );
+    project
+        .update(cx, |project, cx| {
+            project.save_buffer_with_encoding(buffer.clone(), Encoding::Utf8, cx)
+        })
+        .await
+        .unwrap();
+    assert_eq!(
+        fs.load(Path::new("/dir/main.rs")).await.unwrap(),
+        "// 한국어\r\n// 設定\r\n// 日本語のコメント\r\nfn main() {}\r\n"
+    );
+
+    // Reopening the file with an encoding it isn't valid in leaves the buffer unchanged.
+    assert!(project
+        .update(cx, |project, cx| {
+            project.reopen_buffer_with_encoding(&buffer, Encoding::ShiftJis, cx)
+        })
+        .await
+        .is_err());
+    project
+        .update(cx, |project, cx| {
+            project.reopen_buffer_with_encoding(&buffer, Encoding::Windows1252, cx)
+        })
+        .await
+        .unwrap();
+    buffer.update(cx, |buffer, _| {
+        assert_eq!(buffer.encoding(), Encoding::Windows1252);
+        assert!(!buffer.is_dirty());
+        assert!(!buffer.text().contains("한국어"));
+        assert!(buffer.text().ends_with("\nfn main() {}\n"));
+    });
+
+    // When the file is rewritten in another encoding, it's detected again as the buffer is reloaded.
+    project
+        .update(cx, |project, cx| {
+            project.reopen_buffer_with_encoding(&buffer, Encoding::Utf8, cx)
+        })
+        .await
+        .unwrap();
+    fs.insert_file(
+        "/dir/main.rs",
+        Encoding::ShiftJis
+            .encode("// 日本語のコメント\nfn main() {}\n")
+            .unwrap(),
+    )
+    .await;
+    cx.executor().run_until_parked();
+    buffer.update(cx, |buffer, _| {
+        assert_eq!(buffer.encoding(), Encoding::ShiftJis);
+        assert_eq!(buffer.text(), "// 日本語のコメント\nfn main() {}\n");
+    });
 }
 
 #[gpui::test(iterations = 30)]
@@ -3883,30 +7006,445 @@ async fn test_rename(cx: &mut gpui::TestAppContext) {
                     ]
                     .into_iter()
                     .collect(),
-                ),
-                ..Default::default()
-            }))
-        })
-        .next()
-        .await
-        .unwrap();
-    let mut transaction = response.await.unwrap().0;
-    assert_eq!(transaction.len(), 2);
+                ),
+                ..Default::default()
+            }))
+        })
+        .next()
+        .await
+        .unwrap();
+    let mut transaction = response.await.unwrap().0;
+    assert_eq!(transaction.len(), 2);
+    assert_eq!(
+        transaction
+            .remove_entry(&buffer)
+            .unwrap()
+            .0
+            .update(cx, |buffer, _| buffer.text()),
+        "const THREE: usize = 1;"
+    );
+    assert_eq!(
+        transaction
+            .into_keys()
+            .next()
+            .unwrap()
+            .update(cx, |buffer, _| buffer.text()),
+        "const TWO: usize = one::THREE + one::THREE;"
+    );
+}
+
+#[gpui::test]
+async fn test_rename_preview(cx: &mut gpui::TestAppContext) {
+    init_test(cx);
+
+    let fs = FakeFs::new(cx.executor());
+    fs.insert_tree(
+        "/dir",
+        json!({
+            "one.rs": "const ONE: usize = 1;",
+            "two.rs": "const TWO: usize = one::ONE + one::ONE;"
+        }),
+    )
+    .await;
+
+    let project = Project::test(fs.clone(), ["/dir".as_ref()], cx).await;
+
+    let language_registry = project.read_with(cx, |project, _| project.languages().clone());
+    language_registry.add(rust_lang());
+    let mut fake_servers = language_registry.register_fake_lsp_adapter(
+        "Rust",
+        FakeLspAdapter {
+            capabilities: lsp::ServerCapabilities {
+                rename_provider: Some(lsp::OneOf::Left(true)),
+                ..Default::default()
+            },
+            ..Default::default()
+        },
+    );
+
+    let buffer = project
+        .update(cx, |project, cx| {
+            project.open_local_buffer("/dir/one.rs", cx)
+        })
+        .await
+        .unwrap();
+
+    let fake_server = fake_servers.next().await.unwrap();
+
+    let preview = project.update(cx, |project, cx| {
+        project.preview_rename(buffer.clone(), 7, "THREE".to_string(), cx)
+    });
+    fake_server
+        .handle_request::<lsp::request::Rename, _, _>(|params, _| async move {
+            assert_eq!(params.new_name, "THREE");
+            Ok(Some(lsp::WorkspaceEdit {
+                changes: Some(
+                    [
+                        (
+                            lsp::Url::from_file_path("/dir/one.rs").unwrap(),
+                            vec![lsp::TextEdit::new(
+                                lsp::Range::new(lsp::Position::new(0, 6), lsp::Position::new(0, 9)),
+                                "THREE".to_string(),
+                            )],
+                        ),
+                        (
+                            lsp::Url::from_file_path("/dir/two.rs").unwrap(),
+                            vec![
+                                lsp::TextEdit::new(
+                                    lsp::Range::new(
+                                        lsp::Position::new(0, 24),
+                                        lsp::Position::new(0, 27),
+                                    ),
+                                    "THREE".to_string(),
+                                ),
+                                lsp::TextEdit::new(
+                                    lsp::Range::new(
+                                        lsp::Position::new(0, 35),
+                                        lsp::Position::new(0, 38),
+                                    ),
+                                    "THREE".to_string(),
+                                ),
+                            ],
+                        ),
+                    ]
+                    .into_iter()
+                    .collect(),
+                ),
+                ..Default::default()
+            }))
+        })
+        .next()
+        .await
+        .unwrap();
+    let edits = preview.await.unwrap();
+    assert_eq!(edits.len(), 3);
+
+    // The edits aren't applied until they are accepted.
+    let two_buffer = edits
+        .iter()
+        .find(|edit| edit.buffer != buffer)
+        .unwrap()
+        .buffer
+        .clone();
+    assert_eq!(
+        buffer.read_with(cx, |buffer, _| buffer.text()),
+        "const ONE: usize = 1;"
+    );
+    assert_eq!(
+        two_buffer.read_with(cx, |buffer, _| buffer.text()),
+        "const TWO: usize = one::ONE + one::ONE;"
+    );
+
+    // Reject the last edit of `two.rs`.
+    let rejected_start = two_buffer.read_with(cx, |buffer, _| buffer.anchor_before(35));
+    let accepted_edits = edits
+        .into_iter()
+        .filter(|edit| {
+            edit.buffer != two_buffer
+                || two_buffer.read_with(cx, |buffer, _| {
+                    edit.range.start.cmp(&rejected_start, buffer).is_lt()
+                })
+        })
+        .collect::<Vec<_>>();
+    let transaction = project.update(cx, |project, cx| {
+        project.apply_rename_edits(accepted_edits, true, cx)
+    });
+    assert_eq!(transaction.0.len(), 2);
+    assert_eq!(
+        buffer.read_with(cx, |buffer, _| buffer.text()),
+        "const THREE: usize = 1;"
+    );
+    assert_eq!(
+        two_buffer.read_with(cx, |buffer, _| buffer.text()),
+        "const TWO: usize = one::THREE + one::ONE;"
+    );
+}
+
+#[gpui::test]
+async fn test_rename_conflicts(cx: &mut gpui::TestAppContext) {
+    init_test(cx);
+
+    let fs = FakeFs::new(cx.executor());
+    fs.insert_tree(
+        "/dir",
+        json!({
+            "one.rs": "const ONE: usize = 1;",
+            "two.rs": "const TWO: usize = one::ONE + one::ONE;"
+        }),
+    )
+    .await;
+
+    let project = Project::test(fs.clone(), ["/dir".as_ref()], cx).await;
+
+    let language_registry = project.read_with(cx, |project, _| project.languages().clone());
+    language_registry.add(rust_lang());
+    let mut fake_servers = language_registry.register_fake_lsp_adapter(
+        "Rust",
+        FakeLspAdapter {
+            capabilities: lsp::ServerCapabilities {
+                rename_provider: Some(lsp::OneOf::Left(true)),
+                ..Default::default()
+            },
+            ..Default::default()
+        },
+    );
+
+    let buffer = project
+        .update(cx, |project, cx| {
+            project.open_local_buffer("/dir/one.rs", cx)
+        })
+        .await
+        .unwrap();
+    let fake_server = fake_servers.next().await.unwrap();
+    cx.executor().run_until_parked();
+
+    // The server computes the edits of `one.rs` for its first version, but it's edited before they arrive.
+    fake_server.handle_request::<lsp::request::Rename, _, _>(|_, _| async move {
+        Ok(Some(lsp::WorkspaceEdit {
+            document_changes: Some(lsp::DocumentChanges::Edits(vec![
+                lsp::TextDocumentEdit {
+                    text_document: lsp::OptionalVersionedTextDocumentIdentifier {
+                        uri: lsp::Url::from_file_path("/dir/one.rs").unwrap(),
+                        version: Some(0),
+                    },
+                    edits: vec![lsp::Edit::Plain(lsp::TextEdit::new(
+                        lsp::Range::new(lsp::Position::new(0, 6), lsp::Position::new(0, 9)),
+                        "THREE".to_string(),
+                    ))],
+                },
+                lsp::TextDocumentEdit {
+                    text_document: lsp::OptionalVersionedTextDocumentIdentifier {
+                        uri: lsp::Url::from_file_path("/dir/two.rs").unwrap(),
+                        version: None,
+                    },
+                    edits: vec![lsp::Edit::Plain(lsp::TextEdit::new(
+                        lsp::Range::new(lsp::Position::new(0, 24), lsp::Position::new(0, 27)),
+                        "THREE".to_string(),
+                    ))],
+                },
+            ])),
+            ..Default::default()
+        }))
+    });
+    buffer.update(cx, |buffer, cx| buffer.edit([(21..21, "\n")], None, cx));
+    cx.executor().run_until_parked();
+
+    // Skipping the conflicting file still applies the edits of the other files.
+    let mut events = cx.events(&project);
+    let rename = project.update(cx, |project, cx| {
+        project.perform_rename(buffer.clone(), 7, "THREE".to_string(), true, cx)
+    });
+    let prompt = loop {
+        if let Event::LanguageServerPrompt(prompt) = events.next().await.unwrap() {
+            break prompt;
+        }
+    };
+    assert!(
+        prompt.message.contains("one.rs was edited since"),
+        "{}",
+        prompt.message
+    );
+    assert_eq!(
+        prompt
+            .actions
+            .iter()
+            .map(|action| action.title.as_str())
+            .collect::<Vec<_>>(),
+        ["Overwrite", "Skip File", "Open Diff"]
+    );
+    prompt.respond(1).await.unwrap();
+    let transaction = rename.await.unwrap();
+    assert_eq!(transaction.0.len(), 1);
+    let two_buffer = transaction.0.keys().next().unwrap().clone();
+    assert_eq!(
+        two_buffer.read_with(cx, |buffer, _| buffer.text()),
+        "const TWO: usize = one::THREE + one::ONE;"
+    );
+    assert_eq!(
+        buffer.read_with(cx, |buffer, _| buffer.text()),
+        "const ONE: usize = 1;\n"
+    );
+
+    // The edits of a conflicting file can be opened for review instead.
+    let rename = project.update(cx, |project, cx| {
+        project.perform_rename(buffer.clone(), 7, "THREE".to_string(), true, cx)
+    });
+    let prompt = loop {
+        if let Event::LanguageServerPrompt(prompt) = events.next().await.unwrap() {
+            break prompt;
+        }
+    };
+    prompt.respond(2).await.unwrap();
+    let edits = loop {
+        if let Event::ReviewConflictingEdits(edits) = events.next().await.unwrap() {
+            break edits;
+        }
+    };
+    rename.await.unwrap();
+    assert_eq!(edits.len(), 1);
+    assert_eq!(edits[0].buffer, buffer);
+    assert_eq!(edits[0].new_text, "THREE");
+    assert_eq!(
+        buffer.read_with(cx, |buffer, _| edits[0].range.to_offset(buffer)),
+        6..9
+    );
+    assert_eq!(
+        buffer.read_with(cx, |buffer, _| buffer.text()),
+        "const ONE: usize = 1;\n"
+    );
+
+    // The conflicting files of the renames requested by guests are skipped without prompting the host.
+    let rename = project.update(cx, |project, cx| {
+        project.perform_rename(buffer.clone(), 7, "THREE".to_string(), false, cx)
+    });
+    let transaction = rename.await.unwrap();
+    assert_eq!(transaction.0.keys().collect::<Vec<_>>(), [&two_buffer]);
+    assert_eq!(
+        buffer.read_with(cx, |buffer, _| buffer.text()),
+        "const ONE: usize = 1;\n"
+    );
+
+    // When several files conflict, they're all resolved with a single prompt.
+    fake_server.handle_request::<lsp::request::Rename, _, _>(|_, _| async move {
+        Ok(Some(lsp::WorkspaceEdit {
+            document_changes: Some(lsp::DocumentChanges::Edits(
+                ["/dir/one.rs", "/dir/two.rs"]
+                    .into_iter()
+                    .map(|path| lsp::TextDocumentEdit {
+                        text_document: lsp::OptionalVersionedTextDocumentIdentifier {
+                            uri: lsp::Url::from_file_path(path).unwrap(),
+                            version: Some(0),
+                        },
+                        edits: vec![lsp::Edit::Plain(lsp::TextEdit::new(
+                            lsp::Range::new(lsp::Position::new(0, 6), lsp::Position::new(0, 9)),
+                            "FOUR".to_string(),
+                        ))],
+                    })
+                    .collect(),
+            )),
+            ..Default::default()
+        }))
+    });
+    let rename = project.update(cx, |project, cx| {
+        project.perform_rename(buffer.clone(), 7, "FOUR".to_string(), true, cx)
+    });
+    let prompt = loop {
+        if let Event::LanguageServerPrompt(prompt) = events.next().await.unwrap() {
+            break prompt;
+        }
+    };
+    assert!(
+        prompt.message.contains("2 files were changed since"),
+        "{}",
+        prompt.message
+    );
     assert_eq!(
-        transaction
-            .remove_entry(&buffer)
-            .unwrap()
-            .0
-            .update(cx, |buffer, _| buffer.text()),
-        "const THREE: usize = 1;"
+        prompt
+            .actions
+            .iter()
+            .map(|action| action.title.as_str())
+            .collect::<Vec<_>>(),
+        ["Overwrite All", "Skip All", "Open Diffs"]
     );
+    prompt.respond(0).await.unwrap();
+    let transaction = rename.await.unwrap();
+    assert_eq!(transaction.0.len(), 2);
     assert_eq!(
-        transaction
-            .into_keys()
-            .next()
-            .unwrap()
-            .update(cx, |buffer, _| buffer.text()),
-        "const TWO: usize = one::THREE + one::THREE;"
+        buffer.read_with(cx, |buffer, _| buffer.text()),
+        "const FOUR: usize = 1;\n"
+    );
+    assert!(two_buffer
+        .read_with(cx, |buffer, _| buffer.text())
+        .starts_with("const FOUR: usize"));
+}
+
+#[gpui::test]
+async fn test_search_limits(cx: &mut gpui::TestAppContext) {
+    init_test(cx);
+
+    let fs = FakeFs::new(cx.executor());
+    fs.insert_tree(
+        "/dir",
+        json!({
+            "a.rs": "ONE ONE",
+            "b.rs": "ONE",
+            "c.rs": "ONE ONE ONE",
+            "d.rs": "ONE",
+        }),
+    )
+    .await;
+    let project = Project::test(fs.clone(), ["/dir".as_ref()], cx).await;
+    let query = SearchQuery::text(
+        "ONE",
+        false,
+        true,
+        false,
+        Default::default(),
+        Default::default(),
+    )
+    .unwrap();
+
+    let search_with_limits = |limits: SearchLimits, cx: &mut gpui::TestAppContext| {
+        let mut results = project.update(cx, |project, cx| {
+            project.search_with_limits(query.clone(), limits, cx)
+        });
+        async move {
+            let mut range_count = 0;
+            let mut buffer_count = 0;
+            let mut limit_reached = false;
+            while let Some(result) = results.next().await {
+                match result {
+                    SearchResult::Buffer { ranges, .. } => {
+                        buffer_count += 1;
+                        range_count += ranges.len();
+                    }
+                    SearchResult::LimitReached => limit_reached = true,
+                }
+            }
+            (buffer_count, range_count, limit_reached)
+        }
+    };
+
+    assert_eq!(
+        search_with_limits(SearchLimits::default(), cx).await,
+        (4, 7, false)
+    );
+    // The search stops at the files that it's limited to.
+    assert_eq!(
+        search_with_limits(
+            SearchLimits {
+                max_files: 2,
+                max_ranges: 100
+            },
+            cx
+        )
+        .await
+        .0,
+        2
+    );
+    // The search stops at the buffer that exceeds the limit of ranges.
+    let (_, range_count, limit_reached) = search_with_limits(
+        SearchLimits {
+            max_files: 100,
+            max_ranges: 2,
+        },
+        cx,
+    )
+    .await;
+    assert!(range_count > 2);
+    assert!(limit_reached);
+    // Loading more results raises the limits past the ones that were reached.
+    assert_eq!(
+        search_with_limits(
+            SearchLimits {
+                max_files: 2,
+                max_ranges: 100
+            }
+            .doubled(),
+            cx
+        )
+        .await,
+        (4, 7, false)
     );
 }
 
@@ -4643,25 +8181,260 @@ async fn test_multiple_language_server_hovers(cx: &mut gpui::TestAppContext) {
     );
     let _b = language_registry.register_specific_fake_lsp_adapter(
         "tsx",
-        false,
+        false,
+        FakeLspAdapter {
+            name: &language_server_names[2],
+            capabilities: lsp::ServerCapabilities {
+                hover_provider: Some(lsp::HoverProviderCapability::Simple(true)),
+                ..lsp::ServerCapabilities::default()
+            },
+            ..FakeLspAdapter::default()
+        },
+    );
+    let _c = language_registry.register_specific_fake_lsp_adapter(
+        "tsx",
+        false,
+        FakeLspAdapter {
+            name: &language_server_names[3],
+            capabilities: lsp::ServerCapabilities {
+                hover_provider: None,
+                ..lsp::ServerCapabilities::default()
+            },
+            ..FakeLspAdapter::default()
+        },
+    );
+
+    let buffer = project
+        .update(cx, |p, cx| p.open_local_buffer("/dir/a.tsx", cx))
+        .await
+        .unwrap();
+    cx.executor().run_until_parked();
+
+    let mut servers_with_hover_requests = HashMap::default();
+    for i in 0..language_server_names.len() {
+        let new_server = fake_tsx_language_servers.next().await.unwrap_or_else(|| {
+            panic!(
+                "Failed to get language server #{i} with name {}",
+                &language_server_names[i]
+            )
+        });
+        let new_server_name = new_server.server.name();
+        assert!(
+            !servers_with_hover_requests.contains_key(new_server_name),
+            "Unexpected: initialized server with the same name twice. Name: `{new_server_name}`"
+        );
+        let new_server_name = new_server_name.to_string();
+        match new_server_name.as_str() {
+            "TailwindServer" | "TypeScriptServer" => {
+                servers_with_hover_requests.insert(
+                    new_server_name.clone(),
+                    new_server.handle_request::<lsp::request::HoverRequest, _, _>(move |_, _| {
+                        let name = new_server_name.clone();
+                        async move {
+                            Ok(Some(lsp::Hover {
+                                contents: lsp::HoverContents::Scalar(lsp::MarkedString::String(
+                                    format!("{name} hover"),
+                                )),
+                                range: None,
+                            }))
+                        }
+                    }),
+                );
+            }
+            "ESLintServer" => {
+                servers_with_hover_requests.insert(
+                    new_server_name,
+                    new_server.handle_request::<lsp::request::HoverRequest, _, _>(
+                        |_, _| async move { Ok(None) },
+                    ),
+                );
+            }
+            "NoHoverCapabilitiesServer" => {
+                let _never_handled = new_server.handle_request::<lsp::request::HoverRequest, _, _>(
+                    |_, _| async move {
+                        panic!(
+                            "Should not call for hovers server with no corresponding capabilities"
+                        )
+                    },
+                );
+            }
+            unexpected => panic!("Unexpected server name: {unexpected}"),
+        }
+    }
+
+    let hover_task = project.update(cx, |project, cx| {
+        project.hover(&buffer, Point::new(0, 0), cx)
+    });
+    let _: Vec<()> = futures::future::join_all(servers_with_hover_requests.into_values().map(
+        |mut hover_request| async move {
+            hover_request
+                .next()
+                .await
+                .expect("All hover requests should have been triggered")
+        },
+    ))
+    .await;
+    assert_eq!(
+        vec!["TailwindServer hover", "TypeScriptServer hover"],
+        hover_task
+            .await
+            .into_iter()
+            .map(|hover| hover.contents.iter().map(|block| &block.text).join("|"))
+            .sorted()
+            .collect::<Vec<_>>(),
+        "Should receive hover responses from all related servers with hover capabilities"
+    );
+}
+
+#[gpui::test]
+async fn test_merging_language_server_results(cx: &mut gpui::TestAppContext) {
+    init_test(cx);
+
+    let fs = FakeFs::new(cx.executor());
+    fs.insert_tree("/dir", json!({ "a.tsx": "a" })).await;
+
+    let project = Project::test(fs, ["/dir".as_ref()], cx).await;
+    let language_registry = project.read_with(cx, |project, _| project.languages().clone());
+    language_registry.add(tsx_lang());
+    let capabilities = lsp::ServerCapabilities {
+        hover_provider: Some(lsp::HoverProviderCapability::Simple(true)),
+        completion_provider: Some(lsp::CompletionOptions::default()),
+        ..lsp::ServerCapabilities::default()
+    };
+    let mut fake_tsx_language_servers = language_registry.register_specific_fake_lsp_adapter(
+        "tsx",
+        true,
+        FakeLspAdapter {
+            name: "TypeScriptServer",
+            capabilities: capabilities.clone(),
+            ..FakeLspAdapter::default()
+        },
+    );
+    let _tailwind = language_registry.register_specific_fake_lsp_adapter(
+        "tsx",
+        false,
+        FakeLspAdapter {
+            name: "TailwindServer",
+            capabilities,
+            ..FakeLspAdapter::default()
+        },
+    );
+
+    let buffer = project
+        .update(cx, |p, cx| p.open_local_buffer("/dir/a.tsx", cx))
+        .await
+        .unwrap();
+    cx.executor().run_until_parked();
+
+    for _ in 0..2 {
+        let server = fake_tsx_language_servers.next().await.unwrap();
+        let name = server.server.name().to_string();
+        server.handle_request::<lsp::request::HoverRequest, _, _>({
+            let name = name.clone();
+            move |_, _| {
+                let name = name.clone();
+                async move {
+                    Ok(Some(lsp::Hover {
+                        contents: lsp::HoverContents::Scalar(lsp::MarkedString::String(format!(
+                            "{name} hover"
+                        ))),
+                        range: None,
+                    }))
+                }
+            }
+        });
+        server.handle_request::<lsp::request::Completion, _, _>(move |_, _| {
+            let name = name.clone();
+            async move {
+                Ok(Some(lsp::CompletionResponse::Array(vec![
+                    lsp::CompletionItem {
+                        label: "shared".into(),
+                        ..Default::default()
+                    },
+                    lsp::CompletionItem {
+                        label: format!("{name} item"),
+                        ..Default::default()
+                    },
+                ])))
+            }
+        });
+    }
+
+    cx.update(|cx| {
+        SettingsStore::update_global(cx, |settings, cx| {
+            settings.update_user_settings::<ProjectSettings>(cx, |settings| {
+                settings.lsp_merging.hover = LspMergeSettings {
+                    strategy: LspMergeStrategy::First,
+                    server_ranking: vec!["TailwindServer".to_string()],
+                };
+            });
+        })
+    });
+    let hovers = project
+        .update(cx, |project, cx| {
+            project.hover(&buffer, Point::new(0, 0), cx)
+        })
+        .await;
+    assert_eq!(
+        hovers
+            .iter()
+            .map(|hover| hover.contents.iter().map(|block| &block.text).join("|"))
+            .collect::<Vec<_>>(),
+        ["TailwindServer hover"],
+        "Only the hover of the highest ranked server should be kept"
+    );
+
+    let completions = project
+        .update(cx, |project, cx| {
+            project.completions(&buffer, 1, DEFAULT_COMPLETION_CONTEXT, cx)
+        })
+        .await
+        .unwrap()
+        .completions;
+    assert_eq!(
+        completions
+            .iter()
+            .map(|completion| completion.label.text.as_str())
+            .sorted()
+            .collect::<Vec<_>>(),
+        ["TailwindServer item", "TypeScriptServer item", "shared"],
+        "Completions reported by several servers should be deduplicated by default"
+    );
+}
+
+#[gpui::test]
+async fn test_multiple_language_server_signature_help(cx: &mut gpui::TestAppContext) {
+    init_test(cx);
+
+    let fs = FakeFs::new(cx.executor());
+    fs.insert_tree("/dir", json!({ "a.tsx": "foo(1, " })).await;
+
+    let project = Project::test(fs, ["/dir".as_ref()], cx).await;
+    let language_registry = project.read_with(cx, |project, _| project.languages().clone());
+    language_registry.add(tsx_lang());
+    let capabilities = lsp::ServerCapabilities {
+        signature_help_provider: Some(lsp::SignatureHelpOptions {
+            trigger_characters: Some(vec!["(".into()]),
+            retrigger_characters: Some(vec![",".into()]),
+            work_done_progress_options: Default::default(),
+        }),
+        ..lsp::ServerCapabilities::default()
+    };
+    let mut fake_tsx_language_servers = language_registry.register_specific_fake_lsp_adapter(
+        "tsx",
+        true,
         FakeLspAdapter {
-            name: &language_server_names[2],
-            capabilities: lsp::ServerCapabilities {
-                hover_provider: Some(lsp::HoverProviderCapability::Simple(true)),
-                ..lsp::ServerCapabilities::default()
-            },
+            name: "TypeScriptServer",
+            capabilities: capabilities.clone(),
             ..FakeLspAdapter::default()
         },
     );
-    let _c = language_registry.register_specific_fake_lsp_adapter(
+    let _tailwind = language_registry.register_specific_fake_lsp_adapter(
         "tsx",
         false,
         FakeLspAdapter {
-            name: &language_server_names[3],
-            capabilities: lsp::ServerCapabilities {
-                hover_provider: None,
-                ..lsp::ServerCapabilities::default()
-            },
+            name: "TailwindServer",
+            capabilities,
             ..FakeLspAdapter::default()
         },
     );
@@ -4672,79 +8445,104 @@ async fn test_multiple_language_server_hovers(cx: &mut gpui::TestAppContext) {
         .unwrap();
     cx.executor().run_until_parked();
 
-    let mut servers_with_hover_requests = HashMap::default();
-    for i in 0..language_server_names.len() {
-        let new_server = fake_tsx_language_servers.next().await.unwrap_or_else(|| {
-            panic!(
-                "Failed to get language server #{i} with name {}",
-                &language_server_names[i]
-            )
-        });
-        let new_server_name = new_server.server.name();
-        assert!(
-            !servers_with_hover_requests.contains_key(new_server_name),
-            "Unexpected: initialized server with the same name twice. Name: `{new_server_name}`"
-        );
-        let new_server_name = new_server_name.to_string();
-        match new_server_name.as_str() {
-            "TailwindServer" | "TypeScriptServer" => {
-                servers_with_hover_requests.insert(
-                    new_server_name.clone(),
-                    new_server.handle_request::<lsp::request::HoverRequest, _, _>(move |_, _| {
-                        let name = new_server_name.clone();
-                        async move {
-                            Ok(Some(lsp::Hover {
-                                contents: lsp::HoverContents::Scalar(lsp::MarkedString::String(
-                                    format!("{name} hover"),
-                                )),
-                                range: None,
-                            }))
-                        }
-                    }),
-                );
-            }
-            "ESLintServer" => {
-                servers_with_hover_requests.insert(
-                    new_server_name,
-                    new_server.handle_request::<lsp::request::HoverRequest, _, _>(
-                        |_, _| async move { Ok(None) },
-                    ),
-                );
-            }
-            "NoHoverCapabilitiesServer" => {
-                let _never_handled = new_server.handle_request::<lsp::request::HoverRequest, _, _>(
-                    |_, _| async move {
-                        panic!(
-                            "Should not call for hovers server with no corresponding capabilities"
-                        )
-                    },
-                );
+    let signature = |label: &str, parameters: Vec<lsp::ParameterLabel>| lsp::SignatureInformation {
+        label: label.into(),
+        documentation: None,
+        parameters: Some(
+            parameters
+                .into_iter()
+                .map(|label| lsp::ParameterInformation {
+                    label,
+                    documentation: None,
+                })
+                .collect(),
+        ),
+        active_parameter: None,
+    };
+    for _ in 0..2 {
+        let server = fake_tsx_language_servers.next().await.unwrap();
+        let signatures = match server.server.name() {
+            "TypeScriptServer" => vec![
+                signature(
+                    "foo(a: number)",
+                    vec![lsp::ParameterLabel::Simple("a: number".into())],
+                ),
+                signature(
+                    "foo(a: number, b: string)",
+                    vec![
+                        lsp::ParameterLabel::LabelOffsets([4, 13]),
+                        lsp::ParameterLabel::LabelOffsets([15, 24]),
+                    ],
+                ),
+            ],
+            _ => vec![signature(
+                "foo(a: number)",
+                vec![lsp::ParameterLabel::Simple("a: number".into())],
+            )],
+        };
+        server.handle_request::<lsp::request::SignatureHelpRequest, _, _>(move |params, _| {
+            let signatures = signatures.clone();
+            async move {
+                let context = params.context.unwrap();
+                assert_eq!(context.trigger_character.as_deref(), Some(","));
+                assert!(context.is_retrigger);
+                Ok(Some(lsp::SignatureHelp {
+                    signatures,
+                    active_signature: Some(1),
+                    active_parameter: Some(1),
+                }))
             }
-            unexpected => panic!("Unexpected server name: {unexpected}"),
-        }
+        });
     }
 
-    let hover_task = project.update(cx, |project, cx| {
-        project.hover(&buffer, Point::new(0, 0), cx)
+    cx.update(|cx| {
+        SettingsStore::update_global(cx, |settings, cx| {
+            settings.update_user_settings::<ProjectSettings>(cx, |settings| {
+                settings.lsp_merging.signature_help.server_ranking =
+                    vec!["TypeScriptServer".to_string()];
+            });
+        })
     });
-    let _: Vec<()> = futures::future::join_all(servers_with_hover_requests.into_values().map(
-        |mut hover_request| async move {
-            hover_request
-                .next()
-                .await
-                .expect("All hover requests should have been triggered")
-        },
-    ))
-    .await;
+    let triggers = project.read_with(cx, |project, cx| {
+        project.signature_help_triggers(&buffer, cx)
+    });
+    assert!(triggers.trigger_characters.contains("("));
+    assert!(triggers.retrigger_characters.contains(","));
+
+    let signature_help = project
+        .update(cx, |project, cx| {
+            project.signature_help(
+                &buffer,
+                Point::new(0, 7),
+                SignatureHelpTrigger {
+                    character: Some(",".into()),
+                    is_retrigger: true,
+                },
+                cx,
+            )
+        })
+        .await
+        .unwrap();
     assert_eq!(
-        vec!["TailwindServer hover", "TypeScriptServer hover"],
-        hover_task
-            .await
-            .into_iter()
-            .map(|hover| hover.contents.iter().map(|block| &block.text).join("|"))
-            .sorted()
-            .collect::<Vec<_>>(),
-        "Should receive hover responses from all related servers with hover capabilities"
+        signature_help,
+        SignatureHelp {
+            signatures: vec![
+                SignatureInformation {
+                    label: "foo(a: number)".into(),
+                    documentation: None,
+                    parameters: vec![4..13],
+                    active_parameter: None,
+                },
+                SignatureInformation {
+                    label: "foo(a: number, b: string)".into(),
+                    documentation: None,
+                    parameters: vec![4..13, 15..24],
+                    active_parameter: Some(1),
+                },
+            ],
+            active_signature: 1,
+        },
+        "The signatures reported by both servers should be deduplicated by default"
     );
 }
 
@@ -5309,3 +9107,298 @@ fn get_all_tasks(
         old
     })
 }
+
+#[gpui::test]
+async fn test_code_actions_on_save(cx: &mut gpui::TestAppContext) {
+    init_test(cx);
+    cx.update(|cx| {
+        SettingsStore::update_global(cx, |settings, cx| {
+            settings.update_user_settings::<AllLanguageSettings>(cx, |settings| {
+                settings.defaults.format_on_save = Some(FormatOnSave::Off);
+                settings.defaults.code_actions_on_save = Some(vec![
+                    CodeActionOnSave {
+                        kind: "source.organizeImports".to_string(),
+                        timeout_ms: 1000,
+                    },
+                    CodeActionOnSave {
+                        kind: "source.fixAll".to_string(),
+                        timeout_ms: 100,
+                    },
+                    CodeActionOnSave {
+                        kind: "source.addMissingImports".to_string(),
+                        timeout_ms: 1000,
+                    },
+                ]);
+            });
+        })
+    });
+
+    let fs = FakeFs::new(cx.executor());
+    fs.insert_tree("/dir", json!({ "a.rs": "use b;\nuse a;\n" }))
+        .await;
+
+    let project = Project::test(fs, ["/dir".as_ref()], cx).await;
+
+    let language_registry = project.read_with(cx, |project, _| project.languages().clone());
+    language_registry.add(rust_lang());
+    let mut fake_language_servers = language_registry.register_fake_lsp_adapter(
+        "Rust",
+        FakeLspAdapter {
+            capabilities: lsp::ServerCapabilities {
+                code_action_provider: Some(lsp::CodeActionProviderCapability::Simple(true)),
+                ..lsp::ServerCapabilities::default()
+            },
+            ..FakeLspAdapter::default()
+        },
+    );
+
+    let buffer = project
+        .update(cx, |p, cx| p.open_local_buffer("/dir/a.rs", cx))
+        .await
+        .unwrap();
+    let fake_server = fake_language_servers.next().await.unwrap();
+
+    // Organizing the imports sorts them, fixing everything never finishes, and adding the missing imports sees
+    // the sorted imports.
+    let requested_kinds = Arc::new(Mutex::new(Vec::new()));
+    fake_server.handle_request::<lsp::request::CodeActionRequest, _, _>({
+        let requested_kinds = requested_kinds.clone();
+        move |params, _| {
+            let uri = params.text_document.uri;
+            let kind = params.context.only.unwrap().remove(0);
+            requested_kinds.lock().push(kind.as_str().to_string());
+            async move {
+                let new_text = match kind.as_str() {
+                    "source.organizeImports" => "use a;\nuse b;\n",
+                    "source.addMissingImports" => "use a;\nuse b;\nuse c;\n",
+                    _ => future::pending().await,
+                };
+                Ok(Some(vec![lsp::CodeActionOrCommand::CodeAction(
+                    lsp::CodeAction {
+                        title: kind.as_str().to_string(),
+                        kind: Some(kind),
+                        edit: Some(lsp::WorkspaceEdit {
+                            changes: Some(
+                                [(
+                                    uri,
+                                    vec![lsp::TextEdit {
+                                        range: lsp::Range::new(
+                                            lsp::Position::new(0, 0),
+                                            lsp::Position::new(2, 0),
+                                        ),
+                                        new_text: new_text.into(),
+                                    }],
+                                )]
+                                .into_iter()
+                                .collect(),
+                            ),
+                            ..Default::default()
+                        }),
+                        ..lsp::CodeAction::default()
+                    },
+                )]))
+            }
+        }
+    });
+
+    let format = project.update(cx, |project, cx| {
+        project.format(
+            HashSet::from_iter([buffer.clone()]),
+            true,
+            FormatTrigger::Save,
+            cx,
+        )
+    });
+    cx.executor().run_until_parked();
+    cx.executor().advance_clock(Duration::from_millis(100));
+    let transaction = format.await.unwrap();
+
+    assert_eq!(
+        *requested_kinds.lock(),
+        [
+            "source.organizeImports",
+            "source.fixAll",
+            "source.addMissingImports"
+        ]
+    );
+    assert_eq!(
+        buffer.read_with(cx, |buffer, _| buffer.text()),
+        "use a;\nuse b;\nuse c;\n"
+    );
+    assert!(transaction.0.contains_key(&buffer));
+
+    // The code actions on save don't run when formatting manually.
+    project
+        .update(cx, |project, cx| {
+            project.format(
+                HashSet::from_iter([buffer.clone()]),
+                true,
+                FormatTrigger::Manual,
+                cx,
+            )
+        })
+        .await
+        .unwrap();
+    assert_eq!(requested_kinds.lock().len(), 3);
+}
+
+#[gpui::test]
+async fn test_formatter_chain(cx: &mut gpui::TestAppContext) {
+    init_test(cx);
+    cx.update(|cx| {
+        SettingsStore::update_global(cx, |settings, cx| {
+            settings.update_user_settings::<AllLanguageSettings>(cx, |settings| {
+                settings.defaults.formatter = Some(Formatter::Chain(vec![
+                    Formatter::CodeActions(
+                        [("source.organizeImports".to_string(), true)]
+                            .into_iter()
+                            .collect(),
+                    ),
+                    Formatter::LanguageServer,
+                    Formatter::LanguageServer,
+                ]));
+            });
+        })
+    });
+
+    let fs = FakeFs::new(cx.executor());
+    fs.insert_tree("/dir", json!({ "a.rs": "use b;\nuse a;\n" }))
+        .await;
+
+    let project = Project::test(fs, ["/dir".as_ref()], cx).await;
+
+    let language_registry = project.read_with(cx, |project, _| project.languages().clone());
+    language_registry.add(rust_lang());
+    let mut fake_language_servers = language_registry.register_fake_lsp_adapter(
+        "Rust",
+        FakeLspAdapter {
+            capabilities: lsp::ServerCapabilities {
+                code_action_provider: Some(lsp::CodeActionProviderCapability::Simple(true)),
+                document_formatting_provider: Some(lsp::OneOf::Left(true)),
+                ..lsp::ServerCapabilities::default()
+            },
+            ..FakeLspAdapter::default()
+        },
+    );
+
+    let buffer = project
+        .update(cx, |p, cx| p.open_local_buffer("/dir/a.rs", cx))
+        .await
+        .unwrap();
+    let fake_server = fake_language_servers.next().await.unwrap();
+
+    fake_server.handle_request::<lsp::request::CodeActionRequest, _, _>(|params, _| async move {
+        Ok(Some(vec![lsp::CodeActionOrCommand::CodeAction(
+            lsp::CodeAction {
+                title: "Organize Imports".to_string(),
+                kind: Some(lsp::CodeActionKind::SOURCE_ORGANIZE_IMPORTS),
+                edit: Some(lsp::WorkspaceEdit {
+                    changes: Some(
+                        [(
+                            params.text_document.uri,
+                            vec![lsp::TextEdit {
+                                range: lsp::Range::new(
+                                    lsp::Position::new(0, 0),
+                                    lsp::Position::new(2, 0),
+                                ),
+                                new_text: "use a;\nuse b;\n".into(),
+                            }],
+                        )]
+                        .into_iter()
+                        .collect(),
+                    ),
+                    ..Default::default()
+                }),
+                ..lsp::CodeAction::default()
+            },
+        )]))
+    });
+    // The first formatting stage fails and is skipped, the second one formats the organized imports.
+    let formatting_requests = Arc::new(AtomicUsize::new(0));
+    fake_server.handle_request::<lsp::request::Formatting, _, _>({
+        let formatting_requests = formatting_requests.clone();
+        move |_, _| {
+            let request_ix = formatting_requests.fetch_add(1, SeqCst);
+            async move {
+                if request_ix == 0 {
+                    return Err(anyhow!("formatting failed"));
+                }
+                Ok(Some(vec![lsp::TextEdit {
+                    range: lsp::Range::new(lsp::Position::new(2, 0), lsp::Position::new(2, 0)),
+                    new_text: "\nfn main() {}\n".into(),
+                }]))
+            }
+        }
+    });
+
+    project
+        .update(cx, |project, cx| {
+            project.format(
+                HashSet::from_iter([buffer.clone()]),
+                true,
+                FormatTrigger::Manual,
+                cx,
+            )
+        })
+        .await
+        .unwrap();
+    assert_eq!(formatting_requests.load(SeqCst), 2);
+    assert_eq!(
+        buffer.read_with(cx, |buffer, _| buffer.text()),
+        "use a;\nuse b;\n\nfn main() {}\n"
+    );
+
+    // The edits of all the stages are undone at once.
+    buffer.update(cx, |buffer, cx| buffer.undo(cx));
+    assert_eq!(
+        buffer.read_with(cx, |buffer, _| buffer.text()),
+        "use b;\nuse a;\n"
+    );
+}
+
+#[gpui::test]
+fn test_prettier_plugins_of_formatter_chains(cx: &mut gpui::TestAppContext) {
+    init_test(cx);
+    let set_formatter = |formatter: Formatter, cx: &mut gpui::TestAppContext| {
+        cx.update(|cx| {
+            SettingsStore::update_global(cx, |settings, cx| {
+                settings.update_user_settings::<AllLanguageSettings>(cx, |settings| {
+                    settings.defaults.formatter = Some(formatter);
+                });
+            })
+        });
+        cx.update(|cx| prettier_plugins_for_language(&language_settings(None, None, cx)).is_some())
+    };
+
+    assert!(!set_formatter(
+        Formatter::Chain(vec![Formatter::LanguageServer]),
+        cx
+    ));
+    assert!(set_formatter(
+        Formatter::Chain(vec![
+            Formatter::LanguageServer,
+            Formatter::Chain(vec![Formatter::Prettier]),
+        ]),
+        cx
+    ));
+}
+
+#[test]
+fn test_describe_edited_rows() {
+    assert_eq!(
+        describe_edited_rows(&[Point::new(0, 0)..Point::new(0, 3)]),
+        "line 1"
+    );
+    assert_eq!(
+        describe_edited_rows(&[Point::new(4, 2)..Point::new(5, 0)]),
+        "lines 5-6"
+    );
+    assert_eq!(
+        describe_edited_rows(&[
+            Point::new(6, 0)..Point::new(6, 2),
+            Point::new(0, 0)..Point::new(1, 0),
+            Point::new(2, 0)..Point::new(3, 1),
+        ]),
+        "lines 1-4, 7"
+    );
+}
@@ -0,0 +1,431 @@
+//! Support for the pull diagnostics model, introduced in LSP 3.17: instead of publishing the diagnostics,
+//! the servers reply to `textDocument/diagnostic` and `workspace/diagnostic` requests, issued by Zed.
+//!
+//! Pulled diagnostics are stored the same way the published ones are, so both models can be used by different
+//! language servers of the same buffer at once.
+
+use std::time::Duration;
+
+use anyhow::{anyhow, Context as _, Result};
+use client::TypedEnvelope;
+use collections::HashMap;
+use futures::{channel::mpsc, future::join_all};
+use gpui::{AsyncAppContext, Model, ModelContext, Task};
+use language::Buffer;
+use lsp::LanguageServerId;
+use rpc::proto;
+use serde::{Deserialize, Serialize};
+use text::BufferId;
+use util::ResultExt;
+
+use crate::{
+    lsp_command::{GetDocumentDiagnostics, LspCommand, LspPullDiagnostics},
+    File, LanguageServerState, LanguageServerToQuery, Project,
+};
+
+const DIAGNOSTICS_PULL_DEBOUNCE: Duration = Duration::from_millis(250);
+
+/// `$/progress` notification, with its value left unparsed: it could be either a work done progress
+/// or a partial result of some request.
+pub(crate) enum RawProgress {}
+
+impl lsp::notification::Notification for RawProgress {
+    type Params = RawProgressParams;
+    const METHOD: &'static str = "$/progress";
+}
+
+#[derive(Debug, Deserialize, Serialize)]
+pub(crate) struct RawProgressParams {
+    pub token: lsp::NumberOrString,
+    pub value: serde_json::Value,
+}
+
+/// The request that the partial results reported with a token belong to.
+pub(crate) enum PartialResultRequest {
+    WorkspaceDiagnostics,
+    /// A request whose partial results are sent to its caller as they come, like the references of a symbol.
+    Streamed(mpsc::UnboundedSender<serde_json::Value>),
+}
+
+#[derive(Default)]
+pub(crate) struct PullDiagnostics {
+    buffer_pulls: HashMap<BufferId, Task<()>>,
+    workspace_pulls: HashMap<LanguageServerId, Task<()>>,
+    result_ids: HashMap<LanguageServerId, HashMap<lsp::Url, String>>,
+    /// The partial result tokens of the requests sent to the local language servers, with the server each was
+    /// sent to.
+    partial_result_tokens: HashMap<String, (LanguageServerId, PartialResultRequest)>,
+    next_partial_result_token: usize,
+}
+
+impl PullDiagnostics {
+    /// Registers a partial result token for a request to the language server, to be sent with the request.
+    pub(crate) fn register_partial_result_token(
+        &mut self,
+        server_id: LanguageServerId,
+        request: PartialResultRequest,
+    ) -> String {
+        let token = format!("zed-partial-result-{}", self.next_partial_result_token);
+        self.next_partial_result_token += 1;
+        self.partial_result_tokens
+            .insert(token.clone(), (server_id, request));
+        token
+    }
+
+    /// Forgets the token of a request once it was responded to.
+    pub(crate) fn unregister_partial_result_token(&mut self, token: &str) {
+        self.partial_result_tokens.remove(token);
+    }
+
+    pub(crate) fn language_server_removed(&mut self, server_id: LanguageServerId) {
+        self.workspace_pulls.remove(&server_id);
+        self.result_ids.remove(&server_id);
+        self.partial_result_tokens
+            .retain(|_, (token_server_id, _)| *token_server_id != server_id);
+    }
+}
+
+impl Project {
+    /// Pulls the diagnostics of the buffer from all of its language servers that support `textDocument/diagnostic`.
+    /// The diagnostics are stored in the project, along with the published ones.
+    pub fn pull_diagnostics(
+        &mut self,
+        buffer: Model<Buffer>,
+        cx: &mut ModelContext<Self>,
+    ) -> Task<Result<Vec<LspPullDiagnostics>>> {
+        if !self.is_local() {
+            return self.request_lsp(
+                buffer,
+                LanguageServerToQuery::Primary,
+                GetDocumentDiagnostics::default(),
+                cx,
+            );
+        }
+
+        let buffer_id = buffer.read(cx).remote_id();
+        let uri = File::from_dyn(buffer.read(cx).file())
+            .and_then(File::as_local)
+            .and_then(|file| lsp::Url::from_file_path(file.abs_path(cx)).ok());
+        let pulls = self
+            .language_servers_for_buffer(buffer.read(cx), cx)
+            .filter_map(|(_, server)| {
+                let identifier = match server.capabilities().diagnostic_provider.as_ref()? {
+                    lsp::DiagnosticServerCapabilities::Options(options) => {
+                        options.identifier.clone()
+                    }
+                    lsp::DiagnosticServerCapabilities::RegistrationOptions(options) => {
+                        options.diagnostic_options.identifier.clone()
+                    }
+                };
+                let server_id = server.server_id();
+                let previous_result_id = uri.as_ref().and_then(|uri| {
+                    self.pull_diagnostics
+                        .result_ids
+                        .get(&server_id)?
+                        .get(uri)
+                        .cloned()
+                });
+                // The diagnostics are computed for the contents the server knows when it's asked for them.
+                let document_version = self
+                    .buffer_snapshots
+                    .get(&buffer_id)
+                    .and_then(|snapshots| snapshots.get(&server_id)?.last())
+                    .map(|snapshot| snapshot.version);
+                Some((
+                    server_id,
+                    GetDocumentDiagnostics {
+                        identifier,
+                        previous_result_id,
+                        document_version,
+                    },
+                ))
+            })
+            .collect::<Vec<_>>();
+        let pulls = pulls
+            .into_iter()
+            .map(|(server_id, request)| {
+                self.request_lsp(
+                    buffer.clone(),
+                    LanguageServerToQuery::Other(server_id),
+                    request,
+                    cx,
+                )
+            })
+            .collect::<Vec<_>>();
+
+        cx.background_executor().spawn(async move {
+            Ok(join_all(pulls)
+                .await
+                .into_iter()
+                .filter_map(|pulled| pulled.log_err())
+                .flatten()
+                .collect())
+        })
+    }
+
+    /// Pulls the diagnostics of the buffer after a short delay, cancelling the previously scheduled pull, if any.
+    pub(crate) fn schedule_diagnostics_pull(
+        &mut self,
+        buffer: &Model<Buffer>,
+        cx: &mut ModelContext<Self>,
+    ) {
+        let has_pull_servers = self
+            .language_servers_for_buffer(buffer.read(cx), cx)
+            .any(|(_, server)| server.capabilities().diagnostic_provider.is_some());
+        if !has_pull_servers {
+            return;
+        }
+
+        let buffer_id = buffer.read(cx).remote_id();
+        let buffer = buffer.downgrade();
+        let pull = cx.spawn(move |this, mut cx| async move {
+            cx.background_executor()
+                .timer(DIAGNOSTICS_PULL_DEBOUNCE)
+                .await;
+            let Some(buffer) = buffer.upgrade() else {
+                return;
+            };
+            let Ok(pull) = this.update(&mut cx, |this, cx| this.pull_diagnostics(buffer, cx))
+            else {
+                return;
+            };
+            pull.await.log_err();
+            this.update(&mut cx, |this, _| {
+                this.pull_diagnostics.buffer_pulls.remove(&buffer_id);
+            })
+            .ok();
+        });
+        self.pull_diagnostics.buffer_pulls.insert(buffer_id, pull);
+    }
+
+    /// Pulls the diagnostics of all documents the server knows about with `workspace/diagnostic`,
+    /// reporting previous result ids for the documents pulled before.
+    pub(crate) fn pull_workspace_diagnostics(
+        &mut self,
+        server_id: LanguageServerId,
+        cx: &mut ModelContext<Self>,
+    ) {
+        let Some(LanguageServerState::Running { server, .. }) =
+            self.language_servers.get(&server_id)
+        else {
+            return;
+        };
+        let identifier = match server.capabilities().diagnostic_provider.as_ref() {
+            Some(lsp::DiagnosticServerCapabilities::Options(options))
+                if options.workspace_diagnostics =>
+            {
+                options.identifier.clone()
+            }
+            Some(lsp::DiagnosticServerCapabilities::RegistrationOptions(options))
+                if options.diagnostic_options.workspace_diagnostics =>
+            {
+                options.diagnostic_options.identifier.clone()
+            }
+            _ => return,
+        };
+        let server = server.clone();
+
+        let previous_result_ids = self
+            .pull_diagnostics
+            .result_ids
+            .get(&server_id)
+            .map(|result_ids| {
+                result_ids
+                    .iter()
+                    .map(|(uri, result_id)| lsp::PreviousResultId {
+                        uri: uri.clone(),
+                        value: result_id.clone(),
+                    })
+                    .collect()
+            })
+            .unwrap_or_default();
+        let partial_result_token = self
+            .pull_diagnostics
+            .register_partial_result_token(server_id, PartialResultRequest::WorkspaceDiagnostics);
+
+        let request = server.request::<lsp::request::WorkspaceDiagnosticRequest>(
+            lsp::WorkspaceDiagnosticParams {
+                identifier,
+                previous_result_ids,
+                work_done_progress_params: Default::default(),
+                partial_result_params: lsp::PartialResultParams {
+                    partial_result_token: Some(lsp::NumberOrString::String(
+                        partial_result_token.clone(),
+                    )),
+                },
+            },
+        );
+        let pull = cx.spawn(move |this, mut cx| async move {
+            let response = request.await;
+            this.update(&mut cx, |this, cx| {
+                this.pull_diagnostics
+                    .unregister_partial_result_token(&partial_result_token);
+                this.pull_diagnostics.workspace_pulls.remove(&server_id);
+                let items = match response.context("pulling workspace diagnostics") {
+                    Ok(lsp::WorkspaceDiagnosticReportResult::Report(report)) => report.items,
+                    Ok(lsp::WorkspaceDiagnosticReportResult::Partial(partial)) => partial.items,
+                    Err(e) => {
+                        log::error!("{e:#}");
+                        return;
+                    }
+                };
+                this.apply_pulled_diagnostics(
+                    items
+                        .into_iter()
+                        .map(|item| LspPullDiagnostics::from_workspace_report(server_id, item))
+                        .collect(),
+                    cx,
+                );
+            })
+            .ok();
+        });
+        self.pull_diagnostics
+            .workspace_pulls
+            .insert(server_id, pull);
+    }
+
+    /// Handles `workspace/diagnostic/refresh` request: all diagnostics of the server are pulled again.
+    pub(crate) fn refresh_pulled_diagnostics(
+        &mut self,
+        server_id: LanguageServerId,
+        cx: &mut ModelContext<Self>,
+    ) {
+        let buffers = self
+            .opened_buffers
+            .values()
+            .filter_map(|buffer| buffer.upgrade())
+            .filter(|buffer| {
+                self.language_server_ids_for_buffer(buffer.read(cx), cx)
+                    .contains(&server_id)
+            })
+            .collect::<Vec<_>>();
+        for buffer in buffers {
+            self.schedule_diagnostics_pull(&buffer, cx);
+        }
+        self.pull_workspace_diagnostics(server_id, cx);
+    }
+
+    /// Applies a partial result of a workspace diagnostics pull, or sends the one of another request to its caller,
+    /// reported via `$/progress`. Returns `false` if the token does not belong to any request to the server.
+    pub(crate) fn on_lsp_partial_result(
+        &mut self,
+        server_id: LanguageServerId,
+        token: &str,
+        value: serde_json::Value,
+        cx: &mut ModelContext<Self>,
+    ) -> bool {
+        match self.pull_diagnostics.partial_result_tokens.get(token) {
+            Some((token_server_id, request)) if *token_server_id == server_id => match request {
+                PartialResultRequest::WorkspaceDiagnostics => {}
+                PartialResultRequest::Streamed(tx) => {
+                    tx.unbounded_send(value).ok();
+                    return true;
+                }
+            },
+            _ => return false,
+        }
+
+        let items = match serde_json::from_value::<lsp::WorkspaceDiagnosticReportPartialResult>(
+            value.clone(),
+        ) {
+            Ok(partial) => partial.items,
+            Err(_) => match serde_json::from_value::<lsp::WorkspaceDiagnosticReport>(value) {
+                Ok(report) => report.items,
+                Err(e) => {
+                    log::error!("invalid workspace diagnostics partial result: {e}");
+                    return true;
+                }
+            },
+        };
+        self.apply_pulled_diagnostics(
+            items
+                .into_iter()
+                .map(|item| LspPullDiagnostics::from_workspace_report(server_id, item))
+                .collect(),
+            cx,
+        );
+        true
+    }
+
+    pub(crate) fn apply_pulled_diagnostics(
+        &mut self,
+        pulled_diagnostics: Vec<LspPullDiagnostics>,
+        cx: &mut ModelContext<Self>,
+    ) {
+        for pulled in pulled_diagnostics {
+            let server_id = pulled.server_id;
+            let result_ids = self
+                .pull_diagnostics
+                .result_ids
+                .entry(server_id)
+                .or_default();
+            match pulled.result_id {
+                Some(result_id) => {
+                    result_ids.insert(pulled.uri.clone(), result_id);
+                }
+                None => {
+                    result_ids.remove(&pulled.uri);
+                }
+            }
+
+            let Some(diagnostics) = pulled.diagnostics else {
+                continue;
+            };
+            let Some(LanguageServerState::Running { adapter, .. }) =
+                self.language_servers.get(&server_id)
+            else {
+                continue;
+            };
+            let adapter = adapter.clone();
+            let mut params = lsp::PublishDiagnosticsParams {
+                uri: pulled.uri,
+                diagnostics,
+                version: pulled.version,
+            };
+            adapter.process_diagnostics(&mut params);
+            self.update_diagnostics(
+                server_id,
+                params,
+                &adapter.disk_based_diagnostic_sources,
+                cx,
+            )
+            .log_err();
+        }
+    }
+
+    pub(crate) async fn handle_get_document_diagnostics(
+        this: Model<Self>,
+        envelope: TypedEnvelope<proto::GetDocumentDiagnostics>,
+        mut cx: AsyncAppContext,
+    ) -> Result<proto::GetDocumentDiagnosticsResponse> {
+        let sender_id = envelope.original_sender_id()?;
+        let buffer_id = GetDocumentDiagnostics::buffer_id_from_proto(&envelope.payload)?;
+        let buffer = this.update(&mut cx, |this, _| {
+            this.opened_buffers
+                .get(&buffer_id)
+                .and_then(|buffer| buffer.upgrade())
+                .ok_or_else(|| anyhow!("unknown buffer id {}", buffer_id))
+        })??;
+        GetDocumentDiagnostics::from_proto(
+            envelope.payload,
+            this.clone(),
+            buffer.clone(),
+            cx.clone(),
+        )
+        .await?;
+        let pulled_diagnostics = this
+            .update(&mut cx, |this, cx| {
+                this.pull_diagnostics(buffer.clone(), cx)
+            })?
+            .await?;
+        this.update(&mut cx, |this, cx| {
+            GetDocumentDiagnostics::response_to_proto(
+                pulled_diagnostics,
+                this,
+                sender_id,
+                &buffer.read(cx).version(),
+                cx,
+            )
+        })
+    }
+}
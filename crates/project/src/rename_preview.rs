@@ -0,0 +1,168 @@
+//! Rename previews: the edits of a rename are computed without being applied, so that they can be reviewed,
+//! and only the accepted ones are applied.
+
+use std::ops::Range;
+
+use anyhow::{anyhow, bail, Context as _, Result};
+use collections::HashMap;
+use gpui::{Model, ModelContext, Task};
+use language::{point_to_lsp, Anchor, Buffer, ToPointUtf16};
+use lsp::Edit;
+use snippet::Snippet;
+
+use crate::{workspace_edit_operations, Project, ProjectTransaction};
+
+/// An edit of a rename, or of a conflicting workspace edit, that is yet to be applied.
+#[derive(Clone, Debug, PartialEq)]
+pub struct PendingRenameEdit {
+    pub buffer: Model<Buffer>,
+    pub range: Range<Anchor>,
+    pub new_text: String,
+}
+
+impl Project {
+    /// Asks the primary language server of the buffer for the edits of renaming the symbol at the position,
+    /// without applying them.
+    ///
+    /// Only the renames of local projects that edit text can be previewed: a rename that also creates, renames
+    /// or deletes files has to be performed with [`Project::perform_rename`].
+    pub fn preview_rename<T: ToPointUtf16>(
+        &mut self,
+        buffer: Model<Buffer>,
+        position: T,
+        new_name: String,
+        cx: &mut ModelContext<Self>,
+    ) -> Task<Result<Vec<PendingRenameEdit>>> {
+        if !self.is_local() {
+            return Task::ready(Err(anyhow!(
+                "Renames can only be previewed in local projects"
+            )));
+        }
+        let buffer = buffer.read(cx);
+        let position = position.to_point_utf16(buffer);
+        let Some(uri) = buffer
+            .file()
+            .and_then(|file| file.as_local())
+            .and_then(|file| lsp::Url::from_file_path(file.abs_path(cx)).ok())
+        else {
+            return Task::ready(Err(anyhow!("The buffer has no local file")));
+        };
+        let Some((adapter, server)) = self
+            .primary_language_server_for_buffer(buffer, cx)
+            .map(|(adapter, server)| (adapter.clone(), server.clone()))
+        else {
+            return Task::ready(Err(anyhow!("No language server for the buffer")));
+        };
+        let params = lsp::RenameParams {
+            text_document_position: lsp::TextDocumentPositionParams {
+                text_document: lsp::TextDocumentIdentifier { uri },
+                position: point_to_lsp(position),
+            },
+            new_name,
+            work_done_progress_params: Default::default(),
+        };
+
+        cx.spawn(move |this, mut cx| async move {
+            let Some(edit) = server
+                .request::<lsp::request::Rename>(params)
+                .await
+                .context("rename LSP request")?
+            else {
+                return Ok(Vec::new());
+            };
+
+            let mut pending_edits = Vec::new();
+            for operation in workspace_edit_operations(edit) {
+                let lsp::DocumentChangeOperation::Edit(op) = operation else {
+                    bail!(
+                        "{} changes files along with the rename, which can't be previewed",
+                        server.name()
+                    );
+                };
+                let buffer = this
+                    .update(&mut cx, |this, cx| {
+                        this.open_local_buffer_via_lsp(
+                            op.text_document.uri.clone(),
+                            server.server_id(),
+                            adapter.name.clone(),
+                            cx,
+                        )
+                    })?
+                    .await?;
+                let text_edits = op
+                    .edits
+                    .into_iter()
+                    .map(plain_text_edit)
+                    .collect::<Vec<_>>();
+                let edits = this
+                    .update(&mut cx, |this, cx| {
+                        this.edits_from_lsp(
+                            &buffer,
+                            text_edits,
+                            server.server_id(),
+                            op.text_document.version,
+                            cx,
+                        )
+                    })?
+                    .await?;
+                pending_edits.extend(edits.into_iter().map(|(range, new_text)| {
+                    PendingRenameEdit {
+                        buffer: buffer.clone(),
+                        range,
+                        new_text,
+                    }
+                }));
+            }
+            Ok(pending_edits)
+        })
+    }
+
+    /// Applies the accepted edits of a previewed rename, in one transaction per buffer.
+    pub fn apply_rename_edits(
+        &mut self,
+        edits: Vec<PendingRenameEdit>,
+        push_to_history: bool,
+        cx: &mut ModelContext<Self>,
+    ) -> ProjectTransaction {
+        let mut edits_by_buffer = HashMap::<_, (Model<Buffer>, Vec<_>)>::default();
+        for edit in edits {
+            edits_by_buffer
+                .entry(edit.buffer.entity_id())
+                .or_insert_with(|| (edit.buffer.clone(), Vec::new()))
+                .1
+                .push((edit.range, edit.new_text));
+        }
+
+        let mut project_transaction = ProjectTransaction::default();
+        for (buffer, edits) in edits_by_buffer.into_values() {
+            let transaction = buffer.update(cx, |buffer, cx| {
+                buffer.finalize_last_transaction();
+                buffer.start_transaction();
+                buffer.edit(edits, None, cx);
+                buffer.end_transaction(cx)?;
+                let transaction = buffer.finalize_last_transaction()?.clone();
+                if !push_to_history {
+                    buffer.forget_transaction(transaction.id);
+                }
+                Some(transaction)
+            });
+            if let Some(transaction) = transaction {
+                project_transaction.0.insert(buffer, transaction);
+            }
+        }
+        project_transaction
+    }
+}
+
+/// Returns the text edit of an edit of a workspace edit, with the text of its snippet if it has one.
+pub(crate) fn plain_text_edit(edit: Edit) -> lsp::TextEdit {
+    match edit {
+        Edit::Plain(edit) => edit,
+        Edit::Annotated(edit) => edit.text_edit,
+        Edit::Snippet(edit) => lsp::TextEdit {
+            range: edit.range,
+            new_text: Snippet::parse(&edit.snippet.value)
+                .map_or(edit.snippet.value, |snippet| snippet.text),
+        },
+    }
+}
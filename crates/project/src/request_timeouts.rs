@@ -0,0 +1,57 @@
+//! Per-method deadlines of the requests to the language servers, configured with `lsp_request_timeouts`.
+//!
+//! The language servers cancel the requests that time out with `$/cancelRequest`, and the project
+//! notifies the user about them, so that a hanging server doesn't go unnoticed.
+
+use futures::{channel::mpsc, StreamExt as _};
+use gpui::ModelContext;
+use lsp::LanguageServer;
+use settings::Settings;
+
+use crate::{project_settings::ProjectSettings, Event, LanguageServerState, Project};
+
+impl Project {
+    /// Applies the configured timeouts to the newly started server, and reports its timed out requests.
+    pub(crate) fn watch_request_timeouts(
+        &self,
+        language_server: &LanguageServer,
+        cx: &mut ModelContext<Self>,
+    ) {
+        let timeouts = ProjectSettings::get_global(cx)
+            .lsp_request_timeouts
+            .request_timeouts();
+        language_server.set_request_timeouts(timeouts);
+
+        let (timed_out_tx, mut timed_out_rx) = mpsc::unbounded();
+        language_server.on_request_timeout(move |method, timeout| {
+            timed_out_tx.unbounded_send((method, timeout)).ok();
+        });
+        let server_name = language_server.name().to_string();
+        cx.spawn(|this, mut cx| async move {
+            while let Some((method, timeout)) = timed_out_rx.next().await {
+                let message = format!(
+                    "Language server {server_name} did not respond to {method} within {timeout:?}. The request was cancelled."
+                );
+                if this
+                    .update(&mut cx, |_, cx| cx.emit(Event::Notification(message)))
+                    .is_err()
+                {
+                    break;
+                }
+            }
+        })
+        .detach();
+    }
+
+    /// Applies the changed timeout settings to the running servers.
+    pub(crate) fn refresh_request_timeouts(&self, cx: &ModelContext<Self>) {
+        let timeouts = ProjectSettings::get_global(cx)
+            .lsp_request_timeouts
+            .request_timeouts();
+        for state in self.language_servers.values() {
+            if let LanguageServerState::Running { server, .. } = state {
+                server.set_request_timeouts(timeouts.clone());
+            }
+        }
+    }
+}
@@ -15,6 +15,44 @@ use util::paths::PathMatcher;
 
 static TEXT_REPLACEMENT_SPECIAL_CHARACTERS_REGEX: OnceLock<Regex> = OnceLock::new();
 
+/// The caps on the results of a project search, past which it stops and reports that the limit was reached.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct SearchLimits {
+    pub max_files: usize,
+    pub max_ranges: usize,
+}
+
+impl Default for SearchLimits {
+    fn default() -> Self {
+        Self {
+            max_files: 5_000,
+            max_ranges: 10_000,
+        }
+    }
+}
+
+impl SearchLimits {
+    /// Returns limits twice as large, to load more results than a search that reached these limits.
+    pub fn doubled(self) -> Self {
+        Self {
+            max_files: self.max_files.saturating_mul(2),
+            max_ranges: self.max_ranges.saturating_mul(2),
+        }
+    }
+
+    pub fn from_proto(message: &proto::SearchProject) -> Self {
+        let default = Self::default();
+        Self {
+            max_files: message
+                .max_files
+                .map_or(default.max_files, |max_files| max_files as usize),
+            max_ranges: message
+                .max_ranges
+                .map_or(default.max_ranges, |max_ranges| max_ranges as usize),
+        }
+    }
+}
+
 #[derive(Clone, Debug)]
 pub struct SearchInputs {
     query: Arc<str>,
@@ -168,6 +206,8 @@ impl SearchQuery {
             include_ignored: self.include_ignored(),
             files_to_include: self.files_to_include().sources().join(","),
             files_to_exclude: self.files_to_exclude().sources().join(","),
+            max_files: None,
+            max_ranges: None,
         }
     }
 
@@ -0,0 +1,475 @@
+//! Support for semantic tokens: language servers classify the ranges of the document with
+//! `textDocument/semanticTokens/full`, `/full/delta` and `/range` requests, refining the highlighting done with tree-sitter.
+//!
+//! The tokens are decoded into anchor ranges in the project, so they are shared by all editors of the buffer
+//! and can be replicated to the guests, who request them from the host instead of the language server.
+
+use std::{ops::Range, sync::Arc};
+
+use anyhow::{anyhow, Context as _, Result};
+use client::TypedEnvelope;
+use collections::HashMap;
+use gpui::{AsyncAppContext, Model, ModelContext, Task};
+use language::{
+    point_from_lsp, point_to_lsp,
+    proto::{deserialize_anchor, deserialize_version, serialize_anchor, serialize_version},
+    Anchor, Bias, Buffer, BufferSnapshot, PointUtf16,
+};
+use lsp::{LanguageServer, LanguageServerId};
+use rpc::proto;
+use text::BufferId;
+
+use crate::{Event, File, Project};
+
+/// A range of the buffer, classified by a language server.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct SemanticToken {
+    pub range: Range<Anchor>,
+    /// One of the token types from the legend of the language server, e.g. `function` or `parameter`.
+    pub token_type: Arc<str>,
+    /// Token modifiers from the legend of the language server, e.g. `declaration` or `readonly`.
+    pub token_modifiers: Vec<Arc<str>>,
+}
+
+/// Semantic tokens of a buffer, sorted by their position.
+#[derive(Clone, Debug, Default)]
+pub struct BufferSemanticTokens {
+    /// The version of the buffer the tokens were computed for.
+    pub version: clock::Global,
+    pub tokens: Arc<[SemanticToken]>,
+}
+
+#[derive(Default)]
+pub(crate) struct SemanticTokensCache {
+    buffers: HashMap<BufferId, CachedSemanticTokens>,
+}
+
+struct CachedSemanticTokens {
+    server_id: LanguageServerId,
+    /// Result id of the last full response, used to request the delta from the server.
+    result_id: Option<String>,
+    /// The tokens as sent by the language server, kept to apply the deltas to.
+    data: Vec<lsp::SemanticToken>,
+    tokens: BufferSemanticTokens,
+    /// Set when the server asks to refresh the tokens, so they are requested again even if the buffer had not changed.
+    stale: bool,
+}
+
+enum SemanticTokensRequest {
+    Full,
+    Delta { previous_result_id: String },
+    Range,
+}
+
+enum SemanticTokensUpdate {
+    Replace {
+        result_id: Option<String>,
+        data: Vec<lsp::SemanticToken>,
+    },
+    Edit {
+        previous_result_id: String,
+        result_id: Option<String>,
+        edits: Vec<lsp::SemanticTokensEdit>,
+    },
+}
+
+impl SemanticTokensCache {
+    pub(crate) fn language_server_removed(&mut self, server_id: LanguageServerId) {
+        self.buffers
+            .retain(|_, cached| cached.server_id != server_id);
+    }
+
+    pub(crate) fn buffer_removed(&mut self, buffer_id: BufferId) {
+        self.buffers.remove(&buffer_id);
+    }
+
+    fn invalidate(&mut self, server_id: LanguageServerId) {
+        for cached in self.buffers.values_mut() {
+            if cached.server_id == server_id {
+                cached.stale = true;
+            }
+        }
+    }
+}
+
+impl Project {
+    /// Returns the semantic tokens of the buffer, from the first of its language servers that provides them.
+    ///
+    /// The tokens are cached until the buffer changes: after that, the delta is requested from the server, if it supports it.
+    pub fn semantic_tokens(
+        &mut self,
+        buffer: &Model<Buffer>,
+        cx: &mut ModelContext<Self>,
+    ) -> Task<Result<BufferSemanticTokens>> {
+        if let Some(project_id) = self.remote_id().filter(|_| !self.is_local()) {
+            let request = self.client.request(proto::GetSemanticTokens {
+                project_id,
+                buffer_id: buffer.read(cx).remote_id().to_proto(),
+                version: serialize_version(&buffer.read(cx).version()),
+            });
+            let buffer = buffer.clone();
+            return cx.spawn(move |_, mut cx| async move {
+                let response = request.await?;
+                let version = deserialize_version(&response.version);
+                buffer
+                    .update(&mut cx, |buffer, _| {
+                        buffer.wait_for_version(version.clone())
+                    })?
+                    .await?;
+                let tokens = response
+                    .tokens
+                    .into_iter()
+                    .filter_map(|token| {
+                        Some(SemanticToken {
+                            range: deserialize_anchor(token.start?)?
+                                ..deserialize_anchor(token.end?)?,
+                            token_type: token.token_type.into(),
+                            token_modifiers: token
+                                .token_modifiers
+                                .into_iter()
+                                .map(Arc::from)
+                                .collect(),
+                        })
+                    })
+                    .collect();
+                Ok(BufferSemanticTokens { version, tokens })
+            });
+        } else if !self.is_local() {
+            return Task::ready(Ok(BufferSemanticTokens::default()));
+        }
+
+        let buffer_id = buffer.read(cx).remote_id();
+        let Some(server) = self
+            .language_servers_for_buffer(buffer.read(cx), cx)
+            .map(|(_, server)| server)
+            .find(|server| semantic_tokens_options(&server.capabilities()).is_some())
+            .cloned()
+        else {
+            return Task::ready(Ok(BufferSemanticTokens::default()));
+        };
+        let server_id = server.server_id();
+        let snapshot = buffer.read(cx).snapshot();
+        let cached = self
+            .semantic_tokens
+            .buffers
+            .get(&buffer_id)
+            .filter(|cached| cached.server_id == server_id);
+        if let Some(cached) = cached {
+            if !cached.stale && &cached.tokens.version == snapshot.version() {
+                return Task::ready(Ok(cached.tokens.clone()));
+            }
+        }
+
+        let Some(options) = semantic_tokens_options(&server.capabilities()).cloned() else {
+            return Task::ready(Ok(BufferSemanticTokens::default()));
+        };
+        let request = match (&options.full, cached.and_then(|c| c.result_id.clone())) {
+            (
+                Some(lsp::SemanticTokensFullOptions::Delta { delta: Some(true) }),
+                Some(previous_result_id),
+            ) => SemanticTokensRequest::Delta { previous_result_id },
+            (Some(lsp::SemanticTokensFullOptions::Bool(false)) | None, _) => {
+                if options.range != Some(true) {
+                    return Task::ready(Ok(BufferSemanticTokens::default()));
+                }
+                SemanticTokensRequest::Range
+            }
+            _ => SemanticTokensRequest::Full,
+        };
+        let Some(uri) = File::from_dyn(buffer.read(cx).file())
+            .and_then(File::as_local)
+            .and_then(|file| lsp::Url::from_file_path(file.abs_path(cx)).ok())
+        else {
+            return Task::ready(Ok(BufferSemanticTokens::default()));
+        };
+
+        let buffer_range = lsp::Range::new(
+            point_to_lsp(PointUtf16::zero()),
+            point_to_lsp(snapshot.max_point_utf16()),
+        );
+        cx.spawn(move |this, mut cx| async move {
+            let update = request_semantic_tokens(server, uri, request, buffer_range).await?;
+            this.update(&mut cx, |this, _| {
+                let cached = this
+                    .semantic_tokens
+                    .buffers
+                    .get(&buffer_id)
+                    .filter(|cached| cached.server_id == server_id);
+                let (result_id, data) = match update {
+                    SemanticTokensUpdate::Replace { result_id, data } => (result_id, data),
+                    SemanticTokensUpdate::Edit {
+                        previous_result_id,
+                        result_id,
+                        edits,
+                    } => {
+                        let cached = cached
+                            .filter(|cached| cached.result_id.as_ref() == Some(&previous_result_id))
+                            .ok_or_else(|| {
+                                anyhow!("semantic tokens changed while requesting the delta")
+                            })?;
+                        let mut data = cached.data.clone();
+                        apply_semantic_tokens_edits(&mut data, edits)?;
+                        (result_id, data)
+                    }
+                };
+                let tokens = BufferSemanticTokens {
+                    version: snapshot.version().clone(),
+                    tokens: decode_semantic_tokens(&data, &options.legend, &snapshot).into(),
+                };
+                this.semantic_tokens.buffers.insert(
+                    buffer_id,
+                    CachedSemanticTokens {
+                        server_id,
+                        result_id,
+                        data,
+                        tokens: tokens.clone(),
+                        stale: false,
+                    },
+                );
+                Ok(tokens)
+            })?
+        })
+    }
+
+    /// Handles `workspace/semanticTokens/refresh` request: all editors are asked to request the tokens again.
+    pub(crate) fn refresh_semantic_tokens(
+        &mut self,
+        server_id: LanguageServerId,
+        cx: &mut ModelContext<Self>,
+    ) -> Result<()> {
+        self.semantic_tokens.invalidate(server_id);
+        cx.emit(Event::RefreshSemanticTokens);
+        if let Some(project_id) = self.remote_id() {
+            self.client
+                .send(proto::RefreshSemanticTokens { project_id })?;
+        }
+        Ok(())
+    }
+
+    pub(crate) async fn handle_get_semantic_tokens(
+        this: Model<Self>,
+        envelope: TypedEnvelope<proto::GetSemanticTokens>,
+        mut cx: AsyncAppContext,
+    ) -> Result<proto::GetSemanticTokensResponse> {
+        let buffer_id = BufferId::new(envelope.payload.buffer_id)?;
+        let buffer = this.update(&mut cx, |this, _| {
+            this.opened_buffers
+                .get(&buffer_id)
+                .and_then(|buffer| buffer.upgrade())
+                .ok_or_else(|| anyhow!("unknown buffer id {}", buffer_id))
+        })??;
+        buffer
+            .update(&mut cx, |buffer, _| {
+                buffer.wait_for_version(deserialize_version(&envelope.payload.version))
+            })?
+            .await?;
+        let semantic_tokens = this
+            .update(&mut cx, |this, cx| this.semantic_tokens(&buffer, cx))?
+            .await?;
+        Ok(proto::GetSemanticTokensResponse {
+            tokens: semantic_tokens
+                .tokens
+                .iter()
+                .map(|token| proto::SemanticToken {
+                    start: Some(serialize_anchor(&token.range.start)),
+                    end: Some(serialize_anchor(&token.range.end)),
+                    token_type: token.token_type.to_string(),
+                    token_modifiers: token
+                        .token_modifiers
+                        .iter()
+                        .map(|modifier| modifier.to_string())
+                        .collect(),
+                })
+                .collect(),
+            version: serialize_version(&semantic_tokens.version),
+        })
+    }
+
+    pub(crate) async fn handle_refresh_semantic_tokens(
+        this: Model<Self>,
+        _: TypedEnvelope<proto::RefreshSemanticTokens>,
+        mut cx: AsyncAppContext,
+    ) -> Result<proto::Ack> {
+        this.update(&mut cx, |_, cx| {
+            cx.emit(Event::RefreshSemanticTokens);
+        })?;
+        Ok(proto::Ack {})
+    }
+}
+
+fn semantic_tokens_options(
+    capabilities: &lsp::ServerCapabilities,
+) -> Option<&lsp::SemanticTokensOptions> {
+    match capabilities.semantic_tokens_provider.as_ref()? {
+        lsp::SemanticTokensServerCapabilities::SemanticTokensOptions(options) => Some(options),
+        lsp::SemanticTokensServerCapabilities::SemanticTokensRegistrationOptions(options) => {
+            Some(&options.semantic_tokens_options)
+        }
+    }
+}
+
+async fn request_semantic_tokens(
+    server: Arc<LanguageServer>,
+    uri: lsp::Url,
+    request: SemanticTokensRequest,
+    buffer_range: lsp::Range,
+) -> Result<SemanticTokensUpdate> {
+    let text_document = lsp::TextDocumentIdentifier::new(uri);
+    match request {
+        SemanticTokensRequest::Full => {
+            let response = server
+                .request::<lsp::request::SemanticTokensFullRequest>(lsp::SemanticTokensParams {
+                    work_done_progress_params: Default::default(),
+                    partial_result_params: Default::default(),
+                    text_document,
+                })
+                .await
+                .context("requesting semantic tokens")?;
+            Ok(match response {
+                Some(lsp::SemanticTokensResult::Tokens(tokens)) => SemanticTokensUpdate::Replace {
+                    result_id: tokens.result_id,
+                    data: tokens.data,
+                },
+                Some(lsp::SemanticTokensResult::Partial(partial)) => {
+                    SemanticTokensUpdate::Replace {
+                        result_id: None,
+                        data: partial.data,
+                    }
+                }
+                None => SemanticTokensUpdate::Replace {
+                    result_id: None,
+                    data: Vec::new(),
+                },
+            })
+        }
+        SemanticTokensRequest::Delta { previous_result_id } => {
+            let response = server
+                .request::<lsp::request::SemanticTokensFullDeltaRequest>(
+                    lsp::SemanticTokensDeltaParams {
+                        work_done_progress_params: Default::default(),
+                        partial_result_params: Default::default(),
+                        text_document,
+                        previous_result_id: previous_result_id.clone(),
+                    },
+                )
+                .await
+                .context("requesting semantic tokens delta")?;
+            Ok(match response {
+                Some(lsp::SemanticTokensFullDeltaResult::Tokens(tokens)) => {
+                    SemanticTokensUpdate::Replace {
+                        result_id: tokens.result_id,
+                        data: tokens.data,
+                    }
+                }
+                Some(lsp::SemanticTokensFullDeltaResult::TokensDelta(delta)) => {
+                    SemanticTokensUpdate::Edit {
+                        previous_result_id,
+                        result_id: delta.result_id,
+                        edits: delta.edits,
+                    }
+                }
+                Some(lsp::SemanticTokensFullDeltaResult::PartialTokensDelta { edits }) => {
+                    SemanticTokensUpdate::Edit {
+                        previous_result_id,
+                        result_id: None,
+                        edits,
+                    }
+                }
+                None => SemanticTokensUpdate::Replace {
+                    result_id: None,
+                    data: Vec::new(),
+                },
+            })
+        }
+        SemanticTokensRequest::Range => {
+            let response = server
+                .request::<lsp::request::SemanticTokensRangeRequest>(
+                    lsp::SemanticTokensRangeParams {
+                        work_done_progress_params: Default::default(),
+                        partial_result_params: Default::default(),
+                        text_document,
+                        range: buffer_range,
+                    },
+                )
+                .await
+                .context("requesting semantic tokens range")?;
+            let data = match response {
+                Some(lsp::SemanticTokensRangeResult::Tokens(tokens)) => tokens.data,
+                Some(lsp::SemanticTokensRangeResult::Partial(partial)) => partial.data,
+                None => Vec::new(),
+            };
+            Ok(SemanticTokensUpdate::Replace {
+                result_id: None,
+                data,
+            })
+        }
+    }
+}
+
+/// Applies the edits of a `textDocument/semanticTokens/full/delta` response. The edits index the integers
+/// of the encoded tokens, 5 per token, and refer to the tokens before any of them is applied.
+fn apply_semantic_tokens_edits(
+    data: &mut Vec<lsp::SemanticToken>,
+    mut edits: Vec<lsp::SemanticTokensEdit>,
+) -> Result<()> {
+    edits.sort_by_key(|edit| edit.start);
+    for edit in edits.into_iter().rev() {
+        if edit.start % 5 != 0 || edit.delete_count % 5 != 0 {
+            return Err(anyhow!(
+                "semantic tokens edit does not align with the tokens"
+            ));
+        }
+        let start = (edit.start / 5) as usize;
+        let end = start + (edit.delete_count / 5) as usize;
+        if end > data.len() {
+            return Err(anyhow!("semantic tokens edit is out of bounds"));
+        }
+        data.splice(start..end, edit.data.unwrap_or_default());
+    }
+    Ok(())
+}
+
+fn decode_semantic_tokens(
+    data: &[lsp::SemanticToken],
+    legend: &lsp::SemanticTokensLegend,
+    snapshot: &BufferSnapshot,
+) -> Vec<SemanticToken> {
+    let mut line = 0;
+    let mut start = 0;
+    let mut tokens = Vec::with_capacity(data.len());
+    for token in data {
+        if token.delta_line == 0 {
+            start += token.delta_start;
+        } else {
+            line += token.delta_line;
+            start = token.delta_start;
+        }
+        let Some(token_type) = legend.token_types.get(token.token_type as usize) else {
+            continue;
+        };
+        let token_modifiers = legend
+            .token_modifiers
+            .iter()
+            .enumerate()
+            .filter(|(ix, _)| {
+                *ix < u32::BITS as usize && token.token_modifiers_bitset & (1 << ix) != 0
+            })
+            .map(|(_, modifier)| Arc::from(modifier.as_str()))
+            .collect();
+        let range_start =
+            snapshot.clip_point_utf16(point_from_lsp(lsp::Position::new(line, start)), Bias::Left);
+        let range_end = snapshot.clip_point_utf16(
+            point_from_lsp(lsp::Position::new(line, start + token.length)),
+            Bias::Left,
+        );
+        if range_start == range_end {
+            continue;
+        }
+        tokens.push(SemanticToken {
+            range: snapshot.anchor_after(range_start)..snapshot.anchor_before(range_end),
+            token_type: Arc::from(token_type.as_str()),
+            token_modifiers,
+        });
+    }
+    tokens
+}
@@ -0,0 +1,201 @@
+//! Documents shown by language servers with `window/showDocument`, like the external documentation opened by
+//! rust-analyzer: files are opened in an editor with their range selected, and external documents in the browser,
+//! once the user agreed to it. Only web pages are opened in the browser.
+//!
+//! A dev server has no window to show them in, so it forwards them to the clients of its owner instead.
+
+use std::{path::Path, sync::Arc};
+
+use anyhow::{anyhow, Result};
+use gpui::{AsyncAppContext, Model, PromptLevel, WeakModel};
+use language::{
+    point_from_lsp, Bias, Buffer, CachedLspAdapter, PointUtf16, ToPointUtf16, Unclipped,
+};
+use lsp::{LanguageServerId, MessageActionItem};
+use rpc::{proto, TypedEnvelope};
+use worktree::WorktreeId;
+
+use crate::{Event, LanguageServerPromptRequest, Project, ProjectPath};
+
+const OPEN_ACTION: &str = "Open";
+const CANCEL_ACTION: &str = "Cancel";
+
+impl Project {
+    pub(crate) async fn on_lsp_show_document(
+        this: WeakModel<Self>,
+        params: lsp::ShowDocumentParams,
+        server_id: LanguageServerId,
+        adapter: Arc<CachedLspAdapter>,
+        mut cx: AsyncAppContext,
+    ) -> Result<lsp::ShowDocumentResult> {
+        let this = this.upgrade().ok_or_else(|| anyhow!("project closed"))?;
+        let forwarded_project_id = this.update(&mut cx, |this, _| {
+            this.dev_server_project_id.and_then(|_| this.remote_id())
+        })?;
+        let take_focus = params.take_focus.unwrap_or(false);
+
+        if params.external.unwrap_or(false) || is_web_url(&params.uri) {
+            if !is_web_url(&params.uri) {
+                log::warn!(
+                    "{} asked to open {}, which isn't a web page",
+                    adapter.name.0,
+                    params.uri
+                );
+                return Ok(lsp::ShowDocumentResult { success: false });
+            }
+            let success = if let Some(project_id) = forwarded_project_id {
+                this.update(&mut cx, |this, _| {
+                    this.client.send(proto::ShowDocument {
+                        project_id,
+                        external_url: Some(params.uri.to_string()),
+                        project_path: None,
+                        selection_start: None,
+                        selection_end: None,
+                        take_focus,
+                        language_server_name: adapter.name.0.to_string(),
+                    })
+                })??;
+                true
+            } else {
+                open_external_document(&this, &params.uri, adapter.name.0.to_string(), &mut cx)
+                    .await?
+            };
+            return Ok(lsp::ShowDocumentResult { success });
+        }
+
+        let buffer = this
+            .update(&mut cx, |this, cx| {
+                this.open_local_buffer_via_lsp(params.uri, server_id, adapter.name.clone(), cx)
+            })?
+            .await?;
+        let selection = buffer.update(&mut cx, |buffer, _| {
+            params.selection.map(|range| {
+                let start = buffer.clip_point_utf16(point_from_lsp(range.start), Bias::Left);
+                let end = buffer.clip_point_utf16(point_from_lsp(range.end), Bias::Left);
+                buffer.anchor_before(start)..buffer.anchor_after(end)
+            })
+        })?;
+        if let Some(project_id) = forwarded_project_id {
+            this.update(&mut cx, |this, cx| {
+                let buffer = buffer.read(cx);
+                let file = buffer
+                    .file()
+                    .ok_or_else(|| anyhow!("the shown document has no file"))?;
+                let selection = selection.map(|selection| {
+                    selection.start.to_point_utf16(buffer)..selection.end.to_point_utf16(buffer)
+                });
+                this.client.send(proto::ShowDocument {
+                    project_id,
+                    external_url: None,
+                    project_path: Some(proto::ProjectPath {
+                        worktree_id: WorktreeId::from_usize(file.worktree_id()).to_proto(),
+                        path: file.path().to_string_lossy().to_string(),
+                    }),
+                    selection_start: selection
+                        .as_ref()
+                        .map(|selection| serialize_point(selection.start)),
+                    selection_end: selection.map(|selection| serialize_point(selection.end)),
+                    take_focus,
+                    language_server_name: adapter.name.0.to_string(),
+                })
+            })??;
+        } else {
+            this.update(&mut cx, |_, cx| {
+                cx.emit(Event::ShowDocument {
+                    buffer,
+                    selection,
+                    take_focus,
+                })
+            })?;
+        }
+        Ok(lsp::ShowDocumentResult { success: true })
+    }
+
+    pub(crate) async fn handle_show_document(
+        this: Model<Self>,
+        envelope: TypedEnvelope<proto::ShowDocument>,
+        mut cx: AsyncAppContext,
+    ) -> Result<()> {
+        let payload = envelope.payload;
+        if let Some(url) = payload.external_url {
+            let url = lsp::Url::parse(&url)?;
+            open_external_document(&this, &url, payload.language_server_name, &mut cx).await?;
+            return Ok(());
+        }
+
+        let project_path = payload
+            .project_path
+            .ok_or_else(|| anyhow!("the shown document has no path"))?;
+        let project_path = ProjectPath {
+            worktree_id: WorktreeId::from_proto(project_path.worktree_id),
+            path: Path::new(&project_path.path).into(),
+        };
+        let buffer = this
+            .update(&mut cx, |this, cx| this.open_buffer(project_path, cx))?
+            .await?;
+        let selection = buffer.update(&mut cx, |buffer, _| {
+            let start = deserialize_point(payload.selection_start?, buffer);
+            let end = deserialize_point(payload.selection_end?, buffer);
+            Some(buffer.anchor_before(start)..buffer.anchor_after(end))
+        })?;
+        this.update(&mut cx, |_, cx| {
+            cx.emit(Event::ShowDocument {
+                buffer,
+                selection,
+                take_focus: payload.take_focus,
+            })
+        })?;
+        Ok(())
+    }
+}
+
+fn is_web_url(url: &lsp::Url) -> bool {
+    matches!(url.scheme(), "http" | "https")
+}
+
+/// Asks the user whether to open the web page in the browser, and opens it if they agree. Returns whether it was
+/// opened.
+async fn open_external_document(
+    this: &Model<Project>,
+    url: &lsp::Url,
+    lsp_name: String,
+    cx: &mut AsyncAppContext,
+) -> Result<bool> {
+    if !is_web_url(url) {
+        return Err(anyhow!("{url} isn't a web page"));
+    }
+    let (tx, rx) = smol::channel::bounded(1);
+    let prompt = LanguageServerPromptRequest {
+        level: PromptLevel::Info,
+        message: format!("{lsp_name} wants to open {url} in the browser."),
+        actions: [OPEN_ACTION, CANCEL_ACTION]
+            .into_iter()
+            .map(|title| MessageActionItem {
+                title: title.to_string(),
+                properties: Default::default(),
+            })
+            .collect(),
+        lsp_name,
+        response_channel: tx,
+    };
+    this.update(cx, |_, cx| cx.emit(Event::LanguageServerPrompt(prompt)))?;
+    let open = matches!(rx.recv().await, Ok(action) if action.title == OPEN_ACTION);
+    if open {
+        cx.update(|cx| cx.open_url(url.as_str()))?;
+    }
+    Ok(open)
+}
+
+fn serialize_point(point: PointUtf16) -> proto::PointUtf16 {
+    proto::PointUtf16 {
+        row: point.row,
+        column: point.column,
+    }
+}
+
+fn deserialize_point(point: proto::PointUtf16, buffer: &Buffer) -> PointUtf16 {
+    buffer.clip_point_utf16(
+        Unclipped(PointUtf16::new(point.row, point.column)),
+        Bias::Left,
+    )
+}
@@ -0,0 +1,389 @@
+//! Signature help: the signatures of the call around the cursor, with `textDocument/signatureHelp`. It's requested
+//! from all the language servers of the buffer, and their signatures are merged according to the `lsp_merging`
+//! settings, instead of only showing the ones of the first server to respond.
+
+use std::ops::Range;
+
+use collections::HashSet;
+use futures::future::join_all;
+use gpui::{AppContext, Model, ModelContext, Task};
+use language::{proto::serialize_version, Buffer, ToPointUtf16};
+use lsp::LanguageServerId;
+use rpc::proto;
+use serde::Serialize;
+use util::{debug_panic, ResultExt as _};
+
+use crate::{
+    lsp_command::{GetSignatureHelp, LspCommand},
+    Project,
+};
+
+/// The signatures of the call around a position.
+#[derive(Clone, Debug, PartialEq)]
+pub struct SignatureHelp {
+    pub signatures: Vec<SignatureInformation>,
+    /// The index of the signature shown first.
+    pub active_signature: usize,
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct SignatureInformation {
+    pub label: String,
+    pub documentation: Option<String>,
+    /// The byte ranges of the parameters in the label.
+    pub parameters: Vec<Range<usize>>,
+    /// The index of the parameter at the position, like the one being typed.
+    pub active_parameter: Option<usize>,
+}
+
+/// What signature help is requested for.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct SignatureHelpTrigger {
+    /// The trigger or retrigger character that was typed, if any.
+    pub character: Option<String>,
+    /// Whether signature help is already shown, and is requested again because the contents or the cursor changed.
+    pub is_retrigger: bool,
+}
+
+/// The characters after which the language servers of a buffer show signature help.
+#[derive(Clone, Debug, Default, PartialEq, Eq, Serialize)]
+pub struct SignatureHelpTriggers {
+    /// The characters that show signature help, like `(`.
+    pub trigger_characters: HashSet<String>,
+    /// The characters that request signature help again when it's shown, like `,` or `)`.
+    pub retrigger_characters: HashSet<String>,
+}
+
+impl SignatureHelpTriggers {
+    pub(crate) fn from_capabilities(capabilities: &lsp::ServerCapabilities) -> Self {
+        let mut triggers = Self::default();
+        triggers.extend(capabilities);
+        triggers
+    }
+
+    pub(crate) fn from_proto(server: &proto::LanguageServer) -> Self {
+        Self {
+            trigger_characters: server
+                .signature_help_trigger_characters
+                .iter()
+                .cloned()
+                .collect(),
+            retrigger_characters: server
+                .signature_help_retrigger_characters
+                .iter()
+                .cloned()
+                .collect(),
+        }
+    }
+
+    fn extend(&mut self, capabilities: &lsp::ServerCapabilities) {
+        if let Some(options) = &capabilities.signature_help_provider {
+            self.trigger_characters
+                .extend(options.trigger_characters.iter().flatten().cloned());
+            self.retrigger_characters
+                .extend(options.retrigger_characters.iter().flatten().cloned());
+        }
+    }
+}
+
+impl SignatureHelp {
+    pub(crate) fn from_lsp(signature_help: lsp::SignatureHelp) -> Option<Self> {
+        let signatures = signature_help
+            .signatures
+            .into_iter()
+            .map(|signature| {
+                let parameters = parameter_ranges(&signature.label, signature.parameters);
+                let active_parameter = signature
+                    .active_parameter
+                    .or(signature_help.active_parameter)
+                    .map(|parameter| parameter as usize)
+                    .filter(|parameter| *parameter < parameters.len());
+                SignatureInformation {
+                    documentation: signature.documentation.map(
+                        |documentation| match documentation {
+                            lsp::Documentation::String(text) => text,
+                            lsp::Documentation::MarkupContent(content) => content.value,
+                        },
+                    ),
+                    label: signature.label,
+                    parameters,
+                    active_parameter,
+                }
+            })
+            .collect::<Vec<_>>();
+        if signatures.is_empty() {
+            return None;
+        }
+        let active_signature = signature_help
+            .active_signature
+            .map_or(0, |signature| signature as usize)
+            .min(signatures.len() - 1);
+        Some(Self {
+            signatures,
+            active_signature,
+        })
+    }
+
+    pub(crate) fn to_proto(&self) -> proto::SignatureHelp {
+        proto::SignatureHelp {
+            signatures: self
+                .signatures
+                .iter()
+                .map(|signature| proto::SignatureInformation {
+                    label: signature.label.clone(),
+                    documentation: signature.documentation.clone(),
+                    parameters: signature
+                        .parameters
+                        .iter()
+                        .map(|parameter| proto::SignatureParameter {
+                            start: parameter.start as u64,
+                            end: parameter.end as u64,
+                        })
+                        .collect(),
+                    active_parameter: signature.active_parameter.map(|parameter| parameter as u64),
+                })
+                .collect(),
+            active_signature: self.active_signature as u64,
+        }
+    }
+
+    pub(crate) fn from_proto(signature_help: proto::SignatureHelp) -> Option<Self> {
+        let signatures = signature_help
+            .signatures
+            .into_iter()
+            .map(|signature| {
+                let parameters = signature
+                    .parameters
+                    .into_iter()
+                    .map(|parameter| parameter.start as usize..parameter.end as usize)
+                    .filter(|parameter| {
+                        parameter.start <= parameter.end
+                            && signature.label.is_char_boundary(parameter.start)
+                            && signature.label.is_char_boundary(parameter.end)
+                    })
+                    .collect::<Vec<_>>();
+                SignatureInformation {
+                    active_parameter: signature
+                        .active_parameter
+                        .map(|parameter| parameter as usize)
+                        .filter(|parameter| *parameter < parameters.len()),
+                    label: signature.label,
+                    documentation: signature.documentation,
+                    parameters,
+                }
+            })
+            .collect::<Vec<_>>();
+        if signatures.is_empty() {
+            return None;
+        }
+        let active_signature = (signature_help.active_signature as usize).min(signatures.len() - 1);
+        Some(Self {
+            signatures,
+            active_signature,
+        })
+    }
+}
+
+/// Returns the byte ranges of the parameters in the label of their signature. The labels of parameters are
+/// either a substring of the label of the signature, searched for after the previous parameter, or a range of
+/// UTF-16 offsets in it. The parameters that can't be found in the label get an empty range, so that the index
+/// of the active parameter still matches.
+fn parameter_ranges(
+    label: &str,
+    parameters: Option<Vec<lsp::ParameterInformation>>,
+) -> Vec<Range<usize>> {
+    let mut search_start = 0;
+    parameters
+        .unwrap_or_default()
+        .into_iter()
+        .map(|parameter| {
+            let range = match parameter.label {
+                lsp::ParameterLabel::Simple(parameter_label) => {
+                    label[search_start..].find(&parameter_label).map(|start| {
+                        search_start + start..search_start + start + parameter_label.len()
+                    })
+                }
+                lsp::ParameterLabel::LabelOffsets([start, end]) => {
+                    utf16_offset_to_byte(label, start as usize)
+                        .zip(utf16_offset_to_byte(label, end as usize))
+                        .map(|(start, end)| start..end)
+                }
+            };
+            match range {
+                Some(range) if range.start <= range.end => {
+                    search_start = range.end;
+                    range
+                }
+                _ => search_start..search_start,
+            }
+        })
+        .collect()
+}
+
+fn utf16_offset_to_byte(text: &str, offset_utf16: usize) -> Option<usize> {
+    let mut utf16_len = 0;
+    for (byte_offset, character) in text.char_indices() {
+        if utf16_len >= offset_utf16 {
+            return (utf16_len == offset_utf16).then_some(byte_offset);
+        }
+        utf16_len += character.len_utf16();
+    }
+    (utf16_len == offset_utf16).then_some(text.len())
+}
+
+impl Project {
+    /// Returns the signature help of the call around the position, merged from the language servers of the buffer.
+    pub fn signature_help<T: ToPointUtf16>(
+        &self,
+        buffer: &Model<Buffer>,
+        position: T,
+        trigger: SignatureHelpTrigger,
+        cx: &mut ModelContext<Self>,
+    ) -> Task<Option<SignatureHelp>> {
+        let position = position.to_point_utf16(buffer.read(cx));
+        let request = GetSignatureHelp { position, trigger };
+        if self.is_local() {
+            let signature_helps = self.request_multiple_lsp_locally(
+                buffer,
+                Some(position),
+                GetSignatureHelp::supports_signature_help,
+                request,
+                cx,
+            );
+            cx.spawn(|this, mut cx| async move {
+                let signature_helps = signature_helps
+                    .await
+                    .into_iter()
+                    .filter_map(|(server_id, signature_help)| Some((server_id, signature_help?)))
+                    .collect();
+                this.update(&mut cx, |this, cx| {
+                    this.merge_signature_helps(signature_helps, cx)
+                })
+                .ok()
+                .flatten()
+            })
+        } else if let Some(project_id) = self.remote_id() {
+            let request_task = self.client().request(proto::MultiLspQuery {
+                buffer_id: buffer.read(cx).remote_id().into(),
+                version: serialize_version(&buffer.read(cx).version()),
+                project_id,
+                strategy: Some(proto::multi_lsp_query::Strategy::All(
+                    proto::AllLanguageServers {},
+                )),
+                request: Some(proto::multi_lsp_query::Request::GetSignatureHelp(
+                    request.to_proto(project_id, buffer.read(cx)),
+                )),
+            });
+            let buffer = buffer.clone();
+            cx.spawn(|this, mut cx| async move {
+                let project = this.upgrade()?;
+                let signature_helps = join_all(
+                    request_task
+                        .await
+                        .log_err()
+                        .map(|response| response.responses)
+                        .unwrap_or_default()
+                        .into_iter()
+                        .filter_map(|lsp_response| match lsp_response.response? {
+                            proto::lsp_response::Response::GetSignatureHelpResponse(response) => {
+                                Some((LanguageServerId(lsp_response.server_id as usize), response))
+                            }
+                            unexpected => {
+                                debug_panic!("Unexpected response: {unexpected:?}");
+                                None
+                            }
+                        })
+                        .map(|(server_id, response)| {
+                            let signature_help = request.clone().response_from_proto(
+                                response,
+                                project.clone(),
+                                buffer.clone(),
+                                cx.clone(),
+                            );
+                            async move { Some((server_id, signature_help.await.log_err()??)) }
+                        }),
+                )
+                .await
+                .into_iter()
+                .flatten()
+                .collect();
+                project
+                    .update(&mut cx, |project, cx| {
+                        project.merge_signature_helps(signature_helps, cx)
+                    })
+                    .ok()
+                    .flatten()
+            })
+        } else {
+            log::error!("cannot show signature help: project does not have a remote id");
+            Task::ready(None)
+        }
+    }
+
+    /// Returns the characters declared by the language servers of the buffer to show signature help after.
+    ///
+    /// The servers of remote projects aren't known by buffer, so the ones of their buffers are the servers for
+    /// the language of the buffer, with the triggers the host shared when they started.
+    pub fn signature_help_triggers(
+        &self,
+        buffer: &Model<Buffer>,
+        cx: &AppContext,
+    ) -> SignatureHelpTriggers {
+        let mut triggers = SignatureHelpTriggers::default();
+        if !self.is_local() {
+            let Some(language) = buffer.read(cx).language() else {
+                return triggers;
+            };
+            let adapters = self.languages.lsp_adapters(language);
+            for status in self.language_server_statuses.values() {
+                if adapters
+                    .iter()
+                    .any(|adapter| *adapter.name.0 == *status.name)
+                {
+                    let server_triggers = &status.signature_help_triggers;
+                    triggers
+                        .trigger_characters
+                        .extend(server_triggers.trigger_characters.iter().cloned());
+                    triggers
+                        .retrigger_characters
+                        .extend(server_triggers.retrigger_characters.iter().cloned());
+                }
+            }
+            return triggers;
+        }
+        for (_, server) in self.language_servers_for_buffer(buffer.read(cx), cx) {
+            triggers.extend(server.capabilities());
+        }
+        triggers
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parameter_ranges() {
+        let label = "fn greet(name: &str, émoji: char)";
+        let parameters = parameter_ranges(
+            label,
+            Some(vec![
+                lsp::ParameterInformation {
+                    label: lsp::ParameterLabel::Simple("name: &str".into()),
+                    documentation: None,
+                },
+                lsp::ParameterInformation {
+                    label: lsp::ParameterLabel::LabelOffsets([21, 32]),
+                    documentation: None,
+                },
+            ]),
+        );
+        assert_eq!(
+            parameters
+                .iter()
+                .map(|range| &label[range.clone()])
+                .collect::<Vec<_>>(),
+            ["name: &str", "émoji: char"]
+        );
+    }
+}
@@ -0,0 +1,418 @@
+//! A fallback symbol index for the languages that have no language server: the outline queries of their grammars
+//! find the symbols of the files of the local worktrees, which are indexed as the worktrees are scanned and reindexed
+//! as the files change. The index answers project symbol search, and go to definition by the name of the symbol
+//! under the cursor.
+
+use std::{ops::Range, path::Path, sync::Arc};
+
+use anyhow::Result;
+use collections::{HashMap, HashSet};
+use fs::Fs;
+use fuzzy::CharBag;
+use gpui::{AppContext, BackgroundExecutor, Model, ModelContext, Task};
+use language::{
+    Bias, Buffer, CharKind, CodeLabel, Language, LanguageRegistry, LanguageServerName, Location,
+    PointUtf16, Rope, ToOffset, Unclipped,
+};
+use util::ResultExt;
+use worktree::{PathChange, ProjectEntryId, Worktree, WorktreeId};
+
+use crate::{File, LocationLink, Project, ProjectPath, Symbol};
+
+/// The name that takes the place of the language server name in the symbols of the index.
+pub(crate) const SYMBOL_INDEX_SERVER_NAME: &str = "symbol-index";
+
+/// Larger files are likely generated, and aren't indexed.
+const MAX_INDEXED_FILE_LEN: usize = 1024 * 1024;
+
+#[derive(Default)]
+pub(crate) struct SymbolIndex {
+    files: HashMap<ProjectPath, IndexedFile>,
+    /// The paths that changed since they were last indexed, by worktree.
+    pending_paths: HashMap<WorktreeId, HashSet<Arc<Path>>>,
+    indexing: HashMap<WorktreeId, Task<()>>,
+    /// The languages of the registry when the files were last indexed.
+    language_names: HashSet<String>,
+}
+
+struct IndexedFile {
+    language: Arc<Language>,
+    symbols: Vec<IndexedSymbol>,
+}
+
+#[derive(Debug)]
+struct IndexedSymbol {
+    name: String,
+    label: CodeLabel,
+    kind: lsp::SymbolKind,
+    range: Range<PointUtf16>,
+}
+
+impl SymbolIndex {
+    pub(crate) fn worktree_removed(&mut self, worktree_id: WorktreeId) {
+        self.files.retain(|path, _| path.worktree_id != worktree_id);
+        self.pending_paths.remove(&worktree_id);
+        self.indexing.remove(&worktree_id);
+    }
+}
+
+impl Project {
+    /// Reindexes the symbols of the changed files of a local worktree.
+    pub(crate) fn update_symbol_index(
+        &mut self,
+        worktree: &Model<Worktree>,
+        changes: &[(Arc<Path>, ProjectEntryId, PathChange)],
+        cx: &mut ModelContext<Self>,
+    ) {
+        if changes.is_empty() {
+            return;
+        }
+        self.enqueue_symbol_index_paths(
+            worktree,
+            changes.iter().map(|(path, _, _)| path.clone()),
+            cx,
+        );
+    }
+
+    /// Reindexes all the files of the local worktrees when languages are added to the registry, since the files of
+    /// the new languages were skipped when they were indexed.
+    pub(crate) fn reindex_symbols_of_new_languages(&mut self, cx: &mut ModelContext<Self>) {
+        if !self.is_local() {
+            return;
+        }
+        let language_names = self.languages.language_names();
+        let has_new_languages = language_names
+            .iter()
+            .any(|name| !self.symbol_index.language_names.contains(name));
+        self.symbol_index.language_names = language_names.into_iter().collect();
+        if !has_new_languages {
+            return;
+        }
+        let worktrees = self
+            .worktrees()
+            .filter(|worktree| worktree.read(cx).is_visible())
+            .collect::<Vec<_>>();
+        for worktree in worktrees {
+            let paths = worktree
+                .read(cx)
+                .files(false, 0)
+                .map(|entry| entry.path.clone())
+                .collect::<Vec<_>>();
+            self.enqueue_symbol_index_paths(&worktree, paths, cx);
+        }
+    }
+
+    fn enqueue_symbol_index_paths(
+        &mut self,
+        worktree: &Model<Worktree>,
+        paths: impl IntoIterator<Item = Arc<Path>>,
+        cx: &mut ModelContext<Self>,
+    ) {
+        let worktree_id = worktree.read(cx).id();
+        self.symbol_index
+            .pending_paths
+            .entry(worktree_id)
+            .or_default()
+            .extend(paths);
+        if !self.symbol_index.indexing.contains_key(&worktree_id) {
+            let indexing = self.index_pending_symbols(worktree.clone(), cx);
+            self.symbol_index.indexing.insert(worktree_id, indexing);
+        }
+    }
+
+    fn index_pending_symbols(
+        &mut self,
+        worktree: Model<Worktree>,
+        cx: &mut ModelContext<Self>,
+    ) -> Task<()> {
+        let fs = self.fs.clone();
+        let languages = self.languages.clone();
+        cx.spawn(move |this, mut cx| async move {
+            loop {
+                let Ok(Some((worktree_id, paths))) = this.update(&mut cx, |this, cx| {
+                    let worktree = worktree.read(cx);
+                    let worktree_id = worktree.id();
+                    let paths = this
+                        .symbol_index
+                        .pending_paths
+                        .remove(&worktree_id)
+                        .filter(|paths| !paths.is_empty());
+                    let Some(paths) = paths else {
+                        this.symbol_index.indexing.remove(&worktree_id);
+                        return None;
+                    };
+                    let paths = paths
+                        .into_iter()
+                        .map(|path| {
+                            let abs_path = worktree
+                                .entry_for_path(&path)
+                                .filter(|entry| {
+                                    worktree.is_visible()
+                                        && entry.is_file()
+                                        && !entry.is_ignored
+                                        && !entry.is_external
+                                        && !entry.is_private
+                                })
+                                .and_then(|_| worktree.absolutize(&path).ok());
+                            (path, abs_path)
+                        })
+                        .collect::<Vec<_>>();
+                    Some((worktree_id, paths))
+                }) else {
+                    return;
+                };
+
+                for (path, abs_path) in paths {
+                    let indexed_file = match abs_path {
+                        Some(abs_path) => index_file(
+                            fs.as_ref(),
+                            &languages,
+                            &abs_path,
+                            cx.background_executor().clone(),
+                        )
+                        .await
+                        .log_err()
+                        .flatten(),
+                        None => None,
+                    };
+                    let project_path = ProjectPath { worktree_id, path };
+                    let updated = this.update(&mut cx, |this, _| match indexed_file {
+                        Some(indexed_file) => {
+                            this.symbol_index.files.insert(project_path, indexed_file);
+                        }
+                        None => {
+                            this.symbol_index.files.remove(&project_path);
+                        }
+                    });
+                    if updated.is_err() {
+                        return;
+                    }
+                }
+            }
+        })
+    }
+
+    /// The symbols of the index whose names contain the characters of the query, for the languages that still have
+    /// no language server.
+    pub(crate) fn symbol_index_matches(&self, query: &str, cx: &AppContext) -> Vec<Symbol> {
+        let query = CharBag::from(query);
+        let mut symbols = Vec::new();
+        for (path, file) in &self.symbol_index.files {
+            if !self.languages.lsp_adapters(&file.language).is_empty()
+                || self
+                    .worktree_for_id(path.worktree_id, cx)
+                    .map_or(true, |worktree| !worktree.read(cx).is_visible())
+            {
+                continue;
+            }
+            for symbol in &file.symbols {
+                if !CharBag::from(symbol.name.as_str()).is_superset(query) {
+                    continue;
+                }
+                symbols.push(Symbol {
+                    language_server_name: LanguageServerName(SYMBOL_INDEX_SERVER_NAME.into()),
+                    source_worktree_id: path.worktree_id,
+                    path: path.clone(),
+                    label: symbol.label.clone(),
+                    name: symbol.name.clone(),
+                    kind: symbol.kind,
+                    range: Unclipped(symbol.range.start)..Unclipped(symbol.range.end),
+                    signature: self.symbol_signature(path),
+                    unresolved_symbol: None,
+                });
+            }
+        }
+        symbols
+    }
+
+    /// Falls back to the symbols of the index named like the word under the cursor, if the language servers found no
+    /// definitions because the language of the buffer has none.
+    pub(crate) fn definition_with_symbol_index_fallback(
+        &self,
+        buffer: &Model<Buffer>,
+        position: PointUtf16,
+        definitions: Task<Result<Vec<LocationLink>>>,
+        cx: &mut ModelContext<Self>,
+    ) -> Task<Result<Vec<LocationLink>>> {
+        if !self.is_local() {
+            return definitions;
+        }
+        let snapshot = buffer.read(cx).snapshot();
+        let Some(language) = snapshot.language() else {
+            return definitions;
+        };
+        if !self.languages.lsp_adapters(language).is_empty() {
+            return definitions;
+        }
+        let (word_range, kind) = snapshot.surrounding_word(position.to_offset(&snapshot));
+        if kind != Some(CharKind::Word) {
+            return definitions;
+        }
+        let word = snapshot
+            .text_for_range(word_range.clone())
+            .collect::<String>();
+
+        let buffer_path = File::from_dyn(snapshot.file()).map(|file| ProjectPath {
+            worktree_id: file.worktree_id(cx),
+            path: file.path.clone(),
+        });
+        let mut targets = self
+            .symbol_index
+            .files
+            .iter()
+            .filter(|(_, file)| Arc::ptr_eq(&file.language, language))
+            .flat_map(|(path, file)| {
+                file.symbols
+                    .iter()
+                    .filter(|symbol| symbol.name == word)
+                    .map(|symbol| (path.clone(), symbol.range.clone()))
+            })
+            .collect::<Vec<_>>();
+        if targets.is_empty() {
+            return definitions;
+        }
+        // The definitions of the buffer's own file come first.
+        targets.sort_by(|(path_a, range_a), (path_b, range_b)| {
+            (Some(path_a) != buffer_path.as_ref())
+                .cmp(&(Some(path_b) != buffer_path.as_ref()))
+                .then_with(|| path_a.cmp(path_b))
+                .then_with(|| range_a.start.cmp(&range_b.start))
+        });
+
+        let origin = Location {
+            buffer: buffer.clone(),
+            range: snapshot.anchor_before(word_range.start)..snapshot.anchor_after(word_range.end),
+        };
+        cx.spawn(move |this, mut cx| async move {
+            let definitions = definitions.await;
+            if matches!(&definitions, Ok(definitions) if !definitions.is_empty()) {
+                return definitions;
+            }
+            let mut links = Vec::new();
+            for (path, range) in targets {
+                let Some(target_buffer) = this
+                    .update(&mut cx, |this, cx| this.open_buffer(path, cx))?
+                    .await
+                    .log_err()
+                else {
+                    continue;
+                };
+                let range = target_buffer.read_with(&cx, |target_buffer, _| {
+                    let start = target_buffer.clip_point_utf16(Unclipped(range.start), Bias::Left);
+                    let end = target_buffer.clip_point_utf16(Unclipped(range.end), Bias::Left);
+                    target_buffer.anchor_after(start)..target_buffer.anchor_before(end)
+                })?;
+                links.push(LocationLink {
+                    origin: Some(origin.clone()),
+                    target: Location {
+                        buffer: target_buffer,
+                        range,
+                    },
+                });
+            }
+            Ok(links)
+        })
+    }
+}
+
+/// Finds the symbols of a file, if its language has no language server and an outline query.
+async fn index_file(
+    fs: &dyn Fs,
+    languages: &Arc<LanguageRegistry>,
+    abs_path: &Path,
+    executor: BackgroundExecutor,
+) -> Result<Option<IndexedFile>> {
+    let Ok(language) = languages.language_for_file_path(abs_path).await else {
+        return Ok(None);
+    };
+    if !languages.lsp_adapters(&language).is_empty() {
+        return Ok(None);
+    }
+    let Some(grammar) = language
+        .grammar()
+        .filter(|grammar| grammar.outline_config.is_some())
+        .cloned()
+    else {
+        return Ok(None);
+    };
+    let text = fs.load(abs_path).await?;
+    if text.len() > MAX_INDEXED_FILE_LEN {
+        return Ok(None);
+    }
+
+    let symbols = executor
+        .spawn(async move {
+            let text = Rope::from(text.as_str());
+            grammar
+                .outline_items_for_text(&text)
+                .into_iter()
+                .filter_map(|item| {
+                    let name_range = item.name_ranges.last()?.clone();
+                    let name = item.text.get(name_range.clone())?.to_string();
+                    let kind = symbol_kind(&item.text[..item.name_ranges[0].start], item.depth);
+                    let range = text.point_to_point_utf16(item.range.start)
+                        ..text.point_to_point_utf16(item.range.end);
+                    Some(IndexedSymbol {
+                        name,
+                        label: CodeLabel {
+                            text: item.text,
+                            runs: Vec::new(),
+                            filter_range: name_range,
+                        },
+                        kind,
+                        range,
+                    })
+                })
+                .collect::<Vec<_>>()
+        })
+        .await;
+    Ok(Some(IndexedFile { language, symbols }))
+}
+
+/// Guesses the kind of a symbol from the keywords before its name in its outline item, like the `fn` of `fn main`.
+fn symbol_kind(context: &str, depth: usize) -> lsp::SymbolKind {
+    for keyword in context.split_whitespace().rev() {
+        let kind = match keyword {
+            "fn" | "func" | "function" | "def" | "defp" | "sub" | "proc" => {
+                if depth > 0 {
+                    lsp::SymbolKind::METHOD
+                } else {
+                    lsp::SymbolKind::FUNCTION
+                }
+            }
+            "class" | "object" => lsp::SymbolKind::CLASS,
+            "struct" | "record" | "union" => lsp::SymbolKind::STRUCT,
+            "enum" => lsp::SymbolKind::ENUM,
+            "trait" | "interface" | "protocol" => lsp::SymbolKind::INTERFACE,
+            "mod" | "module" | "namespace" | "package" => lsp::SymbolKind::MODULE,
+            "impl" | "extension" => lsp::SymbolKind::OBJECT,
+            "const" | "static" => lsp::SymbolKind::CONSTANT,
+            "let" | "var" | "val" => lsp::SymbolKind::VARIABLE,
+            "type" | "typedef" => lsp::SymbolKind::TYPE_PARAMETER,
+            "macro" | "macro_rules!" => lsp::SymbolKind::FUNCTION,
+            _ => continue,
+        };
+        return kind;
+    }
+    if depth > 0 {
+        lsp::SymbolKind::FIELD
+    } else {
+        lsp::SymbolKind::VARIABLE
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::symbol_kind;
+
+    #[test]
+    fn test_symbol_kind() {
+        assert_eq!(symbol_kind("fn ", 0), lsp::SymbolKind::FUNCTION);
+        assert_eq!(symbol_kind("pub async fn ", 1), lsp::SymbolKind::METHOD);
+        assert_eq!(
+            symbol_kind("export default class ", 0),
+            lsp::SymbolKind::CLASS
+        );
+        assert_eq!(symbol_kind("", 1), lsp::SymbolKind::FIELD);
+    }
+}
@@ -0,0 +1,224 @@
+//! Diagnostics of tasks: the problems that the problem matchers of a running task find in its terminal output are
+//! shown as diagnostics of the project, next to the ones of the language servers. Each run of a task gets its own
+//! diagnostics server id, so its diagnostics are replaced as the output of a watch task changes, and cleared when
+//! the task is rerun or its terminal is closed.
+
+use std::{
+    path::{Path, PathBuf},
+    sync::Arc,
+    time::Duration,
+};
+
+use collections::{HashMap, HashSet};
+use gpui::{Entity, EntityId, Model, ModelContext, Subscription, Task};
+use language::{Diagnostic, DiagnosticEntry, PointUtf16, Unclipped};
+use lsp::{DiagnosticSeverity, LanguageServerId};
+use task::{CompiledProblemMatcher, Problem, ProblemMatcher, ProblemSeverity, TaskId};
+use terminal::Terminal;
+use util::{post_inc, ResultExt};
+
+use crate::Project;
+
+/// How long the output of a task has to stay unchanged before it's scanned for problems again.
+const TASK_OUTPUT_SCAN_DEBOUNCE: Duration = Duration::from_millis(300);
+
+#[derive(Default)]
+pub(crate) struct TaskDiagnostics {
+    watchers: HashMap<EntityId, TaskOutputWatcher>,
+}
+
+/// The task of a terminal whose output is scanned for problems.
+pub(crate) struct WatchedTask {
+    pub id: TaskId,
+    pub label: String,
+    pub problem_matchers: Vec<ProblemMatcher>,
+    /// The directory that the relative paths of the problems are resolved against.
+    pub cwd: Option<PathBuf>,
+}
+
+struct TaskOutputWatcher {
+    task_id: TaskId,
+    server_id: LanguageServerId,
+    label: String,
+    matchers: Arc<Vec<CompiledProblemMatcher>>,
+    cwd: Option<PathBuf>,
+    reported_paths: HashSet<PathBuf>,
+    scan: Task<()>,
+    _subscriptions: [Subscription; 2],
+}
+
+impl Project {
+    /// Starts showing the problems that the problem matchers of the task find in the output of its terminal as
+    /// diagnostics, replacing the diagnostics of the previous run of the same task.
+    pub(crate) fn watch_task_output(
+        &mut self,
+        terminal: &Model<Terminal>,
+        task: WatchedTask,
+        cx: &mut ModelContext<Self>,
+    ) {
+        let matchers = task
+            .problem_matchers
+            .iter()
+            .filter_map(|matcher| matcher.compile().log_err())
+            .flatten()
+            .collect::<Vec<_>>();
+        if matchers.is_empty() {
+            return;
+        }
+
+        let previous_runs = self
+            .task_diagnostics
+            .watchers
+            .iter()
+            .filter(|(_, watcher)| watcher.task_id == task.id)
+            .map(|(terminal_id, _)| *terminal_id)
+            .collect::<Vec<_>>();
+        for terminal_id in previous_runs {
+            self.stop_watching_task_output(terminal_id, cx);
+        }
+
+        let terminal_id = terminal.entity_id();
+        let subscriptions = [
+            cx.subscribe(terminal, |project, terminal, event, cx| {
+                if matches!(event, terminal::Event::Wakeup) {
+                    project.schedule_task_output_scan(terminal, cx);
+                }
+            }),
+            cx.observe_release(terminal, move |project, _, cx| {
+                project.stop_watching_task_output(terminal_id, cx);
+            }),
+        ];
+        self.task_diagnostics.watchers.insert(
+            terminal_id,
+            TaskOutputWatcher {
+                task_id: task.id,
+                server_id: self.languages.next_language_server_id(),
+                label: task.label,
+                matchers: Arc::new(matchers),
+                cwd: task.cwd,
+                reported_paths: HashSet::default(),
+                scan: Task::ready(()),
+                _subscriptions: subscriptions,
+            },
+        );
+    }
+
+    fn stop_watching_task_output(&mut self, terminal_id: EntityId, cx: &mut ModelContext<Self>) {
+        if let Some(watcher) = self.task_diagnostics.watchers.remove(&terminal_id) {
+            for abs_path in watcher.reported_paths {
+                self.update_diagnostic_entries(watcher.server_id, abs_path, None, Vec::new(), cx)
+                    .log_err();
+            }
+        }
+    }
+
+    fn schedule_task_output_scan(
+        &mut self,
+        terminal: Model<Terminal>,
+        cx: &mut ModelContext<Self>,
+    ) {
+        let terminal_id = terminal.entity_id();
+        let Some(watcher) = self.task_diagnostics.watchers.get_mut(&terminal_id) else {
+            return;
+        };
+        let matchers = watcher.matchers.clone();
+        watcher.scan = cx.spawn(|project, mut cx| async move {
+            cx.background_executor()
+                .timer(TASK_OUTPUT_SCAN_DEBOUNCE)
+                .await;
+            let Some(output) = terminal
+                .update(&mut cx, |terminal, cx| terminal.output_text(cx))
+                .ok()
+            else {
+                return;
+            };
+            let output = output.await;
+            let problems = cx
+                .background_executor()
+                .spawn(async move {
+                    matchers
+                        .iter()
+                        .flat_map(|matcher| matcher.problems(&output))
+                        .collect::<Vec<_>>()
+                })
+                .await;
+            project
+                .update(&mut cx, |project, cx| {
+                    project.publish_task_problems(terminal_id, problems, cx)
+                })
+                .ok();
+        });
+    }
+
+    fn publish_task_problems(
+        &mut self,
+        terminal_id: EntityId,
+        problems: Vec<Problem>,
+        cx: &mut ModelContext<Self>,
+    ) {
+        let Some(watcher) = self.task_diagnostics.watchers.get(&terminal_id) else {
+            return;
+        };
+        let server_id = watcher.server_id;
+        let mut diagnostics_by_path =
+            HashMap::<PathBuf, Vec<DiagnosticEntry<Unclipped<PointUtf16>>>>::default();
+        for problem in problems {
+            let abs_path = problem_abs_path(&problem.path, watcher.cwd.as_deref());
+            let start = PointUtf16::new(problem.start.0, problem.start.1);
+            let end = problem
+                .end
+                .map_or(start, |(row, column)| PointUtf16::new(row, column));
+            let severity = match problem.severity {
+                ProblemSeverity::Error => DiagnosticSeverity::ERROR,
+                ProblemSeverity::Warning => DiagnosticSeverity::WARNING,
+                ProblemSeverity::Information => DiagnosticSeverity::INFORMATION,
+                ProblemSeverity::Hint => DiagnosticSeverity::HINT,
+            };
+            diagnostics_by_path
+                .entry(abs_path)
+                .or_default()
+                .push(DiagnosticEntry {
+                    range: Unclipped(start)..Unclipped(end),
+                    diagnostic: Diagnostic {
+                        source: Some(problem.source.unwrap_or_else(|| watcher.label.clone())),
+                        code: problem.code,
+                        severity,
+                        message: problem.message,
+                        group_id: post_inc(&mut self.next_diagnostic_group_id),
+                        is_primary: true,
+                        // The tasks check the files on disk, so unsaved edits shift their diagnostics.
+                        is_disk_based: true,
+                        is_unnecessary: false,
+                    },
+                });
+        }
+        diagnostics_by_path.retain(|abs_path, _| self.find_local_worktree(abs_path, cx).is_some());
+
+        let Some(watcher) = self.task_diagnostics.watchers.get_mut(&terminal_id) else {
+            return;
+        };
+        let fixed_paths = watcher
+            .reported_paths
+            .iter()
+            .filter(|abs_path| !diagnostics_by_path.contains_key(*abs_path))
+            .cloned()
+            .collect::<Vec<_>>();
+        watcher.reported_paths = diagnostics_by_path.keys().cloned().collect();
+        for abs_path in fixed_paths {
+            self.update_diagnostic_entries(server_id, abs_path, None, Vec::new(), cx)
+                .log_err();
+        }
+        for (abs_path, diagnostics) in diagnostics_by_path {
+            self.update_diagnostic_entries(server_id, abs_path, None, diagnostics, cx)
+                .log_err();
+        }
+    }
+}
+
+fn problem_abs_path(path: &str, cwd: Option<&Path>) -> PathBuf {
+    let path = Path::new(path);
+    match cwd {
+        Some(cwd) if path.is_relative() => cwd.join(path),
+        _ => path.to_path_buf(),
+    }
+}
@@ -0,0 +1,212 @@
+//! Task variables that language servers provide: the function containing the cursor, from the document symbols
+//! of the buffer, and the test and the crate containing it, from the runnables that rust-analyzer reports.
+
+use std::time::Duration;
+
+use futures::{select_biased, FutureExt as _};
+use gpui::{AppContext, Model, ModelContext, Task};
+use language::{Anchor, Buffer, Location, ToOffset as _, ToPointUtf16 as _};
+use lsp::{LanguageServerId, SymbolKind};
+use task::{TaskVariables, VariableName};
+use text::BufferSnapshot;
+use util::ResultExt;
+
+use crate::{
+    lsp_ext_command::{BufferRunnable, Runnables},
+    DocumentSymbol, LanguageServerToQuery, Project,
+};
+
+const RUST_ANALYZER_NAME: &str = "rust-analyzer";
+
+/// How long the tasks wait for the language servers to provide their variables, after which they're spawned with
+/// the other variables only.
+pub(crate) const LSP_TASK_VARIABLES_TIMEOUT: Duration = Duration::from_secs(1);
+
+impl Project {
+    /// Queries the language servers of the buffer for the task variables at the start of the location. None are
+    /// provided if the servers don't respond in time.
+    pub(crate) fn lsp_task_variables(
+        &mut self,
+        location: &Location,
+        cx: &mut ModelContext<Self>,
+    ) -> Task<TaskVariables> {
+        let buffer = location.buffer.clone();
+        let position = location.range.start;
+        let symbols = self.document_symbols(&buffer, cx);
+        let runnables = self.rust_analyzer_for_buffer(&buffer, cx).map(|server_id| {
+            let position = position.to_point_utf16(buffer.read(cx));
+            self.request_lsp(
+                buffer.clone(),
+                LanguageServerToQuery::Other(server_id),
+                Runnables {
+                    position: Some(position),
+                },
+                cx,
+            )
+        });
+
+        let timeout = cx.background_executor().timer(LSP_TASK_VARIABLES_TIMEOUT);
+        let task_variables = cx.spawn(move |_, cx| async move {
+            let mut task_variables = TaskVariables::default();
+            if let Some(symbols) = symbols.await.log_err() {
+                let function = buffer
+                    .read_with(&cx, |buffer, _| {
+                        enclosing_function(&symbols.symbols, position, &buffer.snapshot())
+                    })
+                    .ok()
+                    .flatten();
+                if let Some(function) = function {
+                    task_variables.insert(VariableName::Symbol, function);
+                }
+            }
+            if let Some(runnables) = runnables {
+                if let Some(runnables) = runnables.await.log_err() {
+                    if let Some(test_name) = test_name(&runnables) {
+                        task_variables.insert(VariableName::TestName, test_name);
+                    }
+                    if let Some(crate_name) = crate_name(&runnables) {
+                        task_variables.insert(VariableName::CrateName, crate_name);
+                    }
+                }
+            }
+            task_variables
+        });
+        cx.background_executor().spawn(async move {
+            select_biased! {
+                task_variables = task_variables.fuse() => task_variables,
+                _ = timeout.fuse() => {
+                    log::warn!("timed out waiting for the task variables of the language servers");
+                    TaskVariables::default()
+                }
+            }
+        })
+    }
+
+    fn rust_analyzer_for_buffer(
+        &self,
+        buffer: &Model<Buffer>,
+        cx: &AppContext,
+    ) -> Option<LanguageServerId> {
+        self.language_servers_for_buffer(buffer.read(cx), cx)
+            .find(|(adapter, _)| adapter.name.0.as_ref() == RUST_ANALYZER_NAME)
+            .map(|(_, server)| server.server_id())
+    }
+}
+
+/// Returns the name of the innermost function, method or constructor containing the position.
+fn enclosing_function(
+    symbols: &[DocumentSymbol],
+    position: Anchor,
+    snapshot: &BufferSnapshot,
+) -> Option<String> {
+    let offset = position.to_offset(snapshot);
+    let mut function = None;
+    let mut symbols = symbols;
+    while let Some(symbol) = symbols.iter().find(|symbol| {
+        symbol.range.start.to_offset(snapshot) <= offset
+            && offset <= symbol.range.end.to_offset(snapshot)
+    }) {
+        if matches!(
+            symbol.kind,
+            SymbolKind::FUNCTION | SymbolKind::METHOD | SymbolKind::CONSTRUCTOR
+        ) {
+            function = Some(symbol.name.clone());
+        }
+        symbols = &symbol.children;
+    }
+    function
+}
+
+/// Returns the name of the test containing the position the runnables were queried at, or the name of the
+/// innermost test module containing it if it isn't in a test.
+fn test_name(runnables: &[BufferRunnable]) -> Option<String> {
+    let test = runnables.iter().find_map(|runnable| {
+        runnable
+            .label
+            .strip_prefix("test ")
+            .or_else(|| runnable.label.strip_prefix("doctest "))
+    });
+    let test = test.or_else(|| {
+        runnables
+            .iter()
+            .filter_map(|runnable| runnable.label.strip_prefix("test-mod "))
+            .max_by_key(|module| module.len())
+    });
+    Some(test?.to_string())
+}
+
+/// Returns the package that the cargo runnables are run in.
+fn crate_name(runnables: &[BufferRunnable]) -> Option<String> {
+    runnables.iter().find_map(|runnable| {
+        let args = &runnable.task.args;
+        let package = args
+            .iter()
+            .position(|arg| arg == "--package" || arg == "-p")?;
+        args.get(package + 1).cloned()
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use task::TaskTemplate;
+
+    use super::{crate_name, test_name};
+    use crate::lsp_ext_command::BufferRunnable;
+
+    fn runnable(label: &str, args: &[&str]) -> BufferRunnable {
+        BufferRunnable {
+            label: label.to_string(),
+            position: None,
+            task: TaskTemplate {
+                label: label.to_string(),
+                command: "cargo".to_string(),
+                args: args.iter().map(ToString::to_string).collect(),
+                ..TaskTemplate::default()
+            },
+        }
+    }
+
+    #[test]
+    fn test_runnable_task_variables() {
+        let module = runnable(
+            "test-mod tests",
+            &["test", "--package", "project", "--lib", "--", "tests"],
+        );
+        let nested_module = runnable(
+            "test-mod tests::tasks",
+            &[
+                "test",
+                "--package",
+                "project",
+                "--lib",
+                "--",
+                "tests::tasks",
+            ],
+        );
+        let test = runnable(
+            "test tests::tasks::test_task_contexts",
+            &[
+                "test",
+                "--package",
+                "project",
+                "--lib",
+                "--",
+                "tests::tasks::test_task_contexts",
+                "--exact",
+            ],
+        );
+
+        assert_eq!(
+            test_name(&[module.clone(), nested_module.clone(), test.clone()]),
+            Some("tests::tasks::test_task_contexts".to_string())
+        );
+        assert_eq!(
+            test_name(&[module, nested_module]),
+            Some("tests::tasks".to_string())
+        );
+        assert_eq!(test_name(&[runnable("run main", &["run"])]), None);
+
+        assert_eq!(crate_name(&[test]), Some("project".to_string()));
+        assert_eq!(crate_name(&[runnable("run main", &["run"])]), None);
+    }
+}
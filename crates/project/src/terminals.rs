@@ -1,4 +1,4 @@
-use crate::Project;
+use crate::{task_diagnostics::WatchedTask, Project};
 use anyhow::Context as _;
 use collections::HashMap;
 use gpui::{
@@ -85,6 +85,18 @@ impl Project {
                 .or_else(|| task_cwd.and_then(|spawn_cwd| self.find_local_worktree(&spawn_cwd, cx)))
         };
 
+        if spawn_task.is_some() {
+            if let Some((worktree, _)) = &worktree {
+                let worktree_id = worktree.read(cx).id();
+                if !self.check_worktree_trust(worktree_id, cx) {
+                    anyhow::bail!(
+                        "Tasks can't run in {:?} until it is trusted",
+                        worktree.read(cx).abs_path()
+                    );
+                }
+            }
+        }
+
         let settings_location = worktree.as_ref().map(|(worktree, path)| SettingsLocation {
             worktree_id: worktree.read(cx).id().to_usize(),
             path,
@@ -95,6 +107,29 @@ impl Project {
                 .as_ref()
                 .map_or(true, |work_dir| work_dir.is_local());
         let settings = TerminalSettings::get(settings_location, cx);
+        let watched_task = spawn_task
+            .as_ref()
+            .filter(|spawn_task| self.is_local() && !spawn_task.problem_matchers.is_empty())
+            .map(|spawn_task| WatchedTask {
+                id: spawn_task.id.clone(),
+                label: spawn_task.label.clone(),
+                problem_matchers: spawn_task.problem_matchers.clone(),
+                cwd: spawn_task
+                    .cwd
+                    .as_ref()
+                    .or(working_directory.as_ref())
+                    .and_then(|cwd| cwd.local_path())
+                    .map(ToOwned::to_owned),
+            });
+        let ports_terminal_label = working_directory
+            .as_ref()
+            .filter(|work_dir| !work_dir.is_local())
+            .map(|_| {
+                spawn_task.as_ref().map_or_else(
+                    || "Terminal".to_string(),
+                    |spawn_task| spawn_task.label.clone(),
+                )
+            });
         let python_settings = settings.detect_venv.clone();
         let (completion_tx, completion_rx) = bounded(1);
 
@@ -203,6 +238,13 @@ impl Project {
             })
             .detach();
 
+            if let Some(watched_task) = watched_task {
+                self.watch_task_output(&terminal_handle, watched_task, cx);
+            }
+            if let Some(label) = ports_terminal_label {
+                self.detect_terminal_ports(&terminal_handle, label, cx);
+            }
+
             // if the terminal is not a task, activate full Python virtual environment
             if is_terminal {
                 if let Some(python_settings) = &python_settings.as_option() {
@@ -366,7 +408,10 @@ fn prepare_ssh_shell(
     Ok(Shell::WithArguments { program, args })
 }
 
-fn add_environment_path(env: &mut HashMap<String, String>, new_path: &Path) -> anyhow::Result<()> {
+pub(crate) fn add_environment_path(
+    env: &mut HashMap<String, String>,
+    new_path: &Path,
+) -> anyhow::Result<()> {
     let mut env_paths = vec![new_path.to_path_buf()];
     if let Some(path) = env.get("PATH").or(env::var("PATH").ok().as_ref()) {
         let mut paths = std::env::split_paths(&path).collect::<Vec<_>>();
@@ -0,0 +1,158 @@
+//! The diagnostics of [Vale](https://vale.sh), a prose linter, from its JSON output: `vale --output=JSON`.
+
+use anyhow::{Context as _, Result};
+use collections::HashMap;
+use language::{Point, PointUtf16};
+use lsp::DiagnosticSeverity;
+use serde::Deserialize;
+use text::Rope;
+
+use crate::{DiagnosticFix, SourceDiagnostic};
+
+/// A problem reported by Vale.
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "PascalCase")]
+struct ValeAlert {
+    #[serde(default)]
+    action: ValeAction,
+    /// The one-based columns, in characters, of the first and the last characters of the problem.
+    span: (u32, u32),
+    /// The rule that reported the problem, like `Vale.Spelling`.
+    check: String,
+    message: String,
+    severity: String,
+    /// The text of the problem.
+    #[serde(rename = "Match")]
+    matched: String,
+    /// The one-based row of the problem.
+    line: u32,
+}
+
+/// How Vale suggests to fix a problem.
+#[derive(Debug, Default, Deserialize)]
+#[serde(rename_all = "PascalCase")]
+struct ValeAction {
+    name: String,
+    params: Option<Vec<String>>,
+}
+
+/// Parses the JSON output of Vale for the text it checked, turning its suggested replacements and removals
+/// into fixes.
+pub(crate) fn parse_vale_output(output: &[u8], text: &Rope) -> Result<Vec<SourceDiagnostic>> {
+    let alerts = serde_json::from_slice::<HashMap<String, Vec<ValeAlert>>>(output)
+        .context("parsing the output of vale")?;
+    Ok(alerts
+        .into_values()
+        .flatten()
+        .map(|alert| {
+            let row = alert.line.saturating_sub(1);
+            let start = point_for_column(text, row, alert.span.0.saturating_sub(1));
+            let end = point_for_column(text, row, alert.span.1);
+            let severity = match alert.severity.as_str() {
+                "error" => DiagnosticSeverity::ERROR,
+                "warning" => DiagnosticSeverity::WARNING,
+                _ => DiagnosticSeverity::INFORMATION,
+            };
+            let fixes = match alert.action.name.as_str() {
+                "replace" => alert
+                    .action
+                    .params
+                    .unwrap_or_default()
+                    .into_iter()
+                    .map(|replacement| DiagnosticFix {
+                        title: format!("Replace with \"{replacement}\""),
+                        edits: vec![(start..end, replacement)],
+                    })
+                    .collect(),
+                "remove" => vec![DiagnosticFix {
+                    title: format!("Remove \"{}\"", alert.matched),
+                    edits: vec![(start..end, String::new())],
+                }],
+                _ => Vec::new(),
+            };
+            SourceDiagnostic {
+                range: start..end,
+                severity,
+                source: None,
+                code: Some(alert.check),
+                message: alert.message,
+                fixes,
+            }
+        })
+        .collect())
+}
+
+/// Returns the position of the column of the row, in characters.
+fn point_for_column(text: &Rope, row: u32, column: u32) -> PointUtf16 {
+    if row > text.max_point().row {
+        return text.max_point_utf16();
+    }
+    let line_start = text.point_to_offset(Point::new(row, 0));
+    let line_end = text.point_to_offset(Point::new(row, text.line_len(row)));
+    let column = text
+        .chunks_in_range(line_start..line_end)
+        .flat_map(|chunk| chunk.chars())
+        .take(column as usize)
+        .map(|character| character.len_utf16() as u32)
+        .sum();
+    PointUtf16::new(row, column)
+}
+
+#[cfg(test)]
+mod tests {
+    use language::PointUtf16;
+    use lsp::DiagnosticSeverity;
+    use text::Rope;
+
+    use super::parse_vale_output;
+    use crate::{DiagnosticFix, SourceDiagnostic};
+
+    #[test]
+    fn test_parse_vale_output() {
+        let text = Rope::from("# Title\n\nThé quick fox is very very fast.\n");
+        let output = r#"{
+            "stdin.md": [
+                {
+                    "Action": { "Name": "replace", "Params": ["quickest", "fastest"] },
+                    "Span": [5, 9],
+                    "Check": "Vale.Terms",
+                    "Description": "",
+                    "Link": "",
+                    "Message": "Use 'quickest' instead of 'quick'.",
+                    "Severity": "warning",
+                    "Match": "quick",
+                    "Line": 3
+                }
+            ]
+        }"#;
+        assert_eq!(
+            parse_vale_output(output.as_bytes(), &text).unwrap(),
+            vec![SourceDiagnostic {
+                range: PointUtf16::new(2, 4)..PointUtf16::new(2, 9),
+                severity: DiagnosticSeverity::WARNING,
+                source: None,
+                code: Some("Vale.Terms".to_string()),
+                message: "Use 'quickest' instead of 'quick'.".to_string(),
+                fixes: vec![
+                    DiagnosticFix {
+                        title: "Replace with \"quickest\"".to_string(),
+                        edits: vec![(
+                            PointUtf16::new(2, 4)..PointUtf16::new(2, 9),
+                            "quickest".to_string()
+                        )],
+                    },
+                    DiagnosticFix {
+                        title: "Replace with \"fastest\"".to_string(),
+                        edits: vec![(
+                            PointUtf16::new(2, 4)..PointUtf16::new(2, 9),
+                            "fastest".to_string()
+                        )],
+                    },
+                ],
+            }]
+        );
+
+        assert_eq!(parse_vale_output(b"{}", &text).unwrap(), Vec::new());
+        assert!(parse_vale_output(b"not json", &text).is_err());
+    }
+}
@@ -0,0 +1,233 @@
+//! Version control systems other than git, like Mercurial and Perforce: the files of their repositories get diff
+//! bases, which the diff gutter shows the changes against, statuses and blame like the ones of git repositories.
+//!
+//! Providers are registered in the global [`VcsProviderRegistry`]. Files in git repositories only use git. Like
+//! everything else configured by a worktree, the providers don't run in an untrusted one.
+
+use std::{
+    path::{Path, PathBuf},
+    sync::Arc,
+};
+
+use anyhow::{Context as _, Result};
+use collections::HashMap;
+use git::{blame::Blame, repository::GitFileStatus};
+use gpui::{AppContext, Global, Model, ModelContext, Task};
+use language::Buffer;
+use parking_lot::RwLock;
+use rpc::proto;
+use text::{BufferId, Rope};
+use util::ResultExt;
+
+use crate::{mercurial::Mercurial, perforce::Perforce, File, NoRepositoryError, Project};
+
+/// A version control system whose repositories contain the files of local worktrees.
+///
+/// The methods of the provider block, and are called on background threads.
+pub trait VcsProvider: Send + Sync + 'static {
+    /// The name of the version control system.
+    fn name(&self) -> &'static str;
+
+    /// Returns the root of the repository containing the file, if it's in one.
+    fn repository_root(&self, abs_path: &Path) -> Option<PathBuf>;
+
+    /// Returns the contents of the file at the revision its changes are shown against, or `None` if it isn't
+    /// tracked.
+    fn diff_base(&self, repository_root: &Path, abs_path: &Path) -> Result<Option<String>>;
+
+    /// Returns the status of the file, or `None` if it's unchanged.
+    fn status(&self, repository_root: &Path, abs_path: &Path) -> Result<Option<GitFileStatus>>;
+
+    /// Returns the authors of the lines of the file, whose current contents are given. Providers that can only
+    /// blame the file on disk ignore them.
+    fn blame(&self, repository_root: &Path, abs_path: &Path, content: &Rope) -> Result<Blame>;
+}
+
+struct GlobalVcsProviderRegistry(Arc<VcsProviderRegistry>);
+
+impl Global for GlobalVcsProviderRegistry {}
+
+/// The registry of the version control systems other than git.
+pub struct VcsProviderRegistry {
+    providers: RwLock<Vec<Arc<dyn VcsProvider>>>,
+}
+
+impl Default for VcsProviderRegistry {
+    fn default() -> Self {
+        Self {
+            providers: RwLock::new(vec![Arc::new(Mercurial), Arc::new(Perforce)]),
+        }
+    }
+}
+
+impl Default for GlobalVcsProviderRegistry {
+    fn default() -> Self {
+        Self(Arc::new(VcsProviderRegistry::default()))
+    }
+}
+
+impl VcsProviderRegistry {
+    /// Returns the global [`VcsProviderRegistry`], if one exists.
+    pub fn try_global(cx: &AppContext) -> Option<Arc<Self>> {
+        cx.try_global::<GlobalVcsProviderRegistry>()
+            .map(|registry| registry.0.clone())
+    }
+
+    /// Returns the global [`VcsProviderRegistry`].
+    ///
+    /// Inserts a default [`VcsProviderRegistry`], with the built-in providers, if one does not yet exist.
+    pub fn default_global(cx: &mut AppContext) -> Arc<Self> {
+        cx.default_global::<GlobalVcsProviderRegistry>().0.clone()
+    }
+
+    /// Adds the provided [`VcsProvider`] to the registry.
+    pub fn register_provider(&self, provider: Arc<dyn VcsProvider>) {
+        self.providers.write().push(provider);
+    }
+
+    /// Returns the provider of the innermost repository containing the file, along with the root of that
+    /// repository.
+    fn repository_for_path(&self, abs_path: &Path) -> Option<(Arc<dyn VcsProvider>, PathBuf)> {
+        self.providers
+            .read()
+            .iter()
+            .filter_map(|provider| Some((provider.clone(), provider.repository_root(abs_path)?)))
+            .max_by_key(|(_, root)| root.components().count())
+    }
+}
+
+/// The statuses of the buffers in the repositories of the providers, loaded along with their diff bases.
+#[derive(Default)]
+pub(crate) struct VcsStatuses {
+    statuses: HashMap<BufferId, GitFileStatus>,
+}
+
+impl VcsStatuses {
+    pub(crate) fn buffer_removed(&mut self, buffer_id: BufferId) {
+        self.statuses.remove(&buffer_id);
+    }
+}
+
+impl Project {
+    /// Returns the absolute path of the buffer if it's a local file of a trusted worktree that isn't in a git
+    /// repository, along with the registry of the other version control systems.
+    fn vcs_buffer_path(
+        &self,
+        buffer: &Model<Buffer>,
+        cx: &AppContext,
+    ) -> Option<(Arc<VcsProviderRegistry>, PathBuf)> {
+        if !self.is_local() {
+            return None;
+        }
+        let registry = VcsProviderRegistry::try_global(cx)?;
+        let file = File::from_dyn(buffer.read(cx).file())?;
+        // The repository of an untrusted worktree may run its own hooks or extensions in `hg` or `p4`.
+        if !self.is_worktree_trusted(file.worktree_id(cx), cx) {
+            return None;
+        }
+        let worktree = file.worktree.read(cx).as_local()?;
+        if worktree.snapshot().repo_for_path(&file.path).is_some() {
+            return None;
+        }
+        Some((registry, file.as_local()?.abs_path(cx)))
+    }
+
+    /// Loads the diff base and the status of the buffer from the version control system of its repository, if it
+    /// isn't in a git repository.
+    pub(crate) fn reload_vcs_diff_base(
+        &mut self,
+        buffer: &Model<Buffer>,
+        cx: &mut ModelContext<Self>,
+    ) {
+        let Some((registry, abs_path)) = self.vcs_buffer_path(buffer, cx) else {
+            return;
+        };
+        let buffer = buffer.downgrade();
+        cx.spawn(move |this, mut cx| async move {
+            let (diff_base, status) = cx
+                .background_executor()
+                .spawn(async move {
+                    let Some((provider, root)) = registry.repository_for_path(&abs_path) else {
+                        return Ok((None, None));
+                    };
+                    let diff_base = provider
+                        .diff_base(&root, &abs_path)
+                        .with_context(|| format!("loading the {} diff base", provider.name()))?;
+                    let status = provider
+                        .status(&root, &abs_path)
+                        .with_context(|| format!("loading the {} status", provider.name()))?;
+                    anyhow::Ok((diff_base, status))
+                })
+                .await?;
+            let Some(buffer) = buffer.upgrade() else {
+                return Ok(());
+            };
+            this.update(&mut cx, |this, cx| {
+                let buffer_id = buffer.read(cx).remote_id();
+                let statuses = &mut this.vcs_statuses.statuses;
+                let previous_status = match status {
+                    Some(status) => statuses.insert(buffer_id, status),
+                    None => statuses.remove(&buffer_id),
+                };
+                if previous_status != status {
+                    cx.notify();
+                }
+            })?;
+            let buffer_id = buffer.update(&mut cx, |buffer, cx| {
+                if buffer.diff_base().map(|base| base.to_string()) == diff_base {
+                    return None;
+                }
+                buffer.set_diff_base(diff_base.clone(), cx);
+                Some(buffer.remote_id())
+            })?;
+            if let Some(buffer_id) = buffer_id {
+                this.update(&mut cx, |this, _| {
+                    if let Some(project_id) = this.remote_id() {
+                        this.client
+                            .send(proto::UpdateDiffBase {
+                                project_id,
+                                buffer_id: buffer_id.into(),
+                                diff_base,
+                            })
+                            .log_err();
+                    }
+                })?;
+            }
+            anyhow::Ok(())
+        })
+        .detach_and_log_err(cx);
+    }
+
+    /// Returns the status of the buffer in the version control system of its repository, if it isn't in a git
+    /// repository and has changes.
+    pub fn vcs_status(&self, buffer: &Model<Buffer>, cx: &AppContext) -> Option<GitFileStatus> {
+        self.vcs_statuses
+            .statuses
+            .get(&buffer.read(cx).remote_id())
+            .copied()
+    }
+
+    /// Blames the buffer with the version control system of its repository, or returns `None` if it's in a git
+    /// repository or isn't a local file.
+    pub(crate) fn blame_buffer_with_vcs_provider(
+        &self,
+        buffer: &Model<Buffer>,
+        version: Option<clock::Global>,
+        cx: &AppContext,
+    ) -> Option<Task<Result<Blame>>> {
+        let (registry, abs_path) = self.vcs_buffer_path(buffer, cx)?;
+        let buffer = buffer.read(cx);
+        let content = match version {
+            Some(version) => buffer.rope_for_version(&version).clone(),
+            None => buffer.as_rope().clone(),
+        };
+        Some(cx.background_executor().spawn(async move {
+            let (provider, root) = registry
+                .repository_for_path(&abs_path)
+                .ok_or(NoRepositoryError {})?;
+            provider
+                .blame(&root, &abs_path, &content)
+                .with_context(|| format!("Failed to blame {abs_path:?} with {}", provider.name()))
+        }))
+    }
+}
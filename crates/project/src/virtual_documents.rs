@@ -0,0 +1,118 @@
+//! Virtual documents: the sources that language servers generate, like the decompiled class files of the Java
+//! and Kotlin servers or the remote modules of Deno. The servers refer to them with URIs of their own schemes,
+//! which aren't files, and return their contents with custom requests. They're opened as read-only buffers, so
+//! that going to the definitions in them works like going to the ones in files.
+
+use std::path::PathBuf;
+
+use anyhow::{anyhow, Result};
+use collections::HashMap;
+use gpui::{Model, ModelContext, Task, WeakModel};
+use language::{Buffer, Capability};
+use lsp::{LanguageServerId, TextDocumentIdentifier};
+use serde::{Deserialize, Serialize};
+
+use crate::{LanguageServerState, Project};
+
+/// Returns the decompiled source of a class file of the Java language server, with a `jdt:` URI.
+pub(crate) enum ClassFileContents {}
+
+impl lsp::request::Request for ClassFileContents {
+    type Params = TextDocumentIdentifier;
+    type Result = Option<String>;
+    const METHOD: &'static str = "java/classFileContents";
+}
+
+/// Returns the decompiled source of a class in a jar of the Kotlin language server, with a `kls:` URI.
+pub(crate) enum JarClassContents {}
+
+impl lsp::request::Request for JarClassContents {
+    type Params = TextDocumentIdentifier;
+    type Result = Option<String>;
+    const METHOD: &'static str = "kotlin/jarClassContents";
+}
+
+/// Returns the source of a remote module or of a generated file of Deno, with a `deno:` URI.
+pub(crate) enum VirtualTextDocument {}
+
+#[derive(Debug, Deserialize, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub(crate) struct VirtualTextDocumentParams {
+    pub text_document: TextDocumentIdentifier,
+}
+
+impl lsp::request::Request for VirtualTextDocument {
+    type Params = VirtualTextDocumentParams;
+    type Result = Option<String>;
+    const METHOD: &'static str = "deno/virtualTextDocument";
+}
+
+#[derive(Default)]
+pub(crate) struct VirtualDocuments {
+    /// The buffers of the opened virtual documents, reused when they're opened again.
+    buffers: HashMap<lsp::Url, WeakModel<Buffer>>,
+}
+
+impl Project {
+    /// Opens the virtual document of the language server as a read-only buffer, in the language of its
+    /// extension or else in the language of the server.
+    pub(crate) fn open_virtual_document(
+        &mut self,
+        uri: lsp::Url,
+        language_server_id: LanguageServerId,
+        cx: &mut ModelContext<Self>,
+    ) -> Task<Result<Model<Buffer>>> {
+        if let Some(buffer) = self
+            .virtual_documents
+            .buffers
+            .get(&uri)
+            .and_then(|buffer| buffer.upgrade())
+        {
+            return Task::ready(Ok(buffer));
+        }
+        let Some(LanguageServerState::Running {
+            server, language, ..
+        }) = self.language_servers.get(&language_server_id)
+        else {
+            return Task::ready(Err(anyhow!(
+                "language server {language_server_id} is not running"
+            )));
+        };
+        let server = server.clone();
+        let server_language = language.clone();
+        let languages = self.languages.clone();
+
+        cx.spawn(move |this, mut cx| async move {
+            let document = TextDocumentIdentifier::new(uri.clone());
+            let contents = match uri.scheme() {
+                "jdt" => server.request::<ClassFileContents>(document).await?,
+                "kls" => server.request::<JarClassContents>(document).await?,
+                "deno" => {
+                    server
+                        .request::<VirtualTextDocument>(VirtualTextDocumentParams {
+                            text_document: document,
+                        })
+                        .await?
+                }
+                scheme => return Err(anyhow!("can't open the {scheme}: URI {uri}")),
+            }
+            .ok_or_else(|| anyhow!("{} has no contents for {uri}", server.name()))?;
+
+            let path = PathBuf::from(uri.path());
+            let language = languages
+                .language_for_file_path(&path)
+                .await
+                .unwrap_or(server_language);
+            this.update(&mut cx, |this, cx| {
+                let buffer = this.create_local_buffer(&contents, Some(language), cx);
+                buffer.update(cx, |buffer, cx| {
+                    buffer.set_capability(Capability::ReadOnly, cx)
+                });
+                this.virtual_documents
+                    .buffers
+                    .insert(uri, buffer.downgrade());
+                buffer
+            })
+        })
+    }
+}
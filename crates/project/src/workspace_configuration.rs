@@ -0,0 +1,73 @@
+//! Scoped workspace configuration: language servers of multi-root workspaces and monorepos ask for their
+//! configuration with `workspace/configuration` for a `scopeUri`, like the folder of a package. The `lsp` settings
+//! that apply to the scope, including the ones of the `.zed/settings.json` files of its folders, are given to the
+//! adapter of the server to build the configuration of that scope.
+
+use std::{path::Path, sync::Arc};
+
+use anyhow::Result;
+use gpui::{AppContext, AsyncAppContext, WeakModel};
+use language::{LanguageServerName, LspAdapter, LspAdapterDelegate};
+use serde_json::Value;
+use settings::{Settings, SettingsLocation};
+use worktree::WorktreeId;
+
+use crate::{project_settings::ProjectSettings, Project};
+
+impl Project {
+    /// The configuration of a language server for a scope, or for the root of its worktree if there is none.
+    pub(crate) async fn workspace_configuration_for_scope(
+        this: &WeakModel<Self>,
+        adapter: &Arc<dyn LspAdapter>,
+        delegate: &Arc<dyn LspAdapterDelegate>,
+        scope_uri: Option<&lsp::Url>,
+        cx: &mut AsyncAppContext,
+    ) -> Result<Value> {
+        let worktree_id = WorktreeId::from_proto(delegate.worktree_id());
+        let scoped_settings = this.update(cx, |this, cx| {
+            this.scoped_lsp_settings(&adapter.name(), worktree_id, scope_uri, cx)
+        })?;
+        match scoped_settings {
+            Some(settings) => {
+                adapter
+                    .clone()
+                    .scoped_workspace_configuration(delegate, settings, cx)
+                    .await
+            }
+            None => adapter.clone().workspace_configuration(delegate, cx).await,
+        }
+    }
+
+    /// The `settings` of the language server that apply to the scope, if the local settings of its folders change
+    /// the ones of the user settings.
+    fn scoped_lsp_settings(
+        &self,
+        server_name: &LanguageServerName,
+        worktree_id: WorktreeId,
+        scope_uri: Option<&lsp::Url>,
+        cx: &AppContext,
+    ) -> Option<Value> {
+        let (worktree_id, path) = scope_uri
+            .and_then(|uri| uri.to_file_path().ok())
+            .and_then(|abs_path| {
+                let (worktree, path) = self.find_local_worktree(&abs_path, cx)?;
+                Some((worktree.read(cx).id(), path))
+            })
+            .unwrap_or_else(|| (worktree_id, Path::new("").to_path_buf()));
+        let server_settings = |settings: &ProjectSettings| {
+            settings
+                .lsp
+                .get(&server_name.0)
+                .and_then(|settings| settings.settings.clone())
+        };
+        let scoped = server_settings(ProjectSettings::get(
+            Some(SettingsLocation {
+                worktree_id: worktree_id.to_proto() as usize,
+                path: &path,
+            }),
+            cx,
+        ));
+        let global = server_settings(ProjectSettings::get_global(cx));
+        scoped.filter(|scoped| Some(scoped) != global.as_ref())
+    }
+}
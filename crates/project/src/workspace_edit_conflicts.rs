@@ -0,0 +1,247 @@
+//! Conflicts of workspace edits: before the edits of a language server are applied, they're checked against the
+//! open buffers, and the files they could be misapplied to, like the ones edited since the version the edits were
+//! computed for, are resolved by the user with a single prompt: their edits are applied anyway, skipped while the
+//! rest of the workspace edit is applied, or opened for review. The conflicting files of the edits requested by
+//! guests are skipped, as guests can't answer the prompts of the host.
+
+use anyhow::Result;
+use collections::HashMap;
+use gpui::{AppContext, AsyncAppContext, Model, ModelContext, PromptLevel};
+use language::Buffer;
+use lsp::{LanguageServer, LanguageServerId, MessageActionItem};
+
+use crate::{
+    rename_preview::plain_text_edit, Event, LanguageServerPromptRequest, PendingRenameEdit,
+    Project, ProjectPath,
+};
+
+const OVERWRITE_ACTION: &str = "Overwrite";
+const SKIP_ACTION: &str = "Skip File";
+const OPEN_DIFF_ACTION: &str = "Open Diff";
+const OVERWRITE_ALL_ACTION: &str = "Overwrite All";
+const SKIP_ALL_ACTION: &str = "Skip All";
+const OPEN_DIFFS_ACTION: &str = "Open Diffs";
+
+/// A file the edits of which conflict with the contents of its open buffer.
+#[derive(Clone, Debug)]
+pub(crate) struct WorkspaceEditConflict {
+    uri: lsp::Url,
+    buffer: Model<Buffer>,
+    kind: WorkspaceEditConflictKind,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub(crate) enum WorkspaceEditConflictKind {
+    /// The buffer has unsaved changes the server never saw, so its edits were computed for the saved file.
+    UnsavedChanges,
+    /// The buffer was edited since the version the edits were computed for.
+    StaleVersion,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub(crate) enum WorkspaceEditConflictResolution {
+    /// The edits are applied to the buffer regardless.
+    Overwrite,
+    /// The edits of the file are dropped, and the rest of the workspace edit is applied.
+    Skip,
+    /// The edits of the file are opened for review instead of being applied.
+    OpenDiff,
+}
+
+impl Project {
+    /// Checks the text edits of a workspace edit against the open buffers without applying anything, and returns
+    /// the files the edits of which conflict with their contents.
+    pub(crate) fn workspace_edit_conflicts(
+        &mut self,
+        operations: &[lsp::DocumentChangeOperation],
+        server_id: LanguageServerId,
+        cx: &mut ModelContext<Self>,
+    ) -> Vec<WorkspaceEditConflict> {
+        let mut conflicts = Vec::<WorkspaceEditConflict>::new();
+        for operation in operations {
+            let lsp::DocumentChangeOperation::Edit(op) = operation else {
+                continue;
+            };
+            let uri = &op.text_document.uri;
+            if conflicts.iter().any(|conflict| conflict.uri == *uri) {
+                continue;
+            }
+            // The buffers that aren't open are loaded from the files the server read, so they can't conflict.
+            let Some(buffer) = uri
+                .to_file_path()
+                .ok()
+                .and_then(|abs_path| self.find_local_worktree(&abs_path, cx))
+                .and_then(|(worktree, path)| {
+                    let project_path = ProjectPath {
+                        worktree_id: worktree.read(cx).id(),
+                        path: path.into(),
+                    };
+                    self.get_open_buffer(&project_path, cx)
+                })
+            else {
+                continue;
+            };
+            if let Some(kind) =
+                self.workspace_edit_conflict_kind(&buffer, server_id, op.text_document.version, cx)
+            {
+                conflicts.push(WorkspaceEditConflict {
+                    uri: uri.clone(),
+                    buffer,
+                    kind,
+                });
+            }
+        }
+        conflicts
+    }
+
+    fn workspace_edit_conflict_kind(
+        &self,
+        buffer: &Model<Buffer>,
+        server_id: LanguageServerId,
+        version: Option<i32>,
+        cx: &AppContext,
+    ) -> Option<WorkspaceEditConflictKind> {
+        let buffer = buffer.read(cx);
+        let snapshots = self
+            .buffer_snapshots
+            .get(&buffer.remote_id())
+            .and_then(|snapshots| snapshots.get(&server_id));
+        match version {
+            Some(version) => {
+                let latest_version = snapshots
+                    .and_then(|snapshots| snapshots.last())
+                    .map(|snapshot| snapshot.version);
+                (latest_version != Some(version)).then_some(WorkspaceEditConflictKind::StaleVersion)
+            }
+            // Unversioned edits are computed for the contents the server knows, which are the saved ones unless it
+            // was sent the buffer.
+            None => (buffer.is_dirty() && snapshots.is_none())
+                .then_some(WorkspaceEditConflictKind::UnsavedChanges),
+        }
+    }
+
+    /// Asks the user how to resolve the conflicts, all of them being resolved the same way. The conflicts that are
+    /// dismissed without an answer, and the ones of edits requested by guests, are skipped.
+    pub(crate) async fn resolve_workspace_edit_conflicts(
+        this: &Model<Self>,
+        conflicts: Vec<WorkspaceEditConflict>,
+        language_server: &LanguageServer,
+        requested_by_guest: bool,
+        cx: &mut AsyncAppContext,
+    ) -> Result<HashMap<lsp::Url, WorkspaceEditConflictResolution>> {
+        if conflicts.is_empty() {
+            return Ok(HashMap::default());
+        }
+        if requested_by_guest {
+            log::warn!(
+                "skipping the edits of {} files that conflict with their open buffers",
+                conflicts.len()
+            );
+            return Ok(conflicts
+                .into_iter()
+                .map(|conflict| (conflict.uri, WorkspaceEditConflictResolution::Skip))
+                .collect());
+        }
+
+        let mut paths = Vec::with_capacity(conflicts.len());
+        for conflict in &conflicts {
+            let path = conflict
+                .buffer
+                .read_with(cx, |buffer, cx| {
+                    buffer
+                        .file()
+                        .map(|file| file.full_path(cx).display().to_string())
+                })?
+                .unwrap_or_else(|| conflict.uri.to_string());
+            paths.push(path);
+        }
+        let (message, actions) = match (conflicts.as_slice(), paths.as_slice()) {
+            ([conflict], [path]) => {
+                let message = match conflict.kind {
+                    WorkspaceEditConflictKind::UnsavedChanges => format!(
+                        "{path} has unsaved changes that {} didn't see when it computed its edits.",
+                        language_server.name()
+                    ),
+                    WorkspaceEditConflictKind::StaleVersion => format!(
+                        "{path} was edited since {} computed its edits.",
+                        language_server.name()
+                    ),
+                };
+                (message, [OVERWRITE_ACTION, SKIP_ACTION, OPEN_DIFF_ACTION])
+            }
+            _ => {
+                let message = format!(
+                    "{} files were changed since {} computed its edits: {}.",
+                    paths.len(),
+                    language_server.name(),
+                    paths.join(", ")
+                );
+                (
+                    message,
+                    [OVERWRITE_ALL_ACTION, SKIP_ALL_ACTION, OPEN_DIFFS_ACTION],
+                )
+            }
+        };
+
+        let (tx, rx) = smol::channel::bounded(1);
+        let prompt = LanguageServerPromptRequest {
+            level: PromptLevel::Warning,
+            message,
+            actions: actions
+                .into_iter()
+                .map(|title| MessageActionItem {
+                    title: title.to_string(),
+                    properties: Default::default(),
+                })
+                .collect(),
+            lsp_name: language_server.name().to_string(),
+            response_channel: tx,
+        };
+        this.update(cx, |_, cx| cx.emit(Event::LanguageServerPrompt(prompt)))?;
+        let [overwrite_action, _, open_diff_action] = actions;
+        let resolution = match rx.recv().await {
+            Ok(action) if action.title == overwrite_action => {
+                WorkspaceEditConflictResolution::Overwrite
+            }
+            Ok(action) if action.title == open_diff_action => {
+                WorkspaceEditConflictResolution::OpenDiff
+            }
+            _ => WorkspaceEditConflictResolution::Skip,
+        };
+        Ok(conflicts
+            .into_iter()
+            .map(|conflict| (conflict.uri, resolution))
+            .collect())
+    }
+
+    /// Opens the edits of a conflicting file for review with [`Event::ReviewConflictingEdits`], instead of
+    /// applying them.
+    pub(crate) async fn review_conflicting_edits(
+        this: &Model<Self>,
+        buffer: Model<Buffer>,
+        op: lsp::TextDocumentEdit,
+        server_id: LanguageServerId,
+        cx: &mut AsyncAppContext,
+    ) -> Result<()> {
+        let text_edits = op
+            .edits
+            .into_iter()
+            .map(plain_text_edit)
+            .collect::<Vec<_>>();
+        let edits = this
+            .update(cx, |this, cx| {
+                this.edits_from_lsp(&buffer, text_edits, server_id, op.text_document.version, cx)
+            })?
+            .await?;
+        let edits = edits
+            .into_iter()
+            .map(|(range, new_text)| PendingRenameEdit {
+                buffer: buffer.clone(),
+                range,
+                new_text,
+            })
+            .collect();
+        this.update(cx, |_, cx| cx.emit(Event::ReviewConflictingEdits(edits)))?;
+        Ok(())
+    }
+}
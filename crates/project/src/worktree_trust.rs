@@ -0,0 +1,136 @@
+//! Worktree trust: with the `worktree_trust` setting enabled, nothing configured by a worktree runs until the user
+//! trusts it. Its language servers aren't started, so neither are the login shells that load the environment of
+//! tools like direnv in it, its local settings and tasks aren't loaded, and no task or code cell kernel is spawned
+//! in it.
+//!
+//! Trusted worktrees are remembered by their paths in the user settings, which worktrees can't override.
+
+use std::{path::Path, sync::Arc};
+
+use collections::HashSet;
+use gpui::{AppContext, Model, ModelContext};
+use paths::{
+    local_settings_file_relative_path, local_tasks_file_relative_path,
+    local_vscode_tasks_file_relative_path,
+};
+use settings::{update_settings_file, Settings};
+use worktree::{PathChange, Worktree, WorktreeId};
+
+use crate::{project_settings::ProjectSettings, Event, File, Project};
+
+#[derive(Default)]
+pub(crate) struct WorktreeTrust {
+    /// Worktrees trusted during this session, which may not be in the settings yet.
+    trusted: HashSet<WorktreeId>,
+    /// Untrusted worktrees the user was asked to trust.
+    prompted: HashSet<WorktreeId>,
+}
+
+impl WorktreeTrust {
+    pub(crate) fn worktree_removed(&mut self, worktree_id: WorktreeId) {
+        self.trusted.remove(&worktree_id);
+        self.prompted.remove(&worktree_id);
+    }
+}
+
+impl Project {
+    /// Whether language servers, tasks and the local settings of the worktree may be used. The worktrees of
+    /// remote projects are trusted by their host.
+    pub fn is_worktree_trusted(&self, worktree_id: WorktreeId, cx: &AppContext) -> bool {
+        let settings = &ProjectSettings::get_global(cx).worktree_trust;
+        if !settings.enabled
+            || !self.is_local()
+            || self.worktree_trust.trusted.contains(&worktree_id)
+        {
+            return true;
+        }
+        let Some(worktree) = self.worktree_for_id(worktree_id, cx) else {
+            return false;
+        };
+        let abs_path = worktree.read(cx).abs_path();
+        settings
+            .trusted_paths
+            .iter()
+            .any(|trusted_path| abs_path.starts_with(trusted_path))
+    }
+
+    /// Checks that the worktree is trusted before running something it configures, asking the user to trust it
+    /// the first time it isn't.
+    pub(crate) fn check_worktree_trust(
+        &mut self,
+        worktree_id: WorktreeId,
+        cx: &mut ModelContext<Self>,
+    ) -> bool {
+        if self.is_worktree_trusted(worktree_id, cx) {
+            return true;
+        }
+        if self.worktree_trust.prompted.insert(worktree_id) {
+            cx.emit(Event::WorktreeTrustRequired(worktree_id));
+        }
+        false
+    }
+
+    /// Trusts the worktree, remembering its path in the user settings, and loads its local settings, starts the
+    /// language servers of its open buffers and loads their diff bases from the version control systems other than
+    /// git.
+    pub fn trust_worktree(&mut self, worktree_id: WorktreeId, cx: &mut ModelContext<Self>) {
+        if self.is_worktree_trusted(worktree_id, cx) {
+            return;
+        }
+        let Some(worktree) = self.worktree_for_id(worktree_id, cx) else {
+            return;
+        };
+        self.worktree_trust.trusted.insert(worktree_id);
+        self.worktree_trust.prompted.remove(&worktree_id);
+
+        let abs_path = worktree.read(cx).abs_path().to_path_buf();
+        update_settings_file::<ProjectSettings>(self.fs.clone(), cx, move |settings| {
+            let trusted_paths = &mut settings.worktree_trust.trusted_paths;
+            if !trusted_paths.contains(&abs_path) {
+                trusted_paths.push(abs_path);
+            }
+        });
+
+        self.load_worktree_configuration(&worktree, cx);
+        let buffers = self
+            .opened_buffers
+            .values()
+            .filter_map(|buffer| buffer.upgrade())
+            .filter(|buffer| {
+                File::from_dyn(buffer.read(cx).file())
+                    .map_or(false, |file| file.worktree == worktree)
+            })
+            .collect::<Vec<_>>();
+        for buffer in buffers {
+            let file = File::from_dyn(buffer.read(cx).file()).map(|file| file.path.clone());
+            if let Some((path, language)) = file.zip(buffer.read(cx).language().cloned()) {
+                self.start_language_servers(&worktree, &path, language, cx);
+            }
+            self.reload_vcs_diff_base(&buffer, cx);
+        }
+    }
+
+    /// Loads the local settings and tasks files of a worktree that were skipped while it wasn't trusted.
+    fn load_worktree_configuration(
+        &mut self,
+        worktree: &Model<Worktree>,
+        cx: &mut ModelContext<Self>,
+    ) {
+        let changes = worktree
+            .read(cx)
+            .entries(false, 0)
+            .filter(|entry| is_configuration_file(&entry.path))
+            .map(|entry| (entry.path.clone(), entry.id, PathChange::Added))
+            .collect::<Arc<[_]>>();
+        if !changes.is_empty() {
+            self.update_local_worktree_settings(worktree, &changes, cx);
+        }
+    }
+}
+
+/// Whether the file holds settings or tasks of the worktree, which aren't loaded until the worktree is trusted.
+pub(crate) fn is_configuration_file(path: &Path) -> bool {
+    path.ends_with(local_settings_file_relative_path())
+        || path.ends_with(local_tasks_file_relative_path())
+        || path.ends_with(local_vscode_tasks_file_relative_path())
+}
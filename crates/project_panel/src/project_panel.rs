@@ -1066,15 +1066,16 @@ impl ProjectPanel {
                         return Result::<(), anyhow::Error>::Ok(());
                     }
                 }
-                for (entry_id, _) in file_paths {
-                    this.update(&mut cx, |this, cx| {
-                        this.project
-                            .update(cx, |project, cx| project.delete_entry(entry_id, trash, cx))
-                            .ok_or_else(|| anyhow!("no such entry"))
-                    })??
-                    .await?;
-                }
-                Result::<(), anyhow::Error>::Ok(())
+                let entry_ids = file_paths
+                    .into_iter()
+                    .map(|(entry_id, _)| entry_id)
+                    .collect();
+                this.update(&mut cx, |this, cx| {
+                    this.project.update(cx, |project, cx| {
+                        project.delete_entries(entry_ids, trash, cx)
+                    })
+                })?
+                .await
             })
             .detach_and_log_err(cx);
             Some(())
@@ -1,4 +1,5 @@
 use editor::{scroll::Autoscroll, styled_runs_for_code_label, Bias, Editor};
+use futures::StreamExt as _;
 use fuzzy::{StringMatch, StringMatchCandidate};
 use gpui::{
     rems, AppContext, DismissEvent, FontWeight, Model, ParentElement, StyledText, Task, View,
@@ -33,6 +34,9 @@ pub fn init(cx: &mut AppContext) {
 
 pub type ProjectSymbols = View<Picker<ProjectSymbolsDelegate>>;
 
+/// How many matches are shown at once: more are added when the list is scrolled to its end.
+const MATCHES_PAGE_SIZE: usize = 100;
+
 pub struct ProjectSymbolsDelegate {
     workspace: WeakView<Workspace>,
     project: Model<Project>,
@@ -42,6 +46,7 @@ pub struct ProjectSymbolsDelegate {
     external_match_candidates: Vec<StringMatchCandidate>,
     show_worktree_root_name: bool,
     matches: Vec<StringMatch>,
+    match_limit: usize,
 }
 
 impl ProjectSymbolsDelegate {
@@ -55,16 +60,17 @@ impl ProjectSymbolsDelegate {
             external_match_candidates: Default::default(),
             matches: Default::default(),
             show_worktree_root_name: false,
+            match_limit: MATCHES_PAGE_SIZE,
         }
     }
 
     fn filter(&mut self, query: &str, cx: &mut ViewContext<Picker<Self>>) {
-        const MAX_MATCHES: usize = 100;
+        let max_matches = self.match_limit;
         let mut visible_matches = cx.background_executor().block(fuzzy::match_strings(
             &self.visible_match_candidates,
             query,
             false,
-            MAX_MATCHES,
+            max_matches,
             &Default::default(),
             cx.background_executor().clone(),
         ));
@@ -72,7 +78,7 @@ impl ProjectSymbolsDelegate {
             &self.external_match_candidates,
             query,
             false,
-            MAX_MATCHES - visible_matches.len().min(MAX_MATCHES),
+            max_matches - visible_matches.len().min(max_matches),
             &Default::default(),
             cx.background_executor().clone(),
         ));
@@ -100,6 +106,48 @@ impl ProjectSymbolsDelegate {
         self.matches = matches;
         self.set_selected_index(0, cx);
     }
+
+    fn set_symbols(
+        &mut self,
+        symbols: Vec<Symbol>,
+        query: &str,
+        cx: &mut ViewContext<Picker<Self>>,
+    ) {
+        let project = self.project.read(cx);
+        let (visible_match_candidates, external_match_candidates) = symbols
+            .iter()
+            .enumerate()
+            .map(|(id, symbol)| {
+                StringMatchCandidate::new(
+                    id,
+                    symbol.label.text[symbol.label.filter_range.clone()].to_string(),
+                )
+            })
+            .partition(|candidate| {
+                project
+                    .entry_for_path(&symbols[candidate.id].path, cx)
+                    .map_or(false, |e| !e.is_ignored)
+            });
+
+        self.visible_match_candidates = visible_match_candidates;
+        self.external_match_candidates = external_match_candidates;
+        self.symbols = symbols;
+        self.filter(query, cx);
+    }
+
+    fn has_more_matches(&self) -> bool {
+        self.matches.len() >= self.match_limit
+    }
+
+    fn show_more_matches(picker: &mut Picker<Self>, cx: &mut ViewContext<Picker<Self>>) {
+        let query = picker.query(cx);
+        let delegate = &mut picker.delegate;
+        let selected_match_index = delegate.selected_match_index;
+        delegate.match_limit += MATCHES_PAGE_SIZE;
+        delegate.filter(&query, cx);
+        delegate.set_selected_index(selected_match_index, cx);
+        cx.notify();
+    }
 }
 
 impl PickerDelegate for ProjectSymbolsDelegate {
@@ -114,13 +162,16 @@ impl PickerDelegate for ProjectSymbolsDelegate {
             .get(self.selected_match_index)
             .map(|mat| self.symbols[mat.candidate_id].clone())
         {
-            let buffer = self.project.update(cx, |project, cx| {
-                project.open_buffer_for_symbol(&symbol, cx)
-            });
-            let symbol = symbol.clone();
+            let project = self.project.clone();
+            let symbol = project.update(cx, |project, cx| project.resolve_symbol(&symbol, cx));
             let workspace = self.workspace.clone();
             cx.spawn(|_, mut cx| async move {
-                let buffer = buffer.await?;
+                let symbol = symbol.await?;
+                let buffer = project
+                    .update(&mut cx, |project, cx| {
+                        project.open_buffer_for_symbol(&symbol, cx)
+                    })?
+                    .await?;
                 workspace.update(&mut cx, |workspace, cx| {
                     let position = buffer
                         .read(cx)
@@ -161,36 +212,26 @@ impl PickerDelegate for ProjectSymbolsDelegate {
     }
 
     fn update_matches(&mut self, query: String, cx: &mut ViewContext<Picker<Self>>) -> Task<()> {
+        self.match_limit = MATCHES_PAGE_SIZE;
         self.filter(&query, cx);
         self.show_worktree_root_name = self.project.read(cx).visible_worktrees(cx).count() > 1;
-        let symbols = self
-            .project
-            .update(cx, |project, cx| project.symbols(&query, cx));
+        let mut symbols = self.project.update(cx, |project, cx| {
+            project.symbols_with_partial_results(&query, cx)
+        });
         cx.spawn(|this, mut cx| async move {
-            let symbols = symbols.await.log_err();
-            if let Some(symbols) = symbols {
+            // Show the symbols the language servers find as they report them, instead of after all of them did.
+            let mut all_symbols = Vec::new();
+            while let Some(partial_symbols) = symbols.partial.next().await {
+                all_symbols.extend(partial_symbols);
                 this.update(&mut cx, |this, cx| {
-                    let delegate = &mut this.delegate;
-                    let project = delegate.project.read(cx);
-                    let (visible_match_candidates, external_match_candidates) = symbols
-                        .iter()
-                        .enumerate()
-                        .map(|(id, symbol)| {
-                            StringMatchCandidate::new(
-                                id,
-                                symbol.label.text[symbol.label.filter_range.clone()].to_string(),
-                            )
-                        })
-                        .partition(|candidate| {
-                            project
-                                .entry_for_path(&symbols[candidate.id].path, cx)
-                                .map_or(false, |e| !e.is_ignored)
-                        });
-
-                    delegate.visible_match_candidates = visible_match_candidates;
-                    delegate.external_match_candidates = external_match_candidates;
-                    delegate.symbols = symbols;
-                    delegate.filter(&query, cx);
+                    this.delegate.set_symbols(all_symbols.clone(), &query, cx)
+                })
+                .log_err();
+            }
+            if let Some(rest) = symbols.rest.await.log_err() {
+                all_symbols.extend(rest);
+                this.update(&mut cx, |this, cx| {
+                    this.delegate.set_symbols(all_symbols, &query, cx)
                 })
                 .log_err();
             }
@@ -203,6 +244,10 @@ impl PickerDelegate for ProjectSymbolsDelegate {
         selected: bool,
         cx: &mut ViewContext<Picker<Self>>,
     ) -> Option<Self::ListItem> {
+        if ix + 1 == self.matches.len() && self.has_more_matches() {
+            cx.defer(Self::show_more_matches);
+        }
+
         let string_match = &self.matches[ix];
         let symbol = &self.symbols[string_match.candidate_id];
         let syntax_runs = styled_runs_for_code_label(&symbol.label, cx.theme().syntax());
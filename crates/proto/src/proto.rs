@@ -279,6 +279,7 @@ messages!(
     (ShareProject, Foreground),
     (ShareProjectResponse, Foreground),
     (ShowContacts, Foreground),
+    (ShowDocument, Foreground),
     (StartLanguageServer, Foreground),
     (SubscribeToChannels, Foreground),
     (SynchronizeBuffers, Foreground),
@@ -310,6 +311,14 @@ messages!(
     (UsersResponse, Foreground),
     (LspExtExpandMacro, Background),
     (LspExtExpandMacroResponse, Background),
+    (LspExtViewHir, Background),
+    (LspExtViewHirResponse, Background),
+    (LspExtViewMir, Background),
+    (LspExtViewMirResponse, Background),
+    (LspExtRelatedTests, Background),
+    (LspExtRelatedTestsResponse, Background),
+    (LspExtReloadWorkspace, Background),
+    (LspExtRebuildProcMacros, Background),
     (SetRoomParticipantRole, Foreground),
     (BlameBuffer, Foreground),
     (BlameBufferResponse, Foreground),
@@ -337,7 +346,52 @@ messages!(
     (OpenNewBuffer, Foreground),
     (RestartLanguageServers, Foreground),
     (LinkedEditingRange, Background),
-    (LinkedEditingRangeResponse, Background)
+    (LinkedEditingRangeResponse, Background),
+    (ExecuteLspCommand, Background),
+    (ExecuteLspCommandResponse, Background),
+    (GetDocumentDiagnostics, Background),
+    (GetDocumentDiagnosticsResponse, Background),
+    (PrepareTypeHierarchy, Background),
+    (GetTypeHierarchySupertypes, Background),
+    (GetTypeHierarchySubtypes, Background),
+    (TypeHierarchyResponse, Background),
+    (PrepareCallHierarchy, Background),
+    (PrepareCallHierarchyResponse, Background),
+    (GetIncomingCalls, Background),
+    (GetOutgoingCalls, Background),
+    (CallHierarchyCallsResponse, Background),
+    (GetSemanticTokens, Background),
+    (GetSemanticTokensResponse, Background),
+    (RefreshSemanticTokens, Foreground),
+    (ResolveWorkspaceSymbol, Background),
+    (ResolveWorkspaceSymbolResponse, Background),
+    (GetDocumentLinks, Background),
+    (GetDocumentLinksResponse, Background),
+    (ResolveDocumentLink, Background),
+    (ResolveDocumentLinkResponse, Background),
+    (GetDocumentColors, Background),
+    (GetDocumentColorsResponse, Background),
+    (GetColorPresentations, Background),
+    (GetColorPresentationsResponse, Background),
+    (LspExtClangdAst, Background),
+    (LspExtClangdAstResponse, Background),
+    (LspExtOpenCargoToml, Background),
+    (LspExtOpenCargoTomlResponse, Background),
+    (LspExtParentModule, Background),
+    (LspExtParentModuleResponse, Background),
+    (LspExtRunnables, Background),
+    (LspExtRunnablesResponse, Background),
+    (CancelLanguageServerWork, Foreground),
+    (GetInlineValues, Background),
+    (GetInlineValuesResponse, Background),
+    (RefreshInlineValues, Foreground),
+    (GetFoldingRanges, Background),
+    (GetFoldingRangesResponse, Background),
+    (GetDocumentSymbols, Background),
+    (GetDocumentSymbolsResponse, Background),
+    (GetSignatureHelp, Background),
+    (GetSignatureHelpResponse, Background),
+    (LspPartialResult, Background)
 );
 
 request_messages!(
@@ -348,6 +402,7 @@ request_messages!(
     ),
     (Call, Ack),
     (CancelCall, Ack),
+    (CancelLanguageServerWork, Ack),
     (CopyProjectEntry, ProjectEntryResponse),
     (CompleteWithLanguageModel, LanguageModelResponse),
     (ComputeEmbeddings, ComputeEmbeddingsResponse),
@@ -358,6 +413,7 @@ request_messages!(
     (DeclineCall, Ack),
     (DeleteChannel, Ack),
     (DeleteProjectEntry, ProjectEntryResponse),
+    (ExecuteLspCommand, ExecuteLspCommandResponse),
     (ExpandProjectEntry, ExpandProjectEntryResponse),
     (Follow, FollowResponse),
     (FormatBuffers, FormatBuffersResponse),
@@ -367,18 +423,31 @@ request_messages!(
     (GetChannelMessages, GetChannelMessagesResponse),
     (GetChannelMessagesById, GetChannelMessagesResponse),
     (GetCodeActions, GetCodeActionsResponse),
+    (GetColorPresentations, GetColorPresentationsResponse),
     (GetCompletions, GetCompletionsResponse),
     (GetDefinition, GetDefinitionResponse),
+    (GetInlineValues, GetInlineValuesResponse),
     (GetImplementation, GetImplementationResponse),
+    (GetDocumentDiagnostics, GetDocumentDiagnosticsResponse),
+    (GetDocumentColors, GetDocumentColorsResponse),
     (GetDocumentHighlights, GetDocumentHighlightsResponse),
+    (GetFoldingRanges, GetFoldingRangesResponse),
+    (GetDocumentSymbols, GetDocumentSymbolsResponse),
+    (GetDocumentLinks, GetDocumentLinksResponse),
     (GetHover, GetHoverResponse),
+    (GetIncomingCalls, CallHierarchyCallsResponse),
     (GetNotifications, GetNotificationsResponse),
+    (GetOutgoingCalls, CallHierarchyCallsResponse),
     (GetPrivateUserInfo, GetPrivateUserInfoResponse),
     (GetProjectSymbols, GetProjectSymbolsResponse),
     (GetReferences, GetReferencesResponse),
+    (GetSemanticTokens, GetSemanticTokensResponse),
+    (GetSignatureHelp, GetSignatureHelpResponse),
     (GetSupermavenApiKey, GetSupermavenApiKeyResponse),
     (GetTypeDefinition, GetTypeDefinitionResponse),
     (LinkedEditingRange, LinkedEditingRangeResponse),
+    (GetTypeHierarchySubtypes, TypeHierarchyResponse),
+    (GetTypeHierarchySupertypes, TypeHierarchyResponse),
     (GetUsers, UsersResponse),
     (IncomingCall, Ack),
     (InlayHints, InlayHintsResponse),
@@ -400,8 +469,12 @@ request_messages!(
     (OpenNewBuffer, OpenBufferResponse),
     (PerformRename, PerformRenameResponse),
     (Ping, Ack),
+    (PrepareCallHierarchy, PrepareCallHierarchyResponse),
     (PrepareRename, PrepareRenameResponse),
+    (PrepareTypeHierarchy, TypeHierarchyResponse),
     (RefreshInlayHints, Ack),
+    (RefreshInlineValues, Ack),
+    (RefreshSemanticTokens, Ack),
     (RejoinChannelBuffers, RejoinChannelBuffersResponse),
     (RejoinRoom, RejoinRoomResponse),
     (ReloadBuffers, ReloadBuffersResponse),
@@ -416,7 +489,9 @@ request_messages!(
         ResolveCompletionDocumentation,
         ResolveCompletionDocumentationResponse
     ),
+    (ResolveDocumentLink, ResolveDocumentLinkResponse),
     (ResolveInlayHint, ResolveInlayHintResponse),
+    (ResolveWorkspaceSymbol, ResolveWorkspaceSymbolResponse),
     (RespondToChannelInvite, Ack),
     (RespondToContactRequest, Ack),
     (SaveBuffer, BufferSaved),
@@ -434,6 +509,15 @@ request_messages!(
     (UpdateProject, Ack),
     (UpdateWorktree, Ack),
     (LspExtExpandMacro, LspExtExpandMacroResponse),
+    (LspExtViewHir, LspExtViewHirResponse),
+    (LspExtViewMir, LspExtViewMirResponse),
+    (LspExtRelatedTests, LspExtRelatedTestsResponse),
+    (LspExtReloadWorkspace, Ack),
+    (LspExtRebuildProcMacros, Ack),
+    (LspExtClangdAst, LspExtClangdAstResponse),
+    (LspExtOpenCargoToml, LspExtOpenCargoTomlResponse),
+    (LspExtParentModule, LspExtParentModuleResponse),
+    (LspExtRunnables, LspExtRunnablesResponse),
     (SetRoomParticipantRole, Ack),
     (BlameBuffer, BlameBufferResponse),
     (CreateDevServerProject, CreateDevServerProjectResponse),
@@ -460,25 +544,41 @@ entity_messages!(
     BlameBuffer,
     BufferReloaded,
     BufferSaved,
+    CancelLanguageServerWork,
     CopyProjectEntry,
     CreateBufferForPeer,
     CreateProjectEntry,
     DeleteProjectEntry,
+    ExecuteLspCommand,
     ExpandProjectEntry,
     FormatBuffers,
     GetCodeActions,
+    GetColorPresentations,
     GetCompletions,
     GetDefinition,
     GetImplementation,
+    GetDocumentColors,
+    GetDocumentDiagnostics,
     GetDocumentHighlights,
+    GetDocumentLinks,
+    GetDocumentSymbols,
+    GetFoldingRanges,
     GetHover,
+    GetIncomingCalls,
+    GetInlineValues,
+    GetOutgoingCalls,
     GetProjectSymbols,
     GetReferences,
+    GetSemanticTokens,
+    GetSignatureHelp,
     GetTypeDefinition,
+    GetTypeHierarchySubtypes,
+    GetTypeHierarchySupertypes,
     InlayHints,
     JoinProject,
     LeaveProject,
     LinkedEditingRange,
+    LspPartialResult,
     MultiLspQuery,
     RestartLanguageServers,
     OnTypeFormatting,
@@ -487,15 +587,22 @@ entity_messages!(
     OpenBufferByPath,
     OpenBufferForSymbol,
     PerformRename,
+    PrepareCallHierarchy,
     PrepareRename,
+    PrepareTypeHierarchy,
     RefreshInlayHints,
+    RefreshInlineValues,
+    RefreshSemanticTokens,
     ReloadBuffers,
     RemoveProjectCollaborator,
     RenameProjectEntry,
     ResolveCompletionDocumentation,
+    ResolveDocumentLink,
     ResolveInlayHint,
+    ResolveWorkspaceSymbol,
     SaveBuffer,
     SearchProject,
+    ShowDocument,
     StartLanguageServer,
     SynchronizeBuffers,
     TaskContextForLocation,
@@ -511,6 +618,15 @@ entity_messages!(
     UpdateWorktree,
     UpdateWorktreeSettings,
     LspExtExpandMacro,
+    LspExtViewHir,
+    LspExtViewMir,
+    LspExtRelatedTests,
+    LspExtReloadWorkspace,
+    LspExtRebuildProcMacros,
+    LspExtClangdAst,
+    LspExtOpenCargoToml,
+    LspExtParentModule,
+    LspExtRunnables,
 );
 
 entity_messages!(
@@ -1178,6 +1178,7 @@ pub async fn spawn_ssh_task(
                     use_new_terminal: true,
                     allow_concurrent_runs: false,
                     reveal: RevealStrategy::Always,
+                    problem_matchers: Vec::new(),
                 },
                 cx,
             )
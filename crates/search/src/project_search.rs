@@ -8,7 +8,8 @@ use editor::{
     actions::SelectAll,
     items::active_match_index,
     scroll::{Autoscroll, Axis},
-    Anchor, Editor, EditorElement, EditorEvent, EditorStyle, MultiBuffer, MAX_TAB_TITLE_LEN,
+    Anchor, Editor, EditorElement, EditorEvent, EditorStyle, ExcerptId, MultiBuffer,
+    MAX_TAB_TITLE_LEN,
 };
 use gpui::{
     actions, div, Action, AnyElement, AnyView, AppContext, Context as _, Element, EntityId,
@@ -17,8 +18,14 @@ use gpui::{
     Subscription, Task, TextStyle, UpdateGlobal, View, ViewContext, VisualContext, WeakModel,
     WeakView, WhiteSpace, WindowContext,
 };
+use language::Buffer;
 use menu::Confirm;
-use project::{search::SearchQuery, search_history::SearchHistoryCursor, Project, ProjectPath};
+use project::{
+    compare_search_result_buffers,
+    search::{SearchLimits, SearchQuery},
+    search_history::SearchHistoryCursor,
+    Project, ProjectPath,
+};
 use settings::Settings;
 use smol::stream::StreamExt;
 use std::{
@@ -45,7 +52,13 @@ const MAX_INPUT_WIDTH_REMS: f32 = 30.;
 
 actions!(
     project_search,
-    [SearchInNew, ToggleFocus, NextField, ToggleFilters]
+    [
+        SearchInNew,
+        ToggleFocus,
+        NextField,
+        ToggleFilters,
+        LoadMoreResults
+    ]
 );
 
 #[derive(Default)]
@@ -118,14 +131,25 @@ struct ProjectSearch {
     excerpts: Model<MultiBuffer>,
     pending_search: Option<Task<Option<()>>>,
     match_ranges: Vec<Range<Anchor>>,
+    /// The buffers with matches, in the order they're shown, as the project reports them in no particular order.
+    result_buffers: Vec<ResultBuffer>,
     active_query: Option<SearchQuery>,
     last_search_query_text: Option<String>,
     search_id: usize,
     no_results: Option<bool>,
     limit_reached: bool,
+    /// The limits of the active search, raised when more results are loaded.
+    limits: SearchLimits,
     search_history_cursor: SearchHistoryCursor,
 }
 
+#[derive(Clone)]
+struct ResultBuffer {
+    buffer: Model<Buffer>,
+    last_excerpt_id: ExcerptId,
+    match_count: usize,
+}
+
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
 enum InputPanel {
     Query,
@@ -172,11 +196,13 @@ impl ProjectSearch {
             excerpts: cx.new_model(|_| MultiBuffer::new(replica_id, capability)),
             pending_search: Default::default(),
             match_ranges: Default::default(),
+            result_buffers: Default::default(),
             active_query: None,
             last_search_query_text: None,
             search_id: 0,
             no_results: None,
             limit_reached: false,
+            limits: SearchLimits::default(),
             search_history_cursor: Default::default(),
         }
     }
@@ -189,31 +215,54 @@ impl ProjectSearch {
                 .update(cx, |excerpts, cx| cx.new_model(|cx| excerpts.clone(cx))),
             pending_search: Default::default(),
             match_ranges: self.match_ranges.clone(),
+            result_buffers: self.result_buffers.clone(),
             active_query: self.active_query.clone(),
             last_search_query_text: self.last_search_query_text.clone(),
             search_id: self.search_id,
             no_results: self.no_results,
             limit_reached: self.limit_reached,
+            limits: self.limits,
             search_history_cursor: self.search_history_cursor.clone(),
         })
     }
 
     fn search(&mut self, query: SearchQuery, cx: &mut ModelContext<Self>) {
-        let search = self.project.update(cx, |project, cx| {
+        self.project.update(cx, |project, _| {
             project
                 .search_history_mut()
                 .add(&mut self.search_history_cursor, query.as_str().to_string());
-            project.search(query.clone(), cx)
+        });
+        self.limits = SearchLimits::default();
+        self.run_search(query, cx);
+    }
+
+    /// Searches again with larger limits, once the active search reached its limits.
+    fn load_more(&mut self, cx: &mut ModelContext<Self>) {
+        if !self.limit_reached || self.pending_search.is_some() {
+            return;
+        }
+        if let Some(query) = self.active_query.clone() {
+            self.limits = self.limits.doubled();
+            self.run_search(query, cx);
+        }
+    }
+
+    fn run_search(&mut self, query: SearchQuery, cx: &mut ModelContext<Self>) {
+        let limits = self.limits;
+        let search = self.project.update(cx, |project, cx| {
+            project.search_with_limits(query.clone(), limits, cx)
         });
         self.last_search_query_text = Some(query.as_str().to_string());
         self.search_id += 1;
         self.active_query = Some(query);
         self.match_ranges.clear();
+        self.result_buffers.clear();
         self.pending_search = Some(cx.spawn(|this, mut cx| async move {
             let mut matches = search;
             let this = this.upgrade()?;
             this.update(&mut cx, |this, cx| {
                 this.match_ranges.clear();
+                this.result_buffers.clear();
                 this.excerpts.update(cx, |this, cx| this.clear(cx));
                 this.no_results = Some(true);
                 this.limit_reached = false;
@@ -224,25 +273,62 @@ impl ProjectSearch {
             while let Some(result) = matches.next().await {
                 match result {
                     project::SearchResult::Buffer { buffer, ranges } => {
-                        let mut match_ranges = this
+                        let (result_ix, mut match_ix, mut match_ranges) = this
                             .update(&mut cx, |this, cx| {
                                 this.no_results = Some(false);
-                                this.excerpts.update(cx, |excerpts, cx| {
-                                    excerpts.stream_excerpts_with_context_lines(
-                                        buffer,
+                                // The matches of the buffer are shown after the ones of the buffers before it.
+                                let result_ix = this
+                                    .result_buffers
+                                    .binary_search_by(|result| {
+                                        compare_search_result_buffers(
+                                            result.buffer.read(cx),
+                                            buffer.read(cx),
+                                        )
+                                    })
+                                    .unwrap_or_else(|ix| ix);
+                                let prev_results = &this.result_buffers[..result_ix];
+                                let prev_excerpt_id = prev_results
+                                    .last()
+                                    .map_or(ExcerptId::min(), |result| result.last_excerpt_id);
+                                let match_ix = prev_results
+                                    .iter()
+                                    .map(|result| result.match_count)
+                                    .sum::<usize>();
+                                let match_ranges = this.excerpts.update(cx, |excerpts, cx| {
+                                    excerpts.stream_excerpts_with_context_lines_after(
+                                        prev_excerpt_id,
+                                        buffer.clone(),
                                         ranges,
                                         editor::DEFAULT_MULTIBUFFER_CONTEXT,
                                         cx,
                                     )
-                                })
+                                });
+                                (result_ix, match_ix, match_ranges)
                             })
                             .ok()?;
 
+                        let mut result_buffer = None;
                         while let Some(range) = match_ranges.next().await {
-                            this.update(&mut cx, |this, _| this.match_ranges.push(range))
-                                .ok()?;
+                            let result_buffer = result_buffer.get_or_insert(ResultBuffer {
+                                buffer: buffer.clone(),
+                                last_excerpt_id: range.start.excerpt_id,
+                                match_count: 0,
+                            });
+                            result_buffer.last_excerpt_id = range.start.excerpt_id;
+                            result_buffer.match_count += 1;
+                            this.update(&mut cx, |this, _| {
+                                this.match_ranges.insert(match_ix, range)
+                            })
+                            .ok()?;
+                            match_ix += 1;
                         }
-                        this.update(&mut cx, |_, cx| cx.notify()).ok()?;
+                        this.update(&mut cx, |this, cx| {
+                            if let Some(result_buffer) = result_buffer {
+                                this.result_buffers.insert(result_ix, result_buffer);
+                            }
+                            cx.notify();
+                        })
+                        .ok()?;
                     }
                     project::SearchResult::LimitReached => {
                         limit_reached = true;
@@ -1280,6 +1366,14 @@ impl ProjectSearchBar {
         }
     }
 
+    fn load_more_results(&mut self, _: &LoadMoreResults, cx: &mut ViewContext<Self>) {
+        if let Some(search) = self.active_project_search.as_ref() {
+            search.update(cx, |this, cx| {
+                this.model.update(cx, |model, cx| model.load_more(cx));
+            })
+        }
+    }
+
     fn select_next_match(&mut self, _: &SelectNextMatch, cx: &mut ViewContext<Self>) {
         if let Some(search) = self.active_project_search.as_ref() {
             search.update(cx, |this, cx| {
@@ -1462,6 +1556,15 @@ impl Render for ProjectSearchBar {
                         .child(Label::new("Search limit reached").color(Color::Warning))
                         .ml_2(),
                 )
+                .child(
+                    Button::new("project-search-load-more", "Load More")
+                        .on_click(
+                            cx.listener(|this, _, cx| this.load_more_results(&LoadMoreResults, cx)),
+                        )
+                        .tooltip(|cx| {
+                            Tooltip::for_action("Load more results", &LoadMoreResults, cx)
+                        }),
+                )
             });
 
         let search_line = h_flex()
@@ -1599,6 +1702,7 @@ impl Render for ProjectSearchBar {
             })
             .on_action(cx.listener(Self::select_next_match))
             .on_action(cx.listener(Self::select_prev_match))
+            .on_action(cx.listener(Self::load_more_results))
             .gap_2()
             .w_full()
             .child(search_line)
@@ -1,4 +1,5 @@
 use anyhow::{anyhow, Context, Result};
+use regex::{Captures, Regex, RegexBuilder};
 use smallvec::SmallVec;
 use std::{collections::BTreeMap, ops::Range};
 
@@ -6,21 +7,121 @@ use std::{collections::BTreeMap, ops::Range};
 pub struct Snippet {
     pub text: String,
     pub tabstops: Vec<TabStop>,
+    /// The choices of the tabstops that offer some, by the index of their tabstop.
+    pub choices: BTreeMap<usize, Vec<String>>,
+    /// The ranges of the text that mirror a tabstop through a transform.
+    pub transforms: Vec<TabStopTransform>,
 }
 
 type TabStop = SmallVec<[Range<isize>; 2]>;
 
+/// A range of the text of a snippet that contains the text of a tabstop, transformed, which is updated when the
+/// tabstop is left.
+#[derive(Clone, Debug, PartialEq)]
+pub struct TabStopTransform {
+    /// The index of the transformed tabstop.
+    pub tabstop: usize,
+    pub range: Range<isize>,
+    pub transform: Transform,
+}
+
+/// A regex replacement of a snippet, `/regex/format/options`.
+#[derive(Clone, Debug)]
+pub struct Transform {
+    regex: Regex,
+    format: Vec<FormatItem>,
+    global: bool,
+}
+
+impl PartialEq for Transform {
+    fn eq(&self, other: &Self) -> bool {
+        self.regex.as_str() == other.regex.as_str()
+            && self.format == other.format
+            && self.global == other.global
+    }
+}
+
+#[derive(Clone, Debug, PartialEq)]
+enum FormatItem {
+    Text(String),
+    Group(usize),
+    Case(usize, Case),
+    /// `${1:+if}`, `${1:-else}` and `${1:?if:else}`: the `if` text, or the group when it's `None`, if the group
+    /// matched, and the `else` text otherwise.
+    Conditional {
+        group: usize,
+        if_matched: Option<String>,
+        otherwise: String,
+    },
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+enum Case {
+    Upcase,
+    Downcase,
+    Capitalize,
+    Camelcase,
+    Pascalcase,
+}
+
 impl Snippet {
     pub fn parse(source: &str) -> Result<Self> {
-        let mut text = String::with_capacity(source.len());
-        let mut tabstops = BTreeMap::new();
-        parse_snippet(source, false, &mut text, &mut tabstops)
-            .context("failed to parse snippet")?;
+        Self::parse_with_variables(source, |_| None)
+    }
+
+    /// Parses a snippet, replacing its variables, like `$TM_FILENAME`, with their values.
+    ///
+    /// Unknown variables are replaced with their default, or else with their name as a placeholder. Empty variables
+    /// are replaced with their default too.
+    pub fn parse_with_variables(
+        source: &str,
+        variables: impl Fn(&str) -> Option<String>,
+    ) -> Result<Self> {
+        let mut parser = Parser::new(&variables, BTreeMap::new());
+        parser.parse(source).context("failed to parse snippet")?;
 
+        // The transforms mirror the placeholders of their tabstops, which can come after them.
+        if !parser.transforms.is_empty() {
+            let tabstop_texts = parser
+                .tabstops
+                .iter()
+                .map(|(index, ranges)| {
+                    let text = ranges
+                        .iter()
+                        .find(|range| !range.is_empty())
+                        .map_or(String::new(), |range| {
+                            parser.text[range.start as usize..range.end as usize].to_string()
+                        });
+                    (*index, text)
+                })
+                .collect();
+            parser = Parser::new(&variables, tabstop_texts);
+            parser.parse(source).context("failed to parse snippet")?;
+        }
+
+        let Parser {
+            text,
+            mut tabstops,
+            choices,
+            transforms,
+            variable_placeholders,
+            ..
+        } = parser;
         let len = text.len() as isize;
         let final_tabstop = tabstops.remove(&0);
+        let indices = tabstops
+            .keys()
+            .enumerate()
+            .map(|(ix, index)| (*index, ix))
+            .collect::<BTreeMap<_, _>>();
         let mut tabstops = tabstops.into_values().collect::<Vec<_>>();
+        tabstops.extend(
+            variable_placeholders
+                .into_iter()
+                .map(|range| [range].into_iter().collect()),
+        );
 
+        let final_tabstop_ix = tabstops.len();
         if let Some(final_tabstop) = final_tabstop {
             tabstops.push(final_tabstop);
         } else {
@@ -29,80 +130,480 @@ impl Snippet {
                 tabstops.push(end_tabstop);
             }
         }
+        let tabstop_ix = |index: usize| {
+            if index == 0 {
+                Some(final_tabstop_ix)
+            } else {
+                indices.get(&index).copied()
+            }
+        };
 
-        Ok(Snippet { text, tabstops })
+        Ok(Snippet {
+            text,
+            tabstops,
+            choices: choices
+                .into_iter()
+                .filter_map(|(index, choices)| Some((tabstop_ix(index)?, choices)))
+                .collect(),
+            transforms: transforms
+                .into_iter()
+                .filter_map(|(index, range, transform)| {
+                    Some(TabStopTransform {
+                        tabstop: tabstop_ix(index)?,
+                        range,
+                        transform,
+                    })
+                })
+                .collect(),
+        })
     }
 }
 
-fn parse_snippet<'a>(
-    mut source: &'a str,
-    nested: bool,
-    text: &mut String,
-    tabstops: &mut BTreeMap<usize, TabStop>,
-) -> Result<&'a str> {
-    loop {
-        match source.chars().next() {
-            None => return Ok(""),
-            Some('$') => {
-                source = parse_tabstop(&source[1..], text, tabstops)?;
+impl Transform {
+    /// Replaces the first match of the regex in the text, or all of them with the `g` option, with the format.
+    pub fn apply(&self, text: &str) -> String {
+        let mut result = String::with_capacity(text.len());
+        let mut last_match_end = 0;
+        for captures in self.regex.captures_iter(text) {
+            let Some(matched) = captures.get(0) else {
+                continue;
+            };
+            result.push_str(&text[last_match_end..matched.start()]);
+            for item in &self.format {
+                item.format(&captures, &mut result);
             }
-            Some('\\') => {
-                source = &source[1..];
-                if let Some(c) = source.chars().next() {
-                    text.push(c);
-                    source = &source[c.len_utf8()..];
+            last_match_end = matched.end();
+            if !self.global {
+                break;
+            }
+        }
+        result.push_str(&text[last_match_end..]);
+        result
+    }
+}
+
+impl FormatItem {
+    fn format(&self, captures: &Captures, result: &mut String) {
+        let group = |group: usize| captures.get(group).map(|group| group.as_str());
+        match self {
+            FormatItem::Text(text) => result.push_str(text),
+            FormatItem::Group(index) => result.push_str(group(*index).unwrap_or_default()),
+            FormatItem::Case(index, case) => {
+                result.push_str(&case.apply(group(*index).unwrap_or_default()))
+            }
+            FormatItem::Conditional {
+                group: index,
+                if_matched,
+                otherwise,
+            } => match group(*index).filter(|group| !group.is_empty()) {
+                Some(group) => result.push_str(if_matched.as_deref().unwrap_or(group)),
+                None => result.push_str(otherwise),
+            },
+        }
+    }
+}
+
+impl Case {
+    fn apply(self, text: &str) -> String {
+        let capitalize = |word: &str| {
+            let mut chars = word.chars();
+            chars.next().map_or(String::new(), |first| {
+                first.to_uppercase().chain(chars).collect()
+            })
+        };
+        match self {
+            Case::Upcase => text.to_uppercase(),
+            Case::Downcase => text.to_lowercase(),
+            Case::Capitalize => capitalize(text),
+            Case::Camelcase | Case::Pascalcase => {
+                let mut result = text
+                    .split(|c: char| !c.is_alphanumeric())
+                    .filter(|word| !word.is_empty())
+                    .map(capitalize)
+                    .collect::<String>();
+                if self == Case::Camelcase {
+                    let mut chars = result.chars();
+                    result = chars.next().map_or(String::new(), |first| {
+                        first.to_lowercase().chain(chars).collect()
+                    });
                 }
+                result
             }
-            Some('}') => {
-                if nested {
-                    return Ok(source);
-                } else {
-                    text.push('}');
+        }
+    }
+}
+
+struct Parser<'a> {
+    variables: &'a dyn Fn(&str) -> Option<String>,
+    /// The texts of the tabstops that the transforms are applied to.
+    tabstop_texts: BTreeMap<usize, String>,
+    text: String,
+    tabstops: BTreeMap<usize, TabStop>,
+    choices: BTreeMap<usize, Vec<String>>,
+    transforms: Vec<(usize, Range<isize>, Transform)>,
+    /// The names of the unknown variables, which are tabstops after the numbered ones.
+    variable_placeholders: Vec<Range<isize>>,
+}
+
+impl<'a> Parser<'a> {
+    fn new(
+        variables: &'a dyn Fn(&str) -> Option<String>,
+        tabstop_texts: BTreeMap<usize, String>,
+    ) -> Self {
+        Self {
+            variables,
+            tabstop_texts,
+            text: String::new(),
+            tabstops: BTreeMap::new(),
+            choices: BTreeMap::new(),
+            transforms: Vec::new(),
+            variable_placeholders: Vec::new(),
+        }
+    }
+
+    fn parse(&mut self, source: &str) -> Result<()> {
+        self.text.reserve(source.len());
+        self.parse_snippet(source, false)?;
+        Ok(())
+    }
+
+    fn parse_snippet<'b>(&mut self, mut source: &'b str, nested: bool) -> Result<&'b str> {
+        loop {
+            match source.chars().next() {
+                None => return Ok(""),
+                Some('$') => {
+                    source = self.parse_dollar(&source[1..])?;
+                }
+                Some('\\') => {
                     source = &source[1..];
+                    if let Some(c) = source.chars().next() {
+                        self.text.push(c);
+                        source = &source[c.len_utf8()..];
+                    }
+                }
+                Some('}') => {
+                    if nested {
+                        return Ok(source);
+                    } else {
+                        self.text.push('}');
+                        source = &source[1..];
+                    }
+                }
+                Some(_) => {
+                    let chunk_end = source.find(['}', '$', '\\']).unwrap_or(source.len());
+                    let (chunk, rest) = source.split_at(chunk_end);
+                    self.text.push_str(chunk);
+                    source = rest;
                 }
             }
-            Some(_) => {
-                let chunk_end = source.find(['}', '$', '\\']).unwrap_or(source.len());
-                let (chunk, rest) = source.split_at(chunk_end);
-                text.push_str(chunk);
-                source = rest;
+        }
+    }
+
+    /// Parses a tabstop, a placeholder, a choice or a variable, after its `$`.
+    fn parse_dollar<'b>(&mut self, source: &'b str) -> Result<&'b str> {
+        if let Some(rest) = source.strip_prefix('{') {
+            if rest.starts_with(|c: char| c.is_ascii_digit()) {
+                self.parse_tabstop(rest)
+            } else if rest.starts_with(is_variable_start) {
+                self.parse_variable(rest)
+            } else {
+                Err(anyhow!("expected a tabstop or a variable"))
+            }
+        } else if source.starts_with(|c: char| c.is_ascii_digit()) {
+            let start = self.text.len();
+            let (index, rest) = parse_int(source)?;
+            self.push_tabstop(index, start);
+            Ok(rest)
+        } else if source.starts_with(is_variable_start) {
+            let (name, rest) = parse_variable_name(source);
+            self.push_variable(name, None);
+            Ok(rest)
+        } else {
+            self.text.push('$');
+            Ok(source)
+        }
+    }
+
+    /// Parses the tabstop, placeholder, choice or transform after `${`.
+    fn parse_tabstop<'b>(&mut self, source: &'b str) -> Result<&'b str> {
+        let start = self.text.len();
+        let (index, mut source) = parse_int(source)?;
+        if let Some(rest) = source.strip_prefix(':') {
+            source = self.parse_snippet(rest, true)?;
+        } else if let Some(rest) = source.strip_prefix('|') {
+            let (choices, rest) = parse_choices(rest)?;
+            self.text
+                .push_str(choices.first().map_or("", String::as_str));
+            self.choices.entry(index).or_insert(choices);
+            source = rest;
+        } else if let Some(rest) = source.strip_prefix('/') {
+            let (transform, rest) = parse_transform(rest)?;
+            let value = transform.apply(self.tabstop_texts.get(&index).map_or("", String::as_str));
+            self.text.push_str(&value);
+            self.transforms
+                .push((index, start as isize..self.text.len() as isize, transform));
+            return expect_closing_brace(rest);
+        }
+
+        let rest = expect_closing_brace(source)?;
+        self.push_tabstop(index, start);
+        Ok(rest)
+    }
+
+    /// Parses the variable after `${`, with its default or transform.
+    fn parse_variable<'b>(&mut self, source: &'b str) -> Result<&'b str> {
+        let (name, source) = parse_variable_name(source);
+        let value = (self.variables)(name);
+        if let Some(rest) = source.strip_prefix(':') {
+            // Like unknown variables, empty ones are replaced with their default.
+            let rest = match value.filter(|value| !value.is_empty()) {
+                Some(value) => {
+                    // The default isn't inserted, but it has to be parsed to find where it ends.
+                    let mut default = Parser::new(self.variables, BTreeMap::new());
+                    let rest = default.parse_snippet(rest, true)?;
+                    self.text.push_str(&value);
+                    rest
+                }
+                None => self.parse_snippet(rest, true)?,
+            };
+            expect_closing_brace(rest)
+        } else if let Some(rest) = source.strip_prefix('/') {
+            let (transform, rest) = parse_transform(rest)?;
+            self.text
+                .push_str(&transform.apply(value.as_deref().unwrap_or_default()));
+            expect_closing_brace(rest)
+        } else {
+            let rest = expect_closing_brace(source)?;
+            self.push_variable(name, value);
+            Ok(rest)
+        }
+    }
+
+    fn push_tabstop(&mut self, index: usize, start: usize) {
+        self.tabstops
+            .entry(index)
+            .or_default()
+            .push(start as isize..self.text.len() as isize);
+    }
+
+    fn push_variable(&mut self, name: &str, value: Option<String>) {
+        match value {
+            Some(value) => self.text.push_str(&value),
+            None => {
+                let start = self.text.len() as isize;
+                self.text.push_str(name);
+                self.variable_placeholders
+                    .push(start..self.text.len() as isize);
             }
         }
     }
 }
 
-fn parse_tabstop<'a>(
-    mut source: &'a str,
-    text: &mut String,
-    tabstops: &mut BTreeMap<usize, TabStop>,
-) -> Result<&'a str> {
-    let tabstop_start = text.len();
-    let tabstop_index;
-    if source.starts_with('{') {
-        let (index, rest) = parse_int(&source[1..])?;
-        tabstop_index = index;
-        source = rest;
+fn expect_closing_brace(source: &str) -> Result<&str> {
+    source
+        .strip_prefix('}')
+        .ok_or_else(|| anyhow!("expected a closing brace"))
+}
 
-        if source.starts_with(':') {
-            source = parse_snippet(&source[1..], true, text, tabstops)?;
+fn is_variable_start(c: char) -> bool {
+    c.is_ascii_alphabetic() || c == '_'
+}
+
+fn parse_variable_name(source: &str) -> (&str, &str) {
+    let len = source
+        .find(|c: char| !c.is_ascii_alphanumeric() && c != '_')
+        .unwrap_or(source.len());
+    source.split_at(len)
+}
+
+/// Parses the choices of `${1|one,two|}` after the first `|`, and returns the source after the last one.
+fn parse_choices(source: &str) -> Result<(Vec<String>, &str)> {
+    let mut choices = vec![String::new()];
+    let mut chars = source.char_indices();
+    while let Some((ix, c)) = chars.next() {
+        match c {
+            '\\' => match chars.next() {
+                Some((_, c @ (',' | '|' | '\\' | '$' | '}'))) => {
+                    choices.last_mut().unwrap().push(c)
+                }
+                Some((_, c)) => {
+                    let choice = choices.last_mut().unwrap();
+                    choice.push('\\');
+                    choice.push(c);
+                }
+                None => break,
+            },
+            ',' => choices.push(String::new()),
+            '|' => return Ok((choices, &source[ix + 1..])),
+            c => choices.last_mut().unwrap().push(c),
         }
+    }
+    Err(anyhow!("expected the end of the choices"))
+}
 
-        if source.starts_with('}') {
-            source = &source[1..];
-        } else {
-            return Err(anyhow!("expected a closing brace"));
+/// Parses the `regex/format/options` of a transform after the first `/`, and returns the source after the options.
+fn parse_transform(source: &str) -> Result<(Transform, &str)> {
+    let mut pattern = String::new();
+    let mut chars = source.char_indices();
+    let format_start = loop {
+        match chars.next() {
+            Some((_, '\\')) => match chars.next() {
+                Some((_, '/')) => pattern.push('/'),
+                Some((_, c)) => {
+                    pattern.push('\\');
+                    pattern.push(c);
+                }
+                None => return Err(anyhow!("expected the end of the regex")),
+            },
+            Some((ix, '/')) => break ix + 1,
+            Some((_, c)) => pattern.push(c),
+            None => return Err(anyhow!("expected the end of the regex")),
         }
+    };
+
+    let (format, source) = parse_format(&source[format_start..])?;
+    let options_len = source.find('}').unwrap_or(source.len());
+    let (options, source) = source.split_at(options_len);
+    let regex = RegexBuilder::new(&pattern)
+        .case_insensitive(options.contains('i'))
+        .multi_line(options.contains('m'))
+        .dot_matches_new_line(options.contains('s'))
+        .build()
+        .with_context(|| format!("invalid snippet regex {pattern:?}"))?;
+    Ok((
+        Transform {
+            regex,
+            format,
+            global: options.contains('g'),
+        },
+        source,
+    ))
+}
+
+/// Parses the format of a transform, and returns the source after its closing `/`.
+fn parse_format(mut source: &str) -> Result<(Vec<FormatItem>, &str)> {
+    let mut items = Vec::new();
+    let mut text = String::new();
+    loop {
+        let mut chars = source.chars();
+        match chars.next() {
+            None => return Err(anyhow!("expected the end of the format")),
+            Some('/') => {
+                if !text.is_empty() {
+                    items.push(FormatItem::Text(text));
+                }
+                return Ok((items, &source[1..]));
+            }
+            Some('\\') => {
+                match chars.next() {
+                    Some('n') => text.push('\n'),
+                    Some('t') => text.push('\t'),
+                    Some(c @ ('/' | '$' | '\\' | '}')) => text.push(c),
+                    Some(c) => {
+                        text.push('\\');
+                        text.push(c);
+                    }
+                    None => return Err(anyhow!("expected the end of the format")),
+                }
+                source = chars.as_str();
+            }
+            Some('$') => {
+                let rest = chars.as_str();
+                let (item, rest) = if let Some(rest) = rest.strip_prefix('{') {
+                    parse_format_group(rest)?
+                } else if rest.starts_with(|c: char| c.is_ascii_digit()) {
+                    let (group, rest) = parse_int(rest)?;
+                    (FormatItem::Group(group), rest)
+                } else {
+                    text.push('$');
+                    source = rest;
+                    continue;
+                };
+                if !text.is_empty() {
+                    items.push(FormatItem::Text(std::mem::take(&mut text)));
+                }
+                items.push(item);
+                source = rest;
+            }
+            Some(c) => {
+                text.push(c);
+                source = chars.as_str();
+            }
+        }
+    }
+}
+
+/// Parses a group of a format after its `${`, and returns the source after its closing brace.
+fn parse_format_group(source: &str) -> Result<(FormatItem, &str)> {
+    let (group, source) = parse_int(source)?;
+    let Some(source) = source.strip_prefix(':') else {
+        return Ok((FormatItem::Group(group), expect_closing_brace(source)?));
+    };
+
+    if let Some(source) = source.strip_prefix('/') {
+        let (name, source) = parse_variable_name(source);
+        let case = match name {
+            "upcase" => Case::Upcase,
+            "downcase" => Case::Downcase,
+            "capitalize" => Case::Capitalize,
+            "camelcase" => Case::Camelcase,
+            "pascalcase" => Case::Pascalcase,
+            _ => return Err(anyhow!("unknown snippet case {name:?}")),
+        };
+        Ok((FormatItem::Case(group, case), expect_closing_brace(source)?))
+    } else if let Some(source) = source.strip_prefix('+') {
+        let (if_matched, source) = parse_format_text(source, &['}'])?;
+        Ok((
+            FormatItem::Conditional {
+                group,
+                if_matched: Some(if_matched),
+                otherwise: String::new(),
+            },
+            &source[1..],
+        ))
+    } else if let Some(source) = source.strip_prefix('?') {
+        let (if_matched, source) = parse_format_text(source, &[':'])?;
+        let (otherwise, source) = parse_format_text(&source[1..], &['}'])?;
+        Ok((
+            FormatItem::Conditional {
+                group,
+                if_matched: Some(if_matched),
+                otherwise,
+            },
+            &source[1..],
+        ))
     } else {
-        let (index, rest) = parse_int(source)?;
-        tabstop_index = index;
-        source = rest;
+        let source = source.strip_prefix('-').unwrap_or(source);
+        let (otherwise, source) = parse_format_text(source, &['}'])?;
+        Ok((
+            FormatItem::Conditional {
+                group,
+                if_matched: None,
+                otherwise,
+            },
+            &source[1..],
+        ))
     }
+}
 
-    tabstops
-        .entry(tabstop_index)
-        .or_default()
-        .push(tabstop_start as isize..text.len() as isize);
-    Ok(source)
+/// Parses the text of a condition of a format, up to one of the terminators, which starts the returned source.
+fn parse_format_text<'a>(source: &'a str, terminators: &[char]) -> Result<(String, &'a str)> {
+    let mut text = String::new();
+    let mut chars = source.char_indices();
+    while let Some((ix, c)) = chars.next() {
+        if terminators.contains(&c) {
+            return Ok((text, &source[ix..]));
+        }
+        if c == '\\' {
+            match chars.next() {
+                Some((_, c)) => text.push(c),
+                None => break,
+            }
+        } else {
+            text.push(c);
+        }
+    }
+    Err(anyhow!("expected {terminators:?} in snippet format"))
 }
 
 fn parse_int(source: &str) -> Result<(usize, &str)> {
@@ -199,6 +700,83 @@ mod tests {
         assert_eq!(tabstops(&snippet), &[vec![3..3]]);
     }
 
+    #[test]
+    fn test_snippet_with_choices() {
+        let snippet = Snippet::parse("let x = ${1|one,two\\,three|};$0").unwrap();
+        assert_eq!(snippet.text, "let x = one;");
+        assert_eq!(tabstops(&snippet), &[vec![8..11], vec![12..12]]);
+        assert_eq!(
+            snippet.choices,
+            BTreeMap::from_iter([(0, vec!["one".to_string(), "two,three".to_string()])])
+        );
+    }
+
+    #[test]
+    fn test_snippet_with_variables() {
+        let variables = |name: &str| match name {
+            "TM_FILENAME" => Some("main.rs".to_string()),
+            "TM_SELECTED_TEXT" => Some(String::new()),
+            _ => None,
+        };
+        let snippet = Snippet::parse_with_variables(
+            "// $TM_FILENAME ${TM_SELECTED_TEXT:${1:selection}} ${CLIPBOARD:none} $UNKNOWN",
+            variables,
+        )
+        .unwrap();
+        assert_eq!(snippet.text, "// main.rs selection none UNKNOWN");
+
+        // Unknown variables without a default become placeholders after the numbered tabstops.
+        assert_eq!(
+            tabstops(&snippet),
+            &[vec![11..20], vec![26..33], vec![33..33]]
+        );
+
+        let snippet =
+            Snippet::parse_with_variables("fn ${TM_SELECTED_TEXT:${1:name}}() {}", |name| {
+                (name == "TM_SELECTED_TEXT").then(|| "main".to_string())
+            })
+            .unwrap();
+        assert_eq!(snippet.text, "fn main() {}");
+        assert_eq!(tabstops(&snippet), &[vec![12..12]]);
+
+        let snippet = Snippet::parse_with_variables(
+            "mod ${TM_FILENAME/(.*)\\.rs$/${1:/upcase}/};",
+            variables,
+        )
+        .unwrap();
+        assert_eq!(snippet.text, "mod MAIN;");
+    }
+
+    #[test]
+    fn test_snippet_with_tabstop_transforms() {
+        let snippet =
+            Snippet::parse("struct ${1:foo_bar}; impl ${1/(.*)/${1:/pascalcase}/} {}").unwrap();
+        assert_eq!(snippet.text, "struct foo_bar; impl FooBar {}");
+        assert_eq!(tabstops(&snippet), &[vec![7..14], vec![30..30]]);
+        assert_eq!(snippet.transforms.len(), 1);
+        let transform = &snippet.transforms[0];
+        assert_eq!((transform.tabstop, transform.range.clone()), (0, 21..27));
+        assert_eq!(transform.transform.apply("hello_world"), "HelloWorld");
+
+        // Transforms that come before their tabstop mirror its placeholder too.
+        let snippet = Snippet::parse("${1/a/b/g}-${1:aXa}").unwrap();
+        assert_eq!(snippet.text, "bXb-aXa");
+    }
+
+    #[test]
+    fn test_transform_formats() {
+        let snippet_text = |source: &str| Snippet::parse(source).unwrap().text;
+        assert_eq!(
+            snippet_text("${1:a-b}${1/(a)|(x)/${1:+one}${2:?two:none}${2:-else}/}"),
+            "a-bonenoneelse-b"
+        );
+        assert_eq!(
+            snippet_text("${1:Hello}${1/(.)(.*)/${2:/upcase}\\/$1/}"),
+            "HelloELLO/H"
+        );
+        assert_eq!(snippet_text("${1:aAa}${1/a/x/gi}"), "aAaxxx");
+    }
+
     fn tabstops(snippet: &Snippet) -> Vec<Vec<Range<isize>>> {
         snippet.tabstops.iter().map(|t| t.to_vec()).collect()
     }
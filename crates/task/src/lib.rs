@@ -1,6 +1,7 @@
 //! Baseline interface of Tasks in Zed: all tasks in Zed are intended to use those for implementing their own logic.
 #![deny(missing_docs)]
 
+mod problem_matcher;
 pub mod static_source;
 mod task_template;
 mod vscode_format;
@@ -12,6 +13,9 @@ use std::path::PathBuf;
 use std::str::FromStr;
 use std::{borrow::Cow, path::Path};
 
+pub use problem_matcher::{
+    CompiledProblemMatcher, CustomProblemMatcher, Problem, ProblemMatcher, ProblemSeverity,
+};
 pub use task_template::{RevealStrategy, TaskTemplate, TaskTemplates};
 pub use vscode_format::VsCodeTaskFile;
 
@@ -78,6 +82,8 @@ pub struct SpawnInTerminal {
     pub allow_concurrent_runs: bool,
     /// What to do with the terminal pane and tab, after the command was started.
     pub reveal: RevealStrategy,
+    /// How to find the problems in the output of the task, to show them as diagnostics.
+    pub problem_matchers: Vec<ProblemMatcher>,
 }
 
 /// A final form of the [`TaskTemplate`], that got resolved with a particualar [`TaskContext`] and now is ready to spawn the actual task.
@@ -148,6 +154,10 @@ pub enum VariableName {
     SelectedText,
     /// The symbol selected by the symbol tagging system, specifically the @run capture in a runnables.scm
     RunnableSymbol,
+    /// The full name of the test at the latest cursor/selection position, as reported by the language server.
+    TestName,
+    /// The name of the crate containing the currently opened file, as reported by the language server.
+    CrateName,
     /// Custom variable, provided by the plugin or other external source.
     /// Will be printed with `CUSTOM_` prefix to avoid potential conflicts with other variables.
     Custom(Cow<'static, str>),
@@ -172,6 +182,8 @@ impl FromStr for VariableName {
             "SELECTED_TEXT" => Self::SelectedText,
             "ROW" => Self::Row,
             "COLUMN" => Self::Column,
+            "TEST_NAME" => Self::TestName,
+            "CRATE_NAME" => Self::CrateName,
             _ => {
                 if let Some(custom_name) =
                     without_prefix.strip_prefix(ZED_CUSTOM_VARIABLE_NAME_PREFIX)
@@ -204,6 +216,8 @@ impl std::fmt::Display for VariableName {
             Self::Column => write!(f, "{ZED_VARIABLE_NAME_PREFIX}COLUMN"),
             Self::SelectedText => write!(f, "{ZED_VARIABLE_NAME_PREFIX}SELECTED_TEXT"),
             Self::RunnableSymbol => write!(f, "{ZED_VARIABLE_NAME_PREFIX}RUNNABLE_SYMBOL"),
+            Self::TestName => write!(f, "{ZED_VARIABLE_NAME_PREFIX}TEST_NAME"),
+            Self::CrateName => write!(f, "{ZED_VARIABLE_NAME_PREFIX}CRATE_NAME"),
             Self::Custom(s) => write!(
                 f,
                 "{ZED_VARIABLE_NAME_PREFIX}{ZED_CUSTOM_VARIABLE_NAME_PREFIX}{s}"
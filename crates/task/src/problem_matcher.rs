@@ -0,0 +1,310 @@
+//! Problem matchers find the problems that a task prints, like the errors of `cargo watch -x check` or `tsc --watch`,
+//! to show them as diagnostics of the project.
+
+use anyhow::{anyhow, Context};
+use regex::{Captures, Regex};
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+
+/// A way to find the problems in the output of a task: the name of a built-in problem matcher, or a custom one.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize, JsonSchema)]
+#[serde(untagged)]
+pub enum ProblemMatcher {
+    /// A built-in problem matcher:
+    /// * `rustc` — errors and warnings of `rustc`, `cargo check`, `cargo build` and `cargo watch`
+    /// * `tsc` — errors of the TypeScript compiler, also in watch mode
+    /// * `go` — errors of `go build` and `go vet`
+    Named(String),
+    /// A problem matcher defined by regexes.
+    Custom(CustomProblemMatcher),
+}
+
+/// A problem matcher defined by regexes.
+#[derive(Clone, Debug, Default, PartialEq, Eq, Serialize, Deserialize, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub struct CustomProblemMatcher {
+    /// The source of the diagnostics, shown with their message. Defaults to the label of the task.
+    #[serde(default)]
+    pub source: Option<String>,
+    /// A regex matching the output of a problem, possibly spanning several lines, with the named groups `file`, `line`
+    /// and `message`, and optionally `column`, `end_line`, `end_column`, `severity` and `code`.
+    pub pattern: String,
+    /// A regex matching the output that starts a new run of a watch task, after a file changed. Only the problems
+    /// printed after its last match are reported, replacing the ones of the previous runs.
+    #[serde(default)]
+    pub run_start_pattern: Option<String>,
+}
+
+/// The severity of a problem, from its `severity` group: `error` unless it starts with `warn` or `info`, or is
+/// `note`, `hint` or `help`.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ProblemSeverity {
+    /// An error.
+    Error,
+    /// A warning.
+    Warning,
+    /// An informational message, like a `note`.
+    Information,
+    /// A hint, like a `help` message.
+    Hint,
+}
+
+/// A problem that a task printed.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Problem {
+    /// The path of the file of the problem, as printed: absolute, or relative to the working directory of the task.
+    pub path: String,
+    /// The zero-based row and column of the start of the problem.
+    pub start: (u32, u32),
+    /// The zero-based row and column of the end of the problem, if printed.
+    pub end: Option<(u32, u32)>,
+    /// The severity of the problem.
+    pub severity: ProblemSeverity,
+    /// The code of the problem, like `E0425`.
+    pub code: Option<String>,
+    /// The message of the problem.
+    pub message: String,
+    /// The source of the problem matcher, if it has one.
+    pub source: Option<String>,
+}
+
+/// A problem matcher with its regexes compiled.
+#[derive(Clone, Debug)]
+pub struct CompiledProblemMatcher {
+    source: Option<String>,
+    pattern: Regex,
+    run_start_pattern: Option<Regex>,
+}
+
+impl ProblemMatcher {
+    /// Compiles the regexes of the problem matcher, several ones for built-in matchers of tools that print problems
+    /// in different formats.
+    pub fn compile(&self) -> anyhow::Result<Vec<CompiledProblemMatcher>> {
+        match self {
+            Self::Named(name) => {
+                let matchers = builtin_problem_matchers(name)
+                    .ok_or_else(|| anyhow!("unknown problem matcher {name:?}"))?;
+                matchers.iter().map(CompiledProblemMatcher::new).collect()
+            }
+            Self::Custom(matcher) => Ok(vec![CompiledProblemMatcher::new(matcher)?]),
+        }
+    }
+}
+
+impl CompiledProblemMatcher {
+    fn new(matcher: &CustomProblemMatcher) -> anyhow::Result<Self> {
+        let pattern = Regex::new(&matcher.pattern)
+            .with_context(|| format!("invalid problem matcher pattern {:?}", matcher.pattern))?;
+        for group in ["file", "line", "message"] {
+            if !pattern.capture_names().any(|name| name == Some(group)) {
+                anyhow::bail!(
+                    "problem matcher pattern {:?} has no `{group}` group",
+                    matcher.pattern
+                );
+            }
+        }
+        let run_start_pattern = matcher
+            .run_start_pattern
+            .as_deref()
+            .map(Regex::new)
+            .transpose()
+            .context("invalid problem matcher run start pattern")?;
+        Ok(Self {
+            source: matcher.source.clone(),
+            pattern,
+            run_start_pattern,
+        })
+    }
+
+    /// The problems of the last run of the task in its output.
+    pub fn problems(&self, output: &str) -> Vec<Problem> {
+        let output = match &self.run_start_pattern {
+            Some(run_start_pattern) => run_start_pattern
+                .find_iter(output)
+                .last()
+                .map_or(output, |run_start| &output[run_start.end()..]),
+            None => output,
+        };
+        self.pattern
+            .captures_iter(output)
+            .filter_map(|captures| self.problem(&captures))
+            .collect()
+    }
+
+    fn problem(&self, captures: &Captures) -> Option<Problem> {
+        let text = |group: &str| captures.name(group).map(|capture| capture.as_str().trim());
+        let number = |group: &str| {
+            text(group)
+                .and_then(|number| number.parse::<u32>().ok())
+                .map(|number| number.saturating_sub(1))
+        };
+        let path = text("file").filter(|path| !path.is_empty())?;
+        let row = number("line")?;
+        let column = number("column").unwrap_or(0);
+        let end = number("end_line")
+            .map(|end_row| (end_row, number("end_column").unwrap_or(column)))
+            .or_else(|| number("end_column").map(|end_column| (row, end_column)));
+        let severity = match text("severity").map(|severity| severity.to_lowercase()) {
+            Some(severity) if severity.starts_with("warn") => ProblemSeverity::Warning,
+            Some(severity) if severity.starts_with("info") || severity == "note" => {
+                ProblemSeverity::Information
+            }
+            Some(severity) if severity == "hint" || severity == "help" => ProblemSeverity::Hint,
+            _ => ProblemSeverity::Error,
+        };
+        Some(Problem {
+            path: path.to_string(),
+            start: (row, column),
+            end,
+            severity,
+            code: text("code").map(ToOwned::to_owned),
+            message: text("message")?.to_string(),
+            source: self.source.clone(),
+        })
+    }
+}
+
+fn builtin_problem_matchers(name: &str) -> Option<Vec<CustomProblemMatcher>> {
+    let matchers = match name {
+        "rustc" => vec![CustomProblemMatcher {
+            source: Some("rustc".to_string()),
+            pattern: r"(?m)^(?P<severity>error|warning)(?:\[(?P<code>[^\]]+)\])?: (?P<message>.+)\n\s*--> (?P<file>[^\n]+?):(?P<line>\d+):(?P<column>\d+)$".to_string(),
+            run_start_pattern: Some(r"(?m)^\[Running '.*'\]$".to_string()),
+        }],
+        "tsc" => {
+            let run_start_pattern = Some(
+                r"(?m)^.*(?:Starting compilation in watch mode|File change detected\. Starting incremental compilation)\.\.\.$"
+                    .to_string(),
+            );
+            vec![
+                CustomProblemMatcher {
+                    source: Some("tsc".to_string()),
+                    pattern: r"(?m)^(?P<file>[^\s(][^(\n]*)\((?P<line>\d+),(?P<column>\d+)\): (?P<severity>error|warning) (?P<code>TS\d+): (?P<message>.+)$".to_string(),
+                    run_start_pattern: run_start_pattern.clone(),
+                },
+                CustomProblemMatcher {
+                    source: Some("tsc".to_string()),
+                    pattern: r"(?m)^(?P<file>[^\s:][^:\n]*):(?P<line>\d+):(?P<column>\d+) - (?P<severity>error|warning) (?P<code>TS\d+): (?P<message>.+)$".to_string(),
+                    run_start_pattern,
+                },
+            ]
+        }
+        "go" => vec![CustomProblemMatcher {
+            source: Some("go".to_string()),
+            pattern: r"(?m)^\s*(?P<file>[^\s:][^:\n]*\.go):(?P<line>\d+)(?::(?P<column>\d+))?: (?P<message>.+)$".to_string(),
+            run_start_pattern: None,
+        }],
+        _ => return None,
+    };
+    Some(matchers)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn problems(matcher: ProblemMatcher, output: &str) -> Vec<Problem> {
+        matcher
+            .compile()
+            .unwrap()
+            .iter()
+            .flat_map(|matcher| matcher.problems(output))
+            .collect()
+    }
+
+    #[test]
+    fn test_rustc_problem_matcher() {
+        let output = "\
+[Running 'cargo check']
+    Checking app v0.1.0 (/app)
+error[E0425]: cannot find value `x` in this scope
+ --> src/main.rs:2:5
+  |
+2 |     x
+  |     ^ not found in this scope
+
+[Running 'cargo check']
+    Checking app v0.1.0 (/app)
+warning: unused variable: `y`
+ --> src/lib.rs:10:9
+  |
+";
+        assert_eq!(
+            problems(ProblemMatcher::Named("rustc".to_string()), output),
+            vec![Problem {
+                path: "src/lib.rs".to_string(),
+                start: (9, 8),
+                end: None,
+                severity: ProblemSeverity::Warning,
+                code: None,
+                message: "unused variable: `y`".to_string(),
+                source: Some("rustc".to_string()),
+            }]
+        );
+    }
+
+    #[test]
+    fn test_tsc_problem_matcher() {
+        let output = "\
+[10:00:00 AM] Starting compilation in watch mode...
+
+src/a.ts(1,7): error TS2322: Type 'string' is not assignable to type 'number'.
+src/b.ts:3:1 - error TS2304: Cannot find name 'foo'.
+
+[10:00:01 AM] Found 2 errors. Watching for file changes.
+";
+        assert_eq!(
+            problems(ProblemMatcher::Named("tsc".to_string()), output),
+            vec![
+                Problem {
+                    path: "src/a.ts".to_string(),
+                    start: (0, 6),
+                    end: None,
+                    severity: ProblemSeverity::Error,
+                    code: Some("TS2322".to_string()),
+                    message: "Type 'string' is not assignable to type 'number'.".to_string(),
+                    source: Some("tsc".to_string()),
+                },
+                Problem {
+                    path: "src/b.ts".to_string(),
+                    start: (2, 0),
+                    end: None,
+                    severity: ProblemSeverity::Error,
+                    code: Some("TS2304".to_string()),
+                    message: "Cannot find name 'foo'.".to_string(),
+                    source: Some("tsc".to_string()),
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn test_custom_problem_matcher() {
+        let matcher = ProblemMatcher::Custom(CustomProblemMatcher {
+            source: None,
+            pattern: r"(?m)^(?P<file>\S+):(?P<line>\d+):(?P<column>\d+)-(?P<end_column>\d+) (?P<severity>\w+): (?P<message>.+)$".to_string(),
+            run_start_pattern: None,
+        });
+        assert_eq!(
+            problems(matcher, "lib/a.ml:4:2-9 hint: prefer `let*`\n"),
+            vec![Problem {
+                path: "lib/a.ml".to_string(),
+                start: (3, 1),
+                end: Some((3, 8)),
+                severity: ProblemSeverity::Hint,
+                code: None,
+                message: "prefer `let*`".to_string(),
+                source: None,
+            }]
+        );
+
+        let missing_group = ProblemMatcher::Custom(CustomProblemMatcher {
+            pattern: r"(?P<file>\S+): (?P<message>.+)".to_string(),
+            ..CustomProblemMatcher::default()
+        });
+        assert!(missing_group.compile().is_err());
+        assert!(ProblemMatcher::Named("unknown".to_string())
+            .compile()
+            .is_err());
+    }
+}
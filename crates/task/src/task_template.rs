@@ -8,8 +8,8 @@ use sha2::{Digest, Sha256};
 use util::{truncate_and_remove_front, ResultExt};
 
 use crate::{
-    ResolvedTask, SpawnInTerminal, TaskContext, TaskId, TerminalWorkDir, VariableName,
-    ZED_VARIABLE_NAME_PREFIX,
+    ProblemMatcher, ResolvedTask, SpawnInTerminal, TaskContext, TaskId, TerminalWorkDir,
+    VariableName, ZED_VARIABLE_NAME_PREFIX,
 };
 
 /// A template definition of a Zed task to run.
@@ -49,6 +49,12 @@ pub struct TaskTemplate {
     /// Represents the tags which this template attaches to. Adding this removes this task from other UI.
     #[serde(default)]
     pub tags: Vec<String>,
+
+    /// How to find the problems in the output of the task, to show them as diagnostics of the project while it runs,
+    /// e.g. for watch tasks like `cargo watch -x check` or `tsc --watch`: the names of built-in problem matchers
+    /// (`rustc`, `tsc` and `go`), or custom ones with regexes.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub problem_matchers: Vec<ProblemMatcher>,
 }
 
 /// What to do with the terminal pane and tab, after the command was started.
@@ -194,6 +200,7 @@ impl TaskTemplate {
                 use_new_terminal: self.use_new_terminal,
                 allow_concurrent_runs: self.allow_concurrent_runs,
                 reveal: self.reveal,
+                problem_matchers: self.problem_matchers.clone(),
             }),
         })
     }
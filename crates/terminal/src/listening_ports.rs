@@ -0,0 +1,64 @@
+use std::sync::OnceLock;
+
+use regex::Regex;
+
+static LISTENING_PORT_REGEXES: OnceLock<Vec<Regex>> = OnceLock::new();
+
+/// The ports that the output of a terminal says a server is listening on, in the order they were first printed,
+/// like the `3000` of `Listening on port 3000`, `Server running at http://localhost:3000/` or
+/// `Serving HTTP on 0.0.0.0 port 3000`.
+pub fn listening_ports(output: &str) -> Vec<u16> {
+    let regexes = LISTENING_PORT_REGEXES.get_or_init(|| {
+        [
+            r"(?i)\blisten(?:ing)?\s+(?:on\s+)?(?:port\s+)?(?:[\w.\-\[\]:/]*:)?(\d{2,5})\b",
+            r"(?i)\b(?:localhost|127\.0\.0\.1|0\.0\.0\.0|\[::1?\]):(\d{2,5})\b",
+            r"(?i)\b(?:serving|running|started)\b.*\bport\s+(\d{2,5})\b",
+        ]
+        .into_iter()
+        .map(|regex| Regex::new(regex).unwrap())
+        .collect()
+    });
+
+    let mut ports = Vec::new();
+    for line in output.lines() {
+        let mut line_ports = regexes
+            .iter()
+            .flat_map(|regex| regex.captures_iter(line))
+            .filter_map(|captures| {
+                let port = captures.get(1)?;
+                Some((port.start(), port.as_str().parse::<u16>().ok()?))
+            })
+            .filter(|(_, port)| *port != 0)
+            .collect::<Vec<_>>();
+        line_ports.sort_unstable();
+        for (_, port) in line_ports {
+            if !ports.contains(&port) {
+                ports.push(port);
+            }
+        }
+    }
+    ports
+}
+
+#[cfg(test)]
+mod tests {
+    use super::listening_ports;
+
+    #[test]
+    fn test_listening_ports() {
+        let output = "\
+> next dev
+   - Local:        http://localhost:3000
+Listening on port 8080
+Server listening on [::]:9229
+Serving HTTP on 0.0.0.0 port 8000 (http://0.0.0.0:8000/) ...
+Starting development server at http://127.0.0.1:8000/
+Compiled 12 files in 1.2s, see http://example.com:4000 for the docs
+";
+        assert_eq!(listening_ports(output), vec![3000, 8080, 9229, 8000]);
+        assert_eq!(
+            listening_ports("Found 0 errors. Watching for file changes."),
+            vec![]
+        );
+    }
+}
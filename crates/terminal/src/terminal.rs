@@ -2,9 +2,12 @@ pub mod mappings;
 
 pub use alacritty_terminal;
 
+mod listening_ports;
 mod pty_info;
 pub mod terminal_settings;
 
+pub use listening_ports::listening_ports;
+
 use alacritty_terminal::{
     event::{Event as AlacTermEvent, EventListener, Notify, WindowSize},
     event_loop::{EventLoop, Msg, Notifier},
@@ -13,7 +16,7 @@ use alacritty_terminal::{
     selection::{Selection, SelectionRange, SelectionType},
     sync::FairMutex,
     term::{
-        cell::Cell,
+        cell::{Cell, Flags},
         search::{Match, RegexIter, RegexSearch},
         Config, RenderableCursor, TermMode,
     },
@@ -1150,6 +1153,34 @@ impl Terminal {
         lines
     }
 
+    /// The text of the terminal's scrollback and screen, with the lines that were wrapped to fit its width joined back.
+    /// It's read on the background executor, as the scrollback can be long.
+    pub fn output_text(&self, cx: &AppContext) -> Task<String> {
+        let term = self.term.clone();
+        cx.background_executor().spawn(async move {
+            let terminal = term.lock_unfair();
+
+            let mut text = String::new();
+            let mut line = String::new();
+            for row in terminal.topmost_line().0..=terminal.bottommost_line().0 {
+                let mut wrapped = false;
+                for cell in &terminal.grid()[Line(row)] {
+                    if !cell.flags.contains(Flags::WIDE_CHAR_SPACER) {
+                        line.push(cell.c);
+                    }
+                    wrapped = cell.flags.contains(Flags::WRAPLINE);
+                }
+                if !wrapped {
+                    text.push_str(line.trim_end());
+                    text.push('\n');
+                    line.clear();
+                }
+            }
+            text.push_str(line.trim_end());
+            text
+        })
+    }
+
     pub fn focus_in(&self) {
         if self.last_content.mode.contains(TermMode::FOCUS_IN_OUT) {
             self.write_to_pty("\x1b[I".to_string());
@@ -0,0 +1,318 @@
+//! Discovery of the tests of a project, from three sources:
+//! * the runnables that rust-analyzer reports for the open Rust buffers,
+//! * the runnables that the tree-sitter queries of the other languages find in their open buffers,
+//! * the task templates tagged `test`, which run whole test suites.
+
+use std::path::PathBuf;
+
+use anyhow::Result;
+use gpui::{AsyncWindowContext, Model, SharedString};
+use itertools::Itertools;
+use language::{Anchor, Buffer, Location, RunnableRange, ToPoint};
+use project::{
+    lsp_ext_command::{BufferRunnable, Runnables},
+    File, LanguageServerToQuery, Project, ProjectPath, TaskSourceKind,
+};
+use task::{ResolvedTask, TaskContext, TaskVariables, VariableName};
+use util::ResultExt;
+
+const RUST_ANALYZER_NAME: &str = "rust-analyzer";
+
+/// The tag of the task templates that run test suites.
+pub const TEST_SUITE_TAG: &str = "test";
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum TestKind {
+    /// A single test.
+    Test,
+    /// All tests of a module or a class.
+    Module,
+    /// A test suite, run by a task template.
+    Suite,
+}
+
+/// A test, test module or test suite that the explorer can run.
+#[derive(Clone, Debug)]
+pub struct TestItem {
+    /// The name of the test, as the test runner reports it, possibly without the path of its module or class.
+    pub name: String,
+    pub kind: TestKind,
+    /// The row of the item in its buffer.
+    pub row: Option<u32>,
+    pub task_source_kind: TaskSourceKind,
+    pub task: ResolvedTask,
+}
+
+/// The tests of a buffer, or the test suites of the project if there is no `path`.
+#[derive(Clone, Debug)]
+pub struct TestGroup {
+    pub label: SharedString,
+    pub path: Option<ProjectPath>,
+    pub tests: Vec<TestItem>,
+}
+
+/// Discovers the tests of the open buffers of the project and its test suites.
+pub(crate) async fn discover_tests(
+    project: Model<Project>,
+    mut cx: AsyncWindowContext,
+) -> Result<Vec<TestGroup>> {
+    let buffers = project.update(&mut cx, |project, cx| {
+        project
+            .opened_buffers()
+            .into_iter()
+            .filter_map(|buffer| {
+                let file = File::from_dyn(buffer.read(cx).file())?;
+                let path = ProjectPath {
+                    worktree_id: file.worktree_id(cx),
+                    path: file.path.clone(),
+                };
+                Some((buffer, path))
+            })
+            .sorted_by(|(_, a), (_, b)| a.cmp(b))
+            .collect::<Vec<_>>()
+    })?;
+
+    let mut groups = Vec::new();
+    for (buffer, path) in buffers {
+        let mut tests = language_server_tests(&project, &buffer, &mut cx)
+            .await
+            .log_err()
+            .unwrap_or_default();
+        if tests.is_empty() {
+            tests = tree_sitter_tests(&project, &buffer, &mut cx).await?;
+        }
+        if !tests.is_empty() {
+            groups.push(TestGroup {
+                label: path.path.to_string_lossy().to_string().into(),
+                path: Some(path),
+                tests,
+            });
+        }
+    }
+
+    let suites = test_suites(&project, &mut cx)?;
+    if !suites.is_empty() {
+        groups.push(TestGroup {
+            label: "Test Suites".into(),
+            path: None,
+            tests: suites,
+        });
+    }
+    Ok(groups)
+}
+
+/// The tests, test modules and doctests of the buffer that rust-analyzer reports, if it's running for it.
+async fn language_server_tests(
+    project: &Model<Project>,
+    buffer: &Model<Buffer>,
+    cx: &mut AsyncWindowContext,
+) -> Result<Vec<TestItem>> {
+    let server = project.update(cx, |project, cx| {
+        let buffer_ref = buffer.read(cx);
+        let language = buffer_ref.language()?.clone();
+        let (_, server) = project
+            .language_servers_for_buffer(buffer_ref, cx)
+            .find(|(adapter, _)| adapter.name.0.as_ref() == RUST_ANALYZER_NAME)?;
+        Some((language, server.server_id()))
+    })?;
+    let Some((language, server_id)) = server else {
+        return Ok(Vec::new());
+    };
+    let runnables = project
+        .update(cx, |project, cx| {
+            project.request_lsp(
+                buffer.clone(),
+                LanguageServerToQuery::Other(server_id),
+                Runnables { position: None },
+                cx,
+            )
+        })?
+        .await?;
+
+    let task_source_kind = TaskSourceKind::Language {
+        name: language.name(),
+    };
+    let id_base = task_source_kind.to_id_base();
+    buffer.update(cx, |buffer, _| {
+        runnables
+            .into_iter()
+            .filter_map(
+                |BufferRunnable {
+                     label,
+                     position,
+                     task,
+                 }| {
+                    let (kind, name) = if let Some(name) = label.strip_prefix("test-mod ") {
+                        (TestKind::Module, name)
+                    } else if let Some(name) = label
+                        .strip_prefix("test ")
+                        .or_else(|| label.strip_prefix("doctest "))
+                    {
+                        (TestKind::Test, name)
+                    } else {
+                        return None;
+                    };
+                    Some(TestItem {
+                        name: name.to_string(),
+                        kind,
+                        row: position.map(|position| position.to_point(buffer).row),
+                        task_source_kind: task_source_kind.clone(),
+                        task: task.resolve_task(&id_base, &TaskContext::default())?,
+                    })
+                },
+            )
+            .collect()
+    })
+}
+
+/// The tests and test modules that the runnables queries of the buffer's language find, run with the task
+/// templates having their tags, in the same way as from the gutter of the editor.
+async fn tree_sitter_tests(
+    project: &Model<Project>,
+    buffer: &Model<Buffer>,
+    cx: &mut AsyncWindowContext,
+) -> Result<Vec<TestItem>> {
+    let runnables = buffer.update(cx, |buffer, _| {
+        let snapshot = buffer.snapshot();
+        snapshot
+            .runnable_ranges(Anchor::MIN..Anchor::MAX)
+            .filter(|runnable| {
+                runnable
+                    .runnable
+                    .tags
+                    .iter()
+                    .any(|tag| tag.0.contains("test"))
+            })
+            .map(|runnable| {
+                let name = test_name(
+                    &runnable,
+                    &snapshot
+                        .text_for_range(runnable.run_range.clone())
+                        .collect::<String>(),
+                );
+                let position = snapshot.anchor_before(runnable.run_range.start);
+                (runnable, name, position)
+            })
+            .collect::<Vec<_>>()
+    })?;
+
+    let mut tests = Vec::new();
+    for (runnable, name, position) in runnables {
+        let kind = if runnable
+            .runnable
+            .tags
+            .iter()
+            .any(|tag| matches!(tag.0.as_ref(), "rust-mod-test" | "python-unittest-class"))
+        {
+            TestKind::Module
+        } else {
+            TestKind::Test
+        };
+        let templates = project.update(cx, |project, cx| {
+            let worktree_id =
+                File::from_dyn(buffer.read(cx).file()).map(|file| file.worktree_id(cx));
+            let inventory = project.task_inventory().read(cx);
+            inventory
+                .list_tasks(Some(runnable.runnable.language.clone()), worktree_id)
+                .into_iter()
+                .filter(|(_, template)| {
+                    runnable.runnable.tags.iter().any(|tag| {
+                        template
+                            .tags
+                            .iter()
+                            .any(|template_tag| **template_tag == *tag.0)
+                    })
+                })
+                .sorted_by_key(|(kind, _)| kind.clone())
+                .collect::<Vec<_>>()
+        })?;
+        // Strongest source wins, as in the editor: worktree templates over global ones over language ones.
+        let Some((task_source_kind, template)) = templates.into_iter().next() else {
+            continue;
+        };
+
+        let mut captured_variables = TaskVariables::default();
+        for (capture_name, value) in runnable.extra_captures {
+            captured_variables.insert(VariableName::Custom(capture_name.into()), value);
+        }
+        let location = Location {
+            buffer: buffer.clone(),
+            range: position..position,
+        };
+        let Some(context) = project
+            .update(cx, |project, cx| {
+                project.task_context_for_location(captured_variables, location, cx)
+            })?
+            .await
+        else {
+            continue;
+        };
+        let Some(task) = template.resolve_task(&task_source_kind.to_id_base(), &context) else {
+            continue;
+        };
+        let row = buffer.update(cx, |buffer, _| position.to_point(buffer).row)?;
+        tests.push(TestItem {
+            name,
+            kind,
+            row: Some(row),
+            task_source_kind,
+            task,
+        });
+    }
+    Ok(tests)
+}
+
+/// The name that the test runner reports for a runnable, other than the path of its module or class: the text of
+/// its `@run` capture, or the name given to `t.Run` for Go subtests.
+fn test_name(runnable: &RunnableRange, run_text: &str) -> String {
+    if let Some(subtest_name) = runnable.extra_captures.get("_subtest_name") {
+        return subtest_name.trim_matches('"').replace(' ', "_");
+    }
+    run_text.to_string()
+}
+
+/// The task templates tagged `test` of the worktrees and the user, resolved to run in the root of their worktree.
+fn test_suites(project: &Model<Project>, cx: &mut AsyncWindowContext) -> Result<Vec<TestItem>> {
+    project.update(cx, |project, cx| {
+        let worktree_roots = project
+            .visible_worktrees(cx)
+            .map(|worktree| {
+                let worktree = worktree.read(cx);
+                (worktree.id(), worktree.abs_path())
+            })
+            .collect::<Vec<_>>();
+        let default_cwd: Option<PathBuf> = worktree_roots
+            .first()
+            .map(|(_, abs_path)| abs_path.to_path_buf());
+        let inventory = project.task_inventory().read(cx);
+        inventory
+            .list_tasks(None, None)
+            .into_iter()
+            .filter(|(_, template)| template.tags.iter().any(|tag| tag == TEST_SUITE_TAG))
+            .filter_map(|(task_source_kind, template)| {
+                let cwd = task_source_kind
+                    .worktree()
+                    .and_then(|worktree_id| {
+                        worktree_roots
+                            .iter()
+                            .find(|(id, _)| *id == worktree_id)
+                            .map(|(_, abs_path)| abs_path.to_path_buf())
+                    })
+                    .or_else(|| default_cwd.clone());
+                let context = TaskContext {
+                    cwd,
+                    task_variables: TaskVariables::default(),
+                };
+                let task = template.resolve_task(&task_source_kind.to_id_base(), &context)?;
+                Some(TestItem {
+                    name: template.label.clone(),
+                    kind: TestKind::Suite,
+                    row: None,
+                    task_source_kind,
+                    task,
+                })
+            })
+            .unique_by(|test| test.task.id.clone())
+            .collect()
+    })
+}
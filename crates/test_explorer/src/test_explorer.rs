@@ -0,0 +1,685 @@
+mod discovery;
+mod test_explorer_settings;
+mod test_results;
+
+use std::{path::PathBuf, sync::Arc, time::Duration};
+
+use anyhow::Context as _;
+use collections::{HashMap, HashSet};
+use db::kvp::KEY_VALUE_STORE;
+use editor::{scroll::Autoscroll, Editor};
+use gpui::{
+    actions, uniform_list, Action, AnyElement, AppContext, AsyncWindowContext, EventEmitter,
+    FocusHandle, FocusableView, Model, Pixels, Render, SharedString, Subscription, Task,
+    UniformListScrollHandle, View, ViewContext, VisualContext, WeakView, WindowContext,
+};
+use language::Point;
+use project::{Fs, Project, ProjectPath, TaskSourceKind};
+use serde::{Deserialize, Serialize};
+use settings::Settings;
+use ui::{prelude::*, IconButton, ListItem, Tooltip};
+use util::ResultExt;
+use workspace::{
+    dock::{DockPosition, Panel, PanelEvent},
+    tasks::schedule_resolved_task,
+    Workspace,
+};
+use worktree::WorktreeId;
+
+pub use discovery::{TestGroup, TestItem, TestKind, TEST_SUITE_TAG};
+use test_explorer_settings::{TestExplorerDockPosition, TestExplorerSettings};
+pub use test_results::{parse_test_results, reported_name_matches, TestStatus};
+
+actions!(test_explorer, [ToggleFocus, RefreshTests, RunAllTests]);
+
+const TEST_EXPLORER_KEY: &str = "TestExplorer";
+const DISCOVERY_DEBOUNCE: Duration = Duration::from_millis(250);
+
+/// A test of the explorer, identified across discoveries by the group it's in and its name.
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+struct TestKey {
+    group: SharedString,
+    name: String,
+}
+
+impl TestKey {
+    fn new(group: &TestGroup, test: &TestItem) -> Self {
+        Self {
+            group: group.label.clone(),
+            name: test.name.clone(),
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug)]
+enum ListEntry {
+    Group(usize),
+    Test(usize, usize),
+}
+
+/// A panel of the tests discovered in the project, to run them and see whether they pass.
+///
+/// Tests are run in the background and their status is parsed from the output of the test runner; they can also
+/// be run in a terminal like any other task, or watched to run again whenever a file of their worktree changes.
+pub struct TestExplorer {
+    fs: Arc<dyn Fs>,
+    workspace: WeakView<Workspace>,
+    project: Model<Project>,
+    width: Option<Pixels>,
+    active: bool,
+    focus_handle: FocusHandle,
+    scroll_handle: UniformListScrollHandle,
+    pending_serialization: Task<Option<()>>,
+    groups: Vec<TestGroup>,
+    collapsed_groups: HashSet<SharedString>,
+    statuses: HashMap<TestKey, TestStatus>,
+    watched: HashSet<TestKey>,
+    running: HashMap<TestKey, Task<()>>,
+    discovery_task: Task<()>,
+    _subscriptions: Vec<Subscription>,
+}
+
+#[derive(Serialize, Deserialize)]
+struct SerializedTestExplorer {
+    width: Option<Pixels>,
+    active: Option<bool>,
+}
+
+pub fn init(cx: &mut AppContext) {
+    TestExplorerSettings::register(cx);
+
+    cx.observe_new_views(|workspace: &mut Workspace, _| {
+        workspace.register_action(|workspace, _: &ToggleFocus, cx| {
+            workspace.toggle_panel_focus::<TestExplorer>(cx);
+        });
+    })
+    .detach();
+}
+
+impl TestExplorer {
+    pub async fn load(
+        workspace: WeakView<Workspace>,
+        mut cx: AsyncWindowContext,
+    ) -> anyhow::Result<View<Self>> {
+        let serialized_panel = cx
+            .background_executor()
+            .spawn(async move { KEY_VALUE_STORE.read_kvp(TEST_EXPLORER_KEY) })
+            .await
+            .context("loading test explorer")
+            .log_err()
+            .flatten()
+            .map(|panel| serde_json::from_str::<SerializedTestExplorer>(&panel))
+            .transpose()
+            .log_err()
+            .flatten();
+
+        workspace.update(&mut cx, |workspace, cx| {
+            let panel = Self::new(workspace, cx);
+            if let Some(serialized_panel) = serialized_panel {
+                panel.update(cx, |panel, cx| {
+                    panel.width = serialized_panel.width.map(|px| px.round());
+                    panel.active = serialized_panel.active.unwrap_or(false);
+                    cx.notify();
+                });
+            }
+            panel
+        })
+    }
+
+    fn new(workspace: &mut Workspace, cx: &mut ViewContext<Workspace>) -> View<Self> {
+        let project = workspace.project().clone();
+        let fs = workspace.app_state().fs.clone();
+        let workspace_handle = cx.view().clone();
+        cx.new_view(|cx| {
+            let workspace_subscription =
+                cx.subscribe(&workspace_handle, |test_explorer, _, event, cx| {
+                    if let workspace::Event::ActiveItemChanged = event {
+                        test_explorer.schedule_discovery(cx);
+                    }
+                });
+            let project_subscription = cx.subscribe(&project, |test_explorer, _, event, cx| {
+                if let project::Event::WorktreeUpdatedEntries(worktree_id, changes) = event {
+                    let Some(worktree) = test_explorer
+                        .project
+                        .read(cx)
+                        .worktree_for_id(*worktree_id, cx)
+                    else {
+                        return;
+                    };
+                    let worktree = worktree.read(cx);
+                    let changed_files = changes.iter().any(|(_, entry_id, _)| {
+                        worktree
+                            .entry_for_id(*entry_id)
+                            .map_or(false, |entry| entry.is_file() && !entry.is_ignored)
+                    });
+                    if changed_files {
+                        test_explorer.schedule_discovery(cx);
+                        test_explorer.rerun_watched_tests(*worktree_id, cx);
+                    }
+                }
+            });
+
+            let mut test_explorer = Self {
+                fs,
+                workspace: workspace_handle.downgrade(),
+                project,
+                width: None,
+                active: false,
+                focus_handle: cx.focus_handle(),
+                scroll_handle: UniformListScrollHandle::new(),
+                pending_serialization: Task::ready(None),
+                groups: Vec::new(),
+                collapsed_groups: HashSet::default(),
+                statuses: HashMap::default(),
+                watched: HashSet::default(),
+                running: HashMap::default(),
+                discovery_task: Task::ready(()),
+                _subscriptions: vec![workspace_subscription, project_subscription],
+            };
+            test_explorer.schedule_discovery(cx);
+            test_explorer
+        })
+    }
+
+    fn serialize(&mut self, cx: &mut ViewContext<Self>) {
+        let width = self.width;
+        let active = Some(self.active);
+        self.pending_serialization = cx.background_executor().spawn(
+            async move {
+                KEY_VALUE_STORE
+                    .write_kvp(
+                        TEST_EXPLORER_KEY.into(),
+                        serde_json::to_string(&SerializedTestExplorer { width, active })?,
+                    )
+                    .await?;
+                anyhow::Ok(())
+            }
+            .log_err(),
+        );
+    }
+
+    fn refresh_tests(&mut self, _: &RefreshTests, cx: &mut ViewContext<Self>) {
+        self.schedule_discovery(cx);
+    }
+
+    /// Runs the test suites of the project, or all of its tests if it has none.
+    fn run_all_tests(&mut self, _: &RunAllTests, cx: &mut ViewContext<Self>) {
+        let tests = || {
+            self.groups
+                .iter()
+                .flat_map(|group| group.tests.iter().map(move |test| (group, test)))
+        };
+        let has_suites = tests().any(|(_, test)| test.kind == TestKind::Suite);
+        let keys = tests()
+            .filter(|(_, test)| !has_suites || test.kind == TestKind::Suite)
+            .map(|(group, test)| TestKey::new(group, test))
+            .collect::<Vec<_>>();
+        for key in keys {
+            self.run_test(&key, cx);
+        }
+    }
+
+    /// Discovers the tests again, once the changes that triggered it have settled.
+    fn schedule_discovery(&mut self, cx: &mut ViewContext<Self>) {
+        let project = self.project.clone();
+        self.discovery_task = cx.spawn(|test_explorer, mut cx| async move {
+            cx.background_executor().timer(DISCOVERY_DEBOUNCE).await;
+            let Some(groups) = discovery::discover_tests(project, cx.clone())
+                .await
+                .log_err()
+            else {
+                return;
+            };
+            test_explorer
+                .update(&mut cx, |test_explorer, cx| {
+                    test_explorer.groups = groups;
+                    cx.notify();
+                })
+                .ok();
+        });
+    }
+
+    fn find_test(&self, key: &TestKey) -> Option<(&TestGroup, &TestItem)> {
+        let group = self.groups.iter().find(|group| group.label == key.group)?;
+        let test = group.tests.iter().find(|test| test.name == key.name)?;
+        Some((group, test))
+    }
+
+    /// Runs the test in the background with the shell, to update the statuses of the tests that its output reports.
+    /// Tests of remote projects are run in a terminal instead.
+    fn run_test(&mut self, key: &TestKey, cx: &mut ViewContext<Self>) {
+        if self.running.contains_key(key) {
+            return;
+        }
+        if !self.project.read(cx).is_local() {
+            self.run_test_in_terminal(key, cx);
+            return;
+        }
+        let Some(spawn_in_terminal) = self
+            .find_test(key)
+            .and_then(|(_, test)| test.task.resolved.clone())
+        else {
+            return;
+        };
+        let cwd: Option<PathBuf> = match &spawn_in_terminal.cwd {
+            Some(cwd) => match cwd.local_path() {
+                Some(cwd) => Some(cwd.to_path_buf()),
+                None => {
+                    self.run_test_in_terminal(key, cx);
+                    return;
+                }
+            },
+            None => None,
+        };
+
+        let shell = std::env::var("SHELL").unwrap_or_else(|_| "sh".to_string());
+        let command_line = spawn_in_terminal.args.iter().fold(
+            spawn_in_terminal.command.clone(),
+            |mut command_line, arg| {
+                command_line.push(' ');
+                command_line.push_str(arg);
+                command_line
+            },
+        );
+        let mut command = smol::process::Command::new(shell);
+        command
+            .args(["-c", &command_line])
+            .envs(spawn_in_terminal.env)
+            .stdin(smol::process::Stdio::null());
+        if let Some(cwd) = cwd {
+            command.current_dir(cwd);
+        }
+
+        self.statuses.insert(key.clone(), TestStatus::Running);
+        let run_key = key.clone();
+        let task = cx.spawn(|test_explorer, mut cx| async move {
+            let output = command.output().await;
+            test_explorer
+                .update(&mut cx, |test_explorer, cx| {
+                    test_explorer.running.remove(&run_key);
+                    match output {
+                        Ok(output) => {
+                            let mut text = String::from_utf8_lossy(&output.stdout).into_owned();
+                            text.push_str(&String::from_utf8_lossy(&output.stderr));
+                            test_explorer.record_results(&run_key, output.status.success(), &text);
+                        }
+                        Err(error) => {
+                            log::error!("failed to run test {:?}: {error}", run_key.name);
+                            test_explorer
+                                .statuses
+                                .insert(run_key.clone(), TestStatus::Failed);
+                        }
+                    }
+                    cx.notify();
+                })
+                .ok();
+        });
+        self.running.insert(key.clone(), task);
+        cx.notify();
+    }
+
+    /// Updates the statuses of the tests that the output of a run reports, in the group of the test that was run or
+    /// in all of them for test suites, and the status of the test that was run with the exit status of the runner if
+    /// the output doesn't report it.
+    fn record_results(&mut self, run_key: &TestKey, success: bool, output: &str) {
+        let results = parse_test_results(output);
+        let run_kind = self
+            .find_test(run_key)
+            .map_or(TestKind::Test, |(_, test)| test.kind);
+        let mut run_reported = None;
+        for group in &self.groups {
+            if run_kind != TestKind::Suite && group.label != run_key.group {
+                continue;
+            }
+            for test in &group.tests {
+                let Some((_, status)) = results
+                    .iter()
+                    .rev()
+                    .find(|(reported, _)| reported_name_matches(reported, &test.name))
+                else {
+                    continue;
+                };
+                let key = TestKey::new(group, test);
+                if key == *run_key {
+                    run_reported = Some(*status);
+                }
+                self.statuses.insert(key, *status);
+            }
+        }
+
+        let run_status = match run_reported {
+            Some(status) if run_kind == TestKind::Test => status,
+            _ if success => TestStatus::Passed,
+            _ => TestStatus::Failed,
+        };
+        self.statuses.insert(run_key.clone(), run_status);
+    }
+
+    fn run_test_in_terminal(&mut self, key: &TestKey, cx: &mut ViewContext<Self>) {
+        let Some((task_source_kind, task)) = self
+            .find_test(key)
+            .map(|(_, test)| (test.task_source_kind.clone(), test.task.clone()))
+        else {
+            return;
+        };
+        self.workspace
+            .update(cx, |workspace, cx| {
+                schedule_resolved_task(workspace, task_source_kind, task, false, cx);
+            })
+            .log_err();
+    }
+
+    fn toggle_watch(&mut self, key: &TestKey, cx: &mut ViewContext<Self>) {
+        if !self.watched.remove(key) {
+            self.watched.insert(key.clone());
+            self.run_test(key, cx);
+        }
+        cx.notify();
+    }
+
+    /// Runs the watched tests of the worktree again, and the watched test suites.
+    fn rerun_watched_tests(&mut self, worktree_id: WorktreeId, cx: &mut ViewContext<Self>) {
+        let keys = self
+            .groups
+            .iter()
+            .flat_map(|group| {
+                group.tests.iter().filter_map(move |test| {
+                    let in_worktree = match &group.path {
+                        Some(path) => path.worktree_id == worktree_id,
+                        None => match &test.task_source_kind {
+                            TaskSourceKind::Worktree { id, .. } => *id == worktree_id,
+                            _ => true,
+                        },
+                    };
+                    in_worktree.then(|| TestKey::new(group, test))
+                })
+            })
+            .filter(|key| self.watched.contains(key))
+            .collect::<Vec<_>>();
+        for key in keys {
+            self.run_test(&key, cx);
+        }
+    }
+
+    fn open_test(&mut self, path: ProjectPath, row: Option<u32>, cx: &mut ViewContext<Self>) {
+        let Some(workspace) = self.workspace.upgrade() else {
+            return;
+        };
+        let open_task = workspace.update(cx, |workspace, cx| {
+            workspace.open_path(path, None, true, cx)
+        });
+        cx.spawn(|_, mut cx| async move {
+            let item = open_task.await?;
+            if let (Some(editor), Some(row)) = (cx.update(|cx| item.act_as::<Editor>(cx))?, row) {
+                editor.update(&mut cx, |editor, cx| {
+                    let point = Point::new(row, 0);
+                    editor.change_selections(Some(Autoscroll::center()), cx, |selections| {
+                        selections.select_ranges([point..point])
+                    });
+                })?;
+            }
+            anyhow::Ok(())
+        })
+        .detach_and_log_err(cx);
+    }
+
+    fn entries(&self) -> Vec<ListEntry> {
+        let mut entries = Vec::new();
+        for (group_ix, group) in self.groups.iter().enumerate() {
+            entries.push(ListEntry::Group(group_ix));
+            if !self.collapsed_groups.contains(&group.label) {
+                entries.extend(
+                    (0..group.tests.len()).map(|test_ix| ListEntry::Test(group_ix, test_ix)),
+                );
+            }
+        }
+        entries
+    }
+
+    fn render_group(&self, ix: usize, group_ix: usize, cx: &mut ViewContext<Self>) -> AnyElement {
+        let group = &self.groups[group_ix];
+        let label = group.label.clone();
+        let collapsed = self.collapsed_groups.contains(&label);
+        let status = group_status(
+            group
+                .tests
+                .iter()
+                .filter_map(|test| self.statuses.get(&TestKey::new(group, test)).copied()),
+        );
+        ListItem::new(("test-group", ix))
+            .indent_level(0)
+            .toggle(Some(!collapsed))
+            .on_toggle(cx.listener(move |test_explorer, _, cx| {
+                if !test_explorer.collapsed_groups.remove(&label) {
+                    test_explorer.collapsed_groups.insert(label.clone());
+                }
+                cx.notify();
+            }))
+            .start_slot(render_status(status))
+            .child(Label::new(group.label.clone()))
+            .into_any_element()
+    }
+
+    fn render_test(
+        &self,
+        ix: usize,
+        group_ix: usize,
+        test_ix: usize,
+        cx: &mut ViewContext<Self>,
+    ) -> AnyElement {
+        let group = &self.groups[group_ix];
+        let test = &group.tests[test_ix];
+        let key = TestKey::new(group, test);
+        let watched = self.watched.contains(&key);
+        let status = self.statuses.get(&key).copied();
+        let path = group.path.clone();
+        let row = test.row;
+
+        let run_key = key.clone();
+        let terminal_key = key.clone();
+        let watch_key = key;
+        ListItem::new(("test", ix))
+            .indent_level(1)
+            .selectable(path.is_some())
+            .start_slot(render_status(status))
+            .child(Label::new(test.name.clone()).single_line())
+            .end_slot(
+                h_flex()
+                    .gap_1()
+                    .child(
+                        IconButton::new(("run-test", ix), IconName::Play)
+                            .icon_size(IconSize::Small)
+                            .tooltip(|cx| Tooltip::text("Run", cx))
+                            .on_click(cx.listener(move |test_explorer, _, cx| {
+                                test_explorer.run_test(&run_key, cx);
+                            })),
+                    )
+                    .child(
+                        IconButton::new(("run-test-in-terminal", ix), IconName::Terminal)
+                            .icon_size(IconSize::Small)
+                            .tooltip(|cx| Tooltip::text("Run in Terminal", cx))
+                            .on_click(cx.listener(move |test_explorer, _, cx| {
+                                test_explorer.run_test_in_terminal(&terminal_key, cx);
+                            })),
+                    )
+                    .child(
+                        IconButton::new(("watch-test", ix), IconName::Visible)
+                            .icon_size(IconSize::Small)
+                            .selected(watched)
+                            .tooltip(move |cx| {
+                                Tooltip::text(if watched { "Stop Watching" } else { "Watch" }, cx)
+                            })
+                            .on_click(cx.listener(move |test_explorer, _, cx| {
+                                test_explorer.toggle_watch(&watch_key, cx);
+                            })),
+                    ),
+            )
+            .on_click(cx.listener(move |test_explorer, _, cx| {
+                if let Some(path) = path.clone() {
+                    test_explorer.open_test(path, row, cx);
+                }
+            }))
+            .into_any_element()
+    }
+}
+
+/// The status of a group: running or failed if any of its tests is, passed if all that ran passed.
+fn group_status(statuses: impl Iterator<Item = TestStatus>) -> Option<TestStatus> {
+    statuses.fold(None, |group_status, status| match (group_status, status) {
+        (Some(TestStatus::Running), _) | (_, TestStatus::Running) => Some(TestStatus::Running),
+        (Some(TestStatus::Failed), _) | (_, TestStatus::Failed) => Some(TestStatus::Failed),
+        (Some(TestStatus::Passed), _) | (_, TestStatus::Passed) => Some(TestStatus::Passed),
+        _ => Some(TestStatus::Skipped),
+    })
+}
+
+fn render_status(status: Option<TestStatus>) -> Icon {
+    let (icon, color) = match status {
+        None => (IconName::Dash, Color::Muted),
+        Some(TestStatus::Running) => (IconName::ArrowCircle, Color::Accent),
+        Some(TestStatus::Passed) => (IconName::Check, Color::Success),
+        Some(TestStatus::Failed) => (IconName::XCircle, Color::Error),
+        Some(TestStatus::Skipped) => (IconName::Dash, Color::Warning),
+    };
+    Icon::new(icon).size(IconSize::Small).color(color)
+}
+
+impl Panel for TestExplorer {
+    fn persistent_name() -> &'static str {
+        "Test Explorer"
+    }
+
+    fn position(&self, cx: &WindowContext) -> DockPosition {
+        match TestExplorerSettings::get_global(cx).dock {
+            TestExplorerDockPosition::Left => DockPosition::Left,
+            TestExplorerDockPosition::Right => DockPosition::Right,
+        }
+    }
+
+    fn position_is_valid(&self, position: DockPosition) -> bool {
+        matches!(position, DockPosition::Left | DockPosition::Right)
+    }
+
+    fn set_position(&mut self, position: DockPosition, cx: &mut ViewContext<Self>) {
+        settings::update_settings_file::<TestExplorerSettings>(
+            self.fs.clone(),
+            cx,
+            move |settings| {
+                let dock = match position {
+                    DockPosition::Left | DockPosition::Bottom => TestExplorerDockPosition::Left,
+                    DockPosition::Right => TestExplorerDockPosition::Right,
+                };
+                settings.dock = Some(dock);
+            },
+        );
+    }
+
+    fn size(&self, cx: &WindowContext) -> Pixels {
+        self.width
+            .unwrap_or_else(|| TestExplorerSettings::get_global(cx).default_width)
+    }
+
+    fn set_size(&mut self, size: Option<Pixels>, cx: &mut ViewContext<Self>) {
+        self.width = size;
+        self.serialize(cx);
+        cx.notify();
+    }
+
+    fn icon(&self, cx: &WindowContext) -> Option<IconName> {
+        TestExplorerSettings::get_global(cx)
+            .button
+            .then(|| IconName::Check)
+    }
+
+    fn icon_tooltip(&self, _: &WindowContext) -> Option<&'static str> {
+        Some("Test Explorer")
+    }
+
+    fn toggle_action(&self) -> Box<dyn Action> {
+        Box::new(ToggleFocus)
+    }
+
+    fn starts_open(&self, _: &WindowContext) -> bool {
+        self.active
+    }
+
+    fn set_active(&mut self, active: bool, cx: &mut ViewContext<Self>) {
+        let old_active = self.active;
+        self.active = active;
+        if active && old_active != active {
+            self.schedule_discovery(cx);
+        }
+        self.serialize(cx);
+    }
+}
+
+impl FocusableView for TestExplorer {
+    fn focus_handle(&self, _cx: &AppContext) -> FocusHandle {
+        self.focus_handle.clone()
+    }
+}
+
+impl EventEmitter<PanelEvent> for TestExplorer {}
+
+impl Render for TestExplorer {
+    fn render(&mut self, cx: &mut ViewContext<Self>) -> impl IntoElement {
+        let toolbar = h_flex()
+            .justify_between()
+            .p_1()
+            .child(Label::new("Tests").size(LabelSize::Small))
+            .child(
+                h_flex()
+                    .gap_1()
+                    .child(
+                        IconButton::new("run-all-tests", IconName::Play)
+                            .icon_size(IconSize::Small)
+                            .tooltip(|cx| Tooltip::for_action("Run All Tests", &RunAllTests, cx))
+                            .on_click(|_, cx| cx.dispatch_action(RunAllTests.boxed_clone())),
+                    )
+                    .child(
+                        IconButton::new("refresh-tests", IconName::Update)
+                            .icon_size(IconSize::Small)
+                            .tooltip(|cx| Tooltip::for_action("Refresh Tests", &RefreshTests, cx))
+                            .on_click(|_, cx| cx.dispatch_action(RefreshTests.boxed_clone())),
+                    ),
+            );
+
+        let entries = self.entries();
+        v_flex()
+            .id("test-explorer")
+            .size_full()
+            .key_context("TestExplorer")
+            .on_action(cx.listener(Self::refresh_tests))
+            .on_action(cx.listener(Self::run_all_tests))
+            .track_focus(&self.focus_handle)
+            .child(toolbar)
+            .map(|panel| {
+                if entries.is_empty() {
+                    panel.child(
+                        v_flex()
+                            .p_4()
+                            .child(Label::new("No tests found in the open files")),
+                    )
+                } else {
+                    panel.child(
+                        uniform_list(cx.view().clone(), "test-entries", entries.len(), {
+                            move |test_explorer, range, cx| {
+                                range
+                                    .map(|ix| match entries[ix] {
+                                        ListEntry::Group(group_ix) => {
+                                            test_explorer.render_group(ix, group_ix, cx)
+                                        }
+                                        ListEntry::Test(group_ix, test_ix) => {
+                                            test_explorer.render_test(ix, group_ix, test_ix, cx)
+                                        }
+                                    })
+                                    .collect()
+                            }
+                        })
+                        .size_full()
+                        .track_scroll(self.scroll_handle.clone()),
+                    )
+                }
+            })
+    }
+}
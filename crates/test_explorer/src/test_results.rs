@@ -0,0 +1,198 @@
+/// The status of a test in the explorer.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum TestStatus {
+    Running,
+    Passed,
+    Failed,
+    Skipped,
+}
+
+/// Parses the results that a test runner printed for its tests, as `(test name, status)` pairs in the order they
+/// were reported.
+///
+/// Understands the output of libtest (`cargo test`), `pytest -v`, `python -m unittest -v` and `go test -v`; lines
+/// that aren't test results are ignored.
+pub fn parse_test_results(output: &str) -> Vec<(String, TestStatus)> {
+    output
+        .lines()
+        .filter_map(|line| {
+            let line = line.trim_end();
+            parse_libtest_line(line)
+                .or_else(|| parse_unittest_line(line))
+                .or_else(|| parse_pytest_line(line))
+                .or_else(|| parse_go_test_line(line))
+        })
+        .collect()
+}
+
+/// Whether a test name reported by a runner, usually qualified with the path of its module or class, is the test
+/// with the given name.
+pub fn reported_name_matches(reported: &str, name: &str) -> bool {
+    let Some(prefix) = reported.strip_suffix(name) else {
+        return false;
+    };
+    prefix.is_empty() || prefix.ends_with("::") || prefix.ends_with('.') || prefix.ends_with('/')
+}
+
+/// `test tests::parses_empty_input ... ok`
+fn parse_libtest_line(line: &str) -> Option<(String, TestStatus)> {
+    let (name, result) = line.strip_prefix("test ")?.rsplit_once(" ... ")?;
+    let status = match result {
+        "ok" => TestStatus::Passed,
+        "FAILED" => TestStatus::Failed,
+        result if result.starts_with("ignored") => TestStatus::Skipped,
+        _ => return None,
+    };
+    Some((name.to_string(), status))
+}
+
+/// `test_parse (tests.test_parser.ParserTest.test_parse) ... ok`, or without the method name in the parentheses
+/// before Python 3.11.
+fn parse_unittest_line(line: &str) -> Option<(String, TestStatus)> {
+    let (test, result) = line.rsplit_once(" ... ")?;
+    let (method, qualified) = test.split_once(" (")?;
+    let qualified = qualified.strip_suffix(')')?;
+    let status = match result {
+        "ok" | "expected failure" => TestStatus::Passed,
+        "FAIL" | "ERROR" | "unexpected success" => TestStatus::Failed,
+        result if result.starts_with("skipped") => TestStatus::Skipped,
+        _ => return None,
+    };
+    let name = if qualified.ends_with(&format!(".{method}")) {
+        qualified.to_string()
+    } else {
+        format!("{qualified}.{method}")
+    };
+    Some((name, status))
+}
+
+/// `tests/test_parser.py::ParserTest::test_parse PASSED    [ 50%]`
+fn parse_pytest_line(line: &str) -> Option<(String, TestStatus)> {
+    let mut parts = line.split_whitespace();
+    let name = parts.next().filter(|name| name.contains("::"))?;
+    let status = match parts.next()? {
+        "PASSED" | "XFAIL" => TestStatus::Passed,
+        "FAILED" | "ERROR" | "XPASS" => TestStatus::Failed,
+        "SKIPPED" => TestStatus::Skipped,
+        _ => return None,
+    };
+    Some((name.to_string(), status))
+}
+
+/// `--- PASS: TestParse (0.00s)`, indented for subtests.
+fn parse_go_test_line(line: &str) -> Option<(String, TestStatus)> {
+    let line = line.trim_start().strip_prefix("--- ")?;
+    let (result, rest) = line.split_once(": ")?;
+    let status = match result {
+        "PASS" => TestStatus::Passed,
+        "FAIL" => TestStatus::Failed,
+        "SKIP" => TestStatus::Skipped,
+        _ => return None,
+    };
+    let name = rest.split_whitespace().next()?;
+    Some((name.to_string(), status))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_libtest_output() {
+        let output = "\
+running 4 tests
+test tests::parses_empty_input ... ok
+test tests::parses_nested_input ... FAILED
+test tests::slow ... ignored, takes too long
+test src/lib.rs - parse (line 12) ... ok
+
+failures:
+";
+        assert_eq!(
+            parse_test_results(output),
+            vec![
+                ("tests::parses_empty_input".to_string(), TestStatus::Passed),
+                ("tests::parses_nested_input".to_string(), TestStatus::Failed),
+                ("tests::slow".to_string(), TestStatus::Skipped),
+                (
+                    "src/lib.rs - parse (line 12)".to_string(),
+                    TestStatus::Passed
+                ),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_parse_python_output() {
+        let output = "\
+tests/test_parser.py::ParserTest::test_parse PASSED                      [ 33%]
+tests/test_parser.py::ParserTest::test_nested FAILED                     [ 66%]
+tests/test_parser.py::test_slow SKIPPED (takes too long)                 [100%]
+test_parse (tests.test_parser.ParserTest.test_parse) ... ok
+test_nested (tests.test_parser.ParserTest) ... FAIL
+";
+        assert_eq!(
+            parse_test_results(output),
+            vec![
+                (
+                    "tests/test_parser.py::ParserTest::test_parse".to_string(),
+                    TestStatus::Passed
+                ),
+                (
+                    "tests/test_parser.py::ParserTest::test_nested".to_string(),
+                    TestStatus::Failed
+                ),
+                (
+                    "tests/test_parser.py::test_slow".to_string(),
+                    TestStatus::Skipped
+                ),
+                (
+                    "tests.test_parser.ParserTest.test_parse".to_string(),
+                    TestStatus::Passed
+                ),
+                (
+                    "tests.test_parser.ParserTest.test_nested".to_string(),
+                    TestStatus::Failed
+                ),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_parse_go_test_output() {
+        let output = "\
+=== RUN   TestParse
+=== RUN   TestParse/empty
+--- FAIL: TestParse (0.00s)
+    --- FAIL: TestParse/empty (0.00s)
+--- PASS: TestNested (0.01s)
+--- SKIP: TestSlow (0.00s)
+FAIL
+";
+        assert_eq!(
+            parse_test_results(output),
+            vec![
+                ("TestParse".to_string(), TestStatus::Failed),
+                ("TestParse/empty".to_string(), TestStatus::Failed),
+                ("TestNested".to_string(), TestStatus::Passed),
+                ("TestSlow".to_string(), TestStatus::Skipped),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_reported_name_matches() {
+        assert!(reported_name_matches("tests::parses_input", "parses_input"));
+        assert!(reported_name_matches("parses_input", "parses_input"));
+        assert!(reported_name_matches(
+            "tests/test_parser.py::ParserTest::test_parse",
+            "test_parse"
+        ));
+        assert!(reported_name_matches("TestParse/empty", "empty"));
+        assert!(!reported_name_matches(
+            "tests::reparses_input",
+            "parses_input"
+        ));
+        assert!(!reported_name_matches("tests::parses_input", "parses"));
+    }
+}
@@ -600,8 +600,10 @@ fn test_history() {
     buffer.group_until_transaction(transaction_1);
     buffer.undo();
     assert_eq!(buffer.text(), "123456");
+    assert!(!buffer.can_undo_transaction(transaction_1));
     buffer.redo();
     assert_eq!(buffer.text(), "X12cde6");
+    assert!(buffer.can_undo_transaction(transaction_1));
 }
 
 #[test]
@@ -1273,6 +1273,14 @@ impl Buffer {
         self.history.redo_stack.last()
     }
 
+    /// Whether the transaction is on the undo stack, which it isn't once it was undone or forgotten.
+    pub fn can_undo_transaction(&self, transaction_id: TransactionId) -> bool {
+        self.history
+            .undo_stack
+            .iter()
+            .any(|entry| entry.transaction.id == transaction_id)
+    }
+
     pub fn start_transaction(&mut self) -> Option<TransactionId> {
         self.start_transaction_at(Instant::now())
     }
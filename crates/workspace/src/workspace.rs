@@ -135,6 +135,7 @@ actions!(
         ToggleLeftDock,
         ToggleRightDock,
         ToggleZoom,
+        TrustWorktrees,
         Unfollow,
         Welcome,
     ]
@@ -686,6 +687,10 @@ impl Workspace {
                     )
                 }
 
+                project::Event::WorktreeTrustRequired(worktree_id) => {
+                    this.show_worktree_trust_notification(*worktree_id, cx);
+                }
+
                 project::Event::LanguageServerPrompt(request) => {
                     struct LanguageServerPrompt;
 
@@ -1732,6 +1737,50 @@ impl Workspace {
         })
     }
 
+    fn show_worktree_trust_notification(
+        &mut self,
+        worktree_id: WorktreeId,
+        cx: &mut ViewContext<Self>,
+    ) {
+        struct WorktreeTrustNotification;
+
+        let Some(worktree) = self.project.read(cx).worktree_for_id(worktree_id, cx) else {
+            return;
+        };
+        let message = format!(
+            "\"{}\" isn't trusted: its language servers, tasks and local settings are disabled",
+            worktree.read(cx).root_name()
+        );
+        let project = self.project.downgrade();
+        self.show_notification(
+            NotificationId::identified::<WorktreeTrustNotification>(worktree_id.to_usize()),
+            cx,
+            |cx| {
+                cx.new_view(|_| {
+                    MessageNotification::new(message)
+                        .with_click_message("Trust Worktree")
+                        .on_click(move |cx| {
+                            project
+                                .update(cx, |project, cx| project.trust_worktree(worktree_id, cx))
+                                .ok();
+                        })
+                })
+            },
+        );
+    }
+
+    fn trust_worktrees(&mut self, _: &TrustWorktrees, cx: &mut ViewContext<Self>) {
+        self.project.update(cx, |project, cx| {
+            let worktree_ids = project
+                .worktrees()
+                .map(|worktree| worktree.read(cx).id())
+                .collect::<Vec<_>>();
+            for worktree_id in worktree_ids {
+                project.trust_worktree(worktree_id, cx);
+            }
+        });
+    }
+
     fn add_folder_to_project(&mut self, _: &AddFolderToProject, cx: &mut ViewContext<Self>) {
         if self.project.read(cx).is_remote() {
             self.show_error(
@@ -3845,6 +3894,7 @@ impl Workspace {
             .on_action(cx.listener(Self::save_all))
             .on_action(cx.listener(Self::send_keystrokes))
             .on_action(cx.listener(Self::add_folder_to_project))
+            .on_action(cx.listener(Self::trust_worktrees))
             .on_action(cx.listener(Self::follow_next_collaborator))
             .on_action(cx.listener(|workspace, _: &Unfollow, cx| {
                 let pane = workspace.active_pane().clone();
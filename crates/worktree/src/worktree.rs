@@ -107,6 +107,13 @@ pub struct LoadedFile {
     pub diff_base: Option<String>,
 }
 
+/// A file loaded without decoding its contents, so that they can be decoded from the encoding of the file.
+pub struct LoadedBinaryFile {
+    pub file: Arc<File>,
+    pub content: Vec<u8>,
+    pub diff_base: Option<String>,
+}
+
 pub struct LocalWorktree {
     snapshot: LocalSnapshot,
     scan_requests_tx: channel::Sender<ScanRequest>,
@@ -322,6 +329,8 @@ pub struct LocalRepositoryEntry {
     /// Path to the actual .git folder.
     /// Note: if .git is a file, this points to the folder indicated by the .git file
     pub(crate) git_dir_path: Arc<Path>,
+    /// The SHA of the HEAD of the repository when it was last scanned, to tell the changes of checkouts apart.
+    pub(crate) head_sha: Option<String>,
 }
 
 impl LocalRepositoryEntry {
@@ -679,6 +688,19 @@ impl Worktree {
         }
     }
 
+    pub fn load_binary_file(
+        &self,
+        path: &Path,
+        cx: &mut ModelContext<Worktree>,
+    ) -> Task<Result<LoadedBinaryFile>> {
+        match self {
+            Worktree::Local(this) => this.load_binary_file(path, cx),
+            Worktree::Remote(_) => {
+                Task::ready(Err(anyhow!("remote worktrees can't yet load files")))
+            }
+        }
+    }
+
     pub fn write_file(
         &self,
         path: &Path,
@@ -694,6 +716,20 @@ impl Worktree {
         }
     }
 
+    pub fn write_binary_file(
+        &self,
+        path: &Path,
+        content: Vec<u8>,
+        cx: &mut ModelContext<Worktree>,
+    ) -> Task<Result<Arc<File>>> {
+        match self {
+            Worktree::Local(this) => this.write_binary_file(path, content, cx),
+            Worktree::Remote(_) => {
+                Task::ready(Err(anyhow!("remote worktree can't yet write files")))
+            }
+        }
+    }
+
     pub fn create_entry(
         &mut self,
         path: impl Into<Arc<Path>>,
@@ -1246,6 +1282,22 @@ impl LocalWorktree {
     }
 
     fn load_file(&self, path: &Path, cx: &mut ModelContext<Worktree>) -> Task<Result<LoadedFile>> {
+        let load = self.load_binary_file(path, cx);
+        cx.background_executor().spawn(async move {
+            let loaded = load.await?;
+            Ok(LoadedFile {
+                file: loaded.file,
+                text: String::from_utf8(loaded.content)?,
+                diff_base: loaded.diff_base,
+            })
+        })
+    }
+
+    fn load_binary_file(
+        &self,
+        path: &Path,
+        cx: &mut ModelContext<Worktree>,
+    ) -> Task<Result<LoadedBinaryFile>> {
         let path = Arc::from(path);
         let abs_path = self.absolutize(&path);
         let fs = self.fs.clone();
@@ -1254,7 +1306,7 @@ impl LocalWorktree {
 
         cx.spawn(|this, mut cx| async move {
             let abs_path = abs_path?;
-            let text = fs.load(&abs_path).await?;
+            let content = fs.load_bytes(&abs_path).await?;
             let mut index_task = None;
             let snapshot = this.update(&mut cx, |this, _| this.as_local().unwrap().snapshot())?;
             if let Some(repo) = snapshot.repository_for_path(&path) {
@@ -1317,9 +1369,9 @@ impl LocalWorktree {
                 }
             };
 
-            Ok(LoadedFile {
+            Ok(LoadedBinaryFile {
                 file,
-                text,
+                content,
                 diff_base,
             })
         })
@@ -1411,17 +1463,45 @@ impl LocalWorktree {
     ) -> Task<Result<Arc<File>>> {
         let path = path.into();
         let fs = self.fs.clone();
-        let is_private = self.is_path_private(&path);
         let Ok(abs_path) = self.absolutize(&path) else {
             return Task::ready(Err(anyhow!("invalid path {path:?}")));
         };
 
         let write = cx.background_executor().spawn({
-            let fs = fs.clone();
             let abs_path = abs_path.clone();
             async move { fs.save(&abs_path, &text, line_ending).await }
         });
+        self.refresh_written_file(path, abs_path, write, cx)
+    }
+
+    fn write_binary_file(
+        &self,
+        path: impl Into<Arc<Path>>,
+        content: Vec<u8>,
+        cx: &mut ModelContext<Worktree>,
+    ) -> Task<Result<Arc<File>>> {
+        let path = path.into();
+        let fs = self.fs.clone();
+        let Ok(abs_path) = self.absolutize(&path) else {
+            return Task::ready(Err(anyhow!("invalid path {path:?}")));
+        };
 
+        let write = cx.background_executor().spawn({
+            let abs_path = abs_path.clone();
+            async move { fs.save_bytes(&abs_path, &content).await }
+        });
+        self.refresh_written_file(path, abs_path, write, cx)
+    }
+
+    fn refresh_written_file(
+        &self,
+        path: Arc<Path>,
+        abs_path: PathBuf,
+        write: Task<Result<()>>,
+        cx: &mut ModelContext<Worktree>,
+    ) -> Task<Result<Arc<File>>> {
+        let fs = self.fs.clone();
+        let is_private = self.is_path_private(&path);
         cx.spawn(move |this, mut cx| async move {
             write.await?;
             let entry = this
@@ -2837,6 +2917,7 @@ impl BackgroundScannerState {
                 git_dir_scan_id: 0,
                 repo_ptr: repository.clone(),
                 git_dir_path: dot_git_path.clone(),
+                head_sha: repository.head_sha(),
             },
         );
 
@@ -3036,12 +3117,12 @@ impl language::LocalFile for File {
         }
     }
 
-    fn load(&self, cx: &AppContext) -> Task<Result<String>> {
+    fn load_bytes(&self, cx: &AppContext) -> Task<Result<Vec<u8>>> {
         let worktree = self.worktree.read(cx).as_local().unwrap();
         let abs_path = worktree.absolutize(&self.path);
         let fs = worktree.fs.clone();
         cx.background_executor()
-            .spawn(async move { fs.load(&abs_path?).await })
+            .spawn(async move { fs.load_bytes(&abs_path?).await })
     }
 }
 
@@ -3633,7 +3714,20 @@ impl BackgroundScanner {
             }
         });
 
-        if relative_paths.is_empty() && dot_git_paths.is_empty() {
+        let checkouts = self.paths_changed_by_checkouts(&dot_git_paths);
+        let (targeted_paths, targeted_abs_paths) = if checkouts.is_empty() {
+            Default::default()
+        } else {
+            self.targeted_rescan_paths(
+                &root_canonical_path,
+                checkouts,
+                &mut relative_paths,
+                &mut abs_paths,
+            )
+            .await
+        };
+
+        if relative_paths.is_empty() && targeted_paths.is_empty() && dot_git_paths.is_empty() {
             return;
         }
 
@@ -3642,13 +3736,24 @@ impl BackgroundScanner {
         let (scan_job_tx, scan_job_rx) = channel::unbounded();
         log::debug!("received fs events {:?}", relative_paths);
         self.reload_entries_for_paths(
-            root_path,
-            root_canonical_path,
+            root_path.clone(),
+            root_canonical_path.clone(),
             &relative_paths,
             abs_paths,
             Some(scan_job_tx.clone()),
         )
         .await;
+        if !targeted_paths.is_empty() {
+            log::debug!("rescanning paths changed by checkouts {:?}", targeted_paths);
+            self.reload_entries_for_paths(
+                root_path,
+                root_canonical_path,
+                &targeted_paths,
+                targeted_abs_paths,
+                None,
+            )
+            .await;
+        }
 
         self.update_ignore_statuses(scan_job_tx).await;
         self.scan_dirs(false, scan_job_rx).await;
@@ -3668,6 +3773,219 @@ impl BackgroundScanner {
         self.send_status_update(false, None);
     }
 
+    /// Returns the paths changed by the checkouts that moved the HEAD of the given repositories since they were
+    /// last scanned, by the ids of the work directories of the repositories.
+    fn paths_changed_by_checkouts(
+        &self,
+        dot_git_paths: &[PathBuf],
+    ) -> HashMap<ProjectEntryId, Vec<Arc<Path>>> {
+        let repositories = {
+            let state = self.state.lock();
+            let snapshot = &state.snapshot;
+            dot_git_paths
+                .iter()
+                .filter_map(|dot_git_dir| {
+                    let (work_directory_id, repository) = snapshot
+                        .git_repositories
+                        .iter()
+                        .find(|(_, repo)| repo.git_dir_path.as_ref() == dot_git_dir)?;
+                    let work_directory = snapshot.entry_for_id(*work_directory_id)?.path.clone();
+                    let location_in_repo = snapshot
+                        .repository_entries
+                        .get(&RepositoryWorkDirectory(work_directory.clone()))
+                        .and_then(|repo| repo.location_in_repo.clone());
+                    Some((
+                        *work_directory_id,
+                        work_directory,
+                        location_in_repo,
+                        repository.clone(),
+                    ))
+                })
+                .collect::<Vec<_>>()
+        };
+
+        let mut checkouts = HashMap::default();
+        for (work_directory_id, work_directory, location_in_repo, repository) in repositories {
+            let head_sha = repository.repo_ptr.head_sha();
+            if head_sha == repository.head_sha {
+                continue;
+            }
+            self.state
+                .lock()
+                .snapshot
+                .git_repositories
+                .update(&work_directory_id, |entry| {
+                    entry.head_sha = head_sha.clone()
+                });
+
+            // When the changes of the checkout can't be read, its paths are rescanned from the FS events alone.
+            let (Some(old_sha), Some(new_sha)) = (&repository.head_sha, &head_sha) else {
+                continue;
+            };
+            let Some(changed_paths) = repository
+                .repo_ptr
+                .changed_paths(old_sha, new_sha)
+                .log_err()
+            else {
+                continue;
+            };
+            let changed_paths = changed_paths
+                .into_iter()
+                .filter_map(|repo_path| {
+                    let path = match &location_in_repo {
+                        Some(location_in_repo) => repo_path.strip_prefix(location_in_repo).ok()?,
+                        None => repo_path.as_path(),
+                    };
+                    Some(work_directory.join(path).into())
+                })
+                .collect();
+            checkouts.insert(work_directory_id, changed_paths);
+        }
+        checkouts
+    }
+
+    /// Splits the paths of FS events within checked out repositories into the ones that are rescanned recursively
+    /// and the ones the entries of which are only reloaded, which are returned.
+    ///
+    /// A checkout touches most of the directories of a large repository, and rescanning them recursively would
+    /// reload the entire worktree. Instead, only the entries of the loaded directories are reloaded, along with
+    /// their children that were added or removed, and the files within them are reloaded from the paths changed
+    /// by the checkout, keeping the rest of the snapshot intact.
+    async fn targeted_rescan_paths(
+        &self,
+        root_canonical_path: &Path,
+        checkouts: HashMap<ProjectEntryId, Vec<Arc<Path>>>,
+        relative_paths: &mut Vec<Arc<Path>>,
+        abs_paths: &mut Vec<PathBuf>,
+    ) -> (Vec<Arc<Path>>, Vec<PathBuf>) {
+        let mut targeted_dirs = Vec::new();
+        {
+            let state = self.state.lock();
+            let snapshot = &state.snapshot;
+            for (path, abs_path) in mem::take(relative_paths)
+                .into_iter()
+                .zip(mem::take(abs_paths))
+            {
+                let is_loaded_dir = snapshot
+                    .entry_for_path(&path)
+                    .map_or(false, |entry| entry.kind == EntryKind::Dir);
+                let is_checked_out = snapshot.repository_for_path(&path).map_or(false, |repo| {
+                    checkouts.contains_key(&repo.work_directory_id())
+                });
+                let is_git_related = path
+                    .components()
+                    .any(|component| component.as_os_str() == *DOT_GIT);
+                if is_loaded_dir && is_checked_out && !is_git_related {
+                    targeted_dirs.push(path);
+                } else {
+                    relative_paths.push(path);
+                    abs_paths.push(abs_path);
+                }
+            }
+        }
+
+        let mut dir_children = Vec::new();
+        for dir in targeted_dirs {
+            let abs_path = root_canonical_path.join(&dir);
+            match self.fs.read_dir(&abs_path).await {
+                Ok(child_abs_paths) => {
+                    let child_abs_paths = child_abs_paths
+                        .filter_map(|entry| async {
+                            match entry {
+                                Ok(entry) => Some(entry),
+                                Err(error) => {
+                                    log::error!("error processing entry {:?}", error);
+                                    None
+                                }
+                            }
+                        })
+                        .collect::<Vec<_>>()
+                        .await;
+                    let child_paths = child_abs_paths
+                        .iter()
+                        .filter_map(|child_abs_path| Some(dir.join(child_abs_path.file_name()?)))
+                        .collect::<HashSet<_>>();
+                    dir_children.push((dir, child_paths));
+                }
+                Err(error) => {
+                    log::error!("error reading directory {abs_path:?} on checkout: {error:#}");
+                    relative_paths.push(dir);
+                    abs_paths.push(abs_path);
+                }
+            }
+        }
+
+        let mut targeted_paths = Vec::new();
+        let mut new_paths = Vec::<Arc<Path>>::new();
+        {
+            let state = self.state.lock();
+            let snapshot = &state.snapshot;
+            for (dir, child_paths) in dir_children {
+                for entry in snapshot.child_entries(&dir) {
+                    if !child_paths.contains(entry.path.as_ref()) {
+                        targeted_paths.push(entry.path.clone());
+                    }
+                }
+                new_paths.extend(
+                    child_paths
+                        .into_iter()
+                        .filter(|path| snapshot.entry_for_path(path).is_none())
+                        .map(Arc::from),
+                );
+                targeted_paths.push(dir);
+            }
+
+            for path in checkouts.into_values().flatten() {
+                if snapshot.entry_for_path(&path).is_some() {
+                    targeted_paths.push(path);
+                    continue;
+                }
+                // The paths within the directories added by the checkout are scanned along with the topmost
+                // of them, and the ones within unloaded directories are left until those are loaded.
+                let mut new_path = path;
+                while let Some(parent) = new_path.parent() {
+                    match snapshot.entry_for_path(parent) {
+                        Some(entry) => {
+                            if entry.kind == EntryKind::Dir {
+                                new_paths.push(new_path);
+                            }
+                            break;
+                        }
+                        None => new_path = parent.into(),
+                    }
+                }
+            }
+        }
+
+        new_paths.retain(|path| !self.settings.is_path_excluded(path));
+        let mut recursive_paths = mem::take(relative_paths)
+            .into_iter()
+            .zip(mem::take(abs_paths))
+            .chain(
+                new_paths
+                    .into_iter()
+                    .map(|path| (path.clone(), root_canonical_path.join(&path))),
+            )
+            .collect::<Vec<_>>();
+        recursive_paths.sort_unstable_by(|(a, _), (b, _)| a.cmp(b));
+        recursive_paths.dedup_by(|(a, _), (b, _)| a.starts_with(&b));
+        (*relative_paths, *abs_paths) = recursive_paths.into_iter().unzip::<_, _, Vec<_>, Vec<_>>();
+
+        targeted_paths.retain(|path| {
+            !self.settings.is_path_excluded(path)
+                && !relative_paths
+                    .iter()
+                    .any(|recursive_path| path.starts_with(recursive_path))
+        });
+        targeted_paths.sort_unstable();
+        targeted_paths.dedup();
+        let targeted_abs_paths = targeted_paths
+            .iter()
+            .map(|path| root_canonical_path.join(path))
+            .collect();
+        (targeted_paths, targeted_abs_paths)
+    }
+
     async fn forcibly_load_paths(&self, paths: &[Arc<Path>]) -> bool {
         let (scan_job_tx, mut scan_job_rx) = channel::unbounded();
         {
@@ -2307,6 +2307,100 @@ async fn test_repository_subfolder_git_status(cx: &mut TestAppContext) {
     });
 }
 
+#[gpui::test]
+async fn test_rescan_paths_changed_by_checkout(cx: &mut TestAppContext) {
+    init_test(cx);
+    let fs = FakeFs::new(cx.background_executor.clone());
+    fs.insert_tree(
+        "/root",
+        json!({
+            ".git": {},
+            "a": {
+                "a1.txt": "one",
+                "a2.txt": "two",
+                "big": {
+                    "x": { "1.txt": "" },
+                    "y": { "2.txt": "" },
+                    "z": { "3.txt": "" },
+                },
+            },
+            "b": {
+                "b1.txt": "",
+            },
+        }),
+    )
+    .await;
+    fs.set_head_for_repo(Path::new("/root/.git"), "1111", &[]);
+
+    let tree = Worktree::local(
+        Path::new("/root"),
+        true,
+        fs.clone(),
+        Default::default(),
+        &mut cx.to_async(),
+    )
+    .await
+    .unwrap();
+    cx.read(|cx| tree.read(cx).as_local().unwrap().scan_complete())
+        .await;
+    cx.executor().run_until_parked();
+    let untouched_entry_id = tree.read_with(cx, |tree, _| {
+        tree.entry_for_path("a/big/x/1.txt").unwrap().id
+    });
+
+    // Check out a commit that changes, removes and adds files in `a`.
+    fs.pause_events();
+    fs.insert_file("/root/a/a1.txt", b"uno".to_vec()).await;
+    fs.remove_file("/root/a/a2.txt".as_ref(), Default::default())
+        .await
+        .unwrap();
+    fs.create_dir("/root/a/new".as_ref()).await.unwrap();
+    fs.insert_file("/root/a/new/n.txt", Vec::new()).await;
+    fs.set_head_for_repo(
+        Path::new("/root/.git"),
+        "2222",
+        &[
+            Path::new("a/a1.txt"),
+            Path::new("a/a2.txt"),
+            Path::new("a/new/n.txt"),
+        ],
+    );
+    let prev_read_dir_count = fs.read_dir_call_count();
+    fs.flush_events(usize::MAX);
+    cx.executor().run_until_parked();
+
+    tree.read_with(cx, |tree, _| {
+        assert_eq!(
+            tree.entries(true, 0)
+                .map(|entry| entry.path.as_ref())
+                .collect::<Vec<_>>(),
+            vec![
+                Path::new(""),
+                Path::new("a"),
+                Path::new("a/a1.txt"),
+                Path::new("a/big"),
+                Path::new("a/big/x"),
+                Path::new("a/big/x/1.txt"),
+                Path::new("a/big/y"),
+                Path::new("a/big/y/2.txt"),
+                Path::new("a/big/z"),
+                Path::new("a/big/z/3.txt"),
+                Path::new("a/new"),
+                Path::new("a/new/n.txt"),
+                Path::new("b"),
+                Path::new("b/b1.txt"),
+            ]
+        );
+        assert_eq!(
+            tree.entry_for_path("a/big/x/1.txt").unwrap().id,
+            untouched_entry_id
+        );
+
+        // Only `a` is listed, and the directory added by the checkout scanned, instead of rescanning all of `a`.
+        assert_eq!(fs.read_dir_call_count() - prev_read_dir_count, 2);
+    });
+}
+
 #[gpui::test]
 async fn test_propagate_git_statuses(cx: &mut TestAppContext) {
     init_test(cx);
@@ -190,6 +190,8 @@ fn init_ui(app_state: Arc<AppState>, cx: &mut AppContext) -> Result<()> {
     project_panel::init(Assets, cx);
     outline_panel::init(Assets, cx);
     tasks_ui::init(cx);
+    test_explorer::init(cx);
+    forwarded_ports::init(cx);
     channel::init(&app_state.client.clone(), app_state.user_store.clone(), cx);
     search::init(cx);
     vim::init(cx);
@@ -217,7 +219,7 @@ fn init_ui(app_state: Arc<AppState>, cx: &mut AppContext) -> Result<()> {
     );
     supermaven::init(app_state.client.clone(), cx);
 
-    inline_completion_registry::init(app_state.client.telemetry().clone(), cx);
+    inline_completion_registry::init(app_state.client.clone(), cx);
 
     assistant::init(app_state.fs.clone(), app_state.client.clone(), cx);
 
@@ -19,6 +19,7 @@ pub use open_listener::*;
 
 use anyhow::Context as _;
 use assets::Assets;
+use forwarded_ports::ForwardedPortsPanel;
 use futures::{channel::mpsc, select_biased, StreamExt};
 use outline_panel::OutlinePanel;
 use project::TaskSourceKind;
@@ -38,6 +39,7 @@ use workspace::notifications::NotificationId;
 
 use paths::{local_settings_file_relative_path, local_tasks_file_relative_path};
 use terminal_view::terminal_panel::{self, TerminalPanel};
+use test_explorer::TestExplorer;
 use util::{asset_str, ResultExt};
 use uuid::Uuid;
 use vim::VimModeSetting;
@@ -142,6 +144,7 @@ pub fn initialize_workspace(app_state: Arc<AppState>, cx: &mut AppContext) {
             activity_indicator::ActivityIndicator::new(workspace, app_state.languages.clone(), cx);
         let active_buffer_language =
             cx.new_view(|_| language_selector::ActiveBufferLanguage::new(workspace));
+        let active_buffer_encoding = cx.new_view(|_| language_selector::ActiveBufferEncoding::new());
         let vim_mode_indicator = cx.new_view(|cx| vim::ModeIndicator::new(cx));
         let cursor_position =
             cx.new_view(|_| go_to_line::cursor_position::CursorPosition::new(workspace));
@@ -150,6 +153,7 @@ pub fn initialize_workspace(app_state: Arc<AppState>, cx: &mut AppContext) {
             status_bar.add_left_item(activity_indicator, cx);
             status_bar.add_right_item(inline_completion_button, cx);
             status_bar.add_right_item(active_buffer_language, cx);
+            status_bar.add_right_item(active_buffer_encoding, cx);
             status_bar.add_right_item(vim_mode_indicator, cx);
             status_bar.add_right_item(cursor_position, cx);
         });
@@ -194,6 +198,9 @@ pub fn initialize_workspace(app_state: Arc<AppState>, cx: &mut AppContext) {
             let project_panel = ProjectPanel::load(workspace_handle.clone(), cx.clone());
             let outline_panel = OutlinePanel::load(workspace_handle.clone(), cx.clone());
             let terminal_panel = TerminalPanel::load(workspace_handle.clone(), cx.clone());
+            let test_explorer = TestExplorer::load(workspace_handle.clone(), cx.clone());
+            let forwarded_ports_panel =
+                ForwardedPortsPanel::load(workspace_handle.clone(), cx.clone());
             let channels_panel =
                 collab_ui::collab_panel::CollabPanel::load(workspace_handle.clone(), cx.clone());
             let chat_panel =
@@ -207,6 +214,8 @@ pub fn initialize_workspace(app_state: Arc<AppState>, cx: &mut AppContext) {
                 project_panel,
                 outline_panel,
                 terminal_panel,
+                test_explorer,
+                forwarded_ports_panel,
                 assistant_panel,
                 channels_panel,
                 chat_panel,
@@ -215,6 +224,8 @@ pub fn initialize_workspace(app_state: Arc<AppState>, cx: &mut AppContext) {
                 project_panel,
                 outline_panel,
                 terminal_panel,
+                test_explorer,
+                forwarded_ports_panel,
                 assistant_panel,
                 channels_panel,
                 chat_panel,
@@ -226,6 +237,8 @@ pub fn initialize_workspace(app_state: Arc<AppState>, cx: &mut AppContext) {
                 workspace.add_panel(project_panel, cx);
                 workspace.add_panel(outline_panel, cx);
                 workspace.add_panel(terminal_panel, cx);
+                workspace.add_panel(test_explorer, cx);
+                workspace.add_panel(forwarded_ports_panel, cx);
                 workspace.add_panel(channels_panel, cx);
                 workspace.add_panel(chat_panel, cx);
                 workspace.add_panel(notification_panel, cx);
@@ -3183,6 +3196,8 @@ mod tests {
             terminal_view::init(cx);
             assistant::init(app_state.fs.clone(), app_state.client.clone(), cx);
             tasks_ui::init(cx);
+            test_explorer::init(cx);
+            forwarded_ports::init(cx);
             initialize_workspace(app_state.clone(), cx);
             app_state
         })
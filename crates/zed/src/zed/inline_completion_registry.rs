@@ -1,15 +1,17 @@
 use std::{cell::RefCell, rc::Rc, sync::Arc};
 
-use client::telemetry::Telemetry;
+use client::{telemetry::Telemetry, Client};
 use collections::HashMap;
 use copilot::{Copilot, CopilotCompletionProvider};
 use editor::{Editor, EditorMode};
 use gpui::{AnyWindowHandle, AppContext, Context, ViewContext, WeakView};
 use language::language_settings::all_language_settings;
+use local_inline_completion::LocalCompletionProvider;
 use settings::SettingsStore;
 use supermaven::{Supermaven, SupermavenCompletionProvider};
 
-pub fn init(telemetry: Arc<Telemetry>, cx: &mut AppContext) {
+pub fn init(client: Arc<Client>, cx: &mut AppContext) {
+    let telemetry = client.telemetry().clone();
     let editors: Rc<RefCell<HashMap<WeakView<Editor>, AnyWindowHandle>>> = Rc::default();
     cx.observe_new_views({
         let editors = editors.clone();
@@ -34,7 +36,7 @@ pub fn init(telemetry: Arc<Telemetry>, cx: &mut AppContext) {
                 .borrow_mut()
                 .insert(editor_handle, cx.window_handle());
             let provider = all_language_settings(None, cx).inline_completions.provider;
-            assign_inline_completion_provider(editor, provider, &telemetry, cx);
+            assign_inline_completion_provider(editor, provider, &client, &telemetry, cx);
         }
     })
     .detach();
@@ -43,7 +45,7 @@ pub fn init(telemetry: Arc<Telemetry>, cx: &mut AppContext) {
     for (editor, window) in editors.borrow().iter() {
         _ = window.update(cx, |_window, cx| {
             _ = editor.update(cx, |editor, cx| {
-                assign_inline_completion_provider(editor, provider, &telemetry, cx);
+                assign_inline_completion_provider(editor, provider, &client, &telemetry, cx);
             })
         });
     }
@@ -55,7 +57,9 @@ pub fn init(telemetry: Arc<Telemetry>, cx: &mut AppContext) {
             for (editor, window) in editors.borrow().iter() {
                 _ = window.update(cx, |_window, cx| {
                     _ = editor.update(cx, |editor, cx| {
-                        assign_inline_completion_provider(editor, provider, &telemetry, cx);
+                        assign_inline_completion_provider(
+                            editor, provider, &client, &telemetry, cx,
+                        );
                     })
                 });
             }
@@ -103,6 +107,7 @@ fn register_backward_compatible_actions(editor: &mut Editor, cx: &mut ViewContex
 fn assign_inline_completion_provider(
     editor: &mut Editor,
     provider: language::language_settings::InlineCompletionProvider,
+    client: &Arc<Client>,
     telemetry: &Arc<Telemetry>,
     cx: &mut ViewContext<Editor>,
 ) {
@@ -131,5 +136,9 @@ fn assign_inline_completion_provider(
                 editor.set_inline_completion_provider(Some(provider), cx);
             }
         }
+        language::language_settings::InlineCompletionProvider::Local => {
+            let provider = cx.new_model(|_| LocalCompletionProvider::new(client.http_client()));
+            editor.set_inline_completion_provider(Some(provider), cx);
+        }
     }
 }